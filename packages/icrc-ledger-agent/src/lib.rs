@@ -342,7 +342,14 @@ impl Icrc1Agent {
         self.verify_root_hash(&certificate, &hash_tree.digest())
             .await?;
 
-        let last_block_hash_vec = lookup_leaf(&hash_tree, "tip_hash")?;
+        // Ledgers implementing ICRC-3 certify the `last_block_hash` label and
+        // encode the `last_block_index` using LEB128. Older ledgers certify the
+        // `tip_hash` label and encode the index as 8 big-endian bytes.
+        let (last_block_hash_vec, icrc3_encoding) =
+            match lookup_leaf(&hash_tree, "last_block_hash")? {
+                Some(last_block_hash_vec) => (Some(last_block_hash_vec), true),
+                None => (lookup_leaf(&hash_tree, "tip_hash")?, false),
+            };
         if let Some(last_block_hash_vec) = last_block_hash_vec {
             let last_block_hash: Hash = match last_block_hash_vec.clone().try_into() {
                 Ok(last_block_hash) => last_block_hash,
@@ -356,22 +363,30 @@ impl Icrc1Agent {
 
             let last_block_index_vec = lookup_leaf(&hash_tree, "last_block_index")?;
             if let Some(last_block_index_vec) = last_block_index_vec {
-                let last_block_index_bytes: [u8; 8] = match last_block_index_vec.clone().try_into()
-                {
-                    Ok(last_block_index_bytes) => last_block_index_bytes,
-                    Err(_) => {
+                let last_block_index = if icrc3_encoding {
+                    decode_leb128_u64(&last_block_index_vec)
+                } else {
+                    last_block_index_vec
+                        .clone()
+                        .try_into()
+                        .ok()
+                        .map(u64::from_be_bytes)
+                };
+                let last_block_index = match last_block_index {
+                    Some(last_block_index) => last_block_index,
+                    None => {
                         return Err(Icrc1AgentError::VerificationFailed(format!(
                     "DataCertificate hash_tree bytes: {}, cannot be decoded as last_block_index",
                     hex::encode(last_block_index_vec)
                 )))
                     }
                 };
-                let last_block_index = u64::from_be_bytes(last_block_index_bytes);
 
                 return Ok(Some((last_block_hash, Nat::from(last_block_index))));
             } else {
                 return Err(Icrc1AgentError::VerificationFailed(
-                    "certified hash_tree contains tip_hash but not last_block_index".to_string(),
+                    "certified hash_tree contains the last block hash but not last_block_index"
+                        .to_string(),
                 ));
             }
         }
@@ -380,6 +395,23 @@ impl Icrc1Agent {
     }
 }
 
+/// Decodes an unsigned LEB128-encoded integer that must span the whole slice.
+fn decode_leb128_u64(bytes: &[u8]) -> Option<u64> {
+    let mut result: u64 = 0;
+    for (i, byte) in bytes.iter().enumerate() {
+        let bits = (*byte & 0x7f) as u64;
+        let shift = 7 * i as u32;
+        if shift >= 64 || (shift > 0 && bits >> (64 - shift) != 0) {
+            return None;
+        }
+        result |= bits << shift;
+        if byte & 0x80 == 0 {
+            return (i + 1 == bytes.len()).then_some(result);
+        }
+    }
+    None
+}
+
 fn lookup_leaf(hash_tree: &HashTree, leaf_name: &str) -> Result<Option<Vec<u8>>, Icrc1AgentError> {
     match hash_tree.lookup_subtree([leaf_name.as_bytes()]) {
         SubtreeLookupResult::Found(tree) => match tree.as_ref() {
@@ -396,3 +428,21 @@ fn lookup_leaf(hash_tree: &HashTree, leaf_name: &str) -> Result<Option<Vec<u8>>,
         ))),
     }
 }
+
+#[test]
+fn test_decode_leb128_u64() {
+    assert_eq!(decode_leb128_u64(&[0]), Some(0));
+    assert_eq!(decode_leb128_u64(&[0xe5, 0x8e, 0x26]), Some(624485));
+    assert_eq!(
+        decode_leb128_u64(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]),
+        Some(u64::MAX)
+    );
+    // Overflow, truncated input, and trailing bytes.
+    assert_eq!(
+        decode_leb128_u64(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02]),
+        None
+    );
+    assert_eq!(decode_leb128_u64(&[0x80]), None);
+    assert_eq!(decode_leb128_u64(&[0x01, 0x00]), None);
+    assert_eq!(decode_leb128_u64(&[]), None);
+}
@@ -133,6 +133,62 @@ impl std::fmt::Display for Value {
     }
 }
 
+/// The generic value type of the ICRC-3 standard.
+///
+/// Unlike [Value], it has no `Nat64` variant: ICRC-3 represents all natural
+/// numbers as `Nat`. Both types have the same representation-independent hash.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ICRC3Value {
+    Blob(ByteBuf),
+    Text(String),
+    Nat(Nat),
+    Int(Int),
+    Array(Vec<ICRC3Value>),
+    Map(BTreeMap<String, ICRC3Value>),
+}
+
+impl ICRC3Value {
+    /// Computes the representation-independent hash of a value.
+    pub fn hash(self) -> Hash {
+        Value::from(self).hash()
+    }
+}
+
+impl From<Value> for ICRC3Value {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Blob(bytes) => Self::Blob(bytes),
+            Value::Text(text) => Self::Text(text),
+            Value::Nat(nat) => Self::Nat(nat),
+            Value::Nat64(n) => Self::Nat(Nat::from(n)),
+            Value::Int(int) => Self::Int(int),
+            Value::Array(values) => Self::Array(values.into_iter().map(Self::from).collect()),
+            Value::Map(map) => Self::Map(map.into_iter().map(|(k, v)| (k, v.into())).collect()),
+        }
+    }
+}
+
+impl From<ICRC3Value> for Value {
+    fn from(value: ICRC3Value) -> Self {
+        match value {
+            ICRC3Value::Blob(bytes) => Self::Blob(bytes),
+            ICRC3Value::Text(text) => Self::Text(text),
+            ICRC3Value::Nat(nat) => Self::Nat(nat),
+            ICRC3Value::Int(int) => Self::Int(int),
+            ICRC3Value::Array(values) => Self::Array(values.into_iter().map(Self::from).collect()),
+            ICRC3Value::Map(map) => {
+                Self::Map(map.into_iter().map(|(k, v)| (k, v.into())).collect())
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ICRC3Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Value::from(self.clone()))
+    }
+}
+
 /// Encodes a 128-bit integer using unsigned LEB-128 encoding.
 /// Returns the index of the last valid byte in the buffer.
 fn leb128(buf: &mut [u8; INT128_BUF_SIZE], v: u128) -> usize {
@@ -283,3 +339,22 @@ fn test_test_vectors() {
         );
     }
 }
+
+#[test]
+fn test_icrc3_value_hash_agrees_with_value_hash() {
+    let value = Value::map(vec![
+        ("amt", Value::Nat64(1_000_000)),
+        ("big", Value::Nat(u128::MAX.into())),
+        ("neg", Value::Int((-42).into())),
+        (
+            "tx",
+            Value::Array(vec![Value::Nat64(0), Value::blob(vec![1, 2, 3])]),
+        ),
+    ]);
+    let icrc3_value = ICRC3Value::from(value.clone());
+    assert_eq!(
+        icrc3_value,
+        ICRC3Value::from(Value::from(icrc3_value.clone()))
+    );
+    assert_eq!(icrc3_value.hash(), value.hash());
+}
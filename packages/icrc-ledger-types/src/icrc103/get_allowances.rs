@@ -0,0 +1,32 @@
+use candid::{CandidType, Deserialize, Nat};
+use serde::Serialize;
+
+use super::super::icrc1::account::Account;
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GetAllowancesArgs {
+    /// The account whose allowances to return. Defaults to the default
+    /// account of the caller.
+    pub from_account: Option<Account>,
+    /// Start the list after the allowance of this spender.
+    pub prev_spender: Option<Account>,
+    /// The maximum number of allowances to return.
+    pub take: Option<Nat>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Allowance {
+    pub from_account: Account,
+    pub to_spender: Account,
+    pub allowance: Nat,
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+pub type Allowances = Vec<Allowance>;
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum GetAllowancesError {
+    InvalidTakeValue,
+    GenericError { error_code: Nat, message: String },
+}
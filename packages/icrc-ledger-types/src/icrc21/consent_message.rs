@@ -0,0 +1,599 @@
+//! Human-readable consent messages for the ICRC-1 and ICRC-2 ledger methods,
+//! see https://github.com/dfinity/wg-identity-authentication/blob/main/topics/ICRC-21/icrc_21_consent_msg.md.
+
+use super::errors::{ErrorInfo, Icrc21Error};
+use super::requests::{ConsentMessageMetadata, ConsentMessageRequest, DisplayMessageType};
+use super::responses::{ConsentInfo, ConsentMessage, LineDisplayPage};
+use crate::icrc1::account::{Account, Subaccount};
+use crate::icrc1::transfer::{Memo, TransferArg};
+use crate::icrc2::approve::ApproveArgs;
+use crate::icrc2::transfer_from::TransferFromArgs;
+use candid::{Decode, Nat, Principal};
+
+/// The language of all consent messages. Requests for other languages are
+/// answered in English, which the response metadata reflects.
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// The token-specific information required to describe a ledger call.
+#[derive(Clone, Debug)]
+pub struct LedgerInfo {
+    pub token_symbol: String,
+    pub decimals: u8,
+    /// The fee the ledger charges if the call does not specify one.
+    pub transfer_fee: Nat,
+}
+
+/// The content of a consent message, independent of the display type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct ConsentMessageContent {
+    title: String,
+    intro: String,
+    fields: Vec<(String, String)>,
+}
+
+impl ConsentMessageContent {
+    fn new(title: &str, intro: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            intro: intro.to_string(),
+            fields: vec![],
+        }
+    }
+
+    fn field(&mut self, label: &str, value: impl ToString) {
+        self.fields.push((label.to_string(), value.to_string()));
+    }
+
+    fn to_generic_display(&self) -> String {
+        let mut message = format!("# {}\n\n{}", self.title, self.intro);
+        for (label, value) in &self.fields {
+            message.push_str(&format!("\n\n**{}:**\n{}", label, value));
+        }
+        message
+    }
+
+    fn to_line_display(
+        &self,
+        characters_per_line: u16,
+        lines_per_page: u16,
+    ) -> Result<Vec<LineDisplayPage>, Icrc21Error> {
+        if characters_per_line == 0 || lines_per_page == 0 {
+            return Err(Icrc21Error::ConsentMessageUnavailable(ErrorInfo {
+                description: "the line display must have at least one line of one character"
+                    .to_string(),
+            }));
+        }
+        let width = characters_per_line as usize;
+        let mut lines = wrap_text(&self.title, width);
+        lines.extend(wrap_text(&self.intro, width));
+        for (label, value) in &self.fields {
+            lines.extend(wrap_text(&format!("{}:", label), width));
+            lines.extend(wrap_text(value, width));
+        }
+        Ok(lines
+            .chunks(lines_per_page as usize)
+            .map(|lines| LineDisplayPage {
+                lines: lines.to_vec(),
+            })
+            .collect())
+    }
+}
+
+/// Builds the consent message for a call of `icrc1_transfer`, `icrc2_approve`
+/// or `icrc2_transfer_from` that `caller` is about to make.
+pub fn build_consent_info(
+    request: ConsentMessageRequest,
+    caller: Principal,
+    ledger_info: &LedgerInfo,
+) -> Result<ConsentInfo, Icrc21Error> {
+    let content = match request.method.as_str() {
+        "icrc1_transfer" => {
+            let arg = Decode!(request.arg.as_slice(), TransferArg).map_err(decoding_error)?;
+            transfer_content(arg, caller, ledger_info)
+        }
+        "icrc2_approve" => {
+            let arg = Decode!(request.arg.as_slice(), ApproveArgs).map_err(decoding_error)?;
+            approve_content(
+                arg,
+                caller,
+                ledger_info,
+                request.user_preferences.metadata.utc_offset_minutes,
+            )
+        }
+        "icrc2_transfer_from" => {
+            let arg = Decode!(request.arg.as_slice(), TransferFromArgs).map_err(decoding_error)?;
+            transfer_from_content(arg, caller, ledger_info)
+        }
+        method => {
+            return Err(Icrc21Error::UnsupportedCanisterCall(ErrorInfo {
+                description: format!("the method {} is not supported", method),
+            }))
+        }
+    };
+
+    let consent_message = match request.user_preferences.device_spec {
+        None | Some(DisplayMessageType::GenericDisplay) => {
+            ConsentMessage::GenericDisplayMessage(content.to_generic_display())
+        }
+        Some(DisplayMessageType::LineDisplay {
+            characters_per_line,
+            lines_per_page,
+        }) => ConsentMessage::LineDisplayMessage {
+            pages: content.to_line_display(characters_per_line, lines_per_page)?,
+        },
+    };
+
+    Ok(ConsentInfo {
+        consent_message,
+        metadata: ConsentMessageMetadata {
+            language: DEFAULT_LANGUAGE.to_string(),
+            utc_offset_minutes: request.user_preferences.metadata.utc_offset_minutes,
+        },
+    })
+}
+
+fn decoding_error(err: candid::Error) -> Icrc21Error {
+    Icrc21Error::UnsupportedCanisterCall(ErrorInfo {
+        description: format!("failed to decode the call argument: {}", err),
+    })
+}
+
+fn transfer_content(
+    arg: TransferArg,
+    caller: Principal,
+    ledger_info: &LedgerInfo,
+) -> ConsentMessageContent {
+    let mut content = ConsentMessageContent::new(
+        "Transfer tokens",
+        "You are sending tokens from your account.",
+    );
+    caller_account_field(&mut content, "From", caller, arg.from_subaccount);
+    content.field(
+        "Amount",
+        format_tokens(&arg.amount, ledger_info.decimals, &ledger_info.token_symbol),
+    );
+    content.field("To", arg.to);
+    content.field("Fee", format_fee(arg.fee, ledger_info));
+    memo_field(&mut content, arg.memo);
+    content
+}
+
+fn approve_content(
+    arg: ApproveArgs,
+    caller: Principal,
+    ledger_info: &LedgerInfo,
+    utc_offset_minutes: Option<i16>,
+) -> ConsentMessageContent {
+    let mut content = ConsentMessageContent::new(
+        "Approve spending",
+        "You are allowing another account to withdraw tokens from your account.",
+    );
+    caller_account_field(&mut content, "From", caller, arg.from_subaccount);
+    content.field("Spender", arg.spender);
+    content.field(
+        "Requested allowance",
+        format_tokens(&arg.amount, ledger_info.decimals, &ledger_info.token_symbol),
+    );
+    if let Some(expected_allowance) = arg.expected_allowance {
+        content.field(
+            "Expected current allowance",
+            format_tokens(
+                &expected_allowance,
+                ledger_info.decimals,
+                &ledger_info.token_symbol,
+            ),
+        );
+    }
+    content.field(
+        "Expiration",
+        match arg.expires_at {
+            Some(expires_at) => format_timestamp(expires_at, utc_offset_minutes),
+            None => "This approval does not expire.".to_string(),
+        },
+    );
+    content.field("Approval fee", format_fee(arg.fee, ledger_info));
+    memo_field(&mut content, arg.memo);
+    content
+}
+
+fn transfer_from_content(
+    arg: TransferFromArgs,
+    caller: Principal,
+    ledger_info: &LedgerInfo,
+) -> ConsentMessageContent {
+    let mut content = ConsentMessageContent::new(
+        "Transfer from a withdrawal account",
+        "You are withdrawing tokens from an account that approved you as a spender.",
+    );
+    content.field("From", arg.from);
+    content.field("To", arg.to);
+    caller_account_field(&mut content, "Spender", caller, arg.spender_subaccount);
+    content.field(
+        "Amount",
+        format_tokens(&arg.amount, ledger_info.decimals, &ledger_info.token_symbol),
+    );
+    content.field(
+        "Fee paid by the withdrawal account",
+        format_fee(arg.fee, ledger_info),
+    );
+    memo_field(&mut content, arg.memo);
+    content
+}
+
+/// Adds the account of the caller. An anonymous caller is not the principal
+/// that will sign the call, so only the subaccount is shown in that case.
+fn caller_account_field(
+    content: &mut ConsentMessageContent,
+    label: &str,
+    caller: Principal,
+    subaccount: Option<Subaccount>,
+) {
+    if caller != Principal::anonymous() {
+        content.field(
+            label,
+            Account {
+                owner: caller,
+                subaccount,
+            },
+        );
+    } else if let Some(subaccount) = subaccount {
+        content.field(&format!("{} subaccount", label), hex::encode(subaccount));
+    }
+}
+
+fn memo_field(content: &mut ConsentMessageContent, memo: Option<Memo>) {
+    if let Some(memo) = memo {
+        content.field("Memo", format_memo(&memo));
+    }
+}
+
+fn format_fee(fee: Option<Nat>, ledger_info: &LedgerInfo) -> String {
+    format_tokens(
+        &fee.unwrap_or_else(|| ledger_info.transfer_fee.clone()),
+        ledger_info.decimals,
+        &ledger_info.token_symbol,
+    )
+}
+
+/// Formats an amount of the smallest token units as a decimal number of
+/// tokens, e.g. `150000000` with 8 decimals as `1.5 ICP`.
+pub fn format_tokens(amount: &Nat, decimals: u8, token_symbol: &str) -> String {
+    // NB. the Display implementation of Nat groups the digits with underscores.
+    let digits = amount.0.to_string();
+    let decimals = decimals as usize;
+    let (integer, fraction) = if digits.len() > decimals {
+        let (integer, fraction) = digits.split_at(digits.len() - decimals);
+        (integer.to_string(), fraction.to_string())
+    } else {
+        (
+            "0".to_string(),
+            format!("{:0>width$}", digits, width = decimals),
+        )
+    };
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        format!("{} {}", integer, token_symbol)
+    } else {
+        format!("{}.{} {}", integer, fraction, token_symbol)
+    }
+}
+
+/// Formats a memo as text if it is printable UTF-8 and as hex otherwise.
+fn format_memo(memo: &Memo) -> String {
+    match std::str::from_utf8(memo.0.as_slice()) {
+        Ok(text) if !text.chars().any(char::is_control) => text.to_string(),
+        _ => format!("0x{}", hex::encode(memo.0.as_slice())),
+    }
+}
+
+/// Formats nanoseconds since the UNIX epoch as a date and time in the time
+/// zone with the given offset from UTC.
+pub fn format_timestamp(timestamp_nanos: u64, utc_offset_minutes: Option<i16>) -> String {
+    let offset_minutes = utc_offset_minutes.unwrap_or(0) as i64;
+    let seconds = (timestamp_nanos / NANOS_PER_SECOND) as i64 + offset_minutes * 60;
+    let (year, month, day) = civil_from_days(seconds.div_euclid(SECONDS_PER_DAY));
+    let seconds_of_day = seconds.rem_euclid(SECONDS_PER_DAY);
+    let time_zone = if offset_minutes == 0 {
+        "UTC".to_string()
+    } else {
+        format!(
+            "UTC{}{:02}:{:02}",
+            if offset_minutes < 0 { '-' } else { '+' },
+            offset_minutes.abs() / 60,
+            offset_minutes.abs() % 60
+        )
+    };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} {}",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60,
+        time_zone
+    )
+}
+
+/// Converts the number of days since 1970-01-01 into a (year, month, day)
+/// date of the proleptic Gregorian calendar.
+/// See http://howardhinnant.github.io/date_algorithms.html#civil_from_days.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+/// Splits the text into lines of at most `width` characters, breaking lines
+/// at spaces where possible and inside words that do not fit into a line.
+fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        if !line.is_empty() && line.chars().count() + 1 + word.len() <= width {
+            line.push(' ');
+            line.extend(word);
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        while word.len() > width {
+            let rest = word.split_off(width);
+            lines.push(word.into_iter().collect());
+            word = rest;
+        }
+        line.extend(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::icrc21::requests::ConsentMessageSpec;
+    use candid::Encode;
+    use serde_bytes::ByteBuf;
+
+    fn ledger_info() -> LedgerInfo {
+        LedgerInfo {
+            token_symbol: "XTST".to_string(),
+            decimals: 8,
+            transfer_fee: Nat::from(10_000_u64),
+        }
+    }
+
+    fn request(
+        method: &str,
+        arg: Vec<u8>,
+        device_spec: Option<DisplayMessageType>,
+    ) -> ConsentMessageRequest {
+        ConsentMessageRequest {
+            method: method.to_string(),
+            arg: ByteBuf::from(arg),
+            user_preferences: ConsentMessageSpec {
+                metadata: ConsentMessageMetadata {
+                    language: "de".to_string(),
+                    utc_offset_minutes: Some(120),
+                },
+                device_spec,
+            },
+        }
+    }
+
+    fn account(n: u8) -> Account {
+        Account {
+            owner: Principal::from_slice(&[n; 29]),
+            subaccount: None,
+        }
+    }
+
+    #[test]
+    fn test_format_tokens() {
+        assert_eq!(format_tokens(&Nat::from(0_u8), 8, "ICP"), "0 ICP");
+        assert_eq!(format_tokens(&Nat::from(1_u8), 8, "ICP"), "0.00000001 ICP");
+        assert_eq!(
+            format_tokens(&Nat::from(150_000_000_u64), 8, "ICP"),
+            "1.5 ICP"
+        );
+        assert_eq!(
+            format_tokens(&Nat::from(12_345_600_000_000_u64), 8, "ICP"),
+            "123456 ICP"
+        );
+        assert_eq!(format_tokens(&Nat::from(42_u8), 0, "T"), "42 T");
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0, None), "1970-01-01 00:00:00 UTC");
+        // 2024-02-29 23:30:00 UTC.
+        let timestamp = 1_709_249_400 * NANOS_PER_SECOND;
+        assert_eq!(
+            format_timestamp(timestamp, Some(0)),
+            "2024-02-29 23:30:00 UTC"
+        );
+        assert_eq!(
+            format_timestamp(timestamp, Some(90)),
+            "2024-03-01 01:00:00 UTC+01:30"
+        );
+        assert_eq!(
+            format_timestamp(timestamp, Some(-600)),
+            "2024-02-29 13:30:00 UTC-10:00"
+        );
+    }
+
+    #[test]
+    fn test_wrap_text() {
+        assert_eq!(
+            wrap_text("Approve the spending", 10),
+            vec!["Approve", "the", "spending"]
+        );
+        assert_eq!(wrap_text("a b c", 3), vec!["a b", "c"]);
+        assert_eq!(wrap_text("abcdefgh xy", 3), vec!["abc", "def", "gh", "xy"]);
+        assert!(wrap_text("", 3).is_empty());
+    }
+
+    #[test]
+    fn test_transfer_generic_display() {
+        let caller = Principal::from_slice(&[7; 29]);
+        let arg = TransferArg {
+            from_subaccount: None,
+            to: account(1),
+            fee: None,
+            created_at_time: None,
+            memo: Some(Memo::from(b"coffee".to_vec())),
+            amount: Nat::from(250_000_000_u64),
+        };
+        let info = build_consent_info(
+            request("icrc1_transfer", Encode!(&arg).unwrap(), None),
+            caller,
+            &ledger_info(),
+        )
+        .unwrap();
+        assert_eq!(
+            info.metadata,
+            ConsentMessageMetadata {
+                language: "en".to_string(),
+                utc_offset_minutes: Some(120),
+            }
+        );
+        assert_eq!(
+            info.consent_message,
+            ConsentMessage::GenericDisplayMessage(format!(
+                "# Transfer tokens\n\nYou are sending tokens from your account.\n\n\
+                 **From:**\n{}\n\n**Amount:**\n2.5 XTST\n\n**To:**\n{}\n\n\
+                 **Fee:**\n0.0001 XTST\n\n**Memo:**\ncoffee",
+                Account::from(caller),
+                account(1)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_approve_line_display() {
+        let arg = ApproveArgs {
+            from_subaccount: Some([1; 32]),
+            spender: account(2),
+            amount: Nat::from(100_000_000_u64),
+            expected_allowance: None,
+            expires_at: Some(1_709_249_400 * NANOS_PER_SECOND),
+            fee: Some(Nat::from(20_000_u64)),
+            memo: Some(Memo::from(vec![0, 1, 2])),
+            created_at_time: None,
+        };
+        let info = build_consent_info(
+            request(
+                "icrc2_approve",
+                Encode!(&arg).unwrap(),
+                Some(DisplayMessageType::LineDisplay {
+                    characters_per_line: 20,
+                    lines_per_page: 4,
+                }),
+            ),
+            Principal::anonymous(),
+            &ledger_info(),
+        )
+        .unwrap();
+        let pages = match info.consent_message {
+            ConsentMessage::LineDisplayMessage { pages } => pages,
+            message => panic!("unexpected consent message {:?}", message),
+        };
+        let lines: Vec<String> = pages.iter().flat_map(|page| page.lines.clone()).collect();
+        assert!(pages.iter().all(|page| page.lines.len() <= 4));
+        assert!(lines.iter().all(|line| line.chars().count() <= 20));
+        assert_eq!(lines[0], "Approve spending");
+        // The anonymous caller is not the signer, so only the subaccount is shown.
+        assert!(lines.contains(&"From subaccount:".to_string()));
+        assert!(lines.contains(&"1 XTST".to_string()));
+        assert!(lines.contains(&"0.0002 XTST".to_string()));
+        assert!(lines.contains(&"0x000102".to_string()));
+        assert!(lines.contains(&"2024-03-01 01:30:00".to_string()));
+        assert!(lines.contains(&"UTC+02:00".to_string()));
+    }
+
+    #[test]
+    fn test_transfer_from_mentions_all_parties() {
+        let caller = Principal::from_slice(&[7; 29]);
+        let arg = TransferFromArgs {
+            spender_subaccount: None,
+            from: account(1),
+            to: account(2),
+            amount: Nat::from(1_u8),
+            fee: None,
+            memo: None,
+            created_at_time: None,
+        };
+        let info = build_consent_info(
+            request("icrc2_transfer_from", Encode!(&arg).unwrap(), None),
+            caller,
+            &ledger_info(),
+        )
+        .unwrap();
+        let message = match info.consent_message {
+            ConsentMessage::GenericDisplayMessage(message) => message,
+            message => panic!("unexpected consent message {:?}", message),
+        };
+        assert!(message.contains(&format!("**From:**\n{}", account(1))));
+        assert!(message.contains(&format!("**To:**\n{}", account(2))));
+        assert!(message.contains(&format!("**Spender:**\n{}", Account::from(caller))));
+        assert!(message.contains("**Amount:**\n0.00000001 XTST"));
+    }
+
+    #[test]
+    fn test_unsupported_calls() {
+        assert!(matches!(
+            build_consent_info(
+                request("icrc1_balance_of", Encode!(&account(1)).unwrap(), None),
+                Principal::anonymous(),
+                &ledger_info(),
+            ),
+            Err(Icrc21Error::UnsupportedCanisterCall(_))
+        ));
+        assert!(matches!(
+            build_consent_info(
+                request("icrc1_transfer", vec![1, 2, 3], None),
+                Principal::anonymous(),
+                &ledger_info(),
+            ),
+            Err(Icrc21Error::UnsupportedCanisterCall(_))
+        ));
+        let arg = TransferArg {
+            from_subaccount: None,
+            to: account(1),
+            fee: None,
+            created_at_time: None,
+            memo: None,
+            amount: Nat::from(1_u8),
+        };
+        assert!(matches!(
+            build_consent_info(
+                request(
+                    "icrc1_transfer",
+                    Encode!(&arg).unwrap(),
+                    Some(DisplayMessageType::LineDisplay {
+                        characters_per_line: 0,
+                        lines_per_page: 4,
+                    }),
+                ),
+                Principal::anonymous(),
+                &ledger_info(),
+            ),
+            Err(Icrc21Error::ConsentMessageUnavailable(_))
+        ));
+    }
+}
@@ -0,0 +1,22 @@
+use candid::{CandidType, Deserialize, Nat};
+use serde::Serialize;
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ErrorInfo {
+    pub description: String,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum Icrc21Error {
+    /// The canister cannot describe the requested call, e.g. because it does
+    /// not know the method or cannot decode its argument.
+    UnsupportedCanisterCall(ErrorInfo),
+    /// The canister cannot produce a consent message for the requested
+    /// preferences, e.g. for a display that is too small.
+    ConsentMessageUnavailable(ErrorInfo),
+    InsufficientPayment(ErrorInfo),
+    GenericError {
+        error_code: Nat,
+        description: String,
+    },
+}
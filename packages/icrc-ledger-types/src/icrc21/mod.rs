@@ -0,0 +1,4 @@
+pub mod consent_message;
+pub mod errors;
+pub mod requests;
+pub mod responses;
@@ -0,0 +1,37 @@
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+use serde_bytes::ByteBuf;
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ConsentMessageMetadata {
+    /// The BCP-47 language tag of the requested (or returned) message.
+    pub language: String,
+    /// The offset of the user's time zone from UTC, used to display dates.
+    pub utc_offset_minutes: Option<i16>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum DisplayMessageType {
+    /// A device that can render Markdown text of arbitrary length.
+    GenericDisplay,
+    /// A device that displays pages of plain text lines of a fixed width.
+    LineDisplay {
+        characters_per_line: u16,
+        lines_per_page: u16,
+    },
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ConsentMessageSpec {
+    pub metadata: ConsentMessageMetadata,
+    pub device_spec: Option<DisplayMessageType>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ConsentMessageRequest {
+    /// The method of the call the user is asked to consent to.
+    pub method: String,
+    /// The Candid-encoded argument of that call.
+    pub arg: ByteBuf,
+    pub user_preferences: ConsentMessageSpec,
+}
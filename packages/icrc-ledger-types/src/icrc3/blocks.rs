@@ -1,5 +1,6 @@
+use crate::icrc::generic_value::ICRC3Value;
 use crate::icrc3::archive::ArchivedRange;
-use crate::icrc3::archive::QueryBlockArchiveFn;
+use crate::icrc3::archive::{QueryArchiveFn, QueryBlockArchiveFn};
 use crate::{icrc::generic_value::Value, icrc1::transfer::BlockIndex};
 use candid::{CandidType, Deserialize, Nat};
 use serde::Serialize;
@@ -7,6 +8,8 @@ use serde_bytes::ByteBuf;
 
 pub type GenericBlock = Value;
 
+pub type ICRC3GenericBlock = ICRC3Value;
+
 #[derive(Debug, CandidType, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct GetBlocksResponse {
     pub first_index: BlockIndex,
@@ -53,3 +56,45 @@ pub struct DataCertificate {
     pub certificate: Option<serde_bytes::ByteBuf>,
     pub hash_tree: serde_bytes::ByteBuf,
 }
+
+/// The arguments of the ICRC-3 `icrc3_get_blocks` endpoint.
+pub type GetBlocksArgs = Vec<GetBlocksRequest>;
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BlockWithId {
+    pub id: Nat,
+    pub block: ICRC3GenericBlock,
+}
+
+/// A callback that fetches blocks from an archive using `icrc3_get_blocks`.
+pub type ICRC3QueryArchiveFn = QueryArchiveFn<GetBlocksArgs, GetBlocksResult>;
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ArchivedBlocks {
+    pub args: GetBlocksArgs,
+    pub callback: ICRC3QueryArchiveFn,
+}
+
+/// The result of the ICRC-3 `icrc3_get_blocks` endpoint.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GetBlocksResult {
+    pub log_length: Nat,
+    pub blocks: Vec<BlockWithId>,
+    pub archived_blocks: Vec<ArchivedBlocks>,
+}
+
+/// The result of the ICRC-3 `icrc3_get_tip_certificate` endpoint.
+///
+/// The hash tree certifies the `last_block_index` (LEB128-encoded) and the
+/// `last_block_hash` labels.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ICRC3DataCertificate {
+    pub certificate: serde_bytes::ByteBuf,
+    pub hash_tree: serde_bytes::ByteBuf,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SupportedBlockType {
+    pub block_type: String,
+    pub url: String,
+}
@@ -0,0 +1,23 @@
+use candid::{CandidType, Deserialize, Nat};
+use serde::Serialize;
+
+use super::super::icrc1::transfer::{TransferArg, TransferError};
+
+/// The transfers of a batch. The ledger applies each transfer independently,
+/// in order, on behalf of the caller.
+pub type TransferBatchArgs = Vec<TransferArg>;
+
+/// The result of each transfer of a batch, in the order of the arguments.
+pub type TransferBatchResults = Vec<Result<Nat, TransferError>>;
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum TransferBatchError {
+    /// The batch contains more transfers than the ledger accepts in a single call.
+    TooManyRequests {
+        limit: Nat,
+    },
+    GenericError {
+        error_code: Nat,
+        message: String,
+    },
+}
@@ -1,4 +1,7 @@
 pub mod icrc;
 pub mod icrc1;
 pub mod icrc2;
+pub mod icrc103;
+pub mod icrc21;
 pub mod icrc3;
+pub mod icrc4;
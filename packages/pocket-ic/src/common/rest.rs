@@ -25,6 +25,25 @@ pub enum CreateInstanceResponse {
     },
 }
 
+/// Configuration of a new PocketIC instance.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct InstanceConfig {
+    pub subnet_config_set: ExtendedSubnetConfigSet,
+    /// If provided, the state of the instance is persisted in this directory
+    /// (which must be accessible for the PocketIC server process) and an instance
+    /// created later with the same directory resumes from that state.
+    /// In that case, the subnet config set is taken from the directory
+    /// and `subnet_config_set` is ignored.
+    pub state_dir: Option<PathBuf>,
+}
+
+/// Configuration of the auto-progress mode of an instance.
+#[derive(Clone, Serialize, Deserialize, Debug, Copy, JsonSchema)]
+pub struct AutoProgressConfig {
+    /// The interval between two rounds in milliseconds (100ms by default).
+    pub round_interval_ms: Option<u64>,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, Copy, JsonSchema)]
 pub struct RawTime {
     pub nanos_since_epoch: u64,
@@ -71,6 +90,34 @@ pub enum RawCanisterResult {
     Err(UserError),
 }
 
+/// Identifies an ingress message submitted to the subnet with the given ID.
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema, PartialEq, Eq, Hash)]
+pub struct RawMessageId {
+    pub subnet_id: RawSubnetId,
+    #[serde(deserialize_with = "base64::deserialize")]
+    #[serde(serialize_with = "base64::serialize")]
+    pub message_id: Vec<u8>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema)]
+pub enum RawSubmitIngressResult {
+    Ok(RawMessageId),
+    Err(UserError),
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema)]
+pub enum RawIngressStatus {
+    /// The message is not known to the subnet.
+    Unknown,
+    /// The message has been inducted into the subnet, but not executed yet.
+    Received,
+    /// The message is being processed, e.g., it is waiting for a downstream call.
+    Processing,
+    Completed(RawCanisterResult),
+    /// The message has completed, but its result has been pruned.
+    Done,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, JsonSchema)]
 pub enum RawWasmResult {
     /// Raw response, returned in a "happy" case
@@ -234,6 +281,188 @@ pub struct RawVerifyCanisterSigArg {
     pub root_pubkey: Vec<u8>,
 }
 
+#[derive(
+    Clone, Copy, Serialize, Deserialize, Debug, JsonSchema, PartialEq, Eq, PartialOrd, Ord,
+)]
+pub enum CanisterHttpMethod {
+    GET,
+    POST,
+    HEAD,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CanisterHttpHeader {
+    pub name: String,
+    pub value: String,
+}
+
+/// A pending canister HTTP request (HTTPS outcall) made by a canister on a subnet.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CanisterHttpRequest {
+    pub subnet_id: SubnetId,
+    pub request_id: u64,
+    pub http_method: CanisterHttpMethod,
+    pub url: String,
+    pub headers: Vec<CanisterHttpHeader>,
+    #[serde(deserialize_with = "base64::deserialize")]
+    #[serde(serialize_with = "base64::serialize")]
+    pub body: Vec<u8>,
+    pub max_response_bytes: Option<u64>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema, PartialEq, Eq)]
+pub struct RawCanisterHttpRequest {
+    pub subnet_id: RawSubnetId,
+    pub request_id: u64,
+    pub http_method: CanisterHttpMethod,
+    pub url: String,
+    pub headers: Vec<CanisterHttpHeader>,
+    #[serde(deserialize_with = "base64::deserialize")]
+    #[serde(serialize_with = "base64::serialize")]
+    pub body: Vec<u8>,
+    pub max_response_bytes: Option<u64>,
+}
+
+impl From<CanisterHttpRequest> for RawCanisterHttpRequest {
+    fn from(request: CanisterHttpRequest) -> Self {
+        Self {
+            subnet_id: request.subnet_id.into(),
+            request_id: request.request_id,
+            http_method: request.http_method,
+            url: request.url,
+            headers: request.headers,
+            body: request.body,
+            max_response_bytes: request.max_response_bytes,
+        }
+    }
+}
+
+impl From<RawCanisterHttpRequest> for CanisterHttpRequest {
+    fn from(request: RawCanisterHttpRequest) -> Self {
+        Self {
+            subnet_id: request.subnet_id.into(),
+            request_id: request.request_id,
+            http_method: request.http_method,
+            url: request.url,
+            headers: request.headers,
+            body: request.body,
+            max_response_bytes: request.max_response_bytes,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema, PartialEq, Eq)]
+pub struct CanisterHttpReply {
+    pub status: u16,
+    pub headers: Vec<CanisterHttpHeader>,
+    #[serde(deserialize_with = "base64::deserialize")]
+    #[serde(serialize_with = "base64::serialize")]
+    pub body: Vec<u8>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema, PartialEq, Eq)]
+pub struct CanisterHttpReject {
+    pub reject_code: u64,
+    pub message: String,
+}
+
+/// The response to a canister HTTP request, as received by a single replica.
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema, PartialEq, Eq)]
+pub enum CanisterHttpResponse {
+    CanisterHttpReply(CanisterHttpReply),
+    CanisterHttpReject(CanisterHttpReject),
+}
+
+/// A mocked response to a pending canister HTTP request.
+///
+/// By default, every replica of the subnet receives `response`. If
+/// `additional_responses` is not empty, it must contain one response for each
+/// of the remaining replicas of the subnet: the transform function of the
+/// canister is applied to every response and the canister receives the result
+/// only if enough replicas agree on it, as under consensus.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct MockCanisterHttpResponse {
+    pub subnet_id: SubnetId,
+    pub request_id: u64,
+    pub response: CanisterHttpResponse,
+    pub additional_responses: Vec<CanisterHttpResponse>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema, PartialEq, Eq)]
+pub struct RawMockCanisterHttpResponse {
+    pub subnet_id: RawSubnetId,
+    pub request_id: u64,
+    pub response: CanisterHttpResponse,
+    pub additional_responses: Vec<CanisterHttpResponse>,
+}
+
+impl From<MockCanisterHttpResponse> for RawMockCanisterHttpResponse {
+    fn from(mock: MockCanisterHttpResponse) -> Self {
+        Self {
+            subnet_id: mock.subnet_id.into(),
+            request_id: mock.request_id,
+            response: mock.response,
+            additional_responses: mock.additional_responses,
+        }
+    }
+}
+
+impl From<RawMockCanisterHttpResponse> for MockCanisterHttpResponse {
+    fn from(mock: RawMockCanisterHttpResponse) -> Self {
+        Self {
+            subnet_id: mock.subnet_id.into(),
+            request_id: mock.request_id,
+            response: mock.response,
+            additional_responses: mock.additional_responses,
+        }
+    }
+}
+
+/// An output paid by the coinbase transaction of a block mined on the
+/// simulated Bitcoin (regtest) network of an instance.
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema, PartialEq, Eq)]
+pub struct BitcoinOutput {
+    /// A regtest address, e.g., `bcrt1q...`.
+    pub address: String,
+    /// The amount in satoshi.
+    pub value: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema, PartialEq, Eq)]
+pub struct RawMineBitcoinBlocks {
+    /// The outputs paid by the coinbase transaction of the first block.
+    pub outputs: Vec<BitcoinOutput>,
+    pub num_blocks: u32,
+    /// The hash of the block the first block is mined on, if not the tip.
+    pub parent: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema, PartialEq, Eq)]
+pub struct RawBitcoinTransaction {
+    #[serde(deserialize_with = "base64::deserialize")]
+    #[serde(serialize_with = "base64::serialize")]
+    pub transaction: Vec<u8>,
+}
+
+/// Configuration of an HTTP gateway which serves the frontends of the canisters
+/// of a PocketIC instance, e.g., to a browser.
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema)]
+pub struct HttpGatewayConfig {
+    /// The port the HTTP gateway listens at. If `None`, a free port is chosen.
+    pub listen_at: Option<u16>,
+    /// The instance to which the HTTP gateway forwards requests.
+    pub forward_to: InstanceId,
+}
+
+/// Details of a running HTTP gateway.
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema)]
+pub struct HttpGatewayInfo {
+    /// The ID of the HTTP gateway, used to stop it via `/http_gateway/<gateway_id>/stop`.
+    pub gateway_id: usize,
+    /// The port the HTTP gateway listens at.
+    pub port: u16,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize, JsonSchema)]
 pub struct BlobId(
     #[serde(deserialize_with = "base64::deserialize")]
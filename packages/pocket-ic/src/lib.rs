@@ -33,9 +33,12 @@
 //! For more information, see the [README](https://crates.io/crates/pocket-ic).
 //!
 use crate::common::rest::{
-    ApiResponse, BlobCompression, BlobId, CreateInstanceResponse, ExtendedSubnetConfigSet,
-    InstanceId, RawAddCycles, RawCanisterCall, RawCanisterId, RawCanisterResult, RawCycles,
-    RawEffectivePrincipal, RawSetStableMemory, RawStableMemory, RawSubnetId, RawTime,
+    ApiResponse, AutoProgressConfig, BitcoinOutput, BlobCompression, BlobId, CanisterHttpRequest,
+    CreateInstanceResponse, ExtendedSubnetConfigSet, InstanceConfig, InstanceId,
+    MockCanisterHttpResponse, RawAddCycles, RawBitcoinTransaction, RawCanisterCall,
+    RawCanisterHttpRequest, RawCanisterId, RawCanisterResult, RawCycles, RawEffectivePrincipal,
+    RawIngressStatus, RawMessageId, RawMineBitcoinBlocks, RawMockCanisterHttpResponse,
+    RawSetStableMemory, RawStableMemory, RawSubmitIngressResult, RawSubnetId, RawTime,
     RawVerifyCanisterSigArg, RawWasmResult, SubnetId, SubnetSpec, Topology,
 };
 use candid::{
@@ -65,12 +68,14 @@ pub mod common;
 const PROCESSING_TIME_HEADER: &str = "processing-timeout-ms";
 const PROCESSING_TIME_VALUE_MS: u64 = 300_000;
 const LOCALHOST: &str = "127.0.0.1";
+const BUSY_RETRY_DELAY: Duration = Duration::from_millis(10);
 
 const LOG_DIR_PATH_ENV_NAME: &str = "POCKET_IC_LOG_DIR";
 const LOG_DIR_LEVELS_ENV_NAME: &str = "POCKET_IC_LOG_DIR_LEVELS";
 
 pub struct PocketIcBuilder {
     config: ExtendedSubnetConfigSet,
+    state_dir: Option<PathBuf>,
 }
 
 #[allow(clippy::new_without_default)]
@@ -78,11 +83,25 @@ impl PocketIcBuilder {
     pub fn new() -> Self {
         Self {
             config: ExtendedSubnetConfigSet::default(),
+            state_dir: None,
         }
     }
 
     pub fn build(self) -> PocketIc {
-        PocketIc::from_config(self.config)
+        PocketIc::from_config_and_state_dir(self.config, self.state_dir)
+    }
+
+    /// Persist the state of the instance in the given directory. Note that the provided path
+    /// must be accessible for the PocketIC server process.
+    ///
+    /// If the directory already contains the state of a PocketIC instance (e.g., created by
+    /// a previous PocketIC server), the new instance resumes from that state and the subnets
+    /// configured on this builder are ignored.
+    pub fn with_state_dir(self, state_dir: PathBuf) -> Self {
+        Self {
+            state_dir: Some(state_dir),
+            ..self
+        }
     }
 
     /// Add an empty NNS subnet
@@ -211,8 +230,27 @@ impl PocketIc {
     /// Creates a new PocketIC instance with the specified subnet config.
     /// The server is started if it's not already running.
     pub fn from_config(config: impl Into<ExtendedSubnetConfigSet>) -> Self {
+        Self::from_config_and_state_dir(config, None)
+    }
+
+    /// Creates a new PocketIC instance with the specified subnet config whose state
+    /// is persisted in the given directory (if provided).
+    /// If the directory already contains the state of a PocketIC instance,
+    /// the new instance resumes from that state and the specified subnet config is ignored.
+    /// The server is started if it's not already running.
+    pub fn from_config_and_state_dir(
+        config: impl Into<ExtendedSubnetConfigSet>,
+        state_dir: Option<PathBuf>,
+    ) -> Self {
         let config = config.into();
-        config.validate().unwrap();
+        // The subnet config is ignored if an instance is resumed from the state directory.
+        if state_dir.is_none() {
+            config.validate().unwrap();
+        }
+        let instance_config = InstanceConfig {
+            subnet_config_set: config,
+            state_dir,
+        };
 
         let parent_pid = std::os::unix::process::parent_id();
         let log_guard = setup_tracing(parent_pid);
@@ -221,7 +259,7 @@ impl PocketIc {
         let reqwest_client = reqwest::blocking::Client::new();
         let (instance_id, topology) = match reqwest_client
             .post(server_url.join("instances").unwrap())
-            .json(&config)
+            .json(&instance_config)
             .send()
             .expect("Failed to get result")
             .json::<CreateInstanceResponse>()
@@ -346,6 +384,81 @@ impl PocketIc {
         self.post::<(), _>(endpoint, "");
     }
 
+    /// Make the instance progress automatically: its time follows the wall-clock time
+    /// and a round is executed on every subnet each `round_interval` (100ms by default).
+    /// Together with the HTTP interface of the instance, this allows using the instance
+    /// as a local replica.
+    #[instrument(skip(self), fields(instance_id=self.instance_id))]
+    pub fn auto_progress(&self, round_interval: Option<Duration>) {
+        let endpoint = "auto_progress";
+        self.post::<(), _>(
+            endpoint,
+            AutoProgressConfig {
+                round_interval_ms: round_interval.map(|d| d.as_millis() as u64),
+            },
+        );
+    }
+
+    /// Stop the automatic progress of the instance started by `auto_progress`.
+    #[instrument(skip(self), fields(instance_id=self.instance_id))]
+    pub fn stop_progress(&self) {
+        let endpoint = "stop_progress";
+        self.post::<(), _>(endpoint, "");
+    }
+
+    /// Get the pending canister HTTP requests (HTTPS outcalls) of all subnets.
+    #[instrument(ret, skip(self), fields(instance_id=self.instance_id))]
+    pub fn get_canister_http(&self) -> Vec<CanisterHttpRequest> {
+        let endpoint = "read/get_canister_http";
+        let res: Vec<RawCanisterHttpRequest> = self.get(endpoint);
+        res.into_iter().map(|r| r.into()).collect()
+    }
+
+    /// Mock a response to a pending canister HTTP request. The response is
+    /// delivered to the canister in a new block on its subnet.
+    #[instrument(skip(self), fields(instance_id=self.instance_id))]
+    pub fn mock_canister_http_response(
+        &self,
+        mock_canister_http_response: MockCanisterHttpResponse,
+    ) {
+        let endpoint = "update/mock_canister_http";
+        let raw: RawMockCanisterHttpResponse = mock_canister_http_response.into();
+        self.post::<(), _>(endpoint, raw);
+    }
+
+    /// Mine `num_blocks` blocks on the simulated Bitcoin (regtest) network of the
+    /// instance and return their hashes. The first block is mined on top of `parent`
+    /// (or the tip if not set), pays the given outputs, and includes all transactions
+    /// sent by canisters that are not in a block yet; the other blocks are empty.
+    /// Mining on top of a block other than the tip simulates a fork.
+    ///
+    /// The Bitcoin canister receives the blocks in the next rounds of its subnet.
+    /// The simulated network only serves Bitcoin canisters configured for regtest.
+    #[instrument(ret, skip(self), fields(instance_id=self.instance_id))]
+    pub fn mine_bitcoin_blocks(
+        &self,
+        outputs: Vec<BitcoinOutput>,
+        num_blocks: u32,
+        parent: Option<String>,
+    ) -> Vec<String> {
+        let endpoint = "update/mine_bitcoin_blocks";
+        let raw = RawMineBitcoinBlocks {
+            outputs,
+            num_blocks,
+            parent,
+        };
+        self.post(endpoint, raw)
+    }
+
+    /// Get all (consensus-encoded) transactions sent by canisters to the simulated
+    /// Bitcoin network of the instance, in the order they were sent.
+    #[instrument(ret, skip(self), fields(instance_id=self.instance_id))]
+    pub fn get_bitcoin_transactions(&self) -> Vec<Vec<u8>> {
+        let endpoint = "read/get_bitcoin_transactions";
+        let res: Vec<RawBitcoinTransaction> = self.get(endpoint);
+        res.into_iter().map(|r| r.transaction).collect()
+    }
+
     /// Get the root key of this IC instance. Returns `None` if the IC has no NNS subnet.
     #[instrument(skip(self), fields(instance_id=self.instance_id))]
     pub fn root_key(&self) -> Option<Vec<u8>> {
@@ -433,6 +546,66 @@ impl PocketIc {
         )
     }
 
+    /// Submit an update call to a canister without executing it. The call is executed
+    /// in the next rounds (e.g., triggered by `tick`), so that several calls can be in flight
+    /// at the same time. Use `ingress_status` or `await_call` to obtain its result.
+    #[instrument(skip(self, payload), fields(instance_id=self.instance_id, canister_id = %canister_id.to_string(), sender = %sender.to_string(), method = %method, payload_len = %payload.len()))]
+    pub fn submit_call(
+        &self,
+        canister_id: CanisterId,
+        sender: Principal,
+        method: &str,
+        payload: Vec<u8>,
+    ) -> Result<RawMessageId, UserError> {
+        let endpoint = "update/submit_ingress_message";
+        let raw_canister_call = RawCanisterCall {
+            sender: sender.as_slice().to_vec(),
+            canister_id: canister_id.as_slice().to_vec(),
+            method: method.to_string(),
+            payload,
+            effective_principal: RawEffectivePrincipal::None,
+        };
+        let result: RawSubmitIngressResult = self.post(endpoint, raw_canister_call);
+        match result {
+            RawSubmitIngressResult::Ok(message_id) => Ok(message_id),
+            RawSubmitIngressResult::Err(user_error) => Err(user_error),
+        }
+    }
+
+    /// Get the status of an update call submitted via `submit_call`. No rounds are executed.
+    #[instrument(skip(self), fields(instance_id=self.instance_id))]
+    pub fn ingress_status(&self, message_id: &RawMessageId) -> IngressStatus {
+        let endpoint = "read/ingress_status";
+        let result: RawIngressStatus = self.post(endpoint, message_id);
+        match result {
+            RawIngressStatus::Unknown => IngressStatus::Unknown,
+            RawIngressStatus::Received => IngressStatus::Received,
+            RawIngressStatus::Processing => IngressStatus::Processing,
+            RawIngressStatus::Completed(result) => IngressStatus::Completed(result.into()),
+            RawIngressStatus::Done => IngressStatus::Done,
+        }
+    }
+
+    /// Await an update call submitted via `submit_call` by executing rounds
+    /// until the call completes.
+    #[instrument(skip(self), fields(instance_id=self.instance_id))]
+    pub fn await_call(&self, message_id: RawMessageId) -> Result<WasmResult, UserError> {
+        let max_rounds = 100;
+        for _ in 0..max_rounds {
+            match self.ingress_status(&message_id) {
+                IngressStatus::Completed(result) => return result,
+                IngressStatus::Done => panic!("The result of the update call has been pruned."),
+                IngressStatus::Unknown | IngressStatus::Received | IngressStatus::Processing => {
+                    self.tick()
+                }
+            }
+        }
+        panic!(
+            "The update call did not complete after {} rounds.",
+            max_rounds
+        );
+    }
+
     /// Execute a query call on a canister.
     #[instrument(skip(self, payload), fields(instance_id=self.instance_id, canister_id = %canister_id.to_string(), sender = %sender.to_string(), method = %method, payload_len = %payload.len()))]
     pub fn query_call(
@@ -754,35 +927,39 @@ impl PocketIc {
     }
 
     fn get<T: DeserializeOwned>(&self, endpoint: &str) -> T {
-        let result = self
-            .reqwest_client
-            .get(self.instance_url().join(endpoint).unwrap())
-            .header(PROCESSING_TIME_HEADER, PROCESSING_TIME_VALUE_MS)
-            .send()
-            .expect("HTTP failure");
-        Self::check_response(result)
+        let url = self.instance_url().join(endpoint).unwrap();
+        self.request(|| {
+            self.reqwest_client
+                .get(url.clone())
+                .header(PROCESSING_TIME_HEADER, PROCESSING_TIME_VALUE_MS)
+        })
     }
 
     fn post<T: DeserializeOwned, B: Serialize>(&self, endpoint: &str, body: B) -> T {
-        let result = self
-            .reqwest_client
-            .post(self.instance_url().join(endpoint).unwrap())
-            .header(PROCESSING_TIME_HEADER, PROCESSING_TIME_VALUE_MS)
-            .json(&body)
-            .send()
-            .expect("HTTP failure");
-        Self::check_response(result)
+        let url = self.instance_url().join(endpoint).unwrap();
+        self.request(|| {
+            self.reqwest_client
+                .post(url.clone())
+                .header(PROCESSING_TIME_HEADER, PROCESSING_TIME_VALUE_MS)
+                .json(&body)
+        })
     }
 
-    fn check_response<T: DeserializeOwned>(result: reqwest::blocking::Response) -> T {
-        match result.into() {
-            ApiResponse::Success(t) => t,
-            ApiResponse::Error { message } => panic!("{}", message),
-            ApiResponse::Busy { state_label, op_id } => {
-                panic!("Busy: state_label: {}, op_id: {}", state_label, op_id)
-            }
-            ApiResponse::Started { state_label, op_id } => {
-                panic!("Started: state_label: {}, op_id: {}", state_label, op_id)
+    /// Sends the request and retries as long as the instance is busy with another
+    /// operation, e.g., with a round executed in auto-progress mode.
+    fn request<T: DeserializeOwned>(
+        &self,
+        request: impl Fn() -> reqwest::blocking::RequestBuilder,
+    ) -> T {
+        loop {
+            let result = request().send().expect("HTTP failure");
+            match result.into() {
+                ApiResponse::Success(t) => break t,
+                ApiResponse::Error { message } => panic!("{}", message),
+                ApiResponse::Busy { .. } => std::thread::sleep(BUSY_RETRY_DELAY),
+                ApiResponse::Started { state_label, op_id } => {
+                    panic!("Started: state_label: {}, op_id: {}", state_label, op_id)
+                }
             }
         }
     }
@@ -805,13 +982,7 @@ impl PocketIc {
         };
 
         let result: RawCanisterResult = self.post(endpoint, raw_canister_call);
-        match result {
-            RawCanisterResult::Ok(raw_wasm_result) => match raw_wasm_result {
-                RawWasmResult::Reply(data) => Ok(WasmResult::Reply(data)),
-                RawWasmResult::Reject(text) => Ok(WasmResult::Reject(text)),
-            },
-            RawCanisterResult::Err(user_error) => Err(user_error),
-        }
+        result.into()
     }
 
     fn update_call_with_effective_principal(
@@ -1050,6 +1221,7 @@ pub enum ErrorCode {
     CanisterMethodNotFound = 302,
     CanisterAlreadyInstalled = 303,
     CanisterWasmModuleNotFound = 304,
+    CanisterSnapshotNotFound = 305,
     InsufficientMemoryAllocation = 402,
     InsufficientCyclesForCreateCanister = 403,
     SubnetNotFound = 404,
@@ -1089,6 +1261,7 @@ pub enum ErrorCode {
     ReservedCyclesLimitExceededInMemoryAllocation = 533,
     ReservedCyclesLimitExceededInMemoryGrow = 534,
     InsufficientCyclesInMessageMemoryGrow = 535,
+    CanisterWasmMemoryLimitExceeded = 536,
 }
 
 impl TryFrom<u64> for ErrorCode {
@@ -1106,6 +1279,7 @@ impl TryFrom<u64> for ErrorCode {
             302 => Ok(ErrorCode::CanisterMethodNotFound),
             303 => Ok(ErrorCode::CanisterAlreadyInstalled),
             304 => Ok(ErrorCode::CanisterWasmModuleNotFound),
+            305 => Ok(ErrorCode::CanisterSnapshotNotFound),
             402 => Ok(ErrorCode::InsufficientMemoryAllocation),
             403 => Ok(ErrorCode::InsufficientCyclesForCreateCanister),
             404 => Ok(ErrorCode::SubnetNotFound),
@@ -1145,6 +1319,7 @@ impl TryFrom<u64> for ErrorCode {
             533 => Ok(ErrorCode::ReservedCyclesLimitExceededInMemoryAllocation),
             534 => Ok(ErrorCode::ReservedCyclesLimitExceededInMemoryGrow),
             535 => Ok(ErrorCode::InsufficientCyclesInMessageMemoryGrow),
+            536 => Ok(ErrorCode::CanisterWasmMemoryLimitExceeded),
             _ => Err(TryFromError::ValueOutOfRange(err)),
         }
     }
@@ -1195,6 +1370,33 @@ pub enum WasmResult {
     Reject(String),
 }
 
+impl From<RawCanisterResult> for Result<WasmResult, UserError> {
+    fn from(result: RawCanisterResult) -> Self {
+        match result {
+            RawCanisterResult::Ok(raw_wasm_result) => match raw_wasm_result {
+                RawWasmResult::Reply(data) => Ok(WasmResult::Reply(data)),
+                RawWasmResult::Reject(text) => Ok(WasmResult::Reject(text)),
+            },
+            RawCanisterResult::Err(user_error) => Err(user_error),
+        }
+    }
+}
+
+/// The status of an update call submitted via [`PocketIc::submit_call`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IngressStatus {
+    /// The call is not known to the subnet.
+    Unknown,
+    /// The call has been inducted into the subnet, but not executed yet.
+    Received,
+    /// The call is being processed, e.g., it is waiting for a downstream call.
+    Processing,
+    /// The call has completed with the given result.
+    Completed(Result<WasmResult, UserError>),
+    /// The call has completed, but its result has been pruned.
+    Done,
+}
+
 /// Attempt to start a new PocketIC server if it's not already running.
 pub fn start_or_reuse_server() -> Url {
     let bin_path = match std::env::var_os("POCKET_IC_BIN") {
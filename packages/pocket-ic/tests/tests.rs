@@ -7,7 +7,7 @@ use icp_ledger::{
     Symbol, Tokens, TransferArgs, TransferError,
 };
 use pocket_ic::{
-    common::rest::{BlobCompression, SubnetConfigSet, SubnetKind},
+    common::rest::{BitcoinOutput, BlobCompression, SubnetConfigSet, SubnetKind},
     PocketIc, PocketIcBuilder, WasmResult,
 };
 use std::{collections::HashMap, io::Read, time::SystemTime};
@@ -576,6 +576,21 @@ fn test_tick() {
     pic.tick();
 }
 
+#[test]
+fn test_auto_progress() {
+    let pic = PocketIc::new();
+    let time = pic.get_time();
+
+    pic.auto_progress(Some(std::time::Duration::from_millis(10)));
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    pic.stop_progress();
+    let progressed_time = pic.get_time();
+    assert!(progressed_time > time);
+
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    assert_eq!(pic.get_time(), progressed_time);
+}
+
 #[test]
 fn test_root_key() {
     let pic = PocketIc::new();
@@ -669,3 +684,22 @@ fn test_set_and_get_stable_memory_compressed() {
     let read_data = pic.get_stable_memory(canister_id);
     assert_eq!(data, read_data[..8]);
 }
+
+#[test]
+fn test_mine_bitcoin_blocks() {
+    let pic = PocketIc::new();
+    let outputs = vec![BitcoinOutput {
+        address: "mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn".to_string(),
+        value: 100_000,
+    }];
+
+    let blocks = pic.mine_bitcoin_blocks(outputs, 2, None);
+    assert_eq!(blocks.len(), 2);
+
+    // Mining on top of the first block forks the chain.
+    let fork = pic.mine_bitcoin_blocks(vec![], 1, Some(blocks[0].clone()));
+    assert_eq!(fork.len(), 1);
+    assert_ne!(fork[0], blocks[1]);
+
+    assert!(pic.get_bitcoin_transactions().is_empty());
+}
@@ -26,7 +26,7 @@ use ic_types::artifact_kind::EcdsaArtifact;
 use ic_types::consensus::{
     ecdsa::{
         EcdsaArtifactId, EcdsaComplaint, EcdsaMessage, EcdsaMessageType, EcdsaOpening,
-        EcdsaPrefixOf, EcdsaSigShare, EcdsaStats, EcdsaStatsNoOp,
+        EcdsaPrefixOf, EcdsaSigShare, EcdsaStats, EcdsaStatsNoOp, SchnorrSigShare,
     },
     CatchUpPackage,
 };
@@ -257,6 +257,21 @@ impl EcdsaPoolSection for InMemoryEcdsaPoolSection {
         let object_pool = self.get_pool(EcdsaMessageType::Opening);
         object_pool.iter_by_prefix(prefix)
     }
+
+    fn schnorr_signature_shares(
+        &self,
+    ) -> Box<dyn Iterator<Item = (EcdsaMessageId, SchnorrSigShare)> + '_> {
+        let object_pool = self.get_pool(EcdsaMessageType::SchnorrSigShare);
+        object_pool.iter()
+    }
+
+    fn schnorr_signature_shares_by_prefix(
+        &self,
+        prefix: EcdsaPrefixOf<SchnorrSigShare>,
+    ) -> Box<dyn Iterator<Item = (EcdsaMessageId, SchnorrSigShare)> + '_> {
+        let object_pool = self.get_pool(EcdsaMessageType::SchnorrSigShare);
+        object_pool.iter_by_prefix(prefix)
+    }
 }
 
 impl MutableEcdsaPoolSection for InMemoryEcdsaPoolSection {
@@ -22,7 +22,7 @@ use ic_types::{
         dkg,
         ecdsa::{
             EcdsaArtifactId, EcdsaComplaint, EcdsaMessage, EcdsaMessageType, EcdsaOpening,
-            EcdsaPrefix, EcdsaPrefixOf, EcdsaSigShare,
+            EcdsaPrefix, EcdsaPrefixOf, EcdsaSigShare, SchnorrSigShare,
         },
         BlockPayload, BlockProposal, CatchUpPackage, CatchUpPackageShare, ConsensusMessage,
         ConsensusMessageHash, ConsensusMessageHashable, Finalization, FinalizationShare, HasHeight,
@@ -1791,6 +1791,7 @@ impl PersistentEcdsaPoolSection {
             EcdsaMessageType::SigShare => TypeKey::new("ECI"),
             EcdsaMessageType::Complaint => TypeKey::new("ECC"),
             EcdsaMessageType::Opening => TypeKey::new("ECO"),
+            EcdsaMessageType::SchnorrSigShare => TypeKey::new("ECT"),
         }
     }
 }
@@ -1875,6 +1876,21 @@ impl EcdsaPoolSection for PersistentEcdsaPoolSection {
         let message_db = self.get_message_db(EcdsaMessageType::Opening);
         message_db.iter(Some(prefix))
     }
+
+    fn schnorr_signature_shares(
+        &self,
+    ) -> Box<dyn Iterator<Item = (EcdsaMessageId, SchnorrSigShare)> + '_> {
+        let message_db = self.get_message_db(EcdsaMessageType::SchnorrSigShare);
+        message_db.iter(None)
+    }
+
+    fn schnorr_signature_shares_by_prefix(
+        &self,
+        prefix: EcdsaPrefixOf<SchnorrSigShare>,
+    ) -> Box<dyn Iterator<Item = (EcdsaMessageId, SchnorrSigShare)> + '_> {
+        let message_db = self.get_message_db(EcdsaMessageType::SchnorrSigShare);
+        message_db.iter(Some(prefix))
+    }
 }
 
 impl MutableEcdsaPoolSection for PersistentEcdsaPoolSection {
@@ -138,6 +138,8 @@ impl Execution {
                 allocated_message_bytes,
                 instance_stats,
                 system_api_call_counters,
+                canister_log,
+                wasm_profile,
             },
             deltas,
             instance_or_system_api,
@@ -204,6 +206,8 @@ impl Execution {
                     num_instructions_left,
                     instance_stats,
                     system_api_call_counters,
+                    canister_log,
+                    wasm_profile,
                 };
                 self.sandbox_manager.controller.execution_finished(
                     protocol::ctlsvc::ExecutionFinishedRequest {
@@ -230,6 +234,8 @@ impl Execution {
                     allocated_message_bytes,
                     instance_stats,
                     system_api_call_counters,
+                    canister_log,
+                    wasm_profile,
                 };
 
                 self.sandbox_manager.controller.execution_finished(
@@ -202,6 +202,7 @@ mod tests {
             Cycles::new(1_000_000),
             Cycles::zero(),
             None,
+            None,
             BTreeMap::new(),
             CyclesAccountManager::new(
                 NumInstructions::from(1_000_000_000),
@@ -214,11 +215,15 @@ mod tests {
             0,
             ic00_aliases,
             SMALL_APP_SUBNET_MAX_SIZE,
+            BTreeMap::new(),
+            BTreeMap::new(),
+            vec![],
             SchedulerConfig::application_subnet().dirty_page_overhead,
             CanisterTimer::Inactive,
             0,
             BTreeSet::from([controller]),
             RequestMetadata::new(0, Time::from_nanos_since_unix_epoch(0)),
+            0,
         )
     }
 
@@ -251,6 +256,7 @@ mod tests {
                 Cycles::zero(),
                 PrincipalId::try_from([0].as_ref()).unwrap(),
                 CallContextId::from(0),
+                None,
             ),
             globals,
             canister_current_memory_usage: NumBytes::from(0),
@@ -37,6 +37,9 @@ pub enum CertificationVersion {
     V14 = 14,
     /// Added subnet metrics in `subnet` subtree.
     V15 = 15,
+    /// Encoding of best-effort call deadlines, as
+    /// `RequestMetadata::call_subtree_deadline_u64` and `Response::deadline_u64`.
+    V16 = 16,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -75,7 +78,7 @@ pub const CURRENT_CERTIFICATION_VERSION: CertificationVersion = CertificationVer
 ///
 /// The replica will panic if requested to certify using a version higher than
 /// this.
-pub const MAX_SUPPORTED_CERTIFICATION_VERSION: CertificationVersion = CertificationVersion::V15;
+pub const MAX_SUPPORTED_CERTIFICATION_VERSION: CertificationVersion = CertificationVersion::V16;
 
 /// Returns a list of all certification versions up to [MAX_SUPPORTED_CERTIFICATION_VERSION].
 pub fn all_supported_versions() -> impl std::iter::Iterator<Item = CertificationVersion> {
@@ -128,6 +128,7 @@ impl TryFrom<RequestV13> for ic_types::messages::Request {
             method_name: request.method_name,
             method_payload: request.method_payload,
             metadata: None,
+            deadline: None,
         })
     }
 }
@@ -182,6 +183,7 @@ impl TryFrom<RequestV3> for ic_types::messages::Request {
             method_name: request.method_name,
             method_payload: request.method_payload,
             metadata: None,
+            deadline: None,
         })
     }
 }
@@ -231,6 +233,7 @@ impl TryFrom<ResponseV3> for ic_types::messages::Response {
             originator_reply_callback: response.originator_reply_callback.into(),
             refund: response.refund.cycles.try_into()?,
             response_payload: response.response_payload.try_into()?,
+            deadline: None,
         })
     }
 }
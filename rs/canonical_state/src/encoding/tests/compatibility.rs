@@ -336,6 +336,155 @@ fn canonical_encoding_request_v14_plus() {
     }
 }
 
+/// Canonical CBOR encoding of:
+///
+/// ```no_run
+/// RequestOrResponse::Request(
+///     Request {
+///         receiver: canister_test_id(1),
+///         sender: canister_test_id(2),
+///         sender_reply_callback: CallbackId::from(3),
+///         payment: Cycles::new(4),
+///         method_name: "test".to_string(),
+///         method_payload: vec![6],
+///         metadata: Some(RequestMetadata {
+///             call_tree_depth: 13,
+///             call_tree_start_time: Time::as_nanos_since_unix_epoch(101),
+///         }),
+///         deadline: Some(Time::from_nanos_since_unix_epoch(1000)),
+///     }
+/// )
+/// ```
+///
+/// Expected:
+///
+/// ```text
+/// A1                            # map(1)
+///    00                         # field_index(RequestOrResponse::request)
+///    A7                         # map(7)
+///       00                      # field_index(Request::receiver)
+///       4A                      # bytes(10)
+///          00000000000000010101 # "\x00\x00\x00\x00\x00\x00\x00\x01\x01\x01"
+///       01                      # field_index(Request::sender)
+///       4A                      # bytes(10)
+///          00000000000000020101 # "\x00\x00\x00\x00\x00\x00\x00\x02\x01\x01"
+///       02                      # field_index(Request::sender_reply_callback)
+///       03                      # unsigned(3)
+///       03                      # field_index(Request::payment)
+///       A1                      # map(1)
+///          00                   # field_index(Funds::cycles)
+///          A1                   # map(1)
+///             00                # field_index(Cycles::raw)
+///             04                # unsigned(4)
+///       04                      # field_index(Request::method_name)
+///       64                      # text(4)
+///          74657374             # "test"
+///       05                      # field_index(Request::method_payload)
+///       41                      # bytes(1)
+///          06                   # "\x06"
+///       07                      # field_index(Request::metadata)
+///       A3                      # map(3)
+///          00                   # field_index(RequestMetadata::call_tree_depth)
+///          0D                   # unsigned(13)
+///          01                   # field_index(RequestMetadata::call_tree_start_time_u64)
+///          18 65                # unsigned(101)
+///          02                   # field_index(RequestMetadata::call_subtree_deadline_u64)
+///          19 03E8              # unsigned(1000)
+/// ```
+/// Used http://cbor.me/ for printing the human friendly output.
+#[test]
+fn canonical_encoding_request_v16_plus() {
+    for certification_version in
+        all_supported_versions().filter(|v| v >= &CertificationVersion::V16)
+    {
+        let request: RequestOrResponse = RequestBuilder::new()
+            .receiver(canister_test_id(1))
+            .sender(canister_test_id(2))
+            .sender_reply_callback(CallbackId::from(3))
+            .payment(Cycles::new(4))
+            .method_name("test".to_string())
+            .method_payload(vec![6])
+            .metadata(Some(RequestMetadata::new(
+                13,
+                Time::from_nanos_since_unix_epoch(101),
+            )))
+            .deadline(Some(Time::from_nanos_since_unix_epoch(1000)))
+            .build()
+            .into();
+
+        assert_eq!(
+            "A1 00 A7 00 4A 00 00 00 00 00 00 00 01 01 01 01 4A 00 00 00 00 00 00 00 02 01 01 02 03 03 A1 00 A1 00 04 04 64 74 65 73 74 05 41 06 07 A3 00 0D 01 18 65 02 19 03 E8",
+            as_hex(&encode_message(&request, certification_version))
+        );
+    }
+}
+
+/// Canonical CBOR encoding of a best-effort request without call tree
+/// metadata: the deadline is encoded as the only `RequestMetadata` field.
+///
+/// Expected (only the `metadata` field is shown):
+///
+/// ```text
+///       07                      # field_index(Request::metadata)
+///       A1                      # map(1)
+///          02                   # field_index(RequestMetadata::call_subtree_deadline_u64)
+///          19 03E8              # unsigned(1000)
+/// ```
+#[test]
+fn canonical_encoding_best_effort_request_v16_plus() {
+    for certification_version in
+        all_supported_versions().filter(|v| v >= &CertificationVersion::V16)
+    {
+        let request: RequestOrResponse = RequestBuilder::new()
+            .receiver(canister_test_id(1))
+            .sender(canister_test_id(2))
+            .sender_reply_callback(CallbackId::from(3))
+            .payment(Cycles::new(4))
+            .method_name("test".to_string())
+            .method_payload(vec![6])
+            .deadline(Some(Time::from_nanos_since_unix_epoch(1000)))
+            .build()
+            .into();
+
+        assert_eq!(
+            "A1 00 A7 00 4A 00 00 00 00 00 00 00 01 01 01 01 4A 00 00 00 00 00 00 00 02 01 01 02 03 03 A1 00 A1 00 04 04 64 74 65 73 74 05 41 06 07 A1 02 19 03 E8",
+            as_hex(&encode_message(&request, certification_version))
+        );
+    }
+}
+
+/// Request deadlines are not encoded before certification version 16.
+#[test]
+fn canonical_encoding_request_deadline_pre_v16() {
+    for certification_version in all_supported_versions().filter(|v| v < &CertificationVersion::V16)
+    {
+        let request: RequestOrResponse = RequestBuilder::new()
+            .receiver(canister_test_id(1))
+            .sender(canister_test_id(2))
+            .sender_reply_callback(CallbackId::from(3))
+            .payment(Cycles::new(4))
+            .method_name("test".to_string())
+            .method_payload(vec![6])
+            .build()
+            .into();
+        let best_effort_request: RequestOrResponse = RequestBuilder::new()
+            .receiver(canister_test_id(1))
+            .sender(canister_test_id(2))
+            .sender_reply_callback(CallbackId::from(3))
+            .payment(Cycles::new(4))
+            .method_name("test".to_string())
+            .method_payload(vec![6])
+            .deadline(Some(Time::from_nanos_since_unix_epoch(1000)))
+            .build()
+            .into();
+
+        assert_eq!(
+            as_hex(&encode_message(&request, certification_version)),
+            as_hex(&encode_message(&best_effort_request, certification_version))
+        );
+    }
+}
+
 /// Canonical CBOR encoding of:
 ///
 /// ```no_run
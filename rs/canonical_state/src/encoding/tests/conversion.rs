@@ -1,5 +1,5 @@
 use super::test_fixtures::*;
-use crate::{all_supported_versions, encoding::types};
+use crate::{all_supported_versions, encoding::types, CertificationVersion};
 use ic_error_types::RejectCode;
 use ic_protobuf::proxy::ProxyDecodeError;
 use ic_types::messages::{Payload, RejectContext, RequestOrResponse};
@@ -50,6 +50,22 @@ fn roundtrip_conversion_request() {
     }
 }
 
+#[test]
+fn roundtrip_conversion_best_effort_request() {
+    let request = best_effort_request();
+
+    for certification_version in
+        all_supported_versions().filter(|v| v >= &CertificationVersion::V16)
+    {
+        assert_eq!(
+            request,
+            types::RequestOrResponse::from((&request, certification_version))
+                .try_into()
+                .unwrap()
+        );
+    }
+}
+
 #[test]
 fn roundtrip_conversion_response() {
     let response = response();
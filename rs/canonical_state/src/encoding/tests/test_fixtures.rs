@@ -38,6 +38,24 @@ pub fn request(certification_version: CertificationVersion) -> RequestOrResponse
                 Time::from_nanos_since_unix_epoch(100_000),
             )),
         )
+        .deadline(
+            (certification_version >= CertificationVersion::V16)
+                .then_some(Time::from_nanos_since_unix_epoch(200_000)),
+        )
+        .build()
+        .into()
+}
+
+/// A best-effort request with a deadline but no call tree metadata.
+pub fn best_effort_request() -> RequestOrResponse {
+    RequestBuilder::new()
+        .receiver(canister_test_id(1))
+        .sender(canister_test_id(2))
+        .sender_reply_callback(CallbackId::from(3))
+        .payment(Cycles::new(4))
+        .method_name("test".to_string())
+        .method_payload(vec![6])
+        .deadline(Some(Time::from_nanos_since_unix_epoch(200_000)))
         .build()
         .into()
 }
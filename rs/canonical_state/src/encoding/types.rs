@@ -92,6 +92,10 @@ pub struct Response {
     pub response_payload: Payload,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cycles_refund: Option<Cycles>,
+    /// Deadline of the best-effort call this is a response to. Unlike
+    /// requests, responses carry no `RequestMetadata` to hold it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deadline_u64: Option<u64>,
 }
 
 /// Canonical representation of `ic_types::funds::Cycles`.
@@ -257,15 +261,34 @@ impl TryFrom<RequestOrResponse> for ic_types::messages::RequestOrResponse {
     }
 }
 
-impl From<&ic_types::messages::RequestMetadata> for RequestMetadata {
-    fn from(metadata: &ic_types::messages::RequestMetadata) -> Self {
-        RequestMetadata {
-            call_tree_depth: Some(*metadata.call_tree_depth()),
-            call_tree_start_time_u64: Some(
-                metadata.call_tree_start_time().as_nanos_since_unix_epoch(),
-            ),
-            call_subtree_deadline_u64: None,
+impl RequestMetadata {
+    /// Canonical representation of the request's metadata and (best-effort)
+    /// deadline. The deadline is encoded as `call_subtree_deadline_u64` from
+    /// certification version 16 onwards.
+    fn new(
+        metadata: Option<&ic_types::messages::RequestMetadata>,
+        deadline: Option<Time>,
+        certification_version: CertificationVersion,
+    ) -> Option<Self> {
+        let metadata = metadata.filter(|_| certification_version >= CertificationVersion::V14);
+        let deadline = deadline.filter(|_| certification_version >= CertificationVersion::V16);
+        if metadata.is_none() && deadline.is_none() {
+            return None;
         }
+
+        Some(RequestMetadata {
+            call_tree_depth: metadata.map(|metadata| *metadata.call_tree_depth()),
+            call_tree_start_time_u64: metadata
+                .map(|metadata| metadata.call_tree_start_time().as_nanos_since_unix_epoch()),
+            call_subtree_deadline_u64: deadline
+                .map(|deadline| deadline.as_nanos_since_unix_epoch()),
+        })
+    }
+
+    /// Returns `true` if call tree metadata (as opposed to only a deadline) is
+    /// present.
+    fn has_call_tree_metadata(&self) -> bool {
+        self.call_tree_depth.is_some() || self.call_tree_start_time_u64.is_some()
     }
 }
 
@@ -286,9 +309,11 @@ impl From<(&ic_types::messages::Request, CertificationVersion)> for Request {
             method_name: request.method_name.clone(),
             method_payload: request.method_payload.clone(),
             cycles_payment: None,
-            metadata: request.metadata.as_ref().and_then(|metadata| {
-                (certification_version >= CertificationVersion::V14).then_some(metadata.into())
-            }),
+            metadata: RequestMetadata::new(
+                request.metadata.as_ref(),
+                request.deadline,
+                certification_version,
+            ),
         }
     }
 }
@@ -323,7 +348,15 @@ impl TryFrom<Request> for ic_types::messages::Request {
             payment,
             method_name: request.method_name,
             method_payload: request.method_payload,
-            metadata: request.metadata.map(From::from),
+            deadline: request
+                .metadata
+                .as_ref()
+                .and_then(|metadata| metadata.call_subtree_deadline_u64)
+                .map(Time::from_nanos_since_unix_epoch),
+            metadata: request
+                .metadata
+                .filter(RequestMetadata::has_call_tree_metadata)
+                .map(From::from),
         })
     }
 }
@@ -343,6 +376,10 @@ impl From<(&ic_types::messages::Response, CertificationVersion)> for Response {
             refund: funds,
             response_payload: (&response.response_payload, certification_version).into(),
             cycles_refund: None,
+            deadline_u64: response.deadline.and_then(|deadline| {
+                (certification_version >= CertificationVersion::V16)
+                    .then_some(deadline.as_nanos_since_unix_epoch())
+            }),
         }
     }
 }
@@ -367,6 +404,7 @@ impl TryFrom<Response> for ic_types::messages::Response {
             originator_reply_callback: response.originator_reply_callback.into(),
             refund,
             response_payload: response.response_payload.try_into()?,
+            deadline: response.deadline_u64.map(Time::from_nanos_since_unix_epoch),
         })
     }
 }
@@ -78,6 +78,12 @@ pub struct FeatureFlags {
     /// Track dirty pages with a write barrier instead of the signal handler.
     pub write_barrier: FlagStatus,
     pub wasm_native_stable_memory: FlagStatus,
+    /// Enables best-effort inter-canister calls, i.e. the
+    /// `ic0.call_with_best_effort_response` and `ic0.msg_deadline` System API
+    /// calls.
+    pub best_effort_responses: FlagStatus,
+    /// Allows canisters whose main memory is a 64-bit Wasm memory.
+    pub wasm64: FlagStatus,
 }
 
 impl FeatureFlags {
@@ -86,6 +92,8 @@ impl FeatureFlags {
             rate_limiting_of_debug_prints: FlagStatus::Enabled,
             write_barrier: FlagStatus::Disabled,
             wasm_native_stable_memory: FlagStatus::Enabled,
+            best_effort_responses: FlagStatus::Disabled,
+            wasm64: FlagStatus::Disabled,
         }
     }
 }
@@ -178,6 +186,13 @@ pub struct Config {
     /// entry with the number of executed instructions and the duration.
     pub trace_execution: FlagStatus,
 
+    /// If this flag is enabled, then canister modules are instrumented to
+    /// record function entries and exits, and every message execution reports
+    /// the number of instructions executed per Wasm function and call stack.
+    /// This slows down execution considerably and is meant for local testing
+    /// only.
+    pub wasm_profiling: FlagStatus,
+
     /// The maximum number of pages that a message dirties without optimizing dirty
     /// page copying by triggering a new execution slice for copying and using prefaulting.
     pub max_dirty_pages_without_optimization: usize,
@@ -209,6 +224,7 @@ impl Config {
             subnet_type: SubnetType::Application,
             dirty_page_overhead: NumInstructions::new(0),
             trace_execution: FlagStatus::Disabled,
+            wasm_profiling: FlagStatus::Disabled,
             max_dirty_pages_without_optimization: DEFAULT_MAX_DIRTY_PAGES_WITHOUT_OPTIMIZATION,
             dirty_page_copy_overhead: DIRTY_PAGE_COPY_OVERHEAD,
         }
@@ -132,6 +132,14 @@ pub const MAX_COMPILATION_CACHE_SIZE: NumBytes = NumBytes::new(10 * GIB);
 /// Maximum number of controllers allowed in a request (specified in the interface spec).
 pub const MAX_ALLOWED_CONTROLLERS_COUNT: usize = 10;
 
+/// Maximum number of snapshots a canister can have at the same time.
+pub const MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER: usize = 1;
+
+/// The baseline number of instructions charged for taking or loading a
+/// canister snapshot, in addition to the number of bytes being copied.
+pub const CANISTER_SNAPSHOT_BASELINE_INSTRUCTIONS: NumInstructions =
+    NumInstructions::new(2_000_000_000);
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default)]
 pub struct Config {
@@ -261,6 +269,13 @@ pub struct Config {
     /// Indicates whether canister backup and restore feature is enabled or not.
     pub canister_snapshots: FlagStatus,
 
+    /// Maximum number of snapshots a canister can have at the same time.
+    pub max_number_of_snapshots_per_canister: usize,
+
+    /// The baseline number of instructions charged for taking or loading a
+    /// canister snapshot.
+    pub canister_snapshot_baseline_instructions: NumInstructions,
+
     // TODO(IC-272): remove this flag once the feature is enabled by default.
     /// Indicates whether fetching canister logs API is enabled or not.
     pub fetch_canister_logs: FlagStatus,
@@ -334,6 +349,8 @@ impl Default for Config {
             wasm_chunk_store: FlagStatus::Enabled,
             stop_canister_timeout_duration: STOP_CANISTER_TIMEOUT_DURATION,
             canister_snapshots: FlagStatus::Disabled,
+            max_number_of_snapshots_per_canister: MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER,
+            canister_snapshot_baseline_instructions: CANISTER_SNAPSHOT_BASELINE_INSTRUCTIONS,
             fetch_canister_logs: FlagStatus::Disabled,
         }
     }
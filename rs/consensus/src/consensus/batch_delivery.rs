@@ -7,7 +7,9 @@ use crate::{
         metrics::{BatchStats, BlockStats},
         status::{self, Status},
     },
-    ecdsa::utils::{get_ecdsa_subnet_public_key, get_quadruple_ids_to_deliver},
+    ecdsa::utils::{
+        get_ecdsa_subnet_public_key, get_quadruple_ids_to_deliver, get_schnorr_subnet_public_keys,
+    },
 };
 use ic_consensus_utils::{
     crypto_hashable_to_seed, get_block_hash_string, membership::Membership, pool_reader::PoolReader,
@@ -122,6 +124,18 @@ pub fn deliver_batches(
                         return Ok(last_delivered_batch_height);
                     }
                 };
+                let schnorr_subnet_public_keys =
+                    match get_schnorr_subnet_public_keys(&block, pool, log) {
+                        Ok(keys) => keys,
+                        Err(e) => {
+                            warn!(
+                                every_n_seconds => 5,
+                                log,
+                                "Do not deliver height {:?}: {}", h, e
+                            );
+                            return Ok(last_delivered_batch_height);
+                        }
+                    };
 
                 let block_stats = BlockStats::from(&block);
                 let mut batch_stats = BatchStats::new(h);
@@ -187,6 +201,7 @@ pub fn deliver_batches(
                     messages: batch_messages,
                     randomness,
                     ecdsa_subnet_public_keys: ecdsa_subnet_public_key.into_iter().collect(),
+                    schnorr_subnet_public_keys,
                     ecdsa_quadruple_ids: get_quadruple_ids_to_deliver(&block),
                     registry_version: block.context.registry_version,
                     time: block.context.time,
@@ -238,7 +253,7 @@ pub fn deliver_batches(
 /// This function creates responses to the system calls that are redirected to
 /// consensus. There are two types of calls being handled here:
 /// - Initial NiDKG transcript creation, where a response may come from summary payloads.
-/// - Threshold ECDSA signature creation, where a response may come from from data payloads.
+/// - Threshold ECDSA and Schnorr signature creation, where a response may come from from data payloads.
 /// - CanisterHttpResponse handling, where a response to a canister http request may come from data payloads.
 pub fn generate_responses_to_subnet_calls(
     block: &Block,
@@ -262,6 +277,7 @@ pub fn generate_responses_to_subnet_calls(
         let block_payload = block_payload.as_ref().as_data();
         if let Some(payload) = &block_payload.ecdsa {
             consensus_responses.append(&mut generate_responses_to_sign_with_ecdsa_calls(payload));
+            consensus_responses.append(&mut generate_responses_to_sign_with_schnorr_calls(payload));
             consensus_responses.append(&mut generate_responses_to_initial_dealings_calls(payload));
         }
 
@@ -338,6 +354,7 @@ pub fn generate_responses_to_setup_initial_dkg_calls(
                 originator_reply_callback: callback_id,
                 refund: Cycles::zero(),
                 response_payload,
+                deadline: None,
             });
         }
     }
@@ -430,6 +447,20 @@ pub fn generate_responses_to_sign_with_ecdsa_calls(
     consensus_responses
 }
 
+/// Creates responses to `SignWithSchnorr` system calls with the computed
+/// signature.
+fn generate_responses_to_sign_with_schnorr_calls(
+    ecdsa_payload: &ecdsa::EcdsaPayload,
+) -> Vec<Response> {
+    let mut consensus_responses = Vec::<Response>::new();
+    for completed in ecdsa_payload.schnorr_signature_agreements.values() {
+        if let CompletedSignature::Unreported(response) = completed {
+            consensus_responses.push(response.clone());
+        }
+    }
+    consensus_responses
+}
+
 /// Creates responses to `ComputeInitialEcdsaDealingsArgs` system calls with the initial
 /// dealings.
 fn generate_responses_to_initial_dealings_calls(
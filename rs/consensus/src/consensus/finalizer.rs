@@ -482,6 +482,7 @@ mod tests {
                     method_name: "".to_string(),
                     method_payload: vec![],
                     metadata: None,
+                    deadline: None,
                 },
                 nodes_in_target_subnet: BTreeSet::new(),
                 target_id: TARGET_ID,
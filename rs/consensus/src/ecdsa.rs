@@ -195,7 +195,7 @@ use ic_types::crypto::canister_threshold_sig::error::IDkgRetainKeysError;
 use ic_types::{
     artifact::{EcdsaMessageId, Priority, PriorityFn},
     artifact_kind::EcdsaArtifact,
-    consensus::ecdsa::{EcdsaBlockReader, EcdsaMessageAttribute, RequestId},
+    consensus::ecdsa::{EcdsaBlockReader, EcdsaMessageAttribute, RequestId, SchnorrRequestId},
     crypto::canister_threshold_sig::idkg::IDkgTranscriptId,
     malicious_flags::MaliciousFlags,
     Height, NodeId, SubnetId,
@@ -456,6 +456,7 @@ struct EcdsaPriorityFnArgs {
     certified_height: Height,
     requested_transcripts: BTreeSet<IDkgTranscriptId>,
     requested_signatures: BTreeSet<RequestId>,
+    requested_schnorr_signatures: BTreeSet<SchnorrRequestId>,
     active_transcripts: BTreeSet<IDkgTranscriptId>,
 }
 
@@ -498,11 +499,19 @@ impl EcdsaPriorityFnArgs {
             )
         };
 
+        let requested_schnorr_signatures = BTreeSet::from_iter(
+            block_reader
+                .requested_schnorr_signatures()
+                .map(|(request_id, _)| request_id)
+                .cloned(),
+        );
+
         Self {
             finalized_height: block_reader.tip_height(),
             certified_height,
             requested_transcripts,
             requested_signatures,
+            requested_schnorr_signatures,
             active_transcripts,
         }
     }
@@ -590,6 +599,23 @@ fn compute_priority(
                 Priority::Stash
             }
         }
+        EcdsaMessageAttribute::SchnorrSigShare(request_id) => {
+            if request_id.height <= args.finalized_height {
+                if args.requested_schnorr_signatures.contains(request_id) {
+                    Priority::Fetch
+                } else {
+                    metrics
+                        .dropped_adverts
+                        .with_label_values(&[attr.as_str()])
+                        .inc();
+                    Priority::Drop
+                }
+            } else if request_id.height < args.finalized_height + Height::from(LOOK_AHEAD) {
+                Priority::Fetch
+            } else {
+                Priority::Stash
+            }
+        }
         EcdsaMessageAttribute::EcdsaComplaint(transcript_id)
         | EcdsaMessageAttribute::EcdsaOpening(transcript_id) => {
             let height = transcript_id.source_height();
@@ -694,6 +720,7 @@ mod tests {
             certified_height: Height::from(100),
             requested_transcripts,
             requested_signatures: BTreeSet::new(),
+            requested_schnorr_signatures: BTreeSet::new(),
             active_transcripts: BTreeSet::new(),
         };
 
@@ -786,6 +813,7 @@ mod tests {
             certified_height: Height::from(100),
             requested_transcripts: BTreeSet::new(),
             requested_signatures,
+            requested_schnorr_signatures: BTreeSet::new(),
             active_transcripts: BTreeSet::new(),
         };
 
@@ -838,6 +866,7 @@ mod tests {
             certified_height: Height::from(100),
             requested_transcripts,
             requested_signatures: BTreeSet::new(),
+            requested_schnorr_signatures: BTreeSet::new(),
             active_transcripts,
         };
 
@@ -6,8 +6,8 @@
 use super::pre_signer::{EcdsaTranscriptBuilder, EcdsaTranscriptBuilderImpl};
 use super::signer::{EcdsaSignatureBuilder, EcdsaSignatureBuilderImpl};
 use super::utils::{
-    block_chain_reader, get_ecdsa_config_if_enabled, get_enabled_signing_keys,
-    InvalidChainCacheError,
+    block_chain_reader, get_ecdsa_config_if_enabled, get_enabled_schnorr_signing_keys,
+    get_enabled_signing_keys, get_schnorr_config_if_enabled, InvalidChainCacheError,
 };
 use crate::consensus::metrics::{EcdsaPayloadMetrics, CRITICAL_ERROR_ECDSA_KEY_TRANSCRIPT_MISSING};
 pub(super) use errors::EcdsaPayloadError;
@@ -16,13 +16,13 @@ use ic_consensus_utils::crypto::ConsensusCrypto;
 use ic_consensus_utils::pool_reader::PoolReader;
 use ic_crypto::retrieve_mega_public_key_from_registry;
 use ic_error_types::RejectCode;
-use ic_ic00_types::EcdsaKeyId;
+use ic_ic00_types::{EcdsaKeyId, SchnorrKeyId};
 use ic_interfaces::ecdsa::EcdsaPool;
 use ic_interfaces_registry::RegistryClient;
 use ic_interfaces_state_manager::StateManager;
 use ic_logger::{error, info, warn, ReplicaLogger};
 use ic_registry_client_helpers::subnet::SubnetRegistry;
-use ic_registry_subnet_features::EcdsaConfig;
+use ic_registry_subnet_features::{EcdsaConfig, SchnorrConfig};
 use ic_replicated_state::{metadata_state::subnet_call_context_manager::*, ReplicatedState};
 use ic_types::consensus::ecdsa::ECDSA_IMPROVED_LATENCY;
 use ic_types::{
@@ -47,6 +47,7 @@ use std::time::Duration;
 
 mod errors;
 mod key_transcript;
+mod pre_signatures;
 mod quadruples;
 pub(super) mod resharing;
 pub(super) mod signatures;
@@ -72,6 +73,11 @@ pub fn make_bootstrap_summary(
             next_in_creation: ecdsa::KeyTranscriptCreation::Begin,
             key_id,
         },
+        schnorr_key_transcripts: BTreeMap::new(),
+        available_schnorr_pre_signatures: BTreeMap::new(),
+        schnorr_pre_signatures_in_creation: BTreeMap::new(),
+        ongoing_schnorr_signatures: BTreeMap::new(),
+        schnorr_signature_agreements: BTreeMap::new(),
     })
 }
 
@@ -98,6 +104,11 @@ pub fn make_bootstrap_summary_with_initial_dealings(
             next_in_creation: ecdsa::KeyTranscriptCreation::Begin,
             key_id,
         },
+        schnorr_key_transcripts: BTreeMap::new(),
+        available_schnorr_pre_signatures: BTreeMap::new(),
+        schnorr_pre_signatures_in_creation: BTreeMap::new(),
+        ongoing_schnorr_signatures: BTreeMap::new(),
+        schnorr_signature_agreements: BTreeMap::new(),
     };
 
     match ecdsa::unpack_reshare_of_unmasked_params(height, initial_dealings.params()) {
@@ -307,6 +318,15 @@ fn create_summary_payload_helper(
                 xnet_reshare_agreements: ecdsa_payload.xnet_reshare_agreements.clone(),
                 idkg_transcripts: BTreeMap::new(),
                 key_transcript,
+                schnorr_key_transcripts: ecdsa_payload.schnorr_key_transcripts.clone(),
+                available_schnorr_pre_signatures: ecdsa_payload
+                    .available_schnorr_pre_signatures
+                    .clone(),
+                schnorr_pre_signatures_in_creation: ecdsa_payload
+                    .schnorr_pre_signatures_in_creation
+                    .clone(),
+                ongoing_schnorr_signatures: ecdsa_payload.ongoing_schnorr_signatures.clone(),
+                schnorr_signature_agreements: ecdsa_payload.schnorr_signature_agreements.clone(),
             }
         } else {
             ecdsa::EcdsaPayload {
@@ -322,6 +342,15 @@ fn create_summary_payload_helper(
                 xnet_reshare_agreements: ecdsa_payload.xnet_reshare_agreements.clone(),
                 idkg_transcripts: BTreeMap::new(),
                 key_transcript,
+                schnorr_key_transcripts: ecdsa_payload.schnorr_key_transcripts.clone(),
+                available_schnorr_pre_signatures: ecdsa_payload
+                    .available_schnorr_pre_signatures
+                    .clone(),
+                schnorr_pre_signatures_in_creation: ecdsa_payload
+                    .schnorr_pre_signatures_in_creation
+                    .clone(),
+                ongoing_schnorr_signatures: ecdsa_payload.ongoing_schnorr_signatures.clone(),
+                schnorr_signature_agreements: ecdsa_payload.schnorr_signature_agreements.clone(),
             }
         }
     } else {
@@ -339,14 +368,165 @@ fn create_summary_payload_helper(
             xnet_reshare_agreements: ecdsa_payload.xnet_reshare_agreements.clone(),
             idkg_transcripts: BTreeMap::new(),
             key_transcript,
+            schnorr_key_transcripts: ecdsa_payload.schnorr_key_transcripts.clone(),
+            available_schnorr_pre_signatures: ecdsa_payload
+                .available_schnorr_pre_signatures
+                .clone(),
+            schnorr_pre_signatures_in_creation: ecdsa_payload
+                .schnorr_pre_signatures_in_creation
+                .clone(),
+            ongoing_schnorr_signatures: ecdsa_payload.ongoing_schnorr_signatures.clone(),
+            schnorr_signature_agreements: ecdsa_payload.schnorr_signature_agreements.clone(),
         }
     };
 
+    update_schnorr_summary(
+        subnet_id,
+        registry_client,
+        block_reader,
+        height,
+        curr_interval_registry_version,
+        next_interval_registry_version,
+        &mut ecdsa_summary,
+        log,
+    )?;
+
     ecdsa_summary.uid_generator.update_height(height)?;
     update_summary_refs(height, &mut ecdsa_summary, block_reader)?;
     Ok(Some(ecdsa_summary))
 }
 
+/// Carries the threshold Schnorr state over into the summary payload. Starts
+/// the creation of the threshold Schnorr keys once they are enabled, and
+/// reshares them on membership changes, in the same way as the ECDSA key.
+/// Keys that are no longer configured are dropped together with their
+/// pre-signatures and ongoing signatures.
+fn update_schnorr_summary(
+    subnet_id: SubnetId,
+    registry_client: &dyn RegistryClient,
+    block_reader: &dyn EcdsaBlockReader,
+    height: Height,
+    curr_interval_registry_version: RegistryVersion,
+    next_interval_registry_version: RegistryVersion,
+    summary: &mut ecdsa::EcdsaPayload,
+    log: &ReplicaLogger,
+) -> Result<(), EcdsaPayloadError> {
+    let key_ids = get_schnorr_config_if_enabled(
+        subnet_id,
+        curr_interval_registry_version,
+        registry_client,
+        log,
+    )?
+    .map(|schnorr_config| schnorr_config.key_ids)
+    .unwrap_or_default();
+
+    let previous_key_transcripts = std::mem::take(&mut summary.schnorr_key_transcripts);
+    let stale_key_ids: Vec<&SchnorrKeyId> = previous_key_transcripts
+        .keys()
+        .filter(|key_id| !key_ids.contains(key_id))
+        .collect();
+    if !stale_key_ids.is_empty() {
+        info!(
+            log,
+            "Stop using threshold Schnorr keys {:?} on subnet {} at height {}",
+            stale_key_ids,
+            subnet_id,
+            height
+        );
+    }
+
+    for key_id in key_ids {
+        let Some(key_transcript) = previous_key_transcripts.get(&key_id) else {
+            info!(
+                log,
+                "Start to create threshold Schnorr key {} on subnet {} at height {}",
+                key_id,
+                subnet_id,
+                height
+            );
+            summary
+                .schnorr_key_transcripts
+                .insert(key_id.clone(), ecdsa::SchnorrKeyTranscript::new(key_id));
+            continue;
+        };
+
+        let current_key_transcript = key_transcript.current.as_ref();
+        let created_key_transcript =
+            key_transcript::get_created_schnorr_key_transcript(key_transcript, block_reader)?;
+
+        let curr_key_registry_version = created_key_transcript
+            .as_ref()
+            .map(ecdsa::UnmaskedTranscriptWithAttributes::registry_version)
+            .or_else(|| {
+                current_key_transcript
+                    .map(ecdsa::UnmaskedTranscriptWithAttributes::registry_version)
+            })
+            .unwrap_or(curr_interval_registry_version);
+
+        let is_new_key_transcript = created_key_transcript.as_ref().is_some_and(|transcript| {
+            Some(transcript.transcript_id())
+                != current_key_transcript
+                    .map(ecdsa::UnmaskedTranscriptWithAttributes::transcript_id)
+        });
+
+        let next_in_creation = if is_time_to_reshare_key_transcript(
+            registry_client,
+            curr_key_registry_version,
+            next_interval_registry_version,
+            subnet_id,
+        )? && created_key_transcript.is_some()
+        {
+            info!(
+                log,
+                "Noticed subnet membership or mega encryption key change, \
+                will start threshold Schnorr key_transcript_creation for key {}: height = {} \
+                current_version = {}, next_version = {}",
+                key_id,
+                height,
+                curr_key_registry_version,
+                next_interval_registry_version
+            );
+            ecdsa::KeyTranscriptCreation::Begin
+        } else {
+            key_transcript.next_in_creation.clone()
+        };
+
+        if is_new_key_transcript {
+            // Pre-signatures of this key were created for the previous key transcript.
+            summary
+                .available_schnorr_pre_signatures
+                .retain(|id, _| id.key_id() != &key_id);
+            summary
+                .schnorr_pre_signatures_in_creation
+                .retain(|id, _| id.key_id() != &key_id);
+        }
+
+        summary.schnorr_key_transcripts.insert(
+            key_id.clone(),
+            ecdsa::SchnorrKeyTranscript {
+                // Keep using previous key transcript if the next hasn't been created
+                current: created_key_transcript.or_else(|| key_transcript.current.clone()),
+                next_in_creation,
+                key_id,
+            },
+        );
+    }
+
+    // Drop the pre-signatures and signature requests of keys that are not
+    // configured anymore.
+    let key_transcripts = &summary.schnorr_key_transcripts;
+    summary
+        .available_schnorr_pre_signatures
+        .retain(|id, _| key_transcripts.contains_key(id.key_id()));
+    summary
+        .schnorr_pre_signatures_in_creation
+        .retain(|id, _| key_transcripts.contains_key(id.key_id()));
+    summary
+        .ongoing_schnorr_signatures
+        .retain(|request_id, _| key_transcripts.contains_key(request_id.pre_signature_id.key_id()));
+    Ok(())
+}
+
 fn update_summary_refs(
     height: Height,
     summary: &mut ecdsa::EcdsaPayload,
@@ -607,9 +787,133 @@ pub(crate) fn create_data_payload_helper(
         ecdsa_payload_metrics,
         log,
     )?;
+
+    if let Some(schnorr_config) = get_schnorr_config_if_enabled(
+        subnet_id,
+        curr_interval_registry_version,
+        registry_client,
+        log,
+    )? {
+        let sign_with_schnorr_contexts = &state
+            .get_ref()
+            .metadata
+            .subnet_call_context_manager
+            .sign_with_schnorr_contexts;
+        let enabled_schnorr_signing_keys = get_enabled_schnorr_signing_keys(
+            subnet_id,
+            curr_interval_registry_version,
+            registry_client,
+            &schnorr_config,
+        )?;
+        update_schnorr_payload(
+            &mut ecdsa_payload,
+            height,
+            context.time,
+            &schnorr_config,
+            &enabled_schnorr_signing_keys,
+            next_interval_registry_version,
+            &receivers,
+            sign_with_schnorr_contexts,
+            block_reader,
+            transcript_builder,
+            signature_builder,
+            ecdsa_payload_metrics,
+            log,
+        )?;
+    }
     Ok(Some(ecdsa_payload))
 }
 
+/// Update the threshold Schnorr part of the data payload by:
+/// - starting the creation of the configured threshold Schnorr keys if needed;
+/// - collecting completed signatures and pairing new signing requests
+///   with available pre-signatures of the requested key;
+/// - creating new pre-signatures for every key and updating the ones in creation;
+/// - updating the next threshold Schnorr key transcripts.
+/// This must run after [`create_data_payload_helper_2`], as the newly created
+/// transcripts are added to the ones of this round.
+fn update_schnorr_payload(
+    ecdsa_payload: &mut ecdsa::EcdsaPayload,
+    height: Height,
+    context_time: Time,
+    schnorr_config: &SchnorrConfig,
+    enabled_signing_keys: &BTreeSet<SchnorrKeyId>,
+    next_interval_registry_version: RegistryVersion,
+    receivers: &[NodeId],
+    sign_with_schnorr_contexts: &BTreeMap<CallbackId, SignWithSchnorrContext>,
+    block_reader: &dyn EcdsaBlockReader,
+    transcript_builder: &dyn EcdsaTranscriptBuilder,
+    signature_builder: &dyn EcdsaSignatureBuilder,
+    ecdsa_payload_metrics: Option<&EcdsaPayloadMetrics>,
+    log: &ReplicaLogger,
+) -> Result<(), EcdsaPayloadError> {
+    for key_id in &schnorr_config.key_ids {
+        let key_transcript = ecdsa_payload
+            .schnorr_key_transcripts
+            .entry(key_id.clone())
+            .or_insert_with(|| ecdsa::SchnorrKeyTranscript::new(key_id.clone()));
+        // Check if we are creating a new key, if so, start using it immediately.
+        if key_transcript.current.is_none() {
+            key_transcript.current =
+                key_transcript::get_created_schnorr_key_transcript(key_transcript, block_reader)?;
+        }
+    }
+
+    let request_expiry_time = schnorr_config
+        .signature_request_timeout_ns
+        .and_then(|timeout| context_time.checked_sub(Duration::from_nanos(timeout)));
+
+    signatures::update_schnorr_signature_agreements(
+        sign_with_schnorr_contexts,
+        signature_builder,
+        ecdsa_payload,
+    );
+    let new_signing_requests = signatures::get_schnorr_signing_requests(
+        height,
+        request_expiry_time,
+        ecdsa_payload,
+        sign_with_schnorr_contexts,
+        enabled_signing_keys,
+        ecdsa_payload_metrics,
+    );
+    signatures::update_ongoing_schnorr_signatures(
+        new_signing_requests,
+        schnorr_config.pre_signatures_to_create_in_advance,
+        ecdsa_payload,
+        log,
+    );
+
+    pre_signatures::make_new_pre_signatures_if_needed(schnorr_config, ecdsa_payload);
+    let mut new_transcripts = pre_signatures::update_pre_signatures_in_creation(
+        ecdsa_payload,
+        transcript_builder,
+        height,
+        log,
+    )?;
+    for key_transcript in ecdsa_payload.schnorr_key_transcripts.values_mut() {
+        if let Some(new_transcript) = update_next_key_transcript_helper(
+            &key_transcript.current,
+            &mut key_transcript.next_in_creation,
+            &mut ecdsa_payload.uid_generator,
+            AlgorithmId::ThresholdSchnorrBip340,
+            receivers,
+            next_interval_registry_version,
+            transcript_builder,
+            height,
+            log,
+        )? {
+            new_transcripts.push(new_transcript);
+        }
+    }
+
+    for transcript in new_transcripts {
+        ecdsa_payload
+            .idkg_transcripts
+            .insert(transcript.transcript_id, transcript);
+    }
+    Ok(())
+}
+
 pub(crate) fn create_data_payload_helper_2(
     ecdsa_payload: &mut ecdsa::EcdsaPayload,
     height: Height,
@@ -854,6 +1158,7 @@ pub(crate) fn get_signing_requests<'a>(
                         context.key_id
                     ),
                 )),
+                deadline: context.request.deadline,
             };
             ecdsa_payload.signature_agreements.insert(
                 context.pseudo_random_id,
@@ -910,6 +1215,7 @@ pub(crate) fn get_signing_requests<'a>(
                         RejectCode::CanisterError,
                         "Signature request expired",
                     )),
+                    deadline: context.request.deadline,
                 };
                 ecdsa_payload.signature_agreements.insert(
                     context.pseudo_random_id,
@@ -956,11 +1262,34 @@ fn update_next_key_transcript(
     height: Height,
     log: &ReplicaLogger,
 ) -> Result<Option<IDkgTranscript>, EcdsaPayloadError> {
-    let mut new_transcript = None;
-    match (
+    update_next_key_transcript_helper(
         &ecdsa_payload.key_transcript.current,
-        &ecdsa_payload.key_transcript.next_in_creation,
-    ) {
+        &mut ecdsa_payload.key_transcript.next_in_creation,
+        &mut ecdsa_payload.uid_generator,
+        AlgorithmId::ThresholdEcdsaSecp256k1,
+        receivers,
+        registry_version,
+        transcript_cache,
+        height,
+        log,
+    )
+}
+
+/// Update configuration and data about the next key transcript of the given
+/// threshold algorithm. Returns the newly created transcript, if any.
+fn update_next_key_transcript_helper(
+    current: &Option<ecdsa::UnmaskedTranscriptWithAttributes>,
+    next_in_creation: &mut ecdsa::KeyTranscriptCreation,
+    uid_generator: &mut ecdsa::EcdsaUIDGenerator,
+    algorithm_id: AlgorithmId,
+    receivers: &[NodeId],
+    registry_version: RegistryVersion,
+    transcript_cache: &dyn EcdsaTranscriptBuilder,
+    height: Height,
+    log: &ReplicaLogger,
+) -> Result<Option<IDkgTranscript>, EcdsaPayloadError> {
+    let mut new_transcript = None;
+    match (current, &*next_in_creation) {
         (Some(transcript), ecdsa::KeyTranscriptCreation::Begin) => {
             // We have an existing key transcript, need to reshare it to create next
             // Create a new reshare config when there is none
@@ -968,21 +1297,21 @@ fn update_next_key_transcript(
             let receivers_set = receivers.iter().copied().collect::<BTreeSet<_>>();
             info!(
                 log,
-                "Reshare ECDSA key transcript from dealers {:?} to receivers {:?}, height = {}",
+                "Reshare {:?} key transcript from dealers {:?} to receivers {:?}, height = {}",
+                algorithm_id,
                 dealers,
                 receivers,
                 height,
             );
-            ecdsa_payload.key_transcript.next_in_creation =
-                ecdsa::KeyTranscriptCreation::ReshareOfUnmaskedParams(
-                    ecdsa::ReshareOfUnmaskedParams::new(
-                        ecdsa_payload.uid_generator.next_transcript_id(),
-                        receivers_set,
-                        registry_version,
-                        transcript,
-                        transcript.unmasked_transcript(),
-                    ),
-                );
+            *next_in_creation = ecdsa::KeyTranscriptCreation::ReshareOfUnmaskedParams(
+                ecdsa::ReshareOfUnmaskedParams::new(
+                    uid_generator.next_transcript_id(),
+                    receivers_set,
+                    registry_version,
+                    transcript,
+                    transcript.unmasked_transcript(),
+                ),
+            );
         }
 
         (Some(_), ecdsa::KeyTranscriptCreation::ReshareOfUnmaskedParams(config)) => {
@@ -992,35 +1321,34 @@ fn update_next_key_transcript(
             {
                 info!(
                     log,
-                    "ECDSA key transcript created from ReshareOfUnmasked {:?} \
+                    "{:?} key transcript created from ReshareOfUnmasked {:?} \
                     registry_version {} height = {}",
+                    algorithm_id,
                     config.as_ref().transcript_id,
                     transcript.registry_version,
                     height,
                 );
                 let transcript_ref = ecdsa::UnmaskedTranscript::try_from((height, &transcript))?;
-                ecdsa_payload.key_transcript.next_in_creation =
-                    ecdsa::KeyTranscriptCreation::Created(transcript_ref);
+                *next_in_creation = ecdsa::KeyTranscriptCreation::Created(transcript_ref);
                 new_transcript = Some(transcript);
             }
         }
 
         (None, ecdsa::KeyTranscriptCreation::Begin) => {
-            // The first ECDSA key transcript has to be created, starting from a random
+            // The first key transcript has to be created, starting from a random
             // config. Here receivers and dealers are the same set.
-            let transcript_id = ecdsa_payload.uid_generator.next_transcript_id();
+            let transcript_id = uid_generator.next_transcript_id();
             let receivers_set = receivers.iter().copied().collect::<BTreeSet<_>>();
             let dealers_set = receivers_set.clone();
-            ecdsa_payload.key_transcript.next_in_creation =
-                ecdsa::KeyTranscriptCreation::RandomTranscriptParams(
-                    ecdsa::RandomTranscriptParams::new(
-                        transcript_id,
-                        dealers_set,
-                        receivers_set,
-                        registry_version,
-                        AlgorithmId::ThresholdEcdsaSecp256k1,
-                    ),
-                );
+            *next_in_creation = ecdsa::KeyTranscriptCreation::RandomTranscriptParams(
+                ecdsa::RandomTranscriptParams::new(
+                    transcript_id,
+                    dealers_set,
+                    receivers_set,
+                    registry_version,
+                    algorithm_id,
+                ),
+            );
         }
 
         (None, ecdsa::KeyTranscriptCreation::RandomTranscriptParams(config)) => {
@@ -1030,16 +1358,15 @@ fn update_next_key_transcript(
             {
                 let receivers_set = receivers.iter().copied().collect::<BTreeSet<_>>();
                 let transcript_ref = ecdsa::MaskedTranscript::try_from((height, &transcript))?;
-                ecdsa_payload.key_transcript.next_in_creation =
-                    ecdsa::KeyTranscriptCreation::ReshareOfMaskedParams(
-                        ecdsa::ReshareOfMaskedParams::new(
-                            ecdsa_payload.uid_generator.next_transcript_id(),
-                            receivers_set,
-                            registry_version,
-                            &transcript,
-                            transcript_ref,
-                        ),
-                    );
+                *next_in_creation = ecdsa::KeyTranscriptCreation::ReshareOfMaskedParams(
+                    ecdsa::ReshareOfMaskedParams::new(
+                        uid_generator.next_transcript_id(),
+                        receivers_set,
+                        registry_version,
+                        &transcript,
+                        transcript_ref,
+                    ),
+                );
                 new_transcript = Some(transcript);
             }
         }
@@ -1051,15 +1378,15 @@ fn update_next_key_transcript(
             {
                 info!(
                     log,
-                    "ECDSA key transcript created from ReshareOfMasked {:?} \
+                    "{:?} key transcript created from ReshareOfMasked {:?} \
                     registry_version {} height = {}",
+                    algorithm_id,
                     config.as_ref().transcript_id,
                     transcript.registry_version,
                     height,
                 );
                 let transcript_ref = ecdsa::UnmaskedTranscript::try_from((height, &transcript))?;
-                ecdsa_payload.key_transcript.next_in_creation =
-                    ecdsa::KeyTranscriptCreation::Created(transcript_ref);
+                *next_in_creation = ecdsa::KeyTranscriptCreation::Created(transcript_ref);
                 new_transcript = Some(transcript);
             }
         }
@@ -1073,15 +1400,15 @@ fn update_next_key_transcript(
                 // by the reshared param will be used.
                 info!(
                     log,
-                    "ECDSA Key transcript created from XnetReshareOfUnmasked {:?}, \
+                    "{:?} key transcript created from XnetReshareOfUnmasked {:?}, \
                     registry_version {}, height = {}",
+                    algorithm_id,
                     config.as_ref().transcript_id,
                     transcript.registry_version,
                     height,
                 );
                 let transcript_ref = ecdsa::UnmaskedTranscript::try_from((height, &transcript))?;
-                ecdsa_payload.key_transcript.next_in_creation =
-                    ecdsa::KeyTranscriptCreation::Created(transcript_ref);
+                *next_in_creation = ecdsa::KeyTranscriptCreation::Created(transcript_ref);
                 new_transcript = Some(transcript);
             }
         }
@@ -6,7 +6,21 @@ pub(super) fn get_created_key_transcript(
     key_transcript: &ecdsa::EcdsaKeyTranscript,
     block_reader: &dyn EcdsaBlockReader,
 ) -> Result<Option<ecdsa::UnmaskedTranscriptWithAttributes>, EcdsaPayloadError> {
-    if let ecdsa::KeyTranscriptCreation::Created(unmasked) = &key_transcript.next_in_creation {
+    get_created_transcript(&key_transcript.next_in_creation, block_reader)
+}
+
+pub(super) fn get_created_schnorr_key_transcript(
+    key_transcript: &ecdsa::SchnorrKeyTranscript,
+    block_reader: &dyn EcdsaBlockReader,
+) -> Result<Option<ecdsa::UnmaskedTranscriptWithAttributes>, EcdsaPayloadError> {
+    get_created_transcript(&key_transcript.next_in_creation, block_reader)
+}
+
+fn get_created_transcript(
+    next_in_creation: &ecdsa::KeyTranscriptCreation,
+    block_reader: &dyn EcdsaBlockReader,
+) -> Result<Option<ecdsa::UnmaskedTranscriptWithAttributes>, EcdsaPayloadError> {
+    if let ecdsa::KeyTranscriptCreation::Created(unmasked) = next_in_creation {
         let transcript = block_reader.transcript(unmasked.as_ref())?;
         Ok(Some(ecdsa::UnmaskedTranscriptWithAttributes::new(
             transcript.to_attributes(),
@@ -0,0 +1,307 @@
+use super::EcdsaPayloadError;
+
+use crate::ecdsa::pre_signer::EcdsaTranscriptBuilder;
+use ic_logger::{debug, ReplicaLogger};
+use ic_registry_subnet_features::SchnorrConfig;
+use ic_types::{
+    consensus::ecdsa::{self, TranscriptAttributes},
+    crypto::{
+        canister_threshold_sig::idkg::{IDkgTranscript, IDkgTranscriptId},
+        AlgorithmId,
+    },
+    Height, NodeId, RegistryVersion,
+};
+
+use std::collections::BTreeSet;
+
+/// Update the threshold Schnorr pre-signatures in the payload by:
+/// - gathering ready results (new transcripts) from ecdsa pool;
+/// - moving completed pre-signatures from "in creation" to "available".
+/// Returns the newly created transcripts.
+pub(super) fn update_pre_signatures_in_creation(
+    payload: &mut ecdsa::EcdsaPayload,
+    transcript_cache: &dyn EcdsaTranscriptBuilder,
+    height: Height,
+    log: &ReplicaLogger,
+) -> Result<Vec<IDkgTranscript>, EcdsaPayloadError> {
+    let mut newly_available = Vec::new();
+    let mut new_transcripts = Vec::new();
+    for (key, pre_signature) in payload.schnorr_pre_signatures_in_creation.iter_mut() {
+        if pre_signature.blinder_unmasked.is_none() {
+            if let Some(transcript) = transcript_cache.get_completed_transcript(
+                pre_signature.blinder_unmasked_config.as_ref().transcript_id,
+            ) {
+                debug!(
+                    log,
+                    "update_pre_signatures_in_creation: {:?} blinder_unmasked transcript is made",
+                    key
+                );
+                pre_signature.blinder_unmasked =
+                    Some(ecdsa::UnmaskedTranscript::try_from((height, &transcript))?);
+                new_transcripts.push(transcript);
+            }
+        }
+        if let Some(blinder_unmasked) = pre_signature.blinder_unmasked {
+            newly_available.push((key.clone(), blinder_unmasked));
+        }
+    }
+
+    for (key, blinder_unmasked) in newly_available {
+        debug!(
+            log,
+            "update_pre_signatures_in_creation: making of pre-signature {:?} is complete", key
+        );
+        payload.schnorr_pre_signatures_in_creation.remove(&key);
+        payload
+            .available_schnorr_pre_signatures
+            .insert(key, ecdsa::SchnorrPreSignatureRef::new(blinder_unmasked));
+    }
+
+    Ok(new_transcripts)
+}
+
+/// Creating new threshold Schnorr pre-signatures if necessary by updating
+/// schnorr_pre_signatures_in_creation, considering currently available
+/// pre-signatures, pre-signatures in creation, and the schnorr config.
+/// Pre-signatures are created separately for every configured key.
+pub(super) fn make_new_pre_signatures_if_needed(
+    schnorr_config: &SchnorrConfig,
+    ecdsa_payload: &mut ecdsa::EcdsaPayload,
+) {
+    for key_id in &schnorr_config.key_ids {
+        let Some(key_transcript) = ecdsa_payload
+            .schnorr_key_transcripts
+            .get(key_id)
+            .and_then(|key_transcript| key_transcript.current.as_ref())
+        else {
+            continue;
+        };
+        let registry_version = key_transcript.registry_version();
+        let subnet_nodes = key_transcript.receivers().clone();
+
+        let unassigned_pre_signatures = ecdsa_payload
+            .unassigned_pre_signature_ids()
+            .filter(|pre_signature_id| pre_signature_id.key_id() == key_id)
+            .count();
+        let pre_signatures_to_create = schnorr_config.pre_signatures_to_create_in_advance as usize;
+        if pre_signatures_to_create > unassigned_pre_signatures {
+            let uid_generator = &mut ecdsa_payload.uid_generator;
+            for _ in 0..(pre_signatures_to_create - unassigned_pre_signatures) {
+                let blinder_unmasked_config = new_random_unmasked_config(
+                    &subnet_nodes,
+                    registry_version,
+                    uid_generator.next_transcript_id(),
+                );
+                ecdsa_payload.schnorr_pre_signatures_in_creation.insert(
+                    uid_generator.next_pre_signature_id(key_id.clone()),
+                    ecdsa::SchnorrPreSignatureInCreation::new(blinder_unmasked_config),
+                );
+            }
+        }
+    }
+}
+
+/// Create a new random unmasked transcript config for the threshold
+/// Schnorr algorithm.
+fn new_random_unmasked_config(
+    subnet_nodes: &BTreeSet<NodeId>,
+    registry_version: RegistryVersion,
+    transcript_id: IDkgTranscriptId,
+) -> ecdsa::RandomUnmaskedTranscriptParams {
+    ecdsa::RandomUnmaskedTranscriptParams::new(
+        transcript_id,
+        subnet_nodes.clone(),
+        subnet_nodes.clone(),
+        registry_version,
+        AlgorithmId::ThresholdSchnorrBip340,
+    )
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    use crate::ecdsa::test_utils::{
+        set_up_ecdsa_payload, EcdsaPayloadTestHelper, TestEcdsaBlockReader,
+        TestEcdsaTranscriptBuilder,
+    };
+    use ic_crypto_test_utils_canister_threshold_sigs::CanisterThresholdSigTestEnvironment;
+    use ic_crypto_test_utils_reproducible_rng::{reproducible_rng, ReproducibleRng};
+    use ic_ic00_types::{SchnorrAlgorithm, SchnorrKeyId};
+    use ic_logger::replica_logger::no_op_logger;
+    use ic_test_utilities::types::ids::subnet_test_id;
+    use ic_types::{
+        consensus::ecdsa::{EcdsaPayload, IDkgTranscriptOperationRef},
+        SubnetId,
+    };
+
+    fn schnorr_key_id(name: &str) -> SchnorrKeyId {
+        SchnorrKeyId {
+            algorithm: SchnorrAlgorithm::Bip340Secp256k1,
+            name: name.to_string(),
+        }
+    }
+
+    fn set_up(
+        rng: &mut ReproducibleRng,
+        subnet_id: SubnetId,
+        key_ids: &[SchnorrKeyId],
+        height: Height,
+    ) -> (
+        EcdsaPayload,
+        CanisterThresholdSigTestEnvironment,
+        TestEcdsaBlockReader,
+    ) {
+        let (mut ecdsa_payload, env, block_reader) = set_up_ecdsa_payload(
+            rng, subnet_id, /*nodes_count=*/ 4, /*should_create_key_transcript=*/ true,
+        );
+        ecdsa_payload
+            .uid_generator
+            .update_height(height)
+            .expect("Should successfully update the height");
+        for key_id in key_ids {
+            let mut schnorr_key_transcript = ecdsa::SchnorrKeyTranscript::new(key_id.clone());
+            schnorr_key_transcript.current = ecdsa_payload.key_transcript.current.clone();
+            ecdsa_payload
+                .schnorr_key_transcripts
+                .insert(key_id.clone(), schnorr_key_transcript);
+        }
+
+        (ecdsa_payload, env, block_reader)
+    }
+
+    #[test]
+    fn test_make_new_pre_signatures_if_needed() {
+        let mut rng = reproducible_rng();
+        let subnet_id = subnet_test_id(1);
+        let height = Height::new(10);
+        let key_id = schnorr_key_id("some_key");
+        let (mut ecdsa_payload, _env, _block_reader) =
+            set_up(&mut rng, subnet_id, &[key_id.clone()], height);
+        let schnorr_config = SchnorrConfig {
+            key_ids: vec![key_id],
+            pre_signatures_to_create_in_advance: 3,
+            ..SchnorrConfig::default()
+        };
+
+        make_new_pre_signatures_if_needed(&schnorr_config, &mut ecdsa_payload);
+        assert_eq!(ecdsa_payload.schnorr_pre_signatures_in_creation.len(), 3);
+        for pre_signature in ecdsa_payload.schnorr_pre_signatures_in_creation.values() {
+            let config = pre_signature.blinder_unmasked_config.as_ref();
+            assert_eq!(config.algorithm_id, AlgorithmId::ThresholdSchnorrBip340);
+            assert_eq!(
+                config.operation_type_ref,
+                IDkgTranscriptOperationRef::RandomUnmasked
+            );
+        }
+        assert_eq!(ecdsa_payload.peek_next_transcript_id().id(), 3);
+
+        // Enough pre-signatures are in creation already, nothing should be added.
+        make_new_pre_signatures_if_needed(&schnorr_config, &mut ecdsa_payload);
+        assert_eq!(ecdsa_payload.schnorr_pre_signatures_in_creation.len(), 3);
+        assert_eq!(ecdsa_payload.peek_next_transcript_id().id(), 3);
+    }
+
+    #[test]
+    fn test_make_new_pre_signatures_if_needed_for_multiple_keys() {
+        let mut rng = reproducible_rng();
+        let subnet_id = subnet_test_id(1);
+        let height = Height::new(10);
+        let key_id_1 = schnorr_key_id("key_1");
+        let key_id_2 = schnorr_key_id("key_2");
+        let key_id_3 = schnorr_key_id("key_without_transcript");
+        let (mut ecdsa_payload, _env, _block_reader) = set_up(
+            &mut rng,
+            subnet_id,
+            &[key_id_1.clone(), key_id_2.clone()],
+            height,
+        );
+        let schnorr_config = SchnorrConfig {
+            key_ids: vec![key_id_1.clone(), key_id_2.clone(), key_id_3.clone()],
+            pre_signatures_to_create_in_advance: 2,
+            ..SchnorrConfig::default()
+        };
+
+        make_new_pre_signatures_if_needed(&schnorr_config, &mut ecdsa_payload);
+        let count_for_key = |payload: &EcdsaPayload, key_id: &SchnorrKeyId| {
+            payload
+                .schnorr_pre_signatures_in_creation
+                .keys()
+                .filter(|id| id.key_id() == key_id)
+                .count()
+        };
+        assert_eq!(ecdsa_payload.schnorr_pre_signatures_in_creation.len(), 4);
+        assert_eq!(count_for_key(&ecdsa_payload, &key_id_1), 2);
+        assert_eq!(count_for_key(&ecdsa_payload, &key_id_2), 2);
+        // No pre-signatures can be created for a key without a transcript.
+        assert_eq!(count_for_key(&ecdsa_payload, &key_id_3), 0);
+
+        // Consuming a pre-signature of one key only refills that key.
+        let pre_signature_id = ecdsa_payload
+            .schnorr_pre_signatures_in_creation
+            .keys()
+            .find(|id| id.key_id() == &key_id_1)
+            .cloned()
+            .unwrap();
+        ecdsa_payload
+            .schnorr_pre_signatures_in_creation
+            .remove(&pre_signature_id);
+        make_new_pre_signatures_if_needed(&schnorr_config, &mut ecdsa_payload);
+        assert_eq!(count_for_key(&ecdsa_payload, &key_id_1), 2);
+        assert_eq!(count_for_key(&ecdsa_payload, &key_id_2), 2);
+        assert_eq!(ecdsa_payload.peek_next_transcript_id().id(), 5);
+    }
+
+    #[test]
+    fn test_update_pre_signatures_in_creation() {
+        let mut rng = reproducible_rng();
+        let subnet_id = subnet_test_id(1);
+        let key_id = schnorr_key_id("some_key");
+        let (mut payload, env, block_reader) =
+            set_up(&mut rng, subnet_id, &[key_id.clone()], Height::from(100));
+        let transcript_builder = TestEcdsaTranscriptBuilder::new();
+        let schnorr_config = SchnorrConfig {
+            key_ids: vec![key_id],
+            pre_signatures_to_create_in_advance: 1,
+            ..SchnorrConfig::default()
+        };
+        make_new_pre_signatures_if_needed(&schnorr_config, &mut payload);
+        let (pre_signature_id, config_ref) = payload
+            .schnorr_pre_signatures_in_creation
+            .iter()
+            .map(|(id, pre_signature)| (id.clone(), pre_signature.blinder_unmasked_config.clone()))
+            .next()
+            .unwrap();
+
+        // 0. No action case
+        let cur_height = Height::new(1000);
+        let result = update_pre_signatures_in_creation(
+            &mut payload,
+            &transcript_builder,
+            cur_height,
+            &no_op_logger(),
+        );
+        assert!(result.unwrap().is_empty());
+        assert!(payload.available_schnorr_pre_signatures.is_empty());
+        assert_eq!(payload.schnorr_pre_signatures_in_creation.len(), 1);
+
+        // 1. When blinder_unmasked is ready, the pre-signature becomes available.
+        let blinder_transcript = env.nodes.run_idkg_and_create_and_verify_transcript(
+            &config_ref.as_ref().translate(&block_reader).unwrap(),
+            &mut rng,
+        );
+        transcript_builder.add_transcript(config_ref.as_ref().transcript_id, blinder_transcript);
+        let result = update_pre_signatures_in_creation(
+            &mut payload,
+            &transcript_builder,
+            cur_height,
+            &no_op_logger(),
+        )
+        .unwrap();
+        assert_eq!(result.len(), 1);
+        assert!(payload.schnorr_pre_signatures_in_creation.is_empty());
+        assert!(payload
+            .available_schnorr_pre_signatures
+            .contains_key(&pre_signature_id));
+    }
+}
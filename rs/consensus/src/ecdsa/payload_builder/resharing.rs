@@ -78,6 +78,7 @@ pub(super) fn make_reshare_dealings_response(
                             }
                             .encode(),
                         ),
+                        deadline: context.request.deadline,
                     });
                 }
             }
@@ -1,14 +1,16 @@
 use std::collections::{BTreeMap, BTreeSet};
 
 use ic_error_types::RejectCode;
-use ic_ic00_types::{EcdsaKeyId, Payload, SignWithECDSAReply};
+use ic_ic00_types::{EcdsaKeyId, Payload, SchnorrKeyId, SignWithECDSAReply, SignWithSchnorrReply};
 use ic_logger::{debug, ReplicaLogger};
-use ic_replicated_state::metadata_state::subnet_call_context_manager::SignWithEcdsaContext;
+use ic_replicated_state::metadata_state::subnet_call_context_manager::{
+    SignWithEcdsaContext, SignWithSchnorrContext,
+};
 use ic_types::{
     consensus::ecdsa,
     crypto::canister_threshold_sig::ExtendedDerivationPath,
     messages::{CallbackId, RejectContext},
-    Time,
+    Height, Time,
 };
 use phantom_newtype::Id;
 
@@ -71,6 +73,7 @@ pub(crate) fn update_signature_agreements(
                 }
                 .encode(),
             ),
+            deadline: context.request.deadline,
         };
 
         completed.insert(
@@ -172,6 +175,7 @@ pub(crate) fn update_signature_agreements_improved_latency(
                         context.key_id
                     ),
                 )),
+                deadline: context.request.deadline,
             };
             payload.signature_agreements.insert(
                 context.pseudo_random_id,
@@ -201,6 +205,7 @@ pub(crate) fn update_signature_agreements_improved_latency(
                     RejectCode::CanisterError,
                     "Signature request expired",
                 )),
+                deadline: context.request.deadline,
             };
             payload.signature_agreements.insert(
                 context.pseudo_random_id,
@@ -234,6 +239,7 @@ pub(crate) fn update_signature_agreements_improved_latency(
                 }
                 .encode(),
             ),
+            deadline: context.request.deadline,
         };
         payload.signature_agreements.insert(
             context.pseudo_random_id,
@@ -263,6 +269,254 @@ pub(crate) fn build_signature_inputs(
     )
 }
 
+/// Update threshold Schnorr signature agreements in the data payload by:
+/// - dropping agreements that don't have a [SignWithSchnorrContext] anymore (because
+///   the response has been delivered)
+/// - setting remaining agreements to "Reported"
+/// - adding new agreements as "Unreported" by combining shares in the ECDSA pool.
+pub(crate) fn update_schnorr_signature_agreements(
+    all_requests: &BTreeMap<CallbackId, SignWithSchnorrContext>,
+    signature_builder: &dyn EcdsaSignatureBuilder,
+    payload: &mut ecdsa::EcdsaPayload,
+) {
+    let all_random_ids = all_requests
+        .iter()
+        .map(|(callback_id, context)| (context.pseudo_random_id, (callback_id, context)))
+        .collect::<BTreeMap<_, _>>();
+    // Keep the agreements that can still be found in the signing requests
+    // for dedup purpose, they have been reported in the previous round.
+    payload.schnorr_signature_agreements = payload
+        .schnorr_signature_agreements
+        .keys()
+        .filter(|random_id| all_random_ids.contains_key(*random_id))
+        .map(|random_id| (*random_id, ecdsa::CompletedSignature::ReportedToExecution))
+        .collect();
+
+    // Then we collect new signatures into the schnorr_signature_agreements
+    let mut completed = BTreeMap::new();
+    for request_id in payload.ongoing_schnorr_signatures.keys() {
+        let Some((callback_id, context)) = all_random_ids.get(&request_id.pseudo_random_id) else {
+            continue;
+        };
+
+        let Some(signature) = signature_builder.get_completed_schnorr_signature(request_id) else {
+            continue;
+        };
+
+        let response = ic_types::messages::Response {
+            originator: context.request.sender,
+            respondent: ic_types::CanisterId::ic_00(),
+            originator_reply_callback: **callback_id,
+            // Execution is responsible for burning the appropriate cycles
+            // before pushing the new context, so any remaining cycles can
+            // be refunded to the canister.
+            refund: context.request.payment,
+            response_payload: ic_types::messages::Payload::Data(
+                SignWithSchnorrReply {
+                    signature: signature.signature,
+                }
+                .encode(),
+            ),
+            deadline: context.request.deadline,
+        };
+
+        completed.insert(
+            request_id.clone(),
+            ecdsa::CompletedSignature::Unreported(response),
+        );
+    }
+
+    for (request_id, signature) in completed {
+        payload.ongoing_schnorr_signatures.remove(&request_id);
+        payload
+            .schnorr_signature_agreements
+            .insert(request_id.pseudo_random_id, signature);
+    }
+}
+
+/// Return the set of new threshold Schnorr signing requests by pairing them
+/// with unassigned pre-signatures of the requested key, in the order of their
+/// callback ids. This
+/// follows the same logic as [`super::get_signing_requests`]: requests for
+/// unknown keys are rejected, and expired requests are rejected and their
+/// pre-signatures are discarded.
+pub(crate) fn get_schnorr_signing_requests<'a>(
+    height: Height,
+    request_expiry_time: Option<Time>,
+    payload: &mut ecdsa::EcdsaPayload,
+    sign_with_schnorr_contexts: &'a BTreeMap<CallbackId, SignWithSchnorrContext>,
+    valid_keys: &BTreeSet<SchnorrKeyId>,
+    ecdsa_payload_metrics: Option<&EcdsaPayloadMetrics>,
+) -> BTreeMap<ecdsa::SchnorrRequestId, &'a SignWithSchnorrContext> {
+    let known_random_ids_ongoing = payload
+        .ongoing_schnorr_signatures
+        .keys()
+        .map(|id| (id.pseudo_random_id, id.clone()))
+        .collect::<BTreeMap<_, _>>();
+    let mut unassigned_pre_signature_ids: BTreeMap<SchnorrKeyId, Vec<ecdsa::PreSigId>> =
+        BTreeMap::new();
+    for pre_signature_id in payload.unassigned_pre_signature_ids() {
+        unassigned_pre_signature_ids
+            .entry(pre_signature_id.key_id().clone())
+            .or_default()
+            .push(pre_signature_id);
+    }
+    for pre_signature_ids in unassigned_pre_signature_ids.values_mut() {
+        // sort in reverse order (bigger to smaller).
+        pre_signature_ids.sort_by(|a, b| b.cmp(a));
+    }
+    let mut new_requests = BTreeMap::new();
+
+    for (callback_id, context) in sign_with_schnorr_contexts {
+        // Skip known completed requests.
+        if payload
+            .schnorr_signature_agreements
+            .contains_key(&context.pseudo_random_id)
+        {
+            continue;
+        }
+
+        let known_request_id = known_random_ids_ongoing.get(&context.pseudo_random_id);
+
+        if known_request_id.is_none() && !valid_keys.contains(&context.key_id) {
+            // Reject new requests with unknown key Ids.
+            // Note that no pre-signatures are consumed at this stage.
+            let response = ic_types::messages::Response {
+                originator: context.request.sender,
+                respondent: ic_types::CanisterId::ic_00(),
+                originator_reply_callback: *callback_id,
+                refund: context.request.payment,
+                response_payload: ic_types::messages::Payload::Reject(RejectContext::new(
+                    RejectCode::CanisterReject,
+                    format!(
+                        "Invalid or disabled key_id in signature request: {:?}",
+                        context.key_id
+                    ),
+                )),
+                deadline: context.request.deadline,
+            };
+            payload.schnorr_signature_agreements.insert(
+                context.pseudo_random_id,
+                ecdsa::CompletedSignature::Unreported(response),
+            );
+            if let Some(metrics) = ecdsa_payload_metrics {
+                metrics.payload_errors_inc("invalid_keyid_requests");
+            }
+            continue;
+        }
+
+        let request_id = known_request_id.cloned().or_else(|| {
+            unassigned_pre_signature_ids
+                .get_mut(&context.key_id)
+                .and_then(|pre_signature_ids| pre_signature_ids.pop())
+                .map(|pre_signature_id| ecdsa::SchnorrRequestId {
+                    pre_signature_id,
+                    pseudo_random_id: context.pseudo_random_id,
+                    height,
+                })
+        });
+
+        // Reject requests that timed out, once they got paired with a pre-signature.
+        if request_expiry_time.is_some_and(|expiry| context.batch_time < expiry) {
+            let Some(request_id) = request_id else {
+                continue;
+            };
+            let response = ic_types::messages::Response {
+                originator: context.request.sender,
+                respondent: ic_types::CanisterId::ic_00(),
+                originator_reply_callback: *callback_id,
+                refund: context.request.payment,
+                response_payload: ic_types::messages::Payload::Reject(RejectContext::new(
+                    RejectCode::CanisterError,
+                    "Signature request expired",
+                )),
+                deadline: context.request.deadline,
+            };
+            payload.schnorr_signature_agreements.insert(
+                context.pseudo_random_id,
+                ecdsa::CompletedSignature::Unreported(response),
+            );
+            if let Some(metrics) = ecdsa_payload_metrics {
+                metrics.payload_errors_inc("expired_requests");
+            }
+            payload.ongoing_schnorr_signatures.remove(&request_id);
+            payload
+                .schnorr_pre_signatures_in_creation
+                .remove(&request_id.pre_signature_id);
+            payload
+                .available_schnorr_pre_signatures
+                .remove(&request_id.pre_signature_id);
+            continue;
+        }
+
+        if known_request_id.is_none() {
+            if let Some(request_id) = request_id {
+                new_requests.insert(request_id, context);
+            }
+        }
+    }
+
+    new_requests
+}
+
+/// For every new threshold Schnorr signing request, we only start to work
+/// on them if their matched pre-signature has been fully produced and the
+/// transcript of the requested key is available.
+pub(crate) fn update_ongoing_schnorr_signatures(
+    new_requests: BTreeMap<ecdsa::SchnorrRequestId, &SignWithSchnorrContext>,
+    max_ongoing_signatures: u32,
+    payload: &mut ecdsa::EcdsaPayload,
+    log: &ReplicaLogger,
+) {
+    debug!(
+        log,
+        "update_ongoing_schnorr_signatures: number of new_requests={}",
+        new_requests.len()
+    );
+    for (request_id, context) in new_requests.into_iter() {
+        if (payload.ongoing_schnorr_signatures.len() as u32) >= max_ongoing_signatures {
+            return;
+        }
+        let Some(key_transcript) = payload
+            .schnorr_key_transcripts
+            .get(request_id.pre_signature_id.key_id())
+            .and_then(|key_transcript| key_transcript.current.clone())
+        else {
+            continue;
+        };
+        if let Some(pre_signature) = payload
+            .available_schnorr_pre_signatures
+            .remove(&request_id.pre_signature_id)
+        {
+            let sign_inputs =
+                build_schnorr_signature_inputs(context, &pre_signature, &key_transcript);
+            payload
+                .ongoing_schnorr_signatures
+                .insert(request_id, sign_inputs);
+        }
+    }
+}
+
+/// Helper to build threshold Schnorr signature inputs from the context and
+/// the pre-signature
+pub(crate) fn build_schnorr_signature_inputs(
+    context: &SignWithSchnorrContext,
+    pre_signature_ref: &ecdsa::SchnorrPreSignatureRef,
+    key_transcript_ref: &ecdsa::UnmaskedTranscriptWithAttributes,
+) -> ecdsa::ThresholdSchnorrSigInputsRef {
+    let extended_derivation_path = ExtendedDerivationPath {
+        caller: context.request.sender.into(),
+        derivation_path: context.derivation_path.clone(),
+    };
+    ecdsa::ThresholdSchnorrSigInputsRef::new(
+        extended_derivation_path,
+        context.message.clone(),
+        Id::from(context.pseudo_random_id),
+        pre_signature_ref.clone(),
+        key_transcript_ref.unmasked_transcript(),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::BTreeSet;
@@ -270,16 +524,18 @@ mod tests {
     use ic_crypto_test_utils_reproducible_rng::reproducible_rng;
     use ic_ic00_types::EcdsaKeyId;
     use ic_logger::replica_logger::no_op_logger;
+    use ic_registry_subnet_features::SchnorrConfig;
     use ic_test_utilities::types::ids::subnet_test_id;
     use ic_types::{consensus::ecdsa::EcdsaPayload, Height};
 
     use crate::ecdsa::{
         payload_builder::{
-            get_signing_requests, quadruples::test_utils::create_available_quadruple,
+            get_signing_requests, pre_signatures::make_new_pre_signatures_if_needed,
+            quadruples::test_utils::create_available_quadruple,
         },
         test_utils::{
-            empty_response, fake_sign_with_ecdsa_context, set_up_ecdsa_payload,
-            TestEcdsaSignatureBuilder,
+            empty_response, fake_schnorr_key_id, fake_sign_with_ecdsa_context,
+            fake_sign_with_schnorr_context, set_up_ecdsa_payload, TestEcdsaSignatureBuilder,
         },
     };
 
@@ -491,4 +747,56 @@ mod tests {
             )])
         );
     }
+
+    #[test]
+    fn test_get_schnorr_signing_requests_uses_pre_signatures_of_requested_key() {
+        let height = Height::new(789);
+        let (mut ecdsa_payload, _) = set_up(
+            /*should_create_key_transcript=*/ true,
+            /*pseudo_random_ids=*/ vec![],
+        );
+        let key_id_1 = fake_schnorr_key_id("key_1");
+        let key_id_2 = fake_schnorr_key_id("key_2");
+        for key_id in [&key_id_1, &key_id_2] {
+            let mut key_transcript = ecdsa::SchnorrKeyTranscript::new(key_id.clone());
+            key_transcript.current = ecdsa_payload.key_transcript.current.clone();
+            ecdsa_payload
+                .schnorr_key_transcripts
+                .insert(key_id.clone(), key_transcript);
+        }
+        let schnorr_config = SchnorrConfig {
+            key_ids: vec![key_id_1.clone(), key_id_2.clone()],
+            pre_signatures_to_create_in_advance: 1,
+            ..SchnorrConfig::default()
+        };
+        make_new_pre_signatures_if_needed(&schnorr_config, &mut ecdsa_payload);
+        let contexts = BTreeMap::from([
+            (
+                CallbackId::from(0),
+                fake_sign_with_schnorr_context(key_id_2.clone(), pseudo_random_id(0)),
+            ),
+            (
+                CallbackId::from(1),
+                fake_sign_with_schnorr_context(key_id_2.clone(), pseudo_random_id(1)),
+            ),
+        ]);
+
+        let signing_requests = get_schnorr_signing_requests(
+            height,
+            /*request_expiry_time=*/ None,
+            &mut ecdsa_payload,
+            &contexts,
+            &BTreeSet::from([key_id_1, key_id_2.clone()]),
+            /*ecdsa_payload_metrics=*/ None,
+        );
+
+        // There is only one pre-signature of the requested key, so the second
+        // request has to wait, even though a pre-signature of another key is
+        // available.
+        assert_eq!(signing_requests.len(), 1);
+        let request_id = signing_requests.keys().next().unwrap();
+        assert_eq!(request_id.pre_signature_id.key_id(), &key_id_2);
+        assert_eq!(request_id.pseudo_random_id, pseudo_random_id(0));
+        assert_eq!(request_id.height, height);
+    }
 }
@@ -33,6 +33,7 @@ use crate::ecdsa::utils::build_signature_inputs;
 use ic_consensus_utils::crypto::ConsensusCrypto;
 use ic_consensus_utils::pool_reader::PoolReader;
 use ic_crypto::MegaKeyFromRegistryError;
+use ic_interfaces::crypto::{ThresholdEcdsaSigVerifier, ThresholdSchnorrSigVerifier};
 use ic_interfaces::validation::{ValidationError, ValidationResult};
 use ic_interfaces_registry::RegistryClient;
 use ic_interfaces_state_manager::{StateManager, StateManagerError};
@@ -50,9 +51,10 @@ use ic_types::{
         error::{
             IDkgVerifyInitialDealingsError, IDkgVerifyTranscriptError,
             ThresholdEcdsaVerifyCombinedSignatureError,
+            ThresholdSchnorrVerifyCombinedSignatureError,
         },
         idkg::{IDkgTranscript, IDkgTranscriptId, InitialIDkgDealings, SignedIDkgDealing},
-        ThresholdEcdsaCombinedSignature,
+        ThresholdEcdsaCombinedSignature, ThresholdSchnorrCombinedSignature,
     },
     registry::RegistryClientError,
     Height, RegistryVersion, SubnetId,
@@ -79,6 +81,8 @@ pub enum PermanentError {
     ThresholdEcdsaSigInputsError(ecdsa::ThresholdEcdsaSigInputsError),
     TranscriptParamsError(ecdsa::TranscriptParamsError),
     ThresholdEcdsaVerifyCombinedSignatureError(ThresholdEcdsaVerifyCombinedSignatureError),
+    ThresholdSchnorrSigInputsError(ecdsa::ThresholdSchnorrSigInputsError),
+    ThresholdSchnorrVerifyCombinedSignatureError(ThresholdSchnorrVerifyCombinedSignatureError),
     IDkgVerifyTranscriptError(IDkgVerifyTranscriptError),
     IDkgVerifyInitialDealingsError(IDkgVerifyInitialDealingsError),
     MegaKeyFromRegistryError(MegaKeyFromRegistryError),
@@ -127,6 +131,12 @@ impl From<ecdsa::ThresholdEcdsaSigInputsError> for PermanentError {
     }
 }
 
+impl From<ecdsa::ThresholdSchnorrSigInputsError> for PermanentError {
+    fn from(err: ecdsa::ThresholdSchnorrSigInputsError) -> Self {
+        PermanentError::ThresholdSchnorrSigInputsError(err)
+    }
+}
+
 impl From<ecdsa::TranscriptParamsError> for PermanentError {
     fn from(err: ecdsa::TranscriptParamsError) -> Self {
         PermanentError::TranscriptParamsError(err)
@@ -366,11 +376,24 @@ pub fn validate_data_payload(
         },
         metrics,
     )?;
+    let schnorr_signatures = timed_call(
+        "validate_new_schnorr_signature_agreements",
+        || {
+            validate_new_schnorr_signature_agreements(
+                crypto,
+                &block_reader,
+                &prev_payload,
+                curr_payload,
+            )
+        },
+        metrics,
+    )?;
 
     let builder = CachedBuilder {
         transcripts,
         dealings,
         signatures,
+        schnorr_signatures,
     };
 
     let ecdsa_payload = create_data_payload_helper(
@@ -399,6 +422,7 @@ struct CachedBuilder {
     transcripts: BTreeMap<IDkgTranscriptId, IDkgTranscript>,
     dealings: BTreeMap<IDkgTranscriptId, Vec<SignedIDkgDealing>>,
     signatures: BTreeMap<ecdsa::PseudoRandomId, ThresholdEcdsaCombinedSignature>,
+    schnorr_signatures: BTreeMap<ecdsa::PseudoRandomId, ThresholdSchnorrCombinedSignature>,
 }
 
 impl EcdsaTranscriptBuilder for CachedBuilder {
@@ -428,6 +452,15 @@ impl EcdsaSignatureBuilder for CachedBuilder {
     ) -> Option<ThresholdEcdsaCombinedSignature> {
         self.signatures.get(&context.pseudo_random_id).cloned()
     }
+
+    fn get_completed_schnorr_signature(
+        &self,
+        request_id: &ecdsa::SchnorrRequestId,
+    ) -> Option<ThresholdSchnorrCombinedSignature> {
+        self.schnorr_signatures
+            .get(&request_id.pseudo_random_id)
+            .cloned()
+    }
 }
 
 // Validate transcript references
@@ -585,8 +618,7 @@ fn validate_new_signature_agreements(
                 let input = input_ref
                     .translate(block_reader)
                     .map_err(PermanentError::from)?;
-                crypto
-                    .verify_combined_sig(&input, &signature)
+                ThresholdEcdsaSigVerifier::verify_combined_sig(crypto, &input, &signature)
                     .map_err(ThresholdEcdsaVerifyCombinedSignatureError)?;
                 new_signatures.insert(*random_id, signature.clone());
             }
@@ -595,6 +627,54 @@ fn validate_new_signature_agreements(
     Ok(new_signatures)
 }
 
+// Validate new threshold Schnorr signature agreements in the current payload.
+// New signatures are those that are Unreported in the curr_payload and not in prev_payload.
+fn validate_new_schnorr_signature_agreements(
+    crypto: &dyn ConsensusCrypto,
+    block_reader: &dyn EcdsaBlockReader,
+    prev_payload: &ecdsa::EcdsaPayload,
+    curr_payload: &ecdsa::EcdsaPayload,
+) -> Result<BTreeMap<ecdsa::PseudoRandomId, ThresholdSchnorrCombinedSignature>, EcdsaValidationError>
+{
+    use PermanentError::*;
+    let mut new_signatures = BTreeMap::new();
+    for (random_id, completed) in curr_payload.schnorr_signature_agreements.iter() {
+        if let ecdsa::CompletedSignature::Unreported(response) = completed {
+            if let ic_types::messages::Payload::Data(data) = &response.response_payload {
+                use ic_ic00_types::{Payload, SignWithSchnorrReply};
+                let reply = SignWithSchnorrReply::decode(data)
+                    .map_err(|err| PermanentError::DecodingError(format!("{:?}", err)))?;
+                let signature = ThresholdSchnorrCombinedSignature {
+                    signature: reply.signature,
+                };
+                if prev_payload
+                    .schnorr_signature_agreements
+                    .get(random_id)
+                    .is_some()
+                {
+                    return Err(PermanentError::NewSignatureUnexpected(*random_id).into());
+                }
+
+                let input_ref = prev_payload
+                    .ongoing_schnorr_signatures
+                    .iter()
+                    .find_map(|(request_id, sig_input_ref)| {
+                        (request_id.pseudo_random_id == *random_id).then_some(sig_input_ref)
+                    })
+                    .ok_or(NewSignatureMissingInput(*random_id))?;
+
+                let input = input_ref
+                    .translate(block_reader)
+                    .map_err(PermanentError::from)?;
+                ThresholdSchnorrSigVerifier::verify_combined_sig(crypto, &input, &signature)
+                    .map_err(ThresholdSchnorrVerifyCombinedSignatureError)?;
+                new_signatures.insert(*random_id, signature);
+            }
+        }
+    }
+    Ok(new_signatures)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -1226,6 +1306,7 @@ mod test {
             response_payload: ic_types::messages::Payload::Data(
                 SignWithECDSAReply { signature: vec![] }.encode(),
             ),
+            deadline: None,
         });
 
         // Insert agreement for incomplete context
@@ -8,6 +8,7 @@ use ic_consensus_utils::RoundRobin;
 use ic_interfaces::consensus_pool::ConsensusBlockCache;
 use ic_interfaces::crypto::{
     ErrorReproducibility, ThresholdEcdsaSigVerifier, ThresholdEcdsaSigner,
+    ThresholdSchnorrSigVerifier, ThresholdSchnorrSigner,
 };
 use ic_interfaces::ecdsa::{EcdsaChangeAction, EcdsaChangeSet, EcdsaPool};
 use ic_interfaces_state_manager::StateReader;
@@ -17,12 +18,14 @@ use ic_replicated_state::metadata_state::subnet_call_context_manager::SignWithEc
 use ic_replicated_state::ReplicatedState;
 use ic_types::artifact::EcdsaMessageId;
 use ic_types::consensus::ecdsa::{
-    sig_share_prefix, EcdsaBlockReader, EcdsaMessage, EcdsaSigShare, EcdsaStats, HasEcdsaKeyId,
-    RequestId, ThresholdEcdsaSigInputsRef, ECDSA_IMPROVED_LATENCY,
+    schnorr_sig_share_prefix, sig_share_prefix, EcdsaBlockReader, EcdsaMessage, EcdsaSigShare,
+    EcdsaStats, HasEcdsaKeyId, RequestId, SchnorrRequestId, SchnorrSigShare,
+    ThresholdEcdsaSigInputsRef, ThresholdSchnorrSigInputsRef, ECDSA_IMPROVED_LATENCY,
 };
 use ic_types::crypto::canister_threshold_sig::{
-    error::ThresholdEcdsaCombineSigSharesError, ThresholdEcdsaCombinedSignature,
-    ThresholdEcdsaSigInputs, ThresholdEcdsaSigShare,
+    error::{ThresholdEcdsaCombineSigSharesError, ThresholdSchnorrCombineSigSharesError},
+    ThresholdEcdsaCombinedSignature, ThresholdEcdsaSigInputs, ThresholdEcdsaSigShare,
+    ThresholdSchnorrCombinedSignature, ThresholdSchnorrSigInputs, ThresholdSchnorrSigShare,
 };
 use ic_types::{Height, NodeId};
 use std::collections::{BTreeMap, BTreeSet};
@@ -78,11 +81,18 @@ impl EcdsaSignerImpl {
         transcript_loader: &dyn EcdsaTranscriptLoader,
         block_reader: &dyn EcdsaBlockReader,
     ) -> EcdsaChangeSet {
-        if ECDSA_IMPROVED_LATENCY {
+        let mut ret = if ECDSA_IMPROVED_LATENCY {
             self.send_signature_shares_improved_latency(ecdsa_pool, transcript_loader, block_reader)
         } else {
             self.send_signature_shares_deprecated(ecdsa_pool, transcript_loader, block_reader)
-        }
+        };
+        // Threshold Schnorr requests are always matched in the block payload.
+        ret.append(&mut self.send_schnorr_signature_shares(
+            ecdsa_pool,
+            transcript_loader,
+            block_reader,
+        ));
+        ret
     }
 
     fn send_signature_shares_deprecated(
@@ -143,17 +153,49 @@ impl EcdsaSignerImpl {
             .collect()
     }
 
+    fn send_schnorr_signature_shares(
+        &self,
+        ecdsa_pool: &dyn EcdsaPool,
+        transcript_loader: &dyn EcdsaTranscriptLoader,
+        block_reader: &dyn EcdsaBlockReader,
+    ) -> EcdsaChangeSet {
+        block_reader
+            .requested_schnorr_signatures()
+            .filter(|(request_id, _)| {
+                !self.signer_has_issued_schnorr_signature_share(
+                    ecdsa_pool,
+                    &self.node_id,
+                    request_id,
+                )
+            })
+            .flat_map(|(request_id, sig_inputs_ref)| {
+                self.resolve_schnorr_ref(sig_inputs_ref, block_reader, "send_signature_shares")
+                    .map(|sig_inputs| {
+                        self.crypto_create_schnorr_signature_share(
+                            ecdsa_pool,
+                            transcript_loader,
+                            request_id,
+                            &sig_inputs,
+                        )
+                    })
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
     /// Processes the received signature shares
     fn validate_signature_shares(
         &self,
         ecdsa_pool: &dyn EcdsaPool,
         block_reader: &dyn EcdsaBlockReader,
     ) -> EcdsaChangeSet {
-        if ECDSA_IMPROVED_LATENCY {
+        let mut ret = if ECDSA_IMPROVED_LATENCY {
             self.validate_signature_shares_improved_latency(ecdsa_pool, block_reader)
         } else {
             self.validate_signature_shares_deprecated(ecdsa_pool, block_reader)
-        }
+        };
+        ret.append(&mut self.validate_schnorr_signature_shares(ecdsa_pool, block_reader));
+        ret
     }
 
     fn validate_signature_shares_deprecated(
@@ -319,17 +361,97 @@ impl EcdsaSignerImpl {
         ret
     }
 
+    fn validate_schnorr_signature_shares(
+        &self,
+        ecdsa_pool: &dyn EcdsaPool,
+        block_reader: &dyn EcdsaBlockReader,
+    ) -> EcdsaChangeSet {
+        let sig_inputs_map = block_reader
+            .requested_schnorr_signatures()
+            .collect::<BTreeMap<_, _>>();
+
+        // Collection of validated shares
+        let mut validated_sig_shares = BTreeSet::new();
+
+        let mut ret = Vec::new();
+        for (id, share) in ecdsa_pool.unvalidated().schnorr_signature_shares() {
+            // Remove the duplicate entries
+            let key = (share.request_id.clone(), share.signer_id);
+            if validated_sig_shares.contains(&key) {
+                self.metrics
+                    .sign_errors_inc("duplicate_schnorr_sig_shares_in_batch");
+                ret.push(EcdsaChangeAction::HandleInvalid(
+                    id,
+                    format!("Duplicate share in unvalidated batch: {}", share),
+                ));
+                continue;
+            }
+
+            if share.request_id.height > block_reader.tip_height() {
+                // Share is from a node ahead of us, keep it to be
+                // processed later
+                continue;
+            }
+
+            let Some(sig_inputs_ref) = sig_inputs_map.get(&share.request_id) else {
+                // Its for a signature that has not been requested, drop it
+                ret.push(EcdsaChangeAction::RemoveUnvalidated(id));
+                continue;
+            };
+
+            if self.signer_has_issued_schnorr_signature_share(
+                ecdsa_pool,
+                &share.signer_id,
+                &share.request_id,
+            ) {
+                // The node already sent a valid share for this request
+                self.metrics.sign_errors_inc("duplicate_schnorr_sig_share");
+                ret.push(EcdsaChangeAction::HandleInvalid(
+                    id,
+                    format!("Duplicate share: {}", share),
+                ));
+                continue;
+            }
+
+            match self.resolve_schnorr_ref(
+                sig_inputs_ref,
+                block_reader,
+                "validate_signature_shares",
+            ) {
+                Some(sig_inputs) => {
+                    let action = self.crypto_verify_schnorr_signature_share(id, &sig_inputs, share);
+                    if let Some(EcdsaChangeAction::MoveToValidated(_)) = action {
+                        validated_sig_shares.insert(key);
+                    }
+                    ret.append(&mut action.into_iter().collect());
+                }
+                None => {
+                    ret.push(EcdsaChangeAction::HandleInvalid(
+                        id,
+                        format!(
+                            "validate_schnorr_signature_shares(): failed to translate: {}",
+                            share
+                        ),
+                    ));
+                }
+            }
+        }
+        ret
+    }
+
     /// Purges the entries no longer needed from the artifact pool
     fn purge_artifacts(
         &self,
         ecdsa_pool: &dyn EcdsaPool,
         block_reader: &dyn EcdsaBlockReader,
     ) -> EcdsaChangeSet {
-        if ECDSA_IMPROVED_LATENCY {
+        let mut ret = if ECDSA_IMPROVED_LATENCY {
             self.purge_artifacts_improved_latency(ecdsa_pool)
         } else {
             self.purge_artifacts_deprecated(ecdsa_pool, block_reader)
-        }
+        };
+        ret.append(&mut self.purge_schnorr_artifacts(ecdsa_pool, block_reader));
+        ret
     }
 
     fn purge_artifacts_deprecated(
@@ -406,6 +528,44 @@ impl EcdsaSignerImpl {
         ret
     }
 
+    fn purge_schnorr_artifacts(
+        &self,
+        ecdsa_pool: &dyn EcdsaPool,
+        block_reader: &dyn EcdsaBlockReader,
+    ) -> EcdsaChangeSet {
+        let in_progress = block_reader
+            .requested_schnorr_signatures()
+            .map(|(request_id, _)| request_id.clone())
+            .collect::<BTreeSet<_>>();
+
+        let current_height = block_reader.tip_height();
+        let should_purge = |share: &SchnorrSigShare| {
+            share.request_id.height <= current_height && !in_progress.contains(&share.request_id)
+        };
+
+        let mut ret = Vec::new();
+
+        // Unvalidated signature shares.
+        let mut action = ecdsa_pool
+            .unvalidated()
+            .schnorr_signature_shares()
+            .filter(|(_, share)| should_purge(share))
+            .map(|(id, _)| EcdsaChangeAction::RemoveUnvalidated(id))
+            .collect();
+        ret.append(&mut action);
+
+        // Validated signature shares.
+        let mut action = ecdsa_pool
+            .validated()
+            .schnorr_signature_shares()
+            .filter(|(_, share)| should_purge(share))
+            .map(|(id, _)| EcdsaChangeAction::RemoveValidated(id))
+            .collect();
+        ret.append(&mut action);
+
+        ret
+    }
+
     /// Load necessary transcripts for the inputs
     fn load_dependencies(
         &self,
@@ -461,6 +621,48 @@ impl EcdsaSignerImpl {
         )
     }
 
+    /// Helper to create the threshold Schnorr signature share
+    fn crypto_create_schnorr_signature_share(
+        &self,
+        ecdsa_pool: &dyn EcdsaPool,
+        transcript_loader: &dyn EcdsaTranscriptLoader,
+        request_id: &SchnorrRequestId,
+        sig_inputs: &ThresholdSchnorrSigInputs,
+    ) -> EcdsaChangeSet {
+        if let Some(changes) = load_transcripts(
+            ecdsa_pool,
+            transcript_loader,
+            &[
+                sig_inputs.presig_transcript().blinder_unmasked(),
+                sig_inputs.key_transcript(),
+            ],
+        ) {
+            return changes;
+        }
+
+        ThresholdSchnorrSigner::sign_share(&*self.crypto, sig_inputs).map_or_else(
+            |error| {
+                warn!(
+                    self.log,
+                    "Failed to create Schnorr share: request_id = {:?}, {:?}", request_id, error
+                );
+                self.metrics.sign_errors_inc("create_schnorr_sig_share");
+                Default::default()
+            },
+            |share| {
+                let sig_share = SchnorrSigShare {
+                    signer_id: self.node_id,
+                    request_id: request_id.clone(),
+                    share,
+                };
+                self.metrics.sign_metrics_inc("schnorr_sig_shares_sent");
+                vec![EcdsaChangeAction::AddToValidated(
+                    EcdsaMessage::SchnorrSigShare(sig_share),
+                )]
+            },
+        )
+    }
+
     /// Helper to verify the signature share
     fn crypto_verify_signature_share(
         &self,
@@ -507,6 +709,49 @@ impl EcdsaSignerImpl {
         }
     }
 
+    /// Helper to verify the threshold Schnorr signature share
+    fn crypto_verify_schnorr_signature_share(
+        &self,
+        id: EcdsaMessageId,
+        sig_inputs: &ThresholdSchnorrSigInputs,
+        share: SchnorrSigShare,
+    ) -> Option<EcdsaChangeAction> {
+        match ThresholdSchnorrSigVerifier::verify_sig_share(
+            &*self.crypto,
+            share.signer_id,
+            sig_inputs,
+            &share.share,
+        ) {
+            Err(error) if error.is_reproducible() => {
+                self.metrics
+                    .sign_errors_inc("verify_schnorr_sig_share_permanent");
+                Some(EcdsaChangeAction::HandleInvalid(
+                    id,
+                    format!(
+                        "Share validation(permanent error): {}, error = {:?}",
+                        share, error
+                    ),
+                ))
+            }
+            Err(error) => {
+                // Defer in case of transient errors
+                debug!(
+                    self.log,
+                    "Share validation(transient error): {}, error = {:?}", share, error
+                );
+                self.metrics
+                    .sign_errors_inc("verify_schnorr_sig_share_transient");
+                None
+            }
+            Ok(()) => {
+                self.metrics.sign_metrics_inc("schnorr_sig_shares_received");
+                Some(EcdsaChangeAction::MoveToValidated(
+                    EcdsaMessage::SchnorrSigShare(share),
+                ))
+            }
+        }
+    }
+
     /// Checks if the signer node has already issued a signature share for the
     /// request
     fn signer_has_issued_signature_share(
@@ -522,6 +767,21 @@ impl EcdsaSignerImpl {
             .any(|(_, share)| share.request_id == *request_id && share.signer_id == *signer_id)
     }
 
+    /// Checks if the signer node has already issued a threshold Schnorr
+    /// signature share for the request
+    fn signer_has_issued_schnorr_signature_share(
+        &self,
+        ecdsa_pool: &dyn EcdsaPool,
+        signer_id: &NodeId,
+        request_id: &SchnorrRequestId,
+    ) -> bool {
+        let prefix = schnorr_sig_share_prefix(request_id, signer_id);
+        ecdsa_pool
+            .validated()
+            .schnorr_signature_shares_by_prefix(prefix)
+            .any(|(_, share)| share.request_id == *request_id && share.signer_id == *signer_id)
+    }
+
     /// Checks if the signature share should be purged
     fn should_purge(
         &self,
@@ -574,6 +834,38 @@ impl EcdsaSignerImpl {
             }
         }
     }
+
+    /// Resolves the ThresholdSchnorrSigInputsRef -> ThresholdSchnorrSigInputs
+    fn resolve_schnorr_ref(
+        &self,
+        sig_inputs_ref: &ThresholdSchnorrSigInputsRef,
+        block_reader: &dyn EcdsaBlockReader,
+        reason: &str,
+    ) -> Option<ThresholdSchnorrSigInputs> {
+        let _timer = self
+            .metrics
+            .on_state_change_duration
+            .with_label_values(&["resolve_transcript_refs"])
+            .start_timer();
+        match sig_inputs_ref.translate(block_reader) {
+            Ok(sig_inputs) => {
+                self.metrics.sign_metrics_inc("resolve_transcript_refs");
+                Some(sig_inputs)
+            }
+            Err(error) => {
+                warn!(
+                    self.log,
+                    "Failed to resolve Schnorr sig input ref: reason = {}, \
+                     sig_inputs_ref = {:?}, error = {:?}",
+                    reason,
+                    sig_inputs_ref,
+                    error
+                );
+                self.metrics.sign_errors_inc("resolve_transcript_refs");
+                None
+            }
+        }
+    }
 }
 
 impl EcdsaSigner for EcdsaSignerImpl {
@@ -634,6 +926,13 @@ pub(crate) trait EcdsaSignatureBuilder {
         &self,
         context: &SignWithEcdsaContext,
     ) -> Option<ThresholdEcdsaCombinedSignature>;
+
+    /// Returns the specified threshold Schnorr signature if it can be
+    /// successfully built from the current sig shares in the ECDSA pool
+    fn get_completed_schnorr_signature(
+        &self,
+        request_id: &SchnorrRequestId,
+    ) -> Option<ThresholdSchnorrCombinedSignature>;
 }
 
 pub(crate) struct EcdsaSignatureBuilderImpl<'a> {
@@ -700,6 +999,41 @@ impl<'a> EcdsaSignatureBuilderImpl<'a> {
     }
 }
 
+impl<'a> EcdsaSignatureBuilderImpl<'a> {
+    fn crypto_combine_schnorr_signature_shares(
+        &self,
+        request_id: &SchnorrRequestId,
+        inputs: &ThresholdSchnorrSigInputs,
+        shares: &BTreeMap<NodeId, ThresholdSchnorrSigShare>,
+    ) -> Option<ThresholdSchnorrCombinedSignature> {
+        ThresholdSchnorrSigVerifier::combine_sig_shares(self.crypto, inputs, shares).map_or_else(
+            |error| {
+                match error {
+                    ThresholdSchnorrCombineSigSharesError::UnsatisfiedReconstructionThreshold {
+                        threshold: _,
+                        share_count: _,
+                    } => (),
+                    _ => {
+                        warn!(
+                            self.log,
+                            "Failed to combine Schnorr signature shares: request_id = {:?}, {:?}",
+                            request_id,
+                            error
+                        );
+                        self.metrics.payload_errors_inc("combine_schnorr_sig_share");
+                    }
+                };
+                Default::default()
+            },
+            |combined_signature| {
+                self.metrics
+                    .payload_metrics_inc("schnorr_signatures_completed", None);
+                Some(combined_signature)
+            },
+        )
+    }
+}
+
 impl<'a> EcdsaSignatureBuilder for EcdsaSignatureBuilderImpl<'a> {
     fn get_completed_signature(
         &self,
@@ -778,6 +1112,42 @@ impl<'a> EcdsaSignatureBuilder for EcdsaSignatureBuilderImpl<'a> {
             self.ecdsa_pool.stats(),
         )
     }
+
+    fn get_completed_schnorr_signature(
+        &self,
+        request_id: &SchnorrRequestId,
+    ) -> Option<ThresholdSchnorrCombinedSignature> {
+        // Find the sig inputs for the request and translate the refs.
+        let (request_id, sig_inputs_ref) = self
+            .block_reader
+            .requested_schnorr_signatures()
+            .find(|(cur_request_id, _)| **cur_request_id == *request_id)?;
+        let sig_inputs = match sig_inputs_ref.translate(self.block_reader) {
+            Ok(sig_inputs) => sig_inputs,
+            Err(error) => {
+                warn!(
+                    self.log,
+                    "get_completed_schnorr_signature(): translate failed: sig_inputs_ref = {:?}, error = {:?}",
+                    sig_inputs_ref,
+                    error
+                );
+                self.metrics
+                    .payload_errors_inc("schnorr_sig_inputs_translate");
+                return None;
+            }
+        };
+
+        // Collect the signature shares for the request.
+        let mut sig_shares = BTreeMap::new();
+        for (_, share) in self.ecdsa_pool.validated().schnorr_signature_shares() {
+            if share.request_id == *request_id {
+                sig_shares.insert(share.signer_id, share.share.clone());
+            }
+        }
+
+        // Combine the signatures.
+        self.crypto_combine_schnorr_signature_shares(request_id, &sig_inputs, &sig_shares)
+    }
 }
 
 /// Specifies how to handle a received share
@@ -15,12 +15,14 @@ use ic_crypto_test_utils_canister_threshold_sigs::{
 };
 use ic_crypto_test_utils_reproducible_rng::ReproducibleRng;
 use ic_crypto_tree_hash::{LabeledTree, MixedHashTree};
-use ic_ic00_types::EcdsaKeyId;
+use ic_ic00_types::{EcdsaKeyId, SchnorrAlgorithm, SchnorrKeyId};
 use ic_interfaces::ecdsa::{EcdsaChangeAction, EcdsaPool};
 use ic_interfaces_state_manager::{CertifiedStateSnapshot, Labeled};
 use ic_logger::ReplicaLogger;
 use ic_metrics::MetricsRegistry;
-use ic_replicated_state::metadata_state::subnet_call_context_manager::SignWithEcdsaContext;
+use ic_replicated_state::metadata_state::subnet_call_context_manager::{
+    SignWithEcdsaContext, SignWithSchnorrContext,
+};
 use ic_replicated_state::ReplicatedState;
 use ic_test_utilities::consensus::fake::*;
 use ic_test_utilities::state::ReplicatedStateBuilder;
@@ -34,9 +36,9 @@ use ic_types::consensus::ecdsa::{
     EcdsaKeyTranscript, EcdsaMessage, EcdsaOpening, EcdsaOpeningContent, EcdsaPayload,
     EcdsaReshareRequest, EcdsaSigShare, EcdsaUIDGenerator, IDkgTranscriptAttributes,
     IDkgTranscriptOperationRef, IDkgTranscriptParamsRef, KeyTranscriptCreation, MaskedTranscript,
-    PreSignatureQuadrupleRef, QuadrupleId, RequestId, ReshareOfMaskedParams,
-    ThresholdEcdsaSigInputsRef, TranscriptAttributes, TranscriptLookupError, TranscriptRef,
-    UnmaskedTranscript,
+    PreSignatureQuadrupleRef, QuadrupleId, RequestId, ReshareOfMaskedParams, SchnorrRequestId,
+    ThresholdEcdsaSigInputsRef, ThresholdSchnorrSigInputsRef, TranscriptAttributes,
+    TranscriptLookupError, TranscriptRef, UnmaskedTranscript,
 };
 use ic_types::crypto::canister_threshold_sig::idkg::{
     IDkgComplaint, IDkgDealing, IDkgDealingSupport, IDkgMaskedTranscriptOrigin, IDkgOpening,
@@ -45,7 +47,7 @@ use ic_types::crypto::canister_threshold_sig::idkg::{
 };
 use ic_types::crypto::canister_threshold_sig::{
     ExtendedDerivationPath, ThresholdEcdsaCombinedSignature, ThresholdEcdsaSigInputs,
-    ThresholdEcdsaSigShare,
+    ThresholdEcdsaSigShare, ThresholdSchnorrCombinedSignature,
 };
 use ic_types::crypto::AlgorithmId;
 use ic_types::messages::CallbackId;
@@ -69,6 +71,7 @@ pub(crate) fn empty_response() -> ic_types::messages::Response {
         // be refunded to the canister.
         refund: ic_types::Cycles::new(0),
         response_payload: ic_types::messages::Payload::Data(vec![]),
+        deadline: None,
     }
 }
 
@@ -96,6 +99,27 @@ pub fn fake_sign_with_ecdsa_context_with_batch_time(
     }
 }
 
+pub fn fake_schnorr_key_id(name: &str) -> SchnorrKeyId {
+    SchnorrKeyId {
+        algorithm: SchnorrAlgorithm::Bip340Secp256k1,
+        name: name.to_string(),
+    }
+}
+
+pub fn fake_sign_with_schnorr_context(
+    key_id: SchnorrKeyId,
+    pseudo_random_id: [u8; 32],
+) -> SignWithSchnorrContext {
+    SignWithSchnorrContext {
+        request: RequestBuilder::new().build(),
+        key_id,
+        message: vec![0; 32],
+        derivation_path: vec![],
+        pseudo_random_id,
+        batch_time: mock_time(),
+    }
+}
+
 pub fn fake_sign_with_ecdsa_context_with_quadruple(
     id: u8,
     key_id: EcdsaKeyId,
@@ -284,6 +308,7 @@ pub(crate) struct TestEcdsaBlockReader {
     source_subnet_xnet_transcripts: Vec<IDkgTranscriptParamsRef>,
     target_subnet_xnet_transcripts: Vec<IDkgTranscriptParamsRef>,
     requested_signatures: Vec<(RequestId, ThresholdEcdsaSigInputsRef)>,
+    requested_schnorr_signatures: Vec<(SchnorrRequestId, ThresholdSchnorrSigInputsRef)>,
     available_quadruples: BTreeMap<QuadrupleId, PreSignatureQuadrupleRef>,
     idkg_transcripts: BTreeMap<TranscriptRef, IDkgTranscript>,
     fail_to_resolve: bool,
@@ -426,6 +451,19 @@ impl EcdsaBlockReader for TestEcdsaBlockReader {
         self.available_quadruples.get(id)
     }
 
+    fn requested_schnorr_signatures(
+        &self,
+    ) -> Box<dyn Iterator<Item = (&SchnorrRequestId, &ThresholdSchnorrSigInputsRef)> + '_> {
+        Box::new(
+            // False positive `map_identity` warning.
+            // See: https://github.com/rust-lang/rust-clippy/pull/11792 (merged)
+            #[allow(clippy::map_identity)]
+            self.requested_schnorr_signatures
+                .iter()
+                .map(|(id, sig_inputs)| (id, sig_inputs)),
+        )
+    }
+
     fn source_subnet_xnet_transcripts(
         &self,
     ) -> Box<dyn Iterator<Item = &IDkgTranscriptParamsRef> + '_> {
@@ -572,12 +610,14 @@ impl EcdsaTranscriptBuilder for TestEcdsaTranscriptBuilder {
 
 pub(crate) struct TestEcdsaSignatureBuilder {
     pub(crate) signatures: BTreeMap<RequestId, ThresholdEcdsaCombinedSignature>,
+    pub(crate) schnorr_signatures: BTreeMap<SchnorrRequestId, ThresholdSchnorrCombinedSignature>,
 }
 
 impl TestEcdsaSignatureBuilder {
     pub(crate) fn new() -> Self {
         Self {
             signatures: BTreeMap::new(),
+            schnorr_signatures: BTreeMap::new(),
         }
     }
 }
@@ -597,6 +637,13 @@ impl EcdsaSignatureBuilder for TestEcdsaSignatureBuilder {
         let request_id = get_context_request_id(context)?;
         self.signatures.get(&request_id).cloned()
     }
+
+    fn get_completed_schnorr_signature(
+        &self,
+        request_id: &SchnorrRequestId,
+    ) -> Option<ThresholdSchnorrCombinedSignature> {
+        self.schnorr_signatures.get(request_id).cloned()
+    }
 }
 
 #[derive(Clone)]
@@ -1425,6 +1472,11 @@ pub(crate) fn empty_ecdsa_payload(subnet_id: SubnetId) -> EcdsaPayload {
             next_in_creation: KeyTranscriptCreation::Begin,
             key_id: fake_ecdsa_key_id(),
         },
+        schnorr_key_transcripts: BTreeMap::new(),
+        available_schnorr_pre_signatures: BTreeMap::new(),
+        schnorr_pre_signatures_in_creation: BTreeMap::new(),
+        ongoing_schnorr_signatures: BTreeMap::new(),
+        schnorr_signature_agreements: BTreeMap::new(),
     }
 }
 
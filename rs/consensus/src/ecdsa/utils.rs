@@ -4,30 +4,34 @@ use crate::consensus::metrics::EcdsaPayloadMetrics;
 use crate::ecdsa::complaints::{EcdsaTranscriptLoader, TranscriptLoadStatus};
 use ic_artifact_pool::consensus_pool::build_consensus_block_chain;
 use ic_consensus_utils::pool_reader::PoolReader;
-use ic_crypto::get_tecdsa_master_public_key;
-use ic_ic00_types::EcdsaKeyId;
+use ic_crypto::{get_tecdsa_master_public_key, get_tschnorr_master_public_key};
+use ic_ic00_types::{EcdsaKeyId, SchnorrKeyId};
 use ic_interfaces::consensus_pool::ConsensusBlockChain;
 use ic_interfaces::ecdsa::{EcdsaChangeAction, EcdsaChangeSet, EcdsaPool};
 use ic_interfaces_registry::RegistryClient;
 use ic_logger::{warn, ReplicaLogger};
 use ic_protobuf::registry::subnet::v1 as pb;
 use ic_registry_client_helpers::ecdsa_keys::EcdsaKeysRegistry;
+use ic_registry_client_helpers::schnorr_keys::SchnorrKeysRegistry;
 use ic_registry_client_helpers::subnet::SubnetRegistry;
-use ic_registry_subnet_features::EcdsaConfig;
+use ic_registry_subnet_features::{EcdsaConfig, SchnorrConfig};
 use ic_replicated_state::metadata_state::subnet_call_context_manager::SignWithEcdsaContext;
 use ic_types::consensus::ecdsa::{PreSignatureQuadrupleRef, QuadrupleId};
 use ic_types::consensus::Block;
 use ic_types::consensus::{
     ecdsa::{
-        EcdsaBlockReader, EcdsaMessage, IDkgTranscriptParamsRef, RequestId,
-        ThresholdEcdsaSigInputsRef, TranscriptLookupError, TranscriptRef,
+        EcdsaBlockReader, EcdsaMessage, IDkgTranscriptParamsRef, RequestId, SchnorrRequestId,
+        ThresholdEcdsaSigInputsRef, ThresholdSchnorrSigInputsRef, TranscriptLookupError,
+        TranscriptRef,
     },
     HasHeight,
 };
 use ic_types::crypto::canister_threshold_sig::idkg::{
     IDkgTranscript, IDkgTranscriptOperation, InitialIDkgDealings,
 };
-use ic_types::crypto::canister_threshold_sig::{ExtendedDerivationPath, MasterEcdsaPublicKey};
+use ic_types::crypto::canister_threshold_sig::{
+    ExtendedDerivationPath, MasterEcdsaPublicKey, MasterSchnorrPublicKey,
+};
 use ic_types::registry::RegistryClientError;
 use ic_types::{Height, RegistryVersion, SubnetId};
 use phantom_newtype::Id;
@@ -97,6 +101,19 @@ impl EcdsaBlockReader for EcdsaBlockReaderImpl {
             .and_then(|ecdsa_payload| ecdsa_payload.available_quadruples.get(id))
     }
 
+    fn requested_schnorr_signatures(
+        &self,
+    ) -> Box<dyn Iterator<Item = (&SchnorrRequestId, &ThresholdSchnorrSigInputsRef)> + '_> {
+        self.chain
+            .tip()
+            .payload
+            .as_ref()
+            .as_ecdsa()
+            .map_or(Box::new(std::iter::empty()), |payload| {
+                Box::new(payload.ongoing_schnorr_signatures.iter())
+            })
+    }
+
     fn active_transcripts(&self) -> BTreeSet<TranscriptRef> {
         self.chain
             .tip()
@@ -380,6 +397,31 @@ pub(crate) fn get_ecdsa_config_if_enabled(
     Ok(None)
 }
 
+/// Return [`SchnorrConfig`] if threshold Schnorr is enabled for the given subnet.
+pub(crate) fn get_schnorr_config_if_enabled(
+    subnet_id: SubnetId,
+    registry_version: RegistryVersion,
+    registry_client: &dyn RegistryClient,
+    log: &ReplicaLogger,
+) -> Result<Option<SchnorrConfig>, RegistryClientError> {
+    let Some(schnorr_config) = registry_client.get_schnorr_config(subnet_id, registry_version)?
+    else {
+        return Ok(None);
+    };
+    if schnorr_config.pre_signatures_to_create_in_advance == 0 {
+        warn!(
+            log,
+            "Wrong schnorr_config: pre_signatures_to_create_in_advance is zero"
+        );
+        return Ok(None);
+    }
+    if schnorr_config.key_ids.is_empty() {
+        // This means it is not enabled
+        return Ok(None);
+    }
+    Ok(Some(schnorr_config))
+}
+
 /// Return ids of ECDSA keys of the given [EcdsaConfig] for which
 /// signing is enabled on the given subnet.
 pub(crate) fn get_enabled_signing_keys(
@@ -402,6 +444,28 @@ pub(crate) fn get_enabled_signing_keys(
         .collect())
 }
 
+/// Return ids of threshold Schnorr keys of the given [SchnorrConfig] for
+/// which signing is enabled on the given subnet.
+pub(crate) fn get_enabled_schnorr_signing_keys(
+    subnet_id: SubnetId,
+    registry_version: RegistryVersion,
+    registry_client: &dyn RegistryClient,
+    schnorr_config: &SchnorrConfig,
+) -> Result<BTreeSet<SchnorrKeyId>, RegistryClientError> {
+    let signing_subnets = registry_client
+        .get_schnorr_signing_subnets(registry_version)?
+        .unwrap_or_default();
+    Ok(schnorr_config
+        .key_ids
+        .iter()
+        .filter(|&key_id| match signing_subnets.get(key_id) {
+            Some(subnets) => subnets.contains(&subnet_id),
+            None => false,
+        })
+        .cloned()
+        .collect())
+}
+
 /// Return the set of quadruple IDs to be delivered in the batch of this block.
 /// We deliver IDs of all available quadruples that were created using the current key transcript.
 pub(crate) fn get_quadruple_ids_to_deliver(
@@ -505,6 +569,69 @@ fn get_ecdsa_subnet_public_key_(
     }
 }
 
+/// This function returns the threshold Schnorr subnet public keys to be added
+/// to the batch, if required. It follows the same rules as
+/// [`get_ecdsa_subnet_public_key`], but for every Schnorr key transcript.
+pub(crate) fn get_schnorr_subnet_public_keys(
+    block: &Block,
+    pool: &PoolReader<'_>,
+    log: &ReplicaLogger,
+) -> Result<BTreeMap<SchnorrKeyId, MasterSchnorrPublicKey>, String> {
+    let Some(ecdsa_payload) = block.payload.as_ref().as_ecdsa() else {
+        return Ok(BTreeMap::new());
+    };
+
+    let transcript_refs: Vec<_> = ecdsa_payload
+        .schnorr_key_transcripts
+        .iter()
+        .filter_map(|(key_id, key_transcript)| {
+            key_transcript
+                .current
+                .as_ref()
+                .map(|unmasked| (key_id, *unmasked.as_ref()))
+        })
+        .collect();
+    if transcript_refs.is_empty() {
+        return Ok(BTreeMap::new());
+    }
+
+    let Some(summary) = pool.dkg_summary_block_for_finalized_height(block.height) else {
+        return Err(format!(
+            "Failed to find dkg summary block for height {}",
+            block.height
+        ));
+    };
+    let chain = build_consensus_block_chain(pool.pool(), &summary, block);
+    let block_reader = EcdsaBlockReaderImpl::new(chain);
+
+    let mut public_keys = BTreeMap::new();
+    for (key_id, transcript_ref) in transcript_refs {
+        match block_reader.transcript(&transcript_ref) {
+            Ok(transcript) => match get_tschnorr_master_public_key(&transcript) {
+                Ok(public_key) => {
+                    public_keys.insert(key_id.clone(), public_key);
+                }
+                Err(err) => {
+                    warn!(
+                        log,
+                        "Failed to retrieve Schnorr subnet public key for key {}: {:?}",
+                        key_id,
+                        err
+                    );
+                }
+            },
+            Err(err) => {
+                warn!(
+                    log,
+                    "Failed to translate transcript ref {:?}: {:?}", transcript_ref, err
+                );
+            }
+        }
+    }
+
+    Ok(public_keys)
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
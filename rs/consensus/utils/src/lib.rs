@@ -783,6 +783,11 @@ mod tests {
                 next_in_creation: KeyTranscriptCreation::Begin,
                 key_id: EcdsaKeyId::from_str("Secp256k1:some_key").unwrap(),
             },
+            schnorr_key_transcripts: BTreeMap::new(),
+            available_schnorr_pre_signatures: BTreeMap::new(),
+            schnorr_pre_signatures_in_creation: BTreeMap::new(),
+            ongoing_schnorr_signatures: BTreeMap::new(),
+            schnorr_signature_agreements: BTreeMap::new(),
         }
     }
 
@@ -12,7 +12,7 @@ use k256::{
     },
     Secp256k1,
 };
-use rand::{CryptoRng, RngCore};
+use rand::{CryptoRng, Rng, RngCore};
 use zeroize::ZeroizeOnDrop;
 
 /// An error indicating that decoding a key failed
@@ -324,6 +324,26 @@ impl PrivateKey {
         Some(sig.to_bytes().into())
     }
 
+    /// Sign a message using BIP340 Schnorr
+    ///
+    /// BIP340 signatures are defined only for 32 byte messages. The
+    /// provided RNG is used to produce the auxiliary randomness which
+    /// is mixed into the nonce generation.
+    pub fn sign_message_with_bip340<R: RngCore + CryptoRng>(
+        &self,
+        message: &[u8; 32],
+        rng: &mut R,
+    ) -> [u8; 64] {
+        let bip340 = k256::schnorr::SigningKey::from_bytes(&self.key.to_bytes())
+            .expect("Failed to convert secret key to BIP340 signing key");
+
+        let aux_rand = rng.gen::<[u8; 32]>();
+        let sig = bip340
+            .sign_prehash_with_aux_rand(message, &aux_rand)
+            .expect("Failed to generate BIP340 signature");
+        sig.to_bytes()
+    }
+
     /// Return the public key corresponding to this private key
     pub fn public_key(&self) -> PublicKey {
         let key = self.key.verifying_key();
@@ -441,6 +461,28 @@ impl PublicKey {
         }
     }
 
+    /// Verify a BIP340 Schnorr (message,signature) pair
+    ///
+    /// BIP340 public keys are identified by their x coordinate only, so
+    /// the signature is checked against the even-y point with the same x
+    /// coordinate as this key.
+    pub fn verify_bip340_signature(&self, message: &[u8], signature: &[u8]) -> bool {
+        use k256::schnorr::signature::hazmat::PrehashVerifier;
+
+        let signature = match k256::schnorr::Signature::try_from(signature) {
+            Ok(sig) => sig,
+            Err(_) => return false,
+        };
+
+        let x_only = &self.serialize_sec1(true)[1..];
+        let key = match k256::schnorr::VerifyingKey::from_bytes(x_only) {
+            Ok(key) => key,
+            Err(_) => return false,
+        };
+
+        key.verify_prehash(message, &signature).is_ok()
+    }
+
     /// Verify a (message digest,signature) pair
     pub fn verify_signature_prehashed(&self, digest: &[u8], signature: &[u8]) -> bool {
         use k256::ecdsa::signature::hazmat::PrehashVerifier;
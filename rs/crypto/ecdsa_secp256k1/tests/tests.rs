@@ -337,3 +337,28 @@ mod try_recovery_from_digest {
         assert!(!recid.is_x_reduced());
     }
 }
+
+#[test]
+fn should_accept_bip340_signatures_that_we_generate() {
+    use rand::Rng;
+
+    let rng = &mut reproducible_rng();
+
+    for _ in 0..100 {
+        let sk = PrivateKey::generate_using_rng(rng);
+        let pk = sk.public_key();
+
+        let msg = rng.gen::<[u8; 32]>();
+        let sig = sk.sign_message_with_bip340(&msg, rng);
+        assert_eq!(sig.len(), 64);
+        assert!(pk.verify_bip340_signature(&msg, &sig));
+
+        let mut bad_msg = msg;
+        bad_msg[0] ^= 1;
+        assert!(!pk.verify_bip340_signature(&bad_msg, &sig));
+
+        // An ECDSA signature is not a valid BIP340 signature
+        let ecdsa_sig = sk.sign_digest(&msg).unwrap();
+        assert!(!pk.verify_bip340_signature(&msg, &ecdsa_sig));
+    }
+}
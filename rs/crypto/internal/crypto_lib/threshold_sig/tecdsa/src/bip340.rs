@@ -0,0 +1,363 @@
+use crate::*;
+use ic_crypto_sha2::Sha256;
+
+/// The BIP340 tagged hash `SHA256(SHA256(tag) || SHA256(tag) || input)`
+fn bip340_tagged_hash(tag: &[u8], input: &[&[u8]]) -> [u8; 32] {
+    let tag_hash = Sha256::hash(tag);
+
+    let mut hash = Sha256::new();
+    hash.write(&tag_hash);
+    hash.write(&tag_hash);
+    for i in input {
+        hash.write(i);
+    }
+    hash.finish()
+}
+
+/// Returns the BIP340 encoding of a point, which is its affine x coordinate
+fn bip340_x_only(pt: &EccPoint) -> ThresholdEcdsaResult<Vec<u8>> {
+    Ok(pt.affine_x()?.as_bytes())
+}
+
+/// Computes the BIP340 challenge `H_challenge(R || P || m)` reduced modulo
+/// the group order
+fn bip340_challenge(
+    r: &EccPoint,
+    public_key: &EccPoint,
+    message: &[u8],
+) -> ThresholdEcdsaResult<EccScalar> {
+    let e = bip340_tagged_hash(
+        b"BIP0340/challenge",
+        &[&bip340_x_only(r)?, &bip340_x_only(public_key)?, message],
+    );
+    EccScalar::from_bytes_wide(public_key.curve_type(), &e)
+}
+
+/// BIP340 only uses points with even y coordinate. Returns the point with
+/// even y and if it was necessary to negate the input to obtain it.
+fn fix_to_even_y(pt: &EccPoint) -> ThresholdEcdsaResult<(EccPoint, bool)> {
+    if pt.is_y_even()? {
+        Ok((pt.clone(), false))
+    } else {
+        Ok((pt.negate(), true))
+    }
+}
+
+/// The values shared by the signing, share verification, combination and
+/// verification steps of a threshold BIP340 signature
+struct Bip340Rerandomization {
+    /// The derived public key, normalized to have an even y coordinate
+    public_key: EccPoint,
+    /// If the derived key was negated during normalization
+    negate_key: bool,
+    key_tweak: EccScalar,
+    /// The rerandomized presignature, normalized to have an even y coordinate
+    presig: EccPoint,
+    /// If the rerandomized presignature was negated during normalization
+    negate_presig: bool,
+    randomizer: EccScalar,
+    /// The BIP340 challenge
+    challenge: EccScalar,
+}
+
+impl Bip340Rerandomization {
+    fn compute(
+        message: &[u8],
+        randomness: &Randomness,
+        derivation_path: &DerivationPath,
+        key_transcript: &IDkgTranscriptInternal,
+        presig_transcript: &IDkgTranscriptInternal,
+    ) -> ThresholdEcdsaResult<Self> {
+        let pre_sig = match &presig_transcript.combined_commitment {
+            // random unmasked case
+            CombinedCommitment::BySummation(PolynomialCommitment::Simple(c)) => c.constant_term(),
+            _ => return Err(ThresholdEcdsaError::UnexpectedCommitmentType),
+        };
+
+        let master_public_key = key_transcript.constant_term();
+
+        if pre_sig.curve_type() != EccCurveType::K256
+            || master_public_key.curve_type() != EccCurveType::K256
+        {
+            return Err(ThresholdEcdsaError::CurveMismatch);
+        }
+
+        let (key_tweak, _chain_key) = derivation_path.derive_tweak(&master_public_key)?;
+
+        let (public_key, negate_key) =
+            fix_to_even_y(&master_public_key.add_points(&EccPoint::mul_by_g(&key_tweak))?)?;
+
+        let mut ro = ro::RandomOracle::new("ic-crypto-tschnorr-bip340-rerandomize-presig");
+        ro.add_bytestring("randomness", &randomness.get())?;
+        ro.add_bytestring("message", message)?;
+        ro.add_point("pre_sig", &pre_sig)?;
+        ro.add_scalar("key_tweak", &key_tweak)?;
+        let randomizer = ro.output_scalar(EccCurveType::K256)?;
+
+        let (presig, negate_presig) =
+            fix_to_even_y(&pre_sig.add_points(&EccPoint::mul_by_g(&randomizer))?)?;
+
+        let challenge = bip340_challenge(&presig, &public_key, message)?;
+
+        Ok(Self {
+            public_key,
+            negate_key,
+            key_tweak,
+            presig,
+            negate_presig,
+            randomizer,
+            challenge,
+        })
+    }
+}
+
+/// A share of a threshold BIP340 signature
+///
+/// The share of node `i` is `s_i = r_i + e*x_i` where `r_i` and `x_i` are
+/// the node's shares of the (rerandomized) presignature and of the derived
+/// key, each negated if required to obtain points with even y, and `e` is
+/// the BIP340 challenge.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdBip340SignatureShareInternal {
+    s: EccScalar,
+}
+
+impl ThresholdBip340SignatureShareInternal {
+    pub(crate) fn new(
+        derivation_path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+        key_transcript: &IDkgTranscriptInternal,
+        key_opening: &CommitmentOpening,
+        presig_transcript: &IDkgTranscriptInternal,
+        presig_opening: &CommitmentOpening,
+    ) -> ThresholdEcdsaResult<Self> {
+        let rerandomized = Bip340Rerandomization::compute(
+            message,
+            &randomness,
+            derivation_path,
+            key_transcript,
+            presig_transcript,
+        )?;
+
+        let key_opening = match key_opening {
+            CommitmentOpening::Simple(s) => s,
+            _ => return Err(ThresholdEcdsaError::UnexpectedCommitmentType),
+        };
+
+        let presig_opening = match presig_opening {
+            CommitmentOpening::Simple(s) => s,
+            _ => return Err(ThresholdEcdsaError::UnexpectedCommitmentType),
+        };
+
+        let tweaked_x = key_opening.add(&rerandomized.key_tweak)?;
+        let tweaked_x = if rerandomized.negate_key {
+            tweaked_x.negate()
+        } else {
+            tweaked_x
+        };
+
+        let rerandomized_r = presig_opening.add(&rerandomized.randomizer)?;
+        let rerandomized_r = if rerandomized.negate_presig {
+            rerandomized_r.negate()
+        } else {
+            rerandomized_r
+        };
+
+        let s = rerandomized_r.add(&tweaked_x.mul(&rerandomized.challenge)?)?;
+
+        Ok(Self { s })
+    }
+
+    /// Verify a BIP340 signature share
+    ///
+    /// Since the presignature and the key are shared by unmasked transcripts,
+    /// the share can be checked directly against the commitments evaluated
+    /// at the index of the signer.
+    pub fn verify(
+        &self,
+        derivation_path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+        signer_index: NodeIndex,
+        key_transcript: &IDkgTranscriptInternal,
+        presig_transcript: &IDkgTranscriptInternal,
+    ) -> ThresholdEcdsaResult<()> {
+        let rerandomized = Bip340Rerandomization::compute(
+            message,
+            &randomness,
+            derivation_path,
+            key_transcript,
+            presig_transcript,
+        )?;
+
+        let node_pk = key_transcript
+            .evaluate_at(signer_index)?
+            .add_points(&EccPoint::mul_by_g(&rerandomized.key_tweak))?;
+        let node_pk = if rerandomized.negate_key {
+            node_pk.negate()
+        } else {
+            node_pk
+        };
+
+        let node_r = presig_transcript
+            .evaluate_at(signer_index)?
+            .add_points(&EccPoint::mul_by_g(&rerandomized.randomizer))?;
+        let node_r = if rerandomized.negate_presig {
+            node_r.negate()
+        } else {
+            node_r
+        };
+
+        let lhs = EccPoint::mul_by_g(&self.s);
+        let rhs = node_r.add_points(&node_pk.scalar_mul(&rerandomized.challenge)?)?;
+
+        if lhs != rhs {
+            return Err(ThresholdEcdsaError::InvalidSignatureShare);
+        }
+
+        Ok(())
+    }
+
+    pub fn serialize(&self) -> ThresholdEcdsaSerializationResult<Vec<u8>> {
+        serde_cbor::to_vec(self).map_err(|e| ThresholdEcdsaSerializationError(format!("{}", e)))
+    }
+
+    pub fn deserialize(raw: &[u8]) -> ThresholdEcdsaSerializationResult<Self> {
+        serde_cbor::from_slice::<Self>(raw)
+            .map_err(|e| ThresholdEcdsaSerializationError(format!("{}", e)))
+    }
+}
+
+/// A threshold BIP340 signature
+///
+/// `r` always has an even y coordinate, so the signature is fully
+/// described by the x coordinate of `r` and `s`, as specified in BIP340.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdBip340CombinedSignatureInternal {
+    r: EccPoint,
+    s: EccScalar,
+}
+
+impl ThresholdBip340CombinedSignatureInternal {
+    /// Serialize in the 64 byte format specified by BIP340
+    pub fn serialize(&self) -> ThresholdEcdsaSerializationResult<Vec<u8>> {
+        let r_bytes = bip340_x_only(&self.r)
+            .map_err(|e| ThresholdEcdsaSerializationError(format!("Invalid r: {:?}", e)))?;
+        let s_bytes = self.s.serialize();
+
+        let mut sig = Vec::with_capacity(r_bytes.len() + s_bytes.len());
+        sig.extend_from_slice(&r_bytes);
+        sig.extend_from_slice(&s_bytes);
+        Ok(sig)
+    }
+
+    /// Deserialize from the 64 byte format specified by BIP340
+    pub fn deserialize(bytes: &[u8]) -> ThresholdEcdsaSerializationResult<Self> {
+        const K256: EccCurveType = EccCurveType::K256;
+
+        if bytes.len() != K256.field_bytes() + K256.scalar_bytes() {
+            return Err(ThresholdEcdsaSerializationError(
+                "Bad signature length".to_string(),
+            ));
+        }
+
+        let (r_bytes, s_bytes) = bytes.split_at(K256.field_bytes());
+
+        // Lift x to the point with even y, i.e., parse it as a SEC1 compressed point
+        let mut compressed_r = Vec::with_capacity(K256.point_bytes());
+        compressed_r.push(0x02);
+        compressed_r.extend_from_slice(r_bytes);
+
+        let r = EccPoint::deserialize(K256, &compressed_r)
+            .map_err(|e| ThresholdEcdsaSerializationError(format!("Invalid r: {:?}", e)))?;
+
+        let s = EccScalar::deserialize(K256, s_bytes)
+            .map_err(|e| ThresholdEcdsaSerializationError(format!("Invalid s: {:?}", e)))?;
+
+        Ok(Self { r, s })
+    }
+
+    pub(crate) fn new(
+        derivation_path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+        key_transcript: &IDkgTranscriptInternal,
+        presig_transcript: &IDkgTranscriptInternal,
+        reconstruction_threshold: NumberOfNodes,
+        sig_shares: &BTreeMap<NodeIndex, ThresholdBip340SignatureShareInternal>,
+    ) -> ThresholdEcdsaResult<Self> {
+        let reconstruction_threshold = reconstruction_threshold.get() as usize;
+        if sig_shares.len() < reconstruction_threshold {
+            return Err(ThresholdEcdsaError::InsufficientDealings);
+        }
+
+        let rerandomized = Bip340Rerandomization::compute(
+            message,
+            &randomness,
+            derivation_path,
+            key_transcript,
+            presig_transcript,
+        )?;
+
+        let mut x_values = Vec::with_capacity(reconstruction_threshold);
+        let mut samples = Vec::with_capacity(reconstruction_threshold);
+
+        for (index, sig_share) in sig_shares.iter().take(reconstruction_threshold) {
+            x_values.push(*index);
+            samples.push(sig_share.s.clone());
+        }
+
+        let coefficients = LagrangeCoefficients::at_zero(EccCurveType::K256, &x_values)?;
+        let s = coefficients.interpolate_scalar(&samples)?;
+
+        Ok(Self {
+            r: rerandomized.presig,
+            s,
+        })
+    }
+
+    /// Verify a threshold BIP340 signature
+    ///
+    /// In addition to the BIP340 verification equation, this checks that
+    /// the signature was generated using the given presignature transcript
+    /// and randomness.
+    pub fn verify(
+        &self,
+        derivation_path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+        presig_transcript: &IDkgTranscriptInternal,
+        key_transcript: &IDkgTranscriptInternal,
+    ) -> ThresholdEcdsaResult<()> {
+        if self.s.is_zero() {
+            return Err(ThresholdEcdsaError::InvalidSignature);
+        }
+
+        let rerandomized = Bip340Rerandomization::compute(
+            message,
+            &randomness,
+            derivation_path,
+            key_transcript,
+            presig_transcript,
+        )?;
+
+        if self.r != rerandomized.presig {
+            return Err(ThresholdEcdsaError::InvalidSignature);
+        }
+
+        // s*G == R + e*P
+        let lhs = EccPoint::mul_by_g(&self.s);
+        let rhs = self.r.add_points(
+            &rerandomized
+                .public_key
+                .scalar_mul(&rerandomized.challenge)?,
+        )?;
+
+        if lhs != rhs {
+            return Err(ThresholdEcdsaError::InvalidSignature);
+        }
+
+        Ok(())
+    }
+}
@@ -101,6 +101,7 @@ impl EccCurveType {
         match alg_id {
             AlgorithmId::ThresholdEcdsaSecp256k1 => Some(EccCurveType::K256),
             AlgorithmId::ThresholdEcdsaSecp256r1 => Some(EccCurveType::P256),
+            AlgorithmId::ThresholdSchnorrBip340 => Some(EccCurveType::K256),
             _ => None,
         }
     }
@@ -69,6 +69,15 @@
 //! * Generation and verification of signature shares
 //! * Generation and verification of combined signatures
 //!
+//! ## Protocol: Threshold BIP340 Signature Generation and Verification
+//!
+//! File: `bip340.rs`
+//!
+//! Schnorr signatures in the style of BIP340 over secp256k1, generated
+//! from an unmasked key transcript and an unmasked random presignature
+//! transcript. The presignature is rerandomized using a random oracle,
+//! similar to the presignature used for ECDSA.
+//!
 //! ## Protocol: Multi-encryption gadget (MEGa)
 //!
 //! File: `mega.rs`
@@ -251,6 +260,7 @@ pub struct ThresholdEcdsaSerializationError(pub String);
 pub type ThresholdEcdsaSerializationResult<T> =
     std::result::Result<T, ThresholdEcdsaSerializationError>;
 
+mod bip340;
 mod complaints;
 mod dealings;
 mod fe;
@@ -265,6 +275,9 @@ pub mod test_utils;
 mod transcript;
 pub mod zk;
 
+pub use crate::bip340::{
+    ThresholdBip340CombinedSignatureInternal, ThresholdBip340SignatureShareInternal,
+};
 pub use crate::complaints::IDkgComplaintInternal;
 pub use crate::dealings::*;
 pub use crate::fe::*;
@@ -656,7 +669,12 @@ impl From<ThresholdEcdsaError> for ThresholdEcdsaGenerateSigShareInternalError {
 
 // Returns None if the AlgorithmId does not map to threshold ECDSA
 fn signature_parameters(algorithm_id: AlgorithmId) -> Option<(EccCurveType, usize)> {
-    EccCurveType::from_algorithm(algorithm_id).map(|curve| (curve, curve.scalar_bytes()))
+    match algorithm_id {
+        AlgorithmId::ThresholdEcdsaSecp256k1 | AlgorithmId::ThresholdEcdsaSecp256r1 => {
+            EccCurveType::from_algorithm(algorithm_id).map(|curve| (curve, curve.scalar_bytes()))
+        }
+        _ => None,
+    }
 }
 
 /// Create a new threshold ECDSA signature share
@@ -811,7 +829,7 @@ pub fn combine_sig_shares(
     sig_shares: &BTreeMap<NodeIndex, ThresholdEcdsaSigShareInternal>,
     algorithm_id: AlgorithmId,
 ) -> Result<ThresholdEcdsaCombinedSigInternal, ThresholdEcdsaCombineSigSharesInternalError> {
-    let curve = EccCurveType::from_algorithm(algorithm_id)
+    let (curve, _hash_len) = signature_parameters(algorithm_id)
         .ok_or(ThresholdEcdsaCombineSigSharesInternalError::UnsupportedAlgorithm)?;
 
     sign::ThresholdEcdsaCombinedSigInternal::new(
@@ -929,6 +947,219 @@ pub fn derive_public_key(
     )?)
 }
 
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ThresholdBip340GenerateSigShareInternalError {
+    InvalidArguments(String),
+    InconsistentCommitments,
+    InternalError(String),
+}
+
+impl From<ThresholdEcdsaError> for ThresholdBip340GenerateSigShareInternalError {
+    fn from(e: ThresholdEcdsaError) -> Self {
+        match e {
+            ThresholdEcdsaError::CurveMismatch => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InvalidCommitment => Self::InconsistentCommitments,
+            ThresholdEcdsaError::UnexpectedCommitmentType => Self::InconsistentCommitments,
+            x => Self::InternalError(format!("{:?}", x)),
+        }
+    }
+}
+
+fn check_bip340_algorithm(algorithm_id: AlgorithmId) -> Result<(), String> {
+    match algorithm_id {
+        AlgorithmId::ThresholdSchnorrBip340 => Ok(()),
+        _ => Err(format!("unsupported algorithm: {algorithm_id:?}")),
+    }
+}
+
+/// Create a new threshold BIP340 signature share
+///
+/// The nonce should be random and shared by all nodes, for instance
+/// by deriving a value from the random tape.
+///
+/// The presig_transcript is the transcript of the random unmasked
+/// presignature, and key_opening and presig_opening are our openings
+/// of the commitments in the key and presignature transcripts.
+///
+/// Unlike for ECDSA, the message is not hashed by the caller, but
+/// included as is in the BIP340 challenge.
+#[allow(clippy::too_many_arguments)]
+pub fn sign_bip340_share(
+    derivation_path: &DerivationPath,
+    message: &[u8],
+    nonce: Randomness,
+    key_transcript: &IDkgTranscriptInternal,
+    key_opening: &CommitmentOpening,
+    presig_transcript: &IDkgTranscriptInternal,
+    presig_opening: &CommitmentOpening,
+    algorithm_id: AlgorithmId,
+) -> Result<ThresholdBip340SignatureShareInternal, ThresholdBip340GenerateSigShareInternalError> {
+    check_bip340_algorithm(algorithm_id)
+        .map_err(ThresholdBip340GenerateSigShareInternalError::InvalidArguments)?;
+
+    ThresholdBip340SignatureShareInternal::new(
+        derivation_path,
+        message,
+        nonce,
+        key_transcript,
+        key_opening,
+        presig_transcript,
+        presig_opening,
+    )
+    .map_err(|e| e.into())
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ThresholdBip340VerifySigShareInternalError {
+    InvalidArguments(String),
+    InconsistentCommitments,
+    InvalidSignatureShare,
+    InternalError(String),
+}
+
+impl From<ThresholdEcdsaError> for ThresholdBip340VerifySigShareInternalError {
+    fn from(e: ThresholdEcdsaError) -> Self {
+        match e {
+            ThresholdEcdsaError::CurveMismatch => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InvalidCommitment => Self::InconsistentCommitments,
+            ThresholdEcdsaError::UnexpectedCommitmentType => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InvalidSignatureShare => Self::InvalidSignatureShare,
+            x => Self::InternalError(format!("{:?}", x)),
+        }
+    }
+}
+
+/// Verify a threshold BIP340 signature share
+///
+/// The values provided must be consistent with when the signature share
+/// was created
+#[allow(clippy::too_many_arguments)]
+pub fn verify_bip340_signature_share(
+    sig_share: &ThresholdBip340SignatureShareInternal,
+    derivation_path: &DerivationPath,
+    message: &[u8],
+    randomness: Randomness,
+    signer_index: NodeIndex,
+    key_transcript: &IDkgTranscriptInternal,
+    presig_transcript: &IDkgTranscriptInternal,
+    algorithm_id: AlgorithmId,
+) -> Result<(), ThresholdBip340VerifySigShareInternalError> {
+    check_bip340_algorithm(algorithm_id)
+        .map_err(ThresholdBip340VerifySigShareInternalError::InvalidArguments)?;
+
+    sig_share
+        .verify(
+            derivation_path,
+            message,
+            randomness,
+            signer_index,
+            key_transcript,
+            presig_transcript,
+        )
+        .map_err(|e| e.into())
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ThresholdBip340CombineSigSharesInternalError {
+    UnsupportedAlgorithm,
+    InconsistentCommitments,
+    InsufficientShares,
+    InternalError(String),
+}
+
+impl From<ThresholdEcdsaError> for ThresholdBip340CombineSigSharesInternalError {
+    fn from(e: ThresholdEcdsaError) -> Self {
+        match e {
+            ThresholdEcdsaError::CurveMismatch => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InvalidCommitment => Self::InconsistentCommitments,
+            ThresholdEcdsaError::UnexpectedCommitmentType => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InsufficientDealings => Self::InsufficientShares,
+            x => Self::InternalError(format!("{:?}", x)),
+        }
+    }
+}
+
+/// Combine sufficient signature shares into a BIP340 signature
+///
+/// The signature shares must be verified prior to use, and there must
+/// be at least reconstruction_threshold many of them.
+#[allow(clippy::too_many_arguments)]
+pub fn combine_bip340_signature_shares(
+    derivation_path: &DerivationPath,
+    message: &[u8],
+    randomness: Randomness,
+    key_transcript: &IDkgTranscriptInternal,
+    presig_transcript: &IDkgTranscriptInternal,
+    reconstruction_threshold: NumberOfNodes,
+    sig_shares: &BTreeMap<NodeIndex, ThresholdBip340SignatureShareInternal>,
+    algorithm_id: AlgorithmId,
+) -> Result<ThresholdBip340CombinedSignatureInternal, ThresholdBip340CombineSigSharesInternalError>
+{
+    check_bip340_algorithm(algorithm_id)
+        .map_err(|_| ThresholdBip340CombineSigSharesInternalError::UnsupportedAlgorithm)?;
+
+    ThresholdBip340CombinedSignatureInternal::new(
+        derivation_path,
+        message,
+        randomness,
+        key_transcript,
+        presig_transcript,
+        reconstruction_threshold,
+        sig_shares,
+    )
+    .map_err(|e| e.into())
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ThresholdBip340VerifySignatureInternalError {
+    InvalidSignature,
+    InvalidArguments(String),
+    InconsistentCommitments,
+    InternalError(String),
+}
+
+impl From<ThresholdEcdsaError> for ThresholdBip340VerifySignatureInternalError {
+    fn from(e: ThresholdEcdsaError) -> Self {
+        match e {
+            ThresholdEcdsaError::CurveMismatch => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InvalidCommitment => Self::InconsistentCommitments,
+            ThresholdEcdsaError::UnexpectedCommitmentType => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InvalidSignature => Self::InvalidSignature,
+            x => Self::InternalError(format!("{:?}", x)),
+        }
+    }
+}
+
+/// Verify a threshold BIP340 signature
+///
+/// In addition to checking that the signature itself is consistent
+/// with the provided message and the public key associated with
+/// `derivation_path`, this function also verifies that the signature
+/// was generated correctly with regards to the provided presignature
+/// transcript and randomness.
+pub fn verify_threshold_bip340_signature(
+    signature: &ThresholdBip340CombinedSignatureInternal,
+    derivation_path: &DerivationPath,
+    message: &[u8],
+    randomness: Randomness,
+    presig_transcript: &IDkgTranscriptInternal,
+    key_transcript: &IDkgTranscriptInternal,
+    algorithm_id: AlgorithmId,
+) -> Result<(), ThresholdBip340VerifySignatureInternalError> {
+    check_bip340_algorithm(algorithm_id)
+        .map_err(ThresholdBip340VerifySignatureInternalError::InvalidArguments)?;
+
+    signature
+        .verify(
+            derivation_path,
+            message,
+            randomness,
+            presig_transcript,
+            key_transcript,
+        )
+        .map_err(|e| e.into())
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum IDkgGenerateComplaintsInternalError {
     InvalidArguments(String),
@@ -1,7 +1,7 @@
 use assert_matches::assert_matches;
 use ic_crypto_internal_threshold_sig_ecdsa::*;
 use ic_crypto_test_utils_reproducible_rng::reproducible_rng;
-use ic_types::Randomness;
+use ic_types::{NumberOfNodes, Randomness};
 use rand::Rng;
 use std::collections::BTreeMap;
 
@@ -488,11 +488,11 @@ fn should_multiply_transcripts_with_dynamic_threshold() -> Result<(), ThresholdE
     Ok(())
 }
 
-fn random_subset<R: rand::Rng>(
-    shares: &BTreeMap<NodeIndex, ThresholdEcdsaSigShareInternal>,
+fn random_subset<T: Clone, R: rand::Rng>(
+    shares: &BTreeMap<NodeIndex, T>,
     include: usize,
     rng: &mut R,
-) -> BTreeMap<NodeIndex, ThresholdEcdsaSigShareInternal> {
+) -> BTreeMap<NodeIndex, T> {
     assert!(include <= shares.len());
 
     let mut result = BTreeMap::new();
@@ -749,3 +749,143 @@ fn should_fail_on_hashed_message_length_mismatch() {
         );
     }
 }
+
+#[test]
+fn should_generate_and_verify_threshold_bip340_signatures() -> Result<(), ThresholdEcdsaError> {
+    let alg = ic_types::crypto::AlgorithmId::ThresholdSchnorrBip340;
+    let nodes = 10;
+    let threshold = nodes / 3;
+    let number_of_dealings_corrupted = threshold;
+
+    let rng = &mut reproducible_rng();
+
+    let cfg = TestConfig::new(EccCurveType::K256);
+    let setup = ProtocolSetup::new(cfg, nodes, threshold, Seed::from_rng(rng))?;
+
+    let key = ProtocolRound::random(&setup, nodes, number_of_dealings_corrupted)?;
+    let key = ProtocolRound::reshare_of_masked(&setup, &key, nodes, number_of_dealings_corrupted)?;
+    let presig = ProtocolRound::random_unmasked(&setup, nodes, number_of_dealings_corrupted)?;
+
+    let message = rng.gen::<[u8; 32]>();
+    let random_beacon = Randomness::from(rng.gen::<[u8; 32]>());
+    let derivation_path = DerivationPath::new_bip32(&[1, 2, 3]);
+
+    let mut shares = BTreeMap::new();
+    for node_index in 0..nodes {
+        let share = sign_bip340_share(
+            &derivation_path,
+            &message,
+            random_beacon,
+            &key.transcript,
+            &key.openings[node_index],
+            &presig.transcript,
+            &presig.openings[node_index],
+            alg,
+        )
+        .expect("Failed to create sig share");
+
+        assert_eq!(
+            verify_bip340_signature_share(
+                &share,
+                &derivation_path,
+                &message,
+                random_beacon,
+                node_index as NodeIndex,
+                &key.transcript,
+                &presig.transcript,
+                alg,
+            ),
+            Ok(())
+        );
+
+        // A share is not valid for another signer
+        assert_eq!(
+            verify_bip340_signature_share(
+                &share,
+                &derivation_path,
+                &message,
+                random_beacon,
+                (node_index + 1) as NodeIndex,
+                &key.transcript,
+                &presig.transcript,
+                alg,
+            ),
+            Err(ThresholdBip340VerifySigShareInternalError::InvalidSignatureShare)
+        );
+
+        shares.insert(node_index as NodeIndex, share);
+    }
+
+    let master_public_key = ic_types::crypto::canister_threshold_sig::MasterEcdsaPublicKey {
+        algorithm_id: ic_types::crypto::AlgorithmId::SchnorrSecp256k1,
+        public_key: key.transcript.constant_term().serialize(),
+    };
+    let public_key = sign::derive_public_key(&master_public_key, &derivation_path)?;
+    // BIP340 public keys are the x coordinate of the SEC1 compressed point
+    let verifying_key = k256::schnorr::VerifyingKey::from_bytes(&public_key.public_key[1..])
+        .expect("Failed to parse public key");
+
+    for i in 0..=nodes {
+        let shares = random_subset(&shares, i, rng);
+
+        let sig = combine_bip340_signature_shares(
+            &derivation_path,
+            &message,
+            random_beacon,
+            &key.transcript,
+            &presig.transcript,
+            NumberOfNodes::from(threshold as u32),
+            &shares,
+            alg,
+        );
+
+        if shares.len() < threshold {
+            assert_eq!(
+                sig,
+                Err(ThresholdBip340CombineSigSharesInternalError::InsufficientShares)
+            );
+            continue;
+        }
+
+        let sig = sig.expect("Failed to combine sig shares");
+        assert_eq!(
+            verify_threshold_bip340_signature(
+                &sig,
+                &derivation_path,
+                &message,
+                random_beacon,
+                &presig.transcript,
+                &key.transcript,
+                alg,
+            ),
+            Ok(())
+        );
+
+        let sig_bytes = sig.serialize().expect("Failed to serialize signature");
+        assert_eq!(
+            ThresholdBip340CombinedSignatureInternal::deserialize(&sig_bytes),
+            Ok(sig.clone())
+        );
+
+        let k256_sig = k256::schnorr::Signature::try_from(sig_bytes.as_slice())
+            .expect("Failed to parse signature");
+        assert!(verifying_key.verify_raw(&message, &k256_sig).is_ok());
+
+        // The signature is bound to the randomness used to rerandomize the presignature
+        let other_beacon = Randomness::from(rng.gen::<[u8; 32]>());
+        assert_eq!(
+            verify_threshold_bip340_signature(
+                &sig,
+                &derivation_path,
+                &message,
+                other_beacon,
+                &presig.transcript,
+                &key.transcript,
+                alg,
+            ),
+            Err(ThresholdBip340VerifySignatureInternalError::InvalidSignature)
+        );
+    }
+
+    Ok(())
+}
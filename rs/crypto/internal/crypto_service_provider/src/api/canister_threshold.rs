@@ -2,8 +2,8 @@
 
 use ic_crypto_internal_threshold_sig_ecdsa::{
     CommitmentOpening, IDkgComplaintInternal, IDkgDealingInternal, IDkgTranscriptInternal,
-    IDkgTranscriptOperationInternal, MEGaPublicKey, ThresholdEcdsaCombinedSigInternal,
-    ThresholdEcdsaSigShareInternal,
+    IDkgTranscriptOperationInternal, MEGaPublicKey, ThresholdBip340SignatureShareInternal,
+    ThresholdEcdsaCombinedSigInternal, ThresholdEcdsaSigShareInternal,
 };
 use ic_protobuf::registry::crypto::v1::PublicKey;
 use ic_types::crypto::canister_threshold_sig::error::{
@@ -12,10 +12,11 @@ use ic_types::crypto::canister_threshold_sig::error::{
     IDkgVerifyDealingPublicError, IDkgVerifyOpeningError, IDkgVerifyTranscriptError,
     ThresholdEcdsaCombineSigSharesError, ThresholdEcdsaSignShareError,
     ThresholdEcdsaVerifyCombinedSignatureError, ThresholdEcdsaVerifySigShareError,
+    ThresholdSchnorrSignShareError,
 };
 use ic_types::crypto::canister_threshold_sig::{
     idkg::{BatchSignedIDkgDealing, IDkgTranscriptOperation},
-    ExtendedDerivationPath, ThresholdEcdsaSigInputs,
+    ExtendedDerivationPath, ThresholdEcdsaSigInputs, ThresholdSchnorrSigInputs,
 };
 use ic_types::crypto::AlgorithmId;
 use ic_types::{NodeIndex, NumberOfNodes, Randomness, RegistryVersion};
@@ -181,6 +182,16 @@ pub trait CspThresholdEcdsaSigner {
     ) -> Result<ThresholdEcdsaSigShareInternal, ThresholdEcdsaSignShareError>;
 }
 
+/// Crypto service provider (CSP) client for threshold Schnorr signature share
+/// generation.
+pub trait CspThresholdSchnorrSigner {
+    /// Generate a signature share.
+    fn schnorr_sign_share(
+        &self,
+        inputs: &ThresholdSchnorrSigInputs,
+    ) -> Result<ThresholdBip340SignatureShareInternal, ThresholdSchnorrSignShareError>;
+}
+
 /// Crypto service provider (CSP) client for threshold ECDSA signature
 /// verification.
 pub trait CspThresholdEcdsaSigVerifier {
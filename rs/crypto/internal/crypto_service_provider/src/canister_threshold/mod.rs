@@ -9,6 +9,7 @@ mod tests;
 
 use crate::api::{
     CspCreateMEGaKeyError, CspIDkgProtocol, CspThresholdEcdsaSigVerifier, CspThresholdEcdsaSigner,
+    CspThresholdSchnorrSigner,
 };
 use crate::vault::api::{
     IDkgCreateDealingVaultError, IDkgDealingInternalBytes, IDkgTranscriptInternalBytes,
@@ -23,9 +24,9 @@ use ic_crypto_internal_threshold_sig_ecdsa::{
     verify_threshold_signature as tecdsa_verify_combined_signature,
     verify_transcript as tecdsa_verify_transcript, CommitmentOpening, DerivationPath,
     IDkgComplaintInternal, IDkgDealingInternal, IDkgTranscriptInternal,
-    IDkgTranscriptOperationInternal, MEGaPublicKey, ThresholdEcdsaCombinedSigInternal,
-    ThresholdEcdsaSigShareInternal, ThresholdEcdsaVerifySigShareInternalError,
-    ThresholdEcdsaVerifySignatureInternalError,
+    IDkgTranscriptOperationInternal, MEGaPublicKey, ThresholdBip340SignatureShareInternal,
+    ThresholdEcdsaCombinedSigInternal, ThresholdEcdsaSigShareInternal,
+    ThresholdEcdsaVerifySigShareInternalError, ThresholdEcdsaVerifySignatureInternalError,
 };
 use ic_crypto_internal_types::scope::{ConstScope, Scope};
 use ic_logger::debug;
@@ -36,10 +37,11 @@ use ic_types::crypto::canister_threshold_sig::error::{
     IDkgVerifyDealingPublicError, IDkgVerifyOpeningError, IDkgVerifyTranscriptError,
     ThresholdEcdsaCombineSigSharesError, ThresholdEcdsaSignShareError,
     ThresholdEcdsaVerifyCombinedSignatureError, ThresholdEcdsaVerifySigShareError,
+    ThresholdSchnorrSignShareError,
 };
 use ic_types::crypto::canister_threshold_sig::{
     idkg::{BatchSignedIDkgDealing, IDkgTranscriptOperation},
-    ExtendedDerivationPath, ThresholdEcdsaSigInputs,
+    ExtendedDerivationPath, ThresholdEcdsaSigInputs, ThresholdSchnorrSigInputs,
 };
 use ic_types::crypto::AlgorithmId;
 use ic_types::{NodeIndex, NumberOfNodes, Randomness, RegistryVersion};
@@ -328,6 +330,33 @@ impl CspThresholdEcdsaSigner for Csp {
     }
 }
 
+/// Threshold-Schnorr signature share generation client.
+///
+/// Please see the trait definition for full documentation.
+impl CspThresholdSchnorrSigner for Csp {
+    fn schnorr_sign_share(
+        &self,
+        inputs: &ThresholdSchnorrSigInputs,
+    ) -> Result<ThresholdBip340SignatureShareInternal, ThresholdSchnorrSignShareError> {
+        debug!(self.logger; crypto.method_name => "schnorr_sign_share");
+
+        let key = inputs.key_transcript().transcript_to_bytes();
+        let presig = inputs
+            .presig_transcript()
+            .blinder_unmasked()
+            .transcript_to_bytes();
+
+        self.csp_vault.create_schnorr_sig_share(
+            inputs.derivation_path().clone(),
+            inputs.message().to_vec(),
+            *inputs.nonce(),
+            IDkgTranscriptInternalBytes::from(key),
+            IDkgTranscriptInternalBytes::from(presig),
+            inputs.algorithm_id(),
+        )
+    }
+}
+
 /// Threshold-ECDSA signature verification client.
 ///
 /// Please see the trait definition for full documentation.
@@ -27,7 +27,8 @@ use crate::vault::remote_csp_vault::RemoteCspVault;
 use crate::api::{
     CspIDkgProtocol, CspKeyGenerator, CspPublicAndSecretKeyStoreChecker, CspPublicKeyStore,
     CspSigVerifier, CspSigner, CspThresholdEcdsaSigVerifier, CspThresholdEcdsaSigner,
-    CspTlsHandshakeSignerProvider, NiDkgCspClient, ThresholdSignatureCspClient,
+    CspThresholdSchnorrSigner, CspTlsHandshakeSignerProvider, NiDkgCspClient,
+    ThresholdSignatureCspClient,
 };
 use crate::secret_key_store::SecretKeyStore;
 use crate::types::{CspPublicKey, ExternalPublicKeys};
@@ -60,6 +61,7 @@ pub trait CryptoServiceProvider:
     + CspIDkgProtocol
     + CspThresholdEcdsaSigner
     + CspThresholdEcdsaSigVerifier
+    + CspThresholdSchnorrSigner
     + CspPublicAndSecretKeyStoreChecker
     + CspTlsHandshakeSignerProvider
     + CspPublicKeyStore
@@ -74,6 +76,7 @@ impl<T> CryptoServiceProvider for T where
         + CspIDkgProtocol
         + CspThresholdEcdsaSigner
         + CspThresholdEcdsaSigVerifier
+        + CspThresholdSchnorrSigner
         + NiDkgCspClient
         + CspPublicAndSecretKeyStoreChecker
         + CspTlsHandshakeSignerProvider
@@ -12,6 +12,7 @@ mod tecdsa;
 mod tests;
 mod threshold_sig;
 mod tls;
+mod tschnorr;
 
 use crate::public_key_store::proto_pubkey_store::ProtoPublicKeyStore;
 use crate::public_key_store::PublicKeyStore;
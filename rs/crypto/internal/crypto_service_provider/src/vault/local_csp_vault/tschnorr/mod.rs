@@ -0,0 +1,115 @@
+use crate::public_key_store::PublicKeyStore;
+use crate::secret_key_store::SecretKeyStore;
+use crate::types::CspSecretKey;
+use crate::vault::api::{IDkgTranscriptInternalBytes, ThresholdSchnorrSignerCspVault};
+use crate::vault::local_csp_vault::LocalCspVault;
+use crate::KeyId;
+use ic_crypto_internal_logmon::metrics::{MetricsDomain, MetricsResult, MetricsScope};
+use ic_crypto_internal_threshold_sig_ecdsa::{
+    sign_bip340_share, CombinedCommitment, CommitmentOpening, IDkgTranscriptInternal,
+    ThresholdBip340SignatureShareInternal,
+};
+use ic_types::crypto::canister_threshold_sig::error::ThresholdSchnorrSignShareError;
+use ic_types::crypto::canister_threshold_sig::ExtendedDerivationPath;
+use ic_types::crypto::AlgorithmId;
+use ic_types::Randomness;
+use rand::{CryptoRng, Rng};
+use std::convert::TryFrom;
+
+impl<R: Rng + CryptoRng, S: SecretKeyStore, C: SecretKeyStore, P: PublicKeyStore>
+    ThresholdSchnorrSignerCspVault for LocalCspVault<R, S, C, P>
+{
+    fn create_schnorr_sig_share(
+        &self,
+        derivation_path: ExtendedDerivationPath,
+        message: Vec<u8>,
+        nonce: Randomness,
+        key_raw: IDkgTranscriptInternalBytes,
+        presig_raw: IDkgTranscriptInternalBytes,
+        algorithm_id: AlgorithmId,
+    ) -> Result<ThresholdBip340SignatureShareInternal, ThresholdSchnorrSignShareError> {
+        fn deserialize_transcript(
+            bytes: &[u8],
+        ) -> Result<IDkgTranscriptInternal, ThresholdSchnorrSignShareError> {
+            IDkgTranscriptInternal::deserialize(bytes).map_err(|e| {
+                ThresholdSchnorrSignShareError::SerializationError {
+                    internal_error: e.0,
+                }
+            })
+        }
+
+        let key = deserialize_transcript(key_raw.as_ref())?;
+        let presig = deserialize_transcript(presig_raw.as_ref())?;
+
+        let start_time = self.metrics.now();
+        let result = self.create_schnorr_sig_share_internal(
+            &derivation_path,
+            &message[..],
+            &nonce,
+            &key,
+            &presig,
+            algorithm_id,
+        );
+        self.metrics.observe_duration_seconds(
+            MetricsDomain::ThresholdSchnorr,
+            MetricsScope::Local,
+            "create_schnorr_sig_share",
+            MetricsResult::from(&result),
+            start_time,
+        );
+        result
+    }
+}
+
+impl<R: Rng + CryptoRng, S: SecretKeyStore, C: SecretKeyStore, P: PublicKeyStore>
+    LocalCspVault<R, S, C, P>
+{
+    fn schnorr_commitment_opening_from_sks(
+        &self,
+        combined_commitment: &CombinedCommitment,
+    ) -> Result<CommitmentOpening, ThresholdSchnorrSignShareError> {
+        let commitment = match combined_commitment {
+            CombinedCommitment::BySummation(commitment)
+            | CombinedCommitment::ByInterpolation(commitment) => commitment,
+        };
+
+        let key_id = KeyId::from(commitment);
+        let opening = self.canister_sks_read_lock().get(&key_id);
+        match &opening {
+            Some(CspSecretKey::IDkgCommitmentOpening(bytes)) => CommitmentOpening::try_from(bytes)
+                .map_err(|e| ThresholdSchnorrSignShareError::InternalError {
+                    internal_error: format!("{:?}", e),
+                }),
+            _ => Err(ThresholdSchnorrSignShareError::SecretSharesNotFound {
+                commitment_string: format!("{:?}", commitment),
+            }),
+        }
+    }
+
+    fn create_schnorr_sig_share_internal(
+        &self,
+        derivation_path: &ExtendedDerivationPath,
+        message: &[u8],
+        nonce: &Randomness,
+        key: &IDkgTranscriptInternal,
+        presig: &IDkgTranscriptInternal,
+        algorithm_id: AlgorithmId,
+    ) -> Result<ThresholdBip340SignatureShareInternal, ThresholdSchnorrSignShareError> {
+        let key_share = self.schnorr_commitment_opening_from_sks(&key.combined_commitment)?;
+        let presig_share = self.schnorr_commitment_opening_from_sks(&presig.combined_commitment)?;
+
+        sign_bip340_share(
+            &derivation_path.into(),
+            message,
+            *nonce,
+            key,
+            &key_share,
+            presig,
+            &presig_share,
+            algorithm_id,
+        )
+        .map_err(|e| ThresholdSchnorrSignShareError::InternalError {
+            internal_error: format!("{:?}", e),
+        })
+    }
+}
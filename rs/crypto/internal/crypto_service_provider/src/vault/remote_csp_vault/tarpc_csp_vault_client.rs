@@ -9,7 +9,7 @@ use crate::vault::api::{
     IDkgTranscriptInternalBytes, MultiSignatureCspVault, NiDkgCspVault, PksAndSksContainsErrors,
     PublicAndSecretKeyStoreCspVault, PublicKeyStoreCspVault, PublicRandomSeedGenerator,
     PublicRandomSeedGeneratorError, SecretKeyStoreCspVault, ThresholdEcdsaSignerCspVault,
-    ThresholdSignatureCspVault, ValidatePksAndSksError,
+    ThresholdSchnorrSignerCspVault, ThresholdSignatureCspVault, ValidatePksAndSksError,
 };
 use crate::vault::remote_csp_vault::codec::{Bincode, CspVaultObserver, ObservableCodec};
 use crate::vault::remote_csp_vault::{
@@ -24,7 +24,8 @@ use ic_crypto_internal_threshold_sig_bls12381::api::ni_dkg_errors::{
     CspDkgRetainThresholdKeysError, CspDkgUpdateFsEpochError,
 };
 use ic_crypto_internal_threshold_sig_ecdsa::{
-    CommitmentOpening, IDkgComplaintInternal, MEGaPublicKey, ThresholdEcdsaSigShareInternal,
+    CommitmentOpening, IDkgComplaintInternal, MEGaPublicKey, ThresholdBip340SignatureShareInternal,
+    ThresholdEcdsaSigShareInternal,
 };
 use ic_crypto_internal_types::encrypt::forward_secure::{
     CspFsEncryptionPop, CspFsEncryptionPublicKey,
@@ -38,7 +39,7 @@ use ic_logger::{debug, new_logger, ReplicaLogger};
 use ic_protobuf::registry::crypto::v1::PublicKey;
 use ic_types::crypto::canister_threshold_sig::error::{
     IDkgLoadTranscriptError, IDkgOpenTranscriptError, IDkgRetainKeysError,
-    IDkgVerifyDealingPrivateError, ThresholdEcdsaSignShareError,
+    IDkgVerifyDealingPrivateError, ThresholdEcdsaSignShareError, ThresholdSchnorrSignShareError,
 };
 use ic_types::crypto::canister_threshold_sig::{
     idkg::{BatchSignedIDkgDealing, IDkgTranscriptOperation},
@@ -746,6 +747,34 @@ impl ThresholdEcdsaSignerCspVault for RemoteCspVault {
     }
 }
 
+impl ThresholdSchnorrSignerCspVault for RemoteCspVault {
+    #[inline]
+    fn create_schnorr_sig_share(
+        &self,
+        derivation_path: ExtendedDerivationPath,
+        message: Vec<u8>,
+        nonce: Randomness,
+        key_raw: IDkgTranscriptInternalBytes,
+        presig_raw: IDkgTranscriptInternalBytes,
+        algorithm_id: AlgorithmId,
+    ) -> Result<ThresholdBip340SignatureShareInternal, ThresholdSchnorrSignShareError> {
+        self.tokio_block_on(self.tarpc_csp_client.create_schnorr_sig_share(
+            context_with_timeout(self.rpc_timeout),
+            derivation_path,
+            ByteBuf::from(message),
+            nonce,
+            key_raw,
+            presig_raw,
+            algorithm_id,
+        ))
+        .unwrap_or_else(|rpc_error: tarpc::client::RpcError| {
+            Err(ThresholdSchnorrSignShareError::TransientInternalError {
+                internal_error: rpc_error.to_string(),
+            })
+        })
+    }
+}
+
 impl PublicRandomSeedGenerator for RemoteCspVault {
     fn new_public_seed(&self) -> Result<Seed, PublicRandomSeedGeneratorError> {
         self.tokio_block_on(
@@ -18,7 +18,8 @@ mod sign;
 mod tls;
 
 pub use sign::{
-    get_tecdsa_master_public_key, retrieve_mega_public_key_from_registry, MegaKeyFromRegistryError,
+    get_tecdsa_master_public_key, get_tschnorr_master_public_key,
+    retrieve_mega_public_key_from_registry, MegaKeyFromRegistryError,
 };
 
 use crate::sign::ThresholdSigDataStoreImpl;
@@ -0,0 +1,237 @@
+//! Implementations of ThresholdSchnorrSigner
+use super::ecdsa::MasterPublicKeyExtractionError;
+use ic_crypto_internal_csp::api::CspThresholdSchnorrSigner;
+use ic_crypto_internal_threshold_sig_ecdsa::{
+    combine_bip340_signature_shares, verify_bip340_signature_share,
+    verify_threshold_bip340_signature, DerivationPath, IDkgTranscriptInternal,
+    ThresholdBip340CombineSigSharesInternalError, ThresholdBip340CombinedSignatureInternal,
+    ThresholdBip340SignatureShareInternal, ThresholdBip340VerifySigShareInternalError,
+    ThresholdBip340VerifySignatureInternalError, ThresholdEcdsaSerializationError,
+};
+use ic_types::crypto::canister_threshold_sig::error::{
+    ThresholdSchnorrCombineSigSharesError, ThresholdSchnorrSignShareError,
+    ThresholdSchnorrVerifyCombinedSignatureError, ThresholdSchnorrVerifySigShareError,
+};
+use ic_types::crypto::canister_threshold_sig::idkg::IDkgTranscript;
+use ic_types::crypto::canister_threshold_sig::idkg::IDkgTranscriptType::{Masked, Unmasked};
+use ic_types::crypto::canister_threshold_sig::MasterSchnorrPublicKey;
+use ic_types::crypto::canister_threshold_sig::{
+    ThresholdSchnorrCombinedSignature, ThresholdSchnorrSigInputs, ThresholdSchnorrSigShare,
+};
+use ic_types::crypto::AlgorithmId;
+use ic_types::{NodeId, NodeIndex};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+pub fn sign_share<C: CspThresholdSchnorrSigner>(
+    csp_client: &C,
+    self_node_id: &NodeId,
+    inputs: &ThresholdSchnorrSigInputs,
+) -> Result<ThresholdSchnorrSigShare, ThresholdSchnorrSignShareError> {
+    if !inputs.receivers().contains(*self_node_id) {
+        return Err(ThresholdSchnorrSignShareError::NotAReceiver);
+    }
+
+    let internal_sig_share = csp_client.schnorr_sign_share(inputs)?;
+
+    let sig_share_raw = internal_sig_share.serialize().map_err(|e| {
+        ThresholdSchnorrSignShareError::SerializationError {
+            internal_error: format!("{:?}", e),
+        }
+    })?;
+
+    Ok(ThresholdSchnorrSigShare { sig_share_raw })
+}
+
+pub fn verify_sig_share(
+    signer: NodeId,
+    inputs: &ThresholdSchnorrSigInputs,
+    share: &ThresholdSchnorrSigShare,
+) -> Result<(), ThresholdSchnorrVerifySigShareError> {
+    fn conv_error(e: ThresholdEcdsaSerializationError) -> ThresholdSchnorrVerifySigShareError {
+        ThresholdSchnorrVerifySigShareError::SerializationError {
+            internal_error: e.0,
+        }
+    }
+
+    let presig = IDkgTranscriptInternal::try_from(inputs.presig_transcript().blinder_unmasked())
+        .map_err(conv_error)?;
+    let key = IDkgTranscriptInternal::try_from(inputs.key_transcript()).map_err(conv_error)?;
+
+    let sig_share = ThresholdBip340SignatureShareInternal::deserialize(&share.sig_share_raw)
+        .map_err(
+            |e| ThresholdSchnorrVerifySigShareError::SerializationError {
+                internal_error: format!("{:?}", e),
+            },
+        )?;
+    let signer_index = inputs.index_for_signer_id(signer).ok_or(
+        ThresholdSchnorrVerifySigShareError::InvalidArgumentMissingSignerInTranscript {
+            signer_id: signer,
+        },
+    )?;
+
+    verify_bip340_signature_share(
+        &sig_share,
+        &DerivationPath::from(inputs.derivation_path()),
+        inputs.message(),
+        *inputs.nonce(),
+        signer_index,
+        &key,
+        &presig,
+        inputs.algorithm_id(),
+    )
+    .map_err(|e| match e {
+        ThresholdBip340VerifySigShareInternalError::InvalidSignatureShare => {
+            ThresholdSchnorrVerifySigShareError::InvalidSignatureShare
+        }
+        ThresholdBip340VerifySigShareInternalError::InvalidArguments(s) => {
+            ThresholdSchnorrVerifySigShareError::InvalidArguments(s)
+        }
+        e => ThresholdSchnorrVerifySigShareError::InternalError {
+            internal_error: format!("{:?}", e),
+        },
+    })
+}
+
+pub fn verify_combined_signature(
+    inputs: &ThresholdSchnorrSigInputs,
+    signature: &ThresholdSchnorrCombinedSignature,
+) -> Result<(), ThresholdSchnorrVerifyCombinedSignatureError> {
+    fn conv_error(
+        e: ThresholdEcdsaSerializationError,
+    ) -> ThresholdSchnorrVerifyCombinedSignatureError {
+        ThresholdSchnorrVerifyCombinedSignatureError::SerializationError {
+            internal_error: e.0,
+        }
+    }
+
+    let presig = IDkgTranscriptInternal::try_from(inputs.presig_transcript().blinder_unmasked())
+        .map_err(conv_error)?;
+    let key = IDkgTranscriptInternal::try_from(inputs.key_transcript()).map_err(conv_error)?;
+
+    let signature = ThresholdBip340CombinedSignatureInternal::deserialize(&signature.signature)
+        .map_err(conv_error)?;
+
+    verify_threshold_bip340_signature(
+        &signature,
+        &DerivationPath::from(inputs.derivation_path()),
+        inputs.message(),
+        *inputs.nonce(),
+        &presig,
+        &key,
+        inputs.algorithm_id(),
+    )
+    .map_err(|e| match e {
+        ThresholdBip340VerifySignatureInternalError::InvalidSignature => {
+            ThresholdSchnorrVerifyCombinedSignatureError::InvalidSignature
+        }
+        ThresholdBip340VerifySignatureInternalError::InvalidArguments(s) => {
+            ThresholdSchnorrVerifyCombinedSignatureError::InvalidArguments(s)
+        }
+        e => ThresholdSchnorrVerifyCombinedSignatureError::InternalError {
+            internal_error: format!("{:?}", e),
+        },
+    })
+}
+
+pub fn combine_sig_shares(
+    inputs: &ThresholdSchnorrSigInputs,
+    shares: &BTreeMap<NodeId, ThresholdSchnorrSigShare>,
+) -> Result<ThresholdSchnorrCombinedSignature, ThresholdSchnorrCombineSigSharesError> {
+    fn conv_error(e: ThresholdEcdsaSerializationError) -> ThresholdSchnorrCombineSigSharesError {
+        ThresholdSchnorrCombineSigSharesError::SerializationError {
+            internal_error: e.0,
+        }
+    }
+
+    if shares.len() < inputs.reconstruction_threshold().get() as usize {
+        return Err(
+            ThresholdSchnorrCombineSigSharesError::UnsatisfiedReconstructionThreshold {
+                threshold: inputs.reconstruction_threshold().get(),
+                share_count: shares.len(),
+            },
+        );
+    }
+
+    let presig = IDkgTranscriptInternal::try_from(inputs.presig_transcript().blinder_unmasked())
+        .map_err(conv_error)?;
+    let key = IDkgTranscriptInternal::try_from(inputs.key_transcript()).map_err(conv_error)?;
+
+    let internal_shares = internal_sig_shares_by_index_from_sig_shares(shares, inputs)?;
+
+    let internal_combined_sig = combine_bip340_signature_shares(
+        &DerivationPath::from(inputs.derivation_path()),
+        inputs.message(),
+        *inputs.nonce(),
+        &key,
+        &presig,
+        inputs.reconstruction_threshold(),
+        &internal_shares,
+        inputs.algorithm_id(),
+    )
+    .map_err(|e| match e {
+        ThresholdBip340CombineSigSharesInternalError::InsufficientShares => {
+            ThresholdSchnorrCombineSigSharesError::UnsatisfiedReconstructionThreshold {
+                threshold: inputs.reconstruction_threshold().get(),
+                share_count: shares.len(),
+            }
+        }
+        e => ThresholdSchnorrCombineSigSharesError::InternalError {
+            internal_error: format!("{:?}", e),
+        },
+    })?;
+
+    Ok(ThresholdSchnorrCombinedSignature {
+        signature: internal_combined_sig.serialize().map_err(conv_error)?,
+    })
+}
+
+/// Extracts the threshold Schnorr master public key from the given `idkg_transcript`.
+pub fn get_tschnorr_master_public_key(
+    idkg_transcript: &IDkgTranscript,
+) -> Result<MasterSchnorrPublicKey, MasterPublicKeyExtractionError> {
+    match idkg_transcript.algorithm_id {
+        AlgorithmId::ThresholdSchnorrBip340 => match idkg_transcript.transcript_type {
+            Unmasked(_) => {
+                let internal_transcript = IDkgTranscriptInternal::try_from(idkg_transcript)
+                    .map_err(|e| {
+                        MasterPublicKeyExtractionError::SerializationError(format!("{:?}", e))
+                    })?;
+                Ok(MasterSchnorrPublicKey {
+                    algorithm_id: AlgorithmId::SchnorrSecp256k1,
+                    public_key: internal_transcript.constant_term().serialize(),
+                })
+            }
+            Masked(_) => Err(MasterPublicKeyExtractionError::CannotExtractFromMasked),
+        },
+        _ => Err(MasterPublicKeyExtractionError::UnsupportedAlgorithm(
+            format!("{:?}", idkg_transcript.algorithm_id),
+        )),
+    }
+}
+
+/// Deserialize each raw signature share to the internal format,
+/// and map them by signer index (rather than signer Id).
+fn internal_sig_shares_by_index_from_sig_shares(
+    shares: &BTreeMap<NodeId, ThresholdSchnorrSigShare>,
+    inputs: &ThresholdSchnorrSigInputs,
+) -> Result<
+    BTreeMap<NodeIndex, ThresholdBip340SignatureShareInternal>,
+    ThresholdSchnorrCombineSigSharesError,
+> {
+    shares
+        .iter()
+        .map(|(&id, share)| {
+            let index = inputs
+                .index_for_signer_id(id)
+                .ok_or(ThresholdSchnorrCombineSigSharesError::SignerNotAllowed { node_id: id })?;
+            let internal_share =
+                ThresholdBip340SignatureShareInternal::deserialize(&share.sig_share_raw).map_err(
+                    |e| ThresholdSchnorrCombineSigSharesError::SerializationError {
+                        internal_error: format!("{:?}", e),
+                    },
+                )?;
+            Ok((index, internal_share))
+        })
+        .collect()
+}
@@ -1,8 +1,12 @@
 use ic_crypto_internal_threshold_sig_ecdsa::ThresholdEcdsaDerivePublicKeyError;
-use ic_types::crypto::canister_threshold_sig::error::ThresholdEcdsaGetPublicKeyError;
+use ic_types::crypto::canister_threshold_sig::error::{
+    ThresholdEcdsaGetPublicKeyError, ThresholdSchnorrGetPublicKeyError,
+};
 use ic_types::crypto::canister_threshold_sig::{
-    EcdsaPublicKey, ExtendedDerivationPath, MasterEcdsaPublicKey,
+    EcdsaPublicKey, ExtendedDerivationPath, MasterEcdsaPublicKey, MasterSchnorrPublicKey,
+    SchnorrPublicKey,
 };
+use ic_types::crypto::AlgorithmId;
 
 /// Derives the ECDSA public key from the specified `master_public_key` for
 /// the given `extended_derivation_path`.
@@ -23,3 +27,44 @@ pub fn derive_tecdsa_public_key(
         }
     })
 }
+
+/// Derives the Schnorr public key from the specified `master_public_key` for
+/// the given `extended_derivation_path`.
+///
+/// BIP340 keys are derived in the same way as secp256k1 ECDSA keys and are
+/// returned in compressed SEC1 encoding. Threshold Ed25519 keys are not
+/// supported yet.
+pub fn derive_threshold_schnorr_public_key(
+    master_public_key: &MasterSchnorrPublicKey,
+    extended_derivation_path: &ExtendedDerivationPath,
+) -> Result<SchnorrPublicKey, ThresholdSchnorrGetPublicKeyError> {
+    match master_public_key.algorithm_id {
+        AlgorithmId::SchnorrSecp256k1 => {
+            let master_key = MasterEcdsaPublicKey {
+                algorithm_id: master_public_key.algorithm_id,
+                public_key: master_public_key.public_key.clone(),
+            };
+            let derived = ic_crypto_internal_threshold_sig_ecdsa::derive_public_key(
+                &master_key,
+                &extended_derivation_path.into(),
+            )
+            .map_err(|e| match e {
+                ThresholdEcdsaDerivePublicKeyError::InvalidArgument(s) => {
+                    ThresholdSchnorrGetPublicKeyError::InvalidArgument(s)
+                }
+                ThresholdEcdsaDerivePublicKeyError::InternalError(e) => {
+                    ThresholdSchnorrGetPublicKeyError::InternalError(format!("{:?}", e))
+                }
+            })?;
+            Ok(SchnorrPublicKey {
+                algorithm_id: derived.algorithm_id,
+                public_key: derived.public_key,
+                chain_key: derived.chain_key,
+            })
+        }
+        unsupported => Err(ThresholdSchnorrGetPublicKeyError::InvalidArgument(format!(
+            "derive_threshold_schnorr_public_key does not support alg {}",
+            unsupported
+        ))),
+    }
+}
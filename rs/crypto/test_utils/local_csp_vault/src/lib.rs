@@ -23,13 +23,15 @@ use ic_crypto_internal_csp::vault::api::PublicRandomSeedGenerator;
 use ic_crypto_internal_csp::vault::api::PublicRandomSeedGeneratorError;
 use ic_crypto_internal_csp::vault::api::SecretKeyStoreCspVault;
 use ic_crypto_internal_csp::vault::api::ThresholdEcdsaSignerCspVault;
+use ic_crypto_internal_csp::vault::api::ThresholdSchnorrSignerCspVault;
 use ic_crypto_internal_csp::vault::api::ThresholdSignatureCspVault;
 use ic_crypto_internal_csp::vault::api::TlsHandshakeCspVault;
 use ic_crypto_internal_csp::vault::api::ValidatePksAndSksError;
 use ic_crypto_internal_seed::Seed;
 use ic_crypto_internal_threshold_sig_bls12381::api::ni_dkg_errors;
 use ic_crypto_internal_threshold_sig_ecdsa::{
-    CommitmentOpening, IDkgComplaintInternal, MEGaPublicKey, ThresholdEcdsaSigShareInternal,
+    CommitmentOpening, IDkgComplaintInternal, MEGaPublicKey, ThresholdBip340SignatureShareInternal,
+    ThresholdEcdsaSigShareInternal,
 };
 use ic_crypto_internal_types::encrypt::forward_secure::{
     CspFsEncryptionPop, CspFsEncryptionPublicKey,
@@ -42,7 +44,7 @@ use ic_crypto_tls_interfaces::TlsPublicKeyCert;
 use ic_protobuf::registry::crypto::v1::PublicKey;
 use ic_types::crypto::canister_threshold_sig::error::{
     IDkgLoadTranscriptError, IDkgOpenTranscriptError, IDkgRetainKeysError,
-    IDkgVerifyDealingPrivateError, ThresholdEcdsaSignShareError,
+    IDkgVerifyDealingPrivateError, ThresholdEcdsaSignShareError, ThresholdSchnorrSignShareError,
 };
 use ic_types::crypto::canister_threshold_sig::{
     idkg::{BatchSignedIDkgDealing, IDkgTranscriptOperation},
@@ -200,6 +202,18 @@ mock! {
         ) -> Result<ThresholdEcdsaSigShareInternal, ThresholdEcdsaSignShareError>;
     }
 
+    impl ThresholdSchnorrSignerCspVault for LocalCspVault {
+        fn create_schnorr_sig_share(
+            &self,
+            derivation_path: ExtendedDerivationPath,
+            message: Vec<u8>,
+            nonce: Randomness,
+            key_raw: IDkgTranscriptInternalBytes,
+            presig_raw: IDkgTranscriptInternalBytes,
+            algorithm_id: AlgorithmId,
+        ) -> Result<ThresholdBip340SignatureShareInternal, ThresholdSchnorrSignShareError>;
+    }
+
     impl SecretKeyStoreCspVault for LocalCspVault{
         fn sks_contains(&self, key_id: KeyId) -> Result<bool, CspSecretKeyStoreContainsError>;
     }
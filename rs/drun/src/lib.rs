@@ -4,13 +4,14 @@ use crate::message::{msg_stream_from_file, Message};
 use hex::encode;
 use ic_config::{subnet_config::SubnetConfig, Config};
 use ic_cycles_account_manager::CyclesAccountManager;
-use ic_error_types::{ErrorCode, UserError};
-use ic_execution_environment::ExecutionServices;
+use ic_error_types::{ErrorCode, RejectCode, UserError};
+use ic_execution_environment::{CanisterWasmProfile, ExecutionServices};
 use ic_http_endpoints_metrics::MetricsHttpEndpoint;
 use ic_interfaces::{execution_environment::IngressHistoryReader, messaging::MessageRouting};
 use ic_interfaces_state_manager::StateReader;
 use ic_messaging::MessageRoutingImpl;
 use ic_metrics::MetricsRegistry;
+use ic_pprof::folded_to_pprof;
 use ic_protobuf::registry::{
     provisional_whitelist::v1::ProvisionalWhitelist as PbProvisionalWhitelist,
     routing_table::v1::RoutingTable as PbRoutingTable,
@@ -27,6 +28,7 @@ use ic_registry_routing_table::{routing_table_insert_subnet, RoutingTable};
 use ic_registry_subnet_type::SubnetType;
 use ic_state_manager::StateManagerImpl;
 use ic_test_utilities::consensus::fake::FakeVerifier;
+use ic_test_utilities_metrics::fetch_histogram_stats;
 use ic_test_utilities_registry::{
     add_subnet_record, insert_initial_dkg_transcript, SubnetRecordBuilder,
 };
@@ -40,10 +42,12 @@ use ic_types::{
     time, CanisterId, NodeId, NumInstructions, PrincipalId, Randomness, RegistryVersion, SubnetId,
 };
 use rand::distributions::{Distribution, Uniform};
+use serde::Serialize;
 use slog::{Drain, Logger};
 use std::collections::BTreeMap;
 use std::fs::OpenOptions;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use std::{thread::sleep, time::Duration};
 
@@ -55,6 +59,38 @@ const MAX_BATCHES_UNTIL_RESPONSE: u64 = 10000;
 // how long to wait between batches
 const WAIT_PER_BATCH: Duration = Duration::from_millis(5);
 
+// the metrics counting the instructions executed by messages
+const INSTRUCTIONS_METRICS: [&str; 3] = [
+    "scheduler_instructions_consumed_per_round",
+    "execution_round_subnet_queue_instructions",
+    "execution_query_instructions",
+];
+
+/// The format in which the result of every message is printed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// One line of text per message, as described in the README.
+    #[default]
+    Text,
+    /// One JSON object per message and line.
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!(
+                "Unknown output format {}, expected text or json.",
+                s
+            )),
+        }
+    }
+}
+
 pub struct DrunOptions {
     pub msg_filename: String,
     pub cfg: Config,
@@ -62,6 +98,113 @@ pub struct DrunOptions {
     pub log_file: Option<PathBuf>,
     pub instruction_limit: Option<u64>,
     pub subnet_type: SubnetType,
+    pub output_format: OutputFormat,
+    /// If set, the Wasm profiles of all executed messages are written to this
+    /// file, as a `pprof` protobuf if its extension is `pb` and in the folded
+    /// stack format otherwise.
+    pub wasm_profile: Option<PathBuf>,
+}
+
+/// The result of a single message in the JSON output format.
+#[derive(Serialize)]
+struct MessageOutput {
+    #[serde(rename = "type")]
+    message_type: &'static str,
+    /// One of `replied`, `rejected` (by the canister) or `failed`.
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reply: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reject_code: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reject_message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error_code: Option<String>,
+    instructions: u64,
+    cycles: u128,
+}
+
+impl MessageOutput {
+    fn new(
+        message_type: &'static str,
+        result: Result<WasmResult, UserError>,
+        usage: ResourceUsage,
+    ) -> Self {
+        let output = Self {
+            message_type,
+            status: "replied",
+            reply: None,
+            reject_code: None,
+            reject_message: None,
+            error_code: None,
+            instructions: usage.instructions,
+            cycles: usage.cycles,
+        };
+        match result {
+            Ok(WasmResult::Reply(reply)) => Self {
+                reply: Some(format!("0x{}", encode(reply))),
+                ..output
+            },
+            Ok(WasmResult::Reject(message)) => Self {
+                status: "rejected",
+                reject_code: Some(RejectCode::CanisterReject as u64),
+                reject_message: Some(message),
+                ..output
+            },
+            Err(error) => Self {
+                status: "failed",
+                reject_code: Some(error.reject_code() as u64),
+                reject_message: Some(error.description().to_string()),
+                error_code: Some(error.code().to_string()),
+                ..output
+            },
+        }
+    }
+}
+
+/// The instructions executed and cycles consumed on the subnet.
+#[derive(Clone, Copy, Default)]
+struct ResourceUsage {
+    instructions: u64,
+    cycles: u128,
+}
+
+impl ResourceUsage {
+    fn observe(metrics_registry: &MetricsRegistry, state_manager: &StateManagerImpl) -> Self {
+        let instructions = INSTRUCTIONS_METRICS
+            .iter()
+            .filter_map(|name| fetch_histogram_stats(metrics_registry, name))
+            .map(|stats| stats.sum as u64)
+            .sum();
+        let state = state_manager.get_latest_state().take();
+        let cycles = state
+            .canister_states
+            .values()
+            .map(|canister| {
+                canister
+                    .system_state
+                    .canister_metrics
+                    .consumed_cycles_since_replica_started
+                    .get()
+            })
+            .sum::<u128>()
+            + state
+                .metadata
+                .subnet_metrics
+                .consumed_cycles_by_deleted_canisters
+                .get();
+        Self {
+            instructions,
+            cycles,
+        }
+    }
+
+    fn since(self, earlier: ResourceUsage) -> Self {
+        Self {
+            instructions: self.instructions.saturating_sub(earlier.instructions),
+            cycles: self.cycles.saturating_sub(earlier.cycles),
+        }
+    }
 }
 
 /// Deliver a single message to the Message Routing layer
@@ -70,14 +213,45 @@ fn deliver_message(
     message_routing: &dyn MessageRouting,
     ingress_hist_reader: &dyn IngressHistoryReader,
     extra_batches: u64,
+    time_offset: Duration,
+    output: &Output<'_>,
 ) {
     let message_id = msg.id();
-
-    let _ = execute_ingress_message(message_routing, msg, &message_id, ingress_hist_reader);
+    let usage_before = output.observe_usage();
+
+    let _ = execute_ingress_message(
+        message_routing,
+        msg,
+        &message_id,
+        ingress_hist_reader,
+        time_offset,
+    );
     // print result after waiting, to not interleave the result
     // with debug.print messages from subsequent calls. revise after DFN-1269.
-    wait_extra_batches(message_routing, extra_batches);
-    print_ingress_result(&message_id, ingress_hist_reader);
+    wait_extra_batches(message_routing, extra_batches, time_offset);
+    print_ingress_result(
+        &message_id,
+        ingress_hist_reader,
+        output,
+        output.observe_usage().since(usage_before),
+    );
+}
+
+/// Where and how the results of messages are printed.
+struct Output<'a> {
+    format: OutputFormat,
+    metrics_registry: &'a MetricsRegistry,
+    state_manager: &'a StateManagerImpl,
+}
+
+impl Output<'_> {
+    fn observe_usage(&self) -> ResourceUsage {
+        match self.format {
+            // Avoid the overhead of gathering metrics if they are not printed.
+            OutputFormat::Text => ResourceUsage::default(),
+            OutputFormat::Json => ResourceUsage::observe(self.metrics_registry, self.state_manager),
+        }
+    }
 }
 
 fn setup_logger(log_file: PathBuf) -> Logger {
@@ -161,6 +335,8 @@ pub fn run_drun(uo: DrunOptions) -> Result<(), String> {
         log_file,
         instruction_limit,
         subnet_type,
+        output_format,
+        wasm_profile,
     } = uo;
     // Hardcoded magic values to create a ReplicaConfig that parses.
     let mut subnet_config = SubnetConfig::new(subnet_type);
@@ -214,19 +390,20 @@ pub fn run_drun(uo: DrunOptions) -> Result<(), String> {
         None,
         ic_types::malicious_flags::MaliciousFlags::default(),
     ));
+    let execution_services = ExecutionServices::setup_execution(
+        log.clone().into(),
+        &metrics_registry,
+        replica_config.subnet_id,
+        subnet_type,
+        subnet_config.scheduler_config,
+        cfg.hypervisor.clone(),
+        Arc::clone(&cycles_account_manager),
+        Arc::clone(&state_manager) as Arc<_>,
+        state_manager.get_fd_factory(),
+    );
+    let wasm_profiles = Arc::clone(&execution_services.wasm_profiles);
     let (_, ingress_history_writer, ingress_hist_reader, query_handler, _, _, scheduler) =
-        ExecutionServices::setup_execution(
-            log.clone().into(),
-            &metrics_registry,
-            replica_config.subnet_id,
-            subnet_type,
-            subnet_config.scheduler_config,
-            cfg.hypervisor.clone(),
-            Arc::clone(&cycles_account_manager),
-            Arc::clone(&state_manager) as Arc<_>,
-            state_manager.get_fd_factory(),
-        )
-        .into_parts();
+        execution_services.into_parts();
 
     let _metrics_endpoint = MetricsHttpEndpoint::new_insecure(
         tokio::runtime::Handle::current(),
@@ -249,75 +426,114 @@ pub fn run_drun(uo: DrunOptions) -> Result<(), String> {
         MaliciousFlags::default(),
     );
 
-    msg_stream.try_for_each(|parse_result| {
+    let output = Output {
+        format: output_format,
+        metrics_registry: &metrics_registry,
+        state_manager: &state_manager,
+    };
+    let mut time_offset = Duration::ZERO;
+    let result = msg_stream.try_for_each(|parse_result| {
         parse_result.map(|msg| match msg {
-            Message::Install(msg) => {
-                deliver_message(
-                    msg,
-                    &message_routing,
-                    ingress_hist_reader.as_ref(),
-                    extra_batches,
-                );
-            }
-
             Message::Query(q) => {
+                let usage_before = output.observe_usage();
                 // NOTE: Data certificates aren't supported in drun yet.
                 // To support them, we'd need to do something similar to
                 // http_handler::get_latest_certified_state_and_data_certificate
-                print_query_result(query_handler.query(
-                    q,
-                    state_manager.get_latest_state(),
-                    Vec::new(),
-                ));
+                let result = query_handler.query(q, state_manager.get_latest_state(), Vec::new());
+                print_query_result(result, &output, output.observe_usage().since(usage_before));
             }
 
-            Message::Ingress(msg) => {
-                deliver_message(
-                    msg,
-                    &message_routing,
-                    ingress_hist_reader.as_ref(),
-                    extra_batches,
-                );
-            }
-            Message::Create(msg) => {
+            Message::Install(msg)
+            | Message::Ingress(msg)
+            | Message::Create(msg)
+            | Message::Management(msg) => {
                 deliver_message(
                     msg,
                     &message_routing,
                     ingress_hist_reader.as_ref(),
                     extra_batches,
+                    time_offset,
+                    &output,
                 );
             }
+
+            Message::AdvanceTime(duration) => time_offset += duration,
         })
-    })
+    });
+
+    if let Some(path) = wasm_profile {
+        write_wasm_profiles(&path, wasm_profiles.take())?;
+    }
+    result
 }
 
-fn print_query_result(res: Result<WasmResult, UserError>) {
-    match res {
-        Ok(payload) => {
-            print!("Ok: ");
-            print_wasm_result(payload);
+fn write_wasm_profiles(path: &Path, profiles: Vec<CanisterWasmProfile>) -> Result<(), String> {
+    let folded: String = profiles.iter().map(|profile| profile.to_folded()).collect();
+    let contents = match path.extension() {
+        Some(extension) if extension == "pb" => {
+            folded_to_pprof(&folded, "instructions", "count").map_err(|e| e.to_string())?
         }
-        Err(e) => println!("Err: {}", e),
+        _ => folded.into_bytes(),
+    };
+    std::fs::write(path, contents)
+        .map_err(|e| format!("Failed to write Wasm profile to {}: {}", path.display(), e))
+}
+
+fn print_query_result(
+    res: Result<WasmResult, UserError>,
+    output: &Output<'_>,
+    usage: ResourceUsage,
+) {
+    match output.format {
+        OutputFormat::Text => match res {
+            Ok(payload) => {
+                print!("Ok: ");
+                print_wasm_result(payload);
+            }
+            Err(e) => println!("Err: {}", e),
+        },
+        OutputFormat::Json => print_json(MessageOutput::new("query", res, usage)),
     }
 }
 
-fn print_ingress_result(message_id: &MessageId, ingress_hist_reader: &dyn IngressHistoryReader) {
+fn print_ingress_result(
+    message_id: &MessageId,
+    ingress_hist_reader: &dyn IngressHistoryReader,
+    output: &Output<'_>,
+    usage: ResourceUsage,
+) {
     let status = (ingress_hist_reader.get_latest_status())(message_id);
-    print!("ingress ");
-    match status {
+    let result = match status {
         IngressStatus::Known {
             state: IngressState::Completed(result),
             ..
-        } => {
-            print!("Completed: ");
-            print_wasm_result(result)
-        }
+        } => Ok(result),
         IngressStatus::Known {
             state: IngressState::Failed(error),
             ..
-        } => println!("Err: {}", error),
+        } => Err(error),
         _ => panic!("Ingress message has not finished processing."),
     };
+    match output.format {
+        OutputFormat::Text => {
+            print!("ingress ");
+            match result {
+                Ok(result) => {
+                    print!("Completed: ");
+                    print_wasm_result(result)
+                }
+                Err(error) => println!("Err: {}", error),
+            }
+        }
+        OutputFormat::Json => print_json(MessageOutput::new("ingress", result, usage)),
+    }
+}
+
+fn print_json(output: MessageOutput) {
+    println!(
+        "{}",
+        serde_json::to_string(&output).expect("Failed to serialize message output")
+    );
 }
 
 fn print_wasm_result(wasm_result: WasmResult) {
@@ -334,7 +550,11 @@ fn get_random_seed() -> [u8; 32] {
     seed.try_into().unwrap()
 }
 
-fn build_batch(message_routing: &dyn MessageRouting, msgs: Vec<SignedIngress>) -> Batch {
+fn build_batch(
+    message_routing: &dyn MessageRouting,
+    msgs: Vec<SignedIngress>,
+    time_offset: Duration,
+) -> Batch {
     Batch {
         batch_number: message_routing.expected_batch_height(),
         requires_full_state_hash: !msgs.is_empty(),
@@ -344,9 +564,10 @@ fn build_batch(message_routing: &dyn MessageRouting, msgs: Vec<SignedIngress>) -
         },
         randomness: Randomness::from(get_random_seed()),
         ecdsa_subnet_public_keys: BTreeMap::new(),
+        schnorr_subnet_public_keys: BTreeMap::new(),
         ecdsa_quadruple_ids: BTreeMap::new(),
         registry_version: RegistryVersion::from(1),
-        time: time::current_time(),
+        time: time::current_time() + time_offset,
         consensus_responses: vec![],
         blockmaker_metrics: BlockmakerMetrics::new_for_test(),
     }
@@ -360,8 +581,9 @@ fn execute_ingress_message(
     msg: SignedIngress,
     msg_id: &MessageId,
     ingress_history: &dyn IngressHistoryReader,
+    time_offset: Duration,
 ) -> Result<WasmResult, UserError> {
-    let mut batch = build_batch(message_routing, vec![msg]);
+    let mut batch = build_batch(message_routing, vec![msg], time_offset);
     for _ in 0..MAX_BATCHES_UNTIL_RESPONSE {
         // In the first batch we try to send the ingress message itself. If it fails, we
         // repeat with the same batch.
@@ -372,7 +594,7 @@ fn execute_ingress_message(
         // potential inter-canister messages that the ingress message may have
         // triggered.
         if message_routing.deliver_batch(batch.clone()).is_ok() {
-            batch = build_batch(message_routing, vec![])
+            batch = build_batch(message_routing, vec![], time_offset)
         }
         sleep(WAIT_PER_BATCH);
 
@@ -418,10 +640,14 @@ fn execute_ingress_message(
 ///
 /// This is a temporary measure until DFN-1269 is resolved. In that ticket, we
 /// will actually try to wait until all messages have been executed.
-fn wait_extra_batches(message_routing: &dyn MessageRouting, extra_batches: u64) {
+fn wait_extra_batches(
+    message_routing: &dyn MessageRouting,
+    extra_batches: u64,
+    time_offset: Duration,
+) {
     for _ in 0..extra_batches {
         loop {
-            let batch = build_batch(message_routing, vec![]);
+            let batch = build_batch(message_routing, vec![], time_offset);
             let ok = message_routing.deliver_batch(batch).is_ok();
             sleep(WAIT_PER_BATCH);
             if ok {
@@ -447,4 +673,24 @@ mod tests {
         }
         assert_ne!(equal, len);
     }
+
+    #[test]
+    fn test_json_message_output() {
+        let usage = ResourceUsage {
+            instructions: 1000,
+            cycles: 50,
+        };
+        let output = MessageOutput::new("query", Ok(WasmResult::Reply(vec![1, 2])), usage);
+        assert_eq!(
+            serde_json::to_string(&output).unwrap(),
+            r#"{"type":"query","status":"replied","reply":"0x0102","instructions":1000,"cycles":50}"#
+        );
+
+        let error = UserError::new(ErrorCode::CanisterCalledTrap, "trapped");
+        let output = MessageOutput::new("ingress", Err(error), usage);
+        assert_eq!(
+            serde_json::to_string(&output).unwrap(),
+            r#"{"type":"ingress","status":"failed","reject_code":5,"reject_message":"trapped","error_code":"IC0503","instructions":1000,"cycles":50}"#
+        );
+    }
 }
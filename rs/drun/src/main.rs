@@ -4,7 +4,7 @@ use ic_canister_sandbox_backend_lib::{
     RUN_AS_SANDBOX_LAUNCHER_FLAG,
 };
 use ic_config::{embedders::MeteringType, flag_status::FlagStatus, Config, ConfigSource};
-use ic_drun::{run_drun, DrunOptions};
+use ic_drun::{run_drun, DrunOptions, OutputFormat};
 use ic_registry_subnet_type::SubnetType;
 use std::path::PathBuf;
 
@@ -16,7 +16,9 @@ const ARG_MESSAGES: &str = "messages";
 const ARG_EXTRA_BATCHES: &str = "extra-batches";
 const ARG_INSTRUCTION_LIMIT: &str = "instruction-limit";
 const ARG_SUBNET_TYPE: &str = "subnet-type";
+const ARG_OUTPUT_FORMAT: &str = "output-format";
 const USE_OLD_METERING: &str = "use-old-metering";
+const ARG_WASM_PROFILE: &str = "wasm-profile";
 
 fn main() -> Result<(), String> {
     // Check if `drun` is running in the canister sandbox mode where it waits
@@ -86,6 +88,16 @@ async fn drun_main() -> Result<(), String> {
             })
             .unwrap_or(SubnetType::System);
 
+        let output_format = matches
+            .value_of(ARG_OUTPUT_FORMAT)
+            .map(|arg| {
+                arg.parse().unwrap_or_else(|err| {
+                    eprintln!("Failed to parse {}\n  {}", ARG_OUTPUT_FORMAT, err);
+                    std::process::exit(1);
+                })
+            })
+            .unwrap_or(OutputFormat::Text);
+
         let use_old_metering = matches.get_flag(USE_OLD_METERING);
         cfg.hypervisor.embedders_config.metering_type = if use_old_metering {
             MeteringType::Old
@@ -93,6 +105,11 @@ async fn drun_main() -> Result<(), String> {
             MeteringType::New
         };
 
+        let wasm_profile = matches.value_of(ARG_WASM_PROFILE).map(PathBuf::from);
+        if wasm_profile.is_some() {
+            cfg.hypervisor.embedders_config.wasm_profiling = FlagStatus::Enabled;
+        }
+
         let uo = DrunOptions {
             msg_filename: matches.value_of(ARG_MESSAGES).unwrap().to_string(),
             cfg,
@@ -100,6 +117,8 @@ async fn drun_main() -> Result<(), String> {
             log_file,
             instruction_limit,
             subnet_type,
+            output_format,
+            wasm_profile,
         };
         run_drun(uo)
     })
@@ -162,11 +181,31 @@ fn get_arg_matches() -> ArgMatches {
                 .value_name("Subnet Type")
                 .takes_value(true),
         )
+        .arg(
+            Arg::new(ARG_OUTPUT_FORMAT)
+                .long(ARG_OUTPUT_FORMAT)
+                .help(
+                    "Print the result of every message as `text` (default) or as a line of `json`.",
+                )
+                .value_name("Output Format")
+                .takes_value(true),
+        )
         .arg(
             Arg::new(USE_OLD_METERING)
                 .long(USE_OLD_METERING)
                 .help("Enable the old metering in the local canister execution environment.")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new(ARG_WASM_PROFILE)
+                .long(ARG_WASM_PROFILE)
+                .help(
+                    "Profile the executed Wasm functions and write the instructions per call \
+                     stack to the given file, as a pprof protobuf if it ends with `.pb` and in \
+                     the folded stack format otherwise.",
+                )
+                .value_name("Wasm Profile")
+                .takes_value(true),
+        )
         .get_matches()
 }
@@ -1,7 +1,10 @@
 use super::CanisterId;
 
 use hex::decode;
-use ic_ic00_types::{self as ic00, CanisterInstallMode, Payload};
+use ic_ic00_types::{
+    self as ic00, CanisterIdRecord, CanisterInstallMode, CanisterSettingsArgsBuilder, Payload,
+    ProvisionalTopUpCanisterArgs, UpdateSettingsArgs,
+};
 use ic_types::{
     messages::{SignedIngress, UserQuery},
     time::expiry_time_from_now,
@@ -13,8 +16,9 @@ use std::{
     fmt,
     fs::File,
     io::{self, Read},
-    str::Chars,
+    str::{Chars, FromStr},
     string::FromUtf8Error,
+    time::Duration,
 };
 
 #[derive(Debug, PartialEq)]
@@ -23,6 +27,9 @@ pub(crate) enum Message {
     Query(UserQuery),
     Install(SignedIngress),
     Create(SignedIngress),
+    /// Any other call to the management canister, e.g., to stop a canister.
+    Management(SignedIngress),
+    AdvanceTime(Duration),
 }
 
 #[derive(Debug)]
@@ -146,6 +153,7 @@ pub(crate) fn msg_stream_from_file(
 
 fn parse_message(s: &str, nonce: u64) -> Result<Message, String> {
     let s = s.trim_end();
+    let (sender, s) = parse_sender(s)?;
     let tokens: Vec<&str> = s.splitn(4, char::is_whitespace).collect();
 
     match &tokens[..] {
@@ -160,6 +168,7 @@ fn parse_message(s: &str, nonce: u64) -> Result<Message, String> {
             let signed_ingress = SignedIngressBuilder::new()
                 // `source` should become a self-authenticating id according
                 // to https://sdk.dfinity.org/docs/interface-spec/index.html#id-classes
+                .sender(sender)
                 .canister_id(canister_id)
                 .method_name(method_name)
                 .method_payload(method_payload)
@@ -168,23 +177,55 @@ fn parse_message(s: &str, nonce: u64) -> Result<Message, String> {
             Ok(Message::Ingress(signed_ingress))
         }
         ["query", canister_id, method_name, payload] => Ok(Message::Query(UserQuery {
-            source: UserId::from(PrincipalId::new_anonymous()),
+            source: sender,
             receiver: parse_canister_id(canister_id)?,
             method_name: validate_method_name(method_name)?,
             method_payload: parse_octet_string(payload)?,
             ingress_expiry: expiry_time_from_now().as_nanos_since_unix_epoch(),
             nonce: Some(nonce.to_le_bytes().to_vec()),
         })),
-        ["create"] => parse_create(nonce),
+        ["create"] => parse_create(sender, nonce),
         ["install", canister_id, wasm_file, payload] => {
-            parse_install(nonce, canister_id, payload, wasm_file, "install")
+            parse_install(sender, nonce, canister_id, payload, wasm_file, "install")
         }
         ["reinstall", canister_id, wasm_file, payload] => {
-            parse_install(nonce, canister_id, payload, wasm_file, "reinstall")
+            parse_install(sender, nonce, canister_id, payload, wasm_file, "reinstall")
         }
         ["upgrade", canister_id, wasm_file, payload] => {
-            parse_install(nonce, canister_id, payload, wasm_file, "upgrade")
+            parse_install(sender, nonce, canister_id, payload, wasm_file, "upgrade")
+        }
+        ["update_settings", canister_id, settings @ ..] => {
+            parse_update_settings(sender, nonce, canister_id, &settings.join(" "))
         }
+        ["stop", canister_id] => Ok(management_message(
+            sender,
+            nonce,
+            ic00::Method::StopCanister,
+            CanisterIdRecord::from(parse_canister_id(canister_id)?).encode(),
+        )),
+        ["start", canister_id] => Ok(management_message(
+            sender,
+            nonce,
+            ic00::Method::StartCanister,
+            CanisterIdRecord::from(parse_canister_id(canister_id)?).encode(),
+        )),
+        ["delete", canister_id] => Ok(management_message(
+            sender,
+            nonce,
+            ic00::Method::DeleteCanister,
+            CanisterIdRecord::from(parse_canister_id(canister_id)?).encode(),
+        )),
+        ["top_up", canister_id, amount] => Ok(management_message(
+            sender,
+            nonce,
+            ic00::Method::ProvisionalTopUpCanister,
+            ProvisionalTopUpCanisterArgs::new(
+                parse_canister_id(canister_id)?,
+                parse_number("amount", amount)?,
+            )
+            .encode(),
+        )),
+        ["advance_time", duration] => Ok(Message::AdvanceTime(parse_duration(duration)?)),
         _ => Err(format!(
             "Failed to parse line {}, don't have a pattern to match this with",
             s
@@ -192,21 +233,88 @@ fn parse_message(s: &str, nonce: u64) -> Result<Message, String> {
     }
 }
 
-fn parse_canister_id(canister_id: &str) -> Result<CanisterId, String> {
-    use std::str::FromStr;
-    match PrincipalId::from_str(canister_id) {
-        Ok(id) => Ok(CanisterId::unchecked_from_principal(id)),
-        Err(err) => Err(format!(
+/// Strips the optional `--sender <principal>` following the message type from
+/// the line. Messages are sent by the anonymous principal by default.
+fn parse_sender(s: &str) -> Result<(UserId, String), String> {
+    let mut tokens = s.splitn(4, char::is_whitespace);
+    match (tokens.next(), tokens.next(), tokens.next(), tokens.next()) {
+        (Some(kind), Some("--sender"), Some(sender), rest) => {
+            let sender = UserId::from(parse_principal(sender)?);
+            let line = std::iter::once(kind).chain(rest).collect::<Vec<_>>();
+            Ok((sender, line.join(" ")))
+        }
+        (Some(_), Some("--sender"), None, _) => Err("Missing sender principal.".to_string()),
+        _ => Ok((UserId::from(PrincipalId::new_anonymous()), s.to_string())),
+    }
+}
+
+fn parse_principal(principal_id: &str) -> Result<PrincipalId, String> {
+    PrincipalId::from_str(principal_id).map_err(|err| {
+        format!(
             "Failed to convert {} to principal id with {}",
-            canister_id, err
+            principal_id, err
+        )
+    })
+}
+
+fn parse_canister_id(canister_id: &str) -> Result<CanisterId, String> {
+    parse_principal(canister_id).map(CanisterId::unchecked_from_principal)
+}
+
+fn parse_number<T: FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Illegal value {} for {}.", value, name))
+}
+
+fn parse_duration(duration: &str) -> Result<Duration, String> {
+    let unit_start = duration
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(duration.len());
+    let (value, unit) = duration.split_at(unit_start);
+    let value: u64 = parse_number("duration", value)?;
+    let secs = |factor: u64| {
+        value
+            .checked_mul(factor)
+            .map(Duration::from_secs)
+            .ok_or_else(|| format!("Duration {} is too large.", duration))
+    };
+    match unit {
+        "ns" => Ok(Duration::from_nanos(value)),
+        "ms" => Ok(Duration::from_millis(value)),
+        "s" => Ok(Duration::from_secs(value)),
+        "m" => secs(60),
+        "h" => secs(60 * 60),
+        _ => Err(format!(
+            "Illegal duration {}, expected one of the units ns, ms, s, m or h.",
+            duration
         )),
     }
 }
 
-fn parse_create(nonce: u64) -> Result<Message, String> {
+fn management_message(
+    sender: UserId,
+    nonce: u64,
+    method: ic00::Method,
+    payload: Vec<u8>,
+) -> Message {
+    use ic_test_utilities::types::messages::SignedIngressBuilder;
+
+    let signed_ingress = SignedIngressBuilder::new()
+        .sender(sender)
+        .canister_id(ic00::IC_00)
+        .method_name(method)
+        .method_payload(payload)
+        .nonce(nonce)
+        .build();
+    Message::Management(signed_ingress)
+}
+
+fn parse_create(sender: UserId, nonce: u64) -> Result<Message, String> {
     use ic_test_utilities::types::messages::SignedIngressBuilder;
 
     let signed_ingress = SignedIngressBuilder::new()
+        .sender(sender)
         .method_name(ic00::Method::ProvisionalCreateCanisterWithCycles)
         .canister_id(ic00::IC_00)
         .method_payload(ic00::ProvisionalCreateCanisterWithCyclesArgs::new(None, None).encode())
@@ -216,7 +324,47 @@ fn parse_create(nonce: u64) -> Result<Message, String> {
     Ok(Message::Create(signed_ingress))
 }
 
+/// Parses a whitespace separated list of `<name>=<value>` canister settings.
+fn parse_update_settings(
+    sender: UserId,
+    nonce: u64,
+    canister_id: &str,
+    settings: &str,
+) -> Result<Message, String> {
+    let canister_id = parse_canister_id(canister_id)?;
+    let mut builder = CanisterSettingsArgsBuilder::new();
+    for setting in settings.split_whitespace() {
+        let (name, value) = setting
+            .split_once('=')
+            .ok_or_else(|| format!("Illegal setting {}, expected <name>=<value>.", setting))?;
+        builder = match name {
+            "controllers" => builder.with_controllers(
+                value
+                    .split(',')
+                    .filter(|controller| !controller.is_empty())
+                    .map(parse_principal)
+                    .collect::<Result<_, _>>()?,
+            ),
+            "compute_allocation" => builder.with_compute_allocation(parse_number(name, value)?),
+            "memory_allocation" => builder.with_memory_allocation(parse_number(name, value)?),
+            "freezing_threshold" => builder.with_freezing_threshold(parse_number(name, value)?),
+            "reserved_cycles_limit" => {
+                builder.with_reserved_cycles_limit(parse_number(name, value)?)
+            }
+            "wasm_memory_limit" => builder.with_wasm_memory_limit(parse_number(name, value)?),
+            _ => return Err(format!("Unknown canister setting {}.", name)),
+        };
+    }
+    Ok(management_message(
+        sender,
+        nonce,
+        ic00::Method::UpdateSettings,
+        UpdateSettingsArgs::new(canister_id, builder.build()).encode(),
+    ))
+}
+
 fn parse_install(
+    sender: UserId,
     nonce: u64,
     canister_id: &str,
     payload: &str,
@@ -238,6 +386,7 @@ fn parse_install(
     let signed_ingress = SignedIngressBuilder::new()
         // `source` should become a self-authenticating id according
         // to https://sdk.dfinity.org/docs/interface-spec/index.html#id-classes
+        .sender(sender)
         .canister_id(ic00::IC_00)
         .method_name(ic00::Method::InstallCode)
         .method_payload(
@@ -280,11 +429,21 @@ fn validate_method_name(method_name: &str) -> Result<String, String> {
 fn parse_octet_string(input_str: &str) -> Result<Vec<u8>, String> {
     if input_str.starts_with('"') {
         parse_quoted(input_str)
+    } else if input_str.starts_with('(') {
+        parse_candid(input_str)
     } else {
         parse_hex(input_str)
     }
 }
 
+/// Encodes arguments given in the Candid textual format, e.g., `(42, "foo")`.
+fn parse_candid(s: &str) -> Result<Vec<u8>, String> {
+    let args = candid_parser::parse_idl_args(s)
+        .map_err(|e| format!("Failed to parse Candid arguments {}: {}", s, e))?;
+    args.to_bytes()
+        .map_err(|e| format!("Failed to encode Candid arguments {}: {}", s, e))
+}
+
 fn parse_quoted(quoted_str: &str) -> Result<Vec<u8>, String> {
     if !quoted_str.is_ascii() {
         return Err(String::from("Only ASCII strings are allowed."));
@@ -464,6 +623,70 @@ mod tests {
         assert!(parse_message(s, 0).is_err());
     }
 
+    #[test]
+    fn test_parse_message_with_sender_and_candid_payload_succeeds() {
+        let sender = PrincipalId::new_user_test_id(7);
+        let s = &format!(
+            "query --sender {} {} read (42 : nat8, \"foo\")",
+            sender, APP_CANISTER_URL
+        );
+        match parse_message(s, 0).unwrap() {
+            Message::Query(query) => {
+                assert_eq!(query.source, UserId::from(sender));
+                assert_eq!(query.receiver, canister_test_id(APP_CANISTER_ID));
+                assert_eq!(
+                    query.method_payload,
+                    candid::Encode!(&42_u8, &"foo").unwrap()
+                );
+            }
+            parsed_message => panic!(
+                "parse_message() returned an unexpected message type: {:?}",
+                parsed_message
+            ),
+        }
+
+        let s = &format!("ingress --sender {} write 0x00", APP_CANISTER_URL);
+        assert!(parse_message(s, 0).is_err());
+    }
+
+    #[test]
+    fn test_parse_management_messages() {
+        for s in [
+            format!("stop {}", APP_CANISTER_URL),
+            format!("start {}", APP_CANISTER_URL),
+            format!("delete {}", APP_CANISTER_URL),
+            format!("top_up {} 1000000", APP_CANISTER_URL),
+            format!(
+                "update_settings {} freezing_threshold=0 controllers={}",
+                APP_CANISTER_URL, APP_CANISTER_URL
+            ),
+        ] {
+            assert!(matches!(
+                parse_message(&s, 0).unwrap(),
+                Message::Management(_)
+            ));
+        }
+
+        let s = &format!("update_settings {} freezing=0", APP_CANISTER_URL);
+        assert!(parse_message(s, 0).is_err());
+    }
+
+    #[test]
+    fn test_parse_advance_time() {
+        assert_eq!(
+            parse_message("advance_time 5s", 0).unwrap(),
+            Message::AdvanceTime(Duration::from_secs(5))
+        );
+        assert_eq!(
+            parse_message("advance_time 2h", 0).unwrap(),
+            Message::AdvanceTime(Duration::from_secs(7200))
+        );
+        assert!(parse_message("advance_time 5", 0).is_err());
+        assert!(parse_message("advance_time s", 0).is_err());
+        assert!(parse_message(&format!("advance_time {}m", u64::MAX), 0).is_err());
+        assert!(parse_message(&format!("advance_time {}h", u64::MAX / 60), 0).is_err());
+    }
+
     #[test]
     fn test_line_iterator() {
         let text = Cursor::new(
@@ -66,6 +66,8 @@ fuzz_target!(|module: ConfiguredModule<ICWasmConfig>| {
             rate_limiting_of_debug_prints: FlagStatus::Enabled,
             wasm_native_stable_memory: FlagStatus::Enabled,
             write_barrier: FlagStatus::Enabled,
+            best_effort_responses: FlagStatus::Enabled,
+            wasm64: FlagStatus::Disabled,
         },
         ..Default::default()
     };
@@ -111,9 +111,9 @@ impl Config for ICWasmConfig {
         false
     }
 
-    // SIMD is disabled
+    // Fixed-width SIMD is enabled
     fn simd_enabled(&self) -> bool {
-        false
+        true
     }
 
     // Canonicalize NaNs is disabled
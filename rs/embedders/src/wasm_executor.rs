@@ -2,7 +2,7 @@ use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::Arc;
 
-use ic_replicated_state::canister_state::execution_state::WasmBinary;
+use ic_replicated_state::canister_state::{execution_state::WasmBinary, WASM_PAGE_SIZE_IN_BYTES};
 use ic_replicated_state::page_map::PageAllocatorFileDescriptor;
 use ic_replicated_state::{ExportedFunctions, Global, Memory, NumWasmPages, PageMap};
 use ic_system_api::sandbox_safe_system_state::{SandboxSafeSystemState, SystemStateChanges};
@@ -29,7 +29,10 @@ use ic_metrics::MetricsRegistry;
 use ic_replicated_state::{EmbedderCache, ExecutionState};
 use ic_sys::{page_bytes_from_ptr, PageBytes, PageIndex, PAGE_SIZE};
 use ic_system_api::{ExecutionParameters, ModificationTracking, SystemApiImpl};
-use ic_types::{CanisterId, NumBytes, NumInstructions};
+use ic_types::{
+    CanisterId, MemoryAllocation, NumBytes, NumInstructions, MAX_WASM64_MEMORY_IN_BYTES,
+    WASM64_EXTRA_MEMORY_IN_BYTES,
+};
 use ic_wasm_types::{BinaryEncodedWasm, CanisterModule};
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
@@ -455,6 +458,8 @@ pub fn wasm_execution_error(
             allocated_message_bytes: NumBytes::from(0),
             instance_stats: InstanceStats::default(),
             system_api_call_counters: SystemApiCallCounters::default(),
+            canister_log: Default::default(),
+            wasm_profile: None,
         },
         None,
     )
@@ -561,7 +566,7 @@ pub fn process(
     api_type: ApiType,
     canister_current_memory_usage: NumBytes,
     canister_current_message_memory_usage: NumBytes,
-    execution_parameters: ExecutionParameters,
+    mut execution_parameters: ExecutionParameters,
     subnet_available_memory: SubnetAvailableMemory,
     sandbox_safe_system_state: SandboxSafeSystemState,
     embedder_cache: &EmbedderCache,
@@ -580,6 +585,14 @@ pub fn process(
 ) {
     let canister_id = sandbox_safe_system_state.canister_id();
     let modification_tracking = api_type.modification_tracking();
+    // The default canister memory limit accounts for a 32-bit heap. Canisters
+    // with a 64-bit heap may use the additional memory of their larger heap.
+    if embedder.config().feature_flags.wasm64 == FlagStatus::Enabled
+        && execution_parameters.memory_allocation == MemoryAllocation::BestEffort
+        && WasmtimeEmbedder::is_wasm64(embedder_cache)
+    {
+        execution_parameters.canister_memory_limit += NumBytes::new(WASM64_EXTRA_MEMORY_IN_BYTES);
+    }
     let system_api = SystemApiImpl::new(
         api_type,
         sandbox_safe_system_state,
@@ -608,6 +621,8 @@ pub fn process(
     ) {
         Ok(instance) => instance,
         Err((err, system_api)) => {
+            // should be safe because we've passed Some(api) to new_instance
+            let mut system_api = system_api.unwrap();
             return (
                 SliceExecutionOutput {
                     executed_instructions: NumInstructions::from(0),
@@ -619,9 +634,11 @@ pub fn process(
                     allocated_message_bytes: NumBytes::from(0),
                     instance_stats: InstanceStats::default(),
                     system_api_call_counters: SystemApiCallCounters::default(),
+                    canister_log: system_api.take_canister_log(),
+                    wasm_profile: None,
                 },
                 None,
-                Err(system_api.unwrap()), // should be safe because we've passed Some(api) to new_instance
+                Err(system_api),
             );
         }
     };
@@ -637,9 +654,12 @@ pub fn process(
     // Get the executed/remaining instructions for the message and the slice.
     let instruction_counter = instance.instruction_counter();
     let instance_stats = instance.get_stats();
+    let wasm_profiler = instance.store_data_mut().wasm_profiler.take();
     //unwrap should not fail, because we have passed Some(system_api) to the instance above
     let system_api = instance.store_data_mut().system_api_mut().unwrap();
     let system_api_call_counters = system_api.call_counters();
+    // The log records are returned even if the execution failed.
+    let canister_log = system_api.take_canister_log();
     let slice_instruction_limit = system_api.slice_instruction_limit();
     // Capping at the limit to preserve the existing behaviour. It should be
     // possible to remove capping after ensuring that all callers can handle
@@ -653,6 +673,8 @@ pub fn process(
         .message_instructions_executed(instruction_counter)
         .min(message_instruction_limit);
     let message_instructions_left = message_instruction_limit - message_instructions_executed;
+    let wasm_profile =
+        wasm_profiler.map(|profiler| profiler.finish(message_instructions_executed.get()));
 
     // In case the message dirtied too many pages, as a performance optimization we will
     // yield the control to the replica and then resume copying dirty pages in a new execution slice.
@@ -676,6 +698,8 @@ pub fn process(
                         allocated_message_bytes: NumBytes::from(0),
                         instance_stats,
                         system_api_call_counters,
+                        canister_log,
+                        wasm_profile,
                     },
                     None,
                     Ok(instance),
@@ -700,8 +724,12 @@ pub fn process(
     let mut wasm_result = system_api.take_execution_result(run_result.as_ref().err());
 
     let wasm_heap_size_after = instance.heap_size(CanisterMemoryType::Heap);
-    let wasm_heap_limit =
-        NumWasmPages::from(wasmtime_environ::WASM32_MAX_PAGES as usize) - wasm_reserved_pages;
+    let wasm_heap_max_pages = if instance.is_wasm64() {
+        (MAX_WASM64_MEMORY_IN_BYTES / WASM_PAGE_SIZE_IN_BYTES as u64) as usize
+    } else {
+        wasmtime_environ::WASM32_MAX_PAGES as usize
+    };
+    let wasm_heap_limit = NumWasmPages::from(wasm_heap_max_pages) - wasm_reserved_pages;
 
     if wasm_heap_size_after > wasm_heap_limit {
         wasm_result = Err(HypervisorError::WasmReservedPages);
@@ -780,6 +808,8 @@ pub fn process(
             allocated_message_bytes,
             instance_stats,
             system_api_call_counters,
+            canister_log,
+            wasm_profile,
         },
         wasm_state_changes,
         Ok(instance),
@@ -1,5 +1,5 @@
 use std::{
-    collections::{BTreeSet, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     time::Instant,
 };
 
@@ -38,6 +38,37 @@ pub struct WasmImportsDetails {
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Default)]
 pub struct Complexity(pub u64);
 
+/// The index type of the main memory of a Wasm module. It determines the type
+/// of the heap addresses and sizes passed to the System API.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum WasmMemoryType {
+    #[default]
+    Wasm32,
+    Wasm64,
+}
+
+impl WasmMemoryType {
+    /// Returns the memory type of the first memory in the module, which is the
+    /// canister heap. Imported memories come first in the index space. Modules
+    /// without a memory are treated as 32-bit.
+    pub(crate) fn of_module(module: &ic_wasm_transform::Module) -> Self {
+        let memory64 = module
+            .imports
+            .iter()
+            .find_map(|import| match import.ty {
+                wasmparser::TypeRef::Memory(memory) => Some(memory.memory64),
+                _ => None,
+            })
+            .or_else(|| module.memories.first().map(|memory| memory.memory64))
+            .unwrap_or(false);
+        if memory64 {
+            Self::Wasm64
+        } else {
+            Self::Wasm32
+        }
+    }
+}
+
 /// Returned as a result of `validate_wasm_binary` and provides
 /// additional information about the validation.
 #[derive(Debug, PartialEq, Eq, Default)]
@@ -46,6 +77,7 @@ pub struct WasmValidationDetails {
     pub wasm_metadata: WasmMetadata,
     pub largest_function_instruction_count: NumInstructions,
     pub max_complexity: Complexity,
+    pub wasm_memory_type: WasmMemoryType,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -209,6 +241,7 @@ fn validate_and_instrument(
         config.metering_type,
         config.subnet_type,
         config.dirty_page_overhead,
+        config.wasm_profiling,
     )?;
     Ok((wasm_validation_details, instrumentation_output))
 }
@@ -261,3 +294,25 @@ pub fn compile(
     };
     (EmbedderCache::new(cache), result)
 }
+
+/// Returns the function names from the `name` custom section of the given
+/// module. Used to symbolize the function indices of Wasm profiles.
+pub fn function_names(wasm: &BinaryEncodedWasm) -> BTreeMap<u32, String> {
+    let mut names = BTreeMap::new();
+    for payload in wasmparser::Parser::new(0).parse_all(wasm.as_slice()) {
+        let section = match payload {
+            Ok(wasmparser::Payload::CustomSection(section)) if section.name() == "name" => section,
+            Ok(_) => continue,
+            Err(_) => break,
+        };
+        let reader = wasmparser::NameSectionReader::new(section.data(), section.data_offset());
+        for name in reader.into_iter().flatten() {
+            if let wasmparser::Name::Function(map) = name {
+                for naming in map.into_iter().flatten() {
+                    names.insert(naming.index, naming.name.to_string());
+                }
+            }
+        }
+    }
+    names
+}
@@ -32,6 +32,13 @@
 //! ```
 //! Where the last three will only be inserted if Wasm-native stable memory is enabled.
 //!
+//! If Wasm profiling is enabled, two more functions are inserted after them:
+//!
+//! ```wasm
+//! (import "__" "profile_enter" (func ((param i32))))
+//! (import "__" "profile_exit" (func ((param i32))))
+//! ```
+//!
 //! It then inserts (and exports) a global mutable counter:
 //! ```wasm
 //! (global (;0;) (mut i64) (i64.const 0))
@@ -109,16 +116,25 @@
 //! (memory (export "stable_memory_bytemap") i32 (i64.const STABLE_BYTEMAP_SIZE) (i64.const STABLE_BYTEMAP_SIZE))
 //! ```
 //!
+//! # Wasm profiling
+//!
+//! When Wasm profiling is enabled, every function of the original module calls
+//! `profile_enter` with its original function index on entry and
+//! `profile_exit` before every `return` and at the end of its body. The body
+//! is wrapped in a block so that branches to the function label also pass
+//! through the exit call. These calls are inserted after metering and are
+//! therefore not charged.
+//!
 
 use super::system_api_replacements::replacement_functions;
 use super::validation::API_VERSION_IC0;
-use super::{InstrumentationOutput, Segments, SystemApiFunc};
+use super::{InstrumentationOutput, Segments, SystemApiFunc, WasmMemoryType};
 use ic_config::embedders::MeteringType;
 use ic_config::flag_status::FlagStatus;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::NumWasmPages;
 use ic_sys::PAGE_SIZE;
-use ic_types::{methods::WasmMethod, MAX_WASM_MEMORY_IN_BYTES};
+use ic_types::{methods::WasmMethod, MAX_WASM64_MEMORY_IN_BYTES, MAX_WASM_MEMORY_IN_BYTES};
 use ic_types::{NumInstructions, MAX_STABLE_MEMORY_IN_BYTES};
 use ic_wasm_types::{BinaryEncodedWasm, WasmError, WasmInstrumentationError};
 use wasmtime_environ::WASM_PAGE_SIZE;
@@ -146,7 +162,17 @@ pub(crate) enum InjectedImports {
 }
 
 impl InjectedImports {
-    fn count(wasm_native_stable_memory: FlagStatus) -> usize {
+    fn count(wasm_native_stable_memory: FlagStatus, wasm_profiling: FlagStatus) -> usize {
+        let profiling = match wasm_profiling {
+            FlagStatus::Enabled => 2,
+            FlagStatus::Disabled => 0,
+        };
+        Self::profile_enter_index(wasm_native_stable_memory) as usize + profiling
+    }
+
+    // The profiling imports follow all other injected imports, so their
+    // indices depend on whether Wasm-native stable memory is enabled.
+    fn profile_enter_index(wasm_native_stable_memory: FlagStatus) -> u32 {
         if wasm_native_stable_memory == FlagStatus::Enabled {
             5
         } else {
@@ -173,7 +199,7 @@ pub fn instruction_to_cost(i: &Operator) -> u64 {
 // Gets the cost of an instruction.
 pub fn instruction_to_cost_new(i: &Operator) -> u64 {
     // This aims to be a complete list of all instructions that can be executed, with certain exceptions.
-    // The exceptions are: SIMD instructions, atomic instructions, and the dynamic cost of
+    // The exceptions are: atomic instructions, and the dynamic cost of
     // of operations such as table/memory fill, copy, init. This
     // dynamic cost is treated separately. Here we only assign a static cost to these instructions.
     match i {
@@ -450,6 +476,260 @@ pub fn instruction_to_cost_new(i: &Operator) -> u64 {
         // translated to memory manipulation. Validated in benchmarks.
         Operator::RefFunc { .. } => 130,
 
+        // SIMD instructions.
+        // Vector loads and stores, including the lane and splat variants, are
+        // of cost 1, the same as scalar loads and stores.
+        Operator::V128Load { .. }
+        | Operator::V128Load8x8S { .. }
+        | Operator::V128Load8x8U { .. }
+        | Operator::V128Load16x4S { .. }
+        | Operator::V128Load16x4U { .. }
+        | Operator::V128Load32x2S { .. }
+        | Operator::V128Load32x2U { .. }
+        | Operator::V128Load8Splat { .. }
+        | Operator::V128Load16Splat { .. }
+        | Operator::V128Load32Splat { .. }
+        | Operator::V128Load64Splat { .. }
+        | Operator::V128Load32Zero { .. }
+        | Operator::V128Load64Zero { .. }
+        | Operator::V128Load8Lane { .. }
+        | Operator::V128Load16Lane { .. }
+        | Operator::V128Load32Lane { .. }
+        | Operator::V128Load64Lane { .. }
+        | Operator::V128Store { .. }
+        | Operator::V128Store8Lane { .. }
+        | Operator::V128Store16Lane { .. }
+        | Operator::V128Store32Lane { .. }
+        | Operator::V128Store64Lane { .. } => 1,
+
+        // Vector constants, lane accesses, splats and shuffles boil down to
+        // register moves and byte permutations, so they are of cost 1.
+        Operator::V128Const { .. }
+        | Operator::I8x16Shuffle { .. }
+        | Operator::I8x16Swizzle { .. }
+        | Operator::I8x16Splat { .. }
+        | Operator::I16x8Splat { .. }
+        | Operator::I32x4Splat { .. }
+        | Operator::I64x2Splat { .. }
+        | Operator::F32x4Splat { .. }
+        | Operator::F64x2Splat { .. }
+        | Operator::I8x16ExtractLaneS { .. }
+        | Operator::I8x16ExtractLaneU { .. }
+        | Operator::I8x16ReplaceLane { .. }
+        | Operator::I16x8ExtractLaneS { .. }
+        | Operator::I16x8ExtractLaneU { .. }
+        | Operator::I16x8ReplaceLane { .. }
+        | Operator::I32x4ExtractLane { .. }
+        | Operator::I32x4ReplaceLane { .. }
+        | Operator::I64x2ExtractLane { .. }
+        | Operator::I64x2ReplaceLane { .. }
+        | Operator::F32x4ExtractLane { .. }
+        | Operator::F32x4ReplaceLane { .. }
+        | Operator::F64x2ExtractLane { .. }
+        | Operator::F64x2ReplaceLane { .. } => 1,
+
+        // Bitwise vector operations are of cost 1.
+        Operator::V128Not { .. }
+        | Operator::V128And { .. }
+        | Operator::V128AndNot { .. }
+        | Operator::V128Or { .. }
+        | Operator::V128Xor { .. }
+        | Operator::V128Bitselect { .. }
+        | Operator::V128AnyTrue { .. } => 1,
+
+        // Integer lane-wise arithmetic, comparison, shift and conversion
+        // instructions are of cost 1, the same as their scalar counterparts.
+        Operator::I8x16Eq { .. }
+        | Operator::I8x16Ne { .. }
+        | Operator::I8x16LtS { .. }
+        | Operator::I8x16LtU { .. }
+        | Operator::I8x16GtS { .. }
+        | Operator::I8x16GtU { .. }
+        | Operator::I8x16LeS { .. }
+        | Operator::I8x16LeU { .. }
+        | Operator::I8x16GeS { .. }
+        | Operator::I8x16GeU { .. }
+        | Operator::I16x8Eq { .. }
+        | Operator::I16x8Ne { .. }
+        | Operator::I16x8LtS { .. }
+        | Operator::I16x8LtU { .. }
+        | Operator::I16x8GtS { .. }
+        | Operator::I16x8GtU { .. }
+        | Operator::I16x8LeS { .. }
+        | Operator::I16x8LeU { .. }
+        | Operator::I16x8GeS { .. }
+        | Operator::I16x8GeU { .. }
+        | Operator::I32x4Eq { .. }
+        | Operator::I32x4Ne { .. }
+        | Operator::I32x4LtS { .. }
+        | Operator::I32x4LtU { .. }
+        | Operator::I32x4GtS { .. }
+        | Operator::I32x4GtU { .. }
+        | Operator::I32x4LeS { .. }
+        | Operator::I32x4LeU { .. }
+        | Operator::I32x4GeS { .. }
+        | Operator::I32x4GeU { .. }
+        | Operator::I64x2Eq { .. }
+        | Operator::I64x2Ne { .. }
+        | Operator::I64x2LtS { .. }
+        | Operator::I64x2GtS { .. }
+        | Operator::I64x2LeS { .. }
+        | Operator::I64x2GeS { .. }
+        | Operator::I8x16Abs { .. }
+        | Operator::I8x16Neg { .. }
+        | Operator::I8x16Popcnt { .. }
+        | Operator::I8x16AllTrue { .. }
+        | Operator::I8x16Bitmask { .. }
+        | Operator::I8x16NarrowI16x8S { .. }
+        | Operator::I8x16NarrowI16x8U { .. }
+        | Operator::I8x16Shl { .. }
+        | Operator::I8x16ShrS { .. }
+        | Operator::I8x16ShrU { .. }
+        | Operator::I8x16Add { .. }
+        | Operator::I8x16AddSatS { .. }
+        | Operator::I8x16AddSatU { .. }
+        | Operator::I8x16Sub { .. }
+        | Operator::I8x16SubSatS { .. }
+        | Operator::I8x16SubSatU { .. }
+        | Operator::I8x16MinS { .. }
+        | Operator::I8x16MinU { .. }
+        | Operator::I8x16MaxS { .. }
+        | Operator::I8x16MaxU { .. }
+        | Operator::I8x16AvgrU { .. }
+        | Operator::I16x8ExtAddPairwiseI8x16S { .. }
+        | Operator::I16x8ExtAddPairwiseI8x16U { .. }
+        | Operator::I16x8Abs { .. }
+        | Operator::I16x8Neg { .. }
+        | Operator::I16x8Q15MulrSatS { .. }
+        | Operator::I16x8AllTrue { .. }
+        | Operator::I16x8Bitmask { .. }
+        | Operator::I16x8NarrowI32x4S { .. }
+        | Operator::I16x8NarrowI32x4U { .. }
+        | Operator::I16x8ExtendLowI8x16S { .. }
+        | Operator::I16x8ExtendHighI8x16S { .. }
+        | Operator::I16x8ExtendLowI8x16U { .. }
+        | Operator::I16x8ExtendHighI8x16U { .. }
+        | Operator::I16x8Shl { .. }
+        | Operator::I16x8ShrS { .. }
+        | Operator::I16x8ShrU { .. }
+        | Operator::I16x8Add { .. }
+        | Operator::I16x8AddSatS { .. }
+        | Operator::I16x8AddSatU { .. }
+        | Operator::I16x8Sub { .. }
+        | Operator::I16x8SubSatS { .. }
+        | Operator::I16x8SubSatU { .. }
+        | Operator::I16x8Mul { .. }
+        | Operator::I16x8MinS { .. }
+        | Operator::I16x8MinU { .. }
+        | Operator::I16x8MaxS { .. }
+        | Operator::I16x8MaxU { .. }
+        | Operator::I16x8AvgrU { .. }
+        | Operator::I16x8ExtMulLowI8x16S { .. }
+        | Operator::I16x8ExtMulHighI8x16S { .. }
+        | Operator::I16x8ExtMulLowI8x16U { .. }
+        | Operator::I16x8ExtMulHighI8x16U { .. }
+        | Operator::I32x4ExtAddPairwiseI16x8S { .. }
+        | Operator::I32x4ExtAddPairwiseI16x8U { .. }
+        | Operator::I32x4Abs { .. }
+        | Operator::I32x4Neg { .. }
+        | Operator::I32x4AllTrue { .. }
+        | Operator::I32x4Bitmask { .. }
+        | Operator::I32x4ExtendLowI16x8S { .. }
+        | Operator::I32x4ExtendHighI16x8S { .. }
+        | Operator::I32x4ExtendLowI16x8U { .. }
+        | Operator::I32x4ExtendHighI16x8U { .. }
+        | Operator::I32x4Shl { .. }
+        | Operator::I32x4ShrS { .. }
+        | Operator::I32x4ShrU { .. }
+        | Operator::I32x4Add { .. }
+        | Operator::I32x4Sub { .. }
+        | Operator::I32x4Mul { .. }
+        | Operator::I32x4MinS { .. }
+        | Operator::I32x4MinU { .. }
+        | Operator::I32x4MaxS { .. }
+        | Operator::I32x4MaxU { .. }
+        | Operator::I32x4DotI16x8S { .. }
+        | Operator::I32x4ExtMulLowI16x8S { .. }
+        | Operator::I32x4ExtMulHighI16x8S { .. }
+        | Operator::I32x4ExtMulLowI16x8U { .. }
+        | Operator::I32x4ExtMulHighI16x8U { .. }
+        | Operator::I64x2Abs { .. }
+        | Operator::I64x2Neg { .. }
+        | Operator::I64x2AllTrue { .. }
+        | Operator::I64x2Bitmask { .. }
+        | Operator::I64x2ExtendLowI32x4S { .. }
+        | Operator::I64x2ExtendHighI32x4S { .. }
+        | Operator::I64x2ExtendLowI32x4U { .. }
+        | Operator::I64x2ExtendHighI32x4U { .. }
+        | Operator::I64x2Shl { .. }
+        | Operator::I64x2ShrS { .. }
+        | Operator::I64x2ShrU { .. }
+        | Operator::I64x2Add { .. }
+        | Operator::I64x2Sub { .. }
+        | Operator::I64x2Mul { .. }
+        | Operator::I64x2ExtMulLowI32x4S { .. }
+        | Operator::I64x2ExtMulHighI32x4S { .. }
+        | Operator::I64x2ExtMulLowI32x4U { .. }
+        | Operator::I64x2ExtMulHighI32x4U { .. } => 1,
+
+        // Floating point lane-wise instructions have the same costs as the
+        // corresponding scalar floating point instructions.
+        Operator::F32x4Ceil { .. }
+        | Operator::F32x4Floor { .. }
+        | Operator::F32x4Trunc { .. }
+        | Operator::F32x4Nearest { .. }
+        | Operator::F32x4Sqrt { .. }
+        | Operator::F32x4Add { .. }
+        | Operator::F32x4Sub { .. }
+        | Operator::F32x4Mul { .. }
+        | Operator::F32x4Div { .. }
+        | Operator::F32x4Min { .. }
+        | Operator::F32x4Max { .. }
+        | Operator::F32x4PMin { .. }
+        | Operator::F32x4PMax { .. }
+        | Operator::F64x2Ceil { .. }
+        | Operator::F64x2Floor { .. }
+        | Operator::F64x2Trunc { .. }
+        | Operator::F64x2Nearest { .. }
+        | Operator::F64x2Sqrt { .. }
+        | Operator::F64x2Add { .. }
+        | Operator::F64x2Sub { .. }
+        | Operator::F64x2Mul { .. }
+        | Operator::F64x2Div { .. }
+        | Operator::F64x2Min { .. }
+        | Operator::F64x2Max { .. }
+        | Operator::F64x2PMin { .. }
+        | Operator::F64x2PMax { .. } => 20,
+
+        Operator::F32x4Abs { .. }
+        | Operator::F32x4Neg { .. }
+        | Operator::F64x2Abs { .. }
+        | Operator::F64x2Neg { .. } => 2,
+
+        Operator::F32x4Eq { .. }
+        | Operator::F32x4Ne { .. }
+        | Operator::F32x4Lt { .. }
+        | Operator::F32x4Gt { .. }
+        | Operator::F32x4Le { .. }
+        | Operator::F32x4Ge { .. }
+        | Operator::F64x2Eq { .. }
+        | Operator::F64x2Ne { .. }
+        | Operator::F64x2Lt { .. }
+        | Operator::F64x2Gt { .. }
+        | Operator::F64x2Le { .. }
+        | Operator::F64x2Ge { .. } => 3,
+
+        Operator::F32x4ConvertI32x4S { .. } | Operator::F64x2ConvertLowI32x4S { .. } => 3,
+
+        Operator::F32x4ConvertI32x4U { .. } | Operator::F64x2ConvertLowI32x4U { .. } => 16,
+
+        Operator::I32x4TruncSatF32x4S { .. }
+        | Operator::I32x4TruncSatF32x4U { .. }
+        | Operator::I32x4TruncSatF64x2SZero { .. }
+        | Operator::I32x4TruncSatF64x2UZero { .. } => 20,
+
+        Operator::F32x4DemoteF64x2Zero { .. } | Operator::F64x2PromoteLowF32x4 { .. } => 1,
+
         // Default cost of an instruction is 1.
         _ => 1,
     }
@@ -461,6 +741,8 @@ const UPDATE_MEMORY_FUN_NAME: &str = "update_available_memory";
 const TRY_GROW_STABLE_MEMORY_FUN_NAME: &str = "try_grow_stable_memory";
 const INTERNAL_TRAP_FUN_NAME: &str = "internal_trap";
 const STABLE_READ_FIRST_ACCESS_NAME: &str = "stable_read_first_access";
+const PROFILE_ENTER_FUN_NAME: &str = "profile_enter";
+const PROFILE_EXIT_FUN_NAME: &str = "profile_exit";
 const TABLE_STR: &str = "table";
 pub(crate) const INSTRUCTIONS_COUNTER_GLOBAL_NAME: &str = "canister counter_instructions";
 pub(crate) const DIRTY_PAGES_COUNTER_GLOBAL_NAME: &str = "canister counter_dirty_pages";
@@ -471,6 +753,10 @@ const CANISTER_START_STR: &str = "canister_start";
 const BYTEMAP_SIZE_IN_WASM_PAGES: u64 =
     MAX_WASM_MEMORY_IN_BYTES / (PAGE_SIZE as u64) / (WASM_PAGE_SIZE as u64);
 
+const MAX_WASM64_MEMORY_IN_WASM_PAGES: u64 = MAX_WASM64_MEMORY_IN_BYTES / (WASM_PAGE_SIZE as u64);
+/// There is one byte for each OS page in a 64-bit wasm heap.
+const WASM64_BYTEMAP_SIZE_IN_WASM_PAGES: u64 = MAX_WASM64_MEMORY_IN_WASM_PAGES / (PAGE_SIZE as u64);
+
 const MAX_STABLE_MEMORY_IN_WASM_PAGES: u64 = MAX_STABLE_MEMORY_IN_BYTES / (WASM_PAGE_SIZE as u64);
 /// There is one byte for each OS page in the stable memory.
 const STABLE_BYTEMAP_SIZE_IN_WASM_PAGES: u64 = MAX_STABLE_MEMORY_IN_WASM_PAGES / (PAGE_SIZE as u64);
@@ -560,7 +846,11 @@ fn mutate_function_indices(module: &mut Module, f: impl Fn(u32) -> u32) {
 /// added as the last imports, we'd need to increment only non imported
 /// functions, since imported functions precede all others in the function index
 /// space, but this would be error-prone).
-fn inject_helper_functions(mut module: Module, wasm_native_stable_memory: FlagStatus) -> Module {
+fn inject_helper_functions(
+    mut module: Module,
+    wasm_native_stable_memory: FlagStatus,
+    wasm_profiling: FlagStatus,
+) -> Module {
     // insert types
     let ooi_type = FuncType::new([], []);
     let uam_type = FuncType::new([ValType::I32, ValType::I32, ValType::I32], [ValType::I32]);
@@ -582,8 +872,9 @@ fn inject_helper_functions(mut module: Module, wasm_native_stable_memory: FlagSt
     };
 
     let mut old_imports = module.imports;
-    module.imports =
-        Vec::with_capacity(old_imports.len() + InjectedImports::count(wasm_native_stable_memory));
+    module.imports = Vec::with_capacity(
+        old_imports.len() + InjectedImports::count(wasm_native_stable_memory, wasm_profiling),
+    );
     module.imports.push(ooi_imp);
     module.imports.push(uam_imp);
 
@@ -616,10 +907,22 @@ fn inject_helper_functions(mut module: Module, wasm_native_stable_memory: FlagSt
         module.imports.push(fr_imp);
     }
 
+    if wasm_profiling == FlagStatus::Enabled {
+        let profile_type = FuncType::new([ValType::I32], []);
+        let profile_type_idx = add_func_type(&mut module, profile_type);
+        for name in [PROFILE_ENTER_FUN_NAME, PROFILE_EXIT_FUN_NAME] {
+            module.imports.push(Import {
+                module: INSTRUMENTED_FUN_MODULE,
+                name,
+                ty: TypeRef::Func(profile_type_idx),
+            });
+        }
+    }
+
     module.imports.append(&mut old_imports);
 
     // now increment all function references by InjectedImports::Count
-    let cnt = InjectedImports::count(wasm_native_stable_memory) as u32;
+    let cnt = InjectedImports::count(wasm_native_stable_memory, wasm_profiling) as u32;
     mutate_function_indices(&mut module, |i| i + cnt);
 
     debug_assert!(
@@ -642,6 +945,11 @@ fn inject_helper_functions(mut module: Module, wasm_native_stable_memory: FlagSt
                 == "stable_read_first_access"
         );
     }
+    if wasm_profiling == FlagStatus::Enabled {
+        let enter = InjectedImports::profile_enter_index(wasm_native_stable_memory) as usize;
+        debug_assert!(module.imports[enter].name == "profile_enter");
+        debug_assert!(module.imports[enter + 1].name == "profile_exit");
+    }
 
     module
 }
@@ -672,12 +980,18 @@ pub(super) fn instrument(
     metering_type: MeteringType,
     subnet_type: SubnetType,
     dirty_page_overhead: NumInstructions,
+    wasm_profiling: FlagStatus,
 ) -> Result<InstrumentationOutput, WasmInstrumentationError> {
     let stable_memory_index;
-    let mut module = inject_helper_functions(module, wasm_native_stable_memory);
+    let memory_type = WasmMemoryType::of_module(&module);
+    let mut module = inject_helper_functions(module, wasm_native_stable_memory, wasm_profiling);
     module = export_table(module);
-    (module, stable_memory_index) =
-        update_memories(module, write_barrier, wasm_native_stable_memory);
+    (module, stable_memory_index) = update_memories(
+        module,
+        write_barrier,
+        wasm_native_stable_memory,
+        memory_type,
+    );
 
     let mut extra_strs: Vec<String> = Vec::new();
     module = export_mutable_globals(module, &mut extra_strs);
@@ -757,13 +1071,25 @@ pub(super) fn instrument(
     if !func_types.is_empty() {
         let func_bodies = &mut module.code_sections;
         for (func_ix, func_type) in func_types.into_iter() {
-            inject_update_available_memory(&mut func_bodies[func_ix], &func_type);
+            inject_update_available_memory(&mut func_bodies[func_ix], &func_type, memory_type);
             if write_barrier == FlagStatus::Enabled {
-                inject_mem_barrier(&mut func_bodies[func_ix], &func_type);
+                inject_mem_barrier(&mut func_bodies[func_ix], &func_type, memory_type);
             }
         }
     }
 
+    // Profiling has to be injected before any functions are appended to the
+    // module, so that only the original functions are profiled.
+    if wasm_profiling == FlagStatus::Enabled {
+        let num_injected_imports =
+            InjectedImports::count(wasm_native_stable_memory, wasm_profiling) as u32;
+        inject_profiling(
+            &mut module,
+            num_imported_functions as u32 - num_injected_imports,
+            InjectedImports::profile_enter_index(wasm_native_stable_memory),
+        )?;
+    }
+
     module = export_additional_symbols(module, &special_indices, wasm_native_stable_memory);
 
     if wasm_native_stable_memory == FlagStatus::Enabled {
@@ -773,6 +1099,7 @@ pub(super) fn instrument(
             subnet_type,
             dirty_page_overhead,
             metering_type,
+            memory_type,
         )
     }
 
@@ -828,6 +1155,62 @@ pub(super) fn instrument(
     })
 }
 
+// Makes every function of the original module call `profile_enter` with its
+// original function index on entry and `profile_exit` whenever it returns.
+fn inject_profiling(
+    module: &mut Module<'_>,
+    num_original_imported_functions: u32,
+    profile_enter_fn: u32,
+) -> Result<(), WasmInstrumentationError> {
+    use Operator::*;
+
+    let profile_exit_fn = profile_enter_fn + 1;
+    for i in 0..module.code_sections.len() {
+        let results = match &module.types[module.functions[i] as usize].composite_type {
+            CompositeType::Func(ty) => ty.results().to_vec(),
+            ty => {
+                return Err(WasmInstrumentationError::InvalidFunctionType(format!(
+                    "Function has type which is not a function type. Found type: {:?}",
+                    ty
+                )))
+            }
+        };
+        // The body is wrapped in a block with the same results as the function
+        // so that a branch to the function label ends up at the exit call.
+        let blockty = match results.as_slice() {
+            [] => BlockType::Empty,
+            [result] => BlockType::Type(*result),
+            _ => BlockType::FuncType(add_func_type(module, FuncType::new([], results))),
+        };
+        let func_index = (num_original_imported_functions + i as u32) as i32;
+
+        let body = std::mem::take(&mut module.code_sections[i].instructions);
+        let mut instructions = Vec::with_capacity(body.len() + 6);
+        instructions.push(I32Const { value: func_index });
+        instructions.push(Call {
+            function_index: profile_enter_fn,
+        });
+        instructions.push(Block { blockty });
+        for op in body {
+            if matches!(op, Return | ReturnCall { .. } | ReturnCallIndirect { .. }) {
+                instructions.push(I32Const { value: func_index });
+                instructions.push(Call {
+                    function_index: profile_exit_fn,
+                });
+            }
+            instructions.push(op);
+        }
+        // The original final `end` now closes the block.
+        instructions.push(I32Const { value: func_index });
+        instructions.push(Call {
+            function_index: profile_exit_fn,
+        });
+        instructions.push(End);
+        module.code_sections[i].instructions = instructions;
+    }
+    Ok(())
+}
+
 fn calculate_api_indexes(module: &Module<'_>) -> BTreeMap<SystemApiFunc, u32> {
     module
         .imports
@@ -852,6 +1235,7 @@ fn replace_system_api_functions(
     subnet_type: SubnetType,
     dirty_page_overhead: NumInstructions,
     metering_type: MeteringType,
+    memory_type: WasmMemoryType,
 ) {
     let api_indexes = calculate_api_indexes(module);
     let number_of_func_imports = module
@@ -868,6 +1252,7 @@ fn replace_system_api_functions(
         subnet_type,
         dirty_page_overhead,
         metering_type,
+        memory_type,
     ) {
         if let Some(old_index) = api_indexes.get(&api) {
             let type_idx = add_func_type(module, ty);
@@ -1249,57 +1634,102 @@ fn inject_metering(
 // of the original store operation are on the stack
 fn write_barrier_instructions<'a>(
     offset: u64,
+    size: u64,
     val_arg_idx: u32,
     addr_arg_idx: u32,
+    memory_type: WasmMemoryType,
 ) -> Vec<Operator<'a>> {
     use Operator::*;
     let page_size_shift = PAGE_SIZE.trailing_zeros() as i32;
     let tracking_mem_idx = 1;
-    if offset % PAGE_SIZE as u64 == 0 {
-        vec![
-            LocalSet {
-                local_index: val_arg_idx,
-            }, // value
-            LocalTee {
-                local_index: addr_arg_idx,
-            }, // address
+    let mut instructions = vec![
+        LocalSet {
+            local_index: val_arg_idx,
+        }, // value
+        LocalTee {
+            local_index: addr_arg_idx,
+        }, // address
+    ];
+    // If the offset is page-aligned, it can be applied to the bytemap index
+    // directly. Otherwise, it is added to the address before shifting.
+    let bytemap_offset = if offset % PAGE_SIZE as u64 == 0 {
+        offset >> page_size_shift
+    } else {
+        match memory_type {
+            WasmMemoryType::Wasm32 => instructions.extend_from_slice(&[
+                I32Const {
+                    value: offset as i32,
+                },
+                I32Add,
+            ]),
+            WasmMemoryType::Wasm64 => instructions.extend_from_slice(&[
+                I64Const {
+                    value: offset as i64,
+                },
+                I64Add,
+            ]),
+        }
+        0
+    };
+    match memory_type {
+        WasmMemoryType::Wasm32 => instructions.extend_from_slice(&[
             I32Const {
                 value: page_size_shift,
             },
             I32ShrU,
-            I32Const { value: 1 },
-            I32Store8 {
-                memarg: wasmparser::MemArg {
-                    align: 0,
-                    max_align: 0,
-                    offset: offset >> page_size_shift,
-                    memory: tracking_mem_idx,
-                },
-            },
-            // Put original params on the stack
-            LocalGet {
-                local_index: addr_arg_idx,
-            },
-            LocalGet {
-                local_index: val_arg_idx,
+        ]),
+        // The bytemap is a 32-bit memory, and the page index of a 64-bit heap
+        // address always fits into 32 bits.
+        WasmMemoryType::Wasm64 => instructions.extend_from_slice(&[
+            I64Const {
+                value: page_size_shift as i64,
             },
-        ]
-    } else {
-        vec![
-            LocalSet {
-                local_index: val_arg_idx,
-            }, // value
-            LocalTee {
-                local_index: addr_arg_idx,
-            }, // address
-            I32Const {
-                value: offset as i32,
-            },
-            I32Add,
-            I32Const {
-                value: page_size_shift,
+            I64ShrU,
+            I32WrapI64,
+        ]),
+    }
+    instructions.extend_from_slice(&[
+        I32Const { value: 1 },
+        I32Store8 {
+            memarg: wasmparser::MemArg {
+                align: 0,
+                max_align: 0,
+                offset: bytemap_offset,
+                memory: tracking_mem_idx,
             },
-            I32ShrU,
+        },
+    ]);
+    // A store that is not aligned to its size may cross a page boundary, so
+    // the page of its last byte is marked as well.
+    if size > 1 {
+        let last_byte_offset = offset + size - 1;
+        instructions.push(LocalGet {
+            local_index: addr_arg_idx,
+        });
+        match memory_type {
+            WasmMemoryType::Wasm32 => instructions.extend_from_slice(&[
+                I32Const {
+                    value: last_byte_offset as i32,
+                },
+                I32Add,
+                I32Const {
+                    value: page_size_shift,
+                },
+                I32ShrU,
+            ]),
+            WasmMemoryType::Wasm64 => instructions.extend_from_slice(&[
+                I64Const {
+                    value: last_byte_offset as i64,
+                },
+                I64Add,
+                I64Const {
+                    value: page_size_shift as i64,
+                },
+                I64ShrU,
+                I32WrapI64,
+            ]),
+        }
+        instructions.extend_from_slice(&[
             I32Const { value: 1 },
             I32Store8 {
                 memarg: wasmparser::MemArg {
@@ -1309,23 +1739,31 @@ fn write_barrier_instructions<'a>(
                     memory: tracking_mem_idx,
                 },
             },
-            // Put original params on the stack
-            LocalGet {
-                local_index: addr_arg_idx,
-            },
-            LocalGet {
-                local_index: val_arg_idx,
-            },
-        ]
+        ]);
     }
+    instructions.extend_from_slice(&[
+        // Put original params on the stack
+        LocalGet {
+            local_index: addr_arg_idx,
+        },
+        LocalGet {
+            local_index: val_arg_idx,
+        },
+    ]);
+    instructions
 }
 
-fn inject_mem_barrier(func_body: &mut ic_wasm_transform::Body, func_type: &FuncType) {
+fn inject_mem_barrier(
+    func_body: &mut ic_wasm_transform::Body,
+    func_type: &FuncType,
+    memory_type: WasmMemoryType,
+) {
     use Operator::*;
     let mut val_i32_needed = false;
     let mut val_i64_needed = false;
     let mut val_f32_needed = false;
     let mut val_f64_needed = false;
+    let mut val_v128_needed = false;
 
     let mut injection_points: Vec<usize> = Vec::new();
     {
@@ -1347,6 +1785,14 @@ fn inject_mem_barrier(func_body: &mut ic_wasm_transform::Body, func_type: &FuncT
                     val_f64_needed = true;
                     injection_points.push(idx)
                 }
+                V128Store { .. }
+                | V128Store8Lane { .. }
+                | V128Store16Lane { .. }
+                | V128Store32Lane { .. }
+                | V128Store64Lane { .. } => {
+                    val_v128_needed = true;
+                    injection_points.push(idx)
+                }
                 _ => (),
             }
         }
@@ -1359,7 +1805,7 @@ fn inject_mem_barrier(func_body: &mut ic_wasm_transform::Body, func_type: &FuncT
         // the total number of locals.
         let n_locals: u32 = func_body.locals.iter().map(|x| x.0).sum();
         let mut next_local = func_type.params().len() as u32 + n_locals;
-        let arg_i32_addr_idx = next_local;
+        let arg_addr_idx = next_local;
         next_local += 1;
 
         // conditionally add following locals
@@ -1367,14 +1813,19 @@ fn inject_mem_barrier(func_body: &mut ic_wasm_transform::Body, func_type: &FuncT
         let arg_i64_val_idx;
         let arg_f32_val_idx;
         let arg_f64_val_idx;
+        let arg_v128_val_idx;
+
+        match memory_type {
+            WasmMemoryType::Wasm32 => func_body.locals.push((1, ValType::I32)),
+            WasmMemoryType::Wasm64 => func_body.locals.push((1, ValType::I64)),
+        } // addr local
 
         if val_i32_needed {
             arg_i32_val_idx = next_local;
             next_local += 1;
-            func_body.locals.push((2, ValType::I32)); // addr and val locals
+            func_body.locals.push((1, ValType::I32));
         } else {
             arg_i32_val_idx = u32::MAX; // not used
-            func_body.locals.push((1, ValType::I32)); // only addr local
         }
 
         if val_i64_needed {
@@ -1395,12 +1846,20 @@ fn inject_mem_barrier(func_body: &mut ic_wasm_transform::Body, func_type: &FuncT
 
         if val_f64_needed {
             arg_f64_val_idx = next_local;
-            // next_local += 1;
+            next_local += 1;
             func_body.locals.push((1, ValType::F64));
         } else {
             arg_f64_val_idx = u32::MAX;
         }
 
+        if val_v128_needed {
+            arg_v128_val_idx = next_local;
+            // next_local += 1;
+            func_body.locals.push((1, ValType::V128));
+        } else {
+            arg_v128_val_idx = u32::MAX;
+        }
+
         let orig_elems = &func_body.instructions;
         let mut elems: Vec<Operator> = Vec::new();
         let mut last_injection_position = 0;
@@ -1408,40 +1867,30 @@ fn inject_mem_barrier(func_body: &mut ic_wasm_transform::Body, func_type: &FuncT
             let mem_instr = orig_elems[point].clone();
             elems.extend_from_slice(&orig_elems[last_injection_position..point]);
 
-            match mem_instr {
-                I32Store { memarg } | I32Store8 { memarg } | I32Store16 { memarg } => {
-                    elems.extend_from_slice(&write_barrier_instructions(
-                        memarg.offset,
-                        arg_i32_val_idx,
-                        arg_i32_addr_idx,
-                    ));
-                }
-                I64Store { memarg }
-                | I64Store8 { memarg }
-                | I64Store16 { memarg }
-                | I64Store32 { memarg } => {
-                    elems.extend_from_slice(&write_barrier_instructions(
-                        memarg.offset,
-                        arg_i64_val_idx,
-                        arg_i32_addr_idx,
-                    ));
-                }
-                F32Store { memarg } => {
-                    elems.extend_from_slice(&write_barrier_instructions(
-                        memarg.offset,
-                        arg_f32_val_idx,
-                        arg_i32_addr_idx,
-                    ));
-                }
-                F64Store { memarg } => {
-                    elems.extend_from_slice(&write_barrier_instructions(
-                        memarg.offset,
-                        arg_f64_val_idx,
-                        arg_i32_addr_idx,
-                    ));
-                }
-                _ => {}
-            }
+            let (memarg, size, val_arg_idx) = match mem_instr {
+                I32Store { memarg } => (memarg, 4, arg_i32_val_idx),
+                I32Store8 { memarg } => (memarg, 1, arg_i32_val_idx),
+                I32Store16 { memarg } => (memarg, 2, arg_i32_val_idx),
+                I64Store { memarg } => (memarg, 8, arg_i64_val_idx),
+                I64Store8 { memarg } => (memarg, 1, arg_i64_val_idx),
+                I64Store16 { memarg } => (memarg, 2, arg_i64_val_idx),
+                I64Store32 { memarg } => (memarg, 4, arg_i64_val_idx),
+                F32Store { memarg } => (memarg, 4, arg_f32_val_idx),
+                F64Store { memarg } => (memarg, 8, arg_f64_val_idx),
+                V128Store { memarg } => (memarg, 16, arg_v128_val_idx),
+                V128Store8Lane { memarg, .. } => (memarg, 1, arg_v128_val_idx),
+                V128Store16Lane { memarg, .. } => (memarg, 2, arg_v128_val_idx),
+                V128Store32Lane { memarg, .. } => (memarg, 4, arg_v128_val_idx),
+                V128Store64Lane { memarg, .. } => (memarg, 8, arg_v128_val_idx),
+                _ => unreachable!("only store instructions are injection points"),
+            };
+            elems.extend_from_slice(&write_barrier_instructions(
+                memarg.offset,
+                size,
+                val_arg_idx,
+                arg_addr_idx,
+                memory_type,
+            ));
             // add the original store instruction itself
             elems.push(mem_instr);
 
@@ -1456,16 +1905,22 @@ fn inject_mem_barrier(func_body: &mut ic_wasm_transform::Body, func_type: &FuncT
 // `table.grow` instruction to make sure that there's enough available memory
 // left to support the requested extra memory. If no `memory.grow` or
 // `table.grow` instructions are present then the code remains unchanged.
-fn inject_update_available_memory(func_body: &mut ic_wasm_transform::Body, func_type: &FuncType) {
+fn inject_update_available_memory(
+    func_body: &mut ic_wasm_transform::Body,
+    func_type: &FuncType,
+    memory_type: WasmMemoryType,
+) {
     // This is an overestimation of table element size computed based on the
     // existing canister limits.
     const TABLE_ELEMENT_SIZE: u32 = 1024;
     use Operator::*;
     let mut injection_points: Vec<(usize, u32)> = Vec::new();
+    let mut memory64_grow_needed = false;
     {
         for (idx, instr) in func_body.instructions.iter().enumerate() {
             if let MemoryGrow { .. } = instr {
                 injection_points.push((idx, WASM_PAGE_SIZE));
+                memory64_grow_needed |= memory_type == WasmMemoryType::Wasm64;
             }
             if let TableGrow { .. } = instr {
                 injection_points.push((idx, TABLE_ELEMENT_SIZE));
@@ -1481,6 +1936,12 @@ fn inject_update_available_memory(func_body: &mut ic_wasm_transform::Body, func_
         let n_locals: u32 = func_body.locals.iter().map(|x| x.0).sum();
         let memory_local_ix = func_type.params().len() as u32 + n_locals;
         func_body.locals.push((1, ValType::I32));
+        // `memory.grow` of a 64-bit memory takes and returns an `i64`, which
+        // is cached in a separate local.
+        let memory64_local_ix = memory_local_ix + 1;
+        if memory64_grow_needed {
+            func_body.locals.push((1, ValType::I64));
+        }
 
         let orig_elems = &func_body.instructions;
         let mut elems: Vec<Operator> = Vec::new();
@@ -1488,6 +1949,31 @@ fn inject_update_available_memory(func_body: &mut ic_wasm_transform::Body, func_
         for (point, element_size) in injection_points {
             let update_available_memory_instr = orig_elems[point].clone();
             elems.extend_from_slice(&orig_elems[last_injection_position..point]);
+            if memory64_grow_needed && matches!(update_available_memory_instr, MemoryGrow { .. }) {
+                // The number of pages requested from a 64-bit memory is
+                // bounded by the maximum heap size, so it fits into 32 bits
+                // after a successful grow, and `-1` stays `-1` after wrapping.
+                elems.extend_from_slice(&[
+                    LocalTee {
+                        local_index: memory64_local_ix,
+                    },
+                    update_available_memory_instr,
+                    I32WrapI64,
+                    LocalGet {
+                        local_index: memory64_local_ix,
+                    },
+                    I32WrapI64,
+                    I32Const {
+                        value: element_size as i32,
+                    },
+                    Call {
+                        function_index: InjectedImports::UpdateAvailableMemory as u32,
+                    },
+                    I64ExtendI32S,
+                ]);
+                last_injection_position = point + 1;
+                continue;
+            }
             // At this point we have a memory.grow so the argument to it will be on top of
             // the stack, which we just assign to `memory_local_ix` with a local.tee
             // instruction.
@@ -1646,6 +2132,7 @@ fn get_data(
                     offset_expr,
                 } => match offset_expr {
                     Operator::I32Const { value } => *value as usize,
+                    Operator::I64Const { value } => *value as usize,
                     _ => return Err(WasmInstrumentationError::WasmDeserializeError(WasmError::new(
                         "complex initialization expressions for data segments are not supported!".into()
                     ))),
@@ -1692,9 +2179,23 @@ fn update_memories(
     mut module: Module,
     write_barrier: FlagStatus,
     wasm_native_stable_memory: FlagStatus,
+    memory_type: WasmMemoryType,
 ) -> (Module, u32) {
     let mut stable_index = 0;
 
+    // A 64-bit heap is limited to `MAX_WASM64_MEMORY_IN_BYTES` rather than
+    // by its index type.
+    if memory_type == WasmMemoryType::Wasm64 {
+        if let Some(memory) = module.memories.first_mut() {
+            memory.maximum = Some(
+                memory
+                    .maximum
+                    .unwrap_or(MAX_WASM64_MEMORY_IN_WASM_PAGES)
+                    .min(MAX_WASM64_MEMORY_IN_WASM_PAGES),
+            );
+        }
+    }
+
     let mut memory_already_exported = false;
     for export in &mut module.exports {
         if let ExternalKind::Memory = export.kind {
@@ -1713,11 +2214,15 @@ fn update_memories(
     }
 
     if write_barrier == FlagStatus::Enabled && !module.memories.is_empty() {
+        let bytemap_size = match memory_type {
+            WasmMemoryType::Wasm32 => BYTEMAP_SIZE_IN_WASM_PAGES,
+            WasmMemoryType::Wasm64 => WASM64_BYTEMAP_SIZE_IN_WASM_PAGES,
+        };
         module.memories.push(MemoryType {
             memory64: false,
             shared: false,
-            initial: BYTEMAP_SIZE_IN_WASM_PAGES,
-            maximum: Some(BYTEMAP_SIZE_IN_WASM_PAGES),
+            initial: bytemap_size,
+            maximum: Some(bytemap_size),
         });
 
         module.exports.push(Export {
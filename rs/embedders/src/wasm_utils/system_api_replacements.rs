@@ -24,7 +24,7 @@ use ic_wasm_transform::Body;
 use wasmparser::{BlockType, FuncType, Operator, ValType};
 use wasmtime_environ::WASM_PAGE_SIZE;
 
-use super::{instrumentation::SpecialIndices, SystemApiFunc};
+use super::{instrumentation::SpecialIndices, SystemApiFunc, WasmMemoryType};
 
 use crate::wasmtime_embedder::system_api_complexity::system_api;
 
@@ -35,6 +35,7 @@ pub(super) fn replacement_functions(
     subnet_type: SubnetType,
     dirty_page_overhead: NumInstructions,
     metering_type: MeteringType,
+    memory_type: WasmMemoryType,
 ) -> Vec<(SystemApiFunc, (FuncType, Body<'static>))> {
     let count_clean_pages_fn_index = special_indices.count_clean_pages_fn.unwrap();
     let dirty_pages_counter_index = special_indices.dirty_pages_counter_ix.unwrap();
@@ -45,7 +46,14 @@ pub(super) fn replacement_functions(
     use Operator::*;
     let page_size_shift = PAGE_SIZE.trailing_zeros() as i32;
     let stable_memory_bytemap_index = stable_memory_index + 1;
-    vec![
+    // Heap addresses and lengths passed to `memory.copy` must have the index
+    // type of the heap. `Nop` marks a conversion that is not needed and is
+    // removed once the functions are built.
+    let (heap_from_i32, heap_from_i64, max_heap_address) = match memory_type {
+        WasmMemoryType::Wasm32 => (Nop, I32WrapI64, u32::MAX as i64),
+        WasmMemoryType::Wasm64 => (I64ExtendI32U, Nop, i64::MAX),
+    };
+    let functions = vec![
         (
             SystemApiFunc::StableSize,
             (
@@ -454,9 +462,11 @@ pub(super) fn replacement_functions(
                             },
                             Else,
                             LocalGet { local_index: DST },
+                            heap_from_i32.clone(),
                             LocalGet { local_index: SRC },
                             I64ExtendI32U,
                             LocalGet { local_index: LEN },
+                            heap_from_i32.clone(),
                             MemoryCopy {
                                 dst_mem: 0,
                                 src_mem: stable_memory_index,
@@ -567,11 +577,11 @@ pub(super) fn replacement_functions(
                                 function_index: InjectedImports::InternalTrap as u32,
                             },
                             End,
-                            // check if these i64 hold valid i32 heap addresses
+                            // check if these i64 hold valid heap addresses
                             // check dst
                             LocalGet { local_index: DST },
                             I64Const {
-                                value: u32::MAX as i64,
+                                value: max_heap_address,
                             },
                             I64GtU,
                             If {
@@ -587,7 +597,7 @@ pub(super) fn replacement_functions(
                             // check len
                             LocalGet { local_index: LEN },
                             I64Const {
-                                value: u32::MAX as i64,
+                                value: max_heap_address,
                             },
                             I64GtU,
                             If {
@@ -743,10 +753,10 @@ pub(super) fn replacement_functions(
                             },
                             Else,
                             LocalGet { local_index: DST },
-                            I32WrapI64,
+                            heap_from_i64.clone(),
                             LocalGet { local_index: SRC },
                             LocalGet { local_index: LEN },
-                            I32WrapI64,
+                            heap_from_i64.clone(),
                             MemoryCopy {
                                 dst_mem: 0,
                                 src_mem: stable_memory_index,
@@ -968,7 +978,9 @@ pub(super) fn replacement_functions(
                             LocalGet { local_index: DST },
                             I64ExtendI32U,
                             LocalGet { local_index: SRC },
+                            heap_from_i32.clone(),
                             LocalGet { local_index: LEN },
+                            heap_from_i32.clone(),
                             MemoryCopy {
                                 dst_mem: stable_memory_index,
                                 src_mem: 0,
@@ -1088,11 +1100,11 @@ pub(super) fn replacement_functions(
                                 function_index: InjectedImports::InternalTrap as u32,
                             },
                             End,
-                            // check if these i64 hold valid i32 heap addresses
+                            // check if these i64 hold valid heap addresses
                             // check src
                             LocalGet { local_index: SRC },
                             I64Const {
-                                value: u32::MAX as i64,
+                                value: max_heap_address,
                             },
                             I64GtU,
                             If {
@@ -1108,7 +1120,7 @@ pub(super) fn replacement_functions(
                             // check len
                             LocalGet { local_index: LEN },
                             I64Const {
-                                value: u32::MAX as i64,
+                                value: max_heap_address,
                             },
                             I64GtU,
                             If {
@@ -1225,9 +1237,9 @@ pub(super) fn replacement_functions(
                             // copy memory contents
                             LocalGet { local_index: DST },
                             LocalGet { local_index: SRC },
-                            I32WrapI64,
+                            heap_from_i64.clone(),
                             LocalGet { local_index: LEN },
-                            I32WrapI64,
+                            heap_from_i64.clone(),
                             MemoryCopy {
                                 dst_mem: stable_memory_index,
                                 src_mem: 0,
@@ -1260,5 +1272,12 @@ pub(super) fn replacement_functions(
                 },
             ),
         ),
-    ]
+    ];
+    functions
+        .into_iter()
+        .map(|(api, (ty, mut body))| {
+            body.instructions.retain(|instr| !matches!(instr, Nop));
+            (api, (ty, body))
+        })
+        .collect()
 }
@@ -1,13 +1,14 @@
 //! This module is responsible for validating the wasm binaries that are
 //! installed on the Internet Computer.
 
-use super::{Complexity, WasmImportsDetails, WasmValidationDetails};
+use super::{Complexity, WasmImportsDetails, WasmMemoryType, WasmValidationDetails};
 
-use ic_config::embedders::Config as EmbeddersConfig;
-use ic_replicated_state::canister_state::execution_state::{
-    CustomSection, CustomSectionType, WasmMetadata,
+use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
+use ic_replicated_state::canister_state::{
+    execution_state::{CustomSection, CustomSectionType, WasmMetadata},
+    WASM_PAGE_SIZE_IN_BYTES,
 };
-use ic_types::{NumBytes, NumInstructions, MAX_STABLE_MEMORY_IN_BYTES};
+use ic_types::{NumBytes, NumInstructions, MAX_STABLE_MEMORY_IN_BYTES, MAX_WASM64_MEMORY_IN_BYTES};
 use ic_wasm_transform::{Body, DataSegment, DataSegmentKind, Module};
 use ic_wasm_types::{BinaryEncodedWasm, WasmValidationError};
 use std::{
@@ -59,7 +60,16 @@ pub(super) const API_VERSION_IC0: &str = "ic0";
 // user tries to import a function that doesn't exist in any of the expected
 // modules vs the case where the function exists but is imported from the wrong
 // module.
-fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>> {
+//
+// Heap addresses and sizes are `i32` for modules with a 32-bit main memory and
+// `i64` for modules with a 64-bit main memory.
+fn get_valid_system_apis(
+    memory_type: WasmMemoryType,
+) -> HashMap<String, HashMap<String, FunctionSignature>> {
+    let ptr = match memory_type {
+        WasmMemoryType::Wasm32 => ValType::I32,
+        WasmMemoryType::Wasm64 => ValType::I64,
+    };
     let valid_system_apis = vec![
         (
             // Public methods
@@ -68,7 +78,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValType::I32],
+                    return_type: vec![ptr],
                 },
             )],
         ),
@@ -77,7 +87,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32, ValType::I32],
+                    param_types: vec![ptr, ptr, ptr],
                     return_type: vec![],
                 },
             )],
@@ -88,7 +98,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValType::I32],
+                    return_type: vec![ptr],
                 },
             )],
         ),
@@ -97,7 +107,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32, ValType::I32],
+                    param_types: vec![ptr, ptr, ptr],
                     return_type: vec![],
                 },
             )],
@@ -108,7 +118,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValType::I32],
+                    return_type: vec![ptr],
                 },
             )],
         ),
@@ -117,7 +127,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32, ValType::I32],
+                    param_types: vec![ptr, ptr, ptr],
                     return_type: vec![],
                 },
             )],
@@ -142,13 +152,23 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
                 },
             )],
         ),
+        (
+            "msg_deadline",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![],
+                    return_type: vec![ValType::I64],
+                },
+            )],
+        ),
         (
             "msg_reject_msg_size",
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValType::I32],
+                    return_type: vec![ptr],
                 },
             )],
         ),
@@ -157,7 +177,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32, ValType::I32],
+                    param_types: vec![ptr, ptr, ptr],
                     return_type: vec![],
                 },
             )],
@@ -167,7 +187,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32],
+                    param_types: vec![ptr, ptr],
                     return_type: vec![],
                 },
             )],
@@ -187,7 +207,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32],
+                    param_types: vec![ptr, ptr],
                     return_type: vec![],
                 },
             )],
@@ -198,7 +218,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValType::I32],
+                    return_type: vec![ptr],
                 },
             )],
         ),
@@ -207,7 +227,47 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32, ValType::I32],
+                    param_types: vec![ptr, ptr, ptr],
+                    return_type: vec![],
+                },
+            )],
+        ),
+        (
+            "subnet_self_size",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![],
+                    return_type: vec![ptr],
+                },
+            )],
+        ),
+        (
+            "subnet_self_copy",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ptr, ptr, ptr],
+                    return_type: vec![],
+                },
+            )],
+        ),
+        (
+            "root_key_size",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![],
+                    return_type: vec![ptr],
+                },
+            )],
+        ),
+        (
+            "root_key_copy",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ptr, ptr, ptr],
                     return_type: vec![],
                 },
             )],
@@ -229,10 +289,10 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![
-                        ValType::I32,
-                        ValType::I32,
-                        ValType::I32,
-                        ValType::I32,
+                        ptr,
+                        ptr,
+                        ptr,
+                        ptr,
                         ValType::I32,
                         ValType::I32,
                         ValType::I32,
@@ -247,7 +307,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32],
+                    param_types: vec![ptr, ptr],
                     return_type: vec![],
                 },
             )],
@@ -262,6 +322,16 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
                 },
             )],
         ),
+        (
+            "call_with_best_effort_response",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValType::I32],
+                    return_type: vec![],
+                },
+            )],
+        ),
         (
             "call_cycles_add",
             vec![(
@@ -288,7 +358,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32],
+                    param_types: vec![ptr, ptr],
                     return_type: vec![],
                 },
             )],
@@ -418,7 +488,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32],
+                    param_types: vec![ptr, ptr],
                     return_type: vec![],
                 },
             )],
@@ -468,7 +538,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32],
+                    param_types: vec![ptr, ptr],
                     return_type: vec![],
                 },
             )],
@@ -489,7 +559,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValType::I32],
+                    return_type: vec![ptr],
                 },
             )],
         ),
@@ -498,7 +568,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32, ValType::I32],
+                    param_types: vec![ptr, ptr, ptr],
                     return_type: vec![],
                 },
             )],
@@ -538,7 +608,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32],
+                    param_types: vec![ptr],
                     return_type: vec![],
                 },
             )],
@@ -548,7 +618,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32],
+                    param_types: vec![ptr],
                     return_type: vec![],
                 },
             )],
@@ -558,7 +628,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32],
+                    param_types: vec![ptr],
                     return_type: vec![],
                 },
             )],
@@ -568,7 +638,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I64, ValType::I64, ValType::I32],
+                    param_types: vec![ValType::I64, ValType::I64, ptr],
                     return_type: vec![],
                 },
             )],
@@ -578,7 +648,7 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32],
+                    param_types: vec![ptr, ptr],
                     return_type: vec![ValType::I32],
                 },
             )],
@@ -598,11 +668,61 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I64, ValType::I64, ValType::I32],
+                    param_types: vec![ValType::I64, ValType::I64, ptr],
                     return_type: vec![],
                 },
             )],
         ),
+        (
+            "cost_call",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValType::I64, ValType::I64, ptr],
+                    return_type: vec![],
+                },
+            )],
+        ),
+        (
+            "cost_create_canister",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ptr],
+                    return_type: vec![],
+                },
+            )],
+        ),
+        (
+            "cost_http_request",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValType::I64, ValType::I64, ptr],
+                    return_type: vec![],
+                },
+            )],
+        ),
+        (
+            "cost_sign_with_ecdsa",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ptr, ptr, ValType::I32, ptr],
+                    return_type: vec![ValType::I32],
+                },
+            )],
+        ),
+        (
+            "cost_sign_with_schnorr",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ptr, ptr, ValType::I32, ptr],
+                    return_type: vec![ValType::I32],
+                },
+            )],
+        ),
     ];
 
     valid_system_apis
@@ -743,11 +863,14 @@ fn set_imports_details(import_details: &mut WasmImportsDetails, import_module: &
 //
 // Returns information about what IC0 methods are imported via
 // `WasmImportsDetails`.
-fn validate_import_section(module: &Module) -> Result<WasmImportsDetails, WasmValidationError> {
+fn validate_import_section(
+    module: &Module,
+    memory_type: WasmMemoryType,
+) -> Result<WasmImportsDetails, WasmValidationError> {
     let mut imports_details = WasmImportsDetails::default();
 
     if !module.imports.is_empty() {
-        let valid_system_apis = get_valid_system_apis();
+        let valid_system_apis = get_valid_system_apis(memory_type);
         for entry in &module.imports {
             let import_module = entry.module;
             let field = entry.name;
@@ -931,10 +1054,16 @@ fn validate_export_section(
 }
 
 // Checks that offset-expressions in data sections consist of only one constant
-// expression. Required because of OP. See also:
-// instrumentation.rs
-fn validate_data_section(module: &Module) -> Result<(), WasmValidationError> {
-    fn validate_segment(s: &DataSegment) -> Result<(), WasmValidationError> {
+// expression of the main memory's index type. Required because of OP. See
+// also: instrumentation.rs
+fn validate_data_section(
+    module: &Module,
+    memory_type: WasmMemoryType,
+) -> Result<(), WasmValidationError> {
+    fn validate_segment(
+        s: &DataSegment,
+        memory_type: WasmMemoryType,
+    ) -> Result<(), WasmValidationError> {
         match &s.kind {
             DataSegmentKind::Passive => Err(WasmValidationError::InvalidDataSection(
                 "Empty offset in data segment.".to_string(),
@@ -942,8 +1071,9 @@ fn validate_data_section(module: &Module) -> Result<(), WasmValidationError> {
             DataSegmentKind::Active {
                 memory_index: _,
                 offset_expr,
-            } => match offset_expr {
-                Operator::I32Const { .. } => Ok(()),
+            } => match (memory_type, offset_expr) {
+                (WasmMemoryType::Wasm32, Operator::I32Const { .. })
+                | (WasmMemoryType::Wasm64, Operator::I64Const { .. }) => Ok(()),
                 _ => Err(WasmValidationError::InvalidDataSection(format!(
                     "Invalid offset expression in data segment: {:?}",
                     offset_expr
@@ -953,11 +1083,46 @@ fn validate_data_section(module: &Module) -> Result<(), WasmValidationError> {
     }
 
     for d in &module.data {
-        validate_segment(d)?;
+        validate_segment(d, memory_type)?;
     }
     Ok(())
 }
 
+// Determines whether the main memory of the module is a 32-bit or a 64-bit
+// memory. A 64-bit memory is only allowed if the `wasm64` feature is enabled
+// and must not start out larger than the maximum 64-bit heap size.
+fn validate_memory_section(
+    module: &Module,
+    wasm64: FlagStatus,
+) -> Result<WasmMemoryType, WasmValidationError> {
+    let memory_type = WasmMemoryType::of_module(module);
+    if memory_type == WasmMemoryType::Wasm64 {
+        if wasm64 == FlagStatus::Disabled {
+            return Err(WasmValidationError::InvalidMemorySection(
+                "64-bit memories are not supported.".to_string(),
+            ));
+        }
+        let max_pages = MAX_WASM64_MEMORY_IN_BYTES / WASM_PAGE_SIZE_IN_BYTES as u64;
+        let initial_pages = module
+            .imports
+            .iter()
+            .find_map(|import| match import.ty {
+                TypeRef::Memory(memory) => Some(memory.initial),
+                _ => None,
+            })
+            .or_else(|| module.memories.first().map(|memory| memory.initial))
+            .unwrap_or(0);
+        if initial_pages > max_pages {
+            return Err(WasmValidationError::InvalidMemorySection(format!(
+                "The initial size of the 64-bit memory is {} Wasm pages, \
+                 which exceeds the maximum of {} Wasm pages.",
+                initial_pages, max_pages
+            )));
+        }
+    }
+    Ok(memory_type)
+}
+
 // Checks that no more than `max_globals` are defined in the module
 // and all globals have supported type.
 fn validate_global_section(module: &Module, max_globals: usize) -> Result<(), WasmValidationError> {
@@ -1345,15 +1510,19 @@ pub fn wasmtime_validation_config(embedder_config: &EmbeddersConfig) -> wasmtime
     config.wasm_backtrace_details(wasmtime::WasmBacktraceDetails::Disable);
     config.wasm_bulk_memory(true);
     config.wasm_function_references(false);
-    // Wasm memory64 and multi-memory features are disabled during validation,
-    // but enabled during execution for the Wasm-native stable memory
+    // The Wasm memory64 feature is disabled during validation unless canisters
+    // with a 64-bit main memory are allowed. The memory64 and multi-memory
+    // features are enabled during execution for the Wasm-native stable memory
     // implementation.
-    config.wasm_memory64(false);
+    config.wasm_memory64(embedder_config.feature_flags.wasm64 == FlagStatus::Enabled);
     config.wasm_multi_memory(false);
     config.wasm_reference_types(true);
-    // The SIMD instructions are disable for determinism.
+    // Relaxed SIMD instructions are disabled for determinism, as their results
+    // may differ between hardware platforms.
     config.wasm_relaxed_simd(false);
-    config.wasm_simd(false);
+    // Fixed-width SIMD instructions are deterministic, apart from the NaN
+    // payloads which are canonicalized above.
+    config.wasm_simd(true);
     // Tail calls may be enabled in the future.
     config.wasm_tail_call(false);
     // Threads are disabled for determinism.
@@ -1439,13 +1608,14 @@ pub(super) fn validate_wasm_binary<'a>(
     can_compile(wasm, config)?;
     let module = Module::parse(wasm.as_slice(), false)
         .map_err(|err| WasmValidationError::DecodingError(format!("{}", err)))?;
-    let imports_details = validate_import_section(&module)?;
+    let wasm_memory_type = validate_memory_section(&module, config.feature_flags.wasm64)?;
+    let imports_details = validate_import_section(&module, wasm_memory_type)?;
     validate_export_section(
         &module,
         config.max_number_exported_functions,
         config.max_sum_exported_function_name_lengths,
     )?;
-    validate_data_section(&module)?;
+    validate_data_section(&module, wasm_memory_type)?;
     validate_global_section(&module, config.max_globals)?;
     validate_function_section(&module, config.max_functions)?;
     let (largest_function_instruction_count, max_complexity) = validate_code_section(&module)?;
@@ -1456,6 +1626,7 @@ pub(super) fn validate_wasm_binary<'a>(
             wasm_metadata,
             largest_function_instruction_count,
             max_complexity,
+            wasm_memory_type,
         },
         module,
     ))
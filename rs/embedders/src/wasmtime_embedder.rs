@@ -20,7 +20,7 @@ use wasmtime::{
 pub use host_memory::WasmtimeMemoryCreator;
 use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
 use ic_interfaces::execution_environment::{
-    HypervisorError, HypervisorResult, InstanceStats, SystemApi, TrapCode,
+    HypervisorError, HypervisorResult, InstanceStats, SystemApi, TrapCode, WasmProfile,
 };
 use ic_logger::{debug, error, fatal, ReplicaLogger};
 use ic_replicated_state::{
@@ -162,6 +162,14 @@ struct WasmMemoryInfo {
     dirty_page_tracking: DirtyPageTracking,
 }
 
+/// Returns true if the heap of the given module is a 64-bit memory.
+fn is_wasm64_module(module: &Module) -> bool {
+    module.exports().any(|export| {
+        export.name() == WASM_HEAP_MEMORY_NAME
+            && matches!(export.ty(), wasmtime::ExternType::Memory(memory) if memory.is_64())
+    })
+}
+
 pub struct WasmtimeEmbedder {
     log: ReplicaLogger,
     config: EmbeddersConfig,
@@ -226,13 +234,25 @@ impl WasmtimeEmbedder {
 
     pub fn pre_instantiate(&self, module: &Module) -> HypervisorResult<InstancePre<StoreData>> {
         let mut linker: wasmtime::Linker<StoreData> = Linker::new(module.engine());
-        system_api::syscalls(
-            &mut linker,
-            self.config.feature_flags,
-            self.config.stable_memory_dirty_page_limit,
-            self.config.stable_memory_accessed_page_limit,
-            self.config.metering_type,
-        );
+        // Canisters with a 64-bit heap pass heap addresses and sizes to the
+        // System API as `i64`.
+        if is_wasm64_module(module) {
+            system_api::syscalls::<u64>(
+                &mut linker,
+                self.config.feature_flags,
+                self.config.stable_memory_dirty_page_limit,
+                self.config.stable_memory_accessed_page_limit,
+                self.config.metering_type,
+            );
+        } else {
+            system_api::syscalls::<u32>(
+                &mut linker,
+                self.config.feature_flags,
+                self.config.stable_memory_dirty_page_limit,
+                self.config.stable_memory_accessed_page_limit,
+                self.config.metering_type,
+            );
+        }
 
         let instance_pre = linker.instantiate_pre(module).map_err(|e| {
             HypervisorError::WasmEngineError(WasmEngineError::FailedToInstantiateModule(format!(
@@ -316,6 +336,15 @@ impl WasmtimeEmbedder {
         result
     }
 
+    /// Returns true if the module in the given cache has a 64-bit heap.
+    pub fn is_wasm64(cache: &EmbedderCache) -> bool {
+        cache
+            .downcast::<HypervisorResult<InstancePre<StoreData>>>()
+            .expect("incompatible embedder cache, expected HypervisorResult<wasmtime::InstancePre<StoreData>>>")
+            .as_ref()
+            .map_or(false, |instance_pre| is_wasm64_module(instance_pre.module()))
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn new_instance(
         &self,
@@ -342,6 +371,10 @@ impl WasmtimeEmbedder {
                 num_instructions_global: None,
                 log: self.log.clone(),
                 num_stable_dirty_pages_from_non_native_writes: NumPages::from(0),
+                wasm_profiler: match self.config.wasm_profiling {
+                    FlagStatus::Enabled => Some(WasmProfiler::default()),
+                    FlagStatus::Disabled => None,
+                },
             },
         );
 
@@ -395,6 +428,7 @@ impl WasmtimeEmbedder {
                                 Global::I64(val) => Val::I64(*val),
                                 Global::F32(val) => Val::F32((val).to_bits()),
                                 Global::F64(val) => Val::F64((val).to_bits()),
+                                Global::V128(val) => Val::V128((*val).into()),
                             },
                         )
                         .unwrap_or_else(|e| {
@@ -403,6 +437,7 @@ impl WasmtimeEmbedder {
                                 Global::I64(val) => (val).to_string(),
                                 Global::F32(val) => (val).to_string(),
                                 Global::F64(val) => (val).to_string(),
+                                Global::V128(val) => (val).to_string(),
                             };
                             fatal!(
                                 self.log,
@@ -648,6 +683,8 @@ pub struct StoreData {
     pub log: ReplicaLogger,
     /// Tracks the number of dirty pages in stable memory in non-native stable mode
     pub num_stable_dirty_pages_from_non_native_writes: NumPages,
+    /// Collects the Wasm profile if Wasm profiling is enabled.
+    pub wasm_profiler: Option<WasmProfiler>,
 }
 
 impl StoreData {
@@ -677,6 +714,54 @@ impl StoreData {
     }
 }
 
+/// Aggregates the function entries and exits reported by the profiling
+/// instrumentation into a [`WasmProfile`].
+#[derive(Default)]
+pub struct WasmProfiler {
+    stack: Vec<u32>,
+    last_instructions: u64,
+    profile: WasmProfile,
+}
+
+impl WasmProfiler {
+    // Attributes the instructions executed since the previous event to the
+    // current call stack.
+    fn record(&mut self, instructions: u64) {
+        let delta = instructions.saturating_sub(self.last_instructions);
+        self.last_instructions = self.last_instructions.max(instructions);
+        if delta == 0 {
+            return;
+        }
+        match self.profile.stacks.get_mut(&self.stack) {
+            Some(total) => *total += delta,
+            None => {
+                self.profile.stacks.insert(self.stack.clone(), delta);
+            }
+        }
+    }
+
+    pub fn enter(&mut self, func: u32, instructions: u64) {
+        self.record(instructions);
+        self.stack.push(func);
+    }
+
+    pub fn exit(&mut self, func: u32, instructions: u64) {
+        self.record(instructions);
+        // Pop the innermost frame of the function together with any frames
+        // above it that were left without a matching exit.
+        if let Some(pos) = self.stack.iter().rposition(|f| *f == func) {
+            self.stack.truncate(pos);
+        }
+    }
+
+    /// Returns the profile, attributing the remaining instructions of the
+    /// message to the frames that are still on the stack, e.g. after a trap.
+    pub fn finish(mut self, instructions: u64) -> WasmProfile {
+        self.record(instructions);
+        self.profile
+    }
+}
+
 pub struct PageAccessResults {
     pub dirty_pages: Vec<PageIndex>,
     pub num_accessed_pages: usize,
@@ -1076,7 +1161,8 @@ impl WasmtimeInstance {
     }
 
     /// Returns the heap size.
-    /// Result is guaranteed to fit in a `u32`.
+    /// Result is guaranteed to fit in a `u32` unless the heap is a 64-bit
+    /// memory.
     pub fn heap_size(&mut self, canister_memory_type: CanisterMemoryType) -> NumWasmPages {
         let name = match canister_memory_type {
             CanisterMemoryType::Heap => WASM_HEAP_MEMORY_NAME,
@@ -1085,6 +1171,12 @@ impl WasmtimeInstance {
         NumWasmPages::from(self.get_memory(name).map_or(0, |mem| mem.size(&self.store)) as usize)
     }
 
+    /// Returns true if the heap is a 64-bit memory.
+    pub fn is_wasm64(&mut self) -> bool {
+        self.get_memory(WASM_HEAP_MEMORY_NAME)
+            .map_or(false, |mem| mem.ty(&self.store).is_64())
+    }
+
     /// Returns a list of exported globals.
     pub fn get_exported_globals(&mut self) -> HypervisorResult<Vec<Global>> {
         let globals = get_exported_globals(
@@ -1108,6 +1200,12 @@ impl WasmtimeInstance {
                 ValType::F64 => Ok(Global::F64(
                     g.get(&mut self.store).f64().expect("global f64"),
                 )),
+                ValType::V128 => Ok(Global::V128(
+                    g.get(&mut self.store)
+                        .v128()
+                        .expect("global v128")
+                        .as_u128(),
+                )),
                 _ => Err(HypervisorError::WasmEngineError(WasmEngineError::Other(
                     "unexpected global value type".to_string(),
                 ))),
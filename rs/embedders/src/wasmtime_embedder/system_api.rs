@@ -16,10 +16,10 @@ use ic_sys::PAGE_SIZE;
 use ic_types::{Cycles, NumBytes, NumInstructions, NumPages, Time};
 use ic_wasm_types::WasmEngineError;
 
-use wasmtime::{AsContextMut, Caller, Global, Linker, Val};
+use wasmtime::{AsContextMut, Caller, Global, Linker, Val, WasmTy};
 
 use crate::InternalErrorCode;
-use std::convert::TryFrom;
+use std::{convert::TryFrom, num::TryFromIntError};
 
 use crate::wasmtime_embedder::system_api_complexity::system_api;
 use ic_system_api::SystemApiImpl;
@@ -302,7 +302,23 @@ fn ic0_performance_counter_helper(
     }
 }
 
-pub(crate) fn syscalls(
+/// The type of the heap addresses and sizes passed to and returned from the
+/// System API: `u32` for canisters with a 32-bit heap and `u64` for canisters
+/// with a 64-bit heap.
+pub(crate) trait HeapPointer:
+    WasmTy + Copy + Into<u64> + TryFrom<usize, Error = TryFromIntError> + 'static
+{
+}
+
+impl HeapPointer for u32 {}
+impl HeapPointer for u64 {}
+
+fn to_usize<I: HeapPointer>(value: I) -> usize {
+    let value: u64 = value.into();
+    value as usize
+}
+
+pub(crate) fn syscalls<I: HeapPointer>(
     linker: &mut Linker<StoreData>,
     feature_flags: FeatureFlags,
     stable_memory_dirty_page_limit: NumPages,
@@ -354,7 +370,8 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "msg_caller_copy", {
-            move |mut caller: Caller<'_, StoreData>, dst: u32, offset: u32, size: u32| {
+            move |mut caller: Caller<'_, StoreData>, dst: I, offset: I, size: I| {
+                let (dst, offset, size) = (to_usize(dst), to_usize(offset), to_usize(size));
                 charge_for_cpu_and_mem(
                     &mut caller,
                     overhead!(MSG_CALLER_COPY, metering_type),
@@ -364,7 +381,7 @@ pub(crate) fn syscalls(
                     system_api.ic0_msg_caller_copy(dst, offset, size, memory)
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as usize, size as usize)
+                    mark_writes_on_bytemap(&mut caller, dst, size)
                 } else {
                     Ok(())
                 }
@@ -377,7 +394,7 @@ pub(crate) fn syscalls(
             move |mut caller: Caller<'_, StoreData>| {
                 charge_for_cpu(&mut caller, overhead!(MSG_CALLER_SIZE, metering_type))?;
                 with_system_api(&mut caller, |s| s.ic0_msg_caller_size()).and_then(|s| {
-                    i32::try_from(s).map_err(|e| {
+                    I::try_from(s as usize).map_err(|e| {
                         anyhow::Error::msg(format!("ic0::msg_caller_size failed: {}", e))
                     })
                 })
@@ -390,7 +407,7 @@ pub(crate) fn syscalls(
             move |mut caller: Caller<'_, StoreData>| {
                 charge_for_cpu(&mut caller, overhead!(MSG_ARG_DATA_SIZE, metering_type))?;
                 with_system_api(&mut caller, |s| s.ic0_msg_arg_data_size()).and_then(|s| {
-                    i32::try_from(s).map_err(|e| {
+                    I::try_from(s as usize).map_err(|e| {
                         anyhow::Error::msg(format!("ic0::msg_arg_data_size failed: {}", e))
                     })
                 })
@@ -400,7 +417,8 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "msg_arg_data_copy", {
-            move |mut caller: Caller<'_, StoreData>, dst: u32, offset: u32, size: u32| {
+            move |mut caller: Caller<'_, StoreData>, dst: I, offset: I, size: I| {
+                let (dst, offset, size) = (to_usize(dst), to_usize(offset), to_usize(size));
                 charge_for_cpu_and_mem(
                     &mut caller,
                     overhead!(MSG_ARG_DATA_COPY, metering_type),
@@ -410,7 +428,7 @@ pub(crate) fn syscalls(
                     system_api.ic0_msg_arg_data_copy(dst, offset, size, mem)
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as usize, size as usize)
+                    mark_writes_on_bytemap(&mut caller, dst, size)
                 } else {
                     Ok(())
                 }
@@ -423,7 +441,7 @@ pub(crate) fn syscalls(
             move |mut caller: Caller<'_, StoreData>| {
                 charge_for_cpu(&mut caller, overhead!(MSG_METHOD_NAME_SIZE, metering_type))?;
                 with_system_api(&mut caller, |s| s.ic0_msg_method_name_size()).and_then(|s| {
-                    i32::try_from(s).map_err(|e| {
+                    I::try_from(s as usize).map_err(|e| {
                         anyhow::Error::msg(format!("ic0::msg_metohd_name_size failed: {}", e))
                     })
                 })
@@ -433,7 +451,8 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "msg_method_name_copy", {
-            move |mut caller: Caller<'_, StoreData>, dst: u32, offset: u32, size: u32| {
+            move |mut caller: Caller<'_, StoreData>, dst: I, offset: I, size: I| {
+                let (dst, offset, size) = (to_usize(dst), to_usize(offset), to_usize(size));
                 charge_for_cpu_and_mem(
                     &mut caller,
                     overhead!(MSG_METHOD_NAME_COPY, metering_type),
@@ -443,7 +462,7 @@ pub(crate) fn syscalls(
                     system_api.ic0_msg_method_name_copy(dst, offset, size, memory)
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as usize, size as usize)
+                    mark_writes_on_bytemap(&mut caller, dst, size)
                 } else {
                     Ok(())
                 }
@@ -462,11 +481,12 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "msg_reply_data_append", {
-            move |mut caller: Caller<'_, StoreData>, src: u32, size: u32| {
+            move |mut caller: Caller<'_, StoreData>, src: I, size: I| {
+                let (src, size) = (to_usize(src), to_usize(size));
                 charge_for_cpu_and_mem(
                     &mut caller,
                     overhead!(MSG_REPLY_DATA_APPEND, metering_type),
-                    (INSTRUCTIONS_PER_BYTE_CONVERSION_FACTOR * size) as u64,
+                    INSTRUCTIONS_PER_BYTE_CONVERSION_FACTOR as u64 * size as u64,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_msg_reply_data_append(src, size, memory)
@@ -493,13 +513,31 @@ pub(crate) fn syscalls(
         })
         .unwrap();
 
+    linker
+        .func_wrap("ic0", "msg_deadline", {
+            move |mut caller: Caller<'_, StoreData>| {
+                charge_for_cpu(&mut caller, overhead!(MSG_DEADLINE, metering_type))?;
+                if feature_flags.best_effort_responses == FlagStatus::Enabled {
+                    with_system_api(&mut caller, |s| s.ic0_msg_deadline())
+                } else {
+                    with_error_handling(&mut caller, |_| {
+                        Err(HypervisorError::ContractViolation(
+                            "ic0.msg_deadline is not supported.".to_string(),
+                        ))
+                    })
+                }
+            }
+        })
+        .unwrap();
+
     linker
         .func_wrap("ic0", "msg_reject", {
-            move |mut caller: Caller<'_, StoreData>, src: u32, size: u32| {
+            move |mut caller: Caller<'_, StoreData>, src: I, size: I| {
+                let (src, size) = (to_usize(src), to_usize(size));
                 charge_for_cpu_and_mem(
                     &mut caller,
                     overhead!(MSG_REJECT, metering_type),
-                    (INSTRUCTIONS_PER_BYTE_CONVERSION_FACTOR * size) as u64,
+                    INSTRUCTIONS_PER_BYTE_CONVERSION_FACTOR as u64 * size as u64,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_msg_reject(src, size, memory)
@@ -513,7 +551,7 @@ pub(crate) fn syscalls(
             move |mut caller: Caller<'_, StoreData>| {
                 charge_for_cpu(&mut caller, overhead!(MSG_REJECT_MSG_SIZE, metering_type))?;
                 with_system_api(&mut caller, |s| s.ic0_msg_reject_msg_size()).and_then(|s| {
-                    i32::try_from(s).map_err(|e| {
+                    I::try_from(s as usize).map_err(|e| {
                         anyhow::Error::msg(format!("ic0_msg_reject_msg_size failed: {}", e))
                     })
                 })
@@ -523,7 +561,8 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "msg_reject_msg_copy", {
-            move |mut caller: Caller<'_, StoreData>, dst: u32, offset: u32, size: u32| {
+            move |mut caller: Caller<'_, StoreData>, dst: I, offset: I, size: I| {
+                let (dst, offset, size) = (to_usize(dst), to_usize(offset), to_usize(size));
                 charge_for_cpu_and_mem(
                     &mut caller,
                     overhead!(MSG_REJECT_MSG_COPY, metering_type),
@@ -533,7 +572,7 @@ pub(crate) fn syscalls(
                     system_api.ic0_msg_reject_msg_copy(dst, offset, size, memory)
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as usize, size as usize)
+                    mark_writes_on_bytemap(&mut caller, dst, size)
                 } else {
                     Ok(())
                 }
@@ -546,7 +585,7 @@ pub(crate) fn syscalls(
             move |mut caller: Caller<'_, StoreData>| {
                 charge_for_cpu(&mut caller, overhead!(CANISTER_SELF_SIZE, metering_type))?;
                 with_system_api(&mut caller, |s| s.ic0_canister_self_size()).and_then(|s| {
-                    i32::try_from(s).map_err(|e| {
+                    I::try_from(s).map_err(|e| {
                         anyhow::Error::msg(format!("ic0_canister_self_size failed: {}", e))
                     })
                 })
@@ -556,7 +595,8 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "canister_self_copy", {
-            move |mut caller: Caller<'_, StoreData>, dst: u32, offset: u32, size: u32| {
+            move |mut caller: Caller<'_, StoreData>, dst: I, offset: I, size: I| {
+                let (dst, offset, size) = (to_usize(dst), to_usize(offset), to_usize(size));
                 charge_for_cpu_and_mem(
                     &mut caller,
                     overhead!(CANISTER_SELF_COPY, metering_type),
@@ -566,7 +606,74 @@ pub(crate) fn syscalls(
                     system_api.ic0_canister_self_copy(dst, offset, size, memory)
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as usize, size as usize)
+                    mark_writes_on_bytemap(&mut caller, dst, size)
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "subnet_self_size", {
+            move |mut caller: Caller<'_, StoreData>| {
+                charge_for_cpu(&mut caller, overhead!(SUBNET_SELF_SIZE, metering_type))?;
+                with_system_api(&mut caller, |s| s.ic0_subnet_self_size()).and_then(|s| {
+                    I::try_from(s).map_err(|e| {
+                        anyhow::Error::msg(format!("ic0_subnet_self_size failed: {}", e))
+                    })
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "subnet_self_copy", {
+            move |mut caller: Caller<'_, StoreData>, dst: I, offset: I, size: I| {
+                let (dst, offset, size) = (to_usize(dst), to_usize(offset), to_usize(size));
+                charge_for_cpu_and_mem(
+                    &mut caller,
+                    overhead!(SUBNET_SELF_COPY, metering_type),
+                    size as u64,
+                )?;
+                with_memory_and_system_api(&mut caller, |system_api, memory| {
+                    system_api.ic0_subnet_self_copy(dst, offset, size, memory)
+                })?;
+                if feature_flags.write_barrier == FlagStatus::Enabled {
+                    mark_writes_on_bytemap(&mut caller, dst, size)
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "root_key_size", {
+            move |mut caller: Caller<'_, StoreData>| {
+                charge_for_cpu(&mut caller, overhead!(ROOT_KEY_SIZE, metering_type))?;
+                with_system_api(&mut caller, |s| s.ic0_root_key_size()).and_then(|s| {
+                    I::try_from(s)
+                        .map_err(|e| anyhow::Error::msg(format!("ic0_root_key_size failed: {}", e)))
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "root_key_copy", {
+            move |mut caller: Caller<'_, StoreData>, dst: I, offset: I, size: I| {
+                let (dst, offset, size) = (to_usize(dst), to_usize(offset), to_usize(size));
+                charge_for_cpu_and_mem(
+                    &mut caller,
+                    overhead!(ROOT_KEY_COPY, metering_type),
+                    size as u64,
+                )?;
+                with_memory_and_system_api(&mut caller, |system_api, memory| {
+                    system_api.ic0_root_key_copy(dst, offset, size, memory)
+                })?;
+                if feature_flags.write_barrier == FlagStatus::Enabled {
+                    mark_writes_on_bytemap(&mut caller, dst, size)
                 } else {
                     Ok(())
                 }
@@ -576,34 +683,42 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "debug_print", {
-            move |mut caller: Caller<'_, StoreData>, offset: u32, length: u32| {
+            move |mut caller: Caller<'_, StoreData>, offset: I, length: I| {
+                let (offset, length) = (to_usize(offset), to_usize(length));
                 charge_for_cpu_and_mem(
                     &mut caller,
                     overhead!(DEBUG_PRINT, metering_type),
                     length as u64,
                 )?;
-                match (
+                let print_output = match (
                     caller.data().system_api.as_ref().unwrap().subnet_type(),
                     feature_flags.rate_limiting_of_debug_prints,
                 ) {
-                    // Debug print is a no-op on non-system subnets with rate limiting.
-                    (SubnetType::Application, FlagStatus::Enabled) => Ok(()),
-                    (SubnetType::VerifiedApplication, FlagStatus::Enabled) => Ok(()),
+                    // Debug print output is skipped on non-system subnets with rate limiting.
+                    (SubnetType::Application, FlagStatus::Enabled)
+                    | (SubnetType::VerifiedApplication, FlagStatus::Enabled) => false,
                     // If rate limiting is disabled or the subnet is a system subnet, then
                     // debug print produces output.
-                    (_, FlagStatus::Disabled) | (SubnetType::System, FlagStatus::Enabled) => {
-                        with_memory_and_system_api(&mut caller, |system_api, memory| {
-                            system_api.ic0_debug_print(offset, length, memory)
-                        })
+                    (_, FlagStatus::Disabled) | (SubnetType::System, FlagStatus::Enabled) => true,
+                };
+                // The message is always recorded in the canister log, regardless of
+                // whether it is printed.
+                with_memory_and_system_api(&mut caller, |system_api, memory| {
+                    system_api.save_log_message(offset, length, memory);
+                    if print_output {
+                        system_api.ic0_debug_print(offset, length, memory)
+                    } else {
+                        Ok(())
                     }
-                }
+                })
             }
         })
         .unwrap();
 
     linker
         .func_wrap("ic0", "trap", {
-            move |mut caller: Caller<'_, StoreData>, offset: u32, length: u32| -> Result<(), _> {
+            move |mut caller: Caller<'_, StoreData>, offset: I, length: I| -> Result<(), _> {
+                let (offset, length) = (to_usize(offset), to_usize(length));
                 charge_for_cpu_and_mem(&mut caller, overhead!(TRAP, metering_type), length as u64)?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_trap(offset, length, memory)
@@ -615,14 +730,20 @@ pub(crate) fn syscalls(
     linker
         .func_wrap("ic0", "call_new", {
             move |mut caller: Caller<'_, StoreData>,
-                  callee_src: u32,
-                  callee_size: u32,
-                  name_src: u32,
-                  name_len: u32,
+                  callee_src: I,
+                  callee_size: I,
+                  name_src: I,
+                  name_len: I,
                   reply_fun: u32,
                   reply_env: u32,
                   reject_fun: u32,
                   reject_env: u32| {
+                let (callee_src, callee_size, name_src, name_len) = (
+                    to_usize(callee_src),
+                    to_usize(callee_size),
+                    to_usize(name_src),
+                    to_usize(name_len),
+                );
                 charge_for_cpu_and_mem(
                     &mut caller,
                     overhead!(CALL_NEW, metering_type),
@@ -647,11 +768,12 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "call_data_append", {
-            move |mut caller: Caller<'_, StoreData>, src: u32, size: u32| {
+            move |mut caller: Caller<'_, StoreData>, src: I, size: I| {
+                let (src, size) = (to_usize(src), to_usize(size));
                 charge_for_cpu_and_mem(
                     &mut caller,
                     overhead!(CALL_DATA_APPEND, metering_type),
-                    (INSTRUCTIONS_PER_BYTE_CONVERSION_FACTOR * size) as u64,
+                    INSTRUCTIONS_PER_BYTE_CONVERSION_FACTOR as u64 * size as u64,
                 )?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_call_data_append(src, size, memory)
@@ -669,6 +791,28 @@ pub(crate) fn syscalls(
         })
         .unwrap();
 
+    linker
+        .func_wrap("ic0", "call_with_best_effort_response", {
+            move |mut caller: Caller<'_, StoreData>, timeout_seconds: u32| {
+                charge_for_cpu(
+                    &mut caller,
+                    overhead!(CALL_WITH_BEST_EFFORT_RESPONSE, metering_type),
+                )?;
+                if feature_flags.best_effort_responses == FlagStatus::Enabled {
+                    with_system_api(&mut caller, |s| {
+                        s.ic0_call_with_best_effort_response(timeout_seconds)
+                    })
+                } else {
+                    with_error_handling(&mut caller, |_| {
+                        Err(HypervisorError::ContractViolation(
+                            "ic0.call_with_best_effort_response is not supported.".to_string(),
+                        ))
+                    })
+                }
+            }
+        })
+        .unwrap();
+
     linker
         .func_wrap("ic0", "call_cycles_add", {
             move |mut caller: Caller<'_, StoreData>, amount: u64| {
@@ -886,7 +1030,8 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "canister_cycle_balance128", {
-            move |mut caller: Caller<'_, StoreData>, dst: u32| {
+            move |mut caller: Caller<'_, StoreData>, dst: I| {
+                let dst = to_usize(dst);
                 charge_for_cpu(
                     &mut caller,
                     overhead!(CANISTER_CYCLE_BALANCE128, metering_type),
@@ -895,7 +1040,7 @@ pub(crate) fn syscalls(
                     s.ic0_canister_cycle_balance128(dst, memory)
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as usize, 16)
+                    mark_writes_on_bytemap(&mut caller, dst, 16)
                 } else {
                     Ok(())
                 }
@@ -918,7 +1063,8 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "msg_cycles_available128", {
-            move |mut caller: Caller<'_, StoreData>, dst: u32| {
+            move |mut caller: Caller<'_, StoreData>, dst: I| {
+                let dst = to_usize(dst);
                 charge_for_cpu(
                     &mut caller,
                     overhead!(MSG_CYCLES_AVAILABLE128, metering_type),
@@ -927,7 +1073,7 @@ pub(crate) fn syscalls(
                     system_api.ic0_msg_cycles_available128(dst, memory)
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as usize, 16)
+                    mark_writes_on_bytemap(&mut caller, dst, 16)
                 } else {
                     Ok(())
                 }
@@ -950,7 +1096,8 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "msg_cycles_refunded128", {
-            move |mut caller: Caller<'_, StoreData>, dst: u32| {
+            move |mut caller: Caller<'_, StoreData>, dst: I| {
+                let dst = to_usize(dst);
                 charge_for_cpu(
                     &mut caller,
                     overhead!(MSG_CYCLES_REFUNDED128, metering_type),
@@ -959,7 +1106,7 @@ pub(crate) fn syscalls(
                     system_api.ic0_msg_cycles_refunded128(dst, memory)
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as usize, 16)
+                    mark_writes_on_bytemap(&mut caller, dst, 16)
                 } else {
                     Ok(())
                 }
@@ -978,7 +1125,8 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "msg_cycles_accept128", {
-            move |mut caller: Caller<'_, StoreData>, amount_high: u64, amount_low: u64, dst: u32| {
+            move |mut caller: Caller<'_, StoreData>, amount_high: u64, amount_low: u64, dst: I| {
+                let dst = to_usize(dst);
                 charge_for_cpu(&mut caller, overhead!(MSG_CYCLES_ACCEPT128, metering_type))?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_msg_cycles_accept128(
@@ -988,7 +1136,7 @@ pub(crate) fn syscalls(
                     )
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as usize, 16)
+                    mark_writes_on_bytemap(&mut caller, dst, 16)
                 } else {
                     Ok(())
                 }
@@ -1012,6 +1160,37 @@ pub(crate) fn syscalls(
         })
         .unwrap();
 
+    // The profiling functions are only imported by modules instrumented with
+    // Wasm profiling enabled. They record the number of instructions executed
+    // so far by the message on function entry and exit.
+    linker
+        .func_wrap("__", "profile_enter", {
+            move |mut caller: Caller<'_, StoreData>, func: u32| -> Result<(), _> {
+                with_error_handling(&mut caller, |c| {
+                    let instructions = ic0_performance_counter_helper(c, 0)?;
+                    if let Some(profiler) = c.data_mut().wasm_profiler.as_mut() {
+                        profiler.enter(func, instructions);
+                    }
+                    Ok(())
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("__", "profile_exit", {
+            move |mut caller: Caller<'_, StoreData>, func: u32| -> Result<(), _> {
+                with_error_handling(&mut caller, |c| {
+                    let instructions = ic0_performance_counter_helper(c, 0)?;
+                    if let Some(profiler) = c.data_mut().wasm_profiler.as_mut() {
+                        profiler.exit(func, instructions);
+                    }
+                    Ok(())
+                })
+            }
+        })
+        .unwrap();
+
     linker
         .func_wrap("__", "update_available_memory", {
             move |mut caller: Caller<'_, StoreData>,
@@ -1065,7 +1244,8 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "certified_data_set", {
-            move |mut caller: Caller<'_, StoreData>, src: u32, size: u32| {
+            move |mut caller: Caller<'_, StoreData>, src: I, size: I| {
+                let (src, size) = (to_usize(src), to_usize(size));
                 charge_for_cpu_and_mem(
                     &mut caller,
                     overhead!(CERTIFIED_DATA_SET, metering_type),
@@ -1094,14 +1274,19 @@ pub(crate) fn syscalls(
         .func_wrap("ic0", "data_certificate_size", {
             move |mut caller: Caller<'_, StoreData>| {
                 charge_for_cpu(&mut caller, overhead!(DATA_CERTIFICATE_SIZE, metering_type))?;
-                with_system_api(&mut caller, |s| s.ic0_data_certificate_size())
+                with_system_api(&mut caller, |s| s.ic0_data_certificate_size()).and_then(|s| {
+                    I::try_from(s as usize).map_err(|e| {
+                        anyhow::Error::msg(format!("ic0_data_certificate_size failed: {}", e))
+                    })
+                })
             }
         })
         .unwrap();
 
     linker
         .func_wrap("ic0", "is_controller", {
-            move |mut caller: Caller<'_, StoreData>, src: u32, size: u32| {
+            move |mut caller: Caller<'_, StoreData>, src: I, size: I| {
+                let (src, size) = (to_usize(src), to_usize(size));
                 charge_for_cpu_and_mem(
                     &mut caller,
                     overhead!(IS_CONTROLLER, metering_type),
@@ -1128,7 +1313,8 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "data_certificate_copy", {
-            move |mut caller: Caller<'_, StoreData>, dst: u32, offset: u32, size: u32| {
+            move |mut caller: Caller<'_, StoreData>, dst: I, offset: I, size: I| {
+                let (dst, offset, size) = (to_usize(dst), to_usize(offset), to_usize(size));
                 charge_for_cpu_and_mem(
                     &mut caller,
                     overhead!(DATA_CERTIFICATE_COPY, metering_type),
@@ -1138,7 +1324,7 @@ pub(crate) fn syscalls(
                     system_api.ic0_data_certificate_copy(dst, offset, size, memory)
                 })?;
                 if feature_flags.write_barrier == FlagStatus::Enabled {
-                    mark_writes_on_bytemap(&mut caller, dst as usize, size as usize)
+                    mark_writes_on_bytemap(&mut caller, dst, size)
                 } else {
                     Ok(())
                 }
@@ -1159,7 +1345,8 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "cycles_burn128", {
-            move |mut caller: Caller<'_, StoreData>, amount_high: u64, amount_low: u64, dst: u32| {
+            move |mut caller: Caller<'_, StoreData>, amount_high: u64, amount_low: u64, dst: I| {
+                let dst = to_usize(dst);
                 with_memory_and_system_api(&mut caller, |s, memory| {
                     s.ic0_cycles_burn128(Cycles::from_parts(amount_high, amount_low), dst, memory)
                 })
@@ -1168,6 +1355,103 @@ pub(crate) fn syscalls(
         })
         .unwrap();
 
+    linker
+        .func_wrap("ic0", "cost_call", {
+            move |mut caller: Caller<'_, StoreData>,
+                  method_name_size: u64,
+                  payload_size: u64,
+                  dst: I| {
+                let dst = to_usize(dst);
+                charge_for_cpu(&mut caller, overhead!(COST_CALL, metering_type))?;
+                with_memory_and_system_api(&mut caller, |s, memory| {
+                    s.ic0_cost_call(method_name_size, payload_size, dst, memory)
+                })?;
+                if feature_flags.write_barrier == FlagStatus::Enabled {
+                    mark_writes_on_bytemap(&mut caller, dst, 16)
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "cost_create_canister", {
+            move |mut caller: Caller<'_, StoreData>, dst: I| {
+                let dst = to_usize(dst);
+                charge_for_cpu(&mut caller, overhead!(COST_CREATE_CANISTER, metering_type))?;
+                with_memory_and_system_api(&mut caller, |s, memory| {
+                    s.ic0_cost_create_canister(dst, memory)
+                })?;
+                if feature_flags.write_barrier == FlagStatus::Enabled {
+                    mark_writes_on_bytemap(&mut caller, dst, 16)
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "cost_http_request", {
+            move |mut caller: Caller<'_, StoreData>,
+                  request_size: u64,
+                  max_res_bytes: u64,
+                  dst: I| {
+                let dst = to_usize(dst);
+                charge_for_cpu(&mut caller, overhead!(COST_HTTP_REQUEST, metering_type))?;
+                with_memory_and_system_api(&mut caller, |s, memory| {
+                    s.ic0_cost_http_request(request_size, max_res_bytes, dst, memory)
+                })?;
+                if feature_flags.write_barrier == FlagStatus::Enabled {
+                    mark_writes_on_bytemap(&mut caller, dst, 16)
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "cost_sign_with_ecdsa", {
+            move |mut caller: Caller<'_, StoreData>, src: I, size: I, ecdsa_curve: u32, dst: I| {
+                let (src, size, dst) = (to_usize(src), to_usize(size), to_usize(dst));
+                charge_for_cpu_and_mem(
+                    &mut caller,
+                    overhead!(COST_SIGN_WITH_ECDSA, metering_type),
+                    size as u64,
+                )?;
+                let result = with_memory_and_system_api(&mut caller, |s, memory| {
+                    s.ic0_cost_sign_with_ecdsa(src, size, ecdsa_curve, dst, memory)
+                })?;
+                if result == 0 && feature_flags.write_barrier == FlagStatus::Enabled {
+                    mark_writes_on_bytemap(&mut caller, dst, 16)?;
+                }
+                Ok(result as i32)
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "cost_sign_with_schnorr", {
+            move |mut caller: Caller<'_, StoreData>, src: I, size: I, algorithm: u32, dst: I| {
+                let (src, size, dst) = (to_usize(src), to_usize(size), to_usize(dst));
+                charge_for_cpu_and_mem(
+                    &mut caller,
+                    overhead!(COST_SIGN_WITH_SCHNORR, metering_type),
+                    size as u64,
+                )?;
+                let result = with_memory_and_system_api(&mut caller, |s, memory| {
+                    s.ic0_cost_sign_with_schnorr(src, size, algorithm, dst, memory)
+                })?;
+                if result == 0 && feature_flags.write_barrier == FlagStatus::Enabled {
+                    mark_writes_on_bytemap(&mut caller, dst, 16)?;
+                }
+                Ok(result as i32)
+            }
+        })
+        .unwrap();
+
     linker
         .func_wrap("__", "internal_trap", {
             move |mut caller: Caller<'_, StoreData>, err_code: i32| -> Result<(), _> {
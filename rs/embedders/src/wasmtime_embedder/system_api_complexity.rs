@@ -27,6 +27,7 @@ pub mod overhead {
         pub const CALL_NEW: NumInstructions = NumInstructions::new(0);
         pub const CALL_ON_CLEANUP: NumInstructions = NumInstructions::new(0);
         pub const CALL_PERFORM: NumInstructions = NumInstructions::new(0);
+        pub const CALL_WITH_BEST_EFFORT_RESPONSE: NumInstructions = NumInstructions::new(0);
         pub const CANISTER_CYCLE_BALANCE: NumInstructions = NumInstructions::new(0);
         pub const CANISTER_CYCLE_BALANCE128: NumInstructions = NumInstructions::new(0);
         pub const CANISTER_SELF_COPY: NumInstructions = NumInstructions::new(0);
@@ -34,6 +35,11 @@ pub mod overhead {
         pub const CANISTER_STATUS: NumInstructions = NumInstructions::new(0);
         pub const CANISTER_VERSION: NumInstructions = NumInstructions::new(0);
         pub const CERTIFIED_DATA_SET: NumInstructions = NumInstructions::new(0);
+        pub const COST_CALL: NumInstructions = NumInstructions::new(0);
+        pub const COST_CREATE_CANISTER: NumInstructions = NumInstructions::new(0);
+        pub const COST_HTTP_REQUEST: NumInstructions = NumInstructions::new(0);
+        pub const COST_SIGN_WITH_ECDSA: NumInstructions = NumInstructions::new(0);
+        pub const COST_SIGN_WITH_SCHNORR: NumInstructions = NumInstructions::new(0);
         pub const CYCLES_BURN: NumInstructions = NumInstructions::new(100);
         pub const DATA_CERTIFICATE_COPY: NumInstructions = NumInstructions::new(0);
         pub const DATA_CERTIFICATE_PRESENT: NumInstructions = NumInstructions::new(0);
@@ -52,6 +58,7 @@ pub mod overhead {
         pub const MSG_CYCLES_AVAILABLE128: NumInstructions = NumInstructions::new(0);
         pub const MSG_CYCLES_REFUNDED: NumInstructions = NumInstructions::new(0);
         pub const MSG_CYCLES_REFUNDED128: NumInstructions = NumInstructions::new(0);
+        pub const MSG_DEADLINE: NumInstructions = NumInstructions::new(0);
         pub const MSG_METHOD_NAME_COPY: NumInstructions = NumInstructions::new(20);
         pub const MSG_METHOD_NAME_SIZE: NumInstructions = NumInstructions::new(0);
         pub const MSG_REJECT_CODE: NumInstructions = NumInstructions::new(0);
@@ -61,6 +68,8 @@ pub mod overhead {
         pub const MSG_REPLY_DATA_APPEND: NumInstructions = NumInstructions::new(20);
         pub const MSG_REPLY: NumInstructions = NumInstructions::new(0);
         pub const PERFORMANCE_COUNTER: NumInstructions = NumInstructions::new(200);
+        pub const ROOT_KEY_COPY: NumInstructions = NumInstructions::new(0);
+        pub const ROOT_KEY_SIZE: NumInstructions = NumInstructions::new(0);
         pub const STABLE_GROW: NumInstructions = NumInstructions::new(0);
         pub const STABLE_READ: NumInstructions = NumInstructions::new(20);
         pub const STABLE_SIZE: NumInstructions = NumInstructions::new(0);
@@ -69,6 +78,8 @@ pub mod overhead {
         pub const STABLE64_READ: NumInstructions = NumInstructions::new(20);
         pub const STABLE64_SIZE: NumInstructions = NumInstructions::new(0);
         pub const STABLE64_WRITE: NumInstructions = NumInstructions::new(20);
+        pub const SUBNET_SELF_COPY: NumInstructions = NumInstructions::new(0);
+        pub const SUBNET_SELF_SIZE: NumInstructions = NumInstructions::new(0);
         pub const TIME: NumInstructions = NumInstructions::new(0);
         pub const TRAP: NumInstructions = NumInstructions::new(20);
     }
@@ -82,6 +93,7 @@ pub mod overhead {
         pub const CALL_NEW: NumInstructions = NumInstructions::new(1_500);
         pub const CALL_ON_CLEANUP: NumInstructions = NumInstructions::new(500);
         pub const CALL_PERFORM: NumInstructions = NumInstructions::new(5_000);
+        pub const CALL_WITH_BEST_EFFORT_RESPONSE: NumInstructions = NumInstructions::new(500);
         pub const CANISTER_CYCLE_BALANCE: NumInstructions = NumInstructions::new(500);
         pub const CANISTER_CYCLE_BALANCE128: NumInstructions = NumInstructions::new(500);
         pub const CANISTER_SELF_COPY: NumInstructions = NumInstructions::new(500);
@@ -91,6 +103,11 @@ pub mod overhead {
         pub const CERTIFIED_DATA_SET: NumInstructions = NumInstructions::new(500);
         pub const CONTROLLER_COPY: NumInstructions = NumInstructions::new(500);
         pub const CONTROLLER_SIZE: NumInstructions = NumInstructions::new(500);
+        pub const COST_CALL: NumInstructions = NumInstructions::new(500);
+        pub const COST_CREATE_CANISTER: NumInstructions = NumInstructions::new(500);
+        pub const COST_HTTP_REQUEST: NumInstructions = NumInstructions::new(500);
+        pub const COST_SIGN_WITH_ECDSA: NumInstructions = NumInstructions::new(500);
+        pub const COST_SIGN_WITH_SCHNORR: NumInstructions = NumInstructions::new(500);
         pub const DATA_CERTIFICATE_COPY: NumInstructions = NumInstructions::new(500);
         pub const DATA_CERTIFICATE_PRESENT: NumInstructions = NumInstructions::new(500);
         pub const DATA_CERTIFICATE_SIZE: NumInstructions = NumInstructions::new(500);
@@ -108,6 +125,7 @@ pub mod overhead {
         pub const MSG_CYCLES_AVAILABLE128: NumInstructions = NumInstructions::new(500);
         pub const MSG_CYCLES_REFUNDED: NumInstructions = NumInstructions::new(500);
         pub const MSG_CYCLES_REFUNDED128: NumInstructions = NumInstructions::new(500);
+        pub const MSG_DEADLINE: NumInstructions = NumInstructions::new(500);
         pub const MSG_METHOD_NAME_COPY: NumInstructions = NumInstructions::new(500);
         pub const MSG_METHOD_NAME_SIZE: NumInstructions = NumInstructions::new(500);
         pub const MSG_REJECT_CODE: NumInstructions = NumInstructions::new(500);
@@ -117,6 +135,8 @@ pub mod overhead {
         pub const MSG_REPLY_DATA_APPEND: NumInstructions = NumInstructions::new(500);
         pub const MSG_REPLY: NumInstructions = NumInstructions::new(500);
         pub const PERFORMANCE_COUNTER: NumInstructions = NumInstructions::new(200);
+        pub const ROOT_KEY_COPY: NumInstructions = NumInstructions::new(500);
+        pub const ROOT_KEY_SIZE: NumInstructions = NumInstructions::new(500);
         pub const STABLE_GROW: NumInstructions = NumInstructions::new(500);
         pub const STABLE_READ: NumInstructions = NumInstructions::new(20);
         pub const STABLE_SIZE: NumInstructions = NumInstructions::new(20);
@@ -125,6 +145,8 @@ pub mod overhead {
         pub const STABLE64_READ: NumInstructions = NumInstructions::new(20);
         pub const STABLE64_SIZE: NumInstructions = NumInstructions::new(20);
         pub const STABLE64_WRITE: NumInstructions = NumInstructions::new(20);
+        pub const SUBNET_SELF_COPY: NumInstructions = NumInstructions::new(500);
+        pub const SUBNET_SELF_SIZE: NumInstructions = NumInstructions::new(500);
         pub const TIME: NumInstructions = NumInstructions::new(500);
         pub const TRAP: NumInstructions = NumInstructions::new(500);
     }
@@ -92,6 +92,7 @@ fn test_wasmtime_system_api() {
             num_instructions_global: None,
             log: no_op_logger(),
             num_stable_dirty_pages_from_non_native_writes: ic_types::NumPages::from(0),
+            wasm_profiler: None,
         },
     );
 
@@ -121,7 +122,7 @@ fn test_wasmtime_system_api() {
 
     let mut linker: wasmtime::Linker<StoreData> = wasmtime::Linker::new(&engine);
 
-    system_api::syscalls(
+    system_api::syscalls::<u32>(
         &mut linker,
         config.feature_flags,
         config.stable_memory_dirty_page_limit,
@@ -151,7 +152,7 @@ fn test_wasmtime_system_api() {
 
 #[test]
 fn test_initial_wasmtime_config() {
-    // The following proposals should be disabled: tail_call, simd, relaxed_simd,
+    // The following proposals should be disabled: tail_call, relaxed_simd,
     // threads, multi_memory, exceptions, memory64, extended_const, component_model,
     // function_references, memory_control, gc
     for (proposal, _url, wat, expected_err_msg) in [
@@ -162,10 +163,10 @@ fn test_initial_wasmtime_config() {
             "tail calls support is not enabled",
         ),
         (
-            "simd",
+            "relaxed_simd",
             "https://github.com/WebAssembly/relaxed-simd/",
-            "(module (func $f (drop (v128.const i64x2 0 0))))",
-            "SIMD support is not enabled",
+            "(module (func $f (drop (i8x16.relaxed_swizzle (v128.const i64x2 0 0) (v128.const i64x2 0 0)))))",
+            "relaxed SIMD support is not enabled",
         ),
         (
             "threads",
@@ -228,3 +229,21 @@ fn test_initial_wasmtime_config() {
         );
     }
 }
+
+#[test]
+fn test_wasmtime_config_enables_simd() {
+    let wasm_binary = BinaryEncodedWasm::new(
+        wat::parse_str(
+            r#"(module
+                (func $f (param i32) (result i32)
+                    (i32x4.extract_lane 0
+                        (i32x4.mul (i32x4.splat (local.get 0)) (v128.const i32x4 2 2 2 2)))))"#,
+        )
+        .unwrap(),
+    );
+    validate_and_instrument_for_testing(
+        &WasmtimeEmbedder::new(EmbeddersConfig::default(), no_op_logger()),
+        &wasm_binary,
+    )
+    .expect("SIMD instructions should be accepted");
+}
@@ -365,6 +365,43 @@ fn metering_plain() {
     assert_eq!(instructions_used, 1 + cost_a(10) + ctrap);
 }
 
+#[test]
+fn metering_simd() {
+    let wat = r#"
+        (module
+            (global $g1 (export "g1") (mut i64) (i64.const 0))
+            (func $test (export "canister_update test")
+                (global.set $g1
+                    (i64x2.extract_lane 1
+                        (i64x2.add
+                            (i64x2.splat (i64.const 3))
+                            (i64x2.splat (i64.const 4)))))
+            )
+        )"#;
+    let mut instance = new_instance(wat, 1000);
+    let res = instance.run(func_ref("test")).unwrap();
+
+    let g = &res.exported_globals;
+    assert_eq!(g[0], Global::I64(7));
+
+    let cc = instruction_to_cost_new(&wasmparser::Operator::I64Const { value: 1 });
+    let csplat = instruction_to_cost_new(&wasmparser::Operator::I64x2Splat);
+    let cadd = instruction_to_cost_new(&wasmparser::Operator::I64x2Add);
+    let cextract = instruction_to_cost_new(&wasmparser::Operator::I64x2ExtractLane { lane: 1 });
+    let cset = instruction_to_cost_new(&wasmparser::Operator::GlobalSet { global_index: 0 });
+    let instructions_used = instr_used(&mut instance);
+    // Function is 1 instruction.
+    assert_eq!(
+        instructions_used,
+        1 + 2 * (cc + csplat) + cadd + cextract + cset
+    );
+
+    // Now run the same with insufficient instructions
+    let mut instance = new_instance(wat, instructions_used - 1);
+    let err = instance.run(func_ref("test")).unwrap_err();
+    assert_eq!(err, HypervisorError::InstructionLimitExceeded);
+}
+
 #[test]
 fn metering_block() {
     let wat = format!(
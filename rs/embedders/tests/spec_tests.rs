@@ -744,8 +744,6 @@ fn run_testsuite(subdirectory: &str, config: &Config, parsing_multi_memory_enabl
 /// production for validation.
 fn default_config() -> Config {
     let mut config = wasmtime_validation_config(&ic_config::embedders::Config::default());
-    // Some tests require SIMD instructions to run.
-    config.wasm_simd(true);
     // This is needed to avoid stack overflows in some tests.
     config.max_wasm_stack(512 * 1024);
     config
@@ -1,12 +1,12 @@
 use std::borrow::Cow;
 
 use assert_matches::assert_matches;
-use ic_config::embedders::Config as EmbeddersConfig;
+use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
 use ic_embedders::{
     wasm_utils::{
         validate_and_instrument_for_testing,
         validation::{extract_custom_section_name, RESERVED_SYMBOLS},
-        Complexity, WasmImportsDetails, WasmValidationDetails,
+        Complexity, WasmImportsDetails, WasmMemoryType, WasmValidationDetails,
     },
     WasmtimeEmbedder,
 };
@@ -1136,3 +1136,47 @@ fn wasm_with_multiple_code_sections_is_invalid() {
         ))
     )
 }
+
+#[test]
+fn can_validate_module_with_64_bit_memory_if_enabled() {
+    let wasm = wat2wasm(
+        r#"(module
+                (import "ic0" "msg_arg_data_copy"
+                    (func $msg_arg_data_copy (param i64 i64 i64)))
+                (func (export "canister_update test")
+                    (call $msg_arg_data_copy (i64.const 0) (i64.const 0) (i64.const 1)))
+                (memory i64 1)
+                (data (i64.const 0) "abc"))"#,
+    )
+    .unwrap();
+    assert_matches!(
+        validate_wasm_binary(&wasm, &EmbeddersConfig::default()),
+        Err(WasmValidationError::WasmtimeValidation(_))
+    );
+
+    let mut config = EmbeddersConfig::default();
+    config.feature_flags.wasm64 = FlagStatus::Enabled;
+    assert_eq!(
+        validate_wasm_binary(&wasm, &config)
+            .unwrap()
+            .wasm_memory_type,
+        WasmMemoryType::Wasm64
+    );
+}
+
+#[test]
+fn can_reject_32_bit_system_api_imports_with_64_bit_memory() {
+    let wasm = wat2wasm(
+        r#"(module
+                (import "ic0" "msg_arg_data_copy"
+                    (func $msg_arg_data_copy (param i32 i32 i32)))
+                (memory i64 1))"#,
+    )
+    .unwrap();
+    let mut config = EmbeddersConfig::default();
+    config.feature_flags.wasm64 = FlagStatus::Enabled;
+    assert_matches!(
+        validate_wasm_binary(&wasm, &config),
+        Err(WasmValidationError::InvalidImportSection(_))
+    );
+}
@@ -3,7 +3,7 @@ use ic_embedders::{
     wasm_utils::instrumentation::instruction_to_cost_new, wasmtime_embedder::system_api_complexity,
 };
 use ic_interfaces::execution_environment::{HypervisorError, SystemApi, TrapCode};
-use ic_replicated_state::{canister_state::WASM_PAGE_SIZE_IN_BYTES, Global};
+use ic_replicated_state::{canister_state::WASM_PAGE_SIZE_IN_BYTES, Global, PageIndex};
 use ic_test_utilities::{
     types::ids::user_test_id,
     wasmtime_instance::{WasmtimeInstanceBuilder, DEFAULT_NUM_INSTRUCTIONS},
@@ -412,6 +412,87 @@ fn can_set_and_get_float_globals() {
     );
 }
 
+#[test]
+// Takes a Wasm with a mutable vector global that is updated using SIMD
+// instructions and checks whether we can set and get its value.
+fn can_set_and_get_v128_globals() {
+    let wat = r#"
+                    (module
+                        (global $g (export "g") (mut v128) (v128.const i64x2 0 0))
+                        (func (export "canister_update test")
+                            (global.set $g
+                                (i64x2.add (global.get $g) (v128.const i64x2 1 2)))
+                        )
+                    )"#;
+
+    let mut instance = WasmtimeInstanceBuilder::new().with_wat(wat).build();
+    let res = instance
+        .run(FuncRef::Method(WasmMethod::Update("test".to_string())))
+        .unwrap();
+    // The low lane holds 1 and the high lane holds 2.
+    assert_eq!(res.exported_globals[0], Global::V128((2 << 64) | 1));
+
+    let mut instance = WasmtimeInstanceBuilder::new()
+        .with_wat(wat)
+        .with_globals(vec![
+            Global::V128((5 << 64) | 7),
+            // Last global is the instruction counter which will be
+            // overwritten anyway.
+            Global::I64(0),
+        ])
+        .build();
+    let res = instance
+        .run(FuncRef::Method(WasmMethod::Update("test".to_string())))
+        .unwrap();
+    assert_eq!(res.exported_globals[0], Global::V128((7 << 64) | 8));
+}
+
+#[test]
+// Checks that NaNs produced by SIMD floating point instructions are
+// canonicalized, so that their bit patterns do not depend on the hardware.
+fn simd_nans_are_canonicalized() {
+    let wat = r#"
+                    (module
+                        (global $g (export "g") (mut v128) (v128.const i64x2 0 0))
+                        (func (export "canister_update test")
+                            (global.set $g
+                                (f32x4.div (v128.const f32x4 0 0 0 0) (v128.const f32x4 0 0 0 0)))
+                        )
+                    )"#;
+
+    let mut instance = WasmtimeInstanceBuilder::new().with_wat(wat).build();
+    let res = instance
+        .run(FuncRef::Method(WasmMethod::Update("test".to_string())))
+        .unwrap();
+    assert_eq!(
+        res.exported_globals[0],
+        Global::V128(0x7fc00000_7fc00000_7fc00000_7fc00000)
+    );
+}
+
+#[test]
+// Checks that an unaligned vector store that crosses a page boundary marks
+// both pages as dirty when the write barrier is enabled.
+fn write_barrier_marks_both_pages_of_unaligned_v128_store() {
+    let wat = r#"
+                    (module
+                        (func (export "canister_update test")
+                            (v128.store (i32.const 8185) (v128.const i64x2 -1 -1))
+                        )
+                        (memory (export "memory") 1)
+                    )"#;
+    let mut config = ic_config::embedders::Config::default();
+    config.feature_flags.write_barrier = ic_config::flag_status::FlagStatus::Enabled;
+    let mut instance = WasmtimeInstanceBuilder::new()
+        .with_config(config)
+        .with_wat(wat)
+        .build();
+    let res = instance
+        .run(FuncRef::Method(WasmMethod::Update("test".to_string())))
+        .unwrap();
+    assert_eq!(res.dirty_pages, vec![PageIndex::new(1), PageIndex::new(2)]);
+}
+
 #[test]
 #[should_panic(expected = "global of type I32 cannot be set to I64")]
 fn try_to_set_globals_with_wrong_types() {
@@ -573,6 +654,7 @@ fn read_before_write_stats() {
             Cycles::zero(),
             PrincipalId::new_user_test_id(0),
             0.into(),
+            None,
         ))
         .build();
     instance
@@ -601,6 +683,7 @@ fn read_before_write_stats() {
             Cycles::zero(),
             PrincipalId::new_user_test_id(0),
             0.into(),
+            None,
         ))
         .build();
     instance
@@ -83,6 +83,7 @@ fn test_api_for_update(
             Cycles::zero(),
             caller,
             call_context_test_id(13),
+            None,
         ),
         static_system_state,
         canister_current_memory_usage,
@@ -235,6 +235,7 @@ fn icrc1_ledger_init_arg(
         feature_flags: ledger_init_arg.feature_flags,
         maximum_number_of_accounts: ledger_init_arg.maximum_number_of_accounts,
         accounts_overflow_trim_quantity: ledger_init_arg.accounts_overflow_trim_quantity,
+        max_transfer_batch_size: None,
     }
 }
 
@@ -1,6 +1,6 @@
 use crate::as_round_instructions;
 use crate::canister_settings::{validate_canister_settings, ValidatedCanisterSettings};
-use crate::execution::install_code::{validate_controller, OriginalContext};
+use crate::execution::install_code::{canister_layout, validate_controller, OriginalContext};
 use crate::execution::{install::execute_install, upgrade::execute_upgrade};
 use crate::execution_environment::{
     CompilationCostHandling, RoundContext, RoundCounters, RoundLimits,
@@ -16,9 +16,9 @@ use ic_config::flag_status::FlagStatus;
 use ic_cycles_account_manager::{CyclesAccountManager, ResourceSaturation};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
-    CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallModeV2, CanisterStatusResultV2,
-    CanisterStatusType, InstallChunkedCodeArgs, InstallCodeArgsV2, Method as Ic00Method,
-    StoredChunksReply, UploadChunkReply,
+    CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallModeV2, CanisterSnapshotResponse,
+    CanisterStatusResultV2, CanisterStatusType, InstallChunkedCodeArgs, InstallCodeArgsV2,
+    Method as Ic00Method, StoredChunksReply, UploadChunkReply,
 };
 use ic_interfaces::execution_environment::{
     CanisterOutOfCyclesError, HypervisorError, IngressHistoryWriter, SubnetAvailableMemory,
@@ -28,9 +28,13 @@ use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::canister_state::system_state::ReservationError;
 use ic_replicated_state::{
-    canister_state::system_state::{
-        wasm_chunk_store::{self, WasmChunkStore},
-        CyclesUseCase,
+    canister_snapshots::{CanisterSnapshot, ExecutionStateSnapshot, MemorySnapshot, SnapshotId},
+    canister_state::{
+        execution_state::Memory,
+        system_state::{
+            wasm_chunk_store::{self, WasmChunkStore},
+            CyclesUseCase,
+        },
     },
     metadata_state::subnet_call_context_manager::InstallCodeCallId,
     page_map::PageAllocatorFileDescriptor,
@@ -116,6 +120,8 @@ pub(crate) struct CanisterMgrConfig {
     rate_limiting_of_heap_delta: FlagStatus,
     heap_delta_rate_limit: NumBytes,
     upload_wasm_chunk_instructions: NumInstructions,
+    max_number_of_snapshots_per_canister: usize,
+    canister_snapshot_baseline_instructions: NumInstructions,
 }
 
 impl CanisterMgrConfig {
@@ -134,6 +140,8 @@ impl CanisterMgrConfig {
         rate_limiting_of_heap_delta: FlagStatus,
         heap_delta_rate_limit: NumBytes,
         upload_wasm_chunk_instructions: NumInstructions,
+        max_number_of_snapshots_per_canister: usize,
+        canister_snapshot_baseline_instructions: NumInstructions,
     ) -> Self {
         Self {
             subnet_memory_capacity,
@@ -149,6 +157,8 @@ impl CanisterMgrConfig {
             rate_limiting_of_heap_delta,
             heap_delta_rate_limit,
             upload_wasm_chunk_instructions,
+            max_number_of_snapshots_per_canister,
+            canister_snapshot_baseline_instructions,
         }
     }
 }
@@ -454,6 +464,8 @@ impl CanisterManager {
             | Ok(Ic00Method::ECDSAPublicKey)
             | Ok(Ic00Method::SetupInitialDKG)
             | Ok(Ic00Method::SignWithECDSA)
+            | Ok(Ic00Method::SchnorrPublicKey)
+            | Ok(Ic00Method::SignWithSchnorr)
             | Ok(Ic00Method::ComputeInitialEcdsaDealings)
             // "DepositCycles" can be called by anyone however as ingress message
             // cannot carry cycles, it does not make sense to allow them from users.
@@ -651,6 +663,11 @@ impl CanisterManager {
         if let Some(log_visibility) = settings.log_visibility() {
             canister.system_state.log_visibility = log_visibility;
         }
+        if let Some(wasm_memory_limit) = settings.wasm_memory_limit() {
+            // A limit of zero means that the Wasm memory is not limited.
+            canister.system_state.wasm_memory_limit =
+                (wasm_memory_limit.get() > 0).then_some(wasm_memory_limit);
+        }
     }
 
     /// Tries to apply the requested settings on the canister identified by
@@ -1190,6 +1207,7 @@ impl CanisterManager {
         let freeze_threshold = canister.system_state.freeze_threshold;
         let reserved_cycles_limit = canister.system_state.reserved_balance_limit();
         let log_visibility = canister.system_state.log_visibility;
+        let wasm_memory_limit = canister.system_state.wasm_memory_limit;
 
         Ok(CanisterStatusResultV2::new(
             canister.status(),
@@ -1206,6 +1224,7 @@ impl CanisterManager {
             freeze_threshold.get(),
             reserved_cycles_limit.map(|x| x.get()),
             log_visibility,
+            wasm_memory_limit.map(|x| x.get()),
             self.cycles_account_manager
                 .idle_cycles_burned_rate(
                     memory_allocation,
@@ -1272,6 +1291,10 @@ impl CanisterManager {
 
         // Take out the canister from `ReplicatedState`.
         let canister_to_delete = state.take_canister_state(&canister_id_to_delete).unwrap();
+        // Delete the snapshots belonging to the canister.
+        state
+            .canister_snapshots
+            .delete_snapshots(canister_id_to_delete);
         // Leftover cycles in the balance are considered `consumed`.
         let leftover_cycles = NominalCycles::from(canister_to_delete.system_state.balance());
         let consumed_cycles_by_canister_to_delete = leftover_cycles
@@ -1798,6 +1821,422 @@ impl CanisterManager {
             .collect();
         Ok(StoredChunksReply(keys))
     }
+
+    /// Checks that the canister can afford to increase its memory usage to
+    /// `new_memory_usage` by taking `bytes` of additional memory and, if so,
+    /// reserves the storage cycles and the subnet memory for it.
+    fn reserve_memory_for_snapshot(
+        &self,
+        canister: &mut CanisterState,
+        new_memory_usage: NumBytes,
+        bytes: NumBytes,
+        round_limits: &mut RoundLimits,
+        subnet_size: usize,
+        resource_saturation: &ResourceSaturation,
+    ) -> Result<(), CanisterManagerError> {
+        match canister.memory_allocation() {
+            MemoryAllocation::Reserved(reserved_bytes) => {
+                if reserved_bytes < new_memory_usage {
+                    return Err(CanisterManagerError::NotEnoughMemoryAllocationGiven {
+                        memory_allocation_given: canister.memory_allocation(),
+                        memory_usage_needed: new_memory_usage,
+                    });
+                }
+            }
+            MemoryAllocation::BestEffort => {
+                let reservation_cycles = self.cycles_account_manager.storage_reservation_cycles(
+                    bytes,
+                    resource_saturation,
+                    subnet_size,
+                );
+                let threshold = self.cycles_account_manager.freeze_threshold_cycles(
+                    canister.system_state.freeze_threshold,
+                    canister.memory_allocation(),
+                    new_memory_usage,
+                    canister.message_memory_usage(),
+                    canister.compute_allocation(),
+                    subnet_size,
+                    canister.system_state.reserved_balance() + reservation_cycles,
+                );
+                // Note: if the subtraction here saturates, then we will get an
+                // error later when trying to actually reserve the cycles.
+                if threshold > canister.system_state.balance() - reservation_cycles {
+                    return Err(CanisterManagerError::InsufficientCyclesInMemoryGrow {
+                        bytes,
+                        available: canister.system_state.balance(),
+                        threshold,
+                    });
+                }
+                round_limits
+                    .subnet_available_memory
+                    .check_available_memory(bytes, NumBytes::from(0), NumBytes::from(0))
+                    .map_err(
+                        |_| CanisterManagerError::SubnetMemoryCapacityOverSubscribed {
+                            requested: bytes,
+                            available: NumBytes::from(
+                                round_limits
+                                    .subnet_available_memory
+                                    .get_execution_memory()
+                                    .max(0) as u64,
+                            ),
+                        },
+                    )?;
+                canister
+                    .system_state
+                    .reserve_cycles(reservation_cycles)
+                    .map_err(|err| match err {
+                        ReservationError::InsufficientCycles {
+                            requested,
+                            available,
+                        } => CanisterManagerError::InsufficientCyclesInMemoryGrow {
+                            bytes,
+                            available,
+                            threshold: requested,
+                        },
+                        ReservationError::ReservedLimitExceed { requested, limit } => {
+                            CanisterManagerError::ReservedCyclesLimitExceededInMemoryGrow {
+                                bytes,
+                                requested,
+                                limit,
+                            }
+                        }
+                    })?;
+                // It's safe to unwrap here because we already checked the
+                // available memory above.
+                round_limits.subnet_available_memory
+                    .try_decrement(bytes, NumBytes::from(0), NumBytes::from(0))
+                    .expect("Error: Cannot fail to decrement SubnetAvailableMemory after checking for availability");
+            }
+        }
+        Ok(())
+    }
+
+    /// Charges the canister for the instructions needed to copy `num_bytes`
+    /// of snapshot data.
+    fn charge_for_snapshot_operation(
+        &self,
+        canister: &mut CanisterState,
+        sender: PrincipalId,
+        num_bytes: NumBytes,
+        subnet_size: usize,
+        execution_refund_error_counter: &IntCounter,
+    ) -> Result<NumInstructions, CanisterManagerError> {
+        let instructions = self.config.canister_snapshot_baseline_instructions
+            + NumInstructions::from(num_bytes.get());
+        let current_memory_usage = canister.memory_usage();
+        let message_memory = canister.message_memory_usage();
+        let compute_allocation = canister.compute_allocation();
+        let reveal_top_up = canister.controllers().contains(&sender);
+        let prepaid_cycles = self
+            .cycles_account_manager
+            .prepay_execution_cycles(
+                &mut canister.system_state,
+                current_memory_usage,
+                message_memory,
+                compute_allocation,
+                instructions,
+                subnet_size,
+                reveal_top_up,
+            )
+            .map_err(CanisterManagerError::CanisterSnapshotNotEnoughCycles)?;
+        // To keep the invariant that `prepay_execution_cycles` is always paired
+        // with `refund_unused_execution_cycles` we refund zero immediately.
+        self.cycles_account_manager.refund_unused_execution_cycles(
+            &mut canister.system_state,
+            NumInstructions::from(0),
+            instructions,
+            prepaid_cycles,
+            execution_refund_error_counter,
+            subnet_size,
+            &self.log,
+        );
+        Ok(instructions)
+    }
+
+    fn check_heap_delta_rate_limit(
+        &self,
+        canister: &CanisterState,
+    ) -> Result<(), CanisterManagerError> {
+        if self.config.rate_limiting_of_heap_delta == FlagStatus::Enabled
+            && canister.scheduler_state.heap_delta_debit >= self.config.heap_delta_rate_limit
+        {
+            return Err(CanisterManagerError::CanisterHeapDeltaRateLimited {
+                canister_id: canister.canister_id(),
+                value: canister.scheduler_state.heap_delta_debit,
+                limit: self.config.heap_delta_rate_limit,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the snapshot identified by `snapshot_id` if it exists and
+    /// belongs to the canister identified by `canister_id`.
+    fn get_snapshot<'a>(
+        &self,
+        canister_id: CanisterId,
+        snapshot_id: SnapshotId,
+        state: &'a ReplicatedState,
+    ) -> Result<&'a Arc<CanisterSnapshot>, CanisterManagerError> {
+        match state.canister_snapshots.get(snapshot_id) {
+            None => Err(CanisterManagerError::CanisterSnapshotNotFound {
+                canister_id,
+                snapshot_id,
+            }),
+            Some(snapshot) => {
+                if *snapshot.canister_id() != canister_id {
+                    return Err(CanisterManagerError::CanisterSnapshotInvalidOwnership {
+                        canister_id,
+                        snapshot_id,
+                    });
+                }
+                Ok(snapshot)
+            }
+        }
+    }
+
+    /// Takes a snapshot of the current state of the canister, optionally
+    /// replacing an existing snapshot.
+    ///
+    /// The canister must have been taken out of `state` by the caller.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn take_canister_snapshot(
+        &self,
+        subnet_size: usize,
+        sender: PrincipalId,
+        canister: &mut CanisterState,
+        replace_snapshot: Option<SnapshotId>,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+        resource_saturation: &ResourceSaturation,
+        execution_refund_error_counter: &IntCounter,
+    ) -> Result<CanisterSnapshotResponse, CanisterManagerError> {
+        let canister_id = canister.canister_id();
+        validate_controller(canister, &sender)?;
+
+        let replaced_snapshot_size = match replace_snapshot {
+            Some(replace_snapshot) => self
+                .get_snapshot(canister_id, replace_snapshot, state)?
+                .size(),
+            None => {
+                if state.canister_snapshots.count_snapshots(canister_id)
+                    >= self.config.max_number_of_snapshots_per_canister
+                {
+                    return Err(CanisterManagerError::CanisterSnapshotLimitExceeded {
+                        canister_id,
+                        limit: self.config.max_number_of_snapshots_per_canister,
+                    });
+                }
+                NumBytes::from(0)
+            }
+        };
+
+        self.check_heap_delta_rate_limit(canister)?;
+
+        let execution_state = canister
+            .execution_state
+            .as_ref()
+            .ok_or(CanisterManagerError::CanisterSnapshotExecutionStateNotFound { canister_id })?;
+        let execution_snapshot = ExecutionStateSnapshot {
+            wasm_binary: execution_state.wasm_binary.binary.clone(),
+            exported_globals: execution_state.exported_globals.clone(),
+            stable_memory: MemorySnapshot::new(
+                execution_state.stable_memory.page_map.clone(),
+                execution_state.stable_memory.size,
+            ),
+            wasm_memory: MemorySnapshot::new(
+                execution_state.wasm_memory.page_map.clone(),
+                execution_state.wasm_memory.size,
+            ),
+        };
+        let new_snapshot = CanisterSnapshot::new(
+            canister_id,
+            state.time(),
+            canister.system_state.canister_version,
+            canister.system_state.certified_data.clone(),
+            canister.system_state.wasm_chunk_store.clone(),
+            execution_snapshot,
+        );
+        let new_snapshot_size = new_snapshot.size();
+
+        let instructions = self.charge_for_snapshot_operation(
+            canister,
+            sender,
+            new_snapshot_size,
+            subnet_size,
+            execution_refund_error_counter,
+        )?;
+
+        if new_snapshot_size > replaced_snapshot_size {
+            let new_memory_usage =
+                canister.memory_usage() + new_snapshot_size - replaced_snapshot_size;
+            self.reserve_memory_for_snapshot(
+                canister,
+                new_memory_usage,
+                new_snapshot_size - replaced_snapshot_size,
+                round_limits,
+                subnet_size,
+                resource_saturation,
+            )?;
+        }
+
+        if self.config.rate_limiting_of_heap_delta == FlagStatus::Enabled {
+            canister.scheduler_state.heap_delta_debit += new_snapshot_size;
+        }
+        state.metadata.heap_delta_estimate += new_snapshot_size;
+        round_limits.instructions -= as_round_instructions(instructions);
+
+        if let Some(replace_snapshot) = replace_snapshot {
+            state.canister_snapshots.remove(replace_snapshot);
+            canister.system_state.snapshots_memory_usage -= replaced_snapshot_size;
+        }
+        let taken_at_timestamp = new_snapshot
+            .taken_at_timestamp()
+            .as_nanos_since_unix_epoch();
+        let snapshot_id = state.canister_snapshots.push(Arc::new(new_snapshot));
+        canister.system_state.snapshots_memory_usage += new_snapshot_size;
+
+        Ok(CanisterSnapshotResponse::new(
+            snapshot_id.get(),
+            taken_at_timestamp,
+            new_snapshot_size,
+        ))
+    }
+
+    /// Restores the canister to the state captured in the given snapshot and
+    /// records the load in the canister history.
+    ///
+    /// The canister must have been taken out of `state` by the caller.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn load_canister_snapshot(
+        &self,
+        subnet_size: usize,
+        sender: PrincipalId,
+        canister: &mut CanisterState,
+        snapshot_id: SnapshotId,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+        origin: CanisterChangeOrigin,
+        resource_saturation: &ResourceSaturation,
+        execution_refund_error_counter: &IntCounter,
+    ) -> Result<(), CanisterManagerError> {
+        let canister_id = canister.canister_id();
+        validate_controller(canister, &sender)?;
+
+        let snapshot = Arc::clone(self.get_snapshot(canister_id, snapshot_id, state)?);
+
+        self.check_heap_delta_rate_limit(canister)?;
+
+        let instructions = self.charge_for_snapshot_operation(
+            canister,
+            sender,
+            snapshot.size(),
+            subnet_size,
+            execution_refund_error_counter,
+        )?;
+
+        // Create a new execution state from the module in the snapshot and
+        // replace its memories and globals with the ones from the snapshot.
+        let execution_snapshot = snapshot.execution_snapshot();
+        let layout = canister_layout(&PathBuf::from("NOT_USED"), &canister_id);
+        let (_instructions_from_compilation, result) = self.hypervisor.create_execution_state(
+            execution_snapshot.wasm_binary.clone(),
+            layout.raw_path(),
+            canister_id,
+            round_limits,
+            CompilationCostHandling::CountFullAmount,
+        );
+        let mut new_execution_state =
+            result.map_err(|err| CanisterManagerError::Hypervisor(canister_id, err))?;
+        new_execution_state.wasm_memory = Memory::new(
+            execution_snapshot.wasm_memory.page_map.clone(),
+            execution_snapshot.wasm_memory.size,
+        );
+        new_execution_state.stable_memory = Memory::new(
+            execution_snapshot.stable_memory.page_map.clone(),
+            execution_snapshot.stable_memory.size,
+        );
+        new_execution_state.exported_globals = execution_snapshot.exported_globals.clone();
+
+        let mut new_canister = canister.clone();
+        new_canister.execution_state = Some(new_execution_state);
+        new_canister.system_state.certified_data = snapshot.certified_data().clone();
+        new_canister.system_state.wasm_chunk_store = snapshot.chunk_store().clone();
+
+        let old_memory_usage = canister.memory_usage();
+        let new_memory_usage = new_canister.memory_usage();
+        if new_memory_usage > old_memory_usage {
+            self.reserve_memory_for_snapshot(
+                &mut new_canister,
+                new_memory_usage,
+                new_memory_usage - old_memory_usage,
+                round_limits,
+                subnet_size,
+                resource_saturation,
+            )?;
+        }
+
+        new_canister.system_state.canister_version += 1;
+        new_canister.system_state.add_canister_change(
+            state.time(),
+            origin,
+            CanisterChangeDetails::load_snapshot(
+                snapshot.canister_version(),
+                snapshot_id.get(),
+                snapshot.taken_at_timestamp().as_nanos_since_unix_epoch(),
+            ),
+        );
+        if self.config.rate_limiting_of_heap_delta == FlagStatus::Enabled {
+            new_canister.scheduler_state.heap_delta_debit += snapshot.size();
+        }
+        state.metadata.heap_delta_estimate += snapshot.size();
+        round_limits.instructions -= as_round_instructions(instructions);
+
+        state.canister_snapshots.restore(canister_id, snapshot_id);
+        *canister = new_canister;
+        Ok(())
+    }
+
+    /// Returns the snapshots belonging to the given canister.
+    pub(crate) fn list_canister_snapshots(
+        &self,
+        sender: PrincipalId,
+        canister: &CanisterState,
+        state: &ReplicatedState,
+    ) -> Result<Vec<CanisterSnapshotResponse>, CanisterManagerError> {
+        validate_controller(canister, &sender)?;
+
+        Ok(state
+            .canister_snapshots
+            .list_snapshots(canister.canister_id())
+            .into_iter()
+            .map(|(snapshot_id, snapshot)| {
+                CanisterSnapshotResponse::new(
+                    snapshot_id.get(),
+                    snapshot.taken_at_timestamp().as_nanos_since_unix_epoch(),
+                    snapshot.size(),
+                )
+            })
+            .collect())
+    }
+
+    /// Deletes the given snapshot of the canister.
+    ///
+    /// The canister must have been taken out of `state` by the caller.
+    pub(crate) fn delete_canister_snapshot(
+        &self,
+        sender: PrincipalId,
+        canister: &mut CanisterState,
+        snapshot_id: SnapshotId,
+        state: &mut ReplicatedState,
+    ) -> Result<(), CanisterManagerError> {
+        let canister_id = canister.canister_id();
+        validate_controller(canister, &sender)?;
+
+        let snapshot_size = self.get_snapshot(canister_id, snapshot_id, state)?.size();
+        state.canister_snapshots.remove(snapshot_id);
+        canister.system_state.snapshots_memory_usage -= snapshot_size;
+        Ok(())
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -1879,6 +2318,27 @@ pub(crate) enum CanisterManagerError {
     WasmChunkStoreError {
         message: String,
     },
+    CanisterSnapshotNotFound {
+        canister_id: CanisterId,
+        snapshot_id: SnapshotId,
+    },
+    CanisterSnapshotInvalidOwnership {
+        canister_id: CanisterId,
+        snapshot_id: SnapshotId,
+    },
+    CanisterSnapshotExecutionStateNotFound {
+        canister_id: CanisterId,
+    },
+    CanisterSnapshotLimitExceeded {
+        canister_id: CanisterId,
+        limit: usize,
+    },
+    CanisterSnapshotNotEnoughCycles(CanisterOutOfCyclesError),
+    CanisterHeapDeltaRateLimited {
+        canister_id: CanisterId,
+        value: NumBytes,
+        limit: NumBytes,
+    },
 }
 
 impl From<CanisterManagerError> for UserError {
@@ -2115,6 +2575,52 @@ impl From<CanisterManagerError> for UserError {
                     )
                 )
             }
+            CanisterSnapshotNotFound { canister_id, snapshot_id } => {
+                Self::new(
+                    ErrorCode::CanisterSnapshotNotFound,
+                    format!(
+                        "Could not find the snapshot ID {} for canister {}.", snapshot_id, canister_id,
+                    )
+                )
+            }
+            CanisterSnapshotInvalidOwnership { canister_id, snapshot_id } => {
+                Self::new(
+                    ErrorCode::CanisterRejectedMessage,
+                    format!(
+                        "The snapshot {} does not belong to canister {}.", snapshot_id, canister_id,
+                    )
+                )
+            }
+            CanisterSnapshotExecutionStateNotFound { canister_id } => {
+                Self::new(
+                    ErrorCode::CanisterWasmModuleNotFound,
+                    format!(
+                        "Failed to take a snapshot of canister {} because it has no Wasm module installed.", canister_id,
+                    )
+                )
+            }
+            CanisterSnapshotLimitExceeded { canister_id, limit } => {
+                Self::new(
+                    ErrorCode::CanisterRejectedMessage,
+                    format!(
+                        "Canister {} has reached the maximum number of snapshots allowed: {}. Please provide a snapshot to be replaced.", canister_id, limit,
+                    )
+                )
+            }
+            CanisterSnapshotNotEnoughCycles(err) => {
+                Self::new(
+                    ErrorCode::CanisterOutOfCycles,
+                    format!("Canister snapshotting failed with `{}`", err),
+                )
+            }
+            CanisterHeapDeltaRateLimited { canister_id, value, limit } => {
+                Self::new(
+                    ErrorCode::CanisterRejectedMessage,
+                    format!(
+                        "Canister {} is heap delta rate limited: current delta debit is {}, but limit is {}.", canister_id, value, limit
+                    )
+                )
+            }
         }
     }
 }
@@ -2154,6 +2660,9 @@ pub fn uninstall_canister(
     // Drop its certified data.
     canister.system_state.certified_data = Vec::new();
 
+    // Clear the canister log.
+    canister.system_state.canister_log.clear();
+
     // Deactivate global timer.
     canister.system_state.global_timer = CanisterTimer::Inactive;
     // Increment canister version.
@@ -2200,7 +2709,7 @@ pub fn uninstall_canister(
                         },
                     }));
                 }
-                CallOrigin::CanisterUpdate(caller_canister_id, callback_id) => {
+                CallOrigin::CanisterUpdate(caller_canister_id, callback_id, deadline) => {
                     rejects.push(Response::Canister(CanisterResponse {
                         originator: *caller_canister_id,
                         respondent: canister_id,
@@ -2210,6 +2719,7 @@ pub fn uninstall_canister(
                             RejectCode::CanisterReject,
                             "Canister has been uninstalled.",
                         )),
+                        deadline: *deadline,
                     }));
                 }
                 CallOrigin::CanisterQuery(_, _) | CallOrigin::Query(_) => fatal!(
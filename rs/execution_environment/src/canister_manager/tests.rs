@@ -288,6 +288,8 @@ fn canister_manager_config(
         // 10 MiB should be enough for all the tests.
         NumBytes::from(10 * 1024 * 1024),
         SchedulerConfig::application_subnet().upload_wasm_chunk_instructions,
+        Config::default().max_number_of_snapshots_per_canister,
+        Config::default().canister_snapshot_baseline_instructions,
     )
 }
 
@@ -1715,6 +1717,7 @@ fn stop_a_running_canister() {
             reply_callback: CallbackId::new(0),
             call_id: Some(StopCanisterCallId::new(0)),
             cycles: Cycles::zero(),
+            deadline: None,
         };
         assert_eq!(
             canister_manager.stop_canister(canister_id, stop_context.clone(), &mut state),
@@ -1797,6 +1800,7 @@ fn stop_a_stopped_canister_from_another_canister() {
             reply_callback: CallbackId::from(0),
             call_id: Some(StopCanisterCallId::new(0)),
             cycles: Cycles::from(cycles),
+            deadline: None,
         };
         assert_eq!(
             canister_manager.stop_canister(canister_id, stop_context, &mut state),
@@ -12,6 +12,9 @@ use std::convert::TryFrom;
 
 use crate::canister_manager::CanisterManagerError;
 
+/// The maximum value of the `wasm_memory_limit` setting (2^48 bytes).
+const MAX_WASM_MEMORY_LIMIT: u64 = 1 << 48;
+
 /// Struct used for decoding CanisterSettingsArgs
 #[derive(Default)]
 pub(crate) struct CanisterSettings {
@@ -22,9 +25,11 @@ pub(crate) struct CanisterSettings {
     pub(crate) freezing_threshold: Option<NumSeconds>,
     pub(crate) reserved_cycles_limit: Option<Cycles>,
     pub(crate) log_visibility: Option<LogVisibility>,
+    pub(crate) wasm_memory_limit: Option<NumBytes>,
 }
 
 impl CanisterSettings {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         controller: Option<PrincipalId>,
         controllers: Option<Vec<PrincipalId>>,
@@ -33,6 +38,7 @@ impl CanisterSettings {
         freezing_threshold: Option<NumSeconds>,
         reserved_cycles_limit: Option<Cycles>,
         log_visibility: Option<LogVisibility>,
+        wasm_memory_limit: Option<NumBytes>,
     ) -> Self {
         Self {
             controller,
@@ -42,6 +48,7 @@ impl CanisterSettings {
             freezing_threshold,
             reserved_cycles_limit,
             log_visibility,
+            wasm_memory_limit,
         }
     }
 
@@ -72,6 +79,10 @@ impl CanisterSettings {
     pub fn log_visibility(&self) -> Option<LogVisibility> {
         self.log_visibility
     }
+
+    pub fn wasm_memory_limit(&self) -> Option<NumBytes> {
+        self.wasm_memory_limit
+    }
 }
 
 impl TryFrom<CanisterSettingsArgs> for CanisterSettings {
@@ -109,6 +120,18 @@ impl TryFrom<CanisterSettingsArgs> for CanisterSettings {
             None => None,
         };
 
+        let wasm_memory_limit = match input.wasm_memory_limit {
+            Some(limit) => {
+                let limit = limit
+                    .0
+                    .to_u64()
+                    .filter(|limit| *limit <= MAX_WASM_MEMORY_LIMIT)
+                    .ok_or(UpdateSettingsError::WasmMemoryLimitOutOfRange { provided: limit })?;
+                Some(NumBytes::from(limit))
+            }
+            None => None,
+        };
+
         Ok(CanisterSettings::new(
             controller,
             input
@@ -119,6 +142,7 @@ impl TryFrom<CanisterSettingsArgs> for CanisterSettings {
             freezing_threshold,
             reserved_cycles_limit,
             input.log_visibility,
+            wasm_memory_limit,
         ))
     }
 }
@@ -142,6 +166,7 @@ pub(crate) struct CanisterSettingsBuilder {
     freezing_threshold: Option<NumSeconds>,
     reserved_cycles_limit: Option<Cycles>,
     log_visibility: Option<LogVisibility>,
+    wasm_memory_limit: Option<NumBytes>,
 }
 
 #[allow(dead_code)]
@@ -155,6 +180,7 @@ impl CanisterSettingsBuilder {
             freezing_threshold: None,
             reserved_cycles_limit: None,
             log_visibility: None,
+            wasm_memory_limit: None,
         }
     }
 
@@ -167,6 +193,7 @@ impl CanisterSettingsBuilder {
             freezing_threshold: self.freezing_threshold,
             reserved_cycles_limit: self.reserved_cycles_limit,
             log_visibility: self.log_visibility,
+            wasm_memory_limit: self.wasm_memory_limit,
         }
     }
 
@@ -218,6 +245,13 @@ impl CanisterSettingsBuilder {
             ..self
         }
     }
+
+    pub fn with_wasm_memory_limit(self, wasm_memory_limit: NumBytes) -> Self {
+        Self {
+            wasm_memory_limit: Some(wasm_memory_limit),
+            ..self
+        }
+    }
 }
 
 pub enum UpdateSettingsError {
@@ -225,6 +259,7 @@ pub enum UpdateSettingsError {
     MemoryAllocation(InvalidMemoryAllocationError),
     FreezingThresholdOutOfRange { provided: candid::Nat },
     ReservedCyclesLimitOutOfRange { provided: candid::Nat },
+    WasmMemoryLimitOutOfRange { provided: candid::Nat },
 }
 
 impl From<UpdateSettingsError> for UserError {
@@ -260,6 +295,13 @@ impl From<UpdateSettingsError> for UserError {
                     provided
                 ),
             ),
+            UpdateSettingsError::WasmMemoryLimitOutOfRange { provided } => UserError::new(
+                ErrorCode::CanisterContractViolation,
+                format!(
+                    "Wasm memory limit expected to be in the range of [0..2^48], got {}",
+                    provided
+                ),
+            ),
         }
     }
 }
@@ -285,6 +327,7 @@ pub(crate) struct ValidatedCanisterSettings {
     reserved_cycles_limit: Option<Cycles>,
     reservation_cycles: Cycles,
     log_visibility: Option<LogVisibility>,
+    wasm_memory_limit: Option<NumBytes>,
 }
 
 impl ValidatedCanisterSettings {
@@ -319,6 +362,10 @@ impl ValidatedCanisterSettings {
     pub fn log_visibility(&self) -> Option<LogVisibility> {
         self.log_visibility
     }
+
+    pub fn wasm_memory_limit(&self) -> Option<NumBytes> {
+        self.wasm_memory_limit
+    }
 }
 
 /// Validates the new canisters settings:
@@ -516,5 +563,6 @@ pub(crate) fn validate_canister_settings(
         reserved_cycles_limit: settings.reserved_cycles_limit(),
         reservation_cycles,
         log_visibility: settings.log_visibility(),
+        wasm_memory_limit: settings.wasm_memory_limit(),
     })
 }
@@ -449,6 +449,7 @@ impl InstallCodeHelper {
                 freezing_threshold: None,
                 reserved_cycles_limit: None,
                 log_visibility: None,
+                wasm_memory_limit: None,
             },
             self.canister.memory_usage(),
             self.canister.message_memory_usage(),
@@ -609,7 +610,7 @@ impl InstallCodeHelper {
     pub fn handle_wasm_execution(
         &mut self,
         canister_state_changes: Option<CanisterStateChanges>,
-        output: WasmExecutionOutput,
+        mut output: WasmExecutionOutput,
         original: &OriginalContext,
         round: &RoundContext,
     ) -> (NumInstructions, Result<(), CanisterManagerError>) {
@@ -634,6 +635,10 @@ impl InstallCodeHelper {
             .wasm_result
             .clone()
             .map_or(true, |result| result.is_none()));
+        self.canister
+            .system_state
+            .canister_log
+            .append_delta_log(&mut output.canister_log);
         match output.wasm_result {
             Ok(None) => {}
             Ok(Some(_response)) => {
@@ -133,6 +133,7 @@ pub fn execute_update(
             msg.cycles(),
             *msg.sender(),
             helper.call_context_id(),
+            msg.deadline(),
         ),
         CanisterCallOrTask::Task(CanisterTask::Heartbeat) => ApiType::system_task(
             IC_00.get(),
@@ -23,7 +23,7 @@ use ic_base_types::PrincipalId;
 use ic_config::execution_environment::Config as ExecutionConfig;
 use ic_config::flag_status::FlagStatus;
 use ic_constants::{LOG_CANISTER_OPERATION_CYCLES_THRESHOLD, SMALL_APP_SUBNET_MAX_SIZE};
-use ic_crypto_tecdsa::derive_tecdsa_public_key;
+use ic_crypto_tecdsa::{derive_tecdsa_public_key, derive_threshold_schnorr_public_key};
 use ic_cycles_account_manager::{
     is_delayed_ingress_induction_cost, CyclesAccountManager, IngressInductionCost,
     ResourceSaturation,
@@ -32,12 +32,14 @@ use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
     CanisterChangeOrigin, CanisterHttpRequestArgs, CanisterIdRecord, CanisterInfoRequest,
     CanisterInfoResponse, CanisterSettingsArgs, CanisterStatusType, ClearChunkStoreArgs,
-    ComputeInitialEcdsaDealingsArgs, CreateCanisterArgs, ECDSAPublicKeyArgs,
-    ECDSAPublicKeyResponse, EcdsaKeyId, EmptyBlob, InstallChunkedCodeArgs, InstallCodeArgsV2,
-    Method as Ic00Method, NodeMetricsHistoryArgs, Payload as Ic00Payload,
-    ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs, SetupInitialDKGArgs,
-    SignWithECDSAArgs, StoredChunksArgs, UninstallCodeArgs, UpdateSettingsArgs, UploadChunkArgs,
-    IC_00,
+    ComputeInitialEcdsaDealingsArgs, CreateCanisterArgs, DeleteCanisterSnapshotArgs,
+    ECDSAPublicKeyArgs, ECDSAPublicKeyResponse, EcdsaKeyId, EmptyBlob, InstallChunkedCodeArgs,
+    InstallCodeArgsV2, ListCanisterSnapshotArgs, ListCanisterSnapshotsResponse,
+    LoadCanisterSnapshotArgs, Method as Ic00Method, NodeMetricsHistoryArgs, Payload as Ic00Payload,
+    ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs, SchnorrAlgorithm,
+    SchnorrKeyId, SchnorrPublicKeyArgs, SchnorrPublicKeyResponse, SetupInitialDKGArgs,
+    SignWithECDSAArgs, SignWithSchnorrArgs, StoredChunksArgs, TakeCanisterSnapshotArgs,
+    UninstallCodeArgs, UpdateSettingsArgs, UploadChunkArgs, IC_00,
 };
 use ic_interfaces::execution_environment::{
     ExecutionMode, IngressHistoryWriter, RegistryExecutionSettings, SubnetAvailableMemory,
@@ -47,11 +49,12 @@ use ic_metrics::MetricsRegistry;
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
+    canister_snapshots::SnapshotId,
     canister_state::system_state::PausedExecutionId,
     canister_state::{system_state::CyclesUseCase, NextExecution},
     metadata_state::subnet_call_context_manager::{
         EcdsaDealingsContext, InstallCodeCall, InstallCodeCallId, SetupInitialDkgContext,
-        SignWithEcdsaContext, StopCanisterCall, SubnetCallContext,
+        SignWithEcdsaContext, SignWithSchnorrContext, StopCanisterCall, SubnetCallContext,
     },
     page_map::PageAllocatorFileDescriptor,
     CanisterState, CanisterStatus, ExecutionTask, NetworkTopology, ReplicatedState,
@@ -59,7 +62,9 @@ use ic_replicated_state::{
 use ic_system_api::{ExecutionParameters, InstructionLimits};
 use ic_types::{
     canister_http::CanisterHttpRequestContext,
-    crypto::canister_threshold_sig::{ExtendedDerivationPath, MasterEcdsaPublicKey},
+    crypto::canister_threshold_sig::{
+        ExtendedDerivationPath, MasterEcdsaPublicKey, MasterSchnorrPublicKey,
+    },
     crypto::threshold_sig::ni_dkg::NiDkgTargetId,
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::{
@@ -71,6 +76,7 @@ use ic_types::{
     methods::SystemMethod,
     nominal_cycles::NominalCycles,
     CanisterId, Cycles, LongExecutionMode, NumBytes, NumInstructions, SubnetId, Time,
+    WASM64_EXTRA_MEMORY_IN_BYTES,
 };
 use ic_types::{messages::MessageId, methods::WasmMethod};
 use ic_wasm_types::WasmHash;
@@ -356,6 +362,8 @@ impl ExecutionEnvironment {
             config.rate_limiting_of_heap_delta,
             heap_delta_rate_limit,
             upload_wasm_chunk_instructions,
+            config.max_number_of_snapshots_per_canister,
+            config.canister_snapshot_baseline_instructions,
         );
         let metrics = ExecutionEnvironmentMetrics::new(metrics_registry);
         let canister_manager = CanisterManager::new(
@@ -426,6 +434,7 @@ impl ExecutionEnvironment {
         instruction_limits: InstructionLimits,
         rng: &mut dyn RngCore,
         ecdsa_subnet_public_keys: &BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+        schnorr_subnet_public_keys: &BTreeMap<SchnorrKeyId, MasterSchnorrPublicKey>,
         registry_settings: &RegistryExecutionSettings,
         round_limits: &mut RoundLimits,
     ) -> (ReplicatedState, Option<NumInstructions>) {
@@ -453,11 +462,22 @@ impl ExecutionEnvironment {
                             },
                         );
 
-                        if matches!(
-                            (&context, &response.response_payload),
-                            (&SubnetCallContext::SignWithEcdsa(_), &Payload::Data(_))
-                        ) {
-                            state.metadata.subnet_metrics.ecdsa_signature_agreements += 1;
+                        match (&context, &response.response_payload) {
+                            (SubnetCallContext::SignWithEcdsa(_), Payload::Data(_)) => {
+                                state.metadata.subnet_metrics.ecdsa_signature_agreements += 1;
+                            }
+                            (
+                                SubnetCallContext::SignWithSchnorr(schnorr_context),
+                                Payload::Data(_),
+                            ) => {
+                                *state
+                                    .metadata
+                                    .subnet_metrics
+                                    .schnorr_signature_agreements
+                                    .entry(schnorr_context.key_id.clone())
+                                    .or_default() += 1;
+                            }
+                            _ => {}
                         }
 
                         state.push_subnet_output_response(
@@ -592,6 +612,50 @@ impl ExecutionEnvironment {
                 }
             },
 
+            Ok(Ic00Method::SignWithSchnorr) => match &msg {
+                CanisterCall::Request(request) => match SignWithSchnorrArgs::decode(payload) {
+                    Err(err) => Some((Err(err), msg.take_cycles())),
+                    Ok(args) => {
+                        match get_master_schnorr_public_key(
+                            schnorr_subnet_public_keys,
+                            self.own_subnet_id,
+                            &args.key_id,
+                        ) {
+                            Err(err) => Some((Err(err), msg.take_cycles())),
+                            Ok(_) => match self.sign_with_schnorr(
+                                (**request).clone(),
+                                args.message,
+                                args.derivation_path
+                                    .get()
+                                    .clone()
+                                    .into_iter()
+                                    .map(|x| x.into_vec())
+                                    .collect(),
+                                args.key_id,
+                                registry_settings.max_schnorr_queue_size,
+                                &mut state,
+                                rng,
+                                registry_settings.subnet_size,
+                            ) {
+                                Err(err) => Some((Err(err), msg.take_cycles())),
+                                Ok(()) => {
+                                    self.metrics.observe_message_with_label(
+                                        &request.method_name,
+                                        since.elapsed().as_secs_f64(),
+                                        SUBMITTED_OUTCOME_LABEL.into(),
+                                        SUCCESS_STATUS_LABEL.into(),
+                                    );
+                                    None
+                                }
+                            },
+                        }
+                    }
+                },
+                CanisterCall::Ingress(_) => {
+                    self.reject_unexpected_ingress(Ic00Method::SignWithSchnorr)
+                }
+            },
+
             Ok(Ic00Method::CreateCanister) => {
                 match &mut msg {
                     CanisterCall::Ingress(_) => {
@@ -930,6 +994,47 @@ impl ExecutionEnvironment {
                 }
             }
 
+            Ok(Ic00Method::SchnorrPublicKey) => {
+                let cycles = msg.take_cycles();
+                match &msg {
+                    CanisterCall::Request(request) => {
+                        let res = match SchnorrPublicKeyArgs::decode(request.method_payload()) {
+                            Err(err) => Some(Err(err)),
+                            Ok(args) => match get_master_schnorr_public_key(
+                                schnorr_subnet_public_keys,
+                                self.own_subnet_id,
+                                &args.key_id,
+                            ) {
+                                Err(err) => Some(Err(err)),
+                                Ok(pubkey) => {
+                                    let canister_id = match args.canister_id {
+                                        Some(id) => id.into(),
+                                        None => *msg.sender(),
+                                    };
+                                    Some(
+                                        self.get_schnorr_public_key(
+                                            pubkey,
+                                            canister_id,
+                                            args.derivation_path
+                                                .get()
+                                                .clone()
+                                                .into_iter()
+                                                .map(|x| x.into_vec())
+                                                .collect(),
+                                        )
+                                        .map(|res| res.encode()),
+                                    )
+                                }
+                            },
+                        };
+                        res.map(|res| (res, cycles))
+                    }
+                    CanisterCall::Ingress(_) => {
+                        self.reject_unexpected_ingress(Ic00Method::SchnorrPublicKey)
+                    }
+                }
+            }
+
             Ok(Ic00Method::ComputeInitialEcdsaDealings) => {
                 let cycles = msg.take_cycles();
                 match &msg {
@@ -1118,14 +1223,19 @@ impl ExecutionEnvironment {
 
             Ok(Ic00Method::TakeCanisterSnapshot) => match self.config.canister_snapshots {
                 FlagStatus::Enabled => {
-                    // TODO(EXC-1529): Implement take_canister_snapshot.
-                    Some((
-                        Err(UserError::new(
-                            ErrorCode::CanisterRejectedMessage,
-                            "Canister snapshotting API is not yet implemented.",
-                        )),
-                        msg.take_cycles(),
-                    ))
+                    let resource_saturation =
+                        self.subnet_memory_saturation(&round_limits.subnet_available_memory);
+                    let res = TakeCanisterSnapshotArgs::decode(payload).and_then(|args| {
+                        self.take_canister_snapshot(
+                            *msg.sender(),
+                            &mut state,
+                            args,
+                            round_limits,
+                            registry_settings.subnet_size,
+                            &resource_saturation,
+                        )
+                    });
+                    Some((res, msg.take_cycles()))
                 }
                 FlagStatus::Disabled => {
                     let err = Err(UserError::new(
@@ -1138,14 +1248,21 @@ impl ExecutionEnvironment {
 
             Ok(Ic00Method::LoadCanisterSnapshot) => match self.config.canister_snapshots {
                 FlagStatus::Enabled => {
-                    // TODO(EXC-1530): Implement load_canister_snapshot.
-                    Some((
-                        Err(UserError::new(
-                            ErrorCode::CanisterRejectedMessage,
-                            "Canister snapshotting API is not yet implemented.",
-                        )),
-                        msg.take_cycles(),
-                    ))
+                    let resource_saturation =
+                        self.subnet_memory_saturation(&round_limits.subnet_available_memory);
+                    let res = LoadCanisterSnapshotArgs::decode(payload).and_then(|args| {
+                        let origin = msg.canister_change_origin(args.get_sender_canister_version());
+                        self.load_canister_snapshot(
+                            *msg.sender(),
+                            &mut state,
+                            args,
+                            round_limits,
+                            origin,
+                            registry_settings.subnet_size,
+                            &resource_saturation,
+                        )
+                    });
+                    Some((res, msg.take_cycles()))
                 }
                 FlagStatus::Disabled => {
                     let err = Err(UserError::new(
@@ -1158,14 +1275,9 @@ impl ExecutionEnvironment {
 
             Ok(Ic00Method::ListCanisterSnapshots) => match self.config.canister_snapshots {
                 FlagStatus::Enabled => {
-                    // TODO(EXC-1531): Implement list_canister_snapshot.
-                    Some((
-                        Err(UserError::new(
-                            ErrorCode::CanisterRejectedMessage,
-                            "Canister snapshotting API is not yet implemented.",
-                        )),
-                        msg.take_cycles(),
-                    ))
+                    let res = ListCanisterSnapshotArgs::decode(payload)
+                        .and_then(|args| self.list_canister_snapshots(*msg.sender(), &state, args));
+                    Some((res, msg.take_cycles()))
                 }
                 FlagStatus::Disabled => {
                     let err = Err(UserError::new(
@@ -1178,14 +1290,10 @@ impl ExecutionEnvironment {
 
             Ok(Ic00Method::DeleteCanisterSnapshot) => match self.config.canister_snapshots {
                 FlagStatus::Enabled => {
-                    // TODO(EXC-1532): Implement delete_canister_snapshot.
-                    Some((
-                        Err(UserError::new(
-                            ErrorCode::CanisterRejectedMessage,
-                            "Canister snapshotting API is not yet implemented.",
-                        )),
-                        msg.take_cycles(),
-                    ))
+                    let res = DeleteCanisterSnapshotArgs::decode(payload).and_then(|args| {
+                        self.delete_canister_snapshot(*msg.sender(), &mut state, args)
+                    });
+                    Some((res, msg.take_cycles()))
                 }
                 FlagStatus::Disabled => {
                     let err = Err(UserError::new(
@@ -1222,9 +1330,10 @@ impl ExecutionEnvironment {
                 // responded to (which currently happens in the scheduler).
                 //
                 // This scenario also happens in the case of
-                // Ic00Method::SetupInitialDKG, Ic00Method::HttpRequest, and
-                // Ic00Method::SignWithECDSA. The request is saved and the
-                // response from consensus is handled separately.
+                // Ic00Method::SetupInitialDKG, Ic00Method::HttpRequest,
+                // Ic00Method::SignWithECDSA, and Ic00Method::SignWithSchnorr.
+                // The request is saved and the response from consensus is
+                // handled separately.
                 state
             }
         };
@@ -1441,9 +1550,15 @@ impl ExecutionEnvironment {
     }
 
     /// Returns the maximum amount of memory that can be utilized by a single
-    /// canister.
+    /// canister. If canisters with a 64-bit heap are enabled, this includes
+    /// the additional memory of their larger heap.
     pub fn max_canister_memory_size(&self) -> NumBytes {
-        self.config.max_canister_memory_size
+        match self.config.embedders_config.feature_flags.wasm64 {
+            FlagStatus::Enabled => {
+                self.config.max_canister_memory_size + NumBytes::new(WASM64_EXTRA_MEMORY_IN_BYTES)
+            }
+            FlagStatus::Disabled => self.config.max_canister_memory_size,
+        }
     }
 
     /// Returns the subnet memory capacity.
@@ -1727,6 +1842,130 @@ impl ExecutionEnvironment {
             .map_err(|err| err.into())
     }
 
+    /// Decodes the snapshot ID sent by the user.
+    fn decode_snapshot_id(snapshot_id: Option<u64>) -> Result<SnapshotId, UserError> {
+        snapshot_id.map(SnapshotId::new).ok_or_else(|| {
+            UserError::new(
+                ErrorCode::InvalidManagementPayload,
+                "The provided snapshot ID is not valid.",
+            )
+        })
+    }
+
+    fn take_canister_snapshot(
+        &self,
+        sender: PrincipalId,
+        state: &mut ReplicatedState,
+        args: TakeCanisterSnapshotArgs,
+        round_limits: &mut RoundLimits,
+        subnet_size: usize,
+        resource_saturation: &ResourceSaturation,
+    ) -> Result<Vec<u8>, UserError> {
+        let replace_snapshot = match args.replace_snapshot() {
+            Some(snapshot_id) => Some(Self::decode_snapshot_id(snapshot_id)?),
+            None => None,
+        };
+        let canister_id = args.get_canister_id();
+        // Take the canister out of the state, so that the snapshots can be
+        // modified at the same time.
+        let mut canister = match state.take_canister_state(&canister_id) {
+            Some(canister) => canister,
+            None => return Err(CanisterManagerError::CanisterNotFound(canister_id).into()),
+        };
+        let result = self.canister_manager.take_canister_snapshot(
+            subnet_size,
+            sender,
+            &mut canister,
+            replace_snapshot,
+            state,
+            round_limits,
+            resource_saturation,
+            &self.metrics.execution_cycles_refund_error,
+        );
+        // Put the canister back in the state.
+        state.put_canister_state(canister);
+        result
+            .map(|response| response.encode())
+            .map_err(|err| err.into())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn load_canister_snapshot(
+        &self,
+        sender: PrincipalId,
+        state: &mut ReplicatedState,
+        args: LoadCanisterSnapshotArgs,
+        round_limits: &mut RoundLimits,
+        origin: CanisterChangeOrigin,
+        subnet_size: usize,
+        resource_saturation: &ResourceSaturation,
+    ) -> Result<Vec<u8>, UserError> {
+        let snapshot_id = Self::decode_snapshot_id(args.snapshot_id())?;
+        let canister_id = args.get_canister_id();
+        // Take the canister out of the state, so that the snapshots can be
+        // modified at the same time.
+        let mut canister = match state.take_canister_state(&canister_id) {
+            Some(canister) => canister,
+            None => return Err(CanisterManagerError::CanisterNotFound(canister_id).into()),
+        };
+        let result = self.canister_manager.load_canister_snapshot(
+            subnet_size,
+            sender,
+            &mut canister,
+            snapshot_id,
+            state,
+            round_limits,
+            origin,
+            resource_saturation,
+            &self.metrics.execution_cycles_refund_error,
+        );
+        // Put the canister back in the state.
+        state.put_canister_state(canister);
+        result
+            .map(|()| EmptyBlob.encode())
+            .map_err(|err| err.into())
+    }
+
+    fn list_canister_snapshots(
+        &self,
+        sender: PrincipalId,
+        state: &ReplicatedState,
+        args: ListCanisterSnapshotArgs,
+    ) -> Result<Vec<u8>, UserError> {
+        let canister = get_canister(args.get_canister_id(), state)?;
+        self.canister_manager
+            .list_canister_snapshots(sender, canister, state)
+            .map(|snapshots| ListCanisterSnapshotsResponse(snapshots).encode())
+            .map_err(|err| err.into())
+    }
+
+    fn delete_canister_snapshot(
+        &self,
+        sender: PrincipalId,
+        state: &mut ReplicatedState,
+        args: DeleteCanisterSnapshotArgs,
+    ) -> Result<Vec<u8>, UserError> {
+        let snapshot_id = Self::decode_snapshot_id(args.snapshot_id())?;
+        let canister_id = args.get_canister_id();
+        // Take the canister out of the state, so that the snapshots can be
+        // modified at the same time.
+        let mut canister = match state.take_canister_state(&canister_id) {
+            Some(canister) => canister,
+            None => return Err(CanisterManagerError::CanisterNotFound(canister_id).into()),
+        };
+        let result = self.canister_manager.delete_canister_snapshot(
+            sender,
+            &mut canister,
+            snapshot_id,
+            state,
+        );
+        // Put the canister back in the state.
+        state.put_canister_state(canister);
+        result
+            .map(|()| EmptyBlob.encode())
+            .map_err(|err| err.into())
+    }
+
     fn node_metrics_history(
         &self,
         state: &ReplicatedState,
@@ -2107,6 +2346,7 @@ impl ExecutionEnvironment {
                     reply_callback,
                     call_id,
                     cycles,
+                    deadline,
                 } => {
                     // Rejecting a stop_canister request from a canister.
                     let subnet_id_as_canister_id = CanisterId::from(self.own_subnet_id);
@@ -2121,6 +2361,7 @@ impl ExecutionEnvironment {
                             RejectCode::CanisterError,
                             format!("Canister {}'s stop request cancelled", canister_id),
                         )),
+                        deadline,
                     };
                     state.push_subnet_output_response(response.into());
                 }
@@ -2267,6 +2508,114 @@ impl ExecutionEnvironment {
         Ok(())
     }
 
+    fn get_schnorr_public_key(
+        &self,
+        subnet_public_key: &MasterSchnorrPublicKey,
+        principal_id: PrincipalId,
+        derivation_path: Vec<Vec<u8>>,
+    ) -> Result<SchnorrPublicKeyResponse, UserError> {
+        let path = ExtendedDerivationPath {
+            caller: principal_id,
+            derivation_path,
+        };
+        derive_threshold_schnorr_public_key(subnet_public_key, &path)
+            .map_err(|err| UserError::new(ErrorCode::CanisterRejectedMessage, format!("{}", err)))
+            .map(|res| SchnorrPublicKeyResponse {
+                public_key: res.public_key,
+                chain_code: res.chain_key,
+            })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn sign_with_schnorr(
+        &self,
+        mut request: Request,
+        message: Vec<u8>,
+        derivation_path: Vec<Vec<u8>>,
+        key_id: SchnorrKeyId,
+        max_queue_size: u32,
+        state: &mut ReplicatedState,
+        rng: &mut dyn RngCore,
+        subnet_size: usize,
+    ) -> Result<(), UserError> {
+        if key_id.algorithm == SchnorrAlgorithm::Bip340Secp256k1 && message.len() != 32 {
+            return Err(UserError::new(
+                ErrorCode::CanisterRejectedMessage,
+                format!(
+                    "BIP340 sign_with_schnorr requests require a 32 byte message, got {} bytes.",
+                    message.len()
+                ),
+            ));
+        }
+
+        // If the request isn't from the NNS, then we need to charge for it.
+        // Consensus will return any remaining cycles.
+        let source_subnet = state
+            .metadata
+            .network_topology
+            .routing_table
+            .route(request.sender.get());
+        if source_subnet != Some(state.metadata.network_topology.nns_subnet_id) {
+            let signature_fee = self
+                .cycles_account_manager
+                .schnorr_signature_fee(subnet_size);
+            if request.payment < signature_fee {
+                return Err(UserError::new(
+                    ErrorCode::CanisterRejectedMessage,
+                    format!(
+                        "sign_with_schnorr request sent with {} cycles, but {} cycles are required.",
+                        request.payment, signature_fee
+                    ),
+                ));
+            } else {
+                request.payment -= signature_fee;
+                state
+                    .metadata
+                    .subnet_metrics
+                    .observe_consumed_cycles_with_use_case(
+                        CyclesUseCase::SchnorrOutcalls,
+                        NominalCycles::from(signature_fee),
+                    );
+            }
+        }
+
+        let mut pseudo_random_id = [0u8; 32];
+        rng.fill_bytes(&mut pseudo_random_id);
+
+        info!(
+            self.log,
+            "Assigned the pseudo_random_id {:?} to the new sign_with_schnorr request from {:?}",
+            pseudo_random_id,
+            request.sender()
+        );
+
+        if state
+            .metadata
+            .subnet_call_context_manager
+            .sign_with_schnorr_contexts
+            .len()
+            >= max_queue_size as usize
+        {
+            return Err(UserError::new(
+                ErrorCode::CanisterRejectedMessage,
+                "sign_with_schnorr request could not be handled, the Schnorr signature queue is full."
+                    .to_string(),
+            ));
+        }
+
+        state.metadata.subnet_call_context_manager.push_context(
+            SubnetCallContext::SignWithSchnorr(SignWithSchnorrContext {
+                request,
+                key_id,
+                message,
+                derivation_path,
+                pseudo_random_id,
+                batch_time: state.metadata.batch_time,
+            }),
+        );
+        Ok(())
+    }
+
     fn compute_initial_ecdsa_dealings(
         &self,
         state: &mut ReplicatedState,
@@ -2866,6 +3215,7 @@ impl ExecutionEnvironment {
                 sender,
                 reply_callback,
                 cycles,
+                deadline,
                 ..
             } => {
                 // Responding to stop_canister request from a canister.
@@ -2883,6 +3233,7 @@ impl ExecutionEnvironment {
                     originator_reply_callback: *reply_callback,
                     refund: *cycles,
                     response_payload,
+                    deadline: *deadline,
                 };
                 state.push_subnet_output_response(response.into());
             }
@@ -3309,3 +3660,17 @@ fn get_master_ecdsa_public_key<'a>(
         Some(master_key) => Ok(master_key),
     }
 }
+
+fn get_master_schnorr_public_key<'a>(
+    schnorr_subnet_public_keys: &'a BTreeMap<SchnorrKeyId, MasterSchnorrPublicKey>,
+    subnet_id: SubnetId,
+    key_id: &SchnorrKeyId,
+) -> Result<&'a MasterSchnorrPublicKey, UserError> {
+    match schnorr_subnet_public_keys.get(key_id) {
+        None => Err(UserError::new(
+            ErrorCode::CanisterRejectedMessage,
+            format!("Subnet {} does not hold Schnorr key {}.", subnet_id, key_id),
+        )),
+        Some(master_key) => Ok(master_key),
+    }
+}
@@ -7,13 +7,16 @@ use ic_replicated_state::ReplicatedState;
 use ic_types::nominal_cycles::NominalCycles;
 
 use ic_base_types::{NumBytes, NumSeconds};
+use ic_crypto_tecdsa::derive_threshold_schnorr_public_key;
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
     self as ic00, BitcoinGetUtxosArgs, BitcoinNetwork, BoundedHttpHeaders, CanisterChange,
     CanisterHttpRequestArgs, CanisterIdRecord, CanisterStatusResultV2, CanisterStatusType,
-    DerivationPath, EcdsaCurve, EcdsaKeyId, EmptyBlob, FetchCanisterLogsRequest, HttpMethod,
+    DeleteCanisterSnapshotArgs, DerivationPath, EcdsaCurve, EcdsaKeyId, EmptyBlob,
+    FetchCanisterLogsRequest, HttpMethod, ListCanisterSnapshotArgs, LoadCanisterSnapshotArgs,
     LogVisibility, Method, Payload as Ic00Payload, ProvisionalCreateCanisterWithCyclesArgs,
-    ProvisionalTopUpCanisterArgs, TransformContext, TransformFunc, IC_00,
+    ProvisionalTopUpCanisterArgs, SchnorrAlgorithm, SchnorrKeyId, TakeCanisterSnapshotArgs,
+    TransformContext, TransformFunc, IC_00,
 };
 use ic_registry_routing_table::canister_id_into_u64;
 use ic_registry_routing_table::CanisterIdRange;
@@ -25,7 +28,8 @@ use ic_replicated_state::{
 };
 use ic_test_utilities::assert_utils::assert_balance_equals;
 use ic_test_utilities_execution_environment::{
-    assert_empty_reply, check_ingress_status, get_output_messages, get_reply, ExecutionTest,
+    assert_empty_reply, check_ingress_status, get_output_messages, get_reply,
+    master_schnorr_public_key_for_testing, test_registry_settings, ExecutionTest,
     ExecutionTestBuilder,
 };
 use ic_test_utilities_metrics::{fetch_histogram_vec_count, fetch_int_counter, metric_vec};
@@ -33,6 +37,7 @@ use ic_test_utilities_time::mock_time;
 use ic_types::canister_http::Transform;
 use ic_types::{
     canister_http::CanisterHttpMethod,
+    crypto::canister_threshold_sig::ExtendedDerivationPath,
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::{
         CallbackId, Payload, RejectContext, RequestOrResponse, Response, MAX_RESPONSE_COUNT_BYTES,
@@ -42,8 +47,11 @@ use ic_types::{
 use ic_types_test_utils::ids::{canister_test_id, node_test_id, subnet_test_id, user_test_id};
 use ic_universal_canister::{call_args, wasm, UNIVERSAL_CANISTER_WASM};
 use maplit::btreemap;
+use serde_bytes::ByteBuf;
 use std::mem::size_of;
 
+#[cfg(test)]
+mod canister_snapshots;
 #[cfg(test)]
 mod canister_task;
 
@@ -1471,7 +1479,8 @@ fn setup_initial_dkg_sender_not_on_nns() {
                     ic00::Method::SetupInitialDKG,
                     other_canister,
                 )
-            ))
+            )),
+            deadline: None,
         }
         .into()
     );
@@ -2421,6 +2430,191 @@ fn ecdsa_signature_queue_fills_up() {
     );
 }
 
+fn make_schnorr_key(algorithm: SchnorrAlgorithm, name: &str) -> SchnorrKeyId {
+    SchnorrKeyId {
+        algorithm,
+        name: name.to_string(),
+    }
+}
+
+#[test]
+fn schnorr_public_key_returns_derived_key() {
+    let algorithm = SchnorrAlgorithm::Bip340Secp256k1;
+    let key_id = make_schnorr_key(algorithm, "some_key");
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_type(SubnetType::System)
+        .with_own_subnet_id(subnet_test_id(1))
+        .with_nns_subnet_id(subnet_test_id(2))
+        .with_schnorr_key(key_id.clone())
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let args = ic00::SchnorrPublicKeyArgs {
+        canister_id: None,
+        derivation_path: DerivationPath::new(vec![ByteBuf::from(vec![1, 2, 3])]),
+        key_id,
+    };
+    let run = wasm()
+        .call_simple(
+            ic00::IC_00,
+            Method::SchnorrPublicKey,
+            call_args().other_side(args.encode()),
+        )
+        .build();
+
+    let result = test.ingress(canister_id, "update", run);
+    let response = ic00::SchnorrPublicKeyResponse::decode(&get_reply(result)).unwrap();
+
+    let expected = derive_threshold_schnorr_public_key(
+        &master_schnorr_public_key_for_testing(algorithm),
+        &ExtendedDerivationPath {
+            caller: canister_id.get(),
+            derivation_path: vec![vec![1, 2, 3]],
+        },
+    )
+    .unwrap();
+    assert_eq!(response.public_key, expected.public_key);
+    assert_eq!(response.chain_code, expected.chain_key);
+}
+
+#[test]
+fn sign_with_schnorr_creates_context_and_charges_fee() {
+    let fee = 1_000_000;
+    let payment = 2_000_000u128;
+    let key_id = make_schnorr_key(SchnorrAlgorithm::Bip340Secp256k1, "some_key");
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_type(SubnetType::System)
+        .with_own_subnet_id(subnet_test_id(1))
+        .with_nns_subnet_id(subnet_test_id(2))
+        .with_schnorr_signature_fee(fee)
+        .with_schnorr_key(key_id.clone())
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let args = ic00::SignWithSchnorrArgs {
+        message: vec![7; 32],
+        derivation_path: DerivationPath::new(vec![]),
+        key_id: key_id.clone(),
+    };
+    let run = wasm()
+        .call_with_cycles(
+            ic00::IC_00,
+            Method::SignWithSchnorr,
+            call_args()
+                .other_side(args.encode())
+                .on_reject(wasm().reject_message().reject()),
+            Cycles::from(payment),
+        )
+        .build();
+
+    let (_, ingress_status) = test.ingress_raw(canister_id, "update", run);
+    assert_eq!(
+        ingress_status,
+        IngressStatus::Known {
+            receiver: canister_id.get(),
+            user_id: test.user_id(),
+            time: test.time(),
+            state: IngressState::Processing,
+        }
+    );
+    let (_, context) = test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .sign_with_schnorr_contexts
+        .iter()
+        .next()
+        .unwrap();
+    assert_eq!(context.key_id, key_id);
+    assert_eq!(context.message, vec![7; 32]);
+    assert_eq!(context.request.payment, Cycles::from(payment - fee));
+    let consumed_cycles = test
+        .state()
+        .metadata
+        .subnet_metrics
+        .get_consumed_cycles_by_use_case();
+    assert_eq!(
+        consumed_cycles.get(&CyclesUseCase::SchnorrOutcalls),
+        Some(&NominalCycles::from(fee))
+    );
+    assert_eq!(consumed_cycles.get(&CyclesUseCase::ECDSAOutcalls), None);
+}
+
+#[test]
+fn schnorr_signature_queue_fills_up() {
+    let key_id = make_schnorr_key(SchnorrAlgorithm::Bip340Secp256k1, "some_key");
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_type(SubnetType::System)
+        .with_own_subnet_id(subnet_test_id(1))
+        .with_nns_subnet_id(subnet_test_id(2))
+        .with_schnorr_signature_fee(0)
+        .with_schnorr_key(key_id.clone())
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let args = ic00::SignWithSchnorrArgs {
+        message: vec![1; 32],
+        derivation_path: DerivationPath::new(vec![]),
+        key_id,
+    };
+    let run = wasm()
+        .call_simple(
+            ic00::IC_00,
+            Method::SignWithSchnorr,
+            call_args()
+                .other_side(args.encode())
+                .on_reject(wasm().reject_message().reject()),
+        )
+        .build();
+
+    let max_queue_size = test_registry_settings().max_schnorr_queue_size;
+    for _ in 0..max_queue_size {
+        test.ingress_raw(canister_id, "update", run.clone());
+    }
+    let result = test.ingress(canister_id, "update", run).unwrap();
+
+    assert_eq!(
+        result,
+        WasmResult::Reject(
+            "sign_with_schnorr request could not be handled, the Schnorr signature queue is full."
+                .to_string()
+        )
+    );
+}
+
+#[test]
+fn sign_with_schnorr_rejects_bip340_message_of_wrong_length() {
+    let key_id = make_schnorr_key(SchnorrAlgorithm::Bip340Secp256k1, "some_key");
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_type(SubnetType::System)
+        .with_own_subnet_id(subnet_test_id(1))
+        .with_nns_subnet_id(subnet_test_id(2))
+        .with_schnorr_signature_fee(0)
+        .with_schnorr_key(key_id.clone())
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let args = ic00::SignWithSchnorrArgs {
+        message: vec![1; 31],
+        derivation_path: DerivationPath::new(vec![]),
+        key_id,
+    };
+    let run = wasm()
+        .call_simple(
+            ic00::IC_00,
+            Method::SignWithSchnorr,
+            call_args()
+                .other_side(args.encode())
+                .on_reject(wasm().reject_message().reject()),
+        )
+        .build();
+
+    let result = test.ingress(canister_id, "update", run).unwrap();
+    assert_eq!(
+        result,
+        WasmResult::Reject(
+            "BIP340 sign_with_schnorr requests require a 32 byte message, got 31 bytes."
+                .to_string()
+        )
+    );
+}
+
 #[test]
 fn canister_output_queue_does_not_overflow_when_calling_ic00() {
     let own_subnet = subnet_test_id(1);
@@ -3119,7 +3313,7 @@ fn output_requests_on_application_subnets_update_subnet_available_memory_reserve
 }
 
 #[test]
-fn test_request_snapshot_rejected_because_feature_is_enabled() {
+fn test_request_snapshot_rejected_because_of_invalid_payload() {
     let own_subnet = subnet_test_id(1);
     let caller_canister = canister_test_id(1);
     let mut test = ExecutionTestBuilder::new()
@@ -3142,13 +3336,13 @@ fn test_request_snapshot_rejected_because_feature_is_enabled() {
     assert_matches!(response, RequestOrResponse::Response(_));
     if let RequestOrResponse::Response(res) = response {
         assert_eq!(res.originator, *receiver);
-        assert_eq!(
-            res.response_payload,
-            Payload::Reject(RejectContext::new(
-                RejectCode::CanisterReject,
-                "Canister snapshotting API is not yet implemented."
-            ))
-        );
+        match &res.response_payload {
+            Payload::Reject(context) => {
+                assert_eq!(context.code(), RejectCode::CanisterReject);
+                assert!(context.message().contains("Error decoding candid"));
+            }
+            payload => panic!("Expected a reject, got {:?}", payload),
+        }
     }
 }
 
@@ -3192,24 +3386,37 @@ fn test_ingress_snapshot_rejected_because_feature_is_disabled() {
         .build();
     let uni = test.universal_canister().unwrap();
 
-    let snapshot_methods = [
-        Method::TakeCanisterSnapshot,
-        Method::LoadCanisterSnapshot,
-        Method::DeleteCanisterSnapshot,
-        Method::ListCanisterSnapshots,
+    let snapshot_calls = [
+        (
+            Method::TakeCanisterSnapshot,
+            TakeCanisterSnapshotArgs::new(uni, None).encode(),
+        ),
+        (
+            Method::LoadCanisterSnapshot,
+            LoadCanisterSnapshotArgs::new(uni, 0, None).encode(),
+        ),
+        (
+            Method::DeleteCanisterSnapshot,
+            DeleteCanisterSnapshotArgs::new(uni, 0).encode(),
+        ),
+        (
+            Method::ListCanisterSnapshots,
+            ListCanisterSnapshotArgs::new(uni).encode(),
+        ),
     ];
-    for method in snapshot_methods {
+    for (method, args) in snapshot_calls {
         let call = wasm()
             .call_simple(
                 ic00::IC_00,
                 method,
                 call_args()
-                    .other_side(vec![])
+                    .other_side(args)
                     .on_reject(wasm().reject_message().reject()),
             )
             .build();
         let result = test.ingress(uni, "update", call).unwrap();
-        let expected_result = WasmResult::Reject(format!("Unable to route management canister request {}: UserError(UserError {{ code: CanisterRejectedMessage, description: {} }})", method, "\"Snapshotting API is not yet implemented\""));
+        let expected_result =
+            WasmResult::Reject("This API is not enabled on this subnet".to_string());
         assert_eq!(result, expected_result);
     }
 }
@@ -3285,10 +3492,7 @@ fn test_fetch_canister_logs_should_accept_ingress_message_disabled() {
     let result = test.should_accept_ingress_message(
         test.state().metadata.own_subnet_id.into(),
         Method::FetchCanisterLogs,
-        FetchCanisterLogsRequest {
-            canister_id: canister_id.into(),
-        }
-        .encode(),
+        FetchCanisterLogsRequest::new(canister_id).encode(),
     );
     // Assert.
     // Expect error because the API is disabled.
@@ -3318,10 +3522,7 @@ fn test_fetch_canister_logs_should_accept_ingress_message_enabled() {
     let result = test.should_accept_ingress_message(
         test.state().metadata.own_subnet_id.into(),
         Method::FetchCanisterLogs,
-        FetchCanisterLogsRequest {
-            canister_id: canister_id.into(),
-        }
-        .encode(),
+        FetchCanisterLogsRequest::new(canister_id).encode(),
     );
     // Assert.
     // Expect error since `should_accept_ingress_message` is only called in replicated mode which is not supported.
@@ -0,0 +1,262 @@
+use ic_config::flag_status::FlagStatus;
+use ic_error_types::ErrorCode;
+use ic_ic00_types::{
+    self as ic00, CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, CanisterIdRecord,
+    CanisterSnapshotResponse, DeleteCanisterSnapshotArgs, ListCanisterSnapshotArgs,
+    ListCanisterSnapshotsResponse, LoadCanisterSnapshotArgs, Method, Payload,
+    TakeCanisterSnapshotArgs,
+};
+use ic_replicated_state::canister_snapshots::SnapshotId;
+use ic_test_utilities_execution_environment::{get_reply, ExecutionTest, ExecutionTestBuilder};
+use ic_types::{ingress::WasmResult, CanisterId};
+use ic_universal_canister::{call_args, wasm};
+use std::sync::Arc;
+
+fn take_snapshot(
+    test: &mut ExecutionTest,
+    canister_id: CanisterId,
+    replace_snapshot: Option<u64>,
+) -> CanisterSnapshotResponse {
+    let args = TakeCanisterSnapshotArgs::new(canister_id, replace_snapshot);
+    let result = test.subnet_message(Method::TakeCanisterSnapshot, args.encode());
+    CanisterSnapshotResponse::decode(&get_reply(result)).unwrap()
+}
+
+fn list_snapshots(
+    test: &mut ExecutionTest,
+    canister_id: CanisterId,
+) -> Vec<CanisterSnapshotResponse> {
+    let args = ListCanisterSnapshotArgs::new(canister_id);
+    let result = test.subnet_message(Method::ListCanisterSnapshots, args.encode());
+    ListCanisterSnapshotsResponse::decode(&get_reply(result))
+        .unwrap()
+        .0
+}
+
+fn write_stable_memory(test: &mut ExecutionTest, canister_id: CanisterId, data: &[u8]) {
+    let payload = wasm().stable_grow(1).stable_write(0, data).reply().build();
+    test.ingress(canister_id, "update", payload).unwrap();
+}
+
+fn read_stable_memory(test: &mut ExecutionTest, canister_id: CanisterId, size: u32) -> Vec<u8> {
+    let payload = wasm().stable_read(0, size).append_and_reply().build();
+    get_reply(test.ingress(canister_id, "update", payload))
+}
+
+#[test]
+fn take_and_list_canister_snapshot() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshots(FlagStatus::Enabled)
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let memory_usage_before = test.canister_state(canister_id).memory_usage();
+
+    let response = take_snapshot(&mut test, canister_id, None);
+    assert_eq!(response.snapshot_id(), Some(0));
+    assert_eq!(
+        response.taken_at_timestamp(),
+        test.state().time().as_nanos_since_unix_epoch()
+    );
+
+    let snapshot = test
+        .state()
+        .canister_snapshots
+        .get(SnapshotId::new(0))
+        .unwrap();
+    assert_eq!(*snapshot.canister_id(), canister_id);
+    assert_eq!(response.total_size(), snapshot.size().get());
+
+    // The snapshot is accounted for in the memory usage of the canister.
+    assert_eq!(
+        test.canister_state(canister_id).memory_usage(),
+        memory_usage_before + snapshot.size()
+    );
+
+    assert_eq!(list_snapshots(&mut test, canister_id), vec![response]);
+}
+
+#[test]
+fn take_canister_snapshot_fails_without_module() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshots(FlagStatus::Enabled)
+        .build();
+    let canister_id = test.create_canister(1_000_000_000_000_u128.into());
+    let args = TakeCanisterSnapshotArgs::new(canister_id, None);
+    let err = test
+        .subnet_message(Method::TakeCanisterSnapshot, args.encode())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterWasmModuleNotFound);
+    assert!(test.state().canister_snapshots.is_empty());
+}
+
+#[test]
+fn take_canister_snapshot_respects_limit_and_replaces_snapshot() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshots(FlagStatus::Enabled)
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let first = take_snapshot(&mut test, canister_id, None);
+
+    // Only one snapshot per canister is allowed.
+    let args = TakeCanisterSnapshotArgs::new(canister_id, None);
+    let err = test
+        .subnet_message(Method::TakeCanisterSnapshot, args.encode())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterRejectedMessage);
+
+    // Replacing the existing snapshot succeeds.
+    let second = take_snapshot(&mut test, canister_id, first.snapshot_id());
+    assert_ne!(first.snapshot_id(), second.snapshot_id());
+    assert_eq!(list_snapshots(&mut test, canister_id), vec![second.clone()]);
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .snapshots_memory_usage
+            .get(),
+        second.total_size()
+    );
+}
+
+#[test]
+fn load_canister_snapshot_restores_canister_state() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshots(FlagStatus::Enabled)
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    write_stable_memory(&mut test, canister_id, b"before");
+    let snapshot = take_snapshot(&mut test, canister_id, None);
+
+    write_stable_memory(&mut test, canister_id, b"after!");
+    assert_eq!(read_stable_memory(&mut test, canister_id, 6), b"after!");
+    let version_before = test
+        .canister_state(canister_id)
+        .system_state
+        .canister_version;
+
+    let args = LoadCanisterSnapshotArgs::new(canister_id, snapshot.snapshot_id().unwrap(), None);
+    let result = test.subnet_message(Method::LoadCanisterSnapshot, args.encode());
+    assert!(result.is_ok(), "{:?}", result);
+
+    assert_eq!(read_stable_memory(&mut test, canister_id, 6), b"before");
+    assert!(
+        test.canister_state(canister_id)
+            .system_state
+            .canister_version
+            > version_before
+    );
+}
+
+#[test]
+fn load_canister_snapshot_is_recorded_in_canister_history() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshots(FlagStatus::Enabled)
+        .build();
+    let controller = test.universal_canister().unwrap();
+    let canister_id = test.universal_canister().unwrap();
+    let snapshot = take_snapshot(&mut test, canister_id, None);
+    let snapshot_version = test
+        .state()
+        .canister_snapshots
+        .get(SnapshotId::new(snapshot.snapshot_id().unwrap()))
+        .unwrap()
+        .canister_version();
+    test.set_controller(canister_id, controller.get()).unwrap();
+
+    let sender_canister_version = 42;
+    let args = LoadCanisterSnapshotArgs::new(
+        canister_id,
+        snapshot.snapshot_id().unwrap(),
+        Some(sender_canister_version),
+    );
+    let load_snapshot = wasm()
+        .call_simple(
+            ic00::IC_00,
+            Method::LoadCanisterSnapshot,
+            call_args().other_side(args.encode()),
+        )
+        .build();
+    let result = test.ingress(controller, "update", load_snapshot);
+    assert!(result.is_ok(), "{:?}", result);
+
+    let system_state = &test.canister_state(canister_id).system_state;
+    let last_change = system_state
+        .get_canister_history()
+        .get_changes(1)
+        .next()
+        .unwrap();
+    assert_eq!(
+        last_change,
+        &Arc::new(CanisterChange::new(
+            test.time().as_nanos_since_unix_epoch(),
+            system_state.canister_version,
+            CanisterChangeOrigin::from_canister(controller.get(), Some(sender_canister_version)),
+            CanisterChangeDetails::load_snapshot(
+                snapshot_version,
+                snapshot.snapshot_id().unwrap(),
+                snapshot.taken_at_timestamp(),
+            ),
+        ))
+    );
+}
+
+#[test]
+fn load_canister_snapshot_of_another_canister_fails() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshots(FlagStatus::Enabled)
+        .build();
+    let canister_1 = test.universal_canister().unwrap();
+    let canister_2 = test.universal_canister().unwrap();
+    let snapshot = take_snapshot(&mut test, canister_1, None);
+
+    let args = LoadCanisterSnapshotArgs::new(canister_2, snapshot.snapshot_id().unwrap(), None);
+    let err = test
+        .subnet_message(Method::LoadCanisterSnapshot, args.encode())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterRejectedMessage);
+}
+
+#[test]
+fn delete_canister_snapshot() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshots(FlagStatus::Enabled)
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let memory_usage_before = test.canister_state(canister_id).memory_usage();
+    let snapshot = take_snapshot(&mut test, canister_id, None);
+
+    let args = DeleteCanisterSnapshotArgs::new(canister_id, snapshot.snapshot_id().unwrap());
+    let result = test
+        .subnet_message(Method::DeleteCanisterSnapshot, args.encode())
+        .unwrap();
+    assert_eq!(result, WasmResult::Reply(candid::Encode!().unwrap()));
+    assert!(list_snapshots(&mut test, canister_id).is_empty());
+    assert_eq!(
+        test.canister_state(canister_id).memory_usage(),
+        memory_usage_before
+    );
+
+    // Deleting the snapshot again fails.
+    let err = test
+        .subnet_message(Method::DeleteCanisterSnapshot, args.encode())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterSnapshotNotFound);
+}
+
+#[test]
+fn deleting_canister_deletes_its_snapshots() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshots(FlagStatus::Enabled)
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    take_snapshot(&mut test, canister_id, None);
+
+    test.stop_canister(canister_id);
+    test.process_stopping_canisters();
+    test.subnet_message(
+        Method::DeleteCanister,
+        CanisterIdRecord::from(canister_id).encode(),
+    )
+    .unwrap();
+
+    assert!(test.state().canister_snapshots.is_empty());
+}
@@ -236,6 +236,7 @@ fn dashboard_label_value_from(code: ErrorCode) -> &'static str {
         CanisterFunctionNotFound => "Canister Function Not Found",
         CanisterAlreadyInstalled => "Canister Already Installed",
         CanisterWasmModuleNotFound => "Canister WASM Module Not Found",
+        CanisterSnapshotNotFound => "Canister snapshot not found",
         CanisterNonEmpty => "Canister Non-Empty",
         CanisterOutOfCycles => "Canister Out Of Cycles",
         CanisterTrapped => "Canister Trapped",
@@ -282,6 +283,7 @@ fn dashboard_label_value_from(code: ErrorCode) -> &'static str {
         ReservedCyclesLimitExceededInMemoryAllocation => "Canister cannot increase memory allocation due to its reserved cycles limit",
         ReservedCyclesLimitExceededInMemoryGrow => "Canister cannot grow memory due to its reserved cycles limit",
         InsufficientCyclesInMessageMemoryGrow => "Canister does not have enough cycles to grow message memory",
+        CanisterWasmMemoryLimitExceeded => "Canister exceeded its Wasm memory limit",
         StopCanisterRequestTimeout => "Stop canister request timed out",
     }
 }
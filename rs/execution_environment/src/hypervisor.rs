@@ -2,11 +2,12 @@ use ic_canister_sandbox_backend_lib::replica_controller::sandboxed_execution_con
 use ic_config::execution_environment::{Config, MAX_COMPILATION_CACHE_SIZE};
 use ic_config::flag_status::FlagStatus;
 use ic_cycles_account_manager::CyclesAccountManager;
-use ic_embedders::wasm_executor::{WasmExecutionResult, WasmExecutor};
-use ic_embedders::wasm_utils::decoding::decoded_wasm_size;
+use ic_embedders::wasm_executor::{PausedWasmExecution, WasmExecutionResult, WasmExecutor};
+use ic_embedders::wasm_utils::decoding::{decode_wasm, decoded_wasm_size};
+use ic_embedders::wasm_utils::function_names;
 use ic_embedders::{wasm_executor::WasmExecutorImpl, WasmExecutionInput, WasmtimeEmbedder};
 use ic_embedders::{CompilationCache, CompilationResult};
-use ic_interfaces::execution_environment::{HypervisorResult, WasmExecutionOutput};
+use ic_interfaces::execution_environment::{HypervisorResult, WasmExecutionOutput, WasmProfile};
 use ic_logger::ReplicaLogger;
 use ic_metrics::buckets::decimal_buckets_with_zero;
 use ic_metrics::{buckets::exponential_buckets, MetricsRegistry};
@@ -21,7 +22,11 @@ use ic_types::{
 };
 use ic_wasm_types::CanisterModule;
 use prometheus::{Histogram, IntCounter, IntGauge};
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
 
 use crate::execution::common::{apply_canister_state_changes, update_round_limits};
 use crate::execution_environment::{as_round_instructions, CompilationCostHandling, RoundLimits};
@@ -149,6 +154,109 @@ impl HypervisorMetrics {
     }
 }
 
+/// The Wasm profile of a single message execution, collected if
+/// `wasm_profiling` is enabled in the embedders config.
+#[derive(Clone, Debug)]
+pub struct CanisterWasmProfile {
+    pub canister_id: CanisterId,
+    /// The executed function, e.g. `canister_update transfer`.
+    pub method: String,
+    pub profile: WasmProfile,
+}
+
+impl CanisterWasmProfile {
+    /// Renders the profile in the folded stack format with the canister and
+    /// the executed function as the root frame.
+    pub fn to_folded(&self) -> String {
+        self.profile
+            .to_folded(&format!("{} {}", self.canister_id, self.method))
+    }
+}
+
+/// Collects the Wasm profiles of all executions of a hypervisor in the order
+/// in which the executions finished.
+#[derive(Default)]
+pub struct WasmProfileLog {
+    profiles: Mutex<Vec<CanisterWasmProfile>>,
+}
+
+impl WasmProfileLog {
+    fn push(&self, profile: CanisterWasmProfile) {
+        self.profiles.lock().unwrap().push(profile);
+    }
+
+    /// Returns and removes all profiles collected so far.
+    pub fn take(&self) -> Vec<CanisterWasmProfile> {
+        std::mem::take(&mut *self.profiles.lock().unwrap())
+    }
+}
+
+// Identifies the execution that a profile belongs to. Kept across the slices
+// of a paused execution.
+struct ProfiledExecution {
+    log: Arc<WasmProfileLog>,
+    canister_id: CanisterId,
+    method: String,
+    function_names: Arc<BTreeMap<u32, String>>,
+}
+
+impl ProfiledExecution {
+    fn record(self, result: WasmExecutionResult) -> WasmExecutionResult {
+        match result {
+            WasmExecutionResult::Finished(slice, mut output, changes) => {
+                if let Some(mut profile) = output.wasm_profile.take() {
+                    profile.function_names = profile
+                        .stacks
+                        .keys()
+                        .flatten()
+                        .filter_map(|func| {
+                            self.function_names
+                                .get(func)
+                                .map(|name| (*func, name.clone()))
+                        })
+                        .collect();
+                    self.log.push(CanisterWasmProfile {
+                        canister_id: self.canister_id,
+                        method: self.method,
+                        profile,
+                    });
+                }
+                WasmExecutionResult::Finished(slice, output, changes)
+            }
+            WasmExecutionResult::Paused(slice, paused) => WasmExecutionResult::Paused(
+                slice,
+                Box::new(ProfiledPausedExecution {
+                    execution: self,
+                    paused,
+                }),
+            ),
+        }
+    }
+}
+
+// Records the profile once the wrapped paused execution finishes.
+struct ProfiledPausedExecution {
+    execution: ProfiledExecution,
+    paused: Box<dyn PausedWasmExecution>,
+}
+
+impl std::fmt::Debug for ProfiledPausedExecution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.paused.fmt(f)
+    }
+}
+
+impl PausedWasmExecution for ProfiledPausedExecution {
+    fn resume(self: Box<Self>, execution_state: &ExecutionState) -> WasmExecutionResult {
+        let result = self.paused.resume(execution_state);
+        self.execution.record(result)
+    }
+
+    fn abort(self: Box<Self>) {
+        self.paused.abort()
+    }
+}
+
 #[doc(hidden)]
 pub struct Hypervisor {
     wasm_executor: Arc<dyn WasmExecutor>,
@@ -161,6 +269,8 @@ pub struct Hypervisor {
     deterministic_time_slicing: FlagStatus,
     cost_to_compile_wasm_instruction: NumInstructions,
     dirty_page_overhead: NumInstructions,
+    wasm_profiling: FlagStatus,
+    wasm_profiles: Arc<WasmProfileLog>,
 }
 
 impl Hypervisor {
@@ -270,6 +380,8 @@ impl Hypervisor {
                 .embedders_config
                 .cost_to_compile_wasm_instruction,
             dirty_page_overhead,
+            wasm_profiling: config.embedders_config.wasm_profiling,
+            wasm_profiles: Arc::new(WasmProfileLog::default()),
         }
     }
 
@@ -296,9 +408,17 @@ impl Hypervisor {
             deterministic_time_slicing,
             cost_to_compile_wasm_instruction,
             dirty_page_overhead,
+            wasm_profiling: FlagStatus::Disabled,
+            wasm_profiles: Arc::new(WasmProfileLog::default()),
         }
     }
 
+    /// The profiles of the executions of this hypervisor. Stays empty unless
+    /// `wasm_profiling` is enabled in the embedders config.
+    pub fn wasm_profiles(&self) -> Arc<WasmProfileLog> {
+        Arc::clone(&self.wasm_profiles)
+    }
+
     #[cfg(test)]
     pub fn compile_count(&self) -> u64 {
         self.metrics.compile.get_sample_count()
@@ -399,6 +519,14 @@ impl Hypervisor {
             execution_parameters.compute_allocation,
             request_metadata,
         );
+        let profiled_execution = match self.wasm_profiling {
+            FlagStatus::Enabled => Some(self.profiled_execution(
+                system_state.canister_id(),
+                &func_ref,
+                execution_state,
+            )),
+            FlagStatus::Disabled => None,
+        };
         let (compilation_result, execution_result) = Arc::clone(&self.wasm_executor).execute(
             WasmExecutionInput {
                 api_type,
@@ -418,7 +546,34 @@ impl Hypervisor {
                 .observe_compilation_metrics(&compilation_result);
         }
         self.metrics.observe(&execution_result);
-        execution_result
+        match profiled_execution {
+            Some(profiled_execution) => profiled_execution.record(execution_result),
+            None => execution_result,
+        }
+    }
+
+    fn profiled_execution(
+        &self,
+        canister_id: CanisterId,
+        func_ref: &FuncRef,
+        execution_state: &ExecutionState,
+    ) -> ProfiledExecution {
+        let method = match func_ref {
+            FuncRef::Method(method) => method.to_string(),
+            FuncRef::UpdateClosure(closure) | FuncRef::QueryClosure(closure) => {
+                format!("closure[{}]", closure.func_idx)
+            }
+        };
+        let module = execution_state.wasm_binary.binary.as_slice().to_vec();
+        let function_names = decode_wasm(Arc::new(module))
+            .map(|wasm| function_names(&wasm))
+            .unwrap_or_default();
+        ProfiledExecution {
+            log: Arc::clone(&self.wasm_profiles),
+            canister_id,
+            method,
+            function_names: Arc::new(function_names),
+        }
     }
 
     #[doc(hidden)]
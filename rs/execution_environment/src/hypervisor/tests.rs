@@ -28,7 +28,8 @@ use ic_types::{
     messages::CanisterTask,
     messages::MAX_INTER_CANISTER_PAYLOAD_IN_BYTES,
     methods::WasmMethod,
-    CanisterId, ComputeAllocation, Cycles, NumBytes, NumInstructions, MAX_STABLE_MEMORY_IN_BYTES,
+    CanisterId, ComputeAllocation, Cycles, NumBytes, NumInstructions, SubnetId,
+    MAX_STABLE_MEMORY_IN_BYTES,
 };
 use ic_universal_canister::{call_args, wasm, UNIVERSAL_CANISTER_WASM};
 use proptest::prelude::*;
@@ -1393,6 +1394,155 @@ fn ic0_msg_arg_data_copy_works() {
     assert_eq!(WasmResult::Reply(b"xxxxyyyy".to_vec()), result);
 }
 
+#[test]
+fn wasm64_canister_can_use_64_bit_heap_addresses() {
+    let mut test = ExecutionTestBuilder::new().with_wasm64().build();
+    let wat = r#"
+        (module
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (import "ic0" "msg_reply_data_append"
+                (func $msg_reply_data_append (param i64 i64))
+            )
+            (import "ic0" "msg_arg_data_copy"
+                (func $msg_arg_data_copy (param i64 i64 i64))
+            )
+            (import "ic0" "stable64_grow" (func $stable64_grow (param i64) (result i64)))
+            (import "ic0" "stable64_write"
+                (func $stable64_write (param i64 i64 i64))
+            )
+            (import "ic0" "stable64_read"
+                (func $stable64_read (param i64 i64 i64))
+            )
+            (func (export "canister_update test")
+                    ;; copy the payload to the start of a new heap page
+                    (drop (memory.grow (i64.const 1)))
+                    (call $msg_arg_data_copy
+                        (i64.const 65536) ;; heap dst = 65536
+                        (i64.const 0)     ;; payload offset = 0
+                        (i64.const 4))    ;; length = 4
+                    ;; round-trip the payload through stable memory
+                    (drop (call $stable64_grow (i64.const 1)))
+                    (call $stable64_write (i64.const 0) (i64.const 65536) (i64.const 4))
+                    (call $stable64_read (i64.const 4) (i64.const 0) (i64.const 4))
+                    (call $msg_reply_data_append
+                        (i64.const 0)     ;; heap offset = 0
+                        (i64.const 8))    ;; length = 8
+                    (call $msg_reply)
+            )
+            (memory i64 1 2)
+            (data (i64.const 0) "xxxxabcd")
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    let payload = vec![121, 121, 121, 121];
+    let result = test.ingress(canister_id, "test", payload).unwrap();
+    assert_eq!(WasmResult::Reply(b"xxxxyyyy".to_vec()), result);
+}
+
+#[test]
+fn wasm64_canister_can_grow_heap_beyond_4_gib() {
+    let mut test = ExecutionTestBuilder::new().with_wasm64().build();
+    let wat = r#"
+        (module
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (import "ic0" "msg_reply_data_append"
+                (func $msg_reply_data_append (param i64 i64))
+            )
+            (import "ic0" "msg_arg_data_copy"
+                (func $msg_arg_data_copy (param i64 i64 i64))
+            )
+            (func (export "canister_update test")
+                    ;; grow the heap to 65553 pages, i.e. 4 GiB + 1088 KiB
+                    (if (i64.ne (memory.grow (i64.const 65552)) (i64.const 1))
+                        (then (unreachable))
+                    )
+                    (if (i64.ne (memory.size) (i64.const 65553))
+                        (then (unreachable))
+                    )
+                    ;; copy the payload above 4 GiB
+                    (call $msg_arg_data_copy
+                        (i64.const 4294967304) ;; heap dst = 4 GiB + 8
+                        (i64.const 0)          ;; payload offset = 0
+                        (i64.const 8))         ;; length = 8
+                    ;; copy it from above 4 GiB to the start of the heap
+                    (i64.store (i64.const 0) (i64.load (i64.const 4294967304)))
+                    ;; write right below the end of the heap
+                    (i64.store (i64.const 4296081400) (i64.const 0x6867666564636261))
+                    (call $msg_reply_data_append
+                        (i64.const 0)          ;; heap offset = 0
+                        (i64.const 8))         ;; length = 8
+                    (call $msg_reply_data_append
+                        (i64.const 4296081400) ;; heap offset = heap size - 8
+                        (i64.const 8))         ;; length = 8
+                    (call $msg_reply)
+            )
+            (memory i64 1)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    let payload = b"zyxwvuts".to_vec();
+    let result = test.ingress(canister_id, "test", payload).unwrap();
+    assert_eq!(WasmResult::Reply(b"zyxwvutsabcdefgh".to_vec()), result);
+    assert!(test.canister_state(canister_id).memory_usage() > NumBytes::new(4 << 30));
+}
+
+#[test]
+fn wasm64_canister_memory_limit_includes_larger_heap() {
+    // The limit is too small for a heap beyond 4 GiB unless the additional
+    // memory of a 64-bit heap is accounted for.
+    let mut test = ExecutionTestBuilder::new()
+        .with_wasm64()
+        .with_max_canister_memory_size(4 << 30)
+        .build();
+    let wat = r#"
+        (module
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (func (export "canister_update test")
+                    ;; grow the heap to 65553 pages, i.e. 4 GiB + 1088 KiB
+                    (if (i64.ne (memory.grow (i64.const 65552)) (i64.const 1))
+                        (then (unreachable))
+                    )
+                    (call $msg_reply)
+            )
+            (memory i64 1)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    let result = test.ingress(canister_id, "test", vec![]).unwrap();
+    assert_eq!(WasmResult::Reply(vec![]), result);
+    assert!(test.canister_state(canister_id).memory_usage() > NumBytes::new(4 << 30));
+}
+
+#[test]
+fn wasm32_canister_memory_limit_excludes_larger_heap_of_wasm64() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_wasm64()
+        .with_max_canister_memory_size(1 << 30)
+        .build();
+    let wat = r#"
+        (module
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (func (export "canister_update test")
+                    ;; grow the heap to 1 GiB + 64 KiB
+                    (drop (memory.grow (i32.const 16384)))
+                    (call $msg_reply)
+            )
+            (memory 1)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    let err = test.ingress(canister_id, "test", vec![]).unwrap_err();
+    assert_eq!(ErrorCode::CanisterOutOfMemory, err.code());
+}
+
+#[test]
+fn wasm64_canister_is_rejected_if_wasm64_is_disabled() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let wat = r#"
+        (module
+            (func (export "canister_update test"))
+            (memory i64 1)
+        )"#;
+    let err = test.canister_from_wat(wat).unwrap_err();
+    assert_eq!(ErrorCode::CanisterInvalidWasm, err.code());
+}
+
 #[test]
 fn ic0_msg_reject_works() {
     let mut test = ExecutionTestBuilder::new().build();
@@ -1894,6 +2044,69 @@ fn ic0_canister_self_copy_works() {
     assert_eq!(WasmResult::Reply(canister_id.get().into_vec()), result);
 }
 
+#[test]
+fn ic0_subnet_self_size_and_copy_work() {
+    let subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(7));
+    let mut test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(subnet_id)
+        .build();
+    let wat = r#"
+        (module
+            (import "ic0" "subnet_self_size"
+                (func $subnet_self_size (result i32))
+            )
+            (import "ic0" "subnet_self_copy"
+                (func $subnet_self_copy (param i32 i32 i32))
+            )
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (import "ic0" "msg_reply_data_append"
+            (func $msg_reply_data_append (param i32 i32)))
+            (func (export "canister_update test")
+                ;; heap[0..size] = subnet_id_bytes
+                (call $subnet_self_copy (i32.const 0) (i32.const 0) (call $subnet_self_size))
+                ;; return heap[0..size]
+                (call $msg_reply_data_append (i32.const 0) (call $subnet_self_size))
+                (call $msg_reply)
+            )
+            (memory 1 1)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    let result = test.ingress(canister_id, "test", vec![]).unwrap();
+    assert_eq!(WasmResult::Reply(subnet_id.get().into_vec()), result);
+}
+
+#[test]
+fn ic0_root_key_size_and_copy_work_in_queries() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let wat = r#"
+        (module
+            (import "ic0" "root_key_size"
+                (func $root_key_size (result i32))
+            )
+            (import "ic0" "root_key_copy"
+                (func $root_key_copy (param i32 i32 i32))
+            )
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (import "ic0" "msg_reply_data_append"
+            (func $msg_reply_data_append (param i32 i32)))
+            (func (export "canister_query test")
+                ;; heap[0..size] = root_key
+                (call $root_key_copy (i32.const 0) (i32.const 0) (call $root_key_size))
+                ;; return heap[0..size]
+                (call $msg_reply_data_append (i32.const 0) (call $root_key_size))
+                (call $msg_reply)
+            )
+            (memory 1 1)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    let result = test
+        .non_replicated_query(canister_id, "test", vec![])
+        .unwrap();
+    // The test environment uses this public key for all subnets, including
+    // the NNS subnet.
+    assert_eq!(WasmResult::Reply(vec![1, 2, 3, 4]), result);
+}
+
 #[test]
 fn ic0_call_has_no_effect_on_trap() {
     let mut test = ExecutionTestBuilder::new().build();
@@ -6491,6 +6704,128 @@ fn stable_memory_grow_respects_reserved_cycles_limit() {
         .contains("due to its reserved cycles limit"));
 }
 
+#[test]
+fn wasm_memory_grow_respects_wasm_memory_limit() {
+    let mut test = ExecutionTestBuilder::new().build();
+
+    let wat = r#"
+        (module
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (func $update
+                (if (i32.eq (memory.grow (i32.const 10)) (i32.const -1))
+                  (then (unreachable))
+                )
+                (call $msg_reply)
+            )
+            (memory $memory 1)
+            (export "canister_update update" (func $update))
+        )"#;
+
+    let canister_id = test.canister_from_wat(wat).unwrap();
+
+    test.canister_update_wasm_memory_limit(
+        canister_id,
+        NumBytes::new(5 * WASM_PAGE_SIZE_IN_BYTES as u64),
+    )
+    .unwrap();
+
+    let err = test.ingress(canister_id, "update", vec![]).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterWasmMemoryLimitExceeded);
+    assert!(err
+        .description()
+        .contains("Canister exceeded its current Wasm memory limit"));
+
+    test.canister_update_wasm_memory_limit(
+        canister_id,
+        NumBytes::new(20 * WASM_PAGE_SIZE_IN_BYTES as u64),
+    )
+    .unwrap();
+
+    let result = test.ingress(canister_id, "update", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(vec![]));
+}
+
+#[test]
+fn wasm_memory_limit_of_zero_clears_the_limit() {
+    let mut test = ExecutionTestBuilder::new().build();
+
+    let wat = r#"
+        (module
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (func $update
+                (if (i32.eq (memory.grow (i32.const 10)) (i32.const -1))
+                  (then (unreachable))
+                )
+                (call $msg_reply)
+            )
+            (memory $memory 1)
+            (export "canister_update update" (func $update))
+        )"#;
+
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .wasm_memory_limit,
+        None
+    );
+
+    // Set the limit: growing the memory fails.
+    let limit = NumBytes::new(5 * WASM_PAGE_SIZE_IN_BYTES as u64);
+    test.canister_update_wasm_memory_limit(canister_id, limit)
+        .unwrap();
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .wasm_memory_limit,
+        Some(limit)
+    );
+    let err = test.ingress(canister_id, "update", vec![]).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterWasmMemoryLimitExceeded);
+
+    // Clear the limit: growing the memory succeeds, also beyond the old limit.
+    test.canister_update_wasm_memory_limit(canister_id, NumBytes::new(0))
+        .unwrap();
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .wasm_memory_limit,
+        None
+    );
+    for _ in 0..2 {
+        let result = test.ingress(canister_id, "update", vec![]).unwrap();
+        assert_eq!(result, WasmResult::Reply(vec![]));
+    }
+    assert!(test.execution_state(canister_id).wasm_memory.size.get() > 5);
+}
+
+#[test]
+fn wasm_memory_limit_is_not_enforced_in_upgrade() {
+    let mut test = ExecutionTestBuilder::new().build();
+
+    let wat = r#"
+        (module
+            (func $post_upgrade
+                (if (i32.eq (memory.grow (i32.const 10)) (i32.const -1))
+                  (then (unreachable))
+                )
+            )
+            (memory $memory 1)
+            (export "canister_post_upgrade" (func $post_upgrade))
+        )"#;
+
+    let canister_id = test.canister_from_wat(wat).unwrap();
+
+    test.canister_update_wasm_memory_limit(
+        canister_id,
+        NumBytes::new(5 * WASM_PAGE_SIZE_IN_BYTES as u64),
+    )
+    .unwrap();
+
+    test.upgrade_canister(canister_id, wat::parse_str(wat).unwrap())
+        .unwrap();
+}
+
 #[test]
 fn stable_memory_grow_does_not_reserve_cycles_on_out_of_memory() {
     const CYCLES: Cycles = Cycles::new(200_000_000_000_000);
@@ -6745,3 +7080,66 @@ fn yield_for_dirty_page_copy_does_not_trigger_on_system_subnets_without_dts() {
         NextExecution::None
     );
 }
+
+#[test]
+fn wasm_profiling_records_instructions_per_call_stack() {
+    let mut test = ExecutionTestBuilder::new().with_wasm_profiling().build();
+    let wat = r#"
+        (module
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (func $inner (result i32)
+                (i32.add (i32.const 1) (i32.const 2))
+            )
+            (func $outer
+                (drop (call $inner))
+                (if (i32.eqz (call $inner)) (then (return)))
+                (call $msg_reply)
+            )
+            (func $test (export "canister_update test")
+                (call $outer)
+            )
+            (memory 1)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    test.hypervisor_deprecated().wasm_profiles().take();
+
+    let result = test.ingress(canister_id, "test", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(vec![]));
+
+    let profiles = test.hypervisor_deprecated().wasm_profiles().take();
+    assert_eq!(profiles.len(), 1);
+    assert_eq!(profiles[0].canister_id, canister_id);
+    assert_eq!(profiles[0].method, "canister_update test");
+
+    let profile = &profiles[0].profile;
+    let stacks: Vec<Vec<String>> = profile
+        .stacks
+        .keys()
+        .map(|stack| {
+            stack
+                .iter()
+                .map(|func| profile.function_name(*func))
+                .collect()
+        })
+        .collect();
+    assert!(stacks.contains(&vec![
+        "test".to_string(),
+        "outer".to_string(),
+        "inner".to_string()
+    ]));
+    // Both calls of `inner` returned to `outer`, so no stack is deeper.
+    assert!(stacks.iter().all(|stack| stack.len() <= 3));
+
+    // The functions are numbered after the imported `msg_reply`.
+    let self_instructions = profile.self_instructions();
+    let inclusive_instructions = profile.inclusive_instructions();
+    assert!(self_instructions[&1] > 0);
+    assert!(self_instructions[&2] > 0);
+    assert_eq!(
+        inclusive_instructions[&2],
+        self_instructions[&1] + self_instructions[&2]
+    );
+    assert!(profiles[0]
+        .to_folded()
+        .contains("canister_update test;test;outer;inner "));
+}
@@ -22,7 +22,7 @@ pub use execution_environment::{
     ExecuteMessageResult, ExecutionEnvironment, ExecutionResponse, RoundInstructions, RoundLimits,
 };
 pub use history::{IngressHistoryReaderImpl, IngressHistoryWriterImpl};
-pub use hypervisor::{Hypervisor, HypervisorMetrics};
+pub use hypervisor::{CanisterWasmProfile, Hypervisor, HypervisorMetrics, WasmProfileLog};
 use ic_base_types::PrincipalId;
 use ic_config::{execution_environment::Config, subnet_config::SchedulerConfig};
 use ic_cycles_account_manager::CyclesAccountManager;
@@ -89,6 +89,8 @@ pub struct ExecutionServices {
     pub anonymous_query_handler: AnonymousQueryService,
     pub scheduler: Box<dyn Scheduler<State = ReplicatedState>>,
     pub query_stats_payload_builder: QueryStatsPayloadBuilderParams,
+    /// The Wasm profiles of executed messages if Wasm profiling is enabled.
+    pub wasm_profiles: Arc<WasmProfileLog>,
 }
 
 impl ExecutionServices {
@@ -116,6 +118,7 @@ impl ExecutionServices {
             scheduler_config.dirty_page_overhead,
             Arc::clone(&fd_factory),
         ));
+        let wasm_profiles = hypervisor.wasm_profiles();
 
         let ingress_history_writer = Arc::new(IngressHistoryWriterImpl::new(
             config.clone(),
@@ -211,6 +214,7 @@ impl ExecutionServices {
             anonymous_query_handler,
             scheduler,
             query_stats_payload_builder,
+            wasm_profiles,
         }
     }
 
@@ -5,7 +5,7 @@ use ic_metrics::{
 use ic_system_api::sandbox_safe_system_state::RequestMetadataStats;
 use ic_types::{
     NumInstructions, NumMessages, NumSlices, Time, MAX_STABLE_MEMORY_IN_BYTES,
-    MAX_WASM_MEMORY_IN_BYTES,
+    MAX_WASM64_MEMORY_IN_BYTES, MAX_WASM_MEMORY_IN_BYTES,
 };
 use prometheus::{Histogram, IntCounter, IntCounterVec};
 use std::{cell::RefCell, rc::Rc, time::Instant};
@@ -461,7 +461,14 @@ fn memory_buckets() -> Vec<f64> {
         8 * G,
     ]
     .iter()
-    .chain([MAX_STABLE_MEMORY_IN_BYTES, MAX_WASM_MEMORY_IN_BYTES].iter())
+    .chain(
+        [
+            MAX_STABLE_MEMORY_IN_BYTES,
+            MAX_WASM_MEMORY_IN_BYTES,
+            MAX_WASM64_MEMORY_IN_BYTES,
+        ]
+        .iter(),
+    )
     .cloned()
     .collect();
     // Ensure that all buckets are unique
@@ -799,5 +806,6 @@ mod tests {
         assert!(buckets.contains(&0));
         assert!(buckets.contains(&MAX_STABLE_MEMORY_IN_BYTES));
         assert!(buckets.contains(&MAX_WASM_MEMORY_IN_BYTES));
+        assert!(buckets.contains(&MAX_WASM64_MEMORY_IN_BYTES));
     }
 }
@@ -54,7 +54,7 @@ use tower::{util::BoxCloneService, Service};
 
 pub(crate) use self::query_scheduler::{QueryScheduler, QuerySchedulerFlag};
 use ic_ic00_types::{
-    BitcoinGetBalanceArgs, BitcoinGetUtxosArgs, FetchCanisterLogsRequest,
+    BitcoinGetBalanceArgs, BitcoinGetUtxosArgs, CanisterLogRecord, FetchCanisterLogsRequest,
     FetchCanisterLogsResponse, LogVisibility, Payload, QueryMethod,
 };
 use ic_replicated_state::NetworkTopology;
@@ -358,13 +358,30 @@ fn fetch_canister_logs(
         )),
     }?;
 
-    // TODO(IC-272): temporarily return empty logs until full implementation is ready.
-    let response = FetchCanisterLogsResponse {
-        canister_log_records: Vec::new(),
-    };
+    let response = filter_canister_log_records(
+        &args,
+        canister.system_state.canister_log.records().iter().cloned(),
+    );
     Ok(WasmResult::Reply(Encode!(&response).unwrap()))
 }
 
+/// Applies the filters of the request to the given records (which are
+/// expected to be sorted by `idx`) and truncates the result to at most
+/// `max_records` records.
+pub(crate) fn filter_canister_log_records(
+    args: &FetchCanisterLogsRequest,
+    records: impl IntoIterator<Item = CanisterLogRecord>,
+) -> FetchCanisterLogsResponse {
+    let max_records = args.max_records.unwrap_or(u64::MAX) as usize;
+    let mut matching = records.into_iter().filter(|record| args.matches(record));
+    let canister_log_records: Vec<_> = matching.by_ref().take(max_records).collect();
+    let has_more = matching.next().is_some();
+    FetchCanisterLogsResponse {
+        canister_log_records,
+        has_more,
+    }
+}
+
 impl HttpQueryHandler {
     pub(crate) fn new_service(
         internal: Arc<dyn QueryHandler<State = ReplicatedState>>,
@@ -1057,6 +1057,7 @@ fn query_cache_future_proof_test() {
         | SystemApiCallId::CallNew
         | SystemApiCallId::CallOnCleanup
         | SystemApiCallId::CallPerform
+        | SystemApiCallId::CallWithBestEffortResponse
         | SystemApiCallId::CanisterCycleBalance
         | SystemApiCallId::CanisterCycleBalance128
         | SystemApiCallId::CanisterSelfCopy
@@ -1064,6 +1065,11 @@ fn query_cache_future_proof_test() {
         | SystemApiCallId::CanisterStatus
         | SystemApiCallId::CanisterVersion
         | SystemApiCallId::CertifiedDataSet
+        | SystemApiCallId::CostCall
+        | SystemApiCallId::CostCreateCanister
+        | SystemApiCallId::CostHttpRequest
+        | SystemApiCallId::CostSignWithEcdsa
+        | SystemApiCallId::CostSignWithSchnorr
         | SystemApiCallId::CyclesBurn128
         | SystemApiCallId::DataCertificateCopy
         | SystemApiCallId::DataCertificatePresent
@@ -1082,6 +1088,7 @@ fn query_cache_future_proof_test() {
         | SystemApiCallId::MsgCyclesAvailable128
         | SystemApiCallId::MsgCyclesRefunded
         | SystemApiCallId::MsgCyclesRefunded128
+        | SystemApiCallId::MsgDeadline
         | SystemApiCallId::MsgMethodNameCopy
         | SystemApiCallId::MsgMethodNameSize
         | SystemApiCallId::MsgReject
@@ -1092,6 +1099,8 @@ fn query_cache_future_proof_test() {
         | SystemApiCallId::MsgReplyDataAppend
         | SystemApiCallId::OutOfInstructions
         | SystemApiCallId::PerformanceCounter
+        | SystemApiCallId::RootKeyCopy
+        | SystemApiCallId::RootKeySize
         | SystemApiCallId::Stable64Grow
         | SystemApiCallId::Stable64Read
         | SystemApiCallId::Stable64Size
@@ -1100,6 +1109,8 @@ fn query_cache_future_proof_test() {
         | SystemApiCallId::StableRead
         | SystemApiCallId::StableSize
         | SystemApiCallId::StableWrite
+        | SystemApiCallId::SubnetSelfCopy
+        | SystemApiCallId::SubnetSelfSize
         | SystemApiCallId::Time
         | SystemApiCallId::Trap
         | SystemApiCallId::UpdateAvailableMemory => {
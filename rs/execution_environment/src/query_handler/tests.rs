@@ -1,9 +1,13 @@
+use super::filter_canister_log_records;
 use crate::InternalHttpQueryHandler;
 use ic_base_types::{CanisterId, NumSeconds};
 use ic_btc_interface::NetworkInRequest as BitcoinNetwork;
 use ic_config::execution_environment::INSTRUCTION_OVERHEAD_PER_QUERY_CALL;
 use ic_error_types::{ErrorCode, UserError};
-use ic_ic00_types::{BitcoinGetBalanceArgs, BitcoinGetUtxosArgs, Payload};
+use ic_ic00_types::{
+    BitcoinGetBalanceArgs, BitcoinGetUtxosArgs, CanisterLogContentFilter, CanisterLogRecord,
+    FetchCanisterLogsRange, FetchCanisterLogsRequest, FetchCanisterLogsResponse, Payload,
+};
 use ic_registry_subnet_type::SubnetType;
 use ic_test_utilities::{
     types::ids::user_test_id,
@@ -1720,3 +1724,87 @@ fn test_call_context_performance_counter_correctly_reported_on_composite_query()
     assert!(counters[1] < counters[2]);
     assert!(counters[2] < counters[3]);
 }
+
+fn log_record(idx: u64, timestamp_nanos: u64, content: &[u8]) -> CanisterLogRecord {
+    CanisterLogRecord {
+        idx,
+        timestamp_nanos,
+        content: content.to_vec(),
+    }
+}
+
+fn test_log_records() -> Vec<CanisterLogRecord> {
+    vec![
+        log_record(0, 100, b"info: started"),
+        log_record(1, 200, b"error: out of cycles"),
+        log_record(2, 300, b"info: upgraded"),
+        log_record(3, 400, b"error: trapped"),
+    ]
+}
+
+fn record_indices(response: &FetchCanisterLogsResponse) -> Vec<u64> {
+    response
+        .canister_log_records
+        .iter()
+        .map(|record| record.idx)
+        .collect()
+}
+
+#[test]
+fn filter_canister_log_records_without_filters_returns_all_records() {
+    let args = FetchCanisterLogsRequest::new(CanisterId::from_u64(1));
+    let response = filter_canister_log_records(&args, test_log_records());
+    assert_eq!(response.canister_log_records, test_log_records());
+    assert!(!response.has_more);
+}
+
+#[test]
+fn filter_canister_log_records_by_idx_and_timestamp_range() {
+    let args = FetchCanisterLogsRequest {
+        idx_range: Some(FetchCanisterLogsRange::new(1, 4)),
+        timestamp_nanos_range: Some(FetchCanisterLogsRange::new(0, 400)),
+        ..FetchCanisterLogsRequest::new(CanisterId::from_u64(1))
+    };
+    let response = filter_canister_log_records(&args, test_log_records());
+    assert_eq!(record_indices(&response), vec![1, 2]);
+    assert!(!response.has_more);
+}
+
+#[test]
+fn filter_canister_log_records_by_content() {
+    let args = FetchCanisterLogsRequest {
+        content_filter: Some(CanisterLogContentFilter::Prefix(b"error".to_vec())),
+        ..FetchCanisterLogsRequest::new(CanisterId::from_u64(1))
+    };
+    let response = filter_canister_log_records(&args, test_log_records());
+    assert_eq!(record_indices(&response), vec![1, 3]);
+
+    let args = FetchCanisterLogsRequest {
+        content_filter: Some(CanisterLogContentFilter::Substring(b"up".to_vec())),
+        ..FetchCanisterLogsRequest::new(CanisterId::from_u64(1))
+    };
+    let response = filter_canister_log_records(&args, test_log_records());
+    assert_eq!(record_indices(&response), vec![2]);
+}
+
+#[test]
+fn filter_canister_log_records_paginates_with_max_records() {
+    let mut args = FetchCanisterLogsRequest {
+        max_records: Some(3),
+        ..FetchCanisterLogsRequest::new(CanisterId::from_u64(1))
+    };
+    let response = filter_canister_log_records(&args, test_log_records());
+    assert_eq!(record_indices(&response), vec![0, 1, 2]);
+    assert!(response.has_more);
+
+    // Fetch the next page starting after the last returned record.
+    args.idx_range = Some(FetchCanisterLogsRange::new(3, u64::MAX));
+    let response = filter_canister_log_records(&args, test_log_records());
+    assert_eq!(record_indices(&response), vec![3]);
+    assert!(!response.has_more);
+
+    // An exact fit does not report more records.
+    args.idx_range = None;
+    args.max_records = Some(4);
+    assert!(!filter_canister_log_records(&args, test_log_records()).has_more);
+}
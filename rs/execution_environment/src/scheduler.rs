@@ -12,7 +12,7 @@ use ic_config::subnet_config::SchedulerConfig;
 use ic_crypto_prng::{Csprng, RandomnessPurpose::ExecutionThread};
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_error_types::{ErrorCode, UserError};
-use ic_ic00_types::{CanisterStatusType, EcdsaKeyId, Method as Ic00Method};
+use ic_ic00_types::{CanisterStatusType, EcdsaKeyId, Method as Ic00Method, SchnorrKeyId};
 use ic_interfaces::execution_environment::{ExecutionRoundType, RegistryExecutionSettings};
 use ic_interfaces::execution_environment::{
     IngressHistoryWriter, Scheduler, SubnetAvailableMemory,
@@ -29,7 +29,7 @@ use ic_replicated_state::{
 use ic_system_api::InstructionLimits;
 use ic_types::{
     consensus::ecdsa::QuadrupleId,
-    crypto::canister_threshold_sig::MasterEcdsaPublicKey,
+    crypto::canister_threshold_sig::{MasterEcdsaPublicKey, MasterSchnorrPublicKey},
     ingress::{IngressState, IngressStatus},
     messages::{CanisterMessage, Ingress, MessageId, StopCanisterContext},
     AccumulatedPriority, CanisterId, ComputeAllocation, Cycles, ExecutionRound, LongExecutionMode,
@@ -479,6 +479,7 @@ impl SchedulerImpl {
         long_running_canister_ids: BTreeSet<CanisterId>,
         registry_settings: &RegistryExecutionSettings,
         ecdsa_subnet_public_keys: &BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+        schnorr_subnet_public_keys: &BTreeMap<SchnorrKeyId, MasterSchnorrPublicKey>,
     ) -> ReplicatedState {
         loop {
             let mut available_subnet_messages = false;
@@ -506,6 +507,7 @@ impl SchedulerImpl {
                     registry_settings,
                     measurement_scope,
                     ecdsa_subnet_public_keys,
+                    schnorr_subnet_public_keys,
                 );
                 state = new_state;
 
@@ -538,6 +540,7 @@ impl SchedulerImpl {
         registry_settings: &RegistryExecutionSettings,
         measurement_scope: &MeasurementScope,
         ecdsa_subnet_public_keys: &BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+        schnorr_subnet_public_keys: &BTreeMap<SchnorrKeyId, MasterSchnorrPublicKey>,
     ) -> (ReplicatedState, Option<NumInstructions>) {
         let instruction_limits = get_instructions_limits_for_subnet_message(
             self.deterministic_time_slicing,
@@ -552,6 +555,7 @@ impl SchedulerImpl {
             instruction_limits,
             csprng,
             ecdsa_subnet_public_keys,
+            schnorr_subnet_public_keys,
             registry_settings,
             round_limits,
         );
@@ -645,6 +649,7 @@ impl SchedulerImpl {
         scheduler_round_limits: &mut SchedulerRoundLimits,
         registry_settings: &RegistryExecutionSettings,
         ecdsa_subnet_public_keys: &BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+        schnorr_subnet_public_keys: &BTreeMap<SchnorrKeyId, MasterSchnorrPublicKey>,
     ) -> (ReplicatedState, BTreeSet<CanisterId>) {
         let measurement_scope =
             MeasurementScope::nested(&self.metrics.round_inner, root_measurement_scope);
@@ -698,6 +703,7 @@ impl SchedulerImpl {
                         long_running_canister_ids,
                         registry_settings,
                         ecdsa_subnet_public_keys,
+                        schnorr_subnet_public_keys,
                     );
                     scheduler_round_limits.update_subnet_round_limits(&subnet_round_limits);
                 }
@@ -1398,6 +1404,7 @@ impl Scheduler for SchedulerImpl {
         mut state: ReplicatedState,
         randomness: Randomness,
         ecdsa_subnet_public_keys: BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+        schnorr_subnet_public_keys: BTreeMap<SchnorrKeyId, MasterSchnorrPublicKey>,
         ecdsa_quadruple_ids: BTreeMap<EcdsaKeyId, BTreeSet<QuadrupleId>>,
         current_round: ExecutionRound,
         current_round_type: ExecutionRoundType,
@@ -1566,6 +1573,7 @@ impl Scheduler for SchedulerImpl {
                     registry_settings,
                     &measurement_scope,
                     &ecdsa_subnet_public_keys,
+                    &schnorr_subnet_public_keys,
                 );
                 state = new_state;
                 if subnet_round_limits.reached() {
@@ -1603,6 +1611,7 @@ impl Scheduler for SchedulerImpl {
                     registry_settings,
                     &measurement_scope,
                     &ecdsa_subnet_public_keys,
+                    &schnorr_subnet_public_keys,
                 );
                 state = new_state;
             }
@@ -1662,6 +1671,7 @@ impl Scheduler for SchedulerImpl {
             &mut scheduler_round_limits,
             registry_settings,
             &ecdsa_subnet_public_keys,
+            &schnorr_subnet_public_keys,
         );
 
         // Update [`SignWithEcdsaContext`]s by assigning randomness and matching quadruples.
@@ -2183,6 +2193,13 @@ fn observe_replicated_state_metrics(
         .ecdsa_signature_agreements
         .set(state.metadata.subnet_metrics.ecdsa_signature_agreements as i64);
 
+    for (key_id, count) in &state.metadata.subnet_metrics.schnorr_signature_agreements {
+        metrics
+            .schnorr_signature_agreements
+            .with_label_values(&[&key_id.to_string()])
+            .set(*count as i64);
+    }
+
     let observe_reading = |status: CanisterStatusType, num: i64| {
         metrics
             .registered_canisters
@@ -2314,6 +2331,8 @@ fn get_instructions_limits_for_subnet_message(
             | HttpRequest
             | SetupInitialDKG
             | SignWithECDSA
+            | SchnorrPublicKey
+            | SignWithSchnorr
             | ComputeInitialEcdsaDealings
             | StartCanister
             | StopCanister
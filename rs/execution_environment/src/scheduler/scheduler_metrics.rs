@@ -106,6 +106,7 @@ pub(super) struct SchedulerMetrics {
     pub(super) canister_aborted_install_code: Histogram,
     pub(super) inducted_messages: IntCounterVec,
     pub(super) ecdsa_signature_agreements: IntGauge,
+    pub(super) schnorr_signature_agreements: IntGaugeVec,
     pub(super) ecdsa_delivered_quadruples: HistogramVec,
     pub(super) ecdsa_completed_contexts: IntCounterVec,
     // TODO(EXC-1466): Remove metric once all calls have `call_id` present.
@@ -222,6 +223,11 @@ impl SchedulerMetrics {
                 "replicated_state_ecdsa_signature_agreements_total",
                 "Total number of ECDSA signature agreements created",
             ),
+            schnorr_signature_agreements: metrics_registry.int_gauge_vec(
+                "replicated_state_schnorr_signature_agreements_total",
+                "Total number of Schnorr signature agreements created by key ID",
+                &["key_id"],
+            ),
             ecdsa_delivered_quadruples: metrics_registry.histogram_vec(
                 "execution_ecdsa_delivered_quadruples",
                 "Number of ECDSA quadruples delivered to execution by key ID",
@@ -473,6 +473,7 @@ impl SchedulerTest {
             state,
             Randomness::from([0; 32]),
             self.ecdsa_subnet_public_keys.clone(),
+            BTreeMap::new(),
             self.ecdsa_quadruple_ids.clone(),
             self.round,
             round_type,
@@ -1081,6 +1082,8 @@ impl TestWasmExecutorCore {
                 allocated_message_bytes: NumBytes::from(0),
                 instance_stats: InstanceStats::default(),
                 system_api_call_counters: SystemApiCallCounters::default(),
+                canister_log: Default::default(),
+                wasm_profile: None,
             };
             self.schedule
                 .push((self.round, canister_id, instructions_to_execute));
@@ -1126,6 +1129,8 @@ impl TestWasmExecutorCore {
             num_instructions_left: instructions_left,
             instance_stats,
             system_api_call_counters: SystemApiCallCounters::default(),
+            canister_log: Default::default(),
+            wasm_profile: None,
         };
         self.schedule
             .push((self.round, canister_id, instructions_to_execute));
@@ -1222,6 +1227,7 @@ impl TestWasmExecutorCore {
                 on_reply: closure.clone(),
                 on_reject: closure,
                 on_cleanup: None,
+                deadline: None,
             })
             .map_err(|err| err.to_string())?;
         let request = Request {
@@ -1232,6 +1238,7 @@ impl TestWasmExecutorCore {
             method_name: "update".into(),
             method_payload: encode_message_id_as_payload(call_message_id),
             metadata: None,
+            deadline: None,
         };
         if let Err(req) = system_state.push_output_request(
             canister_current_memory_usage,
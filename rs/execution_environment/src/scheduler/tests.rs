@@ -17,12 +17,16 @@ use ic_error_types::RejectCode;
 use ic_ic00_types::{
     self as ic00, BoundedHttpHeaders, CanisterHttpResponsePayload, CanisterIdRecord,
     CanisterStatusType, DerivationPath, EcdsaCurve, EmptyBlob, Method, Payload as _,
+    SchnorrAlgorithm,
 };
 use ic_interfaces::execution_environment::SubnetAvailableMemory;
 use ic_logger::replica_logger::no_op_logger;
 use ic_registry_routing_table::CanisterIdRange;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::canister_state::system_state::PausedExecutionId;
+use ic_replicated_state::metadata_state::subnet_call_context_manager::{
+    SignWithSchnorrContext, SubnetCallContext,
+};
 use ic_replicated_state::testing::CanisterQueuesTesting;
 use ic_replicated_state::testing::SystemStateTesting;
 use ic_state_machine_tests::{PayloadBuilder, StateMachineBuilder};
@@ -50,6 +54,7 @@ use ic_types::{
 use ic_types::{time::UNIX_EPOCH, ComputeAllocation, Cycles, NumBytes};
 use ic_types_test_utils::ids::user_test_id;
 use ic_universal_canister::{call_args, wasm, UNIVERSAL_CANISTER_WASM};
+use maplit::btreemap;
 use proptest::prelude::*;
 use std::collections::HashMap;
 use std::{cmp::min, ops::Range};
@@ -3049,6 +3054,7 @@ fn ecdsa_signature_agreements_metric_is_updated() {
         originator_reply_callback: *callback_id,
         refund: context.request.payment,
         response_payload: Payload::Reject(RejectContext::new(RejectCode::SysFatal, "")),
+        deadline: None,
     };
 
     test.state_mut().consensus_queue.push(response);
@@ -3097,6 +3103,7 @@ fn ecdsa_signature_agreements_metric_is_updated() {
             }
             .encode(),
         ),
+        deadline: None,
     };
 
     test.state_mut().consensus_queue.push(response);
@@ -3136,6 +3143,112 @@ fn ecdsa_signature_agreements_metric_is_updated() {
     assert!(sign_with_ecdsa_contexts.is_empty());
 }
 
+#[test]
+fn schnorr_signature_agreements_metric_is_updated_per_key() {
+    let key_1 = SchnorrKeyId {
+        algorithm: SchnorrAlgorithm::Bip340Secp256k1,
+        name: String::from("key_1"),
+    };
+    let key_2 = SchnorrKeyId {
+        algorithm: SchnorrAlgorithm::Bip340Secp256k1,
+        name: String::from("key_2"),
+    };
+    let mut test = SchedulerTestBuilder::new().build();
+    let canister_id = test.create_canister();
+
+    // Two requests for `key_1` and one for `key_2`.
+    let mut callback_ids = vec![];
+    for key_id in [&key_1, &key_1, &key_2] {
+        let request = RequestBuilder::default()
+            .sender(canister_id)
+            .receiver(ic_types::CanisterId::ic_00())
+            .method_name(Method::SignWithSchnorr)
+            .build();
+        let callback_id = test
+            .state_mut()
+            .metadata
+            .subnet_call_context_manager
+            .push_context(SubnetCallContext::SignWithSchnorr(SignWithSchnorrContext {
+                request,
+                key_id: key_id.clone(),
+                message: vec![0; 32],
+                derivation_path: vec![],
+                pseudo_random_id: [0; 32],
+                batch_time: mock_time(),
+            }));
+        callback_ids.push(callback_id);
+    }
+
+    // Reply to the first request of each key and reject the second `key_1` one.
+    for (callback_id, response_payload) in [
+        (
+            callback_ids[0],
+            Payload::Data(
+                ic00::SignWithSchnorrReply {
+                    signature: vec![1, 2, 3],
+                }
+                .encode(),
+            ),
+        ),
+        (
+            callback_ids[1],
+            Payload::Reject(RejectContext::new(RejectCode::SysFatal, "")),
+        ),
+        (
+            callback_ids[2],
+            Payload::Data(
+                ic00::SignWithSchnorrReply {
+                    signature: vec![4, 5, 6],
+                }
+                .encode(),
+            ),
+        ),
+    ] {
+        let response = Response {
+            originator: canister_id,
+            respondent: ic_types::CanisterId::ic_00(),
+            originator_reply_callback: callback_id,
+            refund: Cycles::zero(),
+            response_payload,
+            deadline: None,
+        };
+        test.state_mut().consensus_queue.push(response);
+    }
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+
+    observe_replicated_state_metrics(
+        test.scheduler().own_subnet_id,
+        test.state(),
+        1.into(),
+        &test.scheduler().metrics,
+        &no_op_logger(),
+    );
+
+    assert_eq!(
+        test.state()
+            .metadata
+            .subnet_metrics
+            .schnorr_signature_agreements,
+        btreemap! { key_1.clone() => 1, key_2.clone() => 1 }
+    );
+    assert_eq!(
+        fetch_int_gauge_vec(
+            test.metrics_registry(),
+            "replicated_state_schnorr_signature_agreements_total",
+        ),
+        metric_vec(&[
+            (&[("key_id", key_1.to_string().as_str())], 1),
+            (&[("key_id", key_2.to_string().as_str())], 1),
+        ]),
+    );
+    assert!(test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .sign_with_schnorr_contexts
+        .is_empty());
+}
+
 #[test]
 fn consumed_cycles_ecdsa_outcalls_are_added_to_consumed_cycles_total() {
     let ecdsa_key = EcdsaKeyId {
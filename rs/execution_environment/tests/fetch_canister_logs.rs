@@ -2,8 +2,9 @@ use ic_config::execution_environment::Config as ExecutionConfig;
 use ic_config::flag_status::FlagStatus;
 use ic_config::subnet_config::SubnetConfig;
 use ic_ic00_types::{
-    CanisterInstallMode, CanisterSettingsArgsBuilder, FetchCanisterLogsRequest,
-    FetchCanisterLogsResponse, LogVisibility, Payload,
+    CanisterInstallMode, CanisterLogContentFilter, CanisterSettingsArgsBuilder,
+    FetchCanisterLogsRange, FetchCanisterLogsRequest, FetchCanisterLogsResponse, LogVisibility,
+    Payload,
 };
 use ic_registry_subnet_type::SubnetType;
 use ic_state_machine_tests::{
@@ -13,6 +14,8 @@ use ic_state_machine_tests::{
 use ic_test_utilities::universal_canister::UNIVERSAL_CANISTER_WASM;
 use ic_test_utilities_execution_environment::get_reply;
 use ic_types::{CanisterId, Cycles};
+use ic_universal_canister::wasm;
+use std::time::Duration;
 
 fn setup(fetch_canister_logs: FlagStatus) -> (StateMachine, CanisterId) {
     let subnet_type = SubnetType::Application;
@@ -60,10 +63,7 @@ fn test_fetch_canister_logs_disabled_submit_ingress_fails() {
         not_a_controller,
         CanisterId::ic_00(),
         "fetch_canister_logs",
-        FetchCanisterLogsRequest {
-            canister_id: canister_id.into(),
-        }
-        .encode(),
+        FetchCanisterLogsRequest::new(canister_id).encode(),
     );
     // Assert.
     // Expect to get an error because the fetch_canister_logs API is disabled,
@@ -97,10 +97,7 @@ fn test_fetch_canister_logs_disabled_execute_ingress_fails() {
         not_a_controller,
         CanisterId::ic_00(),
         "fetch_canister_logs",
-        FetchCanisterLogsRequest {
-            canister_id: canister_id.into(),
-        }
-        .encode(),
+        FetchCanisterLogsRequest::new(canister_id).encode(),
     );
     // Assert.
     // Expect to get an error because the fetch_canister_logs API is disabled,
@@ -133,10 +130,7 @@ fn test_fetch_canister_logs_enabled_submit_ingress_rejected() {
         not_a_controller,
         CanisterId::ic_00(),
         "fetch_canister_logs",
-        FetchCanisterLogsRequest {
-            canister_id: canister_id.into(),
-        }
-        .encode(),
+        FetchCanisterLogsRequest::new(canister_id).encode(),
     );
     // Assert.
     // Expect error because an update calls are not allowed.
@@ -169,10 +163,7 @@ fn test_fetch_canister_logs_enabled_execute_ingress_rejected() {
         not_a_controller,
         CanisterId::ic_00(),
         "fetch_canister_logs",
-        FetchCanisterLogsRequest {
-            canister_id: canister_id.into(),
-        }
-        .encode(),
+        FetchCanisterLogsRequest::new(canister_id).encode(),
     );
     // Assert.
     // Expect error because an update calls are not allowed.
@@ -205,10 +196,7 @@ fn test_fetch_canister_logs_disabled_query_fails() {
         not_a_controller,
         CanisterId::ic_00(),
         "fetch_canister_logs",
-        FetchCanisterLogsRequest {
-            canister_id: canister_id.into(),
-        }
-        .encode(),
+        FetchCanisterLogsRequest::new(canister_id).encode(),
     );
     // Assert.
     // Expect to get an error because the fetch_canister_logs API is disabled,
@@ -242,17 +230,15 @@ fn test_fetch_canister_logs_enabled_query_log_visibility_public_succeeds() {
         not_a_controller,
         CanisterId::ic_00(),
         "fetch_canister_logs",
-        FetchCanisterLogsRequest {
-            canister_id: canister_id.into(),
-        }
-        .encode(),
+        FetchCanisterLogsRequest::new(canister_id).encode(),
     );
     // Assert.
     // Expect some non-empty result.
     assert_eq!(
         FetchCanisterLogsResponse::decode(&get_reply(result)).unwrap(),
         FetchCanisterLogsResponse {
-            canister_log_records: vec![]
+            canister_log_records: vec![],
+            has_more: false,
         }
     );
 }
@@ -277,10 +263,7 @@ fn test_fetch_canister_logs_enabled_query_log_visibility_invalid_controller_fail
         not_a_controller,
         CanisterId::ic_00(),
         "fetch_canister_logs",
-        FetchCanisterLogsRequest {
-            canister_id: canister_id.into(),
-        }
-        .encode(),
+        FetchCanisterLogsRequest::new(canister_id).encode(),
     );
     // Assert.
     // Expect an error because the caller is not a controller.
@@ -317,17 +300,153 @@ fn test_fetch_canister_logs_enabled_query_log_visibility_valid_controller_succee
         new_controller,
         CanisterId::ic_00(),
         "fetch_canister_logs",
-        FetchCanisterLogsRequest {
-            canister_id: canister_id.into(),
-        }
-        .encode(),
+        FetchCanisterLogsRequest::new(canister_id).encode(),
     );
     // Assert.
     // Expect some non-empty result.
     assert_eq!(
         FetchCanisterLogsResponse::decode(&get_reply(result)).unwrap(),
         FetchCanisterLogsResponse {
-            canister_log_records: vec![]
+            canister_log_records: vec![],
+            has_more: false,
         }
     );
 }
+
+#[test]
+fn test_fetch_canister_logs_returns_filtered_records_of_logging_canister() {
+    // Arrange.
+    // - enable the fetch_canister_logs API
+    // - set the log visibility to public so that any user can read the logs
+    // - let the canister log a few messages in separate rounds
+    let (env, canister_id) = setup(FlagStatus::Enabled);
+    let not_a_controller = PrincipalId::new_user_test_id(42);
+    env.update_settings(
+        &canister_id,
+        CanisterSettingsArgsBuilder::new()
+            .with_log_visibility(LogVisibility::Public)
+            .build(),
+    )
+    .unwrap();
+    let messages: [&[u8]; 3] = [b"info: started", b"error: failed", b"info: retried"];
+    for message in messages {
+        env.execute_ingress(
+            canister_id,
+            "update",
+            wasm().debug_print(message).reply().build(),
+        )
+        .unwrap();
+        env.advance_time(Duration::from_secs(1));
+    }
+    let fetch_logs = |request: FetchCanisterLogsRequest| {
+        let result = env.query_as(
+            not_a_controller,
+            CanisterId::ic_00(),
+            "fetch_canister_logs",
+            request.encode(),
+        );
+        FetchCanisterLogsResponse::decode(&get_reply(result)).unwrap()
+    };
+
+    // Act.
+    let all_records = fetch_logs(FetchCanisterLogsRequest::new(canister_id));
+
+    // Assert.
+    // Expect all messages in order, with consecutive indices and increasing timestamps.
+    assert!(!all_records.has_more);
+    let records = &all_records.canister_log_records;
+    assert_eq!(
+        records
+            .iter()
+            .map(|record| record.content.as_slice())
+            .collect::<Vec<_>>(),
+        messages.to_vec()
+    );
+    assert_eq!(
+        records.iter().map(|record| record.idx).collect::<Vec<_>>(),
+        vec![0, 1, 2]
+    );
+    assert!(records[0].timestamp_nanos < records[1].timestamp_nanos);
+    assert!(records[1].timestamp_nanos < records[2].timestamp_nanos);
+
+    // Filter by content.
+    let response = fetch_logs(FetchCanisterLogsRequest {
+        content_filter: Some(CanisterLogContentFilter::Prefix(b"info".to_vec())),
+        ..FetchCanisterLogsRequest::new(canister_id)
+    });
+    assert_eq!(
+        response.canister_log_records,
+        vec![records[0].clone(), records[2].clone()]
+    );
+
+    // Filter by timestamp range.
+    let response = fetch_logs(FetchCanisterLogsRequest {
+        timestamp_nanos_range: Some(FetchCanisterLogsRange::new(
+            records[1].timestamp_nanos,
+            records[2].timestamp_nanos,
+        )),
+        ..FetchCanisterLogsRequest::new(canister_id)
+    });
+    assert_eq!(response.canister_log_records, vec![records[1].clone()]);
+
+    // Paginate by index.
+    let response = fetch_logs(FetchCanisterLogsRequest {
+        idx_range: Some(FetchCanisterLogsRange::new(1, u64::MAX)),
+        max_records: Some(1),
+        ..FetchCanisterLogsRequest::new(canister_id)
+    });
+    assert_eq!(response.canister_log_records, vec![records[1].clone()]);
+    assert!(response.has_more);
+}
+
+#[test]
+fn test_fetch_canister_logs_returns_records_of_trapping_update_call() {
+    // Arrange.
+    // - enable the fetch_canister_logs API
+    // - set the log visibility to public so that any user can read the logs
+    // - let the canister log a message and then trap
+    let (env, canister_id) = setup(FlagStatus::Enabled);
+    let not_a_controller = PrincipalId::new_user_test_id(42);
+    env.update_settings(
+        &canister_id,
+        CanisterSettingsArgsBuilder::new()
+            .with_log_visibility(LogVisibility::Public)
+            .build(),
+    )
+    .unwrap();
+    env.execute_ingress(
+        canister_id,
+        "update",
+        wasm().debug_print(b"before trap").reply().build(),
+    )
+    .unwrap();
+    let result = env.execute_ingress(
+        canister_id,
+        "update",
+        wasm().debug_print(b"about to trap").trap().build(),
+    );
+    assert_eq!(result.unwrap_err().code(), ErrorCode::CanisterCalledTrap);
+
+    // Act.
+    let result = env.query_as(
+        not_a_controller,
+        CanisterId::ic_00(),
+        "fetch_canister_logs",
+        FetchCanisterLogsRequest::new(canister_id).encode(),
+    );
+
+    // Assert.
+    // Expect the message logged by the trapping call to be kept.
+    let response = FetchCanisterLogsResponse::decode(&get_reply(result)).unwrap();
+    assert_eq!(
+        response
+            .canister_log_records
+            .iter()
+            .map(|record| (record.idx, record.content.as_slice()))
+            .collect::<Vec<_>>(),
+        vec![
+            (0, b"before trap".as_slice()),
+            (1, b"about to trap".as_slice())
+        ]
+    );
+}
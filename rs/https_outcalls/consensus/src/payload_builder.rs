@@ -703,6 +703,7 @@ impl IntoMessages<(Vec<Response>, CanisterHttpBatchStats)> for CanisterHttpPaylo
                 originator_reply_callback: id,
                 refund: Cycles::zero(),
                 response_payload: response,
+                deadline: None,
             })
             .collect();
 
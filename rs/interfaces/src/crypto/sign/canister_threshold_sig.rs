@@ -36,7 +36,9 @@ use ic_types::crypto::canister_threshold_sig::error::{
     IDkgVerifyDealingPrivateError, IDkgVerifyDealingPublicError, IDkgVerifyInitialDealingsError,
     IDkgVerifyOpeningError, IDkgVerifyTranscriptError, ThresholdEcdsaCombineSigSharesError,
     ThresholdEcdsaSignShareError, ThresholdEcdsaVerifyCombinedSignatureError,
-    ThresholdEcdsaVerifySigShareError,
+    ThresholdEcdsaVerifySigShareError, ThresholdSchnorrCombineSigSharesError,
+    ThresholdSchnorrSignShareError, ThresholdSchnorrVerifyCombinedSignatureError,
+    ThresholdSchnorrVerifySigShareError,
 };
 use ic_types::crypto::canister_threshold_sig::idkg::{
     BatchSignedIDkgDealings, IDkgComplaint, IDkgOpening, IDkgTranscript, IDkgTranscriptParams,
@@ -44,6 +46,7 @@ use ic_types::crypto::canister_threshold_sig::idkg::{
 };
 use ic_types::crypto::canister_threshold_sig::{
     ThresholdEcdsaCombinedSignature, ThresholdEcdsaSigInputs, ThresholdEcdsaSigShare,
+    ThresholdSchnorrCombinedSignature, ThresholdSchnorrSigInputs, ThresholdSchnorrSigShare,
 };
 use std::collections::{BTreeMap, HashSet};
 
@@ -615,3 +618,96 @@ pub trait ThresholdEcdsaSigVerifier {
         signature: &ThresholdEcdsaCombinedSignature,
     ) -> Result<(), ThresholdEcdsaVerifyCombinedSignatureError>;
 }
+
+/// A Crypto Component interface to generate threshold Schnorr signature shares during the
+/// online phase of the threshold Schnorr protocol. During the offline phase, nodes precompute
+/// pre-signatures, each consisting of a single unmasked IDKG transcript of a random value.
+/// Each pre-signature is then used by the nodes to reply to a single signing request, and
+/// then it is discarded.
+///
+/// # Protocol Overview:
+/// Like for threshold ECDSA, the threshold signing protocol is non-interactive. Shares can be
+/// publicly verified by anybody and combined into a single Schnorr signature.
+pub trait ThresholdSchnorrSigner {
+    /// Create a threshold Schnorr signature share.
+    ///
+    /// # Prerequisites
+    /// This method depends on the key material for the IDKG transcripts specified in
+    /// `ThresholdSchnorrSigInputs` to be present in the canister secret key store of the
+    /// crypto component. To initialize this key material the transcripts must be loaded
+    /// using the method [`IDkgProtocol::load_transcript`].
+    ///
+    /// # Errors
+    /// * [`ThresholdSchnorrSignShareError::InternalError`] if there was an internal error creating the
+    ///   signature share, likely due to invalid input.
+    /// * [`ThresholdSchnorrSignShareError::NotAReceiver`] if the caller isn't in the
+    ///   transcripts' receivers. Only receivers can create signature shares.
+    /// * [`ThresholdSchnorrSignShareError::SerializationError`] if there was an error deserializing the
+    ///   transcripts or serializing the signature share.
+    /// * [`ThresholdSchnorrSignShareError::SecretSharesNotFound`] if the secret shares necessary
+    ///   for creating the signature share could not be found in the canister secret key store.
+    ///   Calling [`IDkgProtocol::load_transcript`] may be necessary.
+    /// * [`ThresholdSchnorrSignShareError::TransientInternalError`] if there was a transient internal
+    ///   error, e.g., when communicating with the remote CSP vault.
+    fn sign_share(
+        &self,
+        inputs: &ThresholdSchnorrSigInputs,
+    ) -> Result<ThresholdSchnorrSigShare, ThresholdSchnorrSignShareError>;
+}
+
+/// A Crypto Component interface to perform public operations during the online phase of the
+/// threshold Schnorr protocol. These interfaces can be used to verify signature shares, combine
+/// them into a single Schnorr signature, and verify the combined signature.
+pub trait ThresholdSchnorrSigVerifier {
+    /// Verify a threshold Schnorr signature share.
+    ///
+    /// # Errors
+    /// * [`ThresholdSchnorrVerifySigShareError::InternalError`] if there was an internal error while
+    ///   verifying the signature share, likely due to invalid input.
+    /// * [`ThresholdSchnorrVerifySigShareError::SerializationError`] if there was an error deserializing the
+    ///   transcripts or the signature share.
+    /// * [`ThresholdSchnorrVerifySigShareError::InvalidSignatureShare`] if the signature share is not
+    ///   valid.
+    /// * [`ThresholdSchnorrVerifySigShareError::InvalidArgumentMissingSignerInTranscript`] if the signer
+    ///   was not eligible according to the key transcript.
+    fn verify_sig_share(
+        &self,
+        signer: NodeId,
+        inputs: &ThresholdSchnorrSigInputs,
+        share: &ThresholdSchnorrSigShare,
+    ) -> Result<(), ThresholdSchnorrVerifySigShareError>;
+
+    /// Combine the given threshold Schnorr signature shares into a conventional Schnorr signature.
+    ///
+    /// The signature is returned as raw bytes.
+    ///
+    /// # Errors
+    /// * [`ThresholdSchnorrCombineSigSharesError::InternalError`] if there was an internal error while
+    ///   combining the signature shares, likely due to invalid input.
+    /// * [`ThresholdSchnorrCombineSigSharesError::UnsatisfiedReconstructionThreshold`] if the number
+    ///   of signature shares was not sufficient to reconstruct a Schnorr signature.
+    /// * [`ThresholdSchnorrCombineSigSharesError::SerializationError`] if there was an error deserializing the
+    ///   transcripts or the signature shares.
+    /// * [`ThresholdSchnorrCombineSigSharesError::SignerNotAllowed`] if one or more of the signers were
+    ///   not eligible according to the key transcript.
+    fn combine_sig_shares(
+        &self,
+        inputs: &ThresholdSchnorrSigInputs,
+        shares: &BTreeMap<NodeId, ThresholdSchnorrSigShare>,
+    ) -> Result<ThresholdSchnorrCombinedSignature, ThresholdSchnorrCombineSigSharesError>;
+
+    /// Verify a combined Schnorr signature and its consistency with the input. In particular it
+    /// verifies that the signature is computed from the pre-signature transcript of the input.
+    ///
+    /// # Errors
+    /// * [`ThresholdSchnorrVerifyCombinedSignatureError::InternalError`] if there was an internal error while
+    ///   verifying the combined signature, likely due to invalid input.
+    /// * [`ThresholdSchnorrVerifyCombinedSignatureError::InvalidSignature`] if the signature is not valid.
+    /// * [`ThresholdSchnorrVerifyCombinedSignatureError::SerializationError`] if there was an error deserializing the
+    ///   transcripts or the signature.
+    fn verify_combined_sig(
+        &self,
+        inputs: &ThresholdSchnorrSigInputs,
+        signature: &ThresholdSchnorrCombinedSignature,
+    ) -> Result<(), ThresholdSchnorrVerifyCombinedSignatureError>;
+}
@@ -3,6 +3,7 @@
 use ic_types::artifact::EcdsaMessageId;
 use ic_types::consensus::ecdsa::{
     EcdsaComplaint, EcdsaMessage, EcdsaOpening, EcdsaPrefixOf, EcdsaSigShare, EcdsaStats,
+    SchnorrSigShare,
 };
 use ic_types::crypto::canister_threshold_sig::idkg::{IDkgDealingSupport, SignedIDkgDealing};
 
@@ -108,6 +109,19 @@ pub trait EcdsaPoolSection: Send + Sync {
     ) -> Box<dyn Iterator<Item = (EcdsaMessageId, EcdsaOpening)> + '_> {
         unimplemented!()
     }
+
+    /// Iterator for threshold Schnorr signature share objects.
+    fn schnorr_signature_shares(
+        &self,
+    ) -> Box<dyn Iterator<Item = (EcdsaMessageId, SchnorrSigShare)> + '_>;
+
+    /// Iterator for threshold Schnorr signature share objects matching the prefix.
+    fn schnorr_signature_shares_by_prefix(
+        &self,
+        _prefix: EcdsaPrefixOf<SchnorrSigShare>,
+    ) -> Box<dyn Iterator<Item = (EcdsaMessageId, SchnorrSigShare)> + '_> {
+        unimplemented!()
+    }
 }
 
 /// The mutable interface for validated/unvalidated parts of the artifact pool.
@@ -4,14 +4,15 @@ mod errors;
 pub use errors::{CanisterOutOfCyclesError, HypervisorError, TrapCode};
 use ic_base_types::NumBytes;
 use ic_error_types::UserError;
-use ic_ic00_types::EcdsaKeyId;
+use ic_ic00_types::{EcdsaKeyId, SchnorrKeyId};
 use ic_interfaces_state_manager::Labeled;
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_subnet_type::SubnetType;
 use ic_sys::{PageBytes, PageIndex};
 use ic_types::{
+    canister_log::CanisterLog,
     consensus::ecdsa::QuadrupleId,
-    crypto::canister_threshold_sig::MasterEcdsaPublicKey,
+    crypto::canister_threshold_sig::{MasterEcdsaPublicKey, MasterSchnorrPublicKey},
     ingress::{IngressStatus, WasmResult},
     messages::{
         AnonymousQuery, AnonymousQueryResponse, CertificateDelegation, HttpQueryResponse,
@@ -101,6 +102,8 @@ pub enum SystemApiCallId {
     CallOnCleanup,
     /// Tracker for `ic0.call_perform()`
     CallPerform,
+    /// Tracker for `ic0.call_with_best_effort_response()`
+    CallWithBestEffortResponse,
     /// Tracker for `ic0.canister_cycle_balance()`
     CanisterCycleBalance,
     /// Tracker for `ic0.canister_cycle_balance128()`
@@ -115,6 +118,16 @@ pub enum SystemApiCallId {
     CanisterVersion,
     /// Tracker for `ic0.certified_data_set()`
     CertifiedDataSet,
+    /// Tracker for `ic0.cost_call()`
+    CostCall,
+    /// Tracker for `ic0.cost_create_canister()`
+    CostCreateCanister,
+    /// Tracker for `ic0.cost_http_request()`
+    CostHttpRequest,
+    /// Tracker for `ic0.cost_sign_with_ecdsa()`
+    CostSignWithEcdsa,
+    /// Tracker for `ic0.cost_sign_with_schnorr()`
+    CostSignWithSchnorr,
     /// Tracker for `ic0.cycles_burn128()`
     CyclesBurn128,
     /// Tracker for `ic0.data_certificate_copy()`
@@ -151,6 +164,8 @@ pub enum SystemApiCallId {
     MsgCyclesRefunded,
     /// Tracker for `ic0.msg_cycles_refunded128()`
     MsgCyclesRefunded128,
+    /// Tracker for `ic0.msg_deadline()`
+    MsgDeadline,
     /// Tracker for `ic0.msg_method_name_copy()`
     MsgMethodNameCopy,
     /// Tracker for `ic0.msg_method_name_size()`
@@ -171,6 +186,10 @@ pub enum SystemApiCallId {
     OutOfInstructions,
     /// Tracker for `ic0.performance_counter()`
     PerformanceCounter,
+    /// Tracker for `ic0.root_key_copy()`
+    RootKeyCopy,
+    /// Tracker for `ic0.root_key_size()`
+    RootKeySize,
     /// Tracker for `ic0.stable64_grow()`
     Stable64Grow,
     /// Tracker for `ic0.stable64_read()`
@@ -187,6 +206,10 @@ pub enum SystemApiCallId {
     StableSize,
     /// Tracker for `ic0.stable_write())`
     StableWrite,
+    /// Tracker for `ic0.subnet_self_copy()`
+    SubnetSelfCopy,
+    /// Tracker for `ic0.subnet_self_size()`
+    SubnetSelfSize,
     /// Tracker for `ic0.time()`
     Time,
     /// Tracker for `ic0.trap()`
@@ -590,9 +613,9 @@ pub trait SystemApi {
     /// id in case of requests or the user id in case of an ingress message.
     fn ic0_msg_caller_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: usize,
+        offset: usize,
+        size: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
 
@@ -606,9 +629,9 @@ pub trait SystemApi {
     /// memory[dst..dst+size].
     fn ic0_msg_arg_data_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: usize,
+        offset: usize,
+        size: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
 
@@ -620,9 +643,9 @@ pub trait SystemApi {
     /// only be called in the context of inspecting messages.
     fn ic0_msg_method_name_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: usize,
+        offset: usize,
+        size: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
 
@@ -635,8 +658,8 @@ pub trait SystemApi {
     /// it to the (initially empty) data reply.
     fn ic0_msg_reply_data_append(
         &mut self,
-        src: u32,
-        size: u32,
+        src: usize,
+        size: usize,
         heap: &[u8],
     ) -> HypervisorResult<()>;
 
@@ -651,8 +674,15 @@ pub trait SystemApi {
     /// as a reject callback
     fn ic0_msg_reject_code(&self) -> HypervisorResult<i32>;
 
+    /// Returns the deadline of the current best-effort call, in nanoseconds
+    /// since the Unix epoch.
+    ///
+    /// It returns 0 if the current message is an ingress message or a
+    /// guaranteed response call.
+    fn ic0_msg_deadline(&self) -> HypervisorResult<u64>;
+
     /// Replies to sender with an error message
-    fn ic0_msg_reject(&mut self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()>;
+    fn ic0_msg_reject(&mut self, src: usize, size: usize, heap: &[u8]) -> HypervisorResult<()>;
 
     /// Returns the length of the reject message in bytes.
     ///
@@ -671,9 +701,9 @@ pub trait SystemApi {
     /// called from inside a reject callback.
     fn ic0_msg_reject_msg_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: usize,
+        offset: usize,
+        size: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
 
@@ -684,17 +714,51 @@ pub trait SystemApi {
     /// canister to heap[dst..dst+size].
     fn ic0_canister_self_copy(
         &mut self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: usize,
+        offset: usize,
+        size: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()>;
+
+    /// Returns the size of the blob corresponding to the id of the subnet on
+    /// which the canister is running.
+    fn ic0_subnet_self_size(&self) -> HypervisorResult<usize>;
+
+    /// Copies `size` bytes starting from `offset` in the id blob of the
+    /// subnet on which the canister is running to heap[dst..dst+size].
+    fn ic0_subnet_self_copy(
+        &mut self,
+        dst: usize,
+        offset: usize,
+        size: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()>;
+
+    /// Returns the size of the DER-encoded public key of the IC root of trust.
+    fn ic0_root_key_size(&self) -> HypervisorResult<usize>;
+
+    /// Copies `size` bytes starting from `offset` in the DER-encoded public
+    /// key of the IC root of trust to heap[dst..dst+size].
+    ///
+    /// This traps if offset+size is greater than the size of the root key, or
+    /// if dst+size exceeds the size of the Wasm memory.
+    fn ic0_root_key_copy(
+        &mut self,
+        dst: usize,
+        offset: usize,
+        size: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
 
     /// Outputs the specified bytes on the heap as a string on STDOUT.
-    fn ic0_debug_print(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()>;
+    fn ic0_debug_print(&self, src: usize, size: usize, heap: &[u8]) -> HypervisorResult<()>;
+
+    /// Saves the specified bytes on the heap as a record in the canister log.
+    /// Like `ic0.debug_print`, this never fails.
+    fn save_log_message(&mut self, src: usize, size: usize, heap: &[u8]);
 
     /// Traps, with a possibly helpful message
-    fn ic0_trap(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()>;
+    fn ic0_trap(&self, src: usize, size: usize, heap: &[u8]) -> HypervisorResult<()>;
 
     /// Begins assembling a call to the canister specified by
     /// callee_src/callee_size at method name_src/name_size. Two mandatory
@@ -708,10 +772,10 @@ pub trait SystemApi {
     #[allow(clippy::too_many_arguments)]
     fn ic0_call_new(
         &mut self,
-        callee_src: u32,
-        callee_size: u32,
-        name_src: u32,
-        name_len: u32,
+        callee_src: usize,
+        callee_size: usize,
+        name_src: usize,
+        name_len: usize,
         reply_fun: u32,
         reply_env: u32,
         reject_fun: u32,
@@ -722,7 +786,12 @@ pub trait SystemApi {
     /// Appends the specified bytes to the argument of the call. Initially, the
     /// argument is empty. This can be called multiple times between
     /// `ic0.call_new` and `ic0.call_perform`.
-    fn ic0_call_data_append(&mut self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()>;
+    fn ic0_call_data_append(
+        &mut self,
+        src: usize,
+        size: usize,
+        heap: &[u8],
+    ) -> HypervisorResult<()>;
 
     /// Specifies the closure to be called if the reply/reject closures trap.
     /// Can be called at most once between `ic0.call_new` and
@@ -731,6 +800,11 @@ pub trait SystemApi {
     /// See <https://sdk.dfinity.org/docs/interface-spec/index.html#system-api-call>
     fn ic0_call_on_cleanup(&mut self, fun: u32, env: u32) -> HypervisorResult<()>;
 
+    /// Turns the call under construction into a best-effort call with the
+    /// given timeout (capped to a system-defined maximum). Can be called at
+    /// most once between `ic0.call_new` and `ic0.call_perform`.
+    fn ic0_call_with_best_effort_response(&mut self, timeout_seconds: u32) -> HypervisorResult<()>;
+
     /// (deprecated) Please use `ic0_call_cycles_add128` instead, as this API
     /// can only add a 64-bit value.
     ///
@@ -948,7 +1022,11 @@ pub trait SystemApi {
     /// The amount of cycles is represented by a 128-bit value
     /// and is copied in the canister memory starting
     /// starting at the location `dst`.
-    fn ic0_canister_cycle_balance128(&mut self, dst: u32, heap: &mut [u8]) -> HypervisorResult<()>;
+    fn ic0_canister_cycle_balance128(
+        &mut self,
+        dst: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()>;
 
     /// (deprecated) Please use `ic0_msg_cycles_available128` instead.
     /// This API supports only 64-bit values.
@@ -964,7 +1042,7 @@ pub trait SystemApi {
     /// The amount of cycles is represented by a 128-bit value
     /// and is copied in the canister memory starting
     /// starting at the location `dst`.
-    fn ic0_msg_cycles_available128(&self, dst: u32, heap: &mut [u8]) -> HypervisorResult<()>;
+    fn ic0_msg_cycles_available128(&self, dst: usize, heap: &mut [u8]) -> HypervisorResult<()>;
 
     /// (deprecated) Please use `ic0_msg_cycles_refunded128` instead.
     /// This API supports only 64-bit values.
@@ -980,7 +1058,7 @@ pub trait SystemApi {
     /// The amount of cycles is represented by a 128-bit value
     /// and is copied in the canister memory starting
     /// starting at the location `dst`.
-    fn ic0_msg_cycles_refunded128(&self, dst: u32, heap: &mut [u8]) -> HypervisorResult<()>;
+    fn ic0_msg_cycles_refunded128(&self, dst: usize, heap: &mut [u8]) -> HypervisorResult<()>;
 
     /// (deprecated) Please use `ic0_msg_cycles_accept128` instead.
     /// This API supports only 64-bit values.
@@ -1026,13 +1104,18 @@ pub trait SystemApi {
     fn ic0_msg_cycles_accept128(
         &mut self,
         max_amount: Cycles,
-        dst: u32,
+        dst: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
 
     /// Sets the certified data for the canister.
     /// See: <https://sdk.dfinity.org/docs/interface-spec/index.html#system-api-certified-data>
-    fn ic0_certified_data_set(&mut self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()>;
+    fn ic0_certified_data_set(
+        &mut self,
+        src: usize,
+        size: usize,
+        heap: &[u8],
+    ) -> HypervisorResult<()>;
 
     /// If run in non-replicated execution (i.e. query),
     /// returns 1 if the data certificate is present, 0 otherwise.
@@ -1050,9 +1133,9 @@ pub trait SystemApi {
     /// Traps if data_certificate_present returns 0.
     fn ic0_data_certificate_copy(
         &mut self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: usize,
+        offset: usize,
+        size: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
 
@@ -1077,7 +1160,7 @@ pub trait SystemApi {
     /// otherwise a 0 is returned. It can be called multiple times.
     ///
     /// This system call traps if src+size exceeds the size of the WebAssembly memory.
-    fn ic0_is_controller(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<u32>;
+    fn ic0_is_controller(&self, src: usize, size: usize, heap: &[u8]) -> HypervisorResult<u32>;
 
     /// If run in replicated execution (i.e. an update call or a certified
     /// query), returns 1.
@@ -1095,9 +1178,78 @@ pub trait SystemApi {
     fn ic0_cycles_burn128(
         &mut self,
         amount: Cycles,
-        dst: u32,
+        dst: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
+
+    /// Computes the amount of cycles that `ic0.call_perform` would withdraw
+    /// for a call with a method name of `method_name_size` bytes and a
+    /// payload of `payload_size` bytes.
+    ///
+    /// The amount of cycles is represented by a 128-bit value
+    /// and is copied in the canister memory starting
+    /// at the location `dst`.
+    fn ic0_cost_call(
+        &self,
+        method_name_size: u64,
+        payload_size: u64,
+        dst: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()>;
+
+    /// Computes the fee for creating a canister on this subnet.
+    ///
+    /// The amount of cycles is represented by a 128-bit value
+    /// and is copied in the canister memory starting
+    /// at the location `dst`.
+    fn ic0_cost_create_canister(&self, dst: usize, heap: &mut [u8]) -> HypervisorResult<()>;
+
+    /// Computes the fee for an HTTPS outcall with a request of `request_size`
+    /// bytes and a response limited to `max_res_bytes` bytes.
+    ///
+    /// The amount of cycles is represented by a 128-bit value
+    /// and is copied in the canister memory starting
+    /// at the location `dst`.
+    fn ic0_cost_http_request(
+        &self,
+        request_size: u64,
+        max_res_bytes: u64,
+        dst: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()>;
+
+    /// Computes the fee for signing with the ECDSA key whose name is given
+    /// by `src`/`size` on the curve `ecdsa_curve` (0 for secp256k1).
+    ///
+    /// On success, the amount of cycles is copied as a 128-bit value to the
+    /// canister memory at the location `dst` and 0 is returned. Returns 1 if
+    /// the curve is unknown and 2 if no such key is available, in which case
+    /// nothing is copied.
+    fn ic0_cost_sign_with_ecdsa(
+        &self,
+        src: usize,
+        size: usize,
+        ecdsa_curve: u32,
+        dst: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<u32>;
+
+    /// Computes the fee for signing with the Schnorr key whose name is given
+    /// by `src`/`size` for the algorithm `algorithm` (0 for bip340secp256k1,
+    /// 1 for ed25519).
+    ///
+    /// On success, the amount of cycles is copied as a 128-bit value to the
+    /// canister memory at the location `dst` and 0 is returned. Returns 1 if
+    /// the algorithm is unknown and 2 if no such key is available, in which
+    /// case nothing is copied.
+    fn ic0_cost_sign_with_schnorr(
+        &self,
+        src: usize,
+        size: usize,
+        algorithm: u32,
+        dst: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<u32>;
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -1114,6 +1266,7 @@ pub struct RegistryExecutionSettings {
     pub max_number_of_canisters: u64,
     pub provisional_whitelist: ProvisionalWhitelist,
     pub max_ecdsa_queue_size: u32,
+    pub max_schnorr_queue_size: u32,
     pub quadruples_to_create_in_advance: u32,
     pub subnet_size: usize,
 }
@@ -1173,6 +1326,7 @@ pub trait Scheduler: Send {
         state: Self::State,
         randomness: Randomness,
         ecdsa_subnet_public_keys: BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+        schnorr_subnet_public_keys: BTreeMap<SchnorrKeyId, MasterSchnorrPublicKey>,
         ecdsa_quadruple_ids: BTreeMap<EcdsaKeyId, BTreeSet<QuadrupleId>>,
         current_round: ExecutionRound,
         current_round_type: ExecutionRoundType,
@@ -1203,6 +1357,86 @@ pub struct WasmExecutionOutput {
     pub instance_stats: InstanceStats,
     /// How many times each tracked System API call was invoked.
     pub system_api_call_counters: SystemApiCallCounters,
+    /// Log records produced by the execution. They are appended to the
+    /// canister log even if the execution failed.
+    pub canister_log: CanisterLog,
+    /// Instructions executed per Wasm function. Only present if Wasm profiling
+    /// is enabled in the embedders config.
+    pub wasm_profile: Option<WasmProfile>,
+}
+
+/// The number of instructions executed by each Wasm function during a single
+/// message execution.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WasmProfile {
+    /// Maps call stacks, given as function indices from the outermost to the
+    /// innermost frame, to the number of instructions executed in the innermost
+    /// function itself. Instructions executed outside of any function, e.g.
+    /// System API overhead charged before the first call, map to the empty
+    /// stack.
+    pub stacks: BTreeMap<Vec<u32>, u64>,
+    /// Names of the functions from the `name` section of the module, if any.
+    pub function_names: BTreeMap<u32, String>,
+}
+
+impl WasmProfile {
+    /// The total number of instructions covered by the profile.
+    pub fn total_instructions(&self) -> u64 {
+        self.stacks.values().sum()
+    }
+
+    /// The number of instructions executed in each function itself, excluding
+    /// the functions it called.
+    pub fn self_instructions(&self) -> BTreeMap<u32, u64> {
+        let mut result = BTreeMap::new();
+        for (stack, instructions) in &self.stacks {
+            if let Some(func) = stack.last() {
+                *result.entry(*func).or_default() += instructions;
+            }
+        }
+        result
+    }
+
+    /// The number of instructions executed in each function including the
+    /// functions it called. Recursive frames are counted only once.
+    pub fn inclusive_instructions(&self) -> BTreeMap<u32, u64> {
+        let mut result = BTreeMap::new();
+        for (stack, instructions) in &self.stacks {
+            let functions: BTreeSet<_> = stack.iter().collect();
+            for func in functions {
+                *result.entry(*func).or_default() += instructions;
+            }
+        }
+        result
+    }
+
+    /// Returns the name of the given function, or `func[<index>]` if the module
+    /// does not name it.
+    pub fn function_name(&self, func: u32) -> String {
+        match self.function_names.get(&func) {
+            Some(name) => name.clone(),
+            None => format!("func[{}]", func),
+        }
+    }
+
+    /// Renders the profile in the folded stack format understood by
+    /// flamegraph tools: one `root;outer;...;inner <instructions>` line per
+    /// call stack.
+    pub fn to_folded(&self, root: &str) -> String {
+        let mut result = String::new();
+        for (stack, instructions) in &self.stacks {
+            if *instructions == 0 {
+                continue;
+            }
+            result.push_str(root);
+            for func in stack {
+                result.push(';');
+                result.push_str(&self.function_name(*func).replace(';', ":"));
+            }
+            result.push_str(&format!(" {}\n", instructions));
+        }
+        result
+    }
 }
 
 impl fmt::Display for WasmExecutionOutput {
@@ -1336,4 +1570,31 @@ mod tests {
         assert_eq!(available.get_message_memory(), 47);
         assert_eq!(available.get_wasm_custom_sections_memory(), 33);
     }
+
+    #[test]
+    fn test_wasm_profile() {
+        let profile = WasmProfile {
+            stacks: BTreeMap::from([
+                (vec![], 3),
+                (vec![1], 10),
+                (vec![1, 2], 20),
+                (vec![1, 2, 2], 5),
+                (vec![1, 3], 0),
+            ]),
+            function_names: BTreeMap::from([(1, "main".to_string())]),
+        };
+        assert_eq!(profile.total_instructions(), 38);
+        assert_eq!(
+            profile.self_instructions(),
+            BTreeMap::from([(1, 10), (2, 25), (3, 0)])
+        );
+        assert_eq!(
+            profile.inclusive_instructions(),
+            BTreeMap::from([(1, 35), (2, 25), (3, 0)])
+        );
+        assert_eq!(
+            profile.to_folded("update"),
+            "update 3\nupdate;main 10\nupdate;main;func[2] 20\nupdate;main;func[2];func[2] 5\n"
+        );
+    }
 }
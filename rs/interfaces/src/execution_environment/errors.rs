@@ -151,6 +151,12 @@ pub enum HypervisorError {
         available: Cycles,
         threshold: Cycles,
     },
+    /// The Wasm memory of the canister would exceed its `wasm_memory_limit`
+    /// setting.
+    WasmMemoryLimitExceeded {
+        bytes: NumBytes,
+        limit: NumBytes,
+    },
 }
 
 impl From<WasmInstrumentationError> for HypervisorError {
@@ -356,6 +362,16 @@ impl HypervisorError {
                      bytes,
                      threshold - available)
             ),
+            Self::WasmMemoryLimitExceeded { bytes, limit } => UserError::new(
+                E::CanisterWasmMemoryLimitExceeded,
+                format!(
+                    "Canister exceeded its current Wasm memory limit of {} bytes. \
+                     The peak Wasm memory usage was {} bytes. If such high Wasm memory \
+                     usage is expected, the limit can be increased in the canister settings.",
+                    limit.get(),
+                    bytes.get(),
+                )
+            ),
         }
     }
 
@@ -394,6 +410,7 @@ impl HypervisorError {
             HypervisorError::InsufficientCyclesInMessageMemoryGrow { .. } => {
                 "InsufficientCyclesInMessageMemoryGrow"
             }
+            HypervisorError::WasmMemoryLimitExceeded { .. } => "WasmMemoryLimitExceeded",
         }
     }
 }
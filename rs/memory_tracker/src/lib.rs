@@ -893,13 +893,19 @@ mod checksum {
             access_addr: *const libc::c_void,
             access_kind: AccessKind,
         ) {
-            self.index += 1;
-            self.value += self.index
-                * (access_addr as usize - base_addr)
-                * match access_kind {
-                    AccessKind::Read => 1,
-                    AccessKind::Write => 1 << 32,
+            // The access kind is encoded in the lowest bit rather than as a
+            // multiplier, so that accesses to offsets beyond 4 GiB in 64-bit
+            // memories do not collide with accesses of the other kind.
+            let offset = access_addr as usize - base_addr;
+            let access = (offset << 1)
+                | match access_kind {
+                    AccessKind::Read => 0,
+                    AccessKind::Write => 1,
                 };
+            self.index += 1;
+            self.value = self
+                .value
+                .wrapping_add(self.index.wrapping_mul(access.wrapping_add(1)));
         }
     }
 
@@ -831,6 +831,64 @@ fn prefetch_for_write_after_read_unordered() {
     );
 }
 
+#[test]
+fn tracks_pages_beyond_4_gib() {
+    // A 64-bit heap can grow beyond 4 GiB, so page indices and offsets above
+    // `u32::MAX` must be handled. Only the touched pages are ever backed by
+    // physical memory, so the large mapping is cheap.
+    let memory_pages = (6 << 30) / PAGE_SIZE;
+    let delta_page = PageIndex::new(((5 << 30) / PAGE_SIZE) as u64);
+    let zero_page = PageIndex::new(delta_page.get() + 100);
+    let tmpfile = tempfile::Builder::new().prefix("test").tempfile().unwrap();
+    let mut page_map = PageMap::open(
+        tmpfile.path(),
+        &[],
+        Height::new(0),
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+    )
+    .unwrap();
+    page_map.update(&[(delta_page, &[42; PAGE_SIZE])]);
+
+    let memory = unsafe {
+        mmap(
+            std::ptr::null_mut(),
+            memory_pages * PAGE_SIZE,
+            ProtFlags::PROT_NONE,
+            MapFlags::MAP_PRIVATE | MapFlags::MAP_ANON | MapFlags::MAP_NORESERVE,
+            -1,
+            0,
+        )
+        .unwrap()
+    };
+    let tracker = SigsegvMemoryTracker::new(
+        memory,
+        memory_pages * PAGE_SIZE,
+        no_op_logger(),
+        DirtyPageTracking::Track,
+        page_map.clone(),
+    )
+    .unwrap();
+
+    let page_contents = |page_index: PageIndex| unsafe {
+        std::slice::from_raw_parts(
+            (memory as *const u8).add(page_index.get() as usize * PAGE_SIZE),
+            PAGE_SIZE,
+        )
+    };
+
+    sigsegv(&tracker, delta_page, AccessKind::Read);
+    assert_eq!(page_contents(delta_page), &[42; PAGE_SIZE][..]);
+    sigsegv(&tracker, zero_page, AccessKind::Read);
+    assert_eq!(page_contents(zero_page), &[0; PAGE_SIZE][..]);
+
+    sigsegv(&tracker, delta_page, AccessKind::Write);
+    assert_eq!(
+        page_contents(delta_page),
+        &page_map.get_page(delta_page)[..]
+    );
+    assert!(tracker.take_dirty_pages().contains(&delta_page));
+}
+
 #[test]
 fn page_bitmap_restrict_to_unaccessed() {
     let mut bitmap = PageBitmap::new(10);
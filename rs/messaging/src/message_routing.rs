@@ -26,6 +26,7 @@ use ic_registry_client_helpers::{
     node::NodeRegistry,
     provisional_whitelist::ProvisionalWhitelistRegistry,
     routing_table::RoutingTableRegistry,
+    schnorr_keys::SchnorrKeysRegistry,
     subnet::{get_node_ids_from_subnet_record, SubnetListRegistry, SubnetRegistry},
 };
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
@@ -86,6 +87,7 @@ const PHASE_COMMIT: &str = "commit";
 const METRIC_PROCESS_BATCH_DURATION: &str = "mr_process_batch_duration_seconds";
 const METRIC_PROCESS_BATCH_PHASE_DURATION: &str = "mr_process_batch_phase_duration_seconds";
 const METRIC_TIMED_OUT_REQUESTS_TOTAL: &str = "mr_timed_out_requests_total";
+const METRIC_TIMED_OUT_CALLBACKS_TOTAL: &str = "mr_timed_out_callbacks_total";
 const METRIC_SUBNET_SPLIT_HEIGHT: &str = "mr_subnet_split_height";
 const BLOCKS_PROPOSED_TOTAL: &str = "mr_blocks_proposed_total";
 const BLOCKS_NOT_PROPOSED_TOTAL: &str = "mr_blocks_not_proposed_total";
@@ -271,6 +273,8 @@ pub(crate) struct MessageRoutingMetrics {
     pub process_batch_phase_duration: HistogramVec,
     /// Number of timed out requests.
     pub timed_out_requests_total: IntCounter,
+    /// Number of timed out best-effort callbacks.
+    pub timed_out_callbacks_total: IntCounter,
     /// Height at which the subnet last split (if during the lifetime of this
     /// replica process; otherwise zero).
     pub subnet_split_height: IntGaugeVec,
@@ -354,6 +358,10 @@ impl MessageRoutingMetrics {
                 METRIC_TIMED_OUT_REQUESTS_TOTAL,
                 "Count of timed out requests.",
             ),
+            timed_out_callbacks_total: metrics_registry.int_counter(
+                METRIC_TIMED_OUT_CALLBACKS_TOTAL,
+                "Count of timed out best-effort callbacks.",
+            ),
             subnet_split_height: metrics_registry.int_gauge_vec(
                 METRIC_SUBNET_SPLIT_HEIGHT,
                 "Height at which the subnet last split (if during the lifetime of this replica process).",
@@ -724,6 +732,10 @@ impl BatchProcessorImpl {
             .ecdsa_config
             .map(|c| (c.max_queue_size, c.quadruples_to_create_in_advance))
             .unwrap_or_default();
+        let max_schnorr_queue_size = subnet_record
+            .schnorr_config
+            .map(|c| c.max_queue_size)
+            .unwrap_or_default();
 
         let subnet_size = if subnet_record.membership.is_empty() {
             self.metrics.critical_error_missing_subnet_size.inc();
@@ -748,6 +760,7 @@ impl BatchProcessorImpl {
                 max_number_of_canisters,
                 provisional_whitelist,
                 max_ecdsa_queue_size,
+                max_schnorr_queue_size,
                 quadruples_to_create_in_advance,
                 subnet_size,
             },
@@ -879,6 +892,11 @@ impl BatchProcessorImpl {
             .get_ecdsa_signing_subnets(registry_version)
             .map_err(|err| registry_error("ECDSA signing subnets", None, err))?
             .unwrap_or_default();
+        let schnorr_signing_subnets = self
+            .registry
+            .get_schnorr_signing_subnets(registry_version)
+            .map_err(|err| registry_error("Schnorr signing subnets", None, err))?
+            .unwrap_or_default();
 
         Ok(NetworkTopology {
             subnets,
@@ -886,6 +904,7 @@ impl BatchProcessorImpl {
             nns_subnet_id,
             canister_migrations: Arc::new(canister_migrations),
             ecdsa_signing_subnets,
+            schnorr_signing_subnets,
             bitcoin_testnet_canister_id: self.bitcoin_config.testnet_canister_id,
             bitcoin_mainnet_canister_id: self.bitcoin_config.mainnet_canister_id,
         })
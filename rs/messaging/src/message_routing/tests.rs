@@ -3,7 +3,7 @@ use assert_matches::assert_matches;
 use ic_crypto_test_utils_ni_dkg::{
     dummy_transcript_for_tests, dummy_transcript_for_tests_with_params,
 };
-use ic_ic00_types::{EcdsaCurve, EcdsaKeyId};
+use ic_ic00_types::{EcdsaCurve, EcdsaKeyId, SchnorrAlgorithm, SchnorrKeyId};
 use ic_interfaces_registry::RegistryValue;
 use ic_interfaces_state_manager::StateReader;
 use ic_interfaces_state_manager_mocks::MockStateManager;
@@ -18,7 +18,7 @@ use ic_registry_local_registry::LocalRegistry;
 use ic_registry_local_store::{compact_delta_to_changelog, LocalStoreImpl, LocalStoreWriter};
 use ic_registry_proto_data_provider::{ProtoRegistryDataProvider, ProtoRegistryDataProviderError};
 use ic_registry_routing_table::{routing_table_insert_subnet, CanisterMigrations, RoutingTable};
-use ic_registry_subnet_features::EcdsaConfig;
+use ic_registry_subnet_features::{EcdsaConfig, SchnorrConfig};
 use ic_test_utilities::state_manager::FakeStateManager;
 use ic_test_utilities::{
     notification::{Notification, WaitResult},
@@ -126,6 +126,7 @@ struct SubnetRecord<'a> {
     subnet_type: SubnetType,
     features: SubnetFeatures,
     ecdsa_config: EcdsaConfig,
+    schnorr_config: SchnorrConfig,
     max_number_of_canisters: u64,
 }
 
@@ -136,6 +137,7 @@ impl<'a> From<SubnetRecord<'a>> for SubnetRecordProto {
             .with_subnet_type(record.subnet_type)
             .with_features(record.features)
             .with_ecdsa_config(record.ecdsa_config)
+            .with_schnorr_config(record.schnorr_config)
             .with_max_number_of_canisters(record.max_number_of_canisters)
             .build()
     }
@@ -529,6 +531,7 @@ fn make_batch_processor(
         max_number_of_canisters: 0,
         provisional_whitelist: ProvisionalWhitelist::All,
         max_ecdsa_queue_size: 0,
+        max_schnorr_queue_size: 0,
         quadruples_to_create_in_advance: 0,
         subnet_size: 0,
     }));
@@ -604,6 +607,14 @@ fn try_read_registry_succeeds_with_fully_specified_registry_records() {
                 max_queue_size: Some(891),
                 ..Default::default()
             },
+            schnorr_config: SchnorrConfig {
+                key_ids: vec![SchnorrKeyId {
+                    algorithm: SchnorrAlgorithm::Bip340Secp256k1,
+                    name: "schnorr key 1".to_string(),
+                }],
+                max_queue_size: Some(123),
+                ..Default::default()
+            },
             max_number_of_canisters: 387,
         };
 
@@ -800,6 +811,10 @@ fn try_read_registry_succeeds_with_fully_specified_registry_records() {
             own_subnet_record.ecdsa_config.max_queue_size,
             Some(registry_execution_settings.max_ecdsa_queue_size),
         );
+        assert_eq!(
+            own_subnet_record.schnorr_config.max_queue_size,
+            Some(registry_execution_settings.max_schnorr_queue_size),
+        );
         assert_eq!(
             own_subnet_record.membership.len(),
             registry_execution_settings.subnet_size,
@@ -869,6 +884,7 @@ fn try_read_registry_succeeds_with_fully_specified_registry_records() {
             messages: BatchMessages::default(),
             randomness: Randomness::new([123; 32]),
             ecdsa_subnet_public_keys: BTreeMap::default(),
+            schnorr_subnet_public_keys: BTreeMap::default(),
             ecdsa_quadruple_ids: BTreeMap::new(),
             registry_version: fixture.registry.get_latest_version(),
             time: Time::from_nanos_since_unix_epoch(0),
@@ -1556,6 +1572,14 @@ fn process_batch_updates_subnet_metrics() {
                 max_queue_size: Some(891),
                 ..Default::default()
             },
+            schnorr_config: SchnorrConfig {
+                key_ids: vec![SchnorrKeyId {
+                    algorithm: SchnorrAlgorithm::Bip340Secp256k1,
+                    name: "schnorr key 1".to_string(),
+                }],
+                max_queue_size: Some(123),
+                ..Default::default()
+            },
             max_number_of_canisters: 387,
         };
 
@@ -1651,6 +1675,7 @@ fn process_batch_updates_subnet_metrics() {
             messages: BatchMessages::default(),
             randomness: Randomness::new([123; 32]),
             ecdsa_subnet_public_keys: BTreeMap::default(),
+            schnorr_subnet_public_keys: BTreeMap::default(),
             ecdsa_quadruple_ids: BTreeMap::new(),
             registry_version: fixture.registry.get_latest_version(),
             time: Time::from_nanos_since_unix_epoch(0),
@@ -1,4 +1,5 @@
 use crate::message_routing::LatencyMetrics;
+use ic_certification_version::CertificationVersion;
 use ic_constants::SYSTEM_SUBNET_STREAM_MSG_LIMIT;
 use ic_error_types::RejectCode;
 use ic_logger::{error, warn, ReplicaLogger};
@@ -73,6 +74,8 @@ const LABEL_VALUE_TYPE_RESPONSE: &str = "response";
 const LABEL_VALUE_STATUS_SUCCESS: &str = "success";
 const LABEL_VALUE_STATUS_CANISTER_NOT_FOUND: &str = "canister_not_found";
 const LABEL_VALUE_STATUS_PAYLOAD_TOO_LARGE: &str = "payload_too_large";
+const LABEL_VALUE_STATUS_BEST_EFFORT_NOT_SUPPORTED: &str = "best_effort_not_supported";
+const LABEL_VALUE_STATUS_STREAM_FULL: &str = "stream_full";
 
 const CRITICAL_ERROR_INFINITE_LOOP: &str = "mr_stream_builder_infinite_loop";
 const CRITICAL_ERROR_PAYLOAD_TOO_LARGE: &str = "mr_stream_builder_payload_too_large";
@@ -197,6 +200,7 @@ impl StreamBuilderImpl {
                             MR_SYNTHETIC_REJECT_MESSAGE_MAX_LEN,
                         ),
                     ),
+                    deadline: req.deadline,
                 }
                 .into(),
                 // Arbitrary large amount, pushing a response always returns memory.
@@ -296,8 +300,16 @@ impl StreamBuilderImpl {
             .map(|(subnet_id, topology)| (*subnet_id, topology.subnet_type))
             .collect();
 
+        // Best-effort call deadlines are only encoded into streams from certification
+        // version 16 onwards, so until then best-effort requests may only be routed
+        // to local canisters.
+        let remote_best_effort_calls_supported =
+            state.metadata.certification_version >= CertificationVersion::V16;
+
         let mut requests_to_reject = Vec::new();
         let mut oversized_requests = Vec::new();
+        let mut remote_best_effort_requests = Vec::new();
+        let mut shed_requests = Vec::new();
 
         let mut output_iter = state.output_into_iter();
         let mut last_output_size = usize::MAX;
@@ -336,8 +348,23 @@ impl StreamBuilderImpl {
                             .get(&dst_net_id)
                             .unwrap_or(&SubnetType::Application),
                     ) {
-                        // Stream full, skip all other messages to this destination.
-                        output_iter.exclude_queue();
+                        match &msg {
+                            // Stream full, shed best-effort messages rather than have them
+                            // wait for the stream to drain.
+                            RequestOrResponse::Request(req) if req.deadline.is_some() => {
+                                self.observe_message_status(&msg, LABEL_VALUE_STATUS_STREAM_FULL);
+                                validated_next(&mut output_iter, (queue_id, &msg));
+                                shed_requests.push((req.clone(), dst_net_id));
+                            }
+                            // Shed best-effort responses are dropped: the originator's
+                            // callback times out when its deadline expires.
+                            RequestOrResponse::Response(rep) if rep.deadline.is_some() => {
+                                self.observe_message_status(&msg, LABEL_VALUE_STATUS_STREAM_FULL);
+                                validated_next(&mut output_iter, (queue_id, &msg));
+                            }
+                            // Stream full, skip all other messages to this destination.
+                            _ => output_iter.exclude_queue(),
+                        }
                         continue;
                     }
 
@@ -347,6 +374,19 @@ impl StreamBuilderImpl {
                     // Reject messages with oversized payloads, as they may
                     // cause streams to permanently stall.
                     match msg {
+                        // Remote best-effort request that the stream cannot encode yet.
+                        RequestOrResponse::Request(req)
+                            if dst_net_id != self.subnet_id
+                                && req.deadline.is_some()
+                                && !remote_best_effort_calls_supported =>
+                        {
+                            self.observe_message_type_status(
+                                LABEL_VALUE_TYPE_REQUEST,
+                                LABEL_VALUE_STATUS_BEST_EFFORT_NOT_SUPPORTED,
+                            );
+                            remote_best_effort_requests.push((req, dst_net_id));
+                        }
+
                         // Remote request above the payload size limit.
                         RequestOrResponse::Request(req)
                             if dst_net_id != self.subnet_id
@@ -450,6 +490,31 @@ impl StreamBuilderImpl {
             );
         }
 
+        for (req, dst_net_id) in shed_requests {
+            self.reject_local_request(
+                &mut state,
+                &req,
+                RejectCode::SysTransient,
+                format!(
+                    "Best-effort request to canister {} dropped: stream to subnet {} is full",
+                    req.receiver, dst_net_id
+                ),
+            );
+        }
+
+        for (req, dst_net_id) in remote_best_effort_requests {
+            let dst_canister_id = req.receiver;
+            self.reject_local_request(
+                &mut state,
+                &req,
+                RejectCode::DestinationInvalid,
+                format!(
+                    "Best-effort calls to canisters on other subnets are not supported yet: canister {} is on subnet {}",
+                    dst_canister_id, dst_net_id
+                ),
+            );
+        }
+
         for req in oversized_requests {
             let sender = req.sender;
             self.reject_local_request(
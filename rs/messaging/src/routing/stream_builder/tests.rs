@@ -122,6 +122,7 @@ fn reject_local_request() {
                     originator_reply_callback: msg.sender_reply_callback,
                     refund: msg.payment,
                     response_payload: Payload::Reject(expected_reject_context),
+                    deadline: msg.deadline,
                 }
                 .into(),
                 &mut (i64::MAX / 2),
@@ -185,6 +186,7 @@ fn reject_local_request_for_subnet() {
                         RejectCode::SysFatal,
                         reject_message,
                     )),
+                    deadline: msg.deadline,
                 }
                 .into(),
                 &mut (i64::MAX / 2),
@@ -428,6 +430,117 @@ fn build_streams_impl_at_limit_leaves_state_untouched() {
     });
 }
 
+// Tests that best-effort messages at the head of output queues are shed when
+// the destination stream is full, while guaranteed response messages wait.
+#[test]
+fn build_streams_impl_at_limit_sheds_best_effort_messages() {
+    with_test_replica_logger(|log| {
+        let (stream_builder, mut provided_state, metrics_registry) = new_fixture(&log);
+        provided_state.metadata.certification_version = CertificationVersion::V16;
+        provided_state.metadata.network_topology.routing_table = Arc::new(RoutingTable::try_from(
+            btreemap! {
+                CanisterIdRange{ start: CanisterId::from(0x100), end: CanisterId::from(0xfff) } => REMOTE_SUBNET,
+            },
+        ).unwrap());
+
+        // An empty stream, so that the stream builder considers it to be at limit.
+        let mut streams = provided_state.take_streams();
+        streams.get_mut_or_insert(REMOTE_SUBNET);
+        provided_state.put_streams(streams);
+
+        let deadline = Some(Time::from_nanos_since_unix_epoch(1_000));
+        let best_effort_request = RequestBuilder::default()
+            .sender(canister_test_id(1))
+            .receiver(canister_test_id(0x100))
+            .sender_reply_callback(CallbackId::from(1))
+            .method_name("best_effort")
+            .deadline(deadline)
+            .build();
+        let guaranteed_request = generate_message_for_test(
+            canister_test_id(2),
+            canister_test_id(0x100),
+            CallbackId::from(1),
+            "guaranteed".to_string(),
+            Cycles::new(0),
+        );
+        let best_effort_response = Response {
+            originator: canister_test_id(0x101),
+            respondent: canister_test_id(3),
+            originator_reply_callback: CallbackId::from(1),
+            refund: Cycles::new(0),
+            response_payload: Payload::Data(vec![1]),
+            deadline,
+        };
+        provided_state.put_canister_states(canister_states_with_outputs::<RequestOrResponse>(
+            vec![
+                best_effort_request.clone().into(),
+                guaranteed_request.into(),
+                best_effort_response.into(),
+            ],
+        ));
+
+        // The best-effort request and response are consumed; the guaranteed
+        // response request is left in its output queue.
+        let mut expected_state = provided_state.clone();
+        for (src, dst) in [
+            (canister_test_id(1), canister_test_id(0x100)),
+            (canister_test_id(3), canister_test_id(0x101)),
+        ] {
+            expected_state
+                .canister_state_mut(&src)
+                .unwrap()
+                .system_state
+                .queues_mut()
+                .pop_canister_output(&dst)
+                .unwrap();
+        }
+        // And the best-effort request is rejected.
+        stream_builder.reject_local_request(
+            &mut expected_state,
+            &best_effort_request,
+            RejectCode::SysTransient,
+            format!(
+                "Best-effort request to canister {} dropped: stream to subnet {} is full",
+                best_effort_request.receiver, REMOTE_SUBNET
+            ),
+        );
+
+        let result_state = stream_builder.build_streams_impl(provided_state, usize::MAX, 0);
+
+        assert_eq!(result_state.canister_states, expected_state.canister_states);
+        assert_eq!(result_state, expected_state);
+        assert_eq!(
+            1,
+            result_state
+                .canister_state(&canister_test_id(2))
+                .unwrap()
+                .system_state
+                .queues()
+                .output_message_count()
+        );
+
+        assert_routed_messages_eq(
+            metric_vec(&[
+                (
+                    &[
+                        (LABEL_TYPE, LABEL_VALUE_TYPE_REQUEST),
+                        (LABEL_STATUS, LABEL_VALUE_STATUS_STREAM_FULL),
+                    ],
+                    1,
+                ),
+                (
+                    &[
+                        (LABEL_TYPE, LABEL_VALUE_TYPE_RESPONSE),
+                        (LABEL_STATUS, LABEL_VALUE_STATUS_STREAM_FULL),
+                    ],
+                    1,
+                ),
+            ]),
+            &metrics_registry,
+        );
+    });
+}
+
 /// Helper for testing `build_streams_impl()` with various message or byte size
 /// limits.
 ///
@@ -681,6 +794,166 @@ fn build_streams_with_messages_targeted_to_other_subnets() {
     });
 }
 
+/// Sets up a state with one guaranteed response request and one best-effort request
+/// from a local canister to a canister on `REMOTE_SUBNET`.
+fn remote_best_effort_fixture(
+    log: &ReplicaLogger,
+) -> (
+    StreamBuilderImpl,
+    ReplicatedState,
+    MetricsRegistry,
+    Request,
+    Request,
+) {
+    let (stream_builder, mut provided_state, metrics_registry) = new_fixture(log);
+
+    // Ensure the routing table maps the receiver to `REMOTE_SUBNET`.
+    provided_state.metadata.network_topology.routing_table = Arc::new(
+        RoutingTable::try_from(btreemap! {
+            CanisterIdRange{ start: CanisterId::from(0x100), end: CanisterId::from(0xfff) } => REMOTE_SUBNET,
+        })
+        .unwrap(),
+    );
+
+    let guaranteed_request = generate_message_for_test(
+        canister_test_id(1),
+        canister_test_id(0x100),
+        CallbackId::from(1),
+        "guaranteed".to_string(),
+        Cycles::new(0),
+    );
+    let best_effort_request = RequestBuilder::default()
+        .sender(canister_test_id(1))
+        .receiver(canister_test_id(0x100))
+        .sender_reply_callback(CallbackId::from(2))
+        .method_name("best_effort")
+        .deadline(Some(Time::from_nanos_since_unix_epoch(1_000)))
+        .build();
+    provided_state.put_canister_states(canister_states_with_outputs(vec![
+        guaranteed_request.clone(),
+        best_effort_request.clone(),
+    ]));
+
+    (
+        stream_builder,
+        provided_state,
+        metrics_registry,
+        guaranteed_request,
+        best_effort_request,
+    )
+}
+
+// Tests that best-effort requests to remote subnets are rejected until their
+// deadlines can be encoded into streams.
+#[test]
+fn build_streams_rejects_remote_best_effort_requests_before_v16() {
+    with_test_replica_logger(|log| {
+        let (
+            stream_builder,
+            mut provided_state,
+            metrics_registry,
+            guaranteed_request,
+            best_effort_request,
+        ) = remote_best_effort_fixture(&log);
+        provided_state.metadata.certification_version = CertificationVersion::V15;
+
+        // Only the guaranteed response request is routed into the stream.
+        let expected_stream = Stream::new(
+            requests_into_queue_round_robin(
+                StreamIndex::from(0),
+                vec![guaranteed_request],
+                None,
+                provided_state.time(),
+            ),
+            Default::default(),
+        );
+        let mut expected_state = consume_output_queues(&provided_state);
+        expected_state.modify_streams(|streams| {
+            streams.insert(REMOTE_SUBNET, expected_stream);
+        });
+        // And the best-effort request is rejected.
+        stream_builder.reject_local_request(
+            &mut expected_state,
+            &best_effort_request,
+            RejectCode::DestinationInvalid,
+            format!(
+                "Best-effort calls to canisters on other subnets are not supported yet: canister {} is on subnet {}",
+                best_effort_request.receiver, REMOTE_SUBNET
+            ),
+        );
+
+        let result_state = stream_builder.build_streams(provided_state);
+
+        assert_eq!(result_state.canister_states, expected_state.canister_states);
+        assert_eq!(result_state.metadata, expected_state.metadata);
+        assert_eq!(result_state, expected_state);
+
+        assert_routed_messages_eq(
+            metric_vec(&[
+                (
+                    &[
+                        (LABEL_TYPE, LABEL_VALUE_TYPE_REQUEST),
+                        (LABEL_STATUS, LABEL_VALUE_STATUS_SUCCESS),
+                    ],
+                    1,
+                ),
+                (
+                    &[
+                        (LABEL_TYPE, LABEL_VALUE_TYPE_REQUEST),
+                        (LABEL_STATUS, LABEL_VALUE_STATUS_BEST_EFFORT_NOT_SUPPORTED),
+                    ],
+                    1,
+                ),
+            ]),
+            &metrics_registry,
+        );
+    });
+}
+
+// Tests that best-effort requests to remote subnets are routed once their
+// deadlines are encoded into streams.
+#[test]
+fn build_streams_routes_remote_best_effort_requests_from_v16() {
+    with_test_replica_logger(|log| {
+        let (
+            stream_builder,
+            mut provided_state,
+            metrics_registry,
+            guaranteed_request,
+            best_effort_request,
+        ) = remote_best_effort_fixture(&log);
+        provided_state.metadata.certification_version = CertificationVersion::V16;
+
+        let expected_stream = Stream::new(
+            requests_into_queue_round_robin(
+                StreamIndex::from(0),
+                vec![guaranteed_request, best_effort_request],
+                None,
+                provided_state.time(),
+            ),
+            Default::default(),
+        );
+        let mut expected_state = consume_output_queues(&provided_state);
+        expected_state.modify_streams(|streams| {
+            streams.insert(REMOTE_SUBNET, expected_stream);
+        });
+
+        let result_state = stream_builder.build_streams(provided_state);
+
+        assert_eq!(result_state, expected_state);
+        assert_routed_messages_eq(
+            metric_vec(&[(
+                &[
+                    (LABEL_TYPE, LABEL_VALUE_TYPE_REQUEST),
+                    (LABEL_STATUS, LABEL_VALUE_STATUS_SUCCESS),
+                ],
+                2,
+            )]),
+            &metrics_registry,
+        );
+    });
+}
+
 // Tests that remote requests and all responses with oversized payloads are rejected.
 #[test]
 fn build_streams_with_oversized_payloads() {
@@ -711,6 +984,7 @@ fn build_streams_with_oversized_payloads() {
             method_name: method_name.clone(),
             method_payload: oversized_request_payload.clone(),
             metadata: None,
+            deadline: None,
         };
         assert!(local_request.payload_size_bytes() > MAX_INTER_CANISTER_PAYLOAD_IN_BYTES);
 
@@ -723,6 +997,7 @@ fn build_streams_with_oversized_payloads() {
             method_name,
             method_payload: oversized_request_payload,
             metadata: None,
+            deadline: None,
         };
         assert!(remote_request.payload_size_bytes() > MAX_INTER_CANISTER_PAYLOAD_IN_BYTES);
         let remote_request_reject = Response {
@@ -739,6 +1014,7 @@ fn build_streams_with_oversized_payloads() {
                     MAX_INTER_CANISTER_PAYLOAD_IN_BYTES
                 ),
             )),
+            deadline: None,
         };
 
         // Oversized response: will be replaced with a reject response.
@@ -748,6 +1024,7 @@ fn build_streams_with_oversized_payloads() {
             originator_reply_callback: CallbackId::from(3),
             refund: Cycles::new(3),
             response_payload: Payload::Data(oversized_response_payload),
+            deadline: None,
         };
         assert!(data_response.payload_size_bytes() > MAX_INTER_CANISTER_PAYLOAD_IN_BYTES);
         let data_response_reject = Response {
@@ -764,6 +1041,7 @@ fn build_streams_with_oversized_payloads() {
                     MAX_INTER_CANISTER_PAYLOAD_IN_BYTES
                 ),
             )),
+            deadline: None,
         };
 
         // Oversized reject response: will be replaced with a reject response.
@@ -777,6 +1055,7 @@ fn build_streams_with_oversized_payloads() {
                 RejectCode::SysTransient,
                 oversized_error_message,
             )),
+            deadline: None,
         };
         assert!(reject_response.payload_size_bytes() > MAX_INTER_CANISTER_PAYLOAD_IN_BYTES);
         let reject_response_reject = Response {
@@ -789,6 +1068,7 @@ fn build_streams_with_oversized_payloads() {
                 // Long enough message to be properly truncated by the constructor.
                 "x".repeat(10 * 1024),
             )),
+            deadline: None,
         };
 
         let (stream_builder, mut provided_state, metrics_registry) = new_fixture(&log);
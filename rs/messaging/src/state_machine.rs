@@ -127,6 +127,12 @@ impl StateMachine for StateMachineImpl {
         self.metrics
             .timed_out_requests_total
             .inc_by(timed_out_requests);
+
+        // Time out best-effort callbacks.
+        let timed_out_callbacks = state.time_out_callbacks();
+        self.metrics
+            .timed_out_callbacks_total
+            .inc_by(timed_out_callbacks);
         self.observe_phase_duration(PHASE_TIME_OUT_REQUESTS, &since);
 
         // Preprocess messages and add messages to the induction pool through the Demux.
@@ -152,6 +158,7 @@ impl StateMachine for StateMachineImpl {
             state_with_messages,
             batch.randomness,
             batch.ecdsa_subnet_public_keys,
+            batch.schnorr_subnet_public_keys,
             batch.ecdsa_quadruple_ids,
             ExecutionRound::from(batch.batch_number.get()),
             execution_round_type,
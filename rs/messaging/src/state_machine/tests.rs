@@ -4,7 +4,7 @@ use crate::{
     routing::demux::MockDemux, routing::stream_builder::MockStreamBuilder,
     state_machine::StateMachineImpl,
 };
-use ic_ic00_types::EcdsaKeyId;
+use ic_ic00_types::{EcdsaKeyId, SchnorrKeyId};
 use ic_interfaces::execution_environment::Scheduler;
 use ic_interfaces_state_manager::StateManager;
 use ic_metrics::MetricsRegistry;
@@ -20,7 +20,10 @@ use ic_test_utilities_logger::with_test_replica_logger;
 use ic_test_utilities_metrics::fetch_int_counter_vec;
 use ic_types::consensus::ecdsa::QuadrupleId;
 use ic_types::messages::SignedIngress;
-use ic_types::{batch::BatchMessages, crypto::canister_threshold_sig::MasterEcdsaPublicKey};
+use ic_types::{
+    batch::BatchMessages,
+    crypto::canister_threshold_sig::{MasterEcdsaPublicKey, MasterSchnorrPublicKey},
+};
 use ic_types::{Height, PrincipalId, SubnetId, Time};
 use maplit::btreemap;
 use mockall::{mock, predicate::*, Sequence};
@@ -35,6 +38,7 @@ mock! {
             state: ic_replicated_state::ReplicatedState,
             randomness: ic_types::Randomness,
             ecdsa_subnet_public_keys: BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+            schnorr_subnet_public_keys: BTreeMap<SchnorrKeyId, MasterSchnorrPublicKey>,
             ecdsa_quadruple_ids: BTreeMap<EcdsaKeyId, BTreeSet<QuadrupleId>>,
             current_round: ExecutionRound,
             current_round_type: ExecutionRoundType,
@@ -90,12 +94,13 @@ fn test_fixture(provided_batch: &Batch) -> StateMachineTestFixture {
             always(),
             eq(provided_batch.randomness),
             eq(provided_batch.ecdsa_subnet_public_keys.clone()),
+            eq(provided_batch.schnorr_subnet_public_keys.clone()),
             eq(provided_batch.ecdsa_quadruple_ids.clone()),
             eq(round),
             eq(round_type),
             eq(test_registry_settings()),
         )
-        .returning(|state, _, _, _, _, _, _| state);
+        .returning(|state, _, _, _, _, _, _, _| state);
 
     let mut stream_builder = Box::new(MockStreamBuilder::new());
     stream_builder
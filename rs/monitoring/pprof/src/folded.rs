@@ -0,0 +1,183 @@
+//! Conversion of profiles in the folded stack format into `pprof` profiles.
+//!
+//! The folded stack format has one `frame;frame;...;frame <value>` line per
+//! call stack, from the outermost to the innermost frame. It is produced e.g.
+//! by the Wasm profiler of the execution environment and can be rendered as a
+//! flamegraph directly or, after conversion, by `go tool pprof`.
+
+use crate::Error;
+use prost::Message;
+use std::collections::HashMap;
+
+/// The subset of the `pprof` profile message required to represent folded
+/// stacks. See https://github.com/google/pprof/blob/main/proto/profile.proto.
+#[derive(Clone, PartialEq, ::prost::Message)]
+struct Profile {
+    #[prost(message, repeated, tag = "1")]
+    sample_type: Vec<ValueType>,
+    #[prost(message, repeated, tag = "2")]
+    sample: Vec<Sample>,
+    #[prost(message, repeated, tag = "4")]
+    location: Vec<Location>,
+    #[prost(message, repeated, tag = "5")]
+    function: Vec<Function>,
+    #[prost(string, repeated, tag = "6")]
+    string_table: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+struct ValueType {
+    #[prost(int64, tag = "1")]
+    r#type: i64,
+    #[prost(int64, tag = "2")]
+    unit: i64,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+struct Sample {
+    /// Leaf first.
+    #[prost(uint64, repeated, tag = "1")]
+    location_id: Vec<u64>,
+    #[prost(int64, repeated, tag = "2")]
+    value: Vec<i64>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+struct Location {
+    #[prost(uint64, tag = "1")]
+    id: u64,
+    #[prost(message, repeated, tag = "4")]
+    line: Vec<Line>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+struct Line {
+    #[prost(uint64, tag = "1")]
+    function_id: u64,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+struct Function {
+    #[prost(uint64, tag = "1")]
+    id: u64,
+    #[prost(int64, tag = "2")]
+    name: i64,
+}
+
+#[derive(Default)]
+struct ProfileBuilder {
+    profile: Profile,
+    strings: HashMap<String, i64>,
+    // Maps frame names to the IDs of their location, which equal the IDs of
+    // their function.
+    frames: HashMap<String, u64>,
+}
+
+impl ProfileBuilder {
+    fn string(&mut self, s: &str) -> i64 {
+        if let Some(index) = self.strings.get(s) {
+            return *index;
+        }
+        let index = self.profile.string_table.len() as i64;
+        self.profile.string_table.push(s.to_string());
+        self.strings.insert(s.to_string(), index);
+        index
+    }
+
+    fn frame(&mut self, name: &str) -> u64 {
+        if let Some(id) = self.frames.get(name) {
+            return *id;
+        }
+        let id = self.frames.len() as u64 + 1;
+        let name_index = self.string(name);
+        self.profile.function.push(Function {
+            id,
+            name: name_index,
+        });
+        self.profile.location.push(Location {
+            id,
+            line: vec![Line { function_id: id }],
+        });
+        self.frames.insert(name.to_string(), id);
+        id
+    }
+}
+
+/// Converts a profile in the folded stack format into a protobuf-encoded
+/// `pprof` profile with a single sample type, e.g. `("instructions", "count")`.
+pub fn folded_to_pprof(folded: &str, sample_type: &str, unit: &str) -> Result<Vec<u8>, Error> {
+    let mut builder = ProfileBuilder::default();
+    // The string table must start with the empty string.
+    builder.string("");
+    let sample_type = ValueType {
+        r#type: builder.string(sample_type),
+        unit: builder.string(unit),
+    };
+    builder.profile.sample_type.push(sample_type);
+
+    for line in folded.lines().filter(|line| !line.trim().is_empty()) {
+        let (stack, value) = line
+            .rsplit_once(' ')
+            .ok_or_else(|| Error::InvalidFoldedStack(line.to_string()))?;
+        let value: i64 = value
+            .trim()
+            .parse()
+            .map_err(|_| Error::InvalidFoldedStack(line.to_string()))?;
+        let mut location_id: Vec<u64> = stack.split(';').map(|f| builder.frame(f)).collect();
+        location_id.reverse();
+        builder.profile.sample.push(Sample {
+            location_id,
+            value: vec![value],
+        });
+    }
+
+    let mut body = Vec::new();
+    builder.profile.encode(&mut body)?;
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_folded_stacks() {
+        let folded = "update;main 10\nupdate;main;helper 20\n\nupdate 3\n";
+        let bytes = folded_to_pprof(folded, "instructions", "count").unwrap();
+        let profile = Profile::decode(bytes.as_slice()).unwrap();
+
+        assert_eq!(profile.string_table[0], "");
+        let string = |index: i64| profile.string_table[index as usize].as_str();
+        assert_eq!(string(profile.sample_type[0].r#type), "instructions");
+        assert_eq!(string(profile.sample_type[0].unit), "count");
+
+        let name = |id: u64| string(profile.function[id as usize - 1].name);
+        let samples: Vec<(Vec<&str>, i64)> = profile
+            .sample
+            .iter()
+            .map(|sample| {
+                (
+                    sample.location_id.iter().map(|id| name(*id)).collect(),
+                    sample.value[0],
+                )
+            })
+            .collect();
+        assert_eq!(
+            samples,
+            vec![
+                (vec!["main", "update"], 10),
+                (vec!["helper", "main", "update"], 20),
+                (vec!["update"], 3),
+            ]
+        );
+        assert_eq!(profile.function.len(), 3);
+    }
+
+    #[test]
+    fn rejects_lines_without_value() {
+        assert!(matches!(
+            folded_to_pprof("update;main\n", "instructions", "count"),
+            Err(Error::InvalidFoldedStack(_))
+        ));
+    }
+}
@@ -1,5 +1,9 @@
 //! In-process CPU profiling support.
 
+mod folded;
+
+pub use folded::folded_to_pprof;
+
 use async_trait::async_trait;
 use lazy_static::lazy_static;
 use pprof::{protos::Message, ProfilerGuard, Report};
@@ -25,6 +29,11 @@ pub enum Error {
         #[from]
         source: prost::EncodeError,
     },
+    /// A line of a folded stack profile is not of the form
+    /// `frame;...;frame <value>`.
+    #[error("Invalid folded stack line: {0}")]
+    InvalidFoldedStack(String),
+
     #[error("An internal error occurred.")]
     Internal,
 }
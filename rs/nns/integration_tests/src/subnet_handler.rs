@@ -64,6 +64,7 @@ fn test_submit_and_accept_update_subnet_proposal() {
                 ssh_readonly_access: vec![],
                 ssh_backup_access: vec![],
                 ecdsa_config: None,
+                schnorr_config: None,
             };
 
             let key = make_subnet_record_key(subnet_id);
@@ -187,6 +188,7 @@ fn test_submit_and_accept_update_subnet_proposal() {
                     ssh_readonly_access: vec!["pub_key_0".to_string()],
                     ssh_backup_access: vec!["pub_key_1".to_string()],
                     ecdsa_config: None,
+                    schnorr_config: None,
                 }
             );
             Ok(())
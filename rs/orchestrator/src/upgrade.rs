@@ -821,6 +821,11 @@ mod tests {
                     name: key_id.to_string(),
                 },
             },
+            schnorr_key_transcripts: BTreeMap::new(),
+            available_schnorr_pre_signatures: BTreeMap::new(),
+            schnorr_pre_signatures_in_creation: BTreeMap::new(),
+            ongoing_schnorr_signatures: BTreeMap::new(),
+            schnorr_signature_agreements: BTreeMap::new(),
         };
 
         let block = Block::new(
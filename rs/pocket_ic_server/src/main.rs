@@ -21,7 +21,9 @@ use ic_crypto_utils_threshold_sig_der::parse_threshold_sig_key_from_der;
 use pocket_ic::common::rest::{BinaryBlob, BlobCompression, BlobId, RawVerifyCanisterSigArg};
 use pocket_ic_server::state_api::routes::timeout_or_default;
 use pocket_ic_server::state_api::{
-    routes::{instances_routes, status, AppState, RouterExt},
+    routes::{
+        create_http_gateway, instances_routes, status, stop_http_gateway, AppState, RouterExt,
+    },
     state::PocketIcApiStateBuilder,
 };
 use pocket_ic_server::BlobStore;
@@ -129,6 +131,12 @@ async fn start(runtime: Arc<Runtime>) {
         //
         // All instance routes.
         .nest("/instances", instances_routes::<AppState>())
+        //
+        // Start an HTTP gateway for an instance.
+        .directory_route("/http_gateway", post(create_http_gateway))
+        //
+        // Stop an HTTP gateway.
+        .directory_route("/http_gateway/:id/stop", post(stop_http_gateway))
         .layer(DefaultBodyLimit::disable())
         .route_layer(middleware::from_fn_with_state(
             app_state.clone(),
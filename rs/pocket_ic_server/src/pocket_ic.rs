@@ -1,40 +1,65 @@
-use crate::state_api::state::{HasStateLabel, OpOut, PocketIcError, StateLabel};
+use crate::state_api::state::{
+    HasStateLabel, HttpGatewayResponse, OpOut, PocketIcError, StateLabel,
+};
 use crate::OpId;
 use crate::Operation;
 use crate::{copy_dir, BlobStore};
+use candid::{CandidType, Decode, Encode};
 use ic_config::execution_environment;
 use ic_config::subnet_config::SubnetConfig;
 use ic_crypto_sha2::Sha256;
 use ic_crypto_utils_threshold_sig_der::threshold_sig_public_key_to_der;
-use ic_ic00_types::CanisterInstallMode;
+use ic_http_certification::HttpResponse;
+use ic_ic00_types::{CanisterInstallMode, Payload, TransformArgs};
 use ic_registry_proto_data_provider::ProtoRegistryDataProvider;
 use ic_registry_routing_table::{CanisterIdRange, RoutingTable, CANISTER_IDS_PER_SUBNET};
 use ic_registry_subnet_type::SubnetType;
+use ic_response_verification::{
+    verify_request_response_pair, MAX_VERIFICATION_VERSION, MIN_VERIFICATION_VERSION,
+};
 use ic_state_machine_tests::{
-    EcdsaCurve, EcdsaKeyId, IngressState, IngressStatus, StateMachine, StateMachineBuilder,
-    StateMachineConfig, SubmitIngressError, Time,
+    CanisterHttpRequestContext, CanisterHttpResponsePayload, EcdsaCurve, EcdsaKeyId, ErrorCode,
+    HttpHeader, IngressState, IngressStatus, MessageId, PayloadBuilder, RejectCode,
+    SimulatedBitcoinNetwork, StateMachine, StateMachineBuilder, StateMachineConfig,
+    StateMachineStateDir, SubmitIngressError, Time,
 };
 use ic_test_utilities::types::ids::subnet_test_id;
-use ic_types::messages::CertificateDelegation;
+use ic_types::canister_http::MAX_CANISTER_HTTP_RESPONSE_BYTES;
+use ic_types::crypto::Signable;
+use ic_types::messages::{
+    Blob, CallbackId, CertificateDelegation, HttpQueryResponse, HttpQueryResponseReply,
+    HttpReadStateResponse, HttpSignedQueryResponse, HttpStatusResponse, NodeSignature,
+    QueryResponseHash, ReadState, ReplicaHealthStatus, SignedIngress, UserQuery,
+};
 use ic_types::{CanisterId, PrincipalId, SubnetId};
 use itertools::Itertools;
 use pocket_ic::common::rest::{
-    self, BinaryBlob, BlobCompression, ExtendedSubnetConfigSet, RawAddCycles, RawCanisterCall,
-    RawEffectivePrincipal, RawSetStableMemory, SubnetKind, SubnetSpec, Topology,
+    self, BinaryBlob, BitcoinOutput, BlobCompression, CanisterHttpHeader, CanisterHttpMethod,
+    CanisterHttpRequest, CanisterHttpResponse, ExtendedSubnetConfigSet, RawAddCycles,
+    RawCanisterCall, RawEffectivePrincipal, RawMessageId, RawMineBitcoinBlocks,
+    RawMockCanisterHttpResponse, RawSetStableMemory, SubnetKind, SubnetSpec, Topology,
 };
 use rand::rngs::StdRng;
 use rand::Rng;
 use rand::SeedableRng;
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::{
     collections::{BTreeMap, HashMap},
     sync::{Arc, RwLock},
-    time::SystemTime,
+    time::{Duration, SystemTime},
 };
 use tempfile::TempDir;
 use tokio::runtime::Runtime;
 
+/// The version of the IC HTTP interface served under `/api/v2` of an instance.
+const IC_API_VERSION: &str = "0.18.0";
+
+/// The file in the state directory of a PocketIC instance
+/// storing the subnet configs and the topology of the instance.
+const TOPOLOGY_FILE_NAME: &str = "topology.json";
+
 /// We assume that the maximum number of subnets on the mainnet is 1024.
 /// Used for generating canister ID ranges that do not appear on mainnet.
 pub const MAXIMUM_NUMBER_OF_SUBNETS_ON_MAINNET: u64 = 1024;
@@ -48,10 +73,50 @@ pub struct PocketIc {
     // where a canister should be created. This value is seeded,
     // so reproducibility is maintained.
     randomness: StdRng,
+    /// Answers the requests of Bitcoin canisters on all subnets.
+    /// Not persisted in the state directory.
+    bitcoin_network: Arc<SimulatedBitcoinNetwork>,
+}
+
+/// The content of the topology file in the state directory of a PocketIC instance.
+#[derive(Serialize, Deserialize)]
+struct StateDirTopology {
+    subnet_configs: ExtendedSubnetConfigSet,
+    topology: Topology,
 }
 
 impl PocketIc {
-    pub fn new(runtime: Arc<Runtime>, subnet_configs: ExtendedSubnetConfigSet) -> Self {
+    /// Returns the subnet configs of the PocketIC instance whose state is stored
+    /// in the given directory, or `None` if the directory contains no such state.
+    pub fn read_subnet_configs(
+        state_dir: &Path,
+    ) -> Result<Option<ExtendedSubnetConfigSet>, String> {
+        let topology_file = state_dir.join(TOPOLOGY_FILE_NAME);
+        if !topology_file.exists() {
+            return Ok(None);
+        }
+        let bytes = std::fs::read(&topology_file)
+            .map_err(|e| format!("Failed to read {}: {}", topology_file.display(), e))?;
+        let StateDirTopology { subnet_configs, .. } = serde_json::from_slice(&bytes)
+            .map_err(|e| format!("Failed to parse {}: {}", topology_file.display(), e))?;
+        Ok(Some(subnet_configs))
+    }
+
+    /// Creates a new PocketIC instance. If a state directory is provided, the state
+    /// of every subnet is checkpointed in a subdirectory named after the subnet ID
+    /// and an existing state in that directory is resumed. The subnet configs
+    /// must be the ones returned by `read_subnet_configs` in that case
+    /// so that the subnet IDs and the routing table are recreated.
+    pub fn new(
+        runtime: Arc<Runtime>,
+        subnet_configs: ExtendedSubnetConfigSet,
+        state_dir: Option<PathBuf>,
+    ) -> Self {
+        let resumed = state_dir
+            .as_ref()
+            .map(|state_dir| state_dir.join(TOPOLOGY_FILE_NAME).exists())
+            .unwrap_or_default();
+        let persisted = state_dir.is_some();
         let fixed_range_subnets = subnet_configs.get_named();
         let flexible_subnets = {
             // note that for these, the subnet ids are currently ignored.
@@ -71,7 +136,7 @@ impl PocketIc {
         let mut subnet_ids = vec![];
         let mut routing_table = RoutingTable::new();
 
-        let mut nns_subnet_id = subnet_configs.nns.and_then(|x| {
+        let mut nns_subnet_id = subnet_configs.nns.as_ref().and_then(|x| {
             x.get_subnet_id()
                 .map(|y| SubnetId::new(PrincipalId(y.into())))
         });
@@ -119,13 +184,27 @@ impl PocketIc {
                 routing_table.insert(alloc_range, subnet_id).unwrap();
             }
 
-            let state_dir = if let Some(subnet_state_dir) = subnet_state_dir {
-                let tmp_dir = TempDir::new().expect("Failed to create temporary directory");
-                copy_dir(subnet_state_dir, tmp_dir.path()).expect("Failed to copy state directory");
-                Some(tmp_dir)
-            } else {
-                None
-            };
+            let state_dir: Option<Box<dyn StateMachineStateDir>> =
+                if let Some(ref instance_state_dir) = state_dir {
+                    let subnet_dir = instance_state_dir.join(subnet_id.to_string());
+                    if !subnet_dir.exists() {
+                        if let Some(subnet_state_dir) = subnet_state_dir {
+                            copy_dir(subnet_state_dir, &subnet_dir)
+                                .expect("Failed to copy state directory");
+                        } else {
+                            std::fs::create_dir_all(&subnet_dir)
+                                .expect("Failed to create state directory");
+                        }
+                    }
+                    Some(Box::new(subnet_dir))
+                } else if let Some(subnet_state_dir) = subnet_state_dir {
+                    let tmp_dir = TempDir::new().expect("Failed to create temporary directory");
+                    copy_dir(subnet_state_dir, tmp_dir.path())
+                        .expect("Failed to copy state directory");
+                    Some(Box::new(tmp_dir))
+                } else {
+                    None
+                };
 
             subnet_config_info.push(SubnetConfigInfo {
                 subnet_id,
@@ -168,10 +247,24 @@ impl PocketIc {
                 .with_use_cost_scaling_flag(true);
 
             if let Some(state_dir) = state_dir {
-                builder = builder.with_state_dir(state_dir);
+                builder = builder.with_state_machine_state_dir(state_dir);
+            }
+            // The state is only loaded from checkpoints, so every round
+            // of a persisted instance must be checkpointed.
+            if persisted {
+                builder = builder.with_checkpoints_enabled(true);
             }
 
-            builder.build_with_subnets(subnets.clone());
+            let sm = builder.build_with_subnets(subnets.clone());
+
+            // A resumed subnet continues at the time of its latest state.
+            if resumed {
+                let batch_time = sm.get_latest_state().metadata.batch_time;
+                sm.set_time(
+                    SystemTime::UNIX_EPOCH
+                        + Duration::from_nanos(batch_time.as_nanos_since_unix_epoch()),
+                );
+            }
 
             // What will be returned to the client:
             let subnet_config = pocket_ic::common::rest::SubnetConfig {
@@ -190,11 +283,31 @@ impl PocketIc {
             subnet.reload_registry();
         }
 
+        if let Some(state_dir) = state_dir {
+            if !resumed {
+                let state_dir_topology = StateDirTopology {
+                    subnet_configs,
+                    topology: topology.clone(),
+                };
+                std::fs::write(
+                    state_dir.join(TOPOLOGY_FILE_NAME),
+                    serde_json::to_vec_pretty(&state_dir_topology).unwrap(),
+                )
+                .expect("Failed to write topology file");
+            }
+        }
+
+        let bitcoin_network = SimulatedBitcoinNetwork::new();
+        for subnet in subnets.read().unwrap().values() {
+            subnet.set_bitcoin_network(bitcoin_network.clone());
+        }
+
         Self {
             subnets,
             routing_table,
             topology,
             randomness: StdRng::seed_from_u64(42),
+            bitcoin_network,
         }
     }
 
@@ -265,18 +378,46 @@ impl PocketIc {
         }
     }
 
+    /// The subnet whose key is the root key of this instance. This is the NNS
+    /// subnet if there is one and an arbitrary (but fixed) subnet otherwise.
+    fn root_subnet(&self) -> Arc<StateMachine> {
+        self.nns_subnet().unwrap_or_else(|| self.any_subnet())
+    }
+
     fn get_nns_delegation_for_subnet(&self, subnet_id: SubnetId) -> Option<CertificateDelegation> {
-        let nns_subnet = match self.nns_subnet() {
-            Some(nns_subnet) => nns_subnet,
-            None => {
-                return None;
-            }
-        };
-        if nns_subnet.get_subnet_id() == subnet_id {
+        let root_subnet = self.root_subnet();
+        if root_subnet.get_subnet_id() == subnet_id {
             None
         } else {
-            nns_subnet.get_delegation_for_subnet(subnet_id).ok()
+            root_subnet.get_delegation_for_subnet(subnet_id).ok()
+        }
+    }
+
+    /// Executes rounds on all subnets until the ingress message with the given
+    /// ID has completed or failed on the given subnet, for at most `max_rounds`.
+    fn execute_until_ingress_done(
+        &self,
+        subnet: &StateMachine,
+        msg_id: &MessageId,
+        max_rounds: usize,
+    ) -> Option<Result<ic_state_machine_tests::WasmResult, ic_state_machine_tests::UserError>> {
+        for _i in 0..max_rounds {
+            for subnet_ in self.subnets.read().unwrap().values() {
+                subnet_.execute_round();
+            }
+            match subnet.ingress_status(msg_id) {
+                IngressStatus::Known {
+                    state: IngressState::Completed(result),
+                    ..
+                } => return Some(Ok(result)),
+                IngressStatus::Known {
+                    state: IngressState::Failed(error),
+                    ..
+                } => return Some(Err(error)),
+                _ => {}
+            }
         }
+        None
     }
 }
 
@@ -288,6 +429,7 @@ impl Default for PocketIc {
                 application: vec![SubnetSpec::New],
                 ..Default::default()
             },
+            None,
         )
     }
 }
@@ -305,6 +447,7 @@ impl HasStateLabel for PocketIc {
             hasher.write(&subnet_state_hash[..]);
             hasher.write(&nanos.to_be_bytes());
         }
+        hasher.write(&self.bitcoin_network.num_blocks().to_be_bytes());
         StateLabel(hasher.finish())
     }
 }
@@ -448,7 +591,7 @@ struct SubnetConfigInfo {
     pub subnet_id: SubnetId,
     pub ranges: Vec<CanisterIdRange>,
     pub subnet_kind: SubnetKind,
-    pub state_dir: Option<TempDir>,
+    pub state_dir: Option<Box<dyn StateMachineStateDir>>,
 }
 
 // ---------------------------------------------------------------------------------------- //
@@ -540,6 +683,27 @@ impl Operation for Tick {
     }
 }
 
+/// Advances the time on all subnets by the given duration and executes a round
+/// on every subnet. Used by the auto-progress mode of an instance.
+#[derive(Clone, Copy, Debug)]
+pub struct AdvanceTimeAndTick(pub Duration);
+
+impl Operation for AdvanceTimeAndTick {
+    type TargetType = PocketIc;
+
+    fn compute(self, pic: &mut PocketIc) -> OpOut {
+        for subnet in pic.subnets.read().unwrap().values() {
+            subnet.advance_time(self.0);
+            subnet.execute_round();
+        }
+        OpOut::NoOutput
+    }
+
+    fn id(&self) -> OpId {
+        OpId(format!("advance_time_and_tick_{}", self.0.as_nanos()))
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct ExecuteIngressMessage(pub CanisterCall);
 
@@ -568,32 +732,13 @@ impl Operation for ExecuteIngressMessage {
                     Ok(msg_id) => {
                         // Now, we execute on all subnets until we have the result
                         let max_rounds = 100;
-                        for _i in 0..max_rounds {
-                            for subnet_ in pic.subnets.read().unwrap().values() {
-                                subnet_.execute_round();
-                            }
-                            match subnet.ingress_status(&msg_id) {
-                                IngressStatus::Known {
-                                    state: IngressState::Completed(result),
-                                    ..
-                                } => return Ok(result).into(),
-                                IngressStatus::Known {
-                                    state: IngressState::Failed(error),
-                                    ..
-                                } => {
-                                    return Err::<
-                                        ic_state_machine_tests::WasmResult,
-                                        ic_state_machine_tests::UserError,
-                                    >(error)
-                                    .into()
-                                }
-                                _ => {}
-                            }
+                        match pic.execute_until_ingress_done(&subnet, &msg_id, max_rounds) {
+                            Some(result) => result.into(),
+                            None => panic!(
+                                "Failed to answer to ingress {} after {} xnet rounds.",
+                                msg_id, max_rounds
+                            ),
                         }
-                        panic!(
-                            "Failed to answer to ingress {} after {} xnet rounds.",
-                            msg_id, max_rounds
-                        );
                     }
                 }
             }
@@ -607,6 +752,95 @@ impl Operation for ExecuteIngressMessage {
     }
 }
 
+/// Submits an ingress message without executing it. The message is executed
+/// in subsequent rounds (e.g., triggered by `Tick`).
+#[derive(Clone, Debug)]
+pub struct SubmitIngressMessage(pub CanisterCall);
+
+impl Operation for SubmitIngressMessage {
+    type TargetType = PocketIc;
+
+    fn compute(self, pic: &mut PocketIc) -> OpOut {
+        let canister_call = self.0.clone();
+        let subnet = route_call(pic, canister_call);
+        match subnet {
+            Ok(subnet) => {
+                match subnet.submit_ingress_as(
+                    self.0.sender,
+                    self.0.canister_id,
+                    self.0.method,
+                    self.0.payload,
+                ) {
+                    Err(SubmitIngressError::HttpError(e)) => {
+                        OpOut::Error(PocketIcError::BadIngressMessage(e))
+                    }
+                    Err(SubmitIngressError::UserError(e)) => Err::<
+                        ic_state_machine_tests::WasmResult,
+                        ic_state_machine_tests::UserError,
+                    >(e)
+                    .into(),
+                    Ok(msg_id) => {
+                        OpOut::MessageId((subnet.get_subnet_id(), msg_id.as_bytes().to_vec()))
+                    }
+                }
+            }
+            Err(e) => OpOut::Error(PocketIcError::BadIngressMessage(e)),
+        }
+    }
+
+    fn id(&self) -> OpId {
+        let call_id = self.0.id();
+        OpId(format!("submit_ingress_message_{}", call_id.0))
+    }
+}
+
+/// Fetches the status of an ingress message without executing any rounds.
+#[derive(Clone, Debug)]
+pub struct GetIngressStatus {
+    pub subnet_id: SubnetId,
+    pub message_id: MessageId,
+}
+
+impl TryFrom<RawMessageId> for GetIngressStatus {
+    type Error = ConversionError;
+    fn try_from(
+        RawMessageId {
+            subnet_id,
+            message_id,
+        }: RawMessageId,
+    ) -> Result<Self, Self::Error> {
+        let subnet_id =
+            PrincipalId::try_from(subnet_id.subnet_id).map_err(|_| ConversionError {
+                message: "Bad subnet id".to_string(),
+            })?;
+        let message_id = MessageId::try_from(&message_id[..]).map_err(|_| ConversionError {
+            message: "Bad message id".to_string(),
+        })?;
+        Ok(GetIngressStatus {
+            subnet_id: SubnetId::new(subnet_id),
+            message_id,
+        })
+    }
+}
+
+impl Operation for GetIngressStatus {
+    type TargetType = PocketIc;
+
+    fn compute(self, pic: &mut PocketIc) -> OpOut {
+        match pic.get_subnet_with_id(self.subnet_id) {
+            Some(subnet) => OpOut::IngressStatus(subnet.ingress_status(&self.message_id).into()),
+            None => OpOut::Error(PocketIcError::SubnetNotFound(self.subnet_id.get().0)),
+        }
+    }
+
+    fn id(&self) -> OpId {
+        OpId(format!(
+            "ingress_status_{}_{}",
+            self.subnet_id, self.message_id
+        ))
+    }
+}
+
 pub struct Query(pub CanisterCall);
 
 impl Operation for Query {
@@ -637,131 +871,690 @@ impl Operation for Query {
     }
 }
 
-#[derive(Clone, Debug)]
-pub enum EffectivePrincipal {
-    None,
-    SubnetId(SubnetId),
-    CanisterId(CanisterId),
-}
+// ---------------------------------------------------------------------------------------- //
+// Operations backing the IC HTTP interface (`/api/v2`) of an instance
 
+/// Submits a (possibly signed) ingress message received on `/api/v2/canister/<id>/call`
+/// and executes rounds until the message has been processed, so that the agent can read
+/// its status via `read_state` right away.
 #[derive(Clone, Debug)]
-pub struct CanisterCall {
-    pub effective_principal: EffectivePrincipal,
-    pub sender: PrincipalId,
-    pub canister_id: CanisterId,
-    pub method: String,
-    pub payload: Vec<u8>,
+pub struct CallRequest {
+    pub effective_canister_id: CanisterId,
+    pub msg: SignedIngress,
 }
 
-impl TryFrom<RawCanisterCall> for CanisterCall {
-    type Error = ConversionError;
-    fn try_from(
-        RawCanisterCall {
-            sender,
-            canister_id,
-            method,
-            payload,
-            effective_principal,
-        }: RawCanisterCall,
-    ) -> Result<Self, Self::Error> {
-        let effective_principal = match effective_principal {
-            RawEffectivePrincipal::SubnetId(subnet_id) => {
-                let sid = PrincipalId::try_from(subnet_id);
-                match sid {
-                    Ok(sid) => EffectivePrincipal::SubnetId(SubnetId::new(sid)),
-                    Err(_) => {
-                        return Err(ConversionError {
-                            message: "Bad subnet id".to_string(),
-                        })
-                    }
-                }
-            }
-            RawEffectivePrincipal::CanisterId(canister_id) => {
-                match CanisterId::try_from(canister_id) {
-                    Ok(canister_id) => EffectivePrincipal::CanisterId(canister_id),
-                    Err(_) => {
-                        return Err(ConversionError {
-                            message: "Bad effective canister id".to_string(),
-                        })
-                    }
-                }
+impl Operation for CallRequest {
+    type TargetType = PocketIc;
+
+    fn compute(self, pic: &mut PocketIc) -> OpOut {
+        let subnet = match pic.try_route_canister(self.effective_canister_id) {
+            Some(subnet) => subnet,
+            None => {
+                return OpOut::Error(PocketIcError::BadIngressMessage(format!(
+                    "Effective canister ID {} not contained on any subnet",
+                    self.effective_canister_id
+                )))
             }
-            RawEffectivePrincipal::None => EffectivePrincipal::None,
         };
-        let sender = match PrincipalId::try_from(sender) {
-            Ok(sender) => sender,
-            Err(_) => {
-                return Err(ConversionError {
-                    message: "Bad sender principal".to_string(),
-                })
+        match subnet.submit_signed_ingress(self.msg) {
+            Err(SubmitIngressError::HttpError(e)) => {
+                OpOut::Error(PocketIcError::BadIngressMessage(e))
+            }
+            Err(SubmitIngressError::UserError(e)) => {
+                Err::<ic_state_machine_tests::WasmResult, ic_state_machine_tests::UserError>(e)
+                    .into()
+            }
+            Ok(msg_id) => {
+                // The outcome is not returned here: the agent polls for it via `read_state`.
+                pic.execute_until_ingress_done(&subnet, &msg_id, 100);
+                OpOut::NoOutput
+            }
+        }
+    }
+
+    fn id(&self) -> OpId {
+        OpId(format!("call_request_{}", self.msg.id()))
+    }
+}
+
+/// Executes a query received on `/api/v2/canister/<id>/query` and returns the
+/// CBOR-encoded response, signed by a node of the subnet hosting the canister.
+#[derive(Clone, Debug)]
+pub struct QueryRequest {
+    pub effective_canister_id: CanisterId,
+    pub query: UserQuery,
+}
+
+impl Operation for QueryRequest {
+    type TargetType = PocketIc;
+
+    fn compute(self, pic: &mut PocketIc) -> OpOut {
+        let subnet = match pic.try_route_canister(self.effective_canister_id) {
+            Some(subnet) => subnet,
+            None => {
+                return OpOut::Error(PocketIcError::BadIngressMessage(format!(
+                    "Effective canister ID {} not contained on any subnet",
+                    self.effective_canister_id
+                )))
             }
         };
-        let canister_id = match CanisterId::try_from(canister_id) {
-            Ok(canister_id) => canister_id,
-            Err(_) => {
-                return Err(ConversionError {
-                    message: "Bad canister id".to_string(),
-                })
+        let delegation = pic.get_nns_delegation_for_subnet(subnet.get_subnet_id());
+        let result = subnet.query_as_with_delegation(
+            self.query.source.get(),
+            self.query.receiver,
+            self.query.method_name.clone(),
+            self.query.method_payload.clone(),
+            delegation,
+        );
+        let response = match result {
+            Ok(ic_state_machine_tests::WasmResult::Reply(arg)) => HttpQueryResponse::Replied {
+                reply: HttpQueryResponseReply { arg: Blob(arg) },
+            },
+            Ok(ic_state_machine_tests::WasmResult::Reject(message)) => {
+                HttpQueryResponse::Rejected {
+                    error_code: ErrorCode::CanisterRejectedMessage.to_string(),
+                    reject_code: RejectCode::CanisterReject as u64,
+                    reject_message: message,
+                }
             }
+            Err(user_error) => HttpQueryResponse::Rejected {
+                error_code: user_error.code().to_string(),
+                reject_code: user_error.reject_code() as u64,
+                reject_message: user_error.to_string(),
+            },
         };
+        let timestamp = subnet.get_time();
+        let response_hash = QueryResponseHash::new(&response, &self.query, timestamp);
+        let (identity, signature) = subnet
+            .compute_node_signature(0, &response_hash.as_signed_bytes())
+            .unwrap();
+        OpOut::Bytes(into_cbor(&HttpSignedQueryResponse {
+            response,
+            node_signature: NodeSignature {
+                timestamp,
+                signature: Blob(signature.to_vec()),
+                identity,
+            },
+        }))
+    }
 
-        Ok(CanisterCall {
-            effective_principal,
-            sender,
-            canister_id,
-            method,
-            payload,
-        })
+    fn id(&self) -> OpId {
+        OpId(format!("query_request_{}", self.query.id()))
     }
 }
 
-impl CanisterCall {
+/// Returns the CBOR-encoded certificate for the paths requested on
+/// `/api/v2/canister/<id>/read_state`, signed with the key of the subnet
+/// hosting the canister (and delegated by the root subnet, if needed).
+#[derive(Clone, Debug)]
+pub struct ReadStateRequest {
+    pub effective_canister_id: CanisterId,
+    pub read_state: ReadState,
+}
+
+impl Operation for ReadStateRequest {
+    type TargetType = PocketIc;
+
+    fn compute(self, pic: &mut PocketIc) -> OpOut {
+        let subnet = match pic.try_route_canister(self.effective_canister_id) {
+            Some(subnet) => subnet,
+            None => {
+                return OpOut::Error(PocketIcError::BadIngressMessage(format!(
+                    "Effective canister ID {} not contained on any subnet",
+                    self.effective_canister_id
+                )))
+            }
+        };
+        if let Err(e) =
+            verify_read_state_paths(&subnet, &self.read_state, self.effective_canister_id)
+        {
+            return OpOut::Error(e);
+        }
+        let delegation = pic.get_nns_delegation_for_subnet(subnet.get_subnet_id());
+        match subnet.read_state(self.read_state.paths, delegation) {
+            Ok(certificate) => OpOut::Bytes(into_cbor(&HttpReadStateResponse {
+                certificate: Blob(into_cbor(&certificate)),
+            })),
+            Err(e) => OpOut::Error(PocketIcError::BadIngressMessage(e)),
+        }
+    }
+
     fn id(&self) -> OpId {
-        let mut hasher = Sha256::new();
-        hasher.write(&self.payload);
-        let hash = Digest(hasher.finish());
         OpId(format!(
-            "call({:?},{},{},{},{})",
-            self.effective_principal, self.sender, self.canister_id, self.method, hash
+            "read_state_request_{}_{}",
+            self.effective_canister_id,
+            self.read_state.id()
         ))
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
-pub struct SetStableMemory {
-    pub canister_id: CanisterId,
-    pub data: Vec<u8>,
-}
-
-impl SetStableMemory {
-    pub async fn from_store(
-        raw: RawSetStableMemory,
-        store: Arc<dyn BlobStore>,
-    ) -> Result<Self, ConversionError> {
-        if let Ok(canister_id) = CanisterId::try_from(raw.canister_id) {
-            if let Some(BinaryBlob { data, compression }) = store.fetch(raw.blob_id).await {
-                if let Some(data) = decompress(data, compression) {
-                    Ok(SetStableMemory { canister_id, data })
-                } else {
-                    Err(ConversionError {
-                        message: "Decompression failed".to_string(),
-                    })
+/// Verifies that the sender of `read_state` is authorized to retrieve the
+/// requested paths, mirroring the checks performed by a replica.
+fn verify_read_state_paths(
+    subnet: &StateMachine,
+    read_state: &ReadState,
+    effective_canister_id: CanisterId,
+) -> Result<(), PocketIcError> {
+    let mut request_status_id: Option<MessageId> = None;
+    let paths: Vec<Vec<&[u8]>> = read_state
+        .paths
+        .iter()
+        .map(|path| path.iter().map(|label| label.as_bytes()).collect())
+        .collect();
+    for path in paths {
+        match path.as_slice() {
+            [b"time"] => {}
+            [b"canister", canister_id, b"controllers" | b"module_hash"]
+            | [b"canister", canister_id, b"metadata", _] => {
+                let canister_id = PrincipalId::try_from(*canister_id).map_err(|e| {
+                    PocketIcError::BadIngressMessage(format!(
+                        "Could not parse Principal ID: {}.",
+                        e
+                    ))
+                })?;
+                if canister_id != effective_canister_id.get() {
+                    return Err(PocketIcError::BadIngressMessage(format!(
+                        "Effective principal id in URL {} does not match requested principal id: {}.",
+                        effective_canister_id, canister_id
+                    )));
                 }
-            } else {
-                Err(ConversionError {
-                    message: "Bad blob id".to_string(),
-                })
             }
-        } else {
-            Err(ConversionError {
-                message: "Bad canister id".to_string(),
-            })
+            [b"subnet", ..] => {}
+            [b"request_status", request_id]
+            | [b"request_status", request_id, b"status" | b"reply" | b"reject_code" | b"reject_message" | b"error_code"] =>
+            {
+                let message_id = MessageId::try_from(*request_id).map_err(|_| {
+                    PocketIcError::BadIngressMessage(
+                        "Request IDs must be 32 bytes in length.".to_string(),
+                    )
+                })?;
+                if request_status_id.is_some_and(|id| id != message_id) {
+                    return Err(PocketIcError::BadIngressMessage(
+                        "Can only request a single request ID in request_status paths.".to_string(),
+                    ));
+                }
+                if let Some(user_id) = subnet.ingress_status(&message_id).user_id() {
+                    if user_id != read_state.source {
+                        return Err(PocketIcError::Forbidden(
+                            "Request IDs must be for requests signed by the caller.".to_string(),
+                        ));
+                    }
+                }
+                request_status_id = Some(message_id);
+            }
+            _ => {
+                return Err(PocketIcError::BadIngressMessage(
+                    "Invalid path requested.".to_string(),
+                ))
+            }
         }
     }
+    Ok(())
 }
 
-fn decompress(data: Vec<u8>, compression: BlobCompression) -> Option<Vec<u8>> {
-    use std::io::Read;
+/// Returns the CBOR-encoded response to `/api/v2/status`, which contains the
+/// root key of the instance.
+#[derive(Clone, Copy, Debug)]
+pub struct StatusRequest;
+
+impl Operation for StatusRequest {
+    type TargetType = PocketIc;
+
+    fn compute(self, pic: &mut PocketIc) -> OpOut {
+        let root_key = threshold_sig_public_key_to_der(pic.root_subnet().root_key()).unwrap();
+        OpOut::Bytes(into_cbor(&HttpStatusResponse {
+            ic_api_version: IC_API_VERSION.to_string(),
+            root_key: Some(Blob(root_key)),
+            impl_version: None,
+            impl_hash: None,
+            replica_health_status: Some(ReplicaHealthStatus::Healthy),
+            certified_height: None,
+        }))
+    }
+
+    fn id(&self) -> OpId {
+        OpId("status_request".to_string())
+    }
+}
+
+// ---------------------------------------------------------------------------------------- //
+// Operations backing the HTTP gateway of an instance
+
+/// The header a client sets to demand certification of the response.
+const REQUIRE_CERTIFICATION_HEADER_NAME: &str = "x-icx-require-certification";
+/// The header carrying the certificate of a certified response.
+const IC_CERTIFICATE_HEADER_NAME: &str = "ic-certificate";
+/// The header that is not certified by response verification v1.
+const CACHE_HEADER_NAME: &str = "cache-control";
+/// The maximum age of the certificate of a certified response.
+const MAX_CERT_TIME_OFFSET_NS: u128 = 300_000_000_000;
+/// The maximum number of streaming callbacks made to assemble a response body.
+const MAX_HTTP_REQUEST_STREAM_CALLBACK_CALL_COUNT: usize = 1000;
+
+#[derive(CandidType)]
+struct HttpRequestArg {
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    certificate_version: Option<u16>,
+}
+
+#[derive(CandidType, Deserialize)]
+struct HttpRequestReply<S> {
+    status_code: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    streaming_strategy: Option<S>,
+    upgrade: Option<bool>,
+}
+
+/// The only streaming strategy supported by the gateway is the one implemented
+/// by the asset canister.
+#[derive(CandidType, Deserialize)]
+enum StreamingStrategy {
+    Callback {
+        callback: candid::Func,
+        token: StreamingToken,
+    },
+}
+
+#[derive(CandidType, Deserialize)]
+struct StreamingToken {
+    key: String,
+    content_encoding: String,
+    index: candid::Nat,
+    sha256: Option<Vec<u8>>,
+}
+
+#[derive(CandidType, Deserialize)]
+struct StreamingCallbackHttpResponse {
+    body: Vec<u8>,
+    token: Option<StreamingToken>,
+}
+
+/// An HTTP request received by the HTTP gateway of an instance, to be served by
+/// the `http_request` (or `http_request_update`) method of a canister.
+#[derive(Clone, Debug)]
+pub struct HttpGatewayRequest {
+    pub canister_id: CanisterId,
+    /// Serve the response as-is, as done for `<canister_id>.raw.<domain>`.
+    pub skip_verification: bool,
+    pub method: String,
+    /// The path and query of the request URL.
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpGatewayRequest {
+    fn is_certification_required(&self) -> bool {
+        self.headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case(REQUIRE_CERTIFICATION_HEADER_NAME))
+    }
+
+    fn call_http_request(
+        &self,
+        pic: &PocketIc,
+        subnet: &StateMachine,
+    ) -> Result<(Vec<u8>, bool), HttpGatewayResponse> {
+        let arg = HttpRequestArg {
+            method: self.method.clone(),
+            url: self.url.clone(),
+            headers: self.headers.clone(),
+            body: self.body.clone(),
+            certificate_version: Some(u16::from(MAX_VERIFICATION_VERSION)),
+        };
+        let delegation = pic.get_nns_delegation_for_subnet(subnet.get_subnet_id());
+        let result = subnet.query_as_with_delegation(
+            PrincipalId::new_anonymous(),
+            self.canister_id,
+            "http_request",
+            Encode!(&arg).unwrap(),
+            delegation,
+        );
+        let reply = canister_reply(result)?;
+        let response = decode_http_response::<candid::Reserved>(&reply)?;
+        if response.upgrade != Some(true) {
+            return Ok((reply, false));
+        }
+
+        let arg = HttpRequestArg {
+            certificate_version: None,
+            ..arg
+        };
+        let msg_id = subnet
+            .submit_ingress_as(
+                PrincipalId::new_anonymous(),
+                self.canister_id,
+                "http_request_update",
+                Encode!(&arg).unwrap(),
+            )
+            .map_err(|e| {
+                let message = match e {
+                    SubmitIngressError::HttpError(e) => e,
+                    SubmitIngressError::UserError(e) => e.to_string(),
+                };
+                gateway_error(502, message)
+            })?;
+        let max_rounds = 100;
+        match pic.execute_until_ingress_done(subnet, &msg_id, max_rounds) {
+            Some(result) => Ok((canister_reply(result)?, true)),
+            None => Err(gateway_error(
+                504,
+                format!(
+                    "Failed to answer to ingress {} after {} xnet rounds.",
+                    msg_id, max_rounds
+                ),
+            )),
+        }
+    }
+
+    /// Assembles the body of a response using the streaming strategy of the
+    /// asset canister.
+    fn stream_body(
+        &self,
+        pic: &PocketIc,
+        mut body: Vec<u8>,
+        callback: candid::Func,
+        token: StreamingToken,
+    ) -> Result<Vec<u8>, HttpGatewayResponse> {
+        let callback_canister_id = CanisterId::unchecked_from_principal(callback.principal.into());
+        let subnet = pic
+            .try_route_canister(callback_canister_id)
+            .ok_or_else(|| {
+                gateway_error(
+                    502,
+                    format!(
+                        "Streaming callback canister {} not found",
+                        callback_canister_id
+                    ),
+                )
+            })?;
+        let delegation = pic.get_nns_delegation_for_subnet(subnet.get_subnet_id());
+        let mut token = Some(token);
+        for _ in 0..MAX_HTTP_REQUEST_STREAM_CALLBACK_CALL_COUNT {
+            let Some(current_token) = token else {
+                return Ok(body);
+            };
+            let result = subnet.query_as_with_delegation(
+                PrincipalId::new_anonymous(),
+                callback_canister_id,
+                callback.method.clone(),
+                Encode!(&current_token).unwrap(),
+                delegation.clone(),
+            );
+            let reply = canister_reply(result)?;
+            let chunk = Decode!(&reply, StreamingCallbackHttpResponse).map_err(|e| {
+                gateway_error(
+                    502,
+                    format!("Failed to decode streaming callback response: {}", e),
+                )
+            })?;
+            body.extend(chunk.body);
+            token = chunk.token;
+        }
+        Err(gateway_error(
+            502,
+            format!(
+                "Streaming callback was called more than {} times",
+                MAX_HTTP_REQUEST_STREAM_CALLBACK_CALL_COUNT
+            ),
+        ))
+    }
+
+    /// Verifies the certification of a response and returns the headers which
+    /// may be served to the client, as done by `icx-proxy`.
+    fn verify(
+        &self,
+        pic: &PocketIc,
+        subnet: &StateMachine,
+        response: &HttpResponse,
+    ) -> Result<Vec<(String, String)>, HttpGatewayResponse> {
+        let has_ic_certificate = response
+            .headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case(IC_CERTIFICATE_HEADER_NAME));
+        if !self.is_certification_required() && !has_ic_certificate {
+            return Ok(response.headers.clone());
+        }
+        let request = ic_http_certification::HttpRequest {
+            method: self.method.clone(),
+            url: self.url.clone(),
+            headers: self.headers.clone(),
+            body: self.body.clone(),
+        };
+        let root_key = threshold_sig_public_key_to_der(pic.root_subnet().root_key()).unwrap();
+        let verification_info = verify_request_response_pair(
+            request,
+            response.clone(),
+            self.canister_id.get_ref().as_slice(),
+            u128::from(subnet.get_time().as_nanos_since_unix_epoch()),
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_VERIFICATION_VERSION,
+        )
+        .map_err(|_| gateway_error(500, "Body does not pass verification".to_string()))?;
+        if verification_info.verification_version < 2 {
+            // Status codes are not certified in v1, reject known dangerous status codes.
+            if (300..400).contains(&response.status_code) {
+                return Err(gateway_error(
+                    500,
+                    "Response verification v1 does not allow redirects".to_string(),
+                ));
+            }
+            // Headers are also not certified in v1, filter known dangerous headers.
+            Ok(response
+                .headers
+                .iter()
+                .filter(|(name, _)| !name.eq_ignore_ascii_case(CACHE_HEADER_NAME))
+                .cloned()
+                .collect())
+        } else {
+            // If there is no certified response, the canister has decided to certifiably
+            // skip verification. Otherwise, only the certified headers are served.
+            Ok(match verification_info.response {
+                None => response.headers.clone(),
+                Some(certified_response) => certified_response.headers,
+            })
+        }
+    }
+
+    fn serve(&self, pic: &PocketIc) -> Result<HttpGatewayResponse, HttpGatewayResponse> {
+        let subnet = pic
+            .try_route_canister(self.canister_id)
+            .filter(|subnet| subnet.canister_exists(self.canister_id))
+            .ok_or_else(|| {
+                gateway_error(404, format!("Canister {} not found", self.canister_id))
+            })?;
+        let (reply, is_update_call) = self.call_http_request(pic, &subnet)?;
+
+        let response = decode_http_response::<candid::Reserved>(&reply)?;
+        let (body, is_streamed) = match response.streaming_strategy {
+            None => (response.body, false),
+            Some(_) => {
+                let Some(StreamingStrategy::Callback { callback, token }) =
+                    decode_http_response::<StreamingStrategy>(&reply)?.streaming_strategy
+                else {
+                    unreachable!()
+                };
+                (self.stream_body(pic, response.body, callback, token)?, true)
+            }
+        };
+        let response = HttpResponse {
+            status_code: response.status_code,
+            headers: response.headers,
+            body,
+            upgrade: None,
+        };
+
+        // Verification is only performed if the response is not streamed, as
+        // done by `icx-proxy`.
+        let headers = if self.skip_verification || is_update_call || is_streamed {
+            response.headers.clone()
+        } else {
+            self.verify(pic, &subnet, &response)?
+        };
+        Ok(HttpGatewayResponse {
+            status_code: response.status_code,
+            headers,
+            body: response.body,
+        })
+    }
+}
+
+impl Operation for HttpGatewayRequest {
+    type TargetType = PocketIc;
+
+    fn compute(self, pic: &mut PocketIc) -> OpOut {
+        match self.serve(pic) {
+            Ok(response) | Err(response) => OpOut::HttpGatewayResponse(response),
+        }
+    }
+
+    fn id(&self) -> OpId {
+        let mut hasher = Sha256::new();
+        hasher.write(self.method.as_bytes());
+        hasher.write(self.url.as_bytes());
+        for (name, value) in &self.headers {
+            hasher.write(name.as_bytes());
+            hasher.write(value.as_bytes());
+        }
+        hasher.write(&self.body);
+        let hash = Digest(hasher.finish());
+        OpId(format!(
+            "http_gateway_request({},{},{})",
+            self.canister_id, self.skip_verification, hash
+        ))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum EffectivePrincipal {
+    None,
+    SubnetId(SubnetId),
+    CanisterId(CanisterId),
+}
+
+#[derive(Clone, Debug)]
+pub struct CanisterCall {
+    pub effective_principal: EffectivePrincipal,
+    pub sender: PrincipalId,
+    pub canister_id: CanisterId,
+    pub method: String,
+    pub payload: Vec<u8>,
+}
+
+impl TryFrom<RawCanisterCall> for CanisterCall {
+    type Error = ConversionError;
+    fn try_from(
+        RawCanisterCall {
+            sender,
+            canister_id,
+            method,
+            payload,
+            effective_principal,
+        }: RawCanisterCall,
+    ) -> Result<Self, Self::Error> {
+        let effective_principal = match effective_principal {
+            RawEffectivePrincipal::SubnetId(subnet_id) => {
+                let sid = PrincipalId::try_from(subnet_id);
+                match sid {
+                    Ok(sid) => EffectivePrincipal::SubnetId(SubnetId::new(sid)),
+                    Err(_) => {
+                        return Err(ConversionError {
+                            message: "Bad subnet id".to_string(),
+                        })
+                    }
+                }
+            }
+            RawEffectivePrincipal::CanisterId(canister_id) => {
+                match CanisterId::try_from(canister_id) {
+                    Ok(canister_id) => EffectivePrincipal::CanisterId(canister_id),
+                    Err(_) => {
+                        return Err(ConversionError {
+                            message: "Bad effective canister id".to_string(),
+                        })
+                    }
+                }
+            }
+            RawEffectivePrincipal::None => EffectivePrincipal::None,
+        };
+        let sender = match PrincipalId::try_from(sender) {
+            Ok(sender) => sender,
+            Err(_) => {
+                return Err(ConversionError {
+                    message: "Bad sender principal".to_string(),
+                })
+            }
+        };
+        let canister_id = match CanisterId::try_from(canister_id) {
+            Ok(canister_id) => canister_id,
+            Err(_) => {
+                return Err(ConversionError {
+                    message: "Bad canister id".to_string(),
+                })
+            }
+        };
+
+        Ok(CanisterCall {
+            effective_principal,
+            sender,
+            canister_id,
+            method,
+            payload,
+        })
+    }
+}
+
+impl CanisterCall {
+    fn id(&self) -> OpId {
+        let mut hasher = Sha256::new();
+        hasher.write(&self.payload);
+        let hash = Digest(hasher.finish());
+        OpId(format!(
+            "call({:?},{},{},{},{})",
+            self.effective_principal, self.sender, self.canister_id, self.method, hash
+        ))
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SetStableMemory {
+    pub canister_id: CanisterId,
+    pub data: Vec<u8>,
+}
+
+impl SetStableMemory {
+    pub async fn from_store(
+        raw: RawSetStableMemory,
+        store: Arc<dyn BlobStore>,
+    ) -> Result<Self, ConversionError> {
+        if let Ok(canister_id) = CanisterId::try_from(raw.canister_id) {
+            if let Some(BinaryBlob { data, compression }) = store.fetch(raw.blob_id).await {
+                if let Some(data) = decompress(data, compression) {
+                    Ok(SetStableMemory { canister_id, data })
+                } else {
+                    Err(ConversionError {
+                        message: "Decompression failed".to_string(),
+                    })
+                }
+            } else {
+                Err(ConversionError {
+                    message: "Bad blob id".to_string(),
+                })
+            }
+        } else {
+            Err(ConversionError {
+                message: "Bad canister id".to_string(),
+            })
+        }
+    }
+}
+
+fn decompress(data: Vec<u8>, compression: BlobCompression) -> Option<Vec<u8>> {
+    use std::io::Read;
     match compression {
         BlobCompression::Gzip => {
             let mut decoder = flate2::read::GzDecoder::new(&data[..]);
@@ -908,7 +1701,330 @@ impl Operation for AddCycles {
     }
 
     fn id(&self) -> OpId {
-        OpId(format!("add_cycles({},{})", self.canister_id, self.amount))
+        OpId(format!("add_cycles({},{})", self.canister_id, self.amount))
+    }
+}
+
+/// Lists the pending canister HTTP requests (HTTPS outcalls) of all subnets.
+#[derive(Clone, Debug)]
+pub struct GetCanisterHttp;
+
+impl Operation for GetCanisterHttp {
+    type TargetType = PocketIc;
+
+    fn compute(self, pic: &mut PocketIc) -> OpOut {
+        let mut canister_http_requests = vec![];
+        for subnet in pic.subnets.read().unwrap().values() {
+            let subnet_id = subnet.get_subnet_id().get().0;
+            for (request_id, context) in subnet.canister_http_request_contexts() {
+                canister_http_requests.push(CanisterHttpRequest {
+                    subnet_id,
+                    request_id: request_id.get(),
+                    http_method: match context.http_method {
+                        ic_types::canister_http::CanisterHttpMethod::GET => CanisterHttpMethod::GET,
+                        ic_types::canister_http::CanisterHttpMethod::POST => {
+                            CanisterHttpMethod::POST
+                        }
+                        ic_types::canister_http::CanisterHttpMethod::HEAD => {
+                            CanisterHttpMethod::HEAD
+                        }
+                    },
+                    url: context.url,
+                    headers: context
+                        .headers
+                        .into_iter()
+                        .map(|header| CanisterHttpHeader {
+                            name: header.name,
+                            value: header.value,
+                        })
+                        .collect(),
+                    body: context.body.unwrap_or_default(),
+                    max_response_bytes: context.max_response_bytes.map(|bytes| bytes.get()),
+                });
+            }
+        }
+        canister_http_requests.sort();
+        OpOut::CanisterHttp(canister_http_requests)
+    }
+
+    fn id(&self) -> OpId {
+        OpId("get_canister_http".to_string())
+    }
+}
+
+/// Delivers a mocked response to a pending canister HTTP request in a new block
+/// on the subnet of the request.
+///
+/// Every response is passed through the transform function of the request, as done
+/// by the replicas. If several responses are given (one per replica), the canister
+/// only receives the transformed response if enough replicas agree on it, and a
+/// reject otherwise.
+#[derive(Clone, Debug)]
+pub struct MockCanisterHttp {
+    pub subnet_id: SubnetId,
+    pub request_id: u64,
+    pub response: CanisterHttpResponse,
+    pub additional_responses: Vec<CanisterHttpResponse>,
+}
+
+impl From<RawMockCanisterHttpResponse> for MockCanisterHttp {
+    fn from(mock: RawMockCanisterHttpResponse) -> Self {
+        Self {
+            subnet_id: SubnetId::new(PrincipalId(candid::Principal::from_slice(
+                &mock.subnet_id.subnet_id,
+            ))),
+            request_id: mock.request_id,
+            response: mock.response,
+            additional_responses: mock.additional_responses,
+        }
+    }
+}
+
+impl MockCanisterHttp {
+    /// Returns the response of a single replica, as proposed to consensus, i.e.,
+    /// after applying the transform function of the request.
+    fn transform(
+        subnet: &StateMachine,
+        context: &CanisterHttpRequestContext,
+        response: &CanisterHttpResponse,
+    ) -> Result<CanisterHttpResponsePayload, (RejectCode, String)> {
+        let reply = match response {
+            CanisterHttpResponse::CanisterHttpReply(reply) => reply,
+            CanisterHttpResponse::CanisterHttpReject(reject) => {
+                let reject_code =
+                    RejectCode::try_from(reject.reject_code).unwrap_or(RejectCode::SysFatal);
+                return Err((reject_code, reject.message.clone()));
+            }
+        };
+        let payload = CanisterHttpResponsePayload {
+            status: reply.status as u128,
+            headers: reply
+                .headers
+                .iter()
+                .map(|header| HttpHeader {
+                    name: header.name.clone(),
+                    value: header.value.clone(),
+                })
+                .collect(),
+            body: reply.body.clone(),
+        };
+        let payload = match &context.transform {
+            None => payload,
+            Some(transform) => {
+                let transform_args = TransformArgs {
+                    response: payload,
+                    context: transform.context.clone(),
+                };
+                let result = subnet.query_as(
+                    PrincipalId::new_anonymous(),
+                    context.request.sender,
+                    transform.method_name.clone(),
+                    Encode!(&transform_args).unwrap(),
+                );
+                let bytes = match result {
+                    Ok(ic_state_machine_tests::WasmResult::Reply(bytes)) => bytes,
+                    Ok(ic_state_machine_tests::WasmResult::Reject(message)) => {
+                        return Err((RejectCode::CanisterReject, message))
+                    }
+                    Err(user_error) => {
+                        return Err((
+                            user_error.reject_code(),
+                            user_error.description().to_string(),
+                        ))
+                    }
+                };
+                CanisterHttpResponsePayload::decode(&bytes).map_err(|e| {
+                    (
+                        RejectCode::SysFatal,
+                        format!("Failed to decode transformed http response: {}", e),
+                    )
+                })?
+            }
+        };
+        let max_response_bytes = context
+            .max_response_bytes
+            .map(|bytes| bytes.get())
+            .unwrap_or(MAX_CANISTER_HTTP_RESPONSE_BYTES);
+        if payload.body.len() as u64 > max_response_bytes {
+            return Err((
+                RejectCode::SysFatal,
+                format!(
+                    "Http body exceeds size limit of {} bytes.",
+                    max_response_bytes
+                ),
+            ));
+        }
+        Ok(payload)
+    }
+}
+
+impl Operation for MockCanisterHttp {
+    type TargetType = PocketIc;
+
+    fn compute(self, pic: &mut PocketIc) -> OpOut {
+        let invalid_request_id = OpOut::Error(PocketIcError::InvalidCanisterHttpRequestId((
+            self.subnet_id,
+            self.request_id,
+        )));
+        let Some(subnet) = pic.get_subnet_with_id(self.subnet_id) else {
+            return OpOut::Error(PocketIcError::SubnetNotFound(self.subnet_id.get().0));
+        };
+        let request_id = CallbackId::from(self.request_id);
+        let Some(context) = subnet.canister_http_request_contexts().remove(&request_id) else {
+            return invalid_request_id;
+        };
+
+        let subnet_size = pic
+            .topology
+            .0
+            .get(&self.subnet_id.get().0)
+            .map(|config| config.size as usize)
+            .unwrap_or(1);
+        let responses: Vec<_> = std::iter::once(&self.response)
+            .chain(self.additional_responses.iter())
+            .collect();
+        if !self.additional_responses.is_empty() && responses.len() != subnet_size {
+            return OpOut::Error(PocketIcError::BadIngressMessage(format!(
+                "Expected a response for each of the {} replicas of subnet {}, but got {}",
+                subnet_size,
+                self.subnet_id,
+                responses.len()
+            )));
+        }
+        let transformed: Vec<_> = responses
+            .into_iter()
+            .map(|response| Self::transform(&subnet, &context, response))
+            .collect();
+
+        // A response is agreed upon if it is proposed by all but `f` replicas,
+        // where `f` is the number of faulty replicas tolerated by the subnet.
+        let faults_tolerated = (transformed.len() - 1) / 3;
+        let agreed = transformed.iter().find(|candidate| {
+            transformed
+                .iter()
+                .filter(|other| other == candidate)
+                .count()
+                >= transformed.len() - faults_tolerated
+        });
+        let payload_builder = match agreed {
+            Some(Ok(payload)) => PayloadBuilder::new().http_response(request_id, payload),
+            Some(Err((reject_code, message))) => {
+                PayloadBuilder::new().http_response_failure(request_id, *reject_code, message)
+            }
+            None => PayloadBuilder::new().http_response_failure(
+                request_id,
+                RejectCode::SysTransient,
+                "No consensus could be reached. Replicas had different responses.",
+            ),
+        };
+        subnet.execute_payload(payload_builder);
+        OpOut::NoOutput
+    }
+
+    fn id(&self) -> OpId {
+        OpId(format!(
+            "mock_canister_http({},{},{:?},{:?})",
+            self.subnet_id, self.request_id, self.response, self.additional_responses
+        ))
+    }
+}
+
+/// Mines blocks on the simulated Bitcoin network of the instance. The first block
+/// pays the given outputs and includes the transactions sent by canisters that are
+/// not in a block yet.
+#[derive(Clone, Debug)]
+pub struct MineBitcoinBlocks {
+    pub outputs: Vec<(bitcoin::Address, u64)>,
+    pub num_blocks: u32,
+    pub parent: Option<bitcoin::BlockHash>,
+}
+
+impl TryFrom<RawMineBitcoinBlocks> for MineBitcoinBlocks {
+    type Error = String;
+    fn try_from(raw: RawMineBitcoinBlocks) -> Result<Self, Self::Error> {
+        let outputs = raw
+            .outputs
+            .into_iter()
+            .map(|BitcoinOutput { address, value }| {
+                bitcoin::Address::from_str(&address)
+                    .map(|address| (address, value))
+                    .map_err(|e| format!("Invalid Bitcoin address {}: {}", address, e))
+            })
+            .collect::<Result<_, _>>()?;
+        let parent = raw
+            .parent
+            .map(|parent| {
+                bitcoin::BlockHash::from_str(&parent)
+                    .map_err(|e| format!("Invalid block hash {}: {}", parent, e))
+            })
+            .transpose()?;
+        Ok(Self {
+            outputs,
+            num_blocks: raw.num_blocks,
+            parent,
+        })
+    }
+}
+
+impl Operation for MineBitcoinBlocks {
+    type TargetType = PocketIc;
+
+    fn compute(self, pic: &mut PocketIc) -> OpOut {
+        let network = &pic.bitcoin_network;
+        let mut parent = self.parent.unwrap_or_else(|| network.tip());
+        if network.block(&parent).is_none() {
+            return OpOut::Error(PocketIcError::BadIngressMessage(format!(
+                "Unknown Bitcoin block {}",
+                parent
+            )));
+        }
+        let mut hashes = vec![];
+        for i in 0..self.num_blocks {
+            let (outputs, transactions) = if i == 0 {
+                (&self.outputs[..], network.mempool())
+            } else {
+                (&[][..], vec![])
+            };
+            parent = network.mine_block_on(parent, outputs, transactions);
+            hashes.push(parent.to_string());
+        }
+        OpOut::BitcoinBlockHashes(hashes)
+    }
+
+    fn id(&self) -> OpId {
+        let outputs: Vec<_> = self
+            .outputs
+            .iter()
+            .map(|(address, value)| format!("{}:{}", address, value))
+            .collect();
+        OpId(format!(
+            "mine_bitcoin_blocks({:?},{},{:?})",
+            outputs,
+            self.num_blocks,
+            self.parent.map(|parent| parent.to_string())
+        ))
+    }
+}
+
+/// Lists the transactions sent by canisters to the simulated Bitcoin network.
+#[derive(Clone, Debug)]
+pub struct GetBitcoinTransactions;
+
+impl Operation for GetBitcoinTransactions {
+    type TargetType = PocketIc;
+
+    fn compute(self, pic: &mut PocketIc) -> OpOut {
+        OpOut::BitcoinTransactions(
+            pic.bitcoin_network
+                .sent_transactions()
+                .iter()
+                .map(bitcoin::consensus::serialize)
+                .collect(),
+        )
+    }
+
+    fn id(&self) -> OpId {
+        OpId("get_bitcoin_transactions".to_string())
     }
 }
 
@@ -983,6 +2099,46 @@ fn route_call(
     }
 }
 
+/// Serializes the given value to CBOR, prefixed with the "self describing" tag.
+fn into_cbor<R: Serialize>(r: &R) -> Vec<u8> {
+    let mut ser = serde_cbor::Serializer::new(Vec::new());
+    ser.self_describe().expect("Could not write magic tag.");
+    r.serialize(&mut ser).expect("Serialization failed.");
+    ser.into_inner()
+}
+
+fn gateway_error(status_code: u16, message: String) -> HttpGatewayResponse {
+    HttpGatewayResponse {
+        status_code,
+        headers: vec![],
+        body: message.into_bytes(),
+    }
+}
+
+/// Returns the reply of a canister to a request made by the HTTP gateway.
+fn canister_reply(
+    result: Result<ic_state_machine_tests::WasmResult, ic_state_machine_tests::UserError>,
+) -> Result<Vec<u8>, HttpGatewayResponse> {
+    match result {
+        Ok(ic_state_machine_tests::WasmResult::Reply(reply)) => Ok(reply),
+        Ok(ic_state_machine_tests::WasmResult::Reject(message)) => Err(gateway_error(
+            502,
+            format!("Canister rejected the request: {}", message),
+        )),
+        Err(user_error) => Err(gateway_error(
+            502,
+            format!("Canister failed to serve the request: {}", user_error),
+        )),
+    }
+}
+
+fn decode_http_response<S: CandidType + for<'de> Deserialize<'de>>(
+    reply: &[u8],
+) -> Result<HttpRequestReply<S>, HttpGatewayResponse> {
+    Decode!(reply, HttpRequestReply<S>)
+        .map_err(|e| gateway_error(502, format!("Failed to decode HTTP response: {}", e)))
+}
+
 fn systemtime_to_unix_epoch_nanos(st: SystemTime) -> u64 {
     st.duration_since(SystemTime::UNIX_EPOCH)
         .unwrap()
@@ -994,6 +2150,10 @@ fn systemtime_to_unix_epoch_nanos(st: SystemTime) -> u64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::state_api::state::IngressMessageStatus;
+    use ic_ic00_types::{
+        BoundedHttpHeaders, CanisterHttpRequestArgs, HttpMethod, TransformContext, TransformFunc,
+    };
 
     #[test]
     fn state_label_test() {
@@ -1047,6 +2207,61 @@ mod tests {
         assert_eq!(expected_time, actual_time);
     }
 
+    #[test]
+    fn test_persisted_instance() {
+        let state_dir = TempDir::new().unwrap();
+        let subnet_configs = ExtendedSubnetConfigSet {
+            application: vec![SubnetSpec::New],
+            ..Default::default()
+        };
+        let new_pic = |subnet_configs| {
+            PocketIc::new(
+                Runtime::new().unwrap().into(),
+                subnet_configs,
+                Some(state_dir.path().to_path_buf()),
+            )
+        };
+        assert_eq!(PocketIc::read_subnet_configs(state_dir.path()), Ok(None));
+
+        let mut pic = new_pic(subnet_configs.clone());
+        let canister_id = pic.any_subnet().create_canister(None);
+        AddCycles {
+            canister_id,
+            amount: 20_000_000_000_000,
+        }
+        .compute(&mut pic);
+        InstallCanisterAsController {
+            canister_id,
+            mode: CanisterInstallMode::Install,
+            module: counter_wasm(),
+            payload: vec![],
+        }
+        .compute(&mut pic);
+        let (query, update) = query_update_constructors(canister_id);
+        compute_assert_state_change(&mut pic, update("write"));
+        let OpOut::Cycles(cycles) = GetCyclesBalance { canister_id }.compute(&mut pic) else {
+            unreachable!()
+        };
+        let time = GetTime {}.compute(&mut pic);
+        let topology = pic.topology.clone();
+        drop(pic);
+
+        // The instance is resumed with the subnet configs stored in the state directory.
+        let stored_subnet_configs = PocketIc::read_subnet_configs(state_dir.path()).unwrap();
+        assert_eq!(stored_subnet_configs, Some(subnet_configs));
+        let mut pic = new_pic(stored_subnet_configs.unwrap());
+        assert_eq!(pic.topology, topology);
+        assert_eq!(GetTime {}.compute(&mut pic), time);
+        assert_eq!(
+            GetCyclesBalance { canister_id }.compute(&mut pic),
+            OpOut::Cycles(cycles)
+        );
+        assert_eq!(
+            query("read").compute(&mut pic),
+            OpOut::CanisterResult(Ok(pocket_ic::WasmResult::Reply(vec![1, 0, 0, 0])))
+        );
+    }
+
     #[test]
     fn test_execute_message() {
         let (mut pic, canister_id) = new_pic_counter_installed();
@@ -1068,6 +2283,55 @@ mod tests {
         compute_assert_state_change(&mut pic, update);
     }
 
+    #[test]
+    fn test_submit_ingress_message() {
+        let (mut pic, canister_id) = new_pic_counter_installed();
+        let submit = || {
+            SubmitIngressMessage(CanisterCall {
+                sender: PrincipalId::new_anonymous(),
+                canister_id,
+                method: "write".into(),
+                payload: vec![],
+                effective_principal: EffectivePrincipal::None,
+            })
+        };
+        let status = |subnet_id, message_id: &Vec<u8>| GetIngressStatus {
+            subnet_id,
+            message_id: MessageId::try_from(&message_id[..]).unwrap(),
+        };
+
+        // Both messages are in flight when the round is executed.
+        let OpOut::MessageId((subnet_id, msg_id_1)) =
+            compute_assert_state_immutable(&mut pic, submit())
+        else {
+            unreachable!()
+        };
+        let OpOut::MessageId((_, msg_id_2)) = compute_assert_state_immutable(&mut pic, submit())
+        else {
+            unreachable!()
+        };
+        assert_ne!(msg_id_1, msg_id_2);
+        assert_eq!(
+            compute_assert_state_immutable(&mut pic, status(subnet_id, &msg_id_1)),
+            OpOut::IngressStatus(IngressMessageStatus::Unknown)
+        );
+
+        compute_assert_state_change(&mut pic, Tick);
+        let replies: Vec<_> = [msg_id_1, msg_id_2]
+            .iter()
+            .map(|msg_id| {
+                let OpOut::IngressStatus(IngressMessageStatus::Completed(Ok(
+                    pocket_ic::WasmResult::Reply(reply),
+                ))) = compute_assert_state_immutable(&mut pic, status(subnet_id, msg_id))
+                else {
+                    unreachable!()
+                };
+                reply
+            })
+            .collect();
+        assert_eq!(replies, vec![vec![1, 0, 0, 0], vec![2, 0, 0, 0]]);
+    }
+
     #[test]
     fn test_cycles_burn_app_subnet() {
         let (mut pic, canister_id) = new_pic_counter_installed();
@@ -1105,6 +2369,291 @@ mod tests {
         assert_eq!(initial_balance, new_balance);
     }
 
+    #[test]
+    fn test_api_v2_query_request() {
+        let (mut pic, canister_id) = new_pic_counter_installed();
+        let query = QueryRequest {
+            effective_canister_id: canister_id,
+            query: UserQuery {
+                receiver: canister_id,
+                source: PrincipalId::new_anonymous().into(),
+                method_name: "read".into(),
+                method_payload: vec![],
+                ingress_expiry: 0,
+                nonce: None,
+            },
+        };
+
+        let OpOut::Bytes(bytes) = query.compute(&mut pic) else {
+            unreachable!()
+        };
+        let response: HttpQueryResponse = serde_cbor::from_slice(&bytes).unwrap();
+        assert_eq!(
+            response,
+            HttpQueryResponse::Replied {
+                reply: HttpQueryResponseReply {
+                    arg: Blob(vec![0, 0, 0, 0])
+                }
+            }
+        );
+    }
+
+    #[test]
+    fn test_api_v2_status_and_read_state_requests() {
+        let (mut pic, canister_id) = new_pic_counter_installed();
+
+        let OpOut::Bytes(bytes) = StatusRequest.compute(&mut pic) else {
+            unreachable!()
+        };
+        let status: HttpStatusResponse = serde_cbor::from_slice(&bytes).unwrap();
+        let root_key = threshold_sig_public_key_to_der(pic.root_subnet().root_key()).unwrap();
+        assert_eq!(status.root_key, Some(Blob(root_key)));
+
+        let read_state = ReadStateRequest {
+            effective_canister_id: canister_id,
+            read_state: ReadState {
+                source: PrincipalId::new_anonymous().into(),
+                paths: vec![],
+                ingress_expiry: 0,
+                nonce: None,
+            },
+        };
+        let OpOut::Bytes(bytes) = read_state.compute(&mut pic) else {
+            unreachable!()
+        };
+        let response: HttpReadStateResponse = serde_cbor::from_slice(&bytes).unwrap();
+        let certificate: ic_types::messages::Certificate =
+            serde_cbor::from_slice(&response.certificate.0).unwrap();
+        // The instance consists of a single subnet, which is its own root subnet.
+        assert_eq!(certificate.delegation, None);
+    }
+
+    #[test]
+    fn test_http_gateway_request() {
+        let (mut pic, canister_id) = new_pic_counter_installed();
+        let request = |canister_id| HttpGatewayRequest {
+            canister_id,
+            skip_verification: false,
+            method: "GET".to_string(),
+            url: "/".to_string(),
+            headers: vec![],
+            body: vec![],
+        };
+
+        let OpOut::HttpGatewayResponse(response) =
+            request(CanisterId::from_u64(42)).compute(&mut pic)
+        else {
+            unreachable!()
+        };
+        assert_eq!(response.status_code, 404);
+
+        // The counter canister does not export an `http_request` method.
+        let OpOut::HttpGatewayResponse(response) = request(canister_id).compute(&mut pic) else {
+            unreachable!()
+        };
+        assert_eq!(response.status_code, 502);
+    }
+
+    fn http_gateway_get(canister_id: CanisterId, skip_verification: bool) -> HttpGatewayRequest {
+        HttpGatewayRequest {
+            canister_id,
+            skip_verification,
+            method: "GET".to_string(),
+            url: "/".to_string(),
+            headers: vec![],
+            body: vec![],
+        }
+    }
+
+    #[test]
+    fn test_http_gateway_request_with_valid_certification() {
+        let (mut pic, canister_id) = new_pic_certified_http_canister_installed(b"certified");
+
+        let OpOut::HttpGatewayResponse(response) =
+            http_gateway_get(canister_id, false).compute(&mut pic)
+        else {
+            unreachable!()
+        };
+        assert_eq!(response.status_code, 200);
+        assert_eq!(response.body, b"certified");
+    }
+
+    #[test]
+    fn test_http_gateway_request_with_invalid_certification() {
+        let (mut pic, canister_id) = new_pic_certified_http_canister_installed(b"tampered");
+
+        let OpOut::HttpGatewayResponse(response) =
+            http_gateway_get(canister_id, false).compute(&mut pic)
+        else {
+            unreachable!()
+        };
+        assert_eq!(response.status_code, 500);
+        assert_eq!(response.body, b"Body does not pass verification");
+
+        // The raw domain serves the response without verifying it.
+        let OpOut::HttpGatewayResponse(response) =
+            http_gateway_get(canister_id, true).compute(&mut pic)
+        else {
+            unreachable!()
+        };
+        assert_eq!(response.status_code, 200);
+        assert_eq!(response.body, b"tampered");
+    }
+
+    #[test]
+    fn test_mock_canister_http_unknown_request() {
+        let mut pic = PocketIc::default();
+        let subnet_id = pic.any_subnet().get_subnet_id();
+
+        let OpOut::CanisterHttp(canister_http_requests) = GetCanisterHttp.compute(&mut pic) else {
+            unreachable!()
+        };
+        assert!(canister_http_requests.is_empty());
+
+        let mock = MockCanisterHttp {
+            subnet_id,
+            request_id: 0,
+            response: CanisterHttpResponse::CanisterHttpReject(rest::CanisterHttpReject {
+                reject_code: RejectCode::SysTransient as u64,
+                message: "mocked reject".to_string(),
+            }),
+            additional_responses: vec![],
+        };
+        assert_eq!(
+            mock.compute(&mut pic),
+            OpOut::Error(PocketIcError::InvalidCanisterHttpRequestId((subnet_id, 0)))
+        );
+    }
+
+    #[test]
+    fn test_mock_canister_http_response() {
+        let (mut pic, canister_id) = new_pic_http_canister_installed();
+        let (status, request) = start_canister_http(&mut pic, canister_id, false);
+        assert_eq!(request.url, "https://example.com");
+        assert_eq!(request.http_method, CanisterHttpMethod::GET);
+        assert_eq!(request.max_response_bytes, Some(1000));
+
+        let mock = MockCanisterHttp {
+            subnet_id: SubnetId::new(PrincipalId(request.subnet_id)),
+            request_id: request.request_id,
+            response: http_reply(b"mocked"),
+            additional_responses: vec![],
+        };
+        compute_assert_state_change(&mut pic, mock.clone());
+        let pocket_ic::WasmResult::Reply(reply) = canister_http_result(&mut pic, status) else {
+            unreachable!()
+        };
+        assert_eq!(
+            Decode!(&reply, CanisterHttpResponsePayload).unwrap(),
+            CanisterHttpResponsePayload {
+                status: 200,
+                headers: vec![HttpHeader {
+                    name: "content-type".to_string(),
+                    value: "text/plain".to_string(),
+                }],
+                body: b"mocked".to_vec(),
+            }
+        );
+
+        // The request is no longer pending.
+        let OpOut::CanisterHttp(canister_http_requests) = GetCanisterHttp.compute(&mut pic) else {
+            unreachable!()
+        };
+        assert!(canister_http_requests.is_empty());
+        assert_eq!(
+            mock.compute(&mut pic),
+            OpOut::Error(PocketIcError::InvalidCanisterHttpRequestId((
+                SubnetId::new(PrincipalId(request.subnet_id)),
+                request.request_id
+            )))
+        );
+    }
+
+    #[test]
+    fn test_mock_canister_http_response_is_transformed() {
+        let (mut pic, canister_id) = new_pic_http_canister_installed();
+        let transformed = CanisterHttpResponsePayload {
+            status: 201,
+            headers: vec![],
+            body: b"transformed".to_vec(),
+        };
+        compute_assert_state_change(
+            &mut pic,
+            ExecuteIngressMessage(CanisterCall {
+                sender: PrincipalId::new_anonymous(),
+                canister_id,
+                method: "set_transform_reply".into(),
+                payload: Encode!(&transformed).unwrap(),
+                effective_principal: EffectivePrincipal::None,
+            }),
+        );
+
+        let (status, request) = start_canister_http(&mut pic, canister_id, true);
+        let mock = MockCanisterHttp {
+            subnet_id: SubnetId::new(PrincipalId(request.subnet_id)),
+            request_id: request.request_id,
+            response: http_reply(b"mocked"),
+            additional_responses: vec![],
+        };
+        compute_assert_state_change(&mut pic, mock);
+        let pocket_ic::WasmResult::Reply(reply) = canister_http_result(&mut pic, status) else {
+            unreachable!()
+        };
+        assert_eq!(
+            Decode!(&reply, CanisterHttpResponsePayload).unwrap(),
+            transformed
+        );
+    }
+
+    #[test]
+    fn test_mock_canister_http_responses_require_agreement() {
+        let (mut pic, canister_id) = new_pic_http_canister_installed();
+        // An application subnet consists of 13 replicas and tolerates 4 faulty ones.
+        let mock = |request: CanisterHttpRequest, disagreeing: usize| {
+            let mut responses: Vec<_> = (0..13)
+                .map(|i| {
+                    if i < disagreeing {
+                        http_reply(format!("disagreeing {}", i).as_bytes())
+                    } else {
+                        http_reply(b"agreed")
+                    }
+                })
+                .collect();
+            MockCanisterHttp {
+                subnet_id: SubnetId::new(PrincipalId(request.subnet_id)),
+                request_id: request.request_id,
+                response: responses.remove(0),
+                additional_responses: responses,
+            }
+        };
+
+        // All but 4 replicas agree on the response.
+        let (status, request) = start_canister_http(&mut pic, canister_id, false);
+        compute_assert_state_change(&mut pic, mock(request, 4));
+        let pocket_ic::WasmResult::Reply(reply) = canister_http_result(&mut pic, status) else {
+            unreachable!()
+        };
+        let payload = Decode!(&reply, CanisterHttpResponsePayload).unwrap();
+        assert_eq!(payload.body, b"agreed".to_vec());
+
+        // Too many replicas disagree.
+        let (status, request) = start_canister_http(&mut pic, canister_id, false);
+        compute_assert_state_change(&mut pic, mock(request, 5));
+        let pocket_ic::WasmResult::Reject(message) = canister_http_result(&mut pic, status) else {
+            unreachable!()
+        };
+        assert!(message.contains("No consensus could be reached"));
+
+        // A response must be given for every replica.
+        let (_, request) = start_canister_http(&mut pic, canister_id, false);
+        let mut incomplete = mock(request, 0);
+        incomplete.additional_responses.pop();
+        assert!(matches!(
+            incomplete.compute(&mut pic),
+            OpOut::Error(PocketIcError::BadIngressMessage(_))
+        ));
+    }
+
     fn query_update_constructors(
         canister_id: CanisterId,
     ) -> (
@@ -1156,6 +2705,7 @@ mod tests {
                 ii: Some(SubnetSpec::New),
                 ..Default::default()
             },
+            None,
         );
         let canister_id = pic.any_subnet().create_canister(None);
 
@@ -1172,6 +2722,181 @@ mod tests {
         (pic, canister_id)
     }
 
+    /// Installs a canister (see `CERTIFIED_HTTP_WAT`) whose `http_request` method replies
+    /// to `GET /` with the given body and a response verification v2 certificate
+    /// for the body `certified`.
+    fn new_pic_certified_http_canister_installed(body: &[u8]) -> (PocketIc, CanisterId) {
+        use ic_http_certification::{
+            DefaultCelBuilder, DefaultResponseCertification, HttpCertification,
+            HttpCertificationPath, HttpCertificationTree, HttpCertificationTreeEntry,
+            CERTIFICATE_EXPRESSION_HEADER_NAME,
+        };
+
+        fn self_describing_cbor<T: Serialize>(value: &T) -> Vec<u8> {
+            let mut serializer = serde_cbor::Serializer::new(vec![]);
+            serializer.self_describe().unwrap();
+            value.serialize(&mut serializer).unwrap();
+            serializer.into_inner()
+        }
+
+        let cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                vec![],
+            ))
+            .build();
+        let mut headers = vec![(
+            CERTIFICATE_EXPRESSION_HEADER_NAME.to_string(),
+            cel_expr.to_string(),
+        )];
+        let certified_response = HttpResponse {
+            status_code: 200,
+            headers: headers.clone(),
+            body: b"certified".to_vec(),
+            upgrade: None,
+        };
+        let certification =
+            HttpCertification::response_only(&cel_expr, &certified_response, None).unwrap();
+        let path = HttpCertificationPath::exact("/");
+        let entry = HttpCertificationTreeEntry::new(&path, &certification);
+        let mut tree = HttpCertificationTree::default();
+        tree.insert(&entry);
+
+        let mut pic = PocketIc::default();
+        let canister_id = pic.any_subnet().create_canister(None);
+        AddCycles {
+            canister_id,
+            amount: 20_000_000_000_000,
+        }
+        .compute(&mut pic);
+        // The canister sets the root hash of the tree as its certified data on init.
+        let install_op = InstallCanisterAsController {
+            canister_id,
+            mode: CanisterInstallMode::Install,
+            module: wat::parse_str(CERTIFIED_HTTP_WAT).unwrap(),
+            payload: tree.root_hash().to_vec(),
+        };
+        compute_assert_state_change(&mut pic, install_op);
+
+        let call = |method: &str, payload: Vec<u8>| CanisterCall {
+            sender: PrincipalId::new_anonymous(),
+            canister_id,
+            method: method.into(),
+            payload,
+            effective_principal: EffectivePrincipal::None,
+        };
+        let OpOut::CanisterResult(Ok(pocket_ic::WasmResult::Reply(certificate))) =
+            Query(call("data_certificate", vec![])).compute(&mut pic)
+        else {
+            unreachable!()
+        };
+        let witness = tree.witness(&entry, "/").unwrap();
+        headers.push((
+            IC_CERTIFICATE_HEADER_NAME.to_string(),
+            format!(
+                "certificate=:{}:, tree=:{}:, expr_path=:{}:, version=2",
+                base64::encode(certificate),
+                base64::encode(self_describing_cbor(&witness)),
+                base64::encode(self_describing_cbor(&path.to_expr_path())),
+            ),
+        ));
+        let response = HttpRequestReply::<candid::Reserved> {
+            status_code: 200,
+            headers,
+            body: body.to_vec(),
+            streaming_strategy: None,
+            upgrade: None,
+        };
+        compute_assert_state_change(
+            &mut pic,
+            ExecuteIngressMessage(call("set_http_response", Encode!(&response).unwrap())),
+        );
+        (pic, canister_id)
+    }
+
+    fn new_pic_http_canister_installed() -> (PocketIc, CanisterId) {
+        let mut pic = PocketIc::default();
+        let canister_id = pic.any_subnet().create_canister(None);
+        AddCycles {
+            canister_id,
+            amount: 20_000_000_000_000,
+        }
+        .compute(&mut pic);
+        let install_op = InstallCanisterAsController {
+            canister_id,
+            mode: CanisterInstallMode::Install,
+            module: wat::parse_str(HTTP_WAT).unwrap(),
+            payload: vec![],
+        };
+        compute_assert_state_change(&mut pic, install_op);
+        (pic, canister_id)
+    }
+
+    /// Makes the canister (see `HTTP_WAT`) perform a canister HTTP request and returns
+    /// the operation fetching the status of its ingress message and the pending request.
+    fn start_canister_http(
+        pic: &mut PocketIc,
+        canister_id: CanisterId,
+        with_transform: bool,
+    ) -> (GetIngressStatus, CanisterHttpRequest) {
+        let args = CanisterHttpRequestArgs {
+            url: "https://example.com".to_string(),
+            max_response_bytes: Some(1000),
+            headers: BoundedHttpHeaders::new(vec![]),
+            body: None,
+            method: HttpMethod::GET,
+            transform: with_transform.then(|| TransformContext {
+                function: TransformFunc(candid::Func {
+                    principal: canister_id.get().0,
+                    method: "transform".to_string(),
+                }),
+                context: vec![],
+            }),
+        };
+        let OpOut::MessageId((subnet_id, message_id)) = SubmitIngressMessage(CanisterCall {
+            sender: PrincipalId::new_anonymous(),
+            canister_id,
+            method: "http_request".into(),
+            payload: Encode!(&args).unwrap(),
+            effective_principal: EffectivePrincipal::None,
+        })
+        .compute(pic) else {
+            unreachable!()
+        };
+        // One round for the canister to call the management canister
+        // and one for the management canister to start processing the request.
+        Tick.compute(pic);
+        Tick.compute(pic);
+        let OpOut::CanisterHttp(mut canister_http_requests) = GetCanisterHttp.compute(pic) else {
+            unreachable!()
+        };
+        assert_eq!(canister_http_requests.len(), 1);
+        let status = GetIngressStatus {
+            subnet_id,
+            message_id: MessageId::try_from(&message_id[..]).unwrap(),
+        };
+        (status, canister_http_requests.pop().unwrap())
+    }
+
+    fn canister_http_result(pic: &mut PocketIc, status: GetIngressStatus) -> pocket_ic::WasmResult {
+        Tick.compute(pic);
+        let OpOut::IngressStatus(IngressMessageStatus::Completed(Ok(result))) = status.compute(pic)
+        else {
+            unreachable!()
+        };
+        result
+    }
+
+    fn http_reply(body: &[u8]) -> CanisterHttpResponse {
+        CanisterHttpResponse::CanisterHttpReply(rest::CanisterHttpReply {
+            status: 200,
+            headers: vec![CanisterHttpHeader {
+                name: "content-type".to_string(),
+                value: "text/plain".to_string(),
+            }],
+            body: body.to_vec(),
+        })
+    }
+
     fn compute_assert_state_change<O>(pic: &mut PocketIc, op: O) -> OpOut
     where
         O: Operation<TargetType = PocketIc>,
@@ -1231,6 +2956,105 @@ mod tests {
   (export "canister_query read" (func $read))
   (export "canister_query inc_read" (func $write))
   (export "canister_update write" (func $write))
+)
+    "#;
+
+    /// Canister whose `http_request` method replies with the response set via
+    /// `set_http_response` and which sets its certified data to its init argument.
+    const CERTIFIED_HTTP_WAT: &str = r#"
+(module
+  (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i32)))
+  (import "ic0" "msg_arg_data_copy" (func $msg_arg_data_copy (param i32 i32 i32)))
+  (import "ic0" "msg_reply" (func $msg_reply))
+  (import "ic0" "msg_reply_data_append" (func $msg_reply_data_append (param i32 i32)))
+  (import "ic0" "certified_data_set" (func $certified_data_set (param i32 i32)))
+  (import "ic0" "data_certificate_size" (func $data_certificate_size (result i32)))
+  (import "ic0" "data_certificate_copy" (func $data_certificate_copy (param i32 i32 i32)))
+
+  (func $init
+    (call $msg_arg_data_copy (i32.const 0) (i32.const 0) (call $msg_arg_data_size))
+    (call $certified_data_set (i32.const 0) (call $msg_arg_data_size)))
+
+  (func $data_certificate
+    (call $data_certificate_copy (i32.const 0) (i32.const 0) (call $data_certificate_size))
+    (call $msg_reply_data_append (i32.const 0) (call $data_certificate_size))
+    (call $msg_reply))
+
+  (func $set_http_response
+    (global.set $http_response_size (call $msg_arg_data_size))
+    (call $msg_arg_data_copy (i32.const 32768) (i32.const 0) (global.get $http_response_size))
+    (call $msg_reply))
+
+  (func $http_request
+    (call $msg_reply_data_append (i32.const 32768) (global.get $http_response_size))
+    (call $msg_reply))
+
+  (memory $memory 1)
+  (global $http_response_size (mut i32) (i32.const 0))
+  (export "memory" (memory $memory))
+  (export "canister_init" (func $init))
+  (export "canister_query data_certificate" (func $data_certificate))
+  (export "canister_update set_http_response" (func $set_http_response))
+  (export "canister_query http_request" (func $http_request))
+)
+    "#;
+
+    /// A canister forwarding the argument of its `http_request` method to the `http_request`
+    /// method of the management canister and replying with the result. Its `transform` query
+    /// replies with the argument of the latest call to `set_transform_reply`.
+    const HTTP_WAT: &str = r#"
+(module
+  (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i32)))
+  (import "ic0" "msg_arg_data_copy" (func $msg_arg_data_copy (param i32 i32 i32)))
+  (import "ic0" "msg_reject_msg_size" (func $msg_reject_msg_size (result i32)))
+  (import "ic0" "msg_reject_msg_copy" (func $msg_reject_msg_copy (param i32 i32 i32)))
+  (import "ic0" "msg_reply" (func $msg_reply))
+  (import "ic0" "msg_reply_data_append" (func $msg_reply_data_append (param i32 i32)))
+  (import "ic0" "msg_reject" (func $msg_reject (param i32 i32)))
+  (import "ic0" "call_new"
+    (func $call_new (param i32 i32 i32 i32 i32 i32 i32 i32)))
+  (import "ic0" "call_data_append" (func $call_data_append (param i32 i32)))
+  (import "ic0" "call_cycles_add128" (func $call_cycles_add128 (param i64 i64)))
+  (import "ic0" "call_perform" (func $call_perform (result i32)))
+
+  (func $http_request
+    (call $msg_arg_data_copy (i32.const 1024) (i32.const 0) (call $msg_arg_data_size))
+    ;; The management canister has an empty ID.
+    (call $call_new
+      (i32.const 0) (i32.const 0)
+      (i32.const 0) (i32.const 12)
+      (i32.const 0) (i32.const 0)
+      (i32.const 1) (i32.const 0))
+    (call $call_data_append (i32.const 1024) (call $msg_arg_data_size))
+    (call $call_cycles_add128 (i64.const 0) (i64.const 100000000000))
+    (drop (call $call_perform)))
+
+  (func $on_reply (param i32)
+    (call $msg_arg_data_copy (i32.const 1024) (i32.const 0) (call $msg_arg_data_size))
+    (call $msg_reply_data_append (i32.const 1024) (call $msg_arg_data_size))
+    (call $msg_reply))
+
+  (func $on_reject (param i32)
+    (call $msg_reject_msg_copy (i32.const 1024) (i32.const 0) (call $msg_reject_msg_size))
+    (call $msg_reject (i32.const 1024) (call $msg_reject_msg_size)))
+
+  (func $set_transform_reply
+    (global.set $transform_reply_size (call $msg_arg_data_size))
+    (call $msg_arg_data_copy (i32.const 32768) (i32.const 0) (global.get $transform_reply_size))
+    (call $msg_reply))
+
+  (func $transform
+    (call $msg_reply_data_append (i32.const 32768) (global.get $transform_reply_size))
+    (call $msg_reply))
+
+  (table funcref (elem $on_reply $on_reject))
+  (memory $memory 1)
+  (data (i32.const 0) "http_request")
+  (global $transform_reply_size (mut i32) (i32.const 0))
+  (export "memory" (memory $memory))
+  (export "canister_update http_request" (func $http_request))
+  (export "canister_update set_transform_reply" (func $set_transform_reply))
+  (export "canister_query transform" (func $transform))
 )
     "#;
 }
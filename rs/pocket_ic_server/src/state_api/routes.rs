@@ -4,32 +4,60 @@
 /// body. This has to be canonicalized into a PocketIc Operation before we can
 /// deterministically update the PocketIc state machine.
 ///
-use super::state::{InstanceState, OpOut, PocketIcApiState, PocketIcError, UpdateReply};
+use super::state::{
+    HttpGateway, IngressMessageStatus, InstanceState, OpOut, PocketIcApiState, PocketIcError,
+    UpdateReply,
+};
 use crate::pocket_ic::GetSubnet;
 use crate::pocket_ic::{
-    AddCycles, ExecuteIngressMessage, GetCyclesBalance, GetStableMemory, GetTime, PubKey, Query,
-    SetStableMemory, SetTime, Tick,
+    AddCycles, AdvanceTimeAndTick, CallRequest, ExecuteIngressMessage, GetBitcoinTransactions,
+    GetCanisterHttp, GetCyclesBalance, GetIngressStatus, GetStableMemory, GetTime,
+    HttpGatewayRequest, MineBitcoinBlocks, MockCanisterHttp, PubKey, Query, QueryRequest,
+    ReadStateRequest, SetStableMemory, SetTime, StatusRequest, SubmitIngressMessage, Tick,
 };
 use crate::{pocket_ic::PocketIc, BindOperation, BlobStore, InstanceId, Operation};
 use aide::axum::routing::{delete, get, post, ApiMethodRouter};
 use aide::axum::ApiRouter;
 use axum::{
+    body::Bytes,
     extract::{self, Path, State},
     http::{self, HeaderMap, HeaderName, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use axum_extra::headers;
 use axum_extra::headers::HeaderMapExt;
+use ic_types::messages::{
+    HttpQueryContent, HttpReadStateContent, HttpRequest, HttpRequestEnvelope, HttpRequestError,
+    ReadState, SignedIngress, SignedRequestBytes, UserQuery,
+};
 use ic_types::CanisterId;
 use pocket_ic::common::rest::{
-    self, ApiResponse, ExtendedSubnetConfigSet, RawAddCycles, RawCanisterCall, RawCanisterId,
-    RawCanisterResult, RawCycles, RawSetStableMemory, RawStableMemory, RawSubnetId, RawTime,
-    RawWasmResult,
+    self, ApiResponse, AutoProgressConfig, HttpGatewayConfig, HttpGatewayInfo, RawAddCycles,
+    RawBitcoinTransaction, RawCanisterCall, RawCanisterHttpRequest, RawCanisterId,
+    RawCanisterResult, RawCycles, RawIngressStatus, RawMessageId, RawMineBitcoinBlocks,
+    RawMockCanisterHttpResponse, RawSetStableMemory, RawStableMemory, RawSubmitIngressResult,
+    RawSubnetId, RawTime, RawWasmResult,
 };
-use pocket_ic::WasmResult;
+use pocket_ic::{UserError, WasmResult};
 use serde::Serialize;
-use std::{sync::Arc, time::Duration};
-use tokio::{runtime::Runtime, sync::RwLock, time::Instant};
+use std::{str::FromStr, sync::Arc, time::Duration};
+use tokio::{
+    runtime::Runtime,
+    sync::{oneshot, RwLock},
+    time::Instant,
+};
+use tracing::error;
+
+/// The content type of the responses served by the IC HTTP interface of an instance.
+const CONTENT_TYPE_CBOR: &str = "application/cbor";
+
+/// How long requests to the IC HTTP interface of an instance wait for a busy instance.
+const API_V2_BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+const API_V2_BUSY_RETRY_DELAY: Duration = Duration::from_millis(10);
+
+/// The default interval between two rounds of an instance in auto-progress mode.
+const DEFAULT_AUTO_PROGRESS_ROUND_INTERVAL_MS: u64 = 100;
 
 /// Name of a header that allows clients to specify for how long their are willing to wait for a
 /// response on a open http request.
@@ -57,6 +85,12 @@ where
         .directory_route("/get_stable_memory", post(handler_get_stable_memory))
         .directory_route("/get_subnet", post(handler_get_subnet))
         .directory_route("/pub_key", post(handler_pub_key))
+        .directory_route("/get_canister_http", get(handler_get_canister_http))
+        .directory_route("/ingress_status", post(handler_ingress_status))
+        .directory_route(
+            "/get_bitcoin_transactions",
+            get(handler_get_bitcoin_transactions),
+        )
 }
 
 pub fn instance_update_routes<S>() -> ApiRouter<S>
@@ -69,10 +103,39 @@ where
             "/execute_ingress_message",
             post(handler_execute_ingress_message),
         )
+        .directory_route(
+            "/submit_ingress_message",
+            post(handler_submit_ingress_message),
+        )
         .directory_route("/set_time", post(handler_set_time))
         .directory_route("/add_cycles", post(handler_add_cycles))
         .directory_route("/set_stable_memory", post(handler_set_stable_memory))
         .directory_route("/tick", post(handler_tick))
+        .directory_route("/mock_canister_http", post(handler_mock_canister_http))
+        .directory_route("/mine_bitcoin_blocks", post(handler_mine_bitcoin_blocks))
+}
+
+/// The IC HTTP interface of an instance, as used by agents (e.g., dfx).
+/// Requests and responses are CBOR-encoded rather than JSON-encoded.
+pub fn instance_api_v2_routes<S>() -> ApiRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+    AppState: extract::FromRef<S>,
+{
+    ApiRouter::new()
+        .route("/status", axum::routing::get(handler_api_v2_status))
+        .route(
+            "/canister/:ecid/call",
+            axum::routing::post(handler_api_v2_call),
+        )
+        .route(
+            "/canister/:ecid/query",
+            axum::routing::post(handler_api_v2_query),
+        )
+        .route(
+            "/canister/:ecid/read_state",
+            axum::routing::post(handler_api_v2_read_state),
+        )
 }
 
 pub fn instances_routes<S>() -> ApiRouter<S>
@@ -92,11 +155,20 @@ where
         // Deletes an instance.
         .directory_route("/:id", delete(delete_instance))
         //
+        // Makes an instance progress automatically (time and rounds).
+        .directory_route("/:id/auto_progress", post(auto_progress))
+        //
+        // Stops the automatic progress of an instance.
+        .directory_route("/:id/stop_progress", post(stop_progress))
+        //
         // All the read-only endpoints
         .nest("/:id/read", instance_read_routes())
         //
         // All the state-changing endpoints
         .nest("/:id/update", instance_update_routes())
+        //
+        // The IC HTTP interface endpoints
+        .nest("/:id/api/v2", instance_api_v2_routes())
 }
 
 async fn run_operation<T: Serialize>(
@@ -163,6 +235,12 @@ impl From<OpOut> for (StatusCode, ApiResponse<()>) {
     fn from(value: OpOut) -> Self {
         match value {
             OpOut::NoOutput => (StatusCode::OK, ApiResponse::Success(())),
+            OpOut::Error(e) => (
+                StatusCode::BAD_REQUEST,
+                ApiResponse::Error {
+                    message: format!("{:?}", e),
+                },
+            ),
             _ => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 ApiResponse::Error {
@@ -206,21 +284,25 @@ impl From<OpOut> for (StatusCode, ApiResponse<RawStableMemory>) {
     }
 }
 
+fn into_raw_canister_result(wasm_result: Result<WasmResult, UserError>) -> RawCanisterResult {
+    match wasm_result {
+        Ok(WasmResult::Reply(wasm_result)) => {
+            RawCanisterResult::Ok(RawWasmResult::Reply(wasm_result))
+        }
+        Ok(WasmResult::Reject(error_message)) => {
+            RawCanisterResult::Ok(RawWasmResult::Reject(error_message))
+        }
+        Err(user_error) => RawCanisterResult::Err(user_error),
+    }
+}
+
 impl From<OpOut> for (StatusCode, ApiResponse<RawCanisterResult>) {
     fn from(value: OpOut) -> Self {
         match value {
-            OpOut::CanisterResult(wasm_result) => {
-                let inner = match wasm_result {
-                    Ok(WasmResult::Reply(wasm_result)) => {
-                        RawCanisterResult::Ok(RawWasmResult::Reply(wasm_result))
-                    }
-                    Ok(WasmResult::Reject(error_message)) => {
-                        RawCanisterResult::Ok(RawWasmResult::Reject(error_message))
-                    }
-                    Err(user_error) => RawCanisterResult::Err(user_error),
-                };
-                (StatusCode::OK, ApiResponse::Success(inner))
-            }
+            OpOut::CanisterResult(wasm_result) => (
+                StatusCode::OK,
+                ApiResponse::Success(into_raw_canister_result(wasm_result)),
+            ),
             OpOut::Error(e) => (
                 StatusCode::BAD_REQUEST,
                 ApiResponse::Error {
@@ -298,6 +380,131 @@ impl From<OpOut> for (StatusCode, ApiResponse<Vec<u8>>) {
     }
 }
 
+impl From<OpOut> for (StatusCode, ApiResponse<Vec<RawCanisterHttpRequest>>) {
+    fn from(value: OpOut) -> Self {
+        match value {
+            OpOut::CanisterHttp(canister_http_requests) => (
+                StatusCode::OK,
+                ApiResponse::Success(
+                    canister_http_requests
+                        .into_iter()
+                        .map(|request| request.into())
+                        .collect(),
+                ),
+            ),
+            _ => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiResponse::Error {
+                    message: "operation returned invalid type".into(),
+                },
+            ),
+        }
+    }
+}
+
+impl From<OpOut> for (StatusCode, ApiResponse<Vec<String>>) {
+    fn from(value: OpOut) -> Self {
+        match value {
+            OpOut::BitcoinBlockHashes(hashes) => (StatusCode::OK, ApiResponse::Success(hashes)),
+            OpOut::Error(e) => (
+                StatusCode::BAD_REQUEST,
+                ApiResponse::Error {
+                    message: format!("{:?}", e),
+                },
+            ),
+            _ => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiResponse::Error {
+                    message: "operation returned invalid type".into(),
+                },
+            ),
+        }
+    }
+}
+
+impl From<OpOut> for (StatusCode, ApiResponse<Vec<RawBitcoinTransaction>>) {
+    fn from(value: OpOut) -> Self {
+        match value {
+            OpOut::BitcoinTransactions(transactions) => (
+                StatusCode::OK,
+                ApiResponse::Success(
+                    transactions
+                        .into_iter()
+                        .map(|transaction| RawBitcoinTransaction { transaction })
+                        .collect(),
+                ),
+            ),
+            _ => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiResponse::Error {
+                    message: "operation returned invalid type".into(),
+                },
+            ),
+        }
+    }
+}
+
+impl From<OpOut> for (StatusCode, ApiResponse<RawSubmitIngressResult>) {
+    fn from(value: OpOut) -> Self {
+        match value {
+            OpOut::MessageId((subnet_id, message_id)) => (
+                StatusCode::OK,
+                ApiResponse::Success(RawSubmitIngressResult::Ok(RawMessageId {
+                    subnet_id: subnet_id.get().0.into(),
+                    message_id,
+                })),
+            ),
+            OpOut::CanisterResult(Err(user_error)) => (
+                StatusCode::OK,
+                ApiResponse::Success(RawSubmitIngressResult::Err(user_error)),
+            ),
+            OpOut::Error(e) => (
+                StatusCode::BAD_REQUEST,
+                ApiResponse::Error {
+                    message: format!("Submitting the ingress message returned an error: {:?}", e),
+                },
+            ),
+            _ => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiResponse::Error {
+                    message: "operation returned invalid type".into(),
+                },
+            ),
+        }
+    }
+}
+
+impl From<OpOut> for (StatusCode, ApiResponse<RawIngressStatus>) {
+    fn from(value: OpOut) -> Self {
+        match value {
+            OpOut::IngressStatus(status) => {
+                let status = match status {
+                    IngressMessageStatus::Unknown => RawIngressStatus::Unknown,
+                    IngressMessageStatus::Received => RawIngressStatus::Received,
+                    IngressMessageStatus::Processing => RawIngressStatus::Processing,
+                    IngressMessageStatus::Completed(result) => {
+                        RawIngressStatus::Completed(into_raw_canister_result(result))
+                    }
+                    IngressMessageStatus::Done => RawIngressStatus::Done,
+                };
+                (StatusCode::OK, ApiResponse::Success(status))
+            }
+            OpOut::Error(e) => (
+                StatusCode::BAD_REQUEST,
+                ApiResponse::Error {
+                    message: format!("{:?}", e),
+                },
+            ),
+            _ => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiResponse::Error {
+                    message: "operation returned invalid type".into(),
+                },
+            ),
+        }
+    }
+}
+
 // ----------------------------------------------------------------------------------------------------------------- //
 // Read handlers
 
@@ -417,6 +624,49 @@ pub async fn handler_pub_key(
     (code, Json(res))
 }
 
+pub async fn handler_get_canister_http(
+    State(AppState { api_state, .. }): State<AppState>,
+    headers: HeaderMap,
+    Path(instance_id): Path<InstanceId>,
+) -> (StatusCode, Json<ApiResponse<Vec<RawCanisterHttpRequest>>>) {
+    let timeout = timeout_or_default(headers);
+    let op = GetCanisterHttp;
+    let (code, response) = run_operation(&api_state, instance_id, timeout, op).await;
+    (code, Json(response))
+}
+
+pub async fn handler_get_bitcoin_transactions(
+    State(AppState { api_state, .. }): State<AppState>,
+    headers: HeaderMap,
+    Path(instance_id): Path<InstanceId>,
+) -> (StatusCode, Json<ApiResponse<Vec<RawBitcoinTransaction>>>) {
+    let timeout = timeout_or_default(headers);
+    let op = GetBitcoinTransactions;
+    let (code, response) = run_operation(&api_state, instance_id, timeout, op).await;
+    (code, Json(response))
+}
+
+pub async fn handler_ingress_status(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path(instance_id): Path<InstanceId>,
+    headers: HeaderMap,
+    extract::Json(raw_message_id): extract::Json<RawMessageId>,
+) -> (StatusCode, Json<ApiResponse<RawIngressStatus>>) {
+    let timeout = timeout_or_default(headers);
+    match GetIngressStatus::try_from(raw_message_id) {
+        Ok(op) => {
+            let (code, response) = run_operation(&api_state, instance_id, timeout, op).await;
+            (code, Json(response))
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::Error {
+                message: format!("{:?}", e),
+            }),
+        ),
+    }
+}
+
 // ----------------------------------------------------------------------------------------------------------------- //
 // Update handlers
 
@@ -443,6 +693,28 @@ pub async fn handler_execute_ingress_message(
     }
 }
 
+pub async fn handler_submit_ingress_message(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path(instance_id): Path<InstanceId>,
+    headers: HeaderMap,
+    extract::Json(raw_canister_call): extract::Json<RawCanisterCall>,
+) -> (StatusCode, Json<ApiResponse<RawSubmitIngressResult>>) {
+    let timeout = timeout_or_default(headers);
+    match crate::pocket_ic::CanisterCall::try_from(raw_canister_call) {
+        Ok(canister_call) => {
+            let op = SubmitIngressMessage(canister_call);
+            let (code, response) = run_operation(&api_state, instance_id, timeout, op).await;
+            (code, Json(response))
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::Error {
+                message: format!("{:?}", e),
+            }),
+        ),
+    }
+}
+
 pub async fn handler_set_time(
     State(AppState { api_state, .. }): State<AppState>,
     Path(instance_id): Path<InstanceId>,
@@ -515,6 +787,440 @@ pub async fn handler_tick(
     (code, Json(res))
 }
 
+pub async fn handler_mock_canister_http(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path(instance_id): Path<InstanceId>,
+    headers: HeaderMap,
+    axum::extract::Json(raw_mock): axum::extract::Json<RawMockCanisterHttpResponse>,
+) -> (StatusCode, Json<ApiResponse<()>>) {
+    let timeout = timeout_or_default(headers);
+    let op = MockCanisterHttp::from(raw_mock);
+    let (code, response) = run_operation(&api_state, instance_id, timeout, op).await;
+    (code, Json(response))
+}
+
+pub async fn handler_mine_bitcoin_blocks(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path(instance_id): Path<InstanceId>,
+    headers: HeaderMap,
+    axum::extract::Json(raw): axum::extract::Json<RawMineBitcoinBlocks>,
+) -> (StatusCode, Json<ApiResponse<Vec<String>>>) {
+    let timeout = timeout_or_default(headers);
+    match MineBitcoinBlocks::try_from(raw) {
+        Ok(op) => {
+            let (code, response) = run_operation(&api_state, instance_id, timeout, op).await;
+            (code, Json(response))
+        }
+        Err(message) => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::Error { message }),
+        ),
+    }
+}
+
+// ----------------------------------------------------------------------------------------------------------------- //
+// IC HTTP interface handlers
+
+/// Runs an operation on behalf of an agent. The IC HTTP interface has no notion of a busy
+/// instance, so the operation is retried while the instance is busy with another operation
+/// for up to [API_V2_BUSY_TIMEOUT]. An instance that stays busy is reported as temporarily
+/// unavailable. An operation that has been started but does not complete in time keeps running
+/// and is reported as accepted so that the agent can poll for its result.
+async fn run_api_v2_operation(
+    api_state: &ApiState,
+    instance_id: InstanceId,
+    op: impl Operation<TargetType = PocketIc> + Clone + Send + Sync + 'static,
+) -> Result<OpOut, Response> {
+    // Unlike the PocketIC client library, agents do not retry if the instance is busy
+    // with another operation (e.g., with a concurrent call from another agent
+    // or with a round executed in auto-progress mode).
+    let busy_until = Instant::now() + API_V2_BUSY_TIMEOUT;
+    loop {
+        match api_state.update(op.clone().on_instance(instance_id)).await {
+            Err(e) => return Err((StatusCode::BAD_REQUEST, format!("{:?}", e)).into_response()),
+            Ok(UpdateReply::Output(op_out)) => return Ok(op_out),
+            Ok(UpdateReply::Busy { .. }) if Instant::now() < busy_until => {
+                tokio::time::sleep(API_V2_BUSY_RETRY_DELAY).await;
+            }
+            Ok(UpdateReply::Started { .. }) => {
+                return Err(StatusCode::ACCEPTED.into_response());
+            }
+            Ok(UpdateReply::Busy { .. }) => {
+                return Err((
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "The instance is busy. Please try again.".to_string(),
+                )
+                    .into_response())
+            }
+        }
+    }
+}
+
+fn api_v2_response(op_out: OpOut) -> Response {
+    match op_out {
+        OpOut::Bytes(bytes) => (
+            StatusCode::OK,
+            [(http::header::CONTENT_TYPE, CONTENT_TYPE_CBOR)],
+            bytes,
+        )
+            .into_response(),
+        OpOut::NoOutput => StatusCode::ACCEPTED.into_response(),
+        // The ingress message was rejected before it was inducted, e.g.,
+        // by the canister's `canister_inspect_message` method.
+        OpOut::CanisterResult(Err(user_error)) => {
+            (StatusCode::FORBIDDEN, user_error.description).into_response()
+        }
+        OpOut::Error(PocketIcError::BadIngressMessage(message)) => {
+            (StatusCode::BAD_REQUEST, message).into_response()
+        }
+        OpOut::Error(PocketIcError::Forbidden(message)) => {
+            (StatusCode::FORBIDDEN, message).into_response()
+        }
+        op_out => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("operation returned invalid type: {:?}", op_out),
+        )
+            .into_response(),
+    }
+}
+
+fn parse_effective_canister_id(effective_canister_id: &str) -> Result<CanisterId, Response> {
+    CanisterId::from_str(effective_canister_id).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("Malformed effective canister ID: {:?}", e),
+        )
+            .into_response()
+    })
+}
+
+/// Rejects requests where `canister_id` != `effective_canister_id` for calls to
+/// canisters other than the management canister, as done by a replica.
+fn check_effective_canister_id(
+    canister_id: CanisterId,
+    effective_canister_id: CanisterId,
+) -> Result<(), Response> {
+    if canister_id != CanisterId::ic_00() && canister_id != effective_canister_id {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Specified CanisterId {} does not match effective canister id in URL {}",
+                canister_id, effective_canister_id
+            ),
+        )
+            .into_response());
+    }
+    Ok(())
+}
+
+pub async fn handler_api_v2_status(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path(instance_id): Path<InstanceId>,
+) -> Response {
+    match run_api_v2_operation(&api_state, instance_id, StatusRequest).await {
+        Ok(op_out) => api_v2_response(op_out),
+        Err(response) => response,
+    }
+}
+
+pub async fn handler_api_v2_call(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path((instance_id, effective_canister_id)): Path<(InstanceId, String)>,
+    body: Bytes,
+) -> Response {
+    let effective_canister_id = match parse_effective_canister_id(&effective_canister_id) {
+        Ok(effective_canister_id) => effective_canister_id,
+        Err(response) => return response,
+    };
+    let msg = match SignedIngress::try_from(SignedRequestBytes::from(body.to_vec())) {
+        Ok(msg) => msg,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                format!("Could not parse body as call message: {}", e),
+            )
+                .into_response()
+        }
+    };
+    if let Err(response) = check_effective_canister_id(msg.canister_id(), effective_canister_id) {
+        return response;
+    }
+    let op = CallRequest {
+        effective_canister_id,
+        msg,
+    };
+    match run_api_v2_operation(&api_state, instance_id, op).await {
+        Ok(op_out) => api_v2_response(op_out),
+        Err(response) => response,
+    }
+}
+
+pub async fn handler_api_v2_query(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path((instance_id, effective_canister_id)): Path<(InstanceId, String)>,
+    body: Bytes,
+) -> Response {
+    let effective_canister_id = match parse_effective_canister_id(&effective_canister_id) {
+        Ok(effective_canister_id) => effective_canister_id,
+        Err(response) => return response,
+    };
+    let query = match HttpRequestEnvelope::<HttpQueryContent>::try_from(&SignedRequestBytes::from(
+        body.to_vec(),
+    ))
+    .map_err(HttpRequestError::from)
+    .and_then(HttpRequest::<UserQuery>::try_from)
+    {
+        Ok(request) => request.take_content(),
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                format!("Could not parse body as query request: {}", e),
+            )
+                .into_response()
+        }
+    };
+    if let Err(response) = check_effective_canister_id(query.receiver, effective_canister_id) {
+        return response;
+    }
+    let op = QueryRequest {
+        effective_canister_id,
+        query,
+    };
+    match run_api_v2_operation(&api_state, instance_id, op).await {
+        Ok(op_out) => api_v2_response(op_out),
+        Err(response) => response,
+    }
+}
+
+pub async fn handler_api_v2_read_state(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path((instance_id, effective_canister_id)): Path<(InstanceId, String)>,
+    body: Bytes,
+) -> Response {
+    let effective_canister_id = match parse_effective_canister_id(&effective_canister_id) {
+        Ok(effective_canister_id) => effective_canister_id,
+        Err(response) => return response,
+    };
+    let read_state = match HttpRequestEnvelope::<HttpReadStateContent>::try_from(
+        &SignedRequestBytes::from(body.to_vec()),
+    )
+    .map_err(HttpRequestError::from)
+    .and_then(HttpRequest::<ReadState>::try_from)
+    {
+        Ok(request) => request.take_content(),
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                format!("Could not parse body as read_state request: {}", e),
+            )
+                .into_response()
+        }
+    };
+    let op = ReadStateRequest {
+        effective_canister_id,
+        read_state,
+    };
+    match run_api_v2_operation(&api_state, instance_id, op).await {
+        Ok(op_out) => api_v2_response(op_out),
+        Err(response) => response,
+    }
+}
+
+// ----------------------------------------------------------------------------------------------------------------- //
+// HTTP gateway
+
+/// The maximum size of a request body accepted by an HTTP gateway.
+const HTTP_GATEWAY_REQUEST_BODY_SIZE_LIMIT: usize = 10 * 1024 * 1024;
+
+#[derive(Clone)]
+struct HttpGatewayState {
+    app_state: AppState,
+    instance_id: InstanceId,
+}
+
+/// Starts an HTTP gateway which serves the frontends of the canisters of an instance.
+/// The gateway keeps running until it is stopped via `stop_http_gateway` or its
+/// instance is deleted.
+pub async fn create_http_gateway(
+    State(app_state): State<AppState>,
+    extract::Json(config): extract::Json<HttpGatewayConfig>,
+) -> (StatusCode, Json<ApiResponse<HttpGatewayInfo>>) {
+    let instances = app_state.api_state.list_instances().await;
+    if !matches!(
+        instances.get(config.forward_to),
+        Some(InstanceState::Available(_)) | Some(InstanceState::Busy { .. })
+    ) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::Error {
+                message: format!("Instance {} not found", config.forward_to),
+            }),
+        );
+    }
+    let addr = format!("127.0.0.1:{}", config.listen_at.unwrap_or_default());
+    let listener = match tokio::net::TcpListener::bind(&addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiResponse::Error {
+                    message: format!("Failed to bind HTTP gateway to {}: {}", addr, e),
+                }),
+            )
+        }
+    };
+    let port = listener.local_addr().unwrap().port();
+    let api_state = app_state.api_state.clone();
+    let router = axum::Router::new()
+        .fallback(handler_http_gateway)
+        .with_state(HttpGatewayState {
+            app_state,
+            instance_id: config.forward_to,
+        });
+    let (shutdown_sender, shutdown_receiver) = oneshot::channel();
+    let handle = tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, router)
+            .with_graceful_shutdown(async move {
+                let _ = shutdown_receiver.await;
+            })
+            .await
+        {
+            error!("HTTP gateway at port {} failed: {}", port, e);
+        }
+    });
+    let gateway = HttpGateway::new(config.forward_to, shutdown_sender, handle);
+    match api_state.add_http_gateway(gateway).await {
+        Ok(gateway_id) => (
+            StatusCode::CREATED,
+            Json(ApiResponse::Success(HttpGatewayInfo { gateway_id, port })),
+        ),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::Error {
+                message: format!(
+                    "Failed to start HTTP gateway for instance {}: {}",
+                    config.forward_to, e
+                ),
+            }),
+        ),
+    }
+}
+
+/// Stops an HTTP gateway started via `create_http_gateway`.
+pub async fn stop_http_gateway(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path(id): Path<usize>,
+) -> (StatusCode, Json<ApiResponse<()>>) {
+    if api_state.stop_http_gateway(id).await {
+        (StatusCode::OK, Json(ApiResponse::Success(())))
+    } else {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::Error {
+                message: format!("HTTP gateway {} not found", id),
+            }),
+        )
+    }
+}
+
+/// Determines the canister addressed by a request to an HTTP gateway, either from the
+/// host (`<canister_id>.localhost` or `<canister_id>.raw.localhost`) or from the
+/// `canisterId` query parameter. Returns whether response verification is skipped.
+fn resolve_canister_id(headers: &HeaderMap, uri: &http::Uri) -> Option<(CanisterId, bool)> {
+    let host = headers
+        .get(http::header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .map(|host| host.split(':').next().unwrap_or_default());
+    if let Some(host) = host {
+        let mut labels = host.split('.');
+        if let Some(Ok(canister_id)) = labels.next().map(CanisterId::from_str) {
+            return Some((canister_id, labels.next() == Some("raw")));
+        }
+    }
+    uri.query()?
+        .split('&')
+        .filter_map(|param| param.split_once('='))
+        .find(|(name, _)| *name == "canisterId")
+        .and_then(|(_, canister_id)| CanisterId::from_str(canister_id).ok())
+        .map(|canister_id| (canister_id, false))
+}
+
+async fn handler_http_gateway(
+    State(HttpGatewayState {
+        app_state,
+        instance_id,
+    }): State<HttpGatewayState>,
+    request: http::Request<axum::body::Body>,
+) -> Response {
+    // Requests to the gateway keep the server alive, just like requests to the server itself.
+    {
+        let now = Instant::now();
+        let mut min_alive_until = app_state.min_alive_until.write().await;
+        if *min_alive_until < now {
+            *min_alive_until = now;
+        }
+    }
+
+    let (parts, body) = request.into_parts();
+    let Some((canister_id, skip_verification)) = resolve_canister_id(&parts.headers, &parts.uri)
+    else {
+        return (
+            StatusCode::BAD_REQUEST,
+            "Could not find a canister id to forward to.",
+        )
+            .into_response();
+    };
+    let body = match axum::body::to_bytes(body, HTTP_GATEWAY_REQUEST_BODY_SIZE_LIMIT).await {
+        Ok(body) => body,
+        Err(e) => {
+            return (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!("Failed to read request body: {}", e),
+            )
+                .into_response()
+        }
+    };
+    let op = HttpGatewayRequest {
+        canister_id,
+        skip_verification,
+        method: parts.method.to_string(),
+        url: parts
+            .uri
+            .path_and_query()
+            .map(|path_and_query| path_and_query.to_string())
+            .unwrap_or_else(|| "/".to_string()),
+        headers: parts
+            .headers
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect(),
+        body: body.to_vec(),
+    };
+    match run_api_v2_operation(&app_state.api_state, instance_id, op).await {
+        Ok(OpOut::HttpGatewayResponse(response)) => {
+            let mut builder = Response::builder().status(response.status_code);
+            for (name, value) in response.headers {
+                builder = builder.header(name, value);
+            }
+            builder
+                .body(axum::body::Body::from(response.body))
+                .unwrap_or_else(|e| {
+                    (
+                        StatusCode::BAD_GATEWAY,
+                        format!("Canister returned an invalid response: {}", e),
+                    )
+                        .into_response()
+                })
+        }
+        Ok(op_out) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("operation returned invalid type: {:?}", op_out),
+        )
+            .into_response(),
+        Err(response) => response,
+    }
+}
+
 // ----------------------------------------------------------------------------------------------------------------- //
 // Other handlers
 
@@ -531,8 +1237,25 @@ pub async fn create_instance(
         runtime,
         blob_store: _,
     }): State<AppState>,
-    extract::Json(subnet_configs): extract::Json<ExtendedSubnetConfigSet>,
+    extract::Json(rest::InstanceConfig {
+        subnet_config_set,
+        state_dir,
+    }): extract::Json<rest::InstanceConfig>,
 ) -> (StatusCode, Json<rest::CreateInstanceResponse>) {
+    // An instance resumed from a state directory uses the subnet configs stored there.
+    let subnet_configs = match state_dir
+        .as_deref()
+        .map(PocketIc::read_subnet_configs)
+        .transpose()
+    {
+        Ok(stored_subnet_configs) => stored_subnet_configs.flatten().unwrap_or(subnet_config_set),
+        Err(message) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(rest::CreateInstanceResponse::Error { message }),
+            )
+        }
+    };
     if subnet_configs.validate().is_err() {
         return (
             StatusCode::BAD_REQUEST,
@@ -541,9 +1264,10 @@ pub async fn create_instance(
             }),
         );
     }
-    let pocket_ic = tokio::task::spawn_blocking(move || PocketIc::new(runtime, subnet_configs))
-        .await
-        .expect("Failed to launch PocketIC");
+    let pocket_ic =
+        tokio::task::spawn_blocking(move || PocketIc::new(runtime, subnet_configs, state_dir))
+            .await
+            .expect("Failed to launch PocketIC");
 
     let topology = pocket_ic.topology.clone();
     let instance_id = api_state.add_instance(pocket_ic).await;
@@ -581,6 +1305,33 @@ pub async fn delete_instance(
     StatusCode::OK
 }
 
+pub async fn auto_progress(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path(id): Path<InstanceId>,
+    extract::Json(AutoProgressConfig { round_interval_ms }): extract::Json<AutoProgressConfig>,
+) -> (StatusCode, Json<ApiResponse<()>>) {
+    let round_interval =
+        Duration::from_millis(round_interval_ms.unwrap_or(DEFAULT_AUTO_PROGRESS_ROUND_INTERVAL_MS));
+    match api_state
+        .auto_progress(id, round_interval, AdvanceTimeAndTick)
+        .await
+    {
+        Ok(()) => (StatusCode::OK, Json(ApiResponse::Success(()))),
+        Err(message) => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::Error { message }),
+        ),
+    }
+}
+
+pub async fn stop_progress(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path(id): Path<InstanceId>,
+) -> (StatusCode, Json<ApiResponse<()>>) {
+    api_state.stop_progress(id).await;
+    (StatusCode::OK, Json(ApiResponse::Success(())))
+}
+
 pub trait RouterExt<S>
 where
     S: Clone + Send + Sync + 'static,
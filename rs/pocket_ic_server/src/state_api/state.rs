@@ -3,16 +3,22 @@
 /// interface guarantees consistency and determinism.
 ///
 use crate::InstanceId;
-use crate::{Computation, OpId, Operation};
+use crate::{BindOperation, Computation, OpId, Operation};
 use base64;
 use ic_types::{CanisterId, SubnetId};
 use ic_utils::thread::JoinOnDrop;
+use pocket_ic::common::rest::CanisterHttpRequest;
 use pocket_ic::{ErrorCode, UserError, WasmResult};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, sync::Arc, thread::Builder as ThreadBuilder, time::Duration};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    thread::Builder as ThreadBuilder,
+    time::{Duration, SystemTime},
+};
 use tokio::{
-    sync::{mpsc, Mutex, RwLock},
-    task::spawn_blocking,
+    sync::{mpsc, oneshot, Mutex, RwLock},
+    task::{spawn_blocking, JoinHandle},
     time,
 };
 use tracing::trace;
@@ -20,6 +26,10 @@ use tracing::trace;
 // The maximum wait time for a computation to finish synchronously.
 const DEFAULT_SYNC_WAIT_DURATION: Duration = Duration::from_secs(10);
 
+// The maximum wait time for an HTTP gateway to finish serving its pending requests
+// after it has been stopped. The gateway task is aborted afterwards.
+const HTTP_GATEWAY_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
 pub const STATE_LABEL_HASH_SIZE: usize = 32;
 
 /// Uniquely identifies a state.
@@ -79,6 +89,46 @@ struct InnerApiState<T> {
     // PocketIC instance to a background worker and drop it there.
     drop_sender: mpsc::UnboundedSender<T>,
     _drop_worker_handle: JoinOnDrop<()>,
+    // The background tasks of the instances in auto-progress mode.
+    progress_tasks: Mutex<HashMap<InstanceId, JoinHandle<()>>>,
+    // The running HTTP gateways, indexed by their ID. Stopped gateways are set to `None`
+    // so that IDs are never reused.
+    http_gateways: Mutex<Vec<Option<HttpGateway>>>,
+}
+
+/// A running HTTP gateway serving the canisters of an instance.
+pub struct HttpGateway {
+    forward_to: InstanceId,
+    shutdown: oneshot::Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+impl HttpGateway {
+    /// The gateway task behind `handle` is expected to shut down gracefully
+    /// once a message is received on the other end of `shutdown`.
+    pub fn new(
+        forward_to: InstanceId,
+        shutdown: oneshot::Sender<()>,
+        handle: JoinHandle<()>,
+    ) -> Self {
+        Self {
+            forward_to,
+            shutdown,
+            handle,
+        }
+    }
+
+    async fn stop(self) {
+        // The gateway task might have terminated already.
+        let _ = self.shutdown.send(());
+        let mut handle = self.handle;
+        if time::timeout(HTTP_GATEWAY_SHUTDOWN_TIMEOUT, &mut handle)
+            .await
+            .is_err()
+        {
+            handle.abort();
+        }
+    }
 }
 
 pub struct PocketIcApiStateBuilder<T> {
@@ -142,6 +192,8 @@ where
             sync_wait_time,
             drop_sender,
             _drop_worker_handle: JoinOnDrop::new(drop_handle),
+            progress_tasks: Mutex::new(HashMap::new()),
+            http_gateways: Mutex::new(Vec::new()),
         });
         PocketIcApiState { inner }
     }
@@ -165,31 +217,80 @@ pub enum OpOut {
     Cycles(u128),
     Bytes(Vec<u8>),
     SubnetId(SubnetId),
+    HttpGatewayResponse(HttpGatewayResponse),
+    CanisterHttp(Vec<CanisterHttpRequest>),
+    MessageId((SubnetId, Vec<u8>)),
+    IngressStatus(IngressMessageStatus),
+    BitcoinBlockHashes(Vec<String>),
+    BitcoinTransactions(Vec<Vec<u8>>),
     Error(PocketIcError),
 }
 
+/// The status of an ingress message submitted to a subnet.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+pub enum IngressMessageStatus {
+    Unknown,
+    Received,
+    Processing,
+    Completed(Result<WasmResult, UserError>),
+    Done,
+}
+
+/// The response of a canister to an HTTP request received by the HTTP gateway.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct HttpGatewayResponse {
+    pub status_code: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
 pub enum PocketIcError {
     CanisterNotFound(CanisterId),
     BadIngressMessage(String),
     SubnetNotFound(candid::Principal),
+    Forbidden(String),
+    InvalidCanisterHttpRequestId((SubnetId, u64)),
+}
+
+fn into_canister_result(
+    r: Result<ic_state_machine_tests::WasmResult, ic_state_machine_tests::UserError>,
+) -> Result<WasmResult, UserError> {
+    match r {
+        Ok(ic_state_machine_tests::WasmResult::Reply(wasm)) => Ok(WasmResult::Reply(wasm)),
+        Ok(ic_state_machine_tests::WasmResult::Reject(s)) => Ok(WasmResult::Reject(s)),
+        Err(user_err) => Err(UserError {
+            code: ErrorCode::try_from(user_err.code() as u64).unwrap(),
+            description: user_err.description().to_string(),
+        }),
+    }
 }
 
 impl From<Result<ic_state_machine_tests::WasmResult, ic_state_machine_tests::UserError>> for OpOut {
     fn from(
         r: Result<ic_state_machine_tests::WasmResult, ic_state_machine_tests::UserError>,
     ) -> Self {
-        let res = {
-            match r {
-                Ok(ic_state_machine_tests::WasmResult::Reply(wasm)) => Ok(WasmResult::Reply(wasm)),
-                Ok(ic_state_machine_tests::WasmResult::Reject(s)) => Ok(WasmResult::Reject(s)),
-                Err(user_err) => Err(UserError {
-                    code: ErrorCode::try_from(user_err.code() as u64).unwrap(),
-                    description: user_err.description().to_string(),
-                }),
-            }
-        };
-        OpOut::CanisterResult(res)
+        OpOut::CanisterResult(into_canister_result(r))
+    }
+}
+
+impl From<ic_state_machine_tests::IngressStatus> for IngressMessageStatus {
+    fn from(status: ic_state_machine_tests::IngressStatus) -> Self {
+        use ic_state_machine_tests::{IngressState, IngressStatus};
+        match status {
+            IngressStatus::Unknown => IngressMessageStatus::Unknown,
+            IngressStatus::Known { state, .. } => match state {
+                IngressState::Received => IngressMessageStatus::Received,
+                IngressState::Processing => IngressMessageStatus::Processing,
+                IngressState::Completed(result) => {
+                    IngressMessageStatus::Completed(into_canister_result(Ok(result)))
+                }
+                IngressState::Failed(error) => {
+                    IngressMessageStatus::Completed(into_canister_result(Err(error)))
+                }
+                IngressState::Done => IngressMessageStatus::Done,
+            },
+        }
     }
 }
 
@@ -227,8 +328,32 @@ impl std::fmt::Debug for OpOut {
             OpOut::Error(PocketIcError::SubnetNotFound(sid)) => {
                 write!(f, "SubnetNotFound({})", sid)
             }
+            OpOut::Error(PocketIcError::Forbidden(msg)) => {
+                write!(f, "Forbidden({})", msg)
+            }
+            OpOut::Error(PocketIcError::InvalidCanisterHttpRequestId((subnet_id, request_id))) => {
+                write!(
+                    f,
+                    "InvalidCanisterHttpRequestId({},{})",
+                    subnet_id, request_id
+                )
+            }
             OpOut::Bytes(bytes) => write!(f, "Bytes({})", base64::encode(bytes)),
             OpOut::SubnetId(subnet_id) => write!(f, "SubnetId({})", subnet_id),
+            OpOut::HttpGatewayResponse(response) => {
+                write!(f, "HttpGatewayResponse({})", response.status_code)
+            }
+            OpOut::CanisterHttp(canister_http_requests) => {
+                write!(f, "CanisterHttp({:?})", canister_http_requests)
+            }
+            OpOut::MessageId((subnet_id, message_id)) => {
+                write!(f, "MessageId({},{})", subnet_id, hex::encode(message_id))
+            }
+            OpOut::IngressStatus(status) => write!(f, "IngressStatus({:?})", status),
+            OpOut::BitcoinBlockHashes(hashes) => write!(f, "BitcoinBlockHashes({:?})", hashes),
+            OpOut::BitcoinTransactions(transactions) => {
+                write!(f, "BitcoinTransactions({})", transactions.len())
+            }
         }
     }
 }
@@ -329,6 +454,7 @@ where
     }
 
     pub async fn delete_instance(&self, instance_id: InstanceId) {
+        self.stop_progress(instance_id).await;
         let instances = self.inner.instances.read().await;
         let mut instance_state = instances[instance_id].lock().await;
         if let InstanceState::Available(pocket_ic) =
@@ -336,6 +462,25 @@ where
         {
             self.inner.drop_sender.send(pocket_ic).unwrap();
         }
+        drop(instance_state);
+        drop(instances);
+
+        let gateways: Vec<_> = self
+            .inner
+            .http_gateways
+            .lock()
+            .await
+            .iter_mut()
+            .filter(|gateway| {
+                gateway
+                    .as_ref()
+                    .is_some_and(|gateway| gateway.forward_to == instance_id)
+            })
+            .filter_map(Option::take)
+            .collect();
+        for gateway in gateways {
+            gateway.stop().await;
+        }
     }
 
     pub async fn list_instances(&self) -> Vec<InstanceState<()>> {
@@ -357,6 +502,113 @@ where
         res
     }
 
+    /// Enables the auto-progress mode of an instance: a background task executes the
+    /// operation returned by `progress` on the instance every `round_interval`, passing the
+    /// wall-clock time elapsed since the previous execution, until the mode is disabled
+    /// or the instance is deleted. If the instance is busy with another operation,
+    /// the elapsed time is passed on to the next execution.
+    pub async fn auto_progress<S, F>(
+        &self,
+        instance_id: InstanceId,
+        round_interval: Duration,
+        progress: F,
+    ) -> Result<(), String>
+    where
+        S: Operation<TargetType = T> + Send + 'static,
+        F: Fn(Duration) -> S + Send + 'static,
+    {
+        let instances = self.inner.instances.read().await;
+        match instances.get(instance_id) {
+            None => return Err("Instance not found".to_string()),
+            Some(instance_state) => {
+                if let InstanceState::Deleted = &*instance_state.lock().await {
+                    return Err("Instance was deleted".to_string());
+                }
+            }
+        }
+        drop(instances);
+
+        let mut progress_tasks = self.inner.progress_tasks.lock().await;
+        if progress_tasks.contains_key(&instance_id) {
+            return Err("Auto-progress mode is already enabled".to_string());
+        }
+        // The task must not keep the API state alive, e.g., when the server shuts down.
+        let inner = Arc::downgrade(&self.inner);
+        let handle = tokio::spawn(async move {
+            let mut last_progress = SystemTime::now();
+            loop {
+                let round_start = time::Instant::now();
+                let Some(inner) = inner.upgrade() else {
+                    break;
+                };
+                let api_state = PocketIcApiState { inner };
+                let now = SystemTime::now();
+                let op = progress(now.duration_since(last_progress).unwrap_or_default());
+                match api_state.update(op.on_instance(instance_id)).await {
+                    // The instance has been deleted.
+                    Err(_) => break,
+                    Ok(UpdateReply::Busy { .. }) => {}
+                    Ok(UpdateReply::Started { .. } | UpdateReply::Output(_)) => {
+                        last_progress = now;
+                    }
+                }
+                drop(api_state);
+                time::sleep_until(round_start + round_interval).await;
+            }
+        });
+        progress_tasks.insert(instance_id, handle);
+        Ok(())
+    }
+
+    /// Disables the auto-progress mode of an instance (if enabled).
+    pub async fn stop_progress(&self, instance_id: InstanceId) {
+        if let Some(handle) = self.inner.progress_tasks.lock().await.remove(&instance_id) {
+            handle.abort();
+        }
+    }
+
+    /// Registers a running HTTP gateway and returns its ID. Fails (and stops the gateway)
+    /// if the instance the gateway forwards to does not exist or was deleted.
+    pub async fn add_http_gateway(&self, gateway: HttpGateway) -> Result<usize, String> {
+        let instances = self.inner.instances.read().await;
+        let err = match instances.get(gateway.forward_to) {
+            None => "Instance not found",
+            Some(instance_state) => {
+                // Holding the instance lock while registering the gateway ensures that
+                // a concurrent `delete_instance` stops the gateway.
+                let instance_state = instance_state.lock().await;
+                if let InstanceState::Deleted = &*instance_state {
+                    "Instance was deleted"
+                } else {
+                    let mut http_gateways = self.inner.http_gateways.lock().await;
+                    http_gateways.push(Some(gateway));
+                    return Ok(http_gateways.len() - 1);
+                }
+            }
+        };
+        drop(instances);
+        gateway.stop().await;
+        Err(err.to_string())
+    }
+
+    /// Stops an HTTP gateway. Returns `false` if there is no running gateway with the given ID.
+    pub async fn stop_http_gateway(&self, gateway_id: usize) -> bool {
+        let gateway = self
+            .inner
+            .http_gateways
+            .lock()
+            .await
+            .get_mut(gateway_id)
+            .and_then(Option::take);
+        match gateway {
+            Some(gateway) => {
+                gateway.stop().await;
+                true
+            }
+            None => false,
+        }
+    }
+
     /// An operation bound to an instance (a Computation) can update the PocketIC state.
     ///
     /// * If the instance is busy executing an operation, the call returns [UpdateReply::Busy]
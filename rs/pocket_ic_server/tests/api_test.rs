@@ -1,6 +1,10 @@
-use pocket_ic::common::rest::SubnetConfigSet;
+use pocket_ic::common::rest::{
+    CreateInstanceResponse, HttpGatewayConfig, HttpGatewayInfo, InstanceConfig, InstanceId,
+    SubnetConfigSet,
+};
 use reqwest::{StatusCode, Url};
 
+use std::net::TcpStream;
 use std::path::PathBuf;
 use std::process::Command;
 use std::time::{Duration, Instant};
@@ -18,15 +22,18 @@ fn test_status() {
 
 #[test]
 fn test_creation_of_instance_extended() {
-    use pocket_ic::common::rest::ExtendedSubnetConfigSet;
     let url = start_server();
     let client = reqwest::blocking::Client::new();
     let response = client
         .post(url.join("instances").unwrap())
-        .json(&Into::<ExtendedSubnetConfigSet>::into(SubnetConfigSet {
-            application: 1,
-            ..Default::default()
-        }))
+        .json(&InstanceConfig {
+            subnet_config_set: SubnetConfigSet {
+                application: 1,
+                ..Default::default()
+            }
+            .into(),
+            state_dir: None,
+        })
         .send()
         .unwrap();
 
@@ -120,6 +127,88 @@ fn test_blob_store_wrong_encoding() {
         .contains("bad encoding"));
 }
 
+#[test]
+fn test_http_gateway_can_be_stopped() {
+    let url = start_server();
+    let client = reqwest::blocking::Client::new();
+    let instance_id = create_instance(&client, &url);
+    let gateway = create_http_gateway(&client, &url, instance_id);
+    assert!(TcpStream::connect((LOCALHOST, gateway.port)).is_ok());
+
+    let stop_url = url
+        .join(&format!("http_gateway/{}/stop", gateway.gateway_id))
+        .unwrap();
+    let response = client.post(stop_url.clone()).send().unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(TcpStream::connect((LOCALHOST, gateway.port)).is_err());
+
+    // The gateway is already stopped.
+    let response = client.post(stop_url).send().unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn test_http_gateway_is_stopped_when_instance_is_deleted() {
+    let url = start_server();
+    let client = reqwest::blocking::Client::new();
+    let instance_id = create_instance(&client, &url);
+    let gateway = create_http_gateway(&client, &url, instance_id);
+    assert!(TcpStream::connect((LOCALHOST, gateway.port)).is_ok());
+
+    let response = client
+        .delete(url.join(&format!("instances/{}", instance_id)).unwrap())
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(TcpStream::connect((LOCALHOST, gateway.port)).is_err());
+
+    let response = client
+        .post(
+            url.join(&format!("http_gateway/{}/stop", gateway.gateway_id))
+                .unwrap(),
+        )
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+fn create_instance(client: &reqwest::blocking::Client, url: &Url) -> InstanceId {
+    let response = client
+        .post(url.join("instances").unwrap())
+        .json(&InstanceConfig {
+            subnet_config_set: SubnetConfigSet {
+                application: 1,
+                ..Default::default()
+            }
+            .into(),
+            state_dir: None,
+        })
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    match response.json::<CreateInstanceResponse>().unwrap() {
+        CreateInstanceResponse::Created { instance_id, .. } => instance_id,
+        CreateInstanceResponse::Error { message } => panic!("{}", message),
+    }
+}
+
+fn create_http_gateway(
+    client: &reqwest::blocking::Client,
+    url: &Url,
+    instance_id: InstanceId,
+) -> HttpGatewayInfo {
+    let response = client
+        .post(url.join("http_gateway").unwrap())
+        .json(&HttpGatewayConfig {
+            listen_at: None,
+            forward_to: instance_id,
+        })
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    response.json::<HttpGatewayInfo>().unwrap()
+}
+
 fn start_server() -> Url {
     let parent_pid = std::os::unix::process::parent_id();
     let bin_path = std::env::var_os("POCKET_IC_BIN").expect("Missing PocketIC binary");
@@ -217,6 +217,14 @@ fn build_registry_proto(def: &Path, out: &Path) {
         ".registry.crypto.v1.EcdsaKeyId",
         "#[derive(candid::CandidType, Eq)]",
     );
+    config.type_attribute(
+        ".registry.crypto.v1.SchnorrAlgorithm",
+        "#[derive(candid::CandidType)]",
+    );
+    config.type_attribute(
+        ".registry.crypto.v1.SchnorrKeyId",
+        "#[derive(candid::CandidType, Eq)]",
+    );
     config.type_attribute(
         ".registry.node_operator",
         "#[derive(candid::CandidType, serde::Serialize, candid::Deserialize, Eq, Hash)]",
@@ -249,6 +257,10 @@ fn build_registry_proto(def: &Path, out: &Path) {
         ".registry.subnet.v1.EcdsaConfig",
         "#[derive(candid::CandidType, Eq)]",
     );
+    config.type_attribute(
+        ".registry.subnet.v1.SchnorrConfig",
+        "#[derive(candid::CandidType, Eq)]",
+    );
     config.type_attribute(
         ".registry.subnet.v1.SubnetFeatures",
         "#[derive(candid::CandidType, Eq)]",
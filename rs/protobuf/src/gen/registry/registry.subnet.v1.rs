@@ -85,6 +85,10 @@ pub struct SubnetRecord {
     /// appropriate proposal which sets `is_halted` to `false` is approved.
     #[prost(bool, tag = "28")]
     pub halt_at_cup_height: bool,
+    /// Schnorr Config. Threshold Schnorr keys are generated by the same IDKG protocol instance as
+    /// the threshold ECDSA keys, so they are only used if `ecdsa_config` is set as well.
+    #[prost(message, optional, tag = "29")]
+    pub schnorr_config: ::core::option::Option<SchnorrConfig>,
 }
 #[derive(serde::Serialize, serde::Deserialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -384,6 +388,24 @@ pub struct EcdsaConfig {
     #[prost(uint64, optional, tag = "6")]
     pub idkg_key_rotation_period_ms: ::core::option::Option<u64>,
 }
+/// Per subnet threshold Schnorr configuration
+#[derive(serde::Serialize, serde::Deserialize, candid::CandidType, Eq)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SchnorrConfig {
+    /// Number of pre-signatures to create in advance, per key.
+    #[prost(uint32, tag = "1")]
+    pub pre_signatures_to_create_in_advance: u32,
+    /// Identifiers for threshold Schnorr keys held by the subnet.
+    #[prost(message, repeated, tag = "2")]
+    pub key_ids: ::prost::alloc::vec::Vec<super::super::crypto::v1::SchnorrKeyId>,
+    /// The maximum number of signature requests that can be enqueued at once.
+    #[prost(uint32, tag = "3")]
+    pub max_queue_size: u32,
+    /// Signature requests will timeout after the given number of nano seconds.
+    #[prost(uint64, optional, tag = "4")]
+    pub signature_request_timeout_ns: ::core::option::Option<u64>,
+}
 #[derive(
     serde::Serialize,
     serde::Deserialize,
@@ -16,4 +16,10 @@ pub struct CanisterSnapshotBits {
     #[prost(message, optional, tag = "7")]
     pub wasm_chunk_store_metadata:
         ::core::option::Option<super::super::canister_state_bits::v1::WasmChunkStoreMetadata>,
+    #[prost(uint64, tag = "8")]
+    pub stable_memory_size: u64,
+    #[prost(uint64, tag = "9")]
+    pub wasm_memory_size: u64,
+    #[prost(message, repeated, tag = "10")]
+    pub exported_globals: ::prost::alloc::vec::Vec<super::super::canister_state_bits::v1::Global>,
 }
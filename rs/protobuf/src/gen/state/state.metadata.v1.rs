@@ -49,6 +49,14 @@ pub struct EcdsaKeyEntry {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SchnorrKeyEntry {
+    #[prost(message, optional, tag = "1")]
+    pub key_id: ::core::option::Option<super::super::super::registry::crypto::v1::SchnorrKeyId>,
+    #[prost(message, repeated, tag = "2")]
+    pub subnet_ids: ::prost::alloc::vec::Vec<super::super::super::types::v1::SubnetId>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct NetworkTopology {
     #[prost(message, repeated, tag = "1")]
     pub subnets: ::prost::alloc::vec::Vec<SubnetsEntry>,
@@ -69,6 +77,8 @@ pub struct NetworkTopology {
     #[prost(message, repeated, tag = "7")]
     pub bitcoin_mainnet_canister_ids:
         ::prost::alloc::vec::Vec<super::super::super::types::v1::CanisterId>,
+    #[prost(message, repeated, tag = "8")]
+    pub schnorr_signing_subnets: ::prost::alloc::vec::Vec<SchnorrKeyEntry>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -124,6 +134,30 @@ pub struct SignWithEcdsaContextTree {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SignWithSchnorrContext {
+    #[prost(message, optional, tag = "1")]
+    pub request: ::core::option::Option<super::super::queues::v1::Request>,
+    #[prost(message, optional, tag = "2")]
+    pub key_id: ::core::option::Option<super::super::super::registry::crypto::v1::SchnorrKeyId>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub message: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", repeated, tag = "4")]
+    pub derivation_path_vec: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    #[prost(bytes = "vec", tag = "5")]
+    pub pseudo_random_id: ::prost::alloc::vec::Vec<u8>,
+    #[prost(uint64, tag = "6")]
+    pub batch_time: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SignWithSchnorrContextTree {
+    #[prost(uint64, tag = "1")]
+    pub callback_id: u64,
+    #[prost(message, optional, tag = "2")]
+    pub context: ::core::option::Option<SignWithSchnorrContext>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct HttpHeader {
     #[prost(string, tag = "1")]
     pub name: ::prost::alloc::string::String,
@@ -338,6 +372,8 @@ pub struct SubnetCallContextManager {
     pub stop_canister_calls: ::prost::alloc::vec::Vec<StopCanisterCallTree>,
     #[prost(message, repeated, tag = "16")]
     pub raw_rand_contexts: ::prost::alloc::vec::Vec<RawRandContext>,
+    #[prost(message, repeated, tag = "17")]
+    pub sign_with_schnorr_contexts: ::prost::alloc::vec::Vec<SignWithSchnorrContextTree>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -362,6 +398,16 @@ pub struct SubnetMetrics {
     pub canister_state_bytes: ::core::option::Option<u64>,
     #[prost(uint64, optional, tag = "10")]
     pub update_transactions_total: ::core::option::Option<u64>,
+    #[prost(message, repeated, tag = "11")]
+    pub schnorr_signature_agreements: ::prost::alloc::vec::Vec<SchnorrSignatureAgreementsEntry>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SchnorrSignatureAgreementsEntry {
+    #[prost(message, optional, tag = "1")]
+    pub key_id: ::core::option::Option<super::super::super::registry::crypto::v1::SchnorrKeyId>,
+    #[prost(uint64, tag = "2")]
+    pub count: u64,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -471,6 +517,10 @@ pub struct SystemMetadata {
     pub blockmaker_metrics_time_series: ::core::option::Option<BlockmakerMetricsTimeSeries>,
     #[prost(message, repeated, tag = "21")]
     pub api_boundary_nodes: ::prost::alloc::vec::Vec<ApiBoundaryNodeEntry>,
+    /// The ID that will be assigned to the next canister snapshot taken on this
+    /// subnet. Snapshot IDs are never reused.
+    #[prost(uint64, tag = "22")]
+    pub next_snapshot_id: u64,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
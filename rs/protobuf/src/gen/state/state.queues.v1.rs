@@ -70,6 +70,10 @@ pub struct Request {
     pub cycles_payment: ::core::option::Option<Cycles>,
     #[prost(message, optional, tag = "8")]
     pub metadata: ::core::option::Option<RequestMetadata>,
+    /// Deadline of a best-effort call, in nanoseconds since the Unix epoch.
+    /// Not set for guaranteed response calls.
+    #[prost(uint64, optional, tag = "9")]
+    pub deadline_nanos: ::core::option::Option<u64>,
 }
 #[derive(serde::Serialize, serde::Deserialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -94,6 +98,10 @@ pub struct Response {
     pub refund: ::core::option::Option<Funds>,
     #[prost(message, optional, tag = "7")]
     pub cycles_refund: ::core::option::Option<Cycles>,
+    /// Deadline of the best-effort call that this is a response to, in
+    /// nanoseconds since the Unix epoch. Not set for guaranteed responses.
+    #[prost(uint64, optional, tag = "8")]
+    pub deadline_nanos: ::core::option::Option<u64>,
     #[prost(oneof = "response::ResponsePayload", tags = "5, 6")]
     pub response_payload: ::core::option::Option<response::ResponsePayload>,
 }
@@ -385,6 +385,16 @@ pub struct EcdsaPayload {
     pub next_key_in_creation: ::core::option::Option<KeyTranscriptCreation>,
     #[prost(message, optional, tag = "12")]
     pub key_id: ::core::option::Option<super::super::registry::crypto::v1::EcdsaKeyId>,
+    #[prost(message, repeated, tag = "14")]
+    pub schnorr_key_transcripts: ::prost::alloc::vec::Vec<SchnorrKeyTranscript>,
+    #[prost(message, repeated, tag = "15")]
+    pub available_schnorr_pre_signatures: ::prost::alloc::vec::Vec<AvailableSchnorrPreSignature>,
+    #[prost(message, repeated, tag = "16")]
+    pub schnorr_pre_signatures_in_creation: ::prost::alloc::vec::Vec<SchnorrPreSignatureInProgress>,
+    #[prost(message, repeated, tag = "17")]
+    pub ongoing_schnorr_signatures: ::prost::alloc::vec::Vec<OngoingSchnorrSignature>,
+    #[prost(message, repeated, tag = "18")]
+    pub schnorr_signature_agreements: ::prost::alloc::vec::Vec<CompletedSignature>,
 }
 #[derive(serde::Serialize, serde::Deserialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -400,6 +410,17 @@ pub struct EcdsaKeyTranscript {
 #[derive(serde::Serialize, serde::Deserialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SchnorrKeyTranscript {
+    #[prost(message, optional, tag = "1")]
+    pub key_id: ::core::option::Option<super::super::registry::crypto::v1::SchnorrKeyId>,
+    #[prost(message, optional, tag = "2")]
+    pub current: ::core::option::Option<UnmaskedTranscriptWithAttributes>,
+    #[prost(message, optional, tag = "3")]
+    pub next_in_creation: ::core::option::Option<KeyTranscriptCreation>,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct OngoingSignature {
     #[prost(message, optional, tag = "1")]
     pub request_id: ::core::option::Option<RequestId>,
@@ -431,6 +452,37 @@ pub struct QuadrupleInProgress {
 #[derive(serde::Serialize, serde::Deserialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct OngoingSchnorrSignature {
+    #[prost(message, optional, tag = "1")]
+    pub request_id: ::core::option::Option<SchnorrRequestId>,
+    #[prost(message, optional, tag = "2")]
+    pub sig_inputs: ::core::option::Option<ThresholdSchnorrSigInputsRef>,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AvailableSchnorrPreSignature {
+    #[prost(uint64, tag = "1")]
+    pub pre_signature_id: u64,
+    #[prost(message, optional, tag = "2")]
+    pub pre_signature: ::core::option::Option<SchnorrPreSignatureRef>,
+    #[prost(message, optional, tag = "3")]
+    pub key_id: ::core::option::Option<super::super::registry::crypto::v1::SchnorrKeyId>,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SchnorrPreSignatureInProgress {
+    #[prost(uint64, tag = "1")]
+    pub pre_signature_id: u64,
+    #[prost(message, optional, tag = "2")]
+    pub pre_signature: ::core::option::Option<SchnorrPreSignatureInCreation>,
+    #[prost(message, optional, tag = "3")]
+    pub key_id: ::core::option::Option<super::super::registry::crypto::v1::SchnorrKeyId>,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct OngoingXnetReshare {
     #[prost(message, optional, tag = "1")]
     pub request: ::core::option::Option<EcdsaReshareRequest>,
@@ -462,6 +514,19 @@ pub struct RequestId {
 #[derive(serde::Serialize, serde::Deserialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SchnorrRequestId {
+    #[prost(bytes = "vec", tag = "1")]
+    pub pseudo_random_id: ::prost::alloc::vec::Vec<u8>,
+    #[prost(uint64, tag = "2")]
+    pub pre_signature_id: u64,
+    #[prost(uint64, tag = "3")]
+    pub height: u64,
+    #[prost(message, optional, tag = "4")]
+    pub key_id: ::core::option::Option<super::super::registry::crypto::v1::SchnorrKeyId>,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct TranscriptRef {
     #[prost(uint64, tag = "1")]
     pub height: u64,
@@ -626,6 +691,38 @@ pub struct ThresholdEcdsaSigInputsRef {
 #[derive(serde::Serialize, serde::Deserialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SchnorrPreSignatureInCreation {
+    #[prost(message, optional, tag = "1")]
+    pub blinder_unmasked_config: ::core::option::Option<RandomUnmaskedTranscriptParams>,
+    #[prost(message, optional, tag = "2")]
+    pub blinder_unmasked: ::core::option::Option<UnmaskedTranscript>,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SchnorrPreSignatureRef {
+    #[prost(message, optional, tag = "1")]
+    pub blinder_unmasked_ref: ::core::option::Option<UnmaskedTranscript>,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ThresholdSchnorrSigInputsRef {
+    #[prost(message, optional, tag = "1")]
+    pub derivation_path:
+        ::core::option::Option<super::super::registry::subnet::v1::ExtendedDerivationPath>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub message: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub nonce: ::prost::alloc::vec::Vec<u8>,
+    #[prost(message, optional, tag = "4")]
+    pub presig_transcript_ref: ::core::option::Option<SchnorrPreSignatureRef>,
+    #[prost(message, optional, tag = "5")]
+    pub key_transcript_ref: ::core::option::Option<UnmaskedTranscript>,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CompletedSignature {
     #[prost(message, optional, tag = "3")]
     pub unreported: ::core::option::Option<super::super::state::queues::v1::Response>,
@@ -667,7 +764,7 @@ pub struct KeyTranscriptCreation {
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct EcdsaMessage {
-    #[prost(oneof = "ecdsa_message::Msg", tags = "1, 2, 3, 4, 5")]
+    #[prost(oneof = "ecdsa_message::Msg", tags = "1, 2, 3, 4, 5, 6")]
     pub msg: ::core::option::Option<ecdsa_message::Msg>,
 }
 /// Nested message and enum types in `EcdsaMessage`.
@@ -686,6 +783,8 @@ pub mod ecdsa_message {
         Complaint(super::EcdsaComplaint),
         #[prost(message, tag = "5")]
         Opening(super::EcdsaOpening),
+        #[prost(message, tag = "6")]
+        SchnorrSigShare(super::SchnorrSigShare),
     }
 }
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -702,6 +801,17 @@ pub struct EcdsaSigShare {
 #[derive(serde::Serialize, serde::Deserialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SchnorrSigShare {
+    #[prost(message, optional, tag = "1")]
+    pub signer_id: ::core::option::Option<NodeId>,
+    #[prost(message, optional, tag = "2")]
+    pub request_id: ::core::option::Option<SchnorrRequestId>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub sig_share_raw: ::prost::alloc::vec::Vec<u8>,
+}
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct EcdsaComplaint {
     #[prost(message, optional, tag = "1")]
     pub content: ::core::option::Option<EcdsaComplaintContent>,
@@ -766,7 +876,7 @@ pub struct PrefixHashPair {
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct EcdsaArtifactId {
-    #[prost(oneof = "ecdsa_artifact_id::Kind", tags = "1, 2, 3, 4, 5")]
+    #[prost(oneof = "ecdsa_artifact_id::Kind", tags = "1, 2, 3, 4, 5, 6")]
     pub kind: ::core::option::Option<ecdsa_artifact_id::Kind>,
 }
 /// Nested message and enum types in `EcdsaArtifactId`.
@@ -785,6 +895,8 @@ pub mod ecdsa_artifact_id {
         Complaint(super::PrefixHashPair),
         #[prost(message, tag = "5")]
         Opening(super::PrefixHashPair),
+        #[prost(message, tag = "6")]
+        SchnorrSigShare(super::PrefixHashPair),
     }
 }
 #[derive(
@@ -1404,7 +1516,7 @@ pub struct NotarizationAttribute {
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct EcdsaMessageAttribute {
-    #[prost(oneof = "ecdsa_message_attribute::Kind", tags = "1, 2, 3, 4, 5")]
+    #[prost(oneof = "ecdsa_message_attribute::Kind", tags = "1, 2, 3, 4, 5, 6")]
     pub kind: ::core::option::Option<ecdsa_message_attribute::Kind>,
 }
 /// Nested message and enum types in `EcdsaMessageAttribute`.
@@ -1423,6 +1535,8 @@ pub mod ecdsa_message_attribute {
         Complaint(super::super::super::registry::subnet::v1::IDkgTranscriptId),
         #[prost(message, tag = "5")]
         Opening(super::super::super::registry::subnet::v1::IDkgTranscriptId),
+        #[prost(message, tag = "6")]
+        SchnorrSigShare(super::SchnorrRequestId),
     }
 }
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -542,6 +542,7 @@ mod tests {
             ssh_readonly_access: vec![],
             ssh_backup_access: vec![],
             ecdsa_config: None,
+            schnorr_config: None,
         };
 
         let payload = UpdateSubnetPayload {
@@ -643,6 +644,7 @@ mod tests {
                     }
                     .into()
                 ),
+                schnorr_config: None,
                 max_number_of_canisters: 10,
                 ssh_readonly_access: vec!["pub_key_0".to_string()],
                 ssh_backup_access: vec!["pub_key_1".to_string()],
@@ -684,6 +686,7 @@ mod tests {
             ssh_readonly_access: vec![],
             ssh_backup_access: vec![],
             ecdsa_config: None,
+            schnorr_config: None,
         };
 
         let payload = UpdateSubnetPayload {
@@ -759,6 +762,7 @@ mod tests {
                 ssh_readonly_access: vec![],
                 ssh_backup_access: vec![],
                 ecdsa_config: None,
+                schnorr_config: None,
             }
         );
     }
@@ -812,6 +816,7 @@ mod tests {
             ssh_readonly_access: vec![],
             ssh_backup_access: vec![],
             ecdsa_config: None,
+            schnorr_config: None,
         };
 
         let payload = UpdateSubnetPayload {
@@ -881,6 +886,7 @@ mod tests {
             ssh_readonly_access: vec![],
             ssh_backup_access: vec![],
             ecdsa_config: None,
+            schnorr_config: None,
         };
 
         let payload = UpdateSubnetPayload {
@@ -956,6 +962,7 @@ mod tests {
                 ssh_readonly_access: vec![],
                 ssh_backup_access: vec![],
                 ecdsa_config: None,
+                schnorr_config: None,
             }
         );
     }
@@ -994,6 +1001,7 @@ mod tests {
             ssh_readonly_access: vec![],
             ssh_backup_access: vec![],
             ecdsa_config: None,
+            schnorr_config: None,
         };
 
         let payload = UpdateSubnetPayload {
@@ -1069,6 +1077,7 @@ mod tests {
                 ssh_readonly_access: vec![],
                 ssh_backup_access: vec![],
                 ecdsa_config: None,
+                schnorr_config: None,
             }
         );
     }
@@ -12,6 +12,7 @@ pub mod node;
 pub mod node_operator;
 pub mod provisional_whitelist;
 pub mod routing_table;
+pub mod schnorr_keys;
 pub mod subnet;
 pub mod test_proto;
 pub mod unassigned_nodes;
@@ -14,7 +14,7 @@ use ic_registry_keys::{
     make_catch_up_package_contents_key, make_node_record_key, make_replica_version_key,
     make_subnet_list_record_key, make_subnet_record_key, ROOT_SUBNET_ID_KEY,
 };
-use ic_registry_subnet_features::{EcdsaConfig, SubnetFeatures};
+use ic_registry_subnet_features::{EcdsaConfig, SchnorrConfig, SubnetFeatures};
 use ic_types::{
     registry::RegistryClientError::DecodeError, Height, NodeId, PrincipalId,
     PrincipalIdBlobParseError, RegistryVersion, ReplicaVersion, SubnetId,
@@ -90,6 +90,13 @@ pub trait SubnetRegistry {
         version: RegistryVersion,
     ) -> RegistryClientResult<EcdsaConfig>;
 
+    /// Returns schnorr config
+    fn get_schnorr_config(
+        &self,
+        subnet_id: SubnetId,
+        version: RegistryVersion,
+    ) -> RegistryClientResult<SchnorrConfig>;
+
     /// Returns notarization delay settings:
     /// - the unit delay for blockmaker;
     /// - the initial delay for notary, to give time to rank-0 block
@@ -286,6 +293,20 @@ impl<T: RegistryClient + ?Sized> SubnetRegistry for T {
         Ok(subnet.and_then(|subnet| subnet.ecdsa_config.map(|config| config.try_into().unwrap())))
     }
 
+    fn get_schnorr_config(
+        &self,
+        subnet_id: SubnetId,
+        version: RegistryVersion,
+    ) -> RegistryClientResult<SchnorrConfig> {
+        let bytes = self.get_value(&make_subnet_record_key(subnet_id), version);
+        let subnet = deserialize_registry_value::<SubnetRecord>(bytes)?;
+        Ok(subnet.and_then(|subnet| {
+            subnet
+                .schnorr_config
+                .map(|config| config.try_into().unwrap())
+        }))
+    }
+
     fn get_notarization_delay_settings(
         &self,
         subnet_id: SubnetId,
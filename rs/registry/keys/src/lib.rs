@@ -6,7 +6,7 @@
 use candid::{CandidType, Deserialize};
 use core::fmt;
 use ic_base_types::{NodeId, SubnetId};
-use ic_ic00_types::EcdsaKeyId;
+use ic_ic00_types::{EcdsaKeyId, SchnorrAlgorithm, SchnorrKeyId};
 use ic_types::crypto::KeyPurpose;
 use ic_types::registry::RegistryClientError;
 use ic_types::PrincipalId;
@@ -33,6 +33,7 @@ pub const CRYPTO_TLS_CERT_KEY_PREFIX: &str = "crypto_tls_cert_";
 pub const CRYPTO_THRESHOLD_SIGNING_KEY_PREFIX: &str = "crypto_threshold_signing_public_key_";
 pub const DATA_CENTER_KEY_PREFIX: &str = "data_center_record_";
 pub const ECDSA_SIGNING_SUBNET_LIST_KEY_PREFIX: &str = "key_id_";
+pub const SCHNORR_SIGNING_SUBNET_LIST_KEY_PREFIX: &str = "schnorr_key_id_";
 
 pub fn make_ecdsa_signing_subnet_list_key(key_id: &EcdsaKeyId) -> String {
     format!("{}{}", ECDSA_SIGNING_SUBNET_LIST_KEY_PREFIX, key_id)
@@ -59,6 +60,40 @@ pub fn get_ecdsa_key_id_from_signing_subnet_list_key(
         })
 }
 
+pub fn make_schnorr_signing_subnet_list_key(key_id: &SchnorrKeyId) -> String {
+    format!("{}{}", SCHNORR_SIGNING_SUBNET_LIST_KEY_PREFIX, key_id)
+}
+
+pub fn get_schnorr_key_id_from_signing_subnet_list_key(
+    signing_subnet_list_key: &str,
+) -> Result<SchnorrKeyId, RegistryClientError> {
+    let prefix_removed = signing_subnet_list_key
+        .strip_prefix(SCHNORR_SIGNING_SUBNET_LIST_KEY_PREFIX)
+        .ok_or_else(|| RegistryClientError::DecodeError {
+            error: format!(
+                "Schnorr Signing Subnet List key id {} does not start with prefix {}",
+                signing_subnet_list_key, SCHNORR_SIGNING_SUBNET_LIST_KEY_PREFIX
+            ),
+        })?;
+    let key_id = prefix_removed
+        .parse::<SchnorrKeyId>()
+        .map_err(|error| RegistryClientError::DecodeError {
+            error: format!(
+                "Schnorr Signing Subnet List key id {} could not be converted to a SchnorrKeyId: {:?}",
+                signing_subnet_list_key, error
+            ),
+        })?;
+    if key_id.algorithm != SchnorrAlgorithm::Bip340Secp256k1 {
+        return Err(RegistryClientError::DecodeError {
+            error: format!(
+                "Schnorr Signing Subnet List key id {} uses an unsupported algorithm: threshold {} keys are not supported",
+                signing_subnet_list_key, key_id.algorithm
+            ),
+        });
+    }
+    Ok(key_id)
+}
+
 /// Returns the only key whose payload is the list of subnets.
 pub fn make_subnet_list_record_key() -> String {
     SUBNET_LIST_KEY.to_string()
@@ -449,6 +484,48 @@ mod tests {
         )
     }
 
+    #[test]
+    fn schnorr_signing_subnet_list_key_round_trips() {
+        let key_id = SchnorrKeyId {
+            algorithm: SchnorrAlgorithm::Bip340Secp256k1,
+            name: "some_key".to_string(),
+        };
+        let signing_subnet_list_key = make_schnorr_signing_subnet_list_key(&key_id);
+        assert!(!signing_subnet_list_key.starts_with(ECDSA_SIGNING_SUBNET_LIST_KEY_PREFIX));
+        assert_eq!(
+            get_schnorr_key_id_from_signing_subnet_list_key(&signing_subnet_list_key).unwrap(),
+            key_id
+        );
+    }
+
+    #[test]
+    fn schnorr_signing_subnet_list_ed25519_key_is_rejected() {
+        let key_id = SchnorrKeyId {
+            algorithm: SchnorrAlgorithm::Ed25519,
+            name: "some_key".to_string(),
+        };
+        let signing_subnet_list_key = make_schnorr_signing_subnet_list_key(&key_id);
+        assert_eq!(
+            get_schnorr_key_id_from_signing_subnet_list_key(&signing_subnet_list_key).unwrap_err(),
+            RegistryClientError::DecodeError {
+                error: "Schnorr Signing Subnet List key id schnorr_key_id_Ed25519:some_key uses an unsupported algorithm: threshold Ed25519 keys are not supported".to_string()
+            }
+        )
+    }
+
+    #[test]
+    fn schnorr_signing_subnet_list_bad_algorithm_error_message() {
+        let bad_key = "UnknownAlgorithm:key_name";
+        let signing_subnet_list_key =
+            format!("{}{}", SCHNORR_SIGNING_SUBNET_LIST_KEY_PREFIX, bad_key);
+        assert_eq!(
+            get_schnorr_key_id_from_signing_subnet_list_key(&signing_subnet_list_key).unwrap_err(),
+            RegistryClientError::DecodeError {
+                error: "Schnorr Signing Subnet List key id schnorr_key_id_UnknownAlgorithm:key_name could not be converted to a SchnorrKeyId: \"UnknownAlgorithm is not a recognized Schnorr algorithm\"".to_string()
+            }
+        )
+    }
+
     #[test]
     fn firewall_scope_parsing() {
         let id = PrincipalId::new_node_test_id(42);
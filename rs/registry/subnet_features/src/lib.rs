@@ -1,10 +1,11 @@
 use candid::CandidType;
-use ic_ic00_types::EcdsaKeyId;
+use ic_ic00_types::{EcdsaKeyId, SchnorrKeyId};
 use ic_protobuf::{proxy::ProxyDecodeError, registry::subnet::v1 as pb};
 use serde::{Deserialize, Serialize};
 use std::{convert::TryFrom, str::FromStr};
 
 pub const DEFAULT_ECDSA_MAX_QUEUE_SIZE: u32 = 20;
+pub const DEFAULT_SCHNORR_MAX_QUEUE_SIZE: u32 = 20;
 
 /// List of features that can be enabled or disabled on the given subnet.
 #[derive(CandidType, Clone, Copy, Deserialize, Debug, Eq, PartialEq, Serialize)]
@@ -123,6 +124,44 @@ impl TryFrom<pb::EcdsaConfig> for EcdsaConfig {
     }
 }
 
+#[derive(CandidType, Clone, Default, Deserialize, Debug, Eq, PartialEq, Serialize)]
+pub struct SchnorrConfig {
+    pub pre_signatures_to_create_in_advance: u32,
+    pub key_ids: Vec<SchnorrKeyId>,
+    pub max_queue_size: Option<u32>,
+    pub signature_request_timeout_ns: Option<u64>,
+}
+
+impl From<SchnorrConfig> for pb::SchnorrConfig {
+    fn from(item: SchnorrConfig) -> Self {
+        pb::SchnorrConfig {
+            pre_signatures_to_create_in_advance: item.pre_signatures_to_create_in_advance,
+            key_ids: item.key_ids.iter().map(|key| key.into()).collect(),
+            max_queue_size: item
+                .max_queue_size
+                .unwrap_or(DEFAULT_SCHNORR_MAX_QUEUE_SIZE),
+            signature_request_timeout_ns: item.signature_request_timeout_ns,
+        }
+    }
+}
+
+impl TryFrom<pb::SchnorrConfig> for SchnorrConfig {
+    type Error = ProxyDecodeError;
+
+    fn try_from(value: pb::SchnorrConfig) -> Result<Self, Self::Error> {
+        let mut key_ids = vec![];
+        for key in value.key_ids {
+            key_ids.push(SchnorrKeyId::try_from(key)?);
+        }
+        Ok(SchnorrConfig {
+            pre_signatures_to_create_in_advance: value.pre_signatures_to_create_in_advance,
+            key_ids,
+            max_queue_size: Some(value.max_queue_size),
+            signature_request_timeout_ns: value.signature_request_timeout_ns,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -145,4 +184,19 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_schnorr_config_round_trip() {
+        let config = SchnorrConfig {
+            pre_signatures_to_create_in_advance: 5,
+            key_ids: vec![SchnorrKeyId {
+                algorithm: ic_ic00_types::SchnorrAlgorithm::Bip340Secp256k1,
+                name: "some_key".to_string(),
+            }],
+            max_queue_size: Some(30),
+            signature_request_timeout_ns: Some(42),
+        };
+        let proto = pb::SchnorrConfig::from(config.clone());
+        assert_eq!(SchnorrConfig::try_from(proto).unwrap(), config);
+    }
 }
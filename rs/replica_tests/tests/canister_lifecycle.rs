@@ -713,6 +713,7 @@ fn can_get_canister_information() {
                 2592000,
                 Some(5_000_000_000_000u128),
                 LogVisibility::default(),
+                None,
                 0u128,
                 0u128,
                 0u128,
@@ -771,6 +772,7 @@ fn can_get_canister_information() {
                     259200,
                     None,
                     LogVisibility::default(),
+                    None,
                     0u128,
                     0u128,
                     0u128,
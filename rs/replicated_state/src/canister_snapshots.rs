@@ -1,10 +1,19 @@
-use ic_types::{CanisterId, Time};
+use ic_types::{CanisterId, NumBytes, Time};
 use ic_wasm_types::CanisterModule;
 
-use crate::{canister_state::system_state::wasm_chunk_store::WasmChunkStore, PageMap};
+use crate::{
+    canister_state::{
+        execution_state::Global, system_state::wasm_chunk_store::WasmChunkStore,
+        WASM_PAGE_SIZE_IN_BYTES,
+    },
+    NumWasmPages, PageMap,
+};
 
 use phantom_newtype::Id;
-use std::{collections::BTreeMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
 
 pub struct SnapshotIdTag;
 pub type SnapshotId = Id<SnapshotIdTag, u64>;
@@ -13,7 +22,7 @@ pub type SnapshotId = Id<SnapshotIdTag, u64>;
 ///
 /// Additionally, keeps track of all the accumulated changes
 /// since the last flush to the disk.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct CanisterSnapshots {
     next_snapshot_id: SnapshotId,
     pub(crate) snapshots: BTreeMap<SnapshotId, Arc<CanisterSnapshot>>,
@@ -76,14 +85,159 @@ impl CanisterSnapshots {
         }
     }
 
+    /// Records that the canister identified by `canister_id` was restored
+    /// from the snapshot identified by `snapshot_id`.
+    ///
+    /// Does not modify the canister itself, only adds a new item to the
+    /// `unflushed_changes` so that the files of the canister are replaced
+    /// by the files of the snapshot on the next flush to the disk.
+    pub fn restore(&mut self, canister_id: CanisterId, snapshot_id: SnapshotId) {
+        debug_assert!(self.snapshots.contains_key(&snapshot_id));
+        self.unflushed_changes
+            .push(SnapshotOperation::Restore(canister_id, snapshot_id));
+    }
+
+    /// Returns a reference to the snapshot identified by `snapshot_id`.
+    pub fn get(&self, snapshot_id: SnapshotId) -> Option<&Arc<CanisterSnapshot>> {
+        self.snapshots.get(&snapshot_id)
+    }
+
+    /// Returns a mutable reference to the snapshot identified by `snapshot_id`.
+    ///
+    /// The snapshot is cloned if it is shared with another copy of the state.
+    pub fn get_mut(&mut self, snapshot_id: SnapshotId) -> Option<&mut CanisterSnapshot> {
+        self.snapshots.get_mut(&snapshot_id).map(Arc::make_mut)
+    }
+
+    /// Returns an iterator over all snapshots in the collection.
+    pub fn iter(&self) -> impl Iterator<Item = (&SnapshotId, &Arc<CanisterSnapshot>)> {
+        self.snapshots.iter()
+    }
+
+    /// Returns a mutable iterator over all snapshots in the collection.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&SnapshotId, &mut CanisterSnapshot)> {
+        self.snapshots
+            .iter_mut()
+            .map(|(snapshot_id, snapshot)| (snapshot_id, Arc::make_mut(snapshot)))
+    }
+
+    /// Returns all snapshots belonging to the canister identified by `canister_id`.
+    pub fn list_snapshots(
+        &self,
+        canister_id: CanisterId,
+    ) -> Vec<(SnapshotId, Arc<CanisterSnapshot>)> {
+        self.snapshots
+            .iter()
+            .filter(|(_, snapshot)| snapshot.canister_id == canister_id)
+            .map(|(snapshot_id, snapshot)| (*snapshot_id, Arc::clone(snapshot)))
+            .collect()
+    }
+
+    /// Returns the number of snapshots belonging to the canister identified
+    /// by `canister_id`.
+    pub fn count_snapshots(&self, canister_id: CanisterId) -> usize {
+        self.snapshots
+            .values()
+            .filter(|snapshot| snapshot.canister_id == canister_id)
+            .count()
+    }
+
+    /// Removes all snapshots belonging to the canister identified by
+    /// `canister_id`, e.g. because the canister was deleted.
+    pub fn delete_snapshots(&mut self, canister_id: CanisterId) {
+        let snapshot_ids: Vec<_> = self
+            .snapshots
+            .iter()
+            .filter(|(_, snapshot)| snapshot.canister_id == canister_id)
+            .map(|(snapshot_id, _)| *snapshot_id)
+            .collect();
+        for snapshot_id in snapshot_ids {
+            self.remove(snapshot_id);
+        }
+    }
+
+    /// Returns the total memory used by the snapshots belonging to the
+    /// canister identified by `canister_id`.
+    pub fn memory_usage(&self, canister_id: CanisterId) -> NumBytes {
+        self.snapshots
+            .values()
+            .filter(|snapshot| snapshot.canister_id == canister_id)
+            .map(|snapshot| snapshot.size())
+            .sum()
+    }
+
+    /// Returns the ID that will be assigned to the next snapshot.
+    pub fn next_snapshot_id(&self) -> SnapshotId {
+        self.next_snapshot_id
+    }
+
+    /// Returns the number of snapshots in the collection.
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    /// Returns `true` if the collection contains no snapshots.
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
     /// Take the unflushed changes.
     pub fn take_unflushed_changes(&mut self) -> Vec<SnapshotOperation> {
         std::mem::take(&mut self.unflushed_changes)
     }
+
+    /// Retains only the snapshots belonging to the given canisters.
+    ///
+    /// Additionally, adds a `Delete` item to the `unflushed_changes`
+    /// for every dropped snapshot.
+    pub(crate) fn retain_canisters(&mut self, canister_ids: &BTreeSet<CanisterId>) {
+        let unflushed_changes = &mut self.unflushed_changes;
+        self.snapshots.retain(|snapshot_id, snapshot| {
+            let retain = canister_ids.contains(&snapshot.canister_id);
+            if !retain {
+                unflushed_changes.push(SnapshotOperation::Delete(*snapshot_id));
+            }
+            retain
+        });
+    }
+}
+
+/// The state of a canister memory at the time of taking the snapshot.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MemorySnapshot {
+    /// The contents of the memory.
+    pub page_map: PageMap,
+    /// The size of the memory in wasm pages.
+    pub size: NumWasmPages,
+}
+
+impl MemorySnapshot {
+    pub fn new(page_map: PageMap, size: NumWasmPages) -> Self {
+        Self { page_map, size }
+    }
+
+    /// Returns the size of the memory in bytes.
+    pub fn size_in_bytes(&self) -> NumBytes {
+        NumBytes::from(self.size.get() as u64 * WASM_PAGE_SIZE_IN_BYTES as u64)
+    }
+}
+
+/// Contains all information related to the execution state of a canister
+/// at the time of taking the snapshot.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExecutionStateSnapshot {
+    /// The raw canister module.
+    pub wasm_binary: CanisterModule,
+    /// The values of the exported globals.
+    pub exported_globals: Vec<Global>,
+    /// Snapshot of stable memory.
+    pub stable_memory: MemorySnapshot,
+    /// Snapshot of wasm memory.
+    pub wasm_memory: MemorySnapshot,
 }
 
 /// Contains all information related to a canister snapshot.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct CanisterSnapshot {
     /// Identifies the canister to which this snapshot belongs.
     canister_id: CanisterId,
@@ -95,14 +249,8 @@ pub struct CanisterSnapshot {
     certified_data: Vec<u8>,
     /// Snapshot of chunked store.
     chunk_store: WasmChunkStore,
-    /// The raw canister module.
-    /// May not exist depending on whether or not the canister has
-    /// an actual wasm module.
-    wasm_binary: Option<CanisterModule>,
-    /// Snapshot of stable memory.
-    stable_memory: Option<PageMap>,
-    /// Snapshot of wasm memory.
-    wasm_memory: Option<PageMap>,
+    /// Snapshot of the execution state.
+    execution_snapshot: ExecutionStateSnapshot,
 }
 
 impl CanisterSnapshot {
@@ -111,20 +259,16 @@ impl CanisterSnapshot {
         taken_at_timestamp: Time,
         canister_version: u64,
         certified_data: Vec<u8>,
-        stable_memory: Option<PageMap>,
-        wasm_memory: Option<PageMap>,
         chunk_store: WasmChunkStore,
-        wasm_binary: Option<CanisterModule>,
+        execution_snapshot: ExecutionStateSnapshot,
     ) -> CanisterSnapshot {
         Self {
             canister_id,
             taken_at_timestamp,
             canister_version,
             certified_data,
-            stable_memory,
-            wasm_memory,
             chunk_store,
-            wasm_binary,
+            execution_snapshot,
         }
     }
 
@@ -140,17 +284,51 @@ impl CanisterSnapshot {
         &self.taken_at_timestamp
     }
 
-    pub fn stable_memory(&self) -> &Option<PageMap> {
-        &self.stable_memory
+    pub fn certified_data(&self) -> &Vec<u8> {
+        &self.certified_data
+    }
+
+    pub fn execution_snapshot(&self) -> &ExecutionStateSnapshot {
+        &self.execution_snapshot
     }
 
-    pub fn wasm_memory(&self) -> &Option<PageMap> {
-        &self.wasm_memory
+    pub fn execution_snapshot_mut(&mut self) -> &mut ExecutionStateSnapshot {
+        &mut self.execution_snapshot
+    }
+
+    pub fn stable_memory(&self) -> &MemorySnapshot {
+        &self.execution_snapshot.stable_memory
+    }
+
+    pub fn wasm_memory(&self) -> &MemorySnapshot {
+        &self.execution_snapshot.wasm_memory
+    }
+
+    pub fn wasm_binary(&self) -> &CanisterModule {
+        &self.execution_snapshot.wasm_binary
+    }
+
+    pub fn exported_globals(&self) -> &Vec<Global> {
+        &self.execution_snapshot.exported_globals
     }
 
     pub fn chunk_store(&self) -> &WasmChunkStore {
         &self.chunk_store
     }
+
+    pub fn chunk_store_mut(&mut self) -> &mut WasmChunkStore {
+        &mut self.chunk_store
+    }
+
+    /// Returns the total size of the snapshot, which is used for charging
+    /// the canister for the memory it occupies.
+    pub fn size(&self) -> NumBytes {
+        NumBytes::from(self.execution_snapshot.wasm_binary.len() as u64)
+            + self.execution_snapshot.wasm_memory.size_in_bytes()
+            + self.execution_snapshot.stable_memory.size_in_bytes()
+            + NumBytes::from(self.certified_data.len() as u64)
+            + self.chunk_store.memory_usage()
+    }
 }
 
 /// Describes the types of unflushed changes that can be stored by the `SnapshotManager`.
@@ -168,18 +346,27 @@ mod tests {
     use ic_test_utilities::types::ids::canister_test_id;
     use ic_test_utilities_time::mock_time;
     use ic_types::NumBytes;
-    #[test]
-    fn test_push_and_remove_snapshot() {
-        let snapshot = CanisterSnapshot::new(
-            canister_test_id(0),
+
+    fn fake_snapshot(canister_id: CanisterId) -> CanisterSnapshot {
+        let execution_snapshot = ExecutionStateSnapshot {
+            wasm_binary: CanisterModule::new(vec![1, 2, 3]),
+            exported_globals: vec![Global::I32(1)],
+            stable_memory: MemorySnapshot::new(PageMap::new_for_testing(), NumWasmPages::from(1)),
+            wasm_memory: MemorySnapshot::new(PageMap::new_for_testing(), NumWasmPages::from(2)),
+        };
+        CanisterSnapshot::new(
+            canister_id,
             mock_time(),
             0,
             vec![],
-            Some(PageMap::new_for_testing()),
-            Some(PageMap::new_for_testing()),
             WasmChunkStore::new_for_testing(NumBytes::from(20)),
-            Some(CanisterModule::new(vec![1, 2, 3])),
-        );
+            execution_snapshot,
+        )
+    }
+
+    #[test]
+    fn test_push_and_remove_snapshot() {
+        let snapshot = fake_snapshot(canister_test_id(0));
         let mut snapshot_manager = CanisterSnapshots::default();
         assert_eq!(snapshot_manager.snapshots.len(), 0);
         assert_eq!(snapshot_manager.unflushed_changes.len(), 0);
@@ -202,4 +389,40 @@ mod tests {
         assert_eq!(snapshot_manager.unflushed_changes.len(), 0);
         assert_eq!(unflushed_changes.len(), 1);
     }
+
+    #[test]
+    fn test_list_and_delete_snapshots_of_canister() {
+        let mut snapshot_manager = CanisterSnapshots::default();
+        let id_0 = snapshot_manager.push(Arc::new(fake_snapshot(canister_test_id(0))));
+        let id_1 = snapshot_manager.push(Arc::new(fake_snapshot(canister_test_id(1))));
+        let id_2 = snapshot_manager.push(Arc::new(fake_snapshot(canister_test_id(0))));
+        snapshot_manager.take_unflushed_changes();
+
+        let listed: Vec<_> = snapshot_manager
+            .list_snapshots(canister_test_id(0))
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(listed, vec![id_0, id_2]);
+        assert_eq!(snapshot_manager.count_snapshots(canister_test_id(1)), 1);
+
+        let expected_size = fake_snapshot(canister_test_id(0)).size();
+        assert_eq!(
+            snapshot_manager.memory_usage(canister_test_id(0)),
+            expected_size + expected_size
+        );
+
+        snapshot_manager.delete_snapshots(canister_test_id(0));
+        assert_eq!(snapshot_manager.len(), 1);
+        assert!(snapshot_manager.get(id_1).is_some());
+        assert_eq!(
+            snapshot_manager.take_unflushed_changes(),
+            vec![
+                SnapshotOperation::Delete(id_0),
+                SnapshotOperation::Delete(id_2)
+            ]
+        );
+        // Snapshot IDs are never reused.
+        assert_eq!(snapshot_manager.next_snapshot_id(), SnapshotId::new(3));
+    }
 }
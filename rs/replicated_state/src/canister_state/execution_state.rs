@@ -62,6 +62,7 @@ pub enum Global {
     I64(i64),
     F32(f32),
     F64(f64),
+    V128(u128),
 }
 
 impl Global {
@@ -71,6 +72,7 @@ impl Global {
             Global::I64(_) => "i64",
             Global::F32(_) => "f32",
             Global::F64(_) => "f64",
+            Global::V128(_) => "v128",
         }
     }
 }
@@ -82,6 +84,7 @@ impl Hash for Global {
             Global::I64(val) => val.to_le_bytes().to_vec(),
             Global::F32(val) => val.to_le_bytes().to_vec(),
             Global::F64(val) => val.to_le_bytes().to_vec(),
+            Global::V128(val) => val.to_le_bytes().to_vec(),
         };
         bytes.hash(state)
     }
@@ -94,6 +97,7 @@ impl PartialEq<Global> for Global {
             (Global::I64(val), Global::I64(other_val)) => val == other_val,
             (Global::F32(val), Global::F32(other_val)) => val == other_val,
             (Global::F64(val), Global::F64(other_val)) => val == other_val,
+            (Global::V128(val), Global::V128(other_val)) => val == other_val,
             _ => false,
         }
     }
@@ -114,6 +118,9 @@ impl From<&Global> for pb::Global {
             Global::F64(value) => Self {
                 global: Some(pb::global::Global::F64(*value)),
             },
+            Global::V128(value) => Self {
+                global: Some(pb::global::Global::V128(value.to_le_bytes().to_vec())),
+            },
         }
     }
 }
@@ -126,6 +133,15 @@ impl TryFrom<pb::Global> for Global {
             pb::global::Global::I64(value) => Ok(Self::I64(value)),
             pb::global::Global::F32(value) => Ok(Self::F32(value)),
             pb::global::Global::F64(value) => Ok(Self::F64(value)),
+            pb::global::Global::V128(value) => {
+                let bytes: [u8; 16] = value.try_into().map_err(|value: Vec<u8>| {
+                    ProxyDecodeError::ValueOutOfRange {
+                        typ: "Global::V128",
+                        err: format!("expected 16 bytes, got {}", value.len()),
+                    }
+                })?;
+                Ok(Self::V128(u128::from_le_bytes(bytes)))
+            }
         }
     }
 }
@@ -430,7 +446,7 @@ pub struct ExecutionState {
     pub wasm_binary: Arc<WasmBinary>,
 
     /// The persistent heap of the module. The size of this memory is expected
-    /// to fit in a `u32`.
+    /// to fit in a `u32` unless the module has a 64-bit heap.
     pub wasm_memory: Memory,
 
     /// The canister stable memory which is persisted across canister upgrades.
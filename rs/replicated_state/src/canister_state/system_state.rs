@@ -10,6 +10,7 @@ use crate::page_map::PageAllocatorFileDescriptor;
 use crate::{CanisterQueues, CanisterState, InputQueueType, PageMap, StateError};
 pub use call_context_manager::{CallContext, CallContextAction, CallContextManager, CallOrigin};
 use ic_base_types::NumSeconds;
+use ic_error_types::RejectCode;
 use ic_ic00_types::{CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, LogVisibility};
 use ic_logger::{error, ReplicaLogger};
 use ic_protobuf::{
@@ -19,9 +20,10 @@ use ic_protobuf::{
 
 use ic_registry_subnet_type::SubnetType;
 use ic_types::{
+    canister_log::CanisterLog,
     messages::{
-        CanisterCall, CanisterMessage, CanisterMessageOrTask, CanisterTask, Ingress, RejectContext,
-        Request, RequestOrResponse, Response, StopCanisterContext,
+        CanisterCall, CanisterMessage, CanisterMessageOrTask, CanisterTask, Ingress, Payload,
+        RejectContext, Request, RequestOrResponse, Response, StopCanisterContext,
     },
     nominal_cycles::NominalCycles,
     CanisterId, CanisterTimer, Cycles, MemoryAllocation, NumBytes, PrincipalId, Time,
@@ -62,6 +64,7 @@ pub enum CyclesUseCase {
     DeletedCanisters,
     NonConsumed,
     BurnedCycles,
+    SchnorrOutcalls,
 }
 
 impl CyclesUseCase {
@@ -81,6 +84,7 @@ impl CyclesUseCase {
             Self::DeletedCanisters => "DeletedCanisters",
             Self::NonConsumed => "NonConsumed",
             Self::BurnedCycles => "BurnedCycles",
+            Self::SchnorrOutcalls => "SchnorrOutcalls",
         }
     }
 }
@@ -100,6 +104,7 @@ impl From<CyclesUseCase> for i32 {
             CyclesUseCase::DeletedCanisters => 10,
             CyclesUseCase::NonConsumed => 11,
             CyclesUseCase::BurnedCycles => 12,
+            CyclesUseCase::SchnorrOutcalls => 13,
         }
     }
 }
@@ -119,6 +124,7 @@ impl From<i32> for CyclesUseCase {
             10 => Self::DeletedCanisters,
             11 => Self::NonConsumed,
             12 => Self::BurnedCycles,
+            13 => Self::SchnorrOutcalls,
             _ => panic!("Unsupported value"),
         }
     }
@@ -334,6 +340,24 @@ pub struct SystemState {
 
     /// Log visibility of the canister.
     pub log_visibility: LogVisibility,
+
+    /// The user-specified upper limit on the size of the Wasm memory.
+    ///
+    /// A `memory.grow` that would take the Wasm memory above this limit fails
+    /// with an error in update and replicated executions. Upgrades are not
+    /// subject to the limit so that a canister can always be upgraded.
+    pub wasm_memory_limit: Option<NumBytes>,
+
+    /// The total size of the snapshots belonging to this canister.
+    ///
+    /// Snapshots are stored in `ReplicatedState::canister_snapshots`, this
+    /// value is kept here so that the snapshots can be accounted for as part
+    /// of the canister memory usage. It is not persisted, but recomputed from
+    /// the snapshots when loading a checkpoint.
+    pub snapshots_memory_usage: NumBytes,
+
+    /// Log records written by the canister via `ic0.debug_print`.
+    pub canister_log: CanisterLog,
 }
 
 /// A wrapper around the different canister statuses.
@@ -704,6 +728,9 @@ impl SystemState {
             canister_history: CanisterHistory::default(),
             wasm_chunk_store,
             log_visibility: LogVisibility::default(),
+            wasm_memory_limit: None,
+            snapshots_memory_usage: NumBytes::from(0),
+            canister_log: CanisterLog::default(),
         }
     }
 
@@ -748,6 +775,8 @@ impl SystemState {
         wasm_chunk_store_data: PageMap,
         wasm_chunk_store_metadata: WasmChunkStoreMetadata,
         log_visibility: LogVisibility,
+        wasm_memory_limit: Option<NumBytes>,
+        canister_log: CanisterLog,
     ) -> Self {
         Self {
             controllers,
@@ -771,6 +800,9 @@ impl SystemState {
                 wasm_chunk_store_metadata,
             ),
             log_visibility,
+            wasm_memory_limit,
+            snapshots_memory_usage: NumBytes::from(0),
+            canister_log,
         }
     }
 
@@ -1098,6 +1130,13 @@ impl SystemState {
         );
 
         match (&msg, &self.status) {
+            // Best-effort responses are silently dropped when stopped.
+            (RequestOrResponse::Response(response), CanisterStatus::Stopped { .. })
+                if response.deadline.is_some() =>
+            {
+                Ok(())
+            }
+
             // Requests and responses are both rejected when stopped.
             (_, CanisterStatus::Stopped { .. }) => {
                 Err((StateError::CanisterStopped(self.canister_id()), msg))
@@ -1123,6 +1162,16 @@ impl SystemState {
                 },
             ) => {
                 if let RequestOrResponse::Response(response) = &msg {
+                    // Late best-effort responses (whose callback has already expired
+                    // or is otherwise gone) are silently dropped.
+                    if response.deadline.is_some()
+                        && (call_context_manager.is_expired(response.originator_reply_callback)
+                            || call_context_manager
+                                .callback(response.originator_reply_callback)
+                                .is_none())
+                    {
+                        return Ok(());
+                    }
                     call_context_manager
                         .validate_response(response)
                         .map_err(|err| (err, msg.clone()))?;
@@ -1309,6 +1358,72 @@ impl SystemState {
             .time_out_requests(current_time, own_canister_id, local_canisters)
     }
 
+    /// Returns `true` if any of the best-effort callbacks of this canister has
+    /// an expired deadline.
+    pub fn has_expired_callbacks(&self, current_time: Time) -> bool {
+        self.call_context_manager()
+            .map_or(false, |ccm| ccm.has_expired_callbacks(current_time))
+    }
+
+    /// Expires all best-effort callbacks with deadlines before `current_time`,
+    /// enqueuing a `SYS_UNKNOWN` reject response for each of them. Returns the
+    /// number of callbacks that were expired.
+    ///
+    /// Any response to an expired callback that is received later on is
+    /// silently dropped.
+    pub fn time_out_callbacks(
+        &mut self,
+        current_time: Time,
+        own_canister_id: &CanisterId,
+        local_canisters: &BTreeMap<CanisterId, CanisterState>,
+    ) -> u64 {
+        let canister_id = self.canister_id;
+        let call_context_manager = match &mut self.status {
+            CanisterStatus::Running {
+                call_context_manager,
+            }
+            | CanisterStatus::Stopping {
+                call_context_manager,
+                ..
+            } => call_context_manager,
+            CanisterStatus::Stopped => return 0,
+        };
+
+        let mut timed_out_callbacks_count = 0;
+        for callback_id in call_context_manager.expire_callbacks(current_time) {
+            let callback = match call_context_manager.callback(callback_id) {
+                Some(callback) => callback,
+                None => continue,
+            };
+            let response = RequestOrResponse::Response(Arc::new(Response {
+                originator: canister_id,
+                respondent: callback.respondent,
+                originator_reply_callback: callback_id,
+                refund: Cycles::zero(),
+                response_payload: Payload::Reject(RejectContext::new(
+                    RejectCode::SysUnknown,
+                    "Call deadline has expired.",
+                )),
+                deadline: callback.deadline,
+            }));
+            let input_queue_type = if callback.respondent == *own_canister_id
+                || local_canisters.contains_key(&callback.respondent)
+            {
+                InputQueueType::LocalSubnet
+            } else {
+                InputQueueType::RemoteSubnet
+            };
+
+            // Pushing the reject response fails iff the actual response has
+            // already been enqueued, in which case there is nothing to do.
+            if self.queues.push_input(response, input_queue_type).is_ok() {
+                timed_out_callbacks_count += 1;
+            }
+        }
+
+        timed_out_callbacks_count
+    }
+
     /// Re-partitions the local and remote input schedules of `self.queues`
     /// following a canister migration, based on the updated set of local canisters.
     ///
@@ -1344,6 +1459,7 @@ impl SystemState {
             | CyclesUseCase::RequestAndResponseTransmission
             | CyclesUseCase::CanisterCreation
             | CyclesUseCase::ECDSAOutcalls
+            | CyclesUseCase::SchnorrOutcalls
             | CyclesUseCase::HTTPOutcalls
             | CyclesUseCase::DeletedCanisters
             | CyclesUseCase::NonConsumed
@@ -1393,9 +1509,10 @@ impl SystemState {
         use_case: CyclesUseCase,
         consuming_cycles: ConsumingCycles,
     ) {
-        // The three CyclesUseCase below are not valid on the canister
+        // The four CyclesUseCase below are not valid on the canister
         // level, they should only appear on the subnet level.
         debug_assert_ne!(use_case, CyclesUseCase::ECDSAOutcalls);
+        debug_assert_ne!(use_case, CyclesUseCase::SchnorrOutcalls);
         debug_assert_ne!(use_case, CyclesUseCase::HTTPOutcalls);
         debug_assert_ne!(use_case, CyclesUseCase::DeletedCanisters);
 
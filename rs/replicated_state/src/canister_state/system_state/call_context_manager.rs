@@ -19,7 +19,7 @@ use ic_types::{
     UserId,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::{From, TryFrom, TryInto};
 use std::time::Duration;
 
@@ -244,12 +244,21 @@ pub struct CallContextManager {
     /// Maps call context to its responded status.
     call_contexts: BTreeMap<CallContextId, CallContext>,
     callbacks: BTreeMap<CallbackId, Callback>,
+    /// Best-effort callbacks that have not expired yet, ordered by deadline.
+    /// Derived from `callbacks` and `expired_callbacks`, not persisted.
+    unexpired_callbacks: BTreeSet<(Time, CallbackId)>,
+    /// Best-effort callbacks whose deadline has expired and for which a
+    /// `SYS_UNKNOWN` reject response has already been enqueued. Any late
+    /// response to one of these callbacks is dropped.
+    expired_callbacks: BTreeSet<CallbackId>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CallOrigin {
     Ingress(UserId, MessageId),
-    CanisterUpdate(CanisterId, CallbackId),
+    /// Canister update call, with the deadline of the call if it is a
+    /// best-effort call.
+    CanisterUpdate(CanisterId, CallbackId, Option<Time>),
     Query(UserId),
     CanisterQuery(CanisterId, CallbackId),
     /// System task is either a `Heartbeat` or a `GlobalTimer`.
@@ -261,7 +270,7 @@ impl CallOrigin {
     pub fn get_principal(&self) -> PrincipalId {
         match self {
             CallOrigin::Ingress(user_id, _) => user_id.get(),
-            CallOrigin::CanisterUpdate(canister_id, _) => canister_id.get(),
+            CallOrigin::CanisterUpdate(canister_id, _, _) => canister_id.get(),
             CallOrigin::Query(user_id) => user_id.get(),
             CallOrigin::CanisterQuery(canister_id, _) => canister_id.get(),
             CallOrigin::SystemTask => IC_00.get(),
@@ -276,10 +285,11 @@ impl From<&CallOrigin> for pb::call_context::CallOrigin {
                 user_id: Some(user_id_into_protobuf(*user_id)),
                 message_id: message_id.as_bytes().to_vec(),
             }),
-            CallOrigin::CanisterUpdate(canister_id, callback_id) => {
+            CallOrigin::CanisterUpdate(canister_id, callback_id, deadline) => {
                 Self::CanisterUpdate(pb::call_context::CanisterUpdateOrQuery {
                     canister_id: Some(pb_types::CanisterId::from(*canister_id)),
                     callback_id: callback_id.get(),
+                    deadline_nanos: deadline.map(|deadline| deadline.as_nanos_since_unix_epoch()),
                 })
             }
             CallOrigin::Query(user_id) => Self::Query(user_id_into_protobuf(*user_id)),
@@ -287,6 +297,7 @@ impl From<&CallOrigin> for pb::call_context::CallOrigin {
                 Self::CanisterQuery(pb::call_context::CanisterUpdateOrQuery {
                     canister_id: Some(pb_types::CanisterId::from(*canister_id)),
                     callback_id: callback_id.get(),
+                    deadline_nanos: None,
                 })
             }
             CallOrigin::SystemTask => Self::SystemTask(pb::call_context::SystemTask {}),
@@ -312,10 +323,12 @@ impl TryFrom<pb::call_context::CallOrigin> for CallOrigin {
                 pb::call_context::CanisterUpdateOrQuery {
                     canister_id,
                     callback_id,
+                    deadline_nanos,
                 },
             ) => Self::CanisterUpdate(
                 try_from_option_field(canister_id, "CallOrigin::CanisterUpdate::canister_id")?,
                 callback_id.into(),
+                deadline_nanos.map(Time::from_nanos_since_unix_epoch),
             ),
             pb::call_context::CallOrigin::Query(user_id) => {
                 Self::Query(user_id_try_from_protobuf(user_id)?)
@@ -324,6 +337,7 @@ impl TryFrom<pb::call_context::CallOrigin> for CallOrigin {
                 pb::call_context::CanisterUpdateOrQuery {
                     canister_id,
                     callback_id,
+                    ..
                 },
             ) => Self::CanisterQuery(
                 try_from_option_field(canister_id, "CallOrigin::CanisterQuery::canister_id")?,
@@ -536,6 +550,9 @@ impl CallContextManager {
     pub fn register_callback(&mut self, callback: Callback) -> CallbackId {
         self.next_callback_id += 1;
         let callback_id = CallbackId::from(self.next_callback_id);
+        if let Some(deadline) = callback.deadline {
+            self.unexpired_callbacks.insert((deadline, callback_id));
+        }
         self.callbacks.insert(callback_id, callback);
         callback_id
     }
@@ -543,7 +560,44 @@ impl CallContextManager {
     /// If we get a response for one of the outstanding calls, we unregister
     /// the callback and return it.
     pub fn unregister_callback(&mut self, callback_id: CallbackId) -> Option<Callback> {
-        self.callbacks.remove(&callback_id)
+        let callback = self.callbacks.remove(&callback_id)?;
+        if let Some(deadline) = callback.deadline {
+            self.unexpired_callbacks.remove(&(deadline, callback_id));
+        }
+        self.expired_callbacks.remove(&callback_id);
+        Some(callback)
+    }
+
+    /// Returns `true` if at least one best-effort callback has a deadline
+    /// before `now` and has not been expired yet.
+    pub fn has_expired_callbacks(&self, now: Time) -> bool {
+        self.unexpired_callbacks
+            .first()
+            .map_or(false, |(deadline, _)| *deadline < now)
+    }
+
+    /// Marks all best-effort callbacks with deadlines before `now` as expired
+    /// and returns their IDs, in order of increasing deadline.
+    ///
+    /// The callbacks themselves are retained until the corresponding
+    /// `SYS_UNKNOWN` reject responses are executed.
+    pub fn expire_callbacks(&mut self, now: Time) -> Vec<CallbackId> {
+        let mut expired = Vec::new();
+        while let Some((deadline, callback_id)) = self.unexpired_callbacks.first().cloned() {
+            if deadline >= now {
+                break;
+            }
+            self.unexpired_callbacks.pop_first();
+            self.expired_callbacks.insert(callback_id);
+            expired.push(callback_id);
+        }
+        expired
+    }
+
+    /// Returns `true` if the callback with the given ID has expired, i.e. a
+    /// `SYS_UNKNOWN` reject response was already enqueued for it.
+    pub fn is_expired(&self, callback_id: CallbackId) -> bool {
+        self.expired_callbacks.contains(&callback_id)
     }
 
     /// Returns the call origin, which is either the message id of the ingress
@@ -601,9 +655,11 @@ impl CallContextManager {
 impl From<&CanisterCall> for CallOrigin {
     fn from(msg: &CanisterCall) -> Self {
         match msg {
-            CanisterCall::Request(request) => {
-                CallOrigin::CanisterUpdate(request.sender, request.sender_reply_callback)
-            }
+            CanisterCall::Request(request) => CallOrigin::CanisterUpdate(
+                request.sender,
+                request.sender_reply_callback,
+                request.deadline,
+            ),
             CanisterCall::Ingress(ingress) => {
                 CallOrigin::Ingress(ingress.source, ingress.message_id.clone())
             }
@@ -641,6 +697,7 @@ impl From<&CallContextManager> for pb::CallContextManager {
                     callback: Some(callback.into()),
                 })
                 .collect(),
+            expired_callbacks: item.expired_callbacks.iter().map(|id| id.get()).collect(),
         }
     }
 }
@@ -671,11 +728,24 @@ impl TryFrom<pb::CallContextManager> for CallContextManager {
             );
         }
 
+        let expired_callbacks: BTreeSet<CallbackId> = value
+            .expired_callbacks
+            .into_iter()
+            .map(CallbackId::from)
+            .collect();
+        let unexpired_callbacks = callbacks
+            .iter()
+            .filter(|(id, _)| !expired_callbacks.contains(id))
+            .filter_map(|(id, callback)| callback.deadline.map(|deadline| (deadline, *id)))
+            .collect();
+
         Ok(Self {
             next_call_context_id: value.next_call_context_id,
             next_callback_id: value.next_callback_id,
             call_contexts,
             callbacks,
+            unexpired_callbacks,
+            expired_callbacks,
         })
     }
 }
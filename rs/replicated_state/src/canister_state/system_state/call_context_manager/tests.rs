@@ -9,14 +9,14 @@ fn call_context_origin() {
     let id = canister_test_id(42);
     let cb_id = CallbackId::from(1);
     let cc_id = ccm.new_call_context(
-        CallOrigin::CanisterUpdate(id, cb_id),
+        CallOrigin::CanisterUpdate(id, cb_id, None),
         Cycles::new(10),
         Time::from_nanos_since_unix_epoch(0),
         RequestMetadata::new(0, mock_time()),
     );
     assert_eq!(
         ccm.call_contexts().get(&cc_id).unwrap().call_origin,
-        CallOrigin::CanisterUpdate(id, cb_id)
+        CallOrigin::CanisterUpdate(id, cb_id, None)
     );
 }
 
@@ -28,20 +28,20 @@ fn call_context_handling() {
 
     // On two incoming calls
     let call_context_id1 = call_context_manager.new_call_context(
-        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(1)),
+        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(1), None),
         Cycles::zero(),
         Time::from_nanos_since_unix_epoch(0),
         RequestMetadata::new(0, mock_time()),
     );
     let call_context_id2 = call_context_manager.new_call_context(
-        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(2)),
+        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(2), None),
         Cycles::zero(),
         Time::from_nanos_since_unix_epoch(0),
         RequestMetadata::new(0, mock_time()),
     );
 
     let call_context_id3 = call_context_manager.new_call_context(
-        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(3)),
+        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(3), None),
         Cycles::zero(),
         Time::from_nanos_since_unix_epoch(0),
         RequestMetadata::new(0, mock_time()),
@@ -83,6 +83,7 @@ fn call_context_handling() {
         WasmClosure::new(0, 1),
         WasmClosure::new(2, 3),
         None,
+        None,
     ));
     let callback_id2 = call_context_manager.register_callback(Callback::new(
         call_context_id1,
@@ -94,6 +95,7 @@ fn call_context_handling() {
         WasmClosure::new(4, 5),
         WasmClosure::new(6, 7),
         None,
+        None,
     ));
 
     // There are 2 ougoing calls
@@ -110,6 +112,7 @@ fn call_context_handling() {
         WasmClosure::new(8, 9),
         WasmClosure::new(10, 11),
         None,
+        None,
     ));
     // There is 1 outgoing call
     assert_eq!(call_context_manager.outstanding_calls(call_context_id2), 1);
@@ -257,7 +260,7 @@ fn withdraw_cycles_fails_when_not_enough_available_cycles() {
     let id = canister_test_id(42);
     let cb_id = CallbackId::from(1);
     let cc_id = ccm.new_call_context(
-        CallOrigin::CanisterUpdate(id, cb_id),
+        CallOrigin::CanisterUpdate(id, cb_id, None),
         Cycles::new(30),
         Time::from_nanos_since_unix_epoch(0),
         RequestMetadata::new(0, mock_time()),
@@ -280,7 +283,7 @@ fn withdraw_cycles_succeeds_when_enough_available_cycles() {
     let id = canister_test_id(42);
     let cb_id = CallbackId::from(1);
     let cc_id = ccm.new_call_context(
-        CallOrigin::CanisterUpdate(id, cb_id),
+        CallOrigin::CanisterUpdate(id, cb_id, None),
         Cycles::new(30),
         Time::from_nanos_since_unix_epoch(0),
         RequestMetadata::new(0, mock_time()),
@@ -298,7 +301,7 @@ fn withdraw_cycles_succeeds_when_enough_available_cycles() {
 fn test_call_context_instructions_executed_is_updated() {
     let mut call_context_manager = CallContextManager::default();
     let call_context_id = call_context_manager.new_call_context(
-        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(1)),
+        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(1), None),
         Cycles::zero(),
         Time::from_nanos_since_unix_epoch(0),
         RequestMetadata::new(0, mock_time()),
@@ -314,6 +317,7 @@ fn test_call_context_instructions_executed_is_updated() {
         WasmClosure::new(0, 1),
         WasmClosure::new(2, 3),
         None,
+        None,
     ));
 
     // Finish a successful execution with 1K instructions.
@@ -351,3 +355,107 @@ fn test_call_context_instructions_executed_is_updated() {
         (1_000 + 2_000).into()
     );
 }
+
+/// Registers a callback with the given deadline on a fresh call context.
+fn register_callback_with_deadline(
+    call_context_manager: &mut CallContextManager,
+    deadline: Option<Time>,
+) -> CallbackId {
+    let call_context_id = call_context_manager.new_call_context(
+        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(1), None),
+        Cycles::zero(),
+        Time::from_nanos_since_unix_epoch(0),
+        RequestMetadata::new(0, mock_time()),
+    );
+    call_context_manager.register_callback(Callback::new(
+        call_context_id,
+        canister_test_id(1),
+        canister_test_id(2),
+        Cycles::zero(),
+        Cycles::new(42),
+        Cycles::new(84),
+        WasmClosure::new(0, 1),
+        WasmClosure::new(2, 3),
+        None,
+        deadline,
+    ))
+}
+
+#[test]
+fn expire_callbacks_in_deadline_order() {
+    let mut ccm = CallContextManager::default();
+    let guaranteed = register_callback_with_deadline(&mut ccm, None);
+    let late =
+        register_callback_with_deadline(&mut ccm, Some(Time::from_nanos_since_unix_epoch(20)));
+    let early =
+        register_callback_with_deadline(&mut ccm, Some(Time::from_nanos_since_unix_epoch(10)));
+
+    // Nothing has expired yet.
+    assert!(!ccm.has_expired_callbacks(Time::from_nanos_since_unix_epoch(10)));
+    assert!(ccm
+        .expire_callbacks(Time::from_nanos_since_unix_epoch(10))
+        .is_empty());
+
+    // Only the earlier callback expires.
+    assert!(ccm.has_expired_callbacks(Time::from_nanos_since_unix_epoch(11)));
+    assert_eq!(
+        vec![early],
+        ccm.expire_callbacks(Time::from_nanos_since_unix_epoch(11))
+    );
+    assert!(ccm.is_expired(early));
+    assert!(!ccm.is_expired(late));
+    assert!(!ccm.has_expired_callbacks(Time::from_nanos_since_unix_epoch(11)));
+
+    // A callback is only ever expired once; guaranteed response callbacks never expire.
+    assert_eq!(
+        vec![late],
+        ccm.expire_callbacks(Time::from_nanos_since_unix_epoch(u64::MAX))
+    );
+    assert!(!ccm.is_expired(guaranteed));
+    assert!(!ccm.has_expired_callbacks(Time::from_nanos_since_unix_epoch(u64::MAX)));
+
+    // Expired callbacks are retained until unregistered.
+    assert_eq!(3, ccm.callbacks().len());
+    assert!(ccm.unregister_callback(early).is_some());
+    assert!(!ccm.is_expired(early));
+    assert_eq!(2, ccm.callbacks().len());
+}
+
+#[test]
+fn unregister_callback_before_deadline() {
+    let mut ccm = CallContextManager::default();
+    let callback_id =
+        register_callback_with_deadline(&mut ccm, Some(Time::from_nanos_since_unix_epoch(10)));
+
+    assert!(ccm.unregister_callback(callback_id).is_some());
+    assert!(!ccm.has_expired_callbacks(Time::from_nanos_since_unix_epoch(u64::MAX)));
+    assert!(ccm
+        .expire_callbacks(Time::from_nanos_since_unix_epoch(u64::MAX))
+        .is_empty());
+}
+
+#[test]
+fn expired_callbacks_proto_roundtrip() {
+    let mut ccm = CallContextManager::default();
+    let expired =
+        register_callback_with_deadline(&mut ccm, Some(Time::from_nanos_since_unix_epoch(10)));
+    let unexpired =
+        register_callback_with_deadline(&mut ccm, Some(Time::from_nanos_since_unix_epoch(20)));
+    register_callback_with_deadline(&mut ccm, None);
+    assert_eq!(
+        vec![expired],
+        ccm.expire_callbacks(Time::from_nanos_since_unix_epoch(15))
+    );
+
+    let pb_ccm: pb::CallContextManager = (&ccm).into();
+    let round_trip = CallContextManager::try_from(pb_ccm).unwrap();
+
+    assert_eq!(ccm, round_trip);
+    assert!(round_trip.is_expired(expired));
+    assert_eq!(
+        vec![unexpired],
+        round_trip
+            .clone()
+            .expire_callbacks(Time::from_nanos_since_unix_epoch(u64::MAX))
+    );
+}
@@ -13,6 +13,7 @@ use crate::metadata_state::subnet_call_context_manager::InstallCodeCallId;
 use crate::CallOrigin;
 use crate::Memory;
 use ic_base_types::NumSeconds;
+use ic_error_types::RejectCode;
 use ic_ic00_types::{CanisterChange, CanisterChangeDetails, CanisterChangeOrigin};
 use ic_logger::replica_logger::no_op_logger;
 use ic_metrics::MetricsRegistry;
@@ -25,8 +26,8 @@ use ic_test_utilities::types::{
 use ic_test_utilities_time::mock_time;
 use ic_types::{
     messages::{
-        CallContextId, CallbackId, CanisterCall, RequestMetadata, StopCanisterCallId,
-        StopCanisterContext, MAX_RESPONSE_COUNT_BYTES,
+        CallContextId, CallbackId, CanisterCall, Payload, RejectContext, RequestMetadata,
+        StopCanisterCallId, StopCanisterContext, MAX_RESPONSE_COUNT_BYTES,
     },
     methods::{Callback, WasmClosure},
     nominal_cycles::NominalCycles,
@@ -89,13 +90,17 @@ impl CanisterStateFixture {
     }
 
     fn make_callback(&mut self) -> CallbackId {
+        self.make_callback_with_deadline(None)
+    }
+
+    fn make_callback_with_deadline(&mut self, deadline: Option<Time>) -> CallbackId {
         let call_context_id = self
             .canister_state
             .system_state
             .call_context_manager_mut()
             .unwrap()
             .new_call_context(
-                CallOrigin::CanisterUpdate(CANISTER_ID, CallbackId::from(1)),
+                CallOrigin::CanisterUpdate(CANISTER_ID, CallbackId::from(1), None),
                 Cycles::zero(),
                 Time::from_nanos_since_unix_epoch(0),
                 RequestMetadata::new(0, mock_time()),
@@ -114,6 +119,7 @@ impl CanisterStateFixture {
                 WasmClosure::new(0, 2),
                 WasmClosure::new(0, 2),
                 None,
+                deadline,
             ))
     }
 
@@ -186,6 +192,66 @@ fn canister_state_push_input_response_success() {
         .unwrap();
 }
 
+#[test]
+fn canister_state_time_out_callbacks() {
+    let mut fixture = CanisterStateFixture::new();
+    let deadline = Time::from_nanos_since_unix_epoch(1_000);
+    // Make an input queue reservation for the best-effort callback.
+    fixture.with_input_reservation();
+    let callback_id = fixture.make_callback_with_deadline(Some(deadline));
+
+    // Nothing times out before the deadline.
+    assert_eq!(
+        0,
+        fixture.canister_state.system_state.time_out_callbacks(
+            deadline,
+            &CANISTER_ID,
+            &BTreeMap::new()
+        )
+    );
+    assert_eq!(
+        1,
+        fixture.canister_state.system_state.time_out_callbacks(
+            deadline + Duration::from_nanos(1),
+            &CANISTER_ID,
+            &BTreeMap::new()
+        )
+    );
+
+    // A `SYS_UNKNOWN` reject response was enqueued.
+    match fixture.canister_state.pop_input() {
+        Some(CanisterMessage::Response(response)) => {
+            assert_eq!(callback_id, response.originator_reply_callback);
+            assert_eq!(Some(deadline), response.deadline);
+            assert_eq!(
+                Payload::Reject(RejectContext::new(
+                    RejectCode::SysUnknown,
+                    "Call deadline has expired."
+                )),
+                response.response_payload
+            );
+        }
+        msg => panic!("Expected a reject response, got {:?}", msg),
+    }
+
+    // The late response is silently dropped.
+    let late_response: RequestOrResponse = ResponseBuilder::default()
+        .originator(CANISTER_ID)
+        .respondent(OTHER_CANISTER_ID)
+        .originator_reply_callback(callback_id)
+        .deadline(Some(deadline))
+        .build()
+        .into();
+    fixture
+        .push_input(
+            late_response,
+            SubnetType::Application,
+            InputQueueType::RemoteSubnet,
+        )
+        .unwrap();
+    assert!(fixture.canister_state.pop_input().is_none());
+}
+
 #[test]
 #[should_panic(expected = "Expected `RequestOrResponse` to be targeted to canister ID")]
 fn canister_state_push_input_request_mismatched_receiver() {
@@ -566,6 +632,7 @@ fn canister_state_callback_round_trip() {
         WasmClosure::new(0, 2),
         WasmClosure::new(0, 2),
         None,
+        None,
     );
 
     let pb_callback = pb::Callback::from(&callback);
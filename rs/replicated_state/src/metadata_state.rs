@@ -10,7 +10,7 @@ use ic_btc_types_internal::BlockBlob;
 use ic_certification_version::{CertificationVersion, CURRENT_CERTIFICATION_VERSION};
 use ic_constants::MAX_INGRESS_TTL;
 use ic_error_types::{ErrorCode, RejectCode, UserError};
-use ic_ic00_types::{EcdsaKeyId, NodeMetrics, NodeMetricsHistoryResponse};
+use ic_ic00_types::{EcdsaKeyId, NodeMetrics, NodeMetricsHistoryResponse, SchnorrKeyId};
 use ic_protobuf::{
     proxy::{try_from_option_field, ProxyDecodeError},
     registry::subnet::v1 as pb_subnet,
@@ -185,6 +185,9 @@ pub struct NetworkTopology {
     /// Mapping from ECDSA key_id to a list of subnets which can sign with the
     /// given key. Keys without any signing subnets are not included in the map.
     pub ecdsa_signing_subnets: BTreeMap<EcdsaKeyId, Vec<SubnetId>>,
+    /// Mapping from Schnorr key_id to a list of subnets which can sign with the
+    /// given key. Keys without any signing subnets are not included in the map.
+    pub schnorr_signing_subnets: BTreeMap<SchnorrKeyId, Vec<SubnetId>>,
 
     /// The ID of the canister to forward bitcoin testnet requests to.
     pub bitcoin_testnet_canister_id: Option<CanisterId>,
@@ -216,6 +219,7 @@ impl Default for NetworkTopology {
             canister_migrations: Default::default(),
             nns_subnet_id: SubnetId::new(PrincipalId::new_anonymous()),
             ecdsa_signing_subnets: Default::default(),
+            schnorr_signing_subnets: Default::default(),
             bitcoin_testnet_canister_id: None,
             bitcoin_mainnet_canister_id: None,
         }
@@ -231,6 +235,14 @@ impl NetworkTopology {
             .unwrap_or(&[])
     }
 
+    /// Returns a list of subnets which can sign with the given Schnorr key.
+    pub fn schnorr_signing_subnets(&self, key_id: &SchnorrKeyId) -> &[SubnetId] {
+        self.schnorr_signing_subnets
+            .get(key_id)
+            .map(|ids| &ids[..])
+            .unwrap_or(&[])
+    }
+
     /// Returns the size of the given subnet.
     pub fn get_subnet_size(&self, subnet_id: &SubnetId) -> Option<usize> {
         self.subnets
@@ -267,6 +279,20 @@ impl From<&NetworkTopology> for pb_metadata::NetworkTopology {
                     }
                 })
                 .collect(),
+            schnorr_signing_subnets: item
+                .schnorr_signing_subnets
+                .iter()
+                .map(|(key_id, subnet_ids)| {
+                    let subnet_ids = subnet_ids
+                        .iter()
+                        .map(|id| subnet_id_into_protobuf(*id))
+                        .collect();
+                    pb_metadata::SchnorrKeyEntry {
+                        key_id: Some(key_id.into()),
+                        subnet_ids,
+                    }
+                })
+                .collect(),
             bitcoin_testnet_canister_ids: match item.bitcoin_testnet_canister_id {
                 Some(c) => vec![pb_types::CanisterId::from(c)],
                 None => vec![],
@@ -310,6 +336,18 @@ impl TryFrom<pb_metadata::NetworkTopology> for NetworkTopology {
             );
         }
 
+        let mut schnorr_signing_subnets = BTreeMap::new();
+        for entry in item.schnorr_signing_subnets {
+            let mut subnet_ids = vec![];
+            for subnet_id in entry.subnet_ids {
+                subnet_ids.push(subnet_id_try_from_protobuf(subnet_id)?);
+            }
+            schnorr_signing_subnets.insert(
+                try_from_option_field(entry.key_id, "SchnorrKeyEntry::key_id")?,
+                subnet_ids,
+            );
+        }
+
         let bitcoin_testnet_canister_id = match item.bitcoin_testnet_canister_ids.first() {
             Some(canister) => Some(CanisterId::try_from(canister.clone())?),
             None => None,
@@ -336,6 +374,7 @@ impl TryFrom<pb_metadata::NetworkTopology> for NetworkTopology {
                 .into(),
             nns_subnet_id,
             ecdsa_signing_subnets,
+            schnorr_signing_subnets,
             bitcoin_testnet_canister_id,
             bitcoin_mainnet_canister_id,
         })
@@ -412,6 +451,8 @@ pub struct SubnetMetrics {
     pub consumed_cycles_ecdsa_outcalls: NominalCycles,
     consumed_cycles_by_use_case: BTreeMap<CyclesUseCase, NominalCycles>,
     pub ecdsa_signature_agreements: u64,
+    /// The number of agreed `sign_with_schnorr` signatures, per key.
+    pub schnorr_signature_agreements: BTreeMap<SchnorrKeyId, u64>,
     /// The number of canisters that exist on this subnet.
     pub num_canisters: u64,
     /// The total size of the state taken by canisters on this subnet in bytes.
@@ -463,6 +504,7 @@ impl SubnetMetrics {
                 | CyclesUseCase::RequestAndResponseTransmission
                 | CyclesUseCase::Uninstall
                 | CyclesUseCase::CanisterCreation
+                | CyclesUseCase::SchnorrOutcalls
                 | CyclesUseCase::BurnedCycles => total += *cycles,
             }
         }
@@ -492,6 +534,16 @@ impl From<&SubnetMetrics> for pb_metadata::SubnetMetrics {
             num_canisters: Some(item.num_canisters),
             canister_state_bytes: Some(item.canister_state_bytes.get()),
             update_transactions_total: Some(item.update_transactions_total),
+            schnorr_signature_agreements: item
+                .schnorr_signature_agreements
+                .iter()
+                .map(
+                    |(key_id, count)| pb_metadata::SchnorrSignatureAgreementsEntry {
+                        key_id: Some(key_id.into()),
+                        count: *count,
+                    },
+                )
+                .collect(),
         }
     }
 }
@@ -499,6 +551,14 @@ impl From<&SubnetMetrics> for pb_metadata::SubnetMetrics {
 impl TryFrom<pb_metadata::SubnetMetrics> for SubnetMetrics {
     type Error = ProxyDecodeError;
     fn try_from(item: pb_metadata::SubnetMetrics) -> Result<Self, Self::Error> {
+        let mut schnorr_signature_agreements = BTreeMap::new();
+        for entry in item.schnorr_signature_agreements {
+            schnorr_signature_agreements.insert(
+                try_from_option_field(entry.key_id, "SchnorrSignatureAgreementsEntry::key_id")?,
+                entry.count,
+            );
+        }
+
         Ok(Self {
             consumed_cycles_by_deleted_canisters: try_from_option_field(
                 item.consumed_cycles_by_deleted_canisters,
@@ -537,6 +597,7 @@ impl TryFrom<pb_metadata::SubnetMetrics> for SubnetMetrics {
                 item.update_transactions_total,
                 "SubnetMetrics::update_transactions_total",
             )?,
+            schnorr_signature_agreements,
         })
     }
 }
@@ -602,6 +663,8 @@ impl From<&SystemMetadata> for pb_metadata::SystemMetadata {
                 )
                 .collect(),
             blockmaker_metrics_time_series: Some((&item.blockmaker_metrics_time_series).into()),
+            // Owned by `ReplicatedState::canister_snapshots`, set when writing the checkpoint.
+            next_snapshot_id: 0,
         }
     }
 }
@@ -1142,6 +1205,7 @@ impl SystemMetadata {
                         RejectCode::SysTransient,
                         format!("Canister {} migrated during a subnet split", canister_id),
                     )),
+                    deadline: request.deadline,
                 };
                 subnet_queues.push_output_response(response.into());
             }
@@ -1,5 +1,5 @@
 use ic_btc_types_internal::{GetSuccessorsRequestInitial, SendTransactionRequest};
-use ic_ic00_types::EcdsaKeyId;
+use ic_ic00_types::{EcdsaKeyId, SchnorrKeyId};
 use ic_logger::{info, ReplicaLogger};
 use ic_protobuf::{
     proxy::{try_from_option_field, ProxyDecodeError},
@@ -23,6 +23,7 @@ use std::{
 pub enum SubnetCallContext {
     SetupInitialDKG(SetupInitialDkgContext),
     SignWithEcdsa(SignWithEcdsaContext),
+    SignWithSchnorr(SignWithSchnorrContext),
     CanisterHttpRequest(CanisterHttpRequestContext),
     EcdsaDealings(EcdsaDealingsContext),
     BitcoinGetSuccessors(BitcoinGetSuccessorsContext),
@@ -34,6 +35,7 @@ impl SubnetCallContext {
         match &self {
             SubnetCallContext::SetupInitialDKG(context) => &context.request,
             SubnetCallContext::SignWithEcdsa(context) => &context.request,
+            SubnetCallContext::SignWithSchnorr(context) => &context.request,
             SubnetCallContext::CanisterHttpRequest(context) => &context.request,
             SubnetCallContext::EcdsaDealings(context) => &context.request,
             SubnetCallContext::BitcoinGetSuccessors(context) => &context.request,
@@ -45,6 +47,7 @@ impl SubnetCallContext {
         match &self {
             SubnetCallContext::SetupInitialDKG(context) => context.time,
             SubnetCallContext::SignWithEcdsa(context) => context.batch_time,
+            SubnetCallContext::SignWithSchnorr(context) => context.batch_time,
             SubnetCallContext::CanisterHttpRequest(context) => context.time,
             SubnetCallContext::EcdsaDealings(context) => context.time,
             SubnetCallContext::BitcoinGetSuccessors(context) => context.time,
@@ -199,6 +202,7 @@ pub struct SubnetCallContextManager {
     next_callback_id: u64,
     pub setup_initial_dkg_contexts: BTreeMap<CallbackId, SetupInitialDkgContext>,
     pub sign_with_ecdsa_contexts: BTreeMap<CallbackId, SignWithEcdsaContext>,
+    pub sign_with_schnorr_contexts: BTreeMap<CallbackId, SignWithSchnorrContext>,
     pub canister_http_request_contexts: BTreeMap<CallbackId, CanisterHttpRequestContext>,
     pub ecdsa_dealings_contexts: BTreeMap<CallbackId, EcdsaDealingsContext>,
     pub bitcoin_get_successors_contexts: BTreeMap<CallbackId, BitcoinGetSuccessorsContext>,
@@ -224,6 +228,9 @@ impl SubnetCallContextManager {
             SubnetCallContext::SignWithEcdsa(context) => {
                 self.sign_with_ecdsa_contexts.insert(callback_id, context);
             }
+            SubnetCallContext::SignWithSchnorr(context) => {
+                self.sign_with_schnorr_contexts.insert(callback_id, context);
+            }
             SubnetCallContext::CanisterHttpRequest(context) => {
                 self.canister_http_request_contexts
                     .insert(callback_id, context);
@@ -272,6 +279,19 @@ impl SubnetCallContextManager {
                         SubnetCallContext::SignWithEcdsa(context)
                     })
             })
+            .or_else(|| {
+                self.sign_with_schnorr_contexts
+                    .remove(&callback_id)
+                    .map(|context| {
+                        info!(
+                            logger,
+                            "Received the response for SignWithSchnorr request with id {:?} from {:?}",
+                            context.pseudo_random_id,
+                            context.request.sender
+                        );
+                        SubnetCallContext::SignWithSchnorr(context)
+                    })
+            })
             .or_else(|| {
                 self.ecdsa_dealings_contexts
                     .remove(&callback_id)
@@ -435,6 +455,16 @@ impl From<&SubnetCallContextManager> for pb_metadata::SubnetCallContextManager {
                     },
                 )
                 .collect(),
+            sign_with_schnorr_contexts: item
+                .sign_with_schnorr_contexts
+                .iter()
+                .map(
+                    |(callback_id, context)| pb_metadata::SignWithSchnorrContextTree {
+                        callback_id: callback_id.get(),
+                        context: Some(context.into()),
+                    },
+                )
+                .collect(),
             canister_http_request_contexts: item
                 .canister_http_request_contexts
                 .iter()
@@ -534,6 +564,13 @@ impl TryFrom<(Time, pb_metadata::SubnetCallContextManager)> for SubnetCallContex
             sign_with_ecdsa_contexts.insert(CallbackId::new(entry.callback_id), context);
         }
 
+        let mut sign_with_schnorr_contexts = BTreeMap::<CallbackId, SignWithSchnorrContext>::new();
+        for entry in item.sign_with_schnorr_contexts {
+            let context: SignWithSchnorrContext =
+                try_from_option_field(entry.context, "SystemMetadata::SignWithSchnorrContext")?;
+            sign_with_schnorr_contexts.insert(CallbackId::new(entry.callback_id), context);
+        }
+
         let mut canister_http_request_contexts =
             BTreeMap::<CallbackId, CanisterHttpRequestContext>::new();
         for entry in item.canister_http_request_contexts {
@@ -614,6 +651,7 @@ impl TryFrom<(Time, pb_metadata::SubnetCallContextManager)> for SubnetCallContex
             next_callback_id: item.next_callback_id,
             setup_initial_dkg_contexts,
             sign_with_ecdsa_contexts,
+            sign_with_schnorr_contexts,
             canister_http_request_contexts,
             ecdsa_dealings_contexts,
             bitcoin_get_successors_contexts,
@@ -762,6 +800,52 @@ impl TryFrom<pb_metadata::SignWithEcdsaContext> for SignWithEcdsaContext {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignWithSchnorrContext {
+    pub request: Request,
+    pub key_id: SchnorrKeyId,
+    pub message: Vec<u8>,
+    pub derivation_path: Vec<Vec<u8>>,
+    pub pseudo_random_id: [u8; 32],
+    pub batch_time: Time,
+}
+
+impl From<&SignWithSchnorrContext> for pb_metadata::SignWithSchnorrContext {
+    fn from(context: &SignWithSchnorrContext) -> Self {
+        pb_metadata::SignWithSchnorrContext {
+            request: Some((&context.request).into()),
+            key_id: Some((&context.key_id).into()),
+            message: context.message.clone(),
+            derivation_path_vec: context.derivation_path.clone(),
+            pseudo_random_id: context.pseudo_random_id.to_vec(),
+            batch_time: context.batch_time.as_nanos_since_unix_epoch(),
+        }
+    }
+}
+
+impl TryFrom<pb_metadata::SignWithSchnorrContext> for SignWithSchnorrContext {
+    type Error = ProxyDecodeError;
+    fn try_from(context: pb_metadata::SignWithSchnorrContext) -> Result<Self, Self::Error> {
+        let request: Request =
+            try_from_option_field(context.request, "SignWithSchnorrContext::request")?;
+        let key_id: SchnorrKeyId =
+            try_from_option_field(context.key_id, "SignWithSchnorrContext::key_id")?;
+        Ok(SignWithSchnorrContext {
+            request,
+            key_id,
+            message: context.message,
+            derivation_path: context.derivation_path_vec,
+            pseudo_random_id: context.pseudo_random_id.try_into().map_err(|_| {
+                Self::Error::Other(format!(
+                    "pseudo_random_id is not {} bytes.",
+                    NiDkgTargetId::SIZE
+                ))
+            })?,
+            batch_time: Time::from_nanos_since_unix_epoch(context.batch_time),
+        })
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct EcdsaDealingsContext {
     pub request: Request,
@@ -1101,6 +1185,7 @@ mod testing {
             next_callback_id: 0,
             setup_initial_dkg_contexts: Default::default(),
             sign_with_ecdsa_contexts: Default::default(),
+            sign_with_schnorr_contexts: Default::default(),
             canister_http_request_contexts: Default::default(),
             ecdsa_dealings_contexts: Default::default(),
             bitcoin_get_successors_contexts: Default::default(),
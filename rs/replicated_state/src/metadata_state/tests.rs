@@ -5,7 +5,7 @@ use crate::metadata_state::subnet_call_context_manager::{
 use assert_matches::assert_matches;
 use ic_constants::MAX_INGRESS_TTL;
 use ic_error_types::{ErrorCode, UserError};
-use ic_ic00_types::{EcdsaCurve, IC_00};
+use ic_ic00_types::{EcdsaCurve, SchnorrAlgorithm, SchnorrKeyId, IC_00};
 use ic_registry_routing_table::CanisterIdRange;
 use ic_test_utilities::types::{
     ids::{
@@ -835,6 +835,32 @@ fn network_topology_ecdsa_subnets() {
     );
 }
 
+#[test]
+fn network_topology_schnorr_subnets_round_trip() {
+    let key = SchnorrKeyId {
+        algorithm: SchnorrAlgorithm::Bip340Secp256k1,
+        name: "key_1".to_string(),
+    };
+    let network_topology = NetworkTopology {
+        nns_subnet_id: subnet_test_id(42),
+        schnorr_signing_subnets: btreemap! {
+            key.clone() => vec![subnet_test_id(1)],
+        },
+        ..Default::default()
+    };
+
+    assert_eq!(
+        network_topology.schnorr_signing_subnets(&key),
+        &[subnet_test_id(1)]
+    );
+
+    let pb_network_topology = pb_metadata::NetworkTopology::from(&network_topology);
+    assert_eq!(
+        NetworkTopology::try_from(pb_network_topology).unwrap(),
+        network_topology
+    );
+}
+
 /// Test fixture that will produce an ingress status of type completed or failed,
 /// depending on whether `i % 2 == 0` (completed) or not (failed). Both statuses
 /// will have the same payload size.
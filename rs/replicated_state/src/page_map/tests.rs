@@ -191,6 +191,46 @@ fn persisted_map_is_equivalent_to_the_original() {
     assert_eq!(105 * PAGE_SIZE as u64, heap_file.metadata().unwrap().len());
 }
 
+#[test]
+fn can_persist_and_load_a_page_beyond_4_gib() {
+    // 64-bit Wasm memories can grow beyond 4 GiB.
+    let page_index = PageIndex::new(((5 << 30) / PAGE_SIZE) as u64);
+    let tmp = tempfile::Builder::new()
+        .prefix("checkpoints")
+        .tempdir()
+        .unwrap();
+    let heap_file = tmp.path().join("heap");
+    let metrics = StorageMetrics::new(&MetricsRegistry::new());
+
+    let mut pagemap = PageMap::new_for_testing();
+    pagemap.update(&[(page_index, &[5u8; PAGE_SIZE])]);
+    assert_eq!(pagemap.get_page(page_index), &[5u8; PAGE_SIZE]);
+    pagemap
+        .persist_delta(
+            PersistDestination::BaseFile(heap_file.to_path_buf()),
+            &metrics,
+        )
+        .unwrap();
+    assert_eq!(
+        (page_index.get() + 1) * PAGE_SIZE as u64,
+        heap_file.metadata().unwrap().len()
+    );
+
+    let persisted_map = PageMap::open(
+        &heap_file,
+        &[],
+        Height::new(0),
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+    )
+    .unwrap();
+    assert_eq!(persisted_map.num_host_pages(), pagemap.num_host_pages());
+    assert_eq!(persisted_map.get_page(page_index), &[5u8; PAGE_SIZE]);
+    assert_eq!(
+        persisted_map.get_page(PageIndex::new(page_index.get() - 1)),
+        &[0u8; PAGE_SIZE]
+    );
+}
+
 #[test]
 fn can_persist_and_load_an_empty_page_map() {
     let tmp = tempfile::Builder::new()
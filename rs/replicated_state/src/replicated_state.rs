@@ -3,6 +3,7 @@ use super::{
     metadata_state::{IngressHistoryState, Stream, Streams, SystemMetadata},
 };
 use crate::{
+    canister_snapshots::CanisterSnapshots,
     canister_state::queues::CanisterQueuesLoopDetector,
     canister_state::system_state::{push_input, CanisterOutputQueuesIterator},
     metadata_state::{subnet_call_context_manager::SignWithEcdsaContext, StreamMap},
@@ -406,6 +407,9 @@ pub struct ReplicatedState {
     /// Temporary query stats received during the current epoch.
     /// Reset during the start of each epoch.
     pub epoch_query_stats: RawQueryStats,
+
+    /// Manages the canister snapshots.
+    pub canister_snapshots: CanisterSnapshots,
 }
 
 impl ReplicatedState {
@@ -417,6 +421,7 @@ impl ReplicatedState {
             subnet_queues: CanisterQueues::default(),
             consensus_queue: Vec::new(),
             epoch_query_stats: RawQueryStats::default(),
+            canister_snapshots: CanisterSnapshots::default(),
         }
     }
 
@@ -426,6 +431,7 @@ impl ReplicatedState {
         metadata: SystemMetadata,
         subnet_queues: CanisterQueues,
         epoch_query_stats: RawQueryStats,
+        canister_snapshots: CanisterSnapshots,
     ) -> Self {
         let mut res = Self {
             canister_states,
@@ -433,6 +439,7 @@ impl ReplicatedState {
             subnet_queues,
             consensus_queue: Vec::new(),
             epoch_query_stats,
+            canister_snapshots,
         };
         res.update_stream_responses_size_bytes();
         res
@@ -885,6 +892,40 @@ impl ReplicatedState {
         timed_out_requests_count
     }
 
+    /// Times out all best-effort callbacks with expired deadlines (given the
+    /// state time) across all canisters, enqueuing `SYS_UNKNOWN` reject
+    /// responses for them. Returns the number of timed out callbacks.
+    ///
+    /// See `SystemState::time_out_callbacks` for further details.
+    pub fn time_out_callbacks(&mut self) -> u64 {
+        let current_time = self.metadata.time();
+        // Same remove-call-replace approach as in `time_out_requests()`, applied
+        // only to canisters with expired callbacks.
+        let canister_ids_with_expired_callbacks = self
+            .canister_states
+            .iter()
+            .filter(|(_, canister_state)| {
+                canister_state
+                    .system_state
+                    .has_expired_callbacks(current_time)
+            })
+            .map(|(canister_id, _)| *canister_id)
+            .collect::<Vec<_>>();
+
+        let mut timed_out_callbacks_count = 0;
+        for canister_id in canister_ids_with_expired_callbacks {
+            let mut canister = self.canister_states.remove(&canister_id).unwrap();
+            timed_out_callbacks_count += canister.system_state.time_out_callbacks(
+                current_time,
+                &canister_id,
+                &self.canister_states,
+            );
+            self.canister_states.insert(canister_id, canister);
+        }
+
+        timed_out_callbacks_count
+    }
+
     /// Splits the replicated state as part of subnet splitting phase 1, retaining
     /// only the canisters of `subnet_id` (as determined by the provided routing
     /// table).
@@ -923,6 +964,7 @@ impl ReplicatedState {
             mut subnet_queues,
             consensus_queue,
             epoch_query_stats: _,
+            mut canister_snapshots,
         } = self;
 
         // Consensus queue is always empty at the end of the round.
@@ -946,6 +988,9 @@ impl ReplicatedState {
         if metadata.own_subnet_id != subnet_id {
             // On subnet B, start with empty subnet queues.
             subnet_queues = CanisterQueues::default();
+            // Canister snapshots only remain on subnet A'. Snapshots of canisters
+            // migrated away are dropped in the second phase of the split.
+            canister_snapshots = CanisterSnapshots::default();
         }
 
         // Obtain a new metadata state for subnet B. No-op for subnet A' (apart from
@@ -958,6 +1003,7 @@ impl ReplicatedState {
             subnet_queues,
             consensus_queue,
             epoch_query_stats: RawQueryStats::default(), // Don't preserve query stats during subnet splitting.
+            canister_snapshots,
         })
     }
 
@@ -969,6 +1015,8 @@ impl ReplicatedState {
     /// * Updates canisters' input schedules, based on `self.canister_states`.
     /// * Prunes the ingress history, retaining only messages addressed to this
     ///   subnet and messages in terminal states (which will time out).
+    /// * Drops the snapshots of canisters that are no longer hosted by this subnet
+    ///   and recomputes the snapshots memory usage of the remaining canisters.
     pub fn after_split(&mut self) {
         // Destructure `self` in order for the compiler to enforce an explicit decision
         // whenever new fields are added.
@@ -980,6 +1028,7 @@ impl ReplicatedState {
             ref mut subnet_queues,
             consensus_queue: _,
             epoch_query_stats: _,
+            ref mut canister_snapshots,
         } = self;
 
         // Reset query stats after subnet split
@@ -1009,6 +1058,16 @@ impl ReplicatedState {
             }
         }
 
+        // Drop the snapshots of canisters no longer on this subnet.
+        canister_snapshots.retain_canisters(&canister_states.keys().cloned().collect());
+
+        // Snapshots are only preserved on subnet A', so the snapshots memory usage
+        // of canisters must be brought in line with the snapshots actually present.
+        for (canister_id, canister_state) in canister_states.iter_mut() {
+            canister_state.system_state.snapshots_memory_usage =
+                canister_snapshots.memory_usage(*canister_id);
+        }
+
         // Prune the ingress history. And reject in-progress subnet messages being
         // executed by canisters no longer on this subnet.
         metadata.after_split(
@@ -15,10 +15,13 @@ use ic_replicated_state::metadata_state::subnet_call_context_manager::BitcoinSen
 use ic_replicated_state::replicated_state::testing::ReplicatedStateTesting;
 use ic_replicated_state::testing::{CanisterQueuesTesting, SystemStateTesting};
 use ic_replicated_state::{
+    canister_snapshots::{CanisterSnapshot, ExecutionStateSnapshot, MemorySnapshot},
     canister_state::execution_state::{CustomSection, CustomSectionType, WasmMetadata},
+    canister_state::system_state::wasm_chunk_store::WasmChunkStore,
     metadata_state::subnet_call_context_manager::{BitcoinGetSuccessorsContext, SubnetCallContext},
     replicated_state::{MemoryTaken, PeekableOutputIterator, ReplicatedStateMessageRouting},
-    CanisterState, IngressHistoryState, ReplicatedState, SchedulerState, StateError, SystemState,
+    CanisterState, Global, IngressHistoryState, NumWasmPages, PageMap, ReplicatedState,
+    SchedulerState, StateError, SystemState,
 };
 use ic_test_utilities::state::{arb_replicated_state_with_queues, ExecutionStateBuilder};
 use ic_test_utilities::types::ids::{canister_test_id, message_test_id, user_test_id, SUBNET_1};
@@ -32,6 +35,7 @@ use ic_types::{
     },
     CountBytes, Cycles, MemoryAllocation, Time,
 };
+use ic_wasm_types::CanisterModule;
 use maplit::btreemap;
 use proptest::prelude::*;
 use std::collections::{BTreeMap, VecDeque};
@@ -845,6 +849,90 @@ fn split() {
     assert_eq!(expected, state_b);
 }
 
+#[test]
+fn split_recomputes_snapshots_memory_usage() {
+    // We will be splitting subnet A into A' and B.
+    const SUBNET_A: SubnetId = SUBNET_ID;
+    const SUBNET_B: SubnetId = SUBNET_1;
+
+    const CANISTER_1: CanisterId = CANISTER_ID;
+    const CANISTER_2: CanisterId = OTHER_CANISTER_ID;
+    const CANISTERS: [CanisterId; 2] = [CANISTER_1, CANISTER_2];
+
+    // Retain `CANISTER_1` on `SUBNET_A`, migrate `CANISTER_2` to `SUBNET_B`.
+    let routing_table = RoutingTable::try_from(btreemap! {
+        CanisterIdRange {start: CANISTER_1, end: CANISTER_1} => SUBNET_A,
+        CanisterIdRange {start: CANISTER_2, end: CANISTER_2} => SUBNET_B,
+    })
+    .unwrap();
+
+    // Fixture with 2 canisters, each with one snapshot.
+    let mut fixture = ReplicatedStateFixture::with_canisters(&CANISTERS);
+    for canister_id in CANISTERS {
+        let snapshot = CanisterSnapshot::new(
+            canister_id,
+            mock_time(),
+            0,
+            vec![],
+            WasmChunkStore::new_for_testing(NumBytes::from(20)),
+            ExecutionStateSnapshot {
+                wasm_binary: CanisterModule::new(vec![1, 2, 3]),
+                exported_globals: vec![Global::I32(1)],
+                stable_memory: MemorySnapshot::new(
+                    PageMap::new_for_testing(),
+                    NumWasmPages::from(1),
+                ),
+                wasm_memory: MemorySnapshot::new(PageMap::new_for_testing(), NumWasmPages::from(2)),
+            },
+        );
+        let snapshot_size = snapshot.size();
+        fixture.state.canister_snapshots.push(Arc::new(snapshot));
+        fixture
+            .state
+            .canister_state_mut(&canister_id)
+            .unwrap()
+            .system_state
+            .snapshots_memory_usage = snapshot_size;
+    }
+    let snapshots_memory_usage = |state: &ReplicatedState, canister_id: &CanisterId| {
+        state
+            .canister_state(canister_id)
+            .unwrap()
+            .system_state
+            .snapshots_memory_usage
+    };
+    let snapshot_size = snapshots_memory_usage(&fixture.state, &CANISTER_1);
+    assert!(snapshot_size.get() > 0);
+
+    // Subnet A' retains the snapshot of `CANISTER_1` and its memory usage.
+    let mut state_a = fixture
+        .state
+        .clone()
+        .split(SUBNET_A, &routing_table, None)
+        .unwrap();
+    state_a.after_split();
+    assert_eq!(
+        state_a.canister_snapshots.memory_usage(CANISTER_1),
+        snapshot_size
+    );
+    assert_eq!(snapshots_memory_usage(&state_a, &CANISTER_1), snapshot_size);
+
+    // Subnet B drops the snapshot of `CANISTER_2`, so its memory usage must be reset.
+    let mut state_b = fixture
+        .state
+        .clone()
+        .split(SUBNET_B, &routing_table, None)
+        .unwrap();
+    // The canister state is preserved unmodified in phase 1.
+    assert_eq!(snapshots_memory_usage(&state_b, &CANISTER_2), snapshot_size);
+    state_b.after_split();
+    assert!(state_b.canister_snapshots.is_empty());
+    assert_eq!(
+        snapshots_memory_usage(&state_b, &CANISTER_2),
+        NumBytes::from(0)
+    );
+}
+
 proptest! {
     #[test]
     fn peek_and_next_consistent(
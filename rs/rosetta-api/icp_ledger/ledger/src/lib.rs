@@ -6,7 +6,10 @@ use ic_ledger_canister_core::ledger::{
     self as core_ledger, LedgerContext, LedgerData, TransactionInfo,
 };
 use ic_ledger_core::{
-    approvals::AllowanceTable, balances::Balances, block::EncodedBlock, timestamp::TimeStamp,
+    approvals::{AllowanceTable, Approvals, HeapAllowancesData},
+    balances::Balances,
+    block::EncodedBlock,
+    timestamp::TimeStamp,
 };
 use ic_ledger_core::{block::BlockIndex, tokens::Tokens};
 use ic_ledger_hash_of::HashOf;
@@ -20,6 +23,7 @@ use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::ops::Bound::{Excluded, Unbounded};
 use std::sync::RwLock;
 use std::time::Duration;
 
@@ -55,6 +59,10 @@ fn default_transfer_fee() -> Tokens {
     DEFAULT_TRANSFER_FEE
 }
 
+fn default_max_transfer_batch_size() -> u64 {
+    Ledger::DEFAULT_MAX_TRANSFER_BATCH_SIZE
+}
+
 // This is only for deserialization from previous version of the ledger.
 fn unknown_token() -> String {
     "???".to_string()
@@ -64,7 +72,7 @@ fn unknown_token() -> String {
 pub struct Ledger {
     pub balances: LedgerBalances,
     #[serde(default)]
-    pub approvals: AllowanceTable<ApprovalKey, AccountIdentifier, Tokens>,
+    pub approvals: AllowanceTable<HeapAllowancesData<ApprovalKey, AccountIdentifier, Tokens>>,
     pub blockchain: Blockchain<dfn_runtime::DfnRuntime, IcpLedgerArchiveWasm>,
     // A cap on the maximum number of accounts.
     pub maximum_number_of_accounts: usize,
@@ -105,11 +113,28 @@ pub struct Ledger {
 
     #[serde(default)]
     pub feature_flags: FeatureFlags,
+
+    /// The maximum number of transfers in a single icrc4_transfer_batch
+    /// request.
+    #[serde(default = "default_max_transfer_batch_size")]
+    max_transfer_batch_size: u64,
+
+    /// The ICRC-1 accounts of the spenders of the existing approvals.
+    /// Approvals only store account identifiers, which are not enough to list
+    /// allowances with icrc103_get_allowances. Spenders are recorded by
+    /// icrc2_approve and, for approvals made before the ledger recorded them,
+    /// by icrc2_transfer_from.
+    #[serde(default)]
+    pub spender_accounts: BTreeMap<ApprovalKey, Account>,
+    /// The last entry of `spender_accounts` checked by
+    /// `prune_spender_accounts`.
+    #[serde(skip)]
+    spender_accounts_prune_cursor: Option<ApprovalKey>,
 }
 
 impl LedgerContext for Ledger {
     type AccountId = AccountIdentifier;
-    type Approvals = AllowanceTable<ApprovalKey, Self::AccountId, Tokens>;
+    type Approvals = AllowanceTable<HeapAllowancesData<ApprovalKey, Self::AccountId, Tokens>>;
     type BalancesStore = BTreeMap<AccountIdentifier, Tokens>;
     type Tokens = Tokens;
 
@@ -223,6 +248,9 @@ impl Default for Ledger {
             token_symbol: unknown_token(),
             token_name: unknown_token(),
             feature_flags: FeatureFlags::default(),
+            max_transfer_batch_size: Self::DEFAULT_MAX_TRANSFER_BATCH_SIZE,
+            spender_accounts: BTreeMap::new(),
+            spender_accounts_prune_cursor: None,
         }
     }
 }
@@ -236,6 +264,11 @@ impl Ledger {
     const MAX_TRANSACTIONS_TO_PURGE: usize = 100_000;
     /// See Ledger::max_transactions_in_window
     const DEFAULT_MAX_TRANSACTIONS_IN_WINDOW: usize = 3_000_000;
+    /// See Ledger::max_transfer_batch_size
+    const DEFAULT_MAX_TRANSFER_BATCH_SIZE: u64 = 500;
+    /// The maximum number of spender accounts checked for removal each time
+    /// the spender account of an approval is updated.
+    const MAX_SPENDER_ACCOUNTS_TO_PRUNE: usize = 10;
 
     /// This creates a block and adds it to the ledger.
     pub fn add_payment(
@@ -327,6 +360,7 @@ impl Ledger {
         feature_flags: Option<FeatureFlags>,
         maximum_number_of_accounts: Option<usize>,
         accounts_overflow_trim_quantity: Option<usize>,
+        max_transfer_batch_size: Option<u64>,
     ) {
         self.token_symbol = token_symbol.unwrap_or_else(|| "ICP".to_string());
         self.token_name = token_name.unwrap_or_else(|| "Internet Computer".to_string());
@@ -360,6 +394,9 @@ impl Ledger {
         if let Some(accounts_overflow_trim_quantity) = accounts_overflow_trim_quantity {
             self.accounts_overflow_trim_quantity = accounts_overflow_trim_quantity;
         }
+        if let Some(max_transfer_batch_size) = max_transfer_batch_size {
+            self.max_transfer_batch_size = max_transfer_batch_size;
+        }
     }
 
     pub fn change_notification_state(
@@ -430,6 +467,10 @@ impl Ledger {
         }
     }
 
+    pub fn max_transfer_batch_size(&self) -> u64 {
+        self.max_transfer_batch_size
+    }
+
     pub fn upgrade(&mut self, args: UpgradeArgs) {
         if let Some(maximum_number_of_accounts) = args.maximum_number_of_accounts {
             self.maximum_number_of_accounts = maximum_number_of_accounts;
@@ -445,6 +486,54 @@ impl Ledger {
         if let Some(feature_flags) = args.feature_flags {
             self.feature_flags = feature_flags;
         }
+        if let Some(max_transfer_batch_size) = args.max_transfer_batch_size {
+            self.max_transfer_batch_size = max_transfer_batch_size;
+        }
+    }
+
+    /// Records the account of the spender of the approval from `from` after
+    /// an approve or transfer_from operation, or forgets it if the operation
+    /// removed the approval. Also forgets the spender accounts of a few other
+    /// approvals that no longer exist, e.g. because they expired or were
+    /// trimmed.
+    pub fn update_spender_account(
+        &mut self,
+        from: &AccountIdentifier,
+        spender: Account,
+        now: TimeStamp,
+    ) {
+        let spender_id = AccountIdentifier::from(spender);
+        let key = ApprovalKey::from((from, &spender_id));
+        if self.approvals.allowance(from, &spender_id, now).amount == Tokens::ZERO {
+            self.spender_accounts.remove(&key);
+        } else {
+            self.spender_accounts.insert(key, spender);
+        }
+        self.prune_spender_accounts(now, Self::MAX_SPENDER_ACCOUNTS_TO_PRUNE);
+    }
+
+    /// Checks up to `limit` entries of `spender_accounts`, continuing after the
+    /// entry checked last, and removes those whose approval no longer exists.
+    fn prune_spender_accounts(&mut self, now: TimeStamp, limit: usize) {
+        let start = match self.spender_accounts_prune_cursor.take() {
+            Some(cursor) => Excluded(cursor),
+            None => Unbounded,
+        };
+        let keys: Vec<ApprovalKey> = self
+            .spender_accounts
+            .range((start, Unbounded))
+            .take(limit)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in keys.iter() {
+            let (from, spender): (AccountIdentifier, AccountIdentifier) = key.clone().into();
+            if self.approvals.allowance(&from, &spender, now).amount == Tokens::ZERO {
+                self.spender_accounts.remove(key);
+            }
+        }
+        if keys.len() == limit {
+            self.spender_accounts_prune_cursor = keys.last().cloned();
+        }
     }
 }
 
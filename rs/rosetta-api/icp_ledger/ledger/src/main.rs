@@ -14,8 +14,8 @@ use ic_icrc1::endpoints::{convert_transfer_error, StandardRecord};
 use ic_ledger_canister_core::{
     archive::{Archive, ArchiveOptions},
     ledger::{
-        apply_transaction, archive_blocks, block_locations, find_block_in_archive, LedgerAccess,
-        TransferError as CoreTransferError,
+        apply_transaction, apply_transactions, archive_blocks, block_locations,
+        find_block_in_archive, LedgerAccess, TransferError as CoreTransferError,
     },
     range_utils,
 };
@@ -26,17 +26,31 @@ use ic_ledger_core::{
     tokens::{Tokens, DECIMAL_PLACES},
 };
 use icp_ledger::{
-    protobuf, tokens_into_proto, AccountBalanceArgs, AccountIdBlob, AccountIdentifier, ArchiveInfo,
-    ArchivedBlocksRange, ArchivedEncodedBlocksRange, Archives, BinaryAccountBalanceArgs, Block,
-    BlockArg, BlockRes, CandidBlock, Decimals, FeatureFlags, GetBlocksArgs, InitArgs,
+    protobuf, tokens_into_proto, AccountBalanceArgs, AccountIdAllowance, AccountIdBlob,
+    AccountIdentifier, Allowances, ApprovalKey, ArchiveInfo, ArchivedBlocksRange,
+    ArchivedEncodedBlocksRange, Archives, BinaryAccountBalanceArgs, Block, BlockArg, BlockRes,
+    CandidBlock, Decimals, FeatureFlags, GetAllowancesArgs, GetBlocksArgs, InitArgs,
     IterBlocksArgs, LedgerCanisterPayload, Memo, Name, Operation, PaymentError,
     QueryBlocksResponse, QueryEncodedBlocksResponse, SendArgs, Subaccount, Symbol, TipOfChainRes,
     TotalSupplyArgs, Transaction, TransferArgs, TransferError, TransferFee, TransferFeeArgs,
     MAX_BLOCKS_PER_REQUEST, MEMO_SIZE_BYTES,
 };
 use icrc_ledger_types::icrc1::transfer::TransferArg;
+use icrc_ledger_types::icrc103::get_allowances::{
+    Allowance as Icrc103Allowance, Allowances as Icrc103Allowances,
+    GetAllowancesArgs as Icrc103GetAllowancesArgs, GetAllowancesError,
+};
 use icrc_ledger_types::icrc2::allowance::{Allowance, AllowanceArgs};
 use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
+use icrc_ledger_types::icrc21::{
+    consent_message::{build_consent_info, LedgerInfo},
+    errors::{ErrorInfo, Icrc21Error},
+    requests::ConsentMessageRequest,
+    responses::ConsentInfo,
+};
+use icrc_ledger_types::icrc4::transfer_batch::{
+    TransferBatchArgs, TransferBatchError, TransferBatchResults,
+};
 use icrc_ledger_types::{
     icrc::generic_metadata_value::MetadataValue as Value, icrc3::archive::QueryArchiveFn,
 };
@@ -55,6 +69,10 @@ use std::{
     time::Duration,
 };
 
+/// The maximum number of allowances returned by a single get_allowances
+/// request.
+const MAX_TAKE_ALLOWANCES: u64 = 500;
+
 #[derive(Clone)]
 struct DebugOutSink;
 
@@ -98,6 +116,7 @@ fn init(
     feature_flags: Option<FeatureFlags>,
     maximum_number_of_accounts: Option<usize>,
     accounts_overflow_trim_quantity: Option<usize>,
+    max_transfer_batch_size: Option<u64>,
 ) {
     print(format!(
         "[ledger] init(): minting account is {}",
@@ -116,6 +135,7 @@ fn init(
         feature_flags,
         maximum_number_of_accounts,
         accounts_overflow_trim_quantity,
+        max_transfer_batch_size,
     );
     match max_message_size_bytes {
         None => {
@@ -258,35 +278,77 @@ async fn icrc1_send(
     spender_account: Option<Account>,
     created_at_time: Option<u64>,
 ) -> Result<BlockIndex, CoreTransferError<Tokens>> {
-    let from = AccountIdentifier::from(from_account);
-    let to = AccountIdentifier::from(to_account);
     match memo.as_ref() {
         Some(memo) if memo.0.len() > MEMO_SIZE_BYTES => trap_with("the memo field is too large"),
         _ => {}
     };
+    let now = TimeStamp::from_nanos_since_unix_epoch(time_nanos());
+
+    let block_index = {
+        let mut ledger = LEDGER.write().unwrap();
+        let (tx, effective_fee) = prepare_icrc1_transaction(
+            &ledger,
+            memo,
+            amount,
+            fee,
+            from_account,
+            to_account,
+            spender_account,
+            created_at_time,
+        )?;
+        let (block_index, hash) = apply_transaction(&mut *ledger, tx, now, effective_fee)?;
+        if let Some(spender_account) = spender_account {
+            ledger.update_spender_account(
+                &AccountIdentifier::from(from_account),
+                spender_account,
+                now,
+            );
+        }
+
+        set_certified_data(&hash.into_bytes());
+
+        block_index
+    };
+
+    let max_msg_size = *MAX_MESSAGE_SIZE_BYTES.read().unwrap();
+    archive_blocks::<Access>(DebugOutSink, max_msg_size as u64).await;
+    Ok(block_index)
+}
+
+/// Validates the arguments of an ICRC-1 transfer and returns the transaction
+/// to apply along with its effective fee.
+#[allow(clippy::too_many_arguments)]
+fn prepare_icrc1_transaction(
+    ledger: &Ledger,
+    memo: Option<icrc_ledger_types::icrc1::transfer::Memo>,
+    amount: Nat,
+    fee: Option<Nat>,
+    from_account: Account,
+    to_account: Account,
+    spender_account: Option<Account>,
+    created_at_time: Option<u64>,
+) -> Result<(Transaction, Tokens), CoreTransferError<Tokens>> {
+    let from = AccountIdentifier::from(from_account);
+    let to = AccountIdentifier::from(to_account);
     let amount = match amount.0.to_u64() {
         Some(n) => Tokens::from_e8s(n),
         None => {
             // No one can have so many tokens
-            let balance = account_balance(from);
+            let balance = ledger.balances.account_balance(&from);
             assert!(balance.get_e8s() < amount);
             return Err(CoreTransferError::InsufficientFunds { balance });
         }
     };
     let created_at_time = created_at_time.map(TimeStamp::from_nanos_since_unix_epoch);
-    let minting_acc = LEDGER
-        .read()
-        .unwrap()
+    let minting_acc = ledger
         .minting_account_id
         .expect("Minting canister id not initialized");
-    let now = TimeStamp::from_nanos_since_unix_epoch(time_nanos());
     let (operation, effective_fee) = if to == minting_acc {
         if fee.is_some() && fee.as_ref() != Some(&Nat::from(0u64)) {
             return Err(CoreTransferError::BadFee {
                 expected_fee: Tokens::ZERO,
             });
         }
-        let ledger = LEDGER.read().unwrap();
         let balance = ledger.balances.account_balance(&from);
         let min_burn_amount = ledger.transfer_fee.min(balance);
         if amount < min_burn_amount {
@@ -316,7 +378,7 @@ async fn icrc1_send(
         }
         (Operation::Mint { to, amount }, Tokens::ZERO)
     } else {
-        let expected_fee = LEDGER.read().unwrap().transfer_fee;
+        let expected_fee = ledger.transfer_fee;
         if fee.is_some() && fee.as_ref() != Some(&Nat::from(expected_fee.get_e8s())) {
             return Err(CoreTransferError::BadFee { expected_fee });
         }
@@ -331,25 +393,15 @@ async fn icrc1_send(
             expected_fee,
         )
     };
-
-    let block_index = {
-        let mut ledger = LEDGER.write().unwrap();
-        let tx = Transaction {
+    Ok((
+        Transaction {
             operation,
             memo: Memo(0),
             icrc1_memo: memo.map(|x| x.0),
             created_at_time,
-        };
-        let (block_index, hash) = apply_transaction(&mut *ledger, tx, now, effective_fee)?;
-
-        set_certified_data(&hash.into_bytes());
-
-        block_index
-    };
-
-    let max_msg_size = *MAX_MESSAGE_SIZE_BYTES.read().unwrap();
-    archive_blocks::<Access>(DebugOutSink, max_msg_size as u64).await;
-    Ok(block_index)
+        },
+        effective_fee,
+    ))
 }
 
 thread_local! {
@@ -591,10 +643,31 @@ fn icrc1_supported_standards() -> Vec<StandardRecord> {
             name: "ICRC-2".to_string(),
             url: "https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-2".to_string(),
         });
+        standards.push(StandardRecord {
+            name: "ICRC-103".to_string(),
+            url: "https://github.com/dfinity/ICRC/blob/main/ICRCs/ICRC-103/ICRC-103.md".to_string(),
+        });
     }
+    standards.push(StandardRecord {
+        name: "ICRC-4".to_string(),
+        url: "https://github.com/dfinity/ICRC/blob/main/ICRCs/ICRC-4/ICRC-4.md".to_string(),
+    });
+    standards.push(StandardRecord {
+        name: "ICRC-10".to_string(),
+        url: "https://github.com/dfinity/ICRC/blob/main/ICRCs/ICRC-10/ICRC-10.md".to_string(),
+    });
+    standards.push(StandardRecord {
+        name: "ICRC-21".to_string(),
+        url: "https://github.com/dfinity/wg-identity-authentication/blob/main/topics/ICRC-21/icrc_21_consent_msg.md".to_string(),
+    });
     standards
 }
 
+#[candid_method(query, rename = "icrc10_supported_standards")]
+fn icrc10_supported_standards() -> Vec<StandardRecord> {
+    icrc1_supported_standards()
+}
+
 #[candid_method(query, rename = "icrc1_minting_account")]
 fn icrc1_minting_account() -> Option<Account> {
     LEDGER.read().unwrap().icrc1_minting_account
@@ -687,6 +760,7 @@ fn canister_init(arg: LedgerCanisterPayload) {
             arg.feature_flags,
             arg.maximum_number_of_accounts,
             arg.accounts_overflow_trim_quantity,
+            arg.max_transfer_batch_size,
         ),
         LedgerCanisterPayload::Upgrade(_) => {
             trap_with("Cannot initialize the canister with an Upgrade argument. Please provide an Init argument.");
@@ -720,6 +794,7 @@ fn main() {
                         arg.feature_flags,
                         arg.maximum_number_of_accounts,
                         arg.accounts_overflow_trim_quantity,
+                        arg.max_transfer_batch_size,
                     ),
                     Err(old_err) =>
                     trap_with(&format!("Unable to decode init argument.\nDecode as new init returned the error {}\nDecode as old init returned the error {}", new_err, old_err))
@@ -958,6 +1033,100 @@ fn icrc2_transfer_from_candid() {
     })
 }
 
+#[candid_method(update, rename = "icrc4_transfer_batch")]
+async fn icrc4_transfer_batch(
+    args: TransferBatchArgs,
+) -> Result<TransferBatchResults, TransferBatchError> {
+    let limit = LEDGER.read().unwrap().max_transfer_batch_size();
+    if args.len() as u64 > limit {
+        return Err(TransferBatchError::TooManyRequests {
+            limit: Nat::from(limit),
+        });
+    }
+    let caller = caller();
+    // Entries with an oversized memo are rejected individually, trapping would
+    // fail the entries of the batch that are valid.
+    let mut results: Vec<Option<Result<Nat, icrc_ledger_types::icrc1::transfer::TransferError>>> =
+        Vec::with_capacity(args.len());
+    let mut valid_args = Vec::with_capacity(args.len());
+    for arg in args {
+        match arg.memo.as_ref() {
+            Some(memo) if memo.0.len() > MEMO_SIZE_BYTES => {
+                results.push(Some(Err(
+                    icrc_ledger_types::icrc1::transfer::TransferError::GenericError {
+                        error_code: Nat::from(0u64),
+                        message: format!(
+                            "the memo field size of {} bytes is above the allowed limit of {} bytes",
+                            memo.0.len(),
+                            MEMO_SIZE_BYTES
+                        ),
+                    },
+                )));
+            }
+            _ => {
+                results.push(None);
+                valid_args.push(arg);
+            }
+        }
+    }
+    let now = TimeStamp::from_nanos_since_unix_epoch(time_nanos());
+    let applied = {
+        let mut ledger = LEDGER.write().unwrap();
+        let applied = apply_transactions(&mut *ledger, valid_args, now, |ledger, arg| {
+            let from_account = Account {
+                owner: caller.into(),
+                subaccount: arg.from_subaccount,
+            };
+            prepare_icrc1_transaction(
+                ledger,
+                arg.memo,
+                arg.amount,
+                arg.fee,
+                from_account,
+                arg.to,
+                None,
+                arg.created_at_time,
+            )
+        });
+        if let Some(hash) = ledger.blockchain.last_hash {
+            set_certified_data(&hash.into_bytes());
+        }
+        applied
+    };
+
+    let max_msg_size = *MAX_MESSAGE_SIZE_BYTES.read().unwrap();
+    archive_blocks::<Access>(DebugOutSink, max_msg_size as u64).await;
+    let mut applied = applied.into_iter().map(|result| {
+        result.map(Nat::from).map_err(|err| {
+            let err: icrc_ledger_types::icrc1::transfer::TransferError =
+                convert_transfer_error(err)
+                    .try_into()
+                    .unwrap_or_else(|message| {
+                        icrc_ledger_types::icrc1::transfer::TransferError::GenericError {
+                            error_code: Nat::from(0u64),
+                            message,
+                        }
+                    });
+            err
+        })
+    });
+    Ok(results
+        .into_iter()
+        .map(|result| result.unwrap_or_else(|| applied.next().unwrap()))
+        .collect())
+}
+
+#[export_name = "canister_update icrc4_transfer_batch"]
+fn icrc4_transfer_batch_candid() {
+    over_async_may_reject(candid_one, |args: TransferBatchArgs| async {
+        if !LEDGER.read().unwrap().can_send(&caller()) {
+            return Err("Anonymous principal cannot hold tokens on the ledger.".to_string());
+        }
+
+        Ok(icrc4_transfer_batch(args).await)
+    })
+}
+
 /// See caveats of use on send_dfx
 #[cfg(feature = "notify-method")]
 #[export_name = "canister_update notify_dfx"]
@@ -1151,6 +1320,11 @@ fn icrc1_supported_standards_candid() {
     over(candid_one, |()| icrc1_supported_standards())
 }
 
+#[export_name = "canister_query icrc10_supported_standards"]
+fn icrc10_supported_standards_candid() {
+    over(candid_one, |()| icrc10_supported_standards())
+}
+
 #[candid_method(query, rename = "query_blocks")]
 fn query_blocks(GetBlocksArgs { start, length }: GetBlocksArgs) -> QueryBlocksResponse {
     let ledger = LEDGER.read().unwrap();
@@ -1454,6 +1628,7 @@ async fn icrc2_approve(arg: ApproveArgs) -> Result<Nat, ApproveError> {
                 };
                 err
             })?;
+        ledger.update_spender_account(&from, arg.spender, now);
 
         set_certified_data(&hash.into_bytes());
 
@@ -1499,6 +1674,119 @@ fn icrc2_allowance_candid() {
     over(candid_one, icrc2_allowance)
 }
 
+#[candid_method(query, rename = "get_allowances")]
+fn get_allowances(arg: GetAllowancesArgs) -> Allowances {
+    if !LEDGER.read().unwrap().feature_flags.icrc2 {
+        trap_with("ICRC-2 features are not enabled on the ledger.");
+    }
+    let now = TimeStamp::from_nanos_since_unix_epoch(time_nanos());
+    let ledger = LEDGER.read().unwrap();
+    let max_results = arg
+        .take
+        .unwrap_or(MAX_TAKE_ALLOWANCES)
+        .min(MAX_TAKE_ALLOWANCES);
+    ledger
+        .approvals
+        .get_allowances(
+            &arg.from_account_id,
+            arg.prev_spender_id.as_ref(),
+            now,
+            max_results as usize,
+        )
+        .into_iter()
+        .map(|(spender, allowance)| AccountIdAllowance {
+            from_account_id: arg.from_account_id,
+            to_spender_id: spender,
+            allowance: allowance.amount,
+            expires_at: allowance.expires_at.map(|t| t.as_nanos_since_unix_epoch()),
+        })
+        .collect()
+}
+
+#[export_name = "canister_query get_allowances"]
+fn get_allowances_candid() {
+    over(candid_one, get_allowances)
+}
+
+#[candid_method(query, rename = "icrc103_get_allowances")]
+fn icrc103_get_allowances(
+    arg: Icrc103GetAllowancesArgs,
+) -> Result<Icrc103Allowances, GetAllowancesError> {
+    if !LEDGER.read().unwrap().feature_flags.icrc2 {
+        trap_with("ICRC-2 features are not enabled on the ledger.");
+    }
+    let from_account = arg.from_account.unwrap_or_else(|| Account {
+        owner: caller().into(),
+        subaccount: None,
+    });
+    let max_results = match arg.take {
+        Some(take) => take
+            .0
+            .to_u64()
+            .ok_or(GetAllowancesError::InvalidTakeValue)?
+            .min(MAX_TAKE_ALLOWANCES),
+        None => MAX_TAKE_ALLOWANCES,
+    };
+    let now = TimeStamp::from_nanos_since_unix_epoch(time_nanos());
+    let ledger = LEDGER.read().unwrap();
+    let from = AccountIdentifier::from(from_account);
+    // The approvals are ordered by the account identifier of the spender, so
+    // all allowances of the account are sorted by spender account first.
+    let mut allowances: Vec<Icrc103Allowance> = ledger
+        .approvals
+        .get_allowances(&from, None, now, usize::MAX)
+        .into_iter()
+        .filter_map(|(spender, allowance)| {
+            let to_spender = *ledger
+                .spender_accounts
+                .get(&ApprovalKey::from((&from, &spender)))?;
+            Some(Icrc103Allowance {
+                from_account,
+                to_spender,
+                allowance: Nat::from(allowance.amount.get_e8s()),
+                expires_at: allowance.expires_at.map(|t| t.as_nanos_since_unix_epoch()),
+            })
+        })
+        .collect();
+    allowances.sort_by(|a, b| a.to_spender.cmp(&b.to_spender));
+    Ok(allowances
+        .into_iter()
+        .filter(|allowance| {
+            arg.prev_spender
+                .map_or(true, |prev_spender| allowance.to_spender > prev_spender)
+        })
+        .take(max_results as usize)
+        .collect())
+}
+
+#[export_name = "canister_query icrc103_get_allowances"]
+fn icrc103_get_allowances_candid() {
+    over(candid_one, icrc103_get_allowances)
+}
+
+#[candid_method(update, rename = "icrc21_canister_call_consent_message")]
+fn icrc21_canister_call_consent_message(
+    consent_msg_request: ConsentMessageRequest,
+) -> Result<ConsentInfo, Icrc21Error> {
+    let ledger = LEDGER.read().unwrap();
+    if consent_msg_request.method.starts_with("icrc2_") && !ledger.feature_flags.icrc2 {
+        return Err(Icrc21Error::UnsupportedCanisterCall(ErrorInfo {
+            description: "ICRC-2 features are not enabled on the ledger.".to_string(),
+        }));
+    }
+    let ledger_info = LedgerInfo {
+        token_symbol: ledger.token_symbol.clone(),
+        decimals: DECIMAL_PLACES as u8,
+        transfer_fee: Nat::from(ledger.transfer_fee.get_e8s()),
+    };
+    build_consent_info(consent_msg_request, caller().0, &ledger_info)
+}
+
+#[export_name = "canister_update icrc21_canister_call_consent_message"]
+fn icrc21_canister_call_consent_message_candid() {
+    over(candid_one, icrc21_canister_call_consent_message)
+}
+
 candid::export_service!();
 
 #[export_name = "canister_query __get_candid_interface_tmp_hack"]
@@ -12,10 +12,11 @@ use ic_ledger_core::{
     tokens::{CheckedAdd, CheckedSub, Tokens},
 };
 use icp_ledger::{
-    apply_operation, ArchiveOptions, Block, LedgerBalances, Memo, Operation, PaymentError,
-    Transaction, TransferError, DEFAULT_TRANSFER_FEE,
+    apply_operation, ApprovalKey, ArchiveOptions, Block, LedgerBalances, Memo, Operation,
+    PaymentError, Transaction, TransferError, DEFAULT_TRANSFER_FEE,
 };
-use std::collections::HashSet;
+use icrc_ledger_types::icrc1::account::Account;
+use std::collections::{BTreeSet, HashSet};
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, SystemTime};
 
@@ -1371,3 +1372,60 @@ fn test_approval_burn_from() {
     assert_eq!(ctx.balances().account_balance(&spender), Tokens::ZERO);
     assert_eq!(ctx.balances().total_supply().get_e8s(), 90_000);
 }
+
+#[test]
+fn test_spender_accounts_are_pruned_with_their_approvals() {
+    let mut ctx = Ledger::default();
+
+    let from = test_account_id(1);
+    let now = ts(1000);
+    ctx.balances_mut().mint(&from, tokens(1_000_000)).unwrap();
+
+    let spender_account = |n: u64| Account::from(PrincipalId::new_user_test_id(n).0);
+    let approve = |spender: Account, amount: Tokens, expires_at: Option<u64>| Operation::Approve {
+        from,
+        spender: AccountIdentifier::from(spender),
+        allowance: amount,
+        expected_allowance: None,
+        expires_at: expires_at.map(ts),
+        fee: tokens(10_000),
+    };
+    let key = |spender: Account| ApprovalKey::from((&from, &AccountIdentifier::from(spender)));
+
+    let revoked = spender_account(2);
+    let expiring = spender_account(3);
+    let kept = spender_account(4);
+    for (spender, expires_at) in [(revoked, None), (expiring, Some(2000)), (kept, None)] {
+        apply_operation(
+            &mut ctx,
+            &approve(spender, tokens(100_000), expires_at),
+            now,
+        )
+        .unwrap();
+        ctx.update_spender_account(&from, spender, now);
+    }
+    assert_eq!(
+        ctx.spender_accounts
+            .keys()
+            .cloned()
+            .collect::<BTreeSet<_>>(),
+        BTreeSet::from([key(revoked), key(expiring), key(kept)])
+    );
+
+    // Revoking an approval forgets its spender right away.
+    apply_operation(&mut ctx, &approve(revoked, Tokens::ZERO, None), now).unwrap();
+    ctx.update_spender_account(&from, revoked, now);
+    assert!(!ctx.spender_accounts.contains_key(&key(revoked)));
+
+    // The spenders of expired approvals are forgotten by later updates.
+    let later = ts(3000);
+    ctx.update_spender_account(&from, kept, later);
+    assert_eq!(
+        ctx.spender_accounts
+            .keys()
+            .cloned()
+            .collect::<BTreeSet<_>>(),
+        BTreeSet::from([key(kept)])
+    );
+    assert_eq!(ctx.spender_accounts.get(&key(kept)), Some(&kept));
+}
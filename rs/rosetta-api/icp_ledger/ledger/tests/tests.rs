@@ -5,22 +5,29 @@ use dfn_protobuf::ProtoBuf;
 use ic_base_types::CanisterId;
 use ic_icrc1_ledger_sm_tests::{
     balance_of, default_approve_args, default_transfer_from_args, expect_icrc2_disabled,
-    get_allowance, send_approval, send_transfer_from, supported_standards, transfer, MINTER,
+    get_allowance, send_approval, send_transfer_from, setup, supported_standards, transfer,
+    transfer_batch, MINTER,
 };
 use ic_ledger_core::{block::BlockType, Tokens};
 use ic_state_machine_tests::{ErrorCode, PrincipalId, StateMachine, UserError};
 use icp_ledger::{
-    AccountIdBlob, AccountIdentifier, ArchiveOptions, ArchivedBlocksRange, Block, CandidBlock,
-    CandidOperation, CandidTransaction, FeatureFlags, GetBlocksArgs, GetBlocksRes, GetBlocksResult,
-    InitArgs, LedgerCanisterInitPayload, LedgerCanisterPayload, Operation, QueryBlocksResponse,
-    QueryEncodedBlocksResponse, TimeStamp, UpgradeArgs, DEFAULT_TRANSFER_FEE,
+    AccountIdBlob, AccountIdentifier, Allowances, ArchiveOptions, ArchivedBlocksRange, Block,
+    CandidBlock, CandidOperation, CandidTransaction, FeatureFlags, GetAllowancesArgs,
+    GetBlocksArgs, GetBlocksRes, GetBlocksResult, InitArgs, LedgerCanisterInitPayload,
+    LedgerCanisterPayload, Operation, QueryBlocksResponse, QueryEncodedBlocksResponse, TimeStamp,
+    UpgradeArgs, DEFAULT_TRANSFER_FEE,
 };
 use icrc_ledger_types::icrc1::{
     account::Account,
     transfer::{Memo, TransferArg, TransferError},
 };
+use icrc_ledger_types::icrc103::get_allowances::{
+    Allowance as Icrc103Allowance, Allowances as Icrc103Allowances,
+    GetAllowancesArgs as Icrc103GetAllowancesArgs, GetAllowancesError,
+};
 use icrc_ledger_types::icrc2::allowance::AllowanceArgs;
 use icrc_ledger_types::icrc2::approve::ApproveArgs;
+use icrc_ledger_types::icrc4::transfer_batch::TransferBatchError;
 use num_traits::cast::ToPrimitive;
 use on_wire::{FromWire, IntoWire};
 use serde_bytes::ByteBuf;
@@ -181,6 +188,83 @@ fn test_tx_time_bounds() {
     ic_icrc1_ledger_sm_tests::test_tx_time_bounds(ledger_wasm(), encode_init_args);
 }
 
+#[test]
+fn test_icrc103_get_allowances() {
+    ic_icrc1_ledger_sm_tests::test_icrc103_get_allowances(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_icrc4_transfer_batch() {
+    ic_icrc1_ledger_sm_tests::test_icrc4_transfer_batch(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_icrc4_transfer_batch_size_limit() {
+    let from = PrincipalId::new_user_test_id(1);
+    let to = PrincipalId::new_user_test_id(2);
+
+    let env = StateMachine::new();
+    let mut initial_balances = HashMap::new();
+    initial_balances.insert(Account::from(from.0).into(), Tokens::from_e8s(1_000_000));
+    let payload = LedgerCanisterInitPayload::builder()
+        .minting_account(MINTER.into())
+        .icrc1_minting_account(MINTER)
+        .initial_values(initial_balances)
+        .transfer_fee(Tokens::from_e8s(10_000))
+        .token_symbol_and_name("ICP", "Internet Computer")
+        .max_transfer_batch_size(2)
+        .build()
+        .unwrap();
+    let canister_id = env
+        .install_canister(
+            ledger_wasm(),
+            CandidOne(payload).into_bytes().unwrap(),
+            None,
+        )
+        .expect("Unable to install the Ledger canister with the new init");
+    let batch = |n: u64| -> Vec<TransferArg> {
+        (0..n)
+            .map(|i| TransferArg {
+                from_subaccount: None,
+                to: to.0.into(),
+                fee: None,
+                created_at_time: None,
+                memo: None,
+                amount: Nat::from(1_000 + i),
+            })
+            .collect()
+    };
+
+    assert_eq!(
+        transfer_batch(&env, canister_id, from.0, &batch(3)),
+        Err(TransferBatchError::TooManyRequests {
+            limit: Nat::from(2_u64)
+        })
+    );
+    assert_eq!(balance_of(&env, canister_id, to.0), 0);
+    let results =
+        transfer_batch(&env, canister_id, from.0, &batch(2)).expect("failed to transfer a batch");
+    assert!(results.iter().all(Result::is_ok));
+
+    // The limit can be changed on upgrade.
+    env.upgrade_canister(
+        canister_id,
+        ledger_wasm(),
+        Encode!(&LedgerCanisterPayload::Upgrade(Some(UpgradeArgs {
+            maximum_number_of_accounts: None,
+            icrc1_minting_account: None,
+            feature_flags: None,
+            max_transfer_batch_size: Some(3),
+        })))
+        .unwrap(),
+    )
+    .expect("Unable to upgrade the Ledger canister");
+    let results =
+        transfer_batch(&env, canister_id, from.0, &batch(3)).expect("failed to transfer a batch");
+    assert_eq!(results.len(), 3);
+    assert!(results.iter().all(Result::is_ok));
+}
+
 // Check that different blocks produce different hashes.
 #[test]
 fn transaction_hashes_are_unique() {
@@ -220,6 +304,7 @@ fn check_old_init() {
         feature_flags: None,
         maximum_number_of_accounts: None,
         accounts_overflow_trim_quantity: None,
+        max_transfer_batch_size: None,
     })
     .unwrap();
     env.install_canister(ledger_wasm(), old_init, None)
@@ -589,6 +674,7 @@ fn test_feature_flags() {
             maximum_number_of_accounts: None,
             icrc1_minting_account: None,
             feature_flags: Some(FeatureFlags { icrc2: false }),
+            max_transfer_batch_size: None,
         })))
         .unwrap(),
     )
@@ -610,6 +696,7 @@ fn test_feature_flags() {
             maximum_number_of_accounts: None,
             icrc1_minting_account: None,
             feature_flags: Some(FeatureFlags { icrc2: true }),
+            max_transfer_batch_size: None,
         })))
         .unwrap(),
     )
@@ -620,7 +707,10 @@ fn test_feature_flags() {
         standards.push(standard.name);
     }
     standards.sort();
-    assert_eq!(standards, vec!["ICRC-1", "ICRC-2"]);
+    assert_eq!(
+        standards,
+        vec!["ICRC-1", "ICRC-10", "ICRC-103", "ICRC-2", "ICRC-21", "ICRC-4"]
+    );
 
     let block_index =
         send_approval(&env, canister_id, from.0, &approve_args).expect("approval failed");
@@ -667,6 +757,161 @@ fn test_approval_trimming() {
     ic_icrc1_ledger_sm_tests::test_approval_trimming(ledger_wasm(), encode_init_args);
 }
 
+#[test]
+fn test_icrc21_standard() {
+    ic_icrc1_ledger_sm_tests::test_icrc21_standard(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_get_allowances() {
+    let owner = PrincipalId::new_user_test_id(1);
+    let (env, ledger) = setup(
+        ledger_wasm(),
+        encode_init_args,
+        vec![(Account::from(owner.0), 1_000_000)],
+    );
+    let mut spender_ids: Vec<AccountIdentifier> = (2..6)
+        .map(|n| {
+            let spender = Account::from(PrincipalId::new_user_test_id(n).0);
+            send_approval(
+                &env,
+                ledger,
+                owner.0,
+                &default_approve_args(spender, 10_000 * n),
+            )
+            .expect("approval failed");
+            AccountIdentifier::from(spender)
+        })
+        .collect();
+    spender_ids.sort();
+
+    let get_allowances = |prev_spender_id: Option<AccountIdentifier>, take: Option<u64>| {
+        let args = GetAllowancesArgs {
+            from_account_id: AccountIdentifier::from(Account::from(owner.0)),
+            prev_spender_id,
+            take,
+        };
+        Decode!(
+            &env.query(ledger, "get_allowances", Encode!(&args).unwrap())
+                .expect("failed to query get_allowances")
+                .bytes(),
+            Allowances
+        )
+        .expect("failed to decode get_allowances response")
+    };
+    let list_spender_ids = |allowances: Allowances| -> Vec<AccountIdentifier> {
+        allowances
+            .into_iter()
+            .map(|allowance| {
+                assert_eq!(
+                    allowance.from_account_id,
+                    AccountIdentifier::from(Account::from(owner.0))
+                );
+                allowance.to_spender_id
+            })
+            .collect()
+    };
+
+    let all = get_allowances(None, None);
+    assert_eq!(list_spender_ids(all.clone()), spender_ids);
+    assert!(all.iter().all(|allowance| allowance.expires_at.is_none()));
+    assert_eq!(
+        list_spender_ids(get_allowances(None, Some(3))),
+        spender_ids[..3].to_vec()
+    );
+    assert_eq!(
+        list_spender_ids(get_allowances(Some(spender_ids[2]), Some(3))),
+        spender_ids[3..].to_vec()
+    );
+}
+
+#[test]
+fn test_icrc103_get_allowances_of_approval_made_before_upgrade() {
+    let ledger_wasm_mainnet =
+        std::fs::read(std::env::var("ICP_LEDGER_DEPLOYED_VERSION_WASM_PATH").unwrap()).unwrap();
+    let owner = PrincipalId::new_user_test_id(1);
+    let spender = Account {
+        owner: PrincipalId::new_user_test_id(2).0,
+        subaccount: Some([2; 32]),
+    };
+    let to = PrincipalId::new_user_test_id(3);
+
+    let env = StateMachine::new();
+    let mut initial_balances = HashMap::new();
+    initial_balances.insert(Account::from(owner.0).into(), Tokens::from_e8s(1_000_000));
+    let payload = LedgerCanisterInitPayload::builder()
+        .minting_account(MINTER.into())
+        .icrc1_minting_account(MINTER)
+        .initial_values(initial_balances)
+        .transfer_fee(Tokens::from_e8s(10_000))
+        .token_symbol_and_name("ICP", "Internet Computer")
+        .feature_flags(FeatureFlags { icrc2: true })
+        .build()
+        .unwrap();
+    let canister_id = env
+        .install_canister(
+            ledger_wasm_mainnet,
+            CandidOne(payload).into_bytes().unwrap(),
+            None,
+        )
+        .expect("Unable to install the Ledger canister with the new init");
+    send_approval(
+        &env,
+        canister_id,
+        owner.0,
+        &default_approve_args(spender, 100_000),
+    )
+    .expect("approval failed");
+
+    let upgrade = || {
+        env.upgrade_canister(
+            canister_id,
+            ledger_wasm(),
+            Encode!(&LedgerCanisterPayload::Upgrade(None)).unwrap(),
+        )
+        .unwrap()
+    };
+    let get_allowances = || {
+        Decode!(
+            &env.query(
+                canister_id,
+                "icrc103_get_allowances",
+                Encode!(&Icrc103GetAllowancesArgs {
+                    from_account: Some(Account::from(owner.0)),
+                    prev_spender: None,
+                    take: None,
+                })
+                .unwrap()
+            )
+            .expect("failed to query icrc103_get_allowances")
+            .bytes(),
+            Result<Icrc103Allowances, GetAllowancesError>
+        )
+        .expect("failed to decode icrc103_get_allowances response")
+        .expect("failed to get the allowances")
+    };
+    upgrade();
+
+    // The approval only holds the account identifier of the spender, so it
+    // cannot be listed until the spender reveals its account.
+    assert_eq!(get_allowances(), vec![]);
+    let mut transfer_from_args = default_transfer_from_args(owner.0, to.0, 10_000);
+    transfer_from_args.spender_subaccount = spender.subaccount;
+    send_transfer_from(&env, canister_id, spender.owner, &transfer_from_args)
+        .expect("transfer_from failed");
+    let expected = vec![Icrc103Allowance {
+        from_account: Account::from(owner.0),
+        to_spender: spender,
+        allowance: Nat::from(80_000_u64),
+        expires_at: None,
+    }];
+    assert_eq!(get_allowances(), expected);
+
+    // The spender account is kept across upgrades.
+    upgrade();
+    assert_eq!(get_allowances(), expected);
+}
+
 #[test]
 fn account_identifier_test() {
     let env = StateMachine::new();
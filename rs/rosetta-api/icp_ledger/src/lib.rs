@@ -474,6 +474,9 @@ pub struct UpgradeArgs {
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub feature_flags: Option<FeatureFlags>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_transfer_batch_size: Option<u64>,
 }
 
 // This is how we pass arguments to 'init' in main.rs
@@ -492,6 +495,7 @@ pub struct InitArgs {
     pub feature_flags: Option<FeatureFlags>,
     pub maximum_number_of_accounts: Option<usize>,
     pub accounts_overflow_trim_quantity: Option<usize>,
+    pub max_transfer_batch_size: Option<u64>,
 }
 
 impl LedgerCanisterInitPayload {
@@ -526,6 +530,7 @@ pub struct LedgerCanisterInitPayloadBuilder {
     feature_flags: Option<FeatureFlags>,
     maximum_number_of_accounts: Option<usize>,
     accounts_overflow_trim_quantity: Option<usize>,
+    max_transfer_batch_size: Option<u64>,
 }
 
 impl LedgerCanisterInitPayloadBuilder {
@@ -544,6 +549,7 @@ impl LedgerCanisterInitPayloadBuilder {
             feature_flags: None,
             maximum_number_of_accounts: None,
             accounts_overflow_trim_quantity: None,
+            max_transfer_batch_size: None,
         }
     }
 
@@ -615,6 +621,11 @@ impl LedgerCanisterInitPayloadBuilder {
         self
     }
 
+    pub fn max_transfer_batch_size(mut self, max_transfer_batch_size: u64) -> Self {
+        self.max_transfer_batch_size = Some(max_transfer_batch_size);
+        self
+    }
+
     pub fn build(self) -> Result<LedgerCanisterInitPayload, String> {
         let minting_account = self
             .minting_account
@@ -650,6 +661,7 @@ impl LedgerCanisterInitPayloadBuilder {
                 feature_flags: self.feature_flags,
                 maximum_number_of_accounts: self.maximum_number_of_accounts,
                 accounts_overflow_trim_quantity: self.accounts_overflow_trim_quantity,
+                max_transfer_batch_size: self.max_transfer_batch_size,
             },
         )))
     }
@@ -659,6 +671,7 @@ pub struct LedgerCanisterUpgradePayloadBuilder {
     maximum_number_of_accounts: Option<usize>,
     icrc1_minting_account: Option<Account>,
     feature_flags: Option<FeatureFlags>,
+    max_transfer_batch_size: Option<u64>,
 }
 
 impl LedgerCanisterUpgradePayloadBuilder {
@@ -667,6 +680,7 @@ impl LedgerCanisterUpgradePayloadBuilder {
             maximum_number_of_accounts: None,
             icrc1_minting_account: None,
             feature_flags: None,
+            max_transfer_batch_size: None,
         }
     }
 
@@ -680,12 +694,18 @@ impl LedgerCanisterUpgradePayloadBuilder {
         self
     }
 
+    pub fn max_transfer_batch_size(mut self, max_transfer_batch_size: u64) -> Self {
+        self.max_transfer_batch_size = Some(max_transfer_batch_size);
+        self
+    }
+
     pub fn build(self) -> Result<LedgerCanisterUpgradePayload, String> {
         Ok(LedgerCanisterUpgradePayload(
             LedgerCanisterPayload::Upgrade(Some(UpgradeArgs {
                 maximum_number_of_accounts: self.maximum_number_of_accounts,
                 icrc1_minting_account: self.icrc1_minting_account,
                 feature_flags: self.feature_flags,
+                max_transfer_batch_size: self.max_transfer_batch_size,
             })),
         ))
     }
@@ -847,6 +867,25 @@ impl AccountBalanceArgs {
     }
 }
 
+/// Argument taken by the get_allowances endpoint.
+#[derive(Serialize, Deserialize, CandidType, Clone, Hash, Debug, PartialEq, Eq)]
+pub struct GetAllowancesArgs {
+    pub from_account_id: AccountIdentifier,
+    pub prev_spender_id: Option<AccountIdentifier>,
+    pub take: Option<u64>,
+}
+
+/// An allowance returned by the get_allowances endpoint.
+#[derive(Serialize, Deserialize, CandidType, Clone, Hash, Debug, PartialEq, Eq)]
+pub struct AccountIdAllowance {
+    pub from_account_id: AccountIdentifier,
+    pub to_spender_id: AccountIdentifier,
+    pub allowance: Tokens,
+    pub expires_at: Option<u64>,
+}
+
+pub type Allowances = Vec<AccountIdAllowance>;
+
 /// An operation which modifies account balances
 #[derive(
     Serialize, Deserialize, CandidType, Clone, Hash, Debug, PartialEq, Eq, PartialOrd, Ord,
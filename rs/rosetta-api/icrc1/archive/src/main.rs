@@ -10,9 +10,11 @@ use ic_stable_structures::{
 };
 use icrc_ledger_types::icrc3::blocks::BlockRange;
 use icrc_ledger_types::icrc3::blocks::GenericBlock as IcrcBlock;
+use icrc_ledger_types::icrc3::blocks::{BlockWithId, GetBlocksRequest, GetBlocksResult};
 
 use icrc_ledger_types::icrc3::transactions::Transaction;
 use icrc_ledger_types::icrc3::transactions::{GetTransactionsRequest, TransactionRange};
+use num_traits::ToPrimitive;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::cell::RefCell;
@@ -273,6 +275,44 @@ fn get_blocks(req: GetTransactionsRequest) -> BlockRange {
     BlockRange { blocks }
 }
 
+/// Get the blocks in the requested ranges as ICRC-3 generic blocks.
+/// Ranges outside of the blocks this archive stores are ignored.
+#[query]
+#[candid_method(query)]
+fn icrc3_get_blocks(reqs: Vec<GetBlocksRequest>) -> GetBlocksResult {
+    let (offset, max_blocks) =
+        with_archive_opts(|opts| (opts.block_index_offset, opts.max_transactions_per_response));
+    let num_blocks = with_blocks(|blocks| blocks.len());
+
+    let mut blocks = vec![];
+    for req in reqs {
+        let start = match req.start.0.to_u64() {
+            Some(start) => start,
+            None => continue,
+        };
+        let length = req.length.0.to_u64().unwrap_or(u64::MAX);
+        let end = start.saturating_add(length).min(offset + num_blocks);
+        let start = start.max(offset);
+        let remaining = max_blocks.saturating_sub(blocks.len() as u64);
+        let end = end.min(start.saturating_add(remaining));
+        with_blocks(|log| {
+            for id in start..end {
+                let block = log.get(id - offset).unwrap();
+                blocks.push(BlockWithId {
+                    id: id.into(),
+                    block: decode_icrc1_block(id, block).into(),
+                });
+            }
+        });
+    }
+
+    GetBlocksResult {
+        log_length: (offset + num_blocks).into(),
+        blocks,
+        archived_blocks: vec![],
+    }
+}
+
 #[query(hidden = true)]
 fn __get_candid_interface_tmp_hack() -> &'static str {
     include_str!(env!("ARCHIVE_DID_PATH"))
@@ -22,6 +22,9 @@ use ic_stable_structures::{
     Storable,
 };
 use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+use icrc_ledger_types::icrc103::get_allowances::{
+    Allowance, Allowances, GetAllowancesArgs, GetAllowancesError,
+};
 use icrc_ledger_types::icrc3::archive::{ArchivedRange, QueryBlockArchiveFn};
 use icrc_ledger_types::icrc3::blocks::{
     BlockRange, GenericBlock, GetBlocksRequest, GetBlocksResponse,
@@ -38,7 +41,7 @@ use std::convert::TryFrom;
 use std::fmt::Debug;
 use std::hash::Hash;
 use std::io::Read;
-use std::ops::Bound::{Excluded, Included};
+use std::ops::Bound::{Excluded, Included, Unbounded};
 use std::ops::Range;
 use std::time::Duration;
 
@@ -51,9 +54,17 @@ const BLOCK_LOG_INDEX_MEMORY_ID: MemoryId = MemoryId::new(1);
 const BLOCK_LOG_DATA_MEMORY_ID: MemoryId = MemoryId::new(2);
 const ACCOUNT_BLOCK_IDS_MEMORY_ID: MemoryId = MemoryId::new(3);
 const ACCOUNT_DATA_MEMORY_ID: MemoryId = MemoryId::new(4);
+const ALLOWANCES_MEMORY_ID: MemoryId = MemoryId::new(5);
 
 const DEFAULT_MAX_WAIT_TIME: Duration = Duration::from_secs(1);
 
+/// The maximum number of allowances returned by [icrc103_get_allowances].
+const MAX_TAKE_ALLOWANCES: u64 = 500;
+
+/// The maximum number of blocks processed by a single [build_index] run
+/// while backfilling the allowances of an upgraded index.
+const MAX_ALLOWANCES_BACKFILL_BLOCKS: u64 = 10_000;
+
 #[cfg(not(feature = "u256-tokens"))]
 type Tokens = ic_icrc1_tokens_u64::U64;
 
@@ -73,6 +84,12 @@ type AccountBlockIdsMap = StableBTreeMap<AccountBlockIdsMapKey, (), VM>;
 type AccountDataMapKey = (AccountDataType, (Blob<29>, [u8; 32]));
 type AccountDataMap = StableBTreeMap<AccountDataMapKey, Tokens, VM>;
 
+// The allowances are keyed by (from, spender) where both accounts are
+// represented as principal of type Blob<29> and the effective subaccount.
+// This groups all the allowances granted by an account together.
+type AllowancesMapKey = ((Blob<29>, [u8; 32]), (Blob<29>, [u8; 32]));
+type AllowancesMap = StableBTreeMap<AllowancesMapKey, StoredAllowance, VM>;
+
 thread_local! {
     /// Static memory manager to manage the memory available for stable structures.
     static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(MemoryManager::init(DefaultMemoryImpl::default()));
@@ -100,6 +117,11 @@ thread_local! {
         RefCell::new(AccountDataMap::init(memory_manager.get(ACCOUNT_DATA_MEMORY_ID)))
     });
 
+    /// Map that contains the current allowances granted by each account.
+    static ALLOWANCES: RefCell<AllowancesMap> = with_memory_manager(|memory_manager| {
+        RefCell::new(AllowancesMap::init(memory_manager.get(ALLOWANCES_MEMORY_ID)))
+    });
+
     /// Profiling data to understand cycles usage
     static PROFILING_DATA: RefCell<SpanStats> = RefCell::new(SpanStats::default());
 }
@@ -123,6 +145,17 @@ struct State {
 
     /// This fee is used if no fee nor effetive_fee is found in Approve blocks.
     pub last_fee: Option<Tokens>,
+
+    /// The blocks indexed before the index started tracking allowances
+    /// and whose allowance changes have not been processed yet.
+    /// Indexes upgraded from a version without allowances start with
+    /// all their blocks in this range, see [post_upgrade].
+    #[serde(default = "all_blocks")]
+    allowances_backfill: Range<BlockIndex64>,
+}
+
+fn all_blocks() -> Range<BlockIndex64> {
+    0..u64::MAX
 }
 
 // NOTE: the default configuration is dysfunctional, but it's convenient to have
@@ -136,6 +169,7 @@ impl Default for State {
             last_wait_time: Duration::from_secs(0),
             fee_collectors: Default::default(),
             last_fee: None,
+            allowances_backfill: 0..0,
         }
     }
 }
@@ -187,6 +221,36 @@ impl Storable for AccountDataType {
     };
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+struct StoredAllowance {
+    amount: Tokens,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_at: Option<u64>,
+}
+
+impl Storable for StoredAllowance {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut buf = vec![];
+        ciborium::ser::into_writer(self, &mut buf).expect("failed to encode allowance");
+        Cow::Owned(buf)
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        ciborium::de::from_reader(&bytes[..]).expect("failed to decode allowance")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+#[test]
+fn test_stored_allowance_storable() {
+    let allowance = StoredAllowance {
+        amount: Tokens::try_from(Nat::from(1_000_000u64)).unwrap(),
+        expires_at: Some(1_700_000_000_000_000_000),
+    };
+    assert_eq!(allowance, StoredAllowance::from_bytes(allowance.to_bytes()));
+}
+
 #[test]
 fn test_account_data_type_storable() {
     assert_eq!(
@@ -195,6 +259,91 @@ fn test_account_data_type_storable() {
     );
 }
 
+#[test]
+fn test_backfill_allowances() {
+    use ic_ledger_core::timestamp::TimeStamp;
+
+    let account = |n: u64| Account {
+        owner: Principal::from_slice(&n.to_be_bytes()),
+        subaccount: None,
+    };
+    let tokens = |n: u64| Tokens::try_from(Nat::from(n)).unwrap();
+    let approve = |spender: Account, amount: u64| Operation::Approve {
+        from: account(1),
+        spender,
+        amount: tokens(amount),
+        expected_allowance: None,
+        expires_at: None,
+        fee: None,
+    };
+    let operations = vec![
+        Operation::Mint {
+            to: account(1),
+            amount: tokens(1_000_000),
+        },
+        approve(account(2), 100_000),
+        approve(account(3), 200_000),
+        Operation::Transfer {
+            from: account(1),
+            to: account(4),
+            spender: Some(account(2)),
+            amount: tokens(10_000),
+            fee: None,
+        },
+        approve(account(3), 0),
+        approve(account(4), 300_000),
+    ];
+    let blocks = operations
+        .into_iter()
+        .map(|operation| {
+            let transaction = ic_icrc1::Transaction {
+                operation,
+                created_at_time: None,
+                memo: None,
+            };
+            let block = Block::from_transaction(
+                None,
+                transaction,
+                TimeStamp::from_nanos_since_unix_epoch(0),
+                tokens(1),
+                None,
+            );
+            encoded_block_to_generic_block(&block.encode())
+        })
+        .collect();
+    append_blocks(blocks);
+    let list_allowances = || with_allowances(|allowances| allowances.iter().collect::<Vec<_>>());
+    let allowances = list_allowances();
+    assert_eq!(allowances.len(), 2);
+
+    // Simulate an index upgraded from a version that didn't track allowances:
+    // its state has no backfill range and its allowances are empty.
+    let mut legacy_state = match ciborium::value::Value::serialized(&State::default()).unwrap() {
+        ciborium::value::Value::Map(fields) => fields,
+        value => panic!("expected the state to be encoded as a map, got {:?}", value),
+    };
+    legacy_state.retain(|(key, _)| key.as_text() != Some("allowances_backfill"));
+    let legacy_state: State = ciborium::value::Value::Map(legacy_state)
+        .deserialized()
+        .unwrap();
+    assert_eq!(legacy_state.allowances_backfill, all_blocks());
+    with_allowances(|map| {
+        for (key, _) in allowances.iter() {
+            map.remove(key);
+        }
+    });
+    let num_blocks = with_blocks(|blocks| blocks.len());
+    mutate_state(|state| {
+        state.allowances_backfill = legacy_state.allowances_backfill.start
+            ..legacy_state.allowances_backfill.end.min(num_blocks);
+    });
+
+    while !with_state(|state| state.allowances_backfill.is_empty()) {
+        backfill_allowances();
+    }
+    assert_eq!(list_allowances(), allowances);
+}
+
 /// A helper function to access the scalar state.
 fn with_state<R>(f: impl FnOnce(&State) -> R) -> R {
     STATE.with(|cell| f(cell.borrow().get()))
@@ -232,6 +381,11 @@ fn with_account_data<R>(f: impl FnOnce(&mut AccountDataMap) -> R) -> R {
     ACCOUNT_DATA.with(|cell| f(&mut cell.borrow_mut()))
 }
 
+/// A helper function to access the allowances.
+fn with_allowances<R>(f: impl FnOnce(&mut AllowancesMap) -> R) -> R {
+    ALLOWANCES.with(|cell| f(&mut cell.borrow_mut()))
+}
+
 /// A helper function that returns a decoded block stored in the
 /// block log at the given index or None if there is no block at that index.
 /// This function can trap if the index at the given block cannot be decoded
@@ -261,11 +415,19 @@ fn change_balance(account: Account, f: impl FnOnce(Tokens) -> Tokens) {
 }
 
 fn balance_key(account: Account) -> (AccountDataType, (Blob<29>, [u8; 32])) {
+    (AccountDataType::Balance, account_key(account))
+}
+
+fn account_key(account: Account) -> (Blob<29>, [u8; 32]) {
     let owner = Blob::try_from(account.owner.as_slice()).unwrap();
-    (
-        AccountDataType::Balance,
-        (owner, *account.effective_subaccount()),
-    )
+    (owner, *account.effective_subaccount())
+}
+
+fn account_from_key((owner, subaccount): (Blob<29>, [u8; 32])) -> Account {
+    Account {
+        owner: Principal::from_slice(owner.as_slice()),
+        subaccount: (subaccount != [0; 32]).then_some(subaccount),
+    }
 }
 
 #[init]
@@ -331,6 +493,13 @@ fn post_upgrade(index_arg: Option<IndexArg>) {
         _ => (),
     };
 
+    // Indexes upgraded from a version that didn't track allowances
+    // have to process the approvals of the blocks they already indexed.
+    let num_blocks = with_blocks(|blocks| blocks.len());
+    mutate_state(|state| {
+        state.allowances_backfill.end = state.allowances_backfill.end.min(num_blocks);
+    });
+
     // set the first build_index to be called after init
     set_build_index_timer(DEFAULT_MAX_WAIT_TIME);
 }
@@ -419,6 +588,12 @@ pub async fn build_index() -> Option<()> {
             state.is_build_index_running = false;
         });
     });
+    // The allowances of the blocks indexed before the upgrade must be
+    // processed before the allowance changes of new blocks.
+    if !with_state(|state| state.allowances_backfill.is_empty()) {
+        backfill_allowances();
+        return Some(());
+    }
     let next_txid = with_blocks(|blocks| blocks.len());
     let res = get_blocks_from_ledger(next_txid).await?;
     let mut tx_indexed_count: usize = 0;
@@ -482,9 +657,37 @@ fn append_block(block_index: BlockIndex64, block: GenericBlock) {
 
         // change the balance of the involved accounts
         process_balance_changes(block_index, &decoded_block);
+
+        // change the allowances of the involved accounts
+        process_allowance_changes(block_index, &decoded_block);
     });
 }
 
+fn backfill_allowances() {
+    let backfill = with_state(|state| state.allowances_backfill.clone());
+    let end = backfill.end.min(
+        backfill
+            .start
+            .saturating_add(MAX_ALLOWANCES_BACKFILL_BLOCKS),
+    );
+    for block_index in backfill.start..end {
+        let block = get_decoded_block(block_index).unwrap_or_else(|| {
+            trap(&format!(
+                "bug: block {block_index} to backfill allowances not found"
+            ))
+        });
+        process_allowance_changes(block_index, &block);
+    }
+    mutate_state(|state| state.allowances_backfill.start = end);
+    log!(
+        P1,
+        "Backfilled the allowances of blocks {}..{}, remaining: {}",
+        backfill.start,
+        end,
+        backfill.end - end
+    );
+}
+
 fn append_blocks(new_blocks: Vec<GenericBlock>) {
     // the index of the next block that we
     // are going to append
@@ -593,6 +796,84 @@ fn process_balance_changes(block_index: BlockIndex64, block: &Block<Tokens>) {
     );
 }
 
+fn process_allowance_changes(block_index: BlockIndex64, block: &Block<Tokens>) {
+    measure_span(
+        &PROFILING_DATA,
+        "append_blocks.process_allowance_changes",
+        move || match block.transaction.operation {
+            Operation::Approve {
+                from,
+                spender,
+                amount,
+                expires_at,
+                ..
+            } => {
+                let key = (account_key(from), account_key(spender));
+                with_allowances(|allowances| {
+                    if amount == Tokens::zero() {
+                        allowances.remove(&key);
+                    } else {
+                        allowances.insert(key, StoredAllowance { amount, expires_at });
+                    }
+                });
+            }
+            Operation::Transfer {
+                from,
+                spender: Some(spender),
+                amount,
+                fee,
+                ..
+            } if from != spender => {
+                let fee = block.effective_fee.or(fee).unwrap_or_else(|| {
+                    ic_cdk::trap(&format!(
+                        "Block {} is of type Transfer but has no fee or effective fee!",
+                        block_index
+                    ))
+                });
+                let used_allowance = amount.checked_add(&fee).unwrap_or_else(|| {
+                    ic_cdk::trap(&format!(
+                        "token amount overflow while indexing block {block_index}"
+                    ))
+                });
+                use_allowance(block_index, from, spender, used_allowance);
+            }
+            Operation::Burn {
+                from,
+                spender: Some(spender),
+                amount,
+            } if from != spender => use_allowance(block_index, from, spender, amount),
+            _ => {}
+        },
+    );
+}
+
+/// Decreases the allowance granted by `from` to `spender` by `amount`.
+/// It removes the allowance if it drops to 0.
+fn use_allowance(block_index: BlockIndex64, from: Account, spender: Account, amount: Tokens) {
+    let key = (account_key(from), account_key(spender));
+    with_allowances(|allowances| {
+        let Some(mut allowance) = allowances.get(&key) else {
+            log!(
+                P0,
+                "Block {} uses an allowance from {} to {} that doesn't exist",
+                block_index,
+                from,
+                spender
+            );
+            return;
+        };
+        allowance.amount = allowance.amount.checked_sub(&amount).unwrap_or_else(|| {
+            ic_cdk::trap(&format!("Block {} caused an underflow for the allowance from {} to {} when calculating allowance {} - amount {}",
+                block_index, from, spender, allowance.amount, amount));
+        });
+        if allowance.amount == Tokens::zero() {
+            allowances.remove(&key);
+        } else {
+            allowances.insert(key, allowance);
+        }
+    });
+}
+
 fn debit(block_index: BlockIndex64, account: Account, amount: Tokens) {
     change_balance(account, |balance| {
         balance.checked_sub(&amount).unwrap_or_else(|| {
@@ -826,6 +1107,50 @@ fn list_subaccounts(args: ListSubaccountsArgs) -> Vec<Subaccount> {
     })
 }
 
+#[query]
+#[candid_method(query)]
+fn icrc103_get_allowances(arg: GetAllowancesArgs) -> Result<Allowances, GetAllowancesError> {
+    let from_account = arg.from_account.unwrap_or_else(|| Account {
+        owner: ic_cdk::caller(),
+        subaccount: None,
+    });
+    let take = match arg.take {
+        Some(take) => take
+            .0
+            .to_u64()
+            .ok_or(GetAllowancesError::InvalidTakeValue)?,
+        None => MAX_TAKE_ALLOWANCES,
+    }
+    .min(MAX_TAKE_ALLOWANCES);
+    let from_key = account_key(from_account);
+    let start = match arg.prev_spender {
+        Some(prev_spender) => Excluded((from_key.clone(), account_key(prev_spender))),
+        None => Included((
+            from_key.clone(),
+            (Blob::try_from(&[][..]).unwrap(), [0; 32]),
+        )),
+    };
+    let now = ic_cdk::api::time();
+    Ok(with_allowances(|allowances| {
+        allowances
+            .range((start, Unbounded))
+            .take_while(|((from, _), _)| from == &from_key)
+            .filter(|(_, allowance)| {
+                allowance
+                    .expires_at
+                    .map_or(true, |expires_at| expires_at > now)
+            })
+            .take(take as usize)
+            .map(|((_, spender), allowance)| Allowance {
+                from_account,
+                to_spender: account_from_key(spender),
+                allowance: allowance.amount.into(),
+                expires_at: allowance.expires_at,
+            })
+            .collect()
+    }))
+}
+
 #[query(hidden = true)]
 fn http_request(req: HttpRequest) -> HttpResponse {
     if req.path() == "/metrics" {
@@ -22,7 +22,11 @@ use ic_ledger_core::block::BlockType;
 use ic_state_machine_tests::{StateMachine, WasmResult};
 use icrc_ledger_types::icrc1::account::{Account, Subaccount};
 use icrc_ledger_types::icrc1::transfer::{BlockIndex, TransferArg, TransferError};
+use icrc_ledger_types::icrc103::get_allowances::{
+    Allowance, Allowances, GetAllowancesArgs, GetAllowancesError,
+};
 use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
+use icrc_ledger_types::icrc2::transfer_from::{TransferFromArgs, TransferFromError};
 use icrc_ledger_types::icrc3::blocks::{BlockRange, GenericBlock, GetBlocksRequest};
 use icrc_ledger_types::icrc3::transactions::{Mint, Transaction, Transfer};
 use num_traits::cast::ToPrimitive;
@@ -139,6 +143,7 @@ fn upgrade_ledger(
         feature_flags: None,
         maximum_number_of_accounts: None,
         accounts_overflow_trim_quantity: None,
+        max_transfer_batch_size: None,
     }));
     env.upgrade_canister(ledger_id, ledger_wasm(), Encode!(&args).unwrap())
         .unwrap()
@@ -441,6 +446,60 @@ fn approve(
         .unwrap()
 }
 
+fn transfer_from(
+    env: &StateMachine,
+    ledger_id: CanisterId,
+    from: Account,
+    to: Account,
+    spender: Account,
+    amount: u64,
+) -> BlockIndex {
+    let arg = TransferFromArgs {
+        spender_subaccount: spender.subaccount,
+        from,
+        to,
+        amount: amount.into(),
+        fee: None,
+        memo: None,
+        created_at_time: None,
+    };
+    let req = Encode!(&arg).expect("Failed to encode TransferFromArgs");
+    let res = env
+        .execute_ingress_as(
+            PrincipalId(spender.owner),
+            ledger_id,
+            "icrc2_transfer_from",
+            req,
+        )
+        .expect("Failed to transfer tokens")
+        .bytes();
+    Decode!(&res, Result<BlockIndex, TransferFromError>)
+        .expect("Failed to decode Result<BlockIndex, TransferFromError>")
+        .unwrap_or_else(|e| panic!("Failed to transfer tokens. arg:{:?} error:{:?}", arg, e))
+}
+
+fn icrc103_get_allowances(
+    env: &StateMachine,
+    canister_id: CanisterId,
+    from_account: Account,
+    prev_spender: Option<Account>,
+    take: Option<u64>,
+) -> Allowances {
+    let req = Encode!(&GetAllowancesArgs {
+        from_account: Some(from_account),
+        prev_spender,
+        take: take.map(Nat::from),
+    })
+    .expect("Failed to encode GetAllowancesArgs");
+    let res = env
+        .query(canister_id, "icrc103_get_allowances", req)
+        .expect("Failed to get allowances")
+        .bytes();
+    Decode!(&res, Result<Allowances, GetAllowancesError>)
+        .expect("Failed to decode Result<Allowances, GetAllowancesError>")
+        .expect("Failed to get allowances")
+}
+
 // Same as get_account_transactions but with the old index interface.
 fn old_get_account_transactions(
     env: &StateMachine,
@@ -1481,3 +1540,148 @@ fn test_principal_subaccounts() {
     // The subaccount 1 should show up in a `list_subaccount` query although it has only been involved in an Approve transaction
     assert!(subaccounts.contains(&account(2, 1).subaccount.unwrap()));
 }
+
+#[test]
+fn test_icrc103_get_allowances() {
+    let initial_balances: Vec<_> = vec![(account(1, 0), 1_000_000_000_000)];
+    let env = &StateMachine::new();
+    let minter = minter_identity().sender().unwrap();
+    let ledger_id = install_ledger(
+        env,
+        initial_balances,
+        default_archive_options(),
+        None,
+        minter,
+    );
+    let index_id = install_index_ng(env, ledger_id);
+
+    approve(env, ledger_id, account(1, 0), account(2, 0), 1_000_000);
+    approve(env, ledger_id, account(1, 0), account(3, 0), 2_000_000);
+    approve(env, ledger_id, account(1, 0), account(4, 0), 3_000_000);
+    transfer_from(
+        env,
+        ledger_id,
+        account(1, 0),
+        account(5, 0),
+        account(2, 0),
+        100_000,
+    );
+    // Setting an allowance to 0 removes it.
+    approve(env, ledger_id, account(1, 0), account(3, 0), 0);
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+
+    let allowances = icrc103_get_allowances(env, index_id, account(1, 0), None, None);
+    assert_eq!(
+        allowances,
+        vec![
+            Allowance {
+                from_account: account(1, 0),
+                to_spender: account(2, 0),
+                allowance: Nat::from(1_000_000 - 100_000 - FEE),
+                expires_at: None,
+            },
+            Allowance {
+                from_account: account(1, 0),
+                to_spender: account(4, 0),
+                allowance: Nat::from(3_000_000u64),
+                expires_at: None,
+            },
+        ]
+    );
+    assert_eq!(
+        allowances,
+        icrc103_get_allowances(env, ledger_id, account(1, 0), None, None)
+    );
+
+    // Pagination.
+    assert_eq!(
+        icrc103_get_allowances(env, index_id, account(1, 0), None, Some(1)),
+        allowances[..1]
+    );
+    assert_eq!(
+        icrc103_get_allowances(env, index_id, account(1, 0), Some(account(2, 0)), None),
+        allowances[1..]
+    );
+
+    // Accounts that didn't grant any allowance have none.
+    assert_eq!(
+        icrc103_get_allowances(env, index_id, account(2, 0), None, None),
+        vec![]
+    );
+}
+
+#[test]
+fn test_icrc103_get_allowances_after_upgrade() {
+    let initial_balances: Vec<_> = vec![(account(1, 0), 1_000_000_000_000)];
+    let env = &StateMachine::new();
+    let minter = minter_identity().sender().unwrap();
+    let ledger_id = install_ledger(
+        env,
+        initial_balances,
+        default_archive_options(),
+        None,
+        minter,
+    );
+    let index_id = install_index_ng(env, ledger_id);
+
+    approve(env, ledger_id, account(1, 0), account(2, 0), 1_000_000);
+    approve(env, ledger_id, account(1, 0), account(3, 0), 2_000_000);
+    transfer_from(
+        env,
+        ledger_id,
+        account(1, 0),
+        account(5, 0),
+        account(2, 0),
+        100_000,
+    );
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+
+    env.upgrade_canister(
+        index_id,
+        index_ng_wasm(),
+        Encode!(&None::<IndexArg>).unwrap(),
+    )
+    .unwrap();
+
+    // The allowances of the blocks indexed before the upgrade are kept and
+    // keep changing with the blocks indexed after the upgrade.
+    transfer_from(
+        env,
+        ledger_id,
+        account(1, 0),
+        account(5, 0),
+        account(3, 0),
+        200_000,
+    );
+    approve(env, ledger_id, account(1, 0), account(4, 0), 3_000_000);
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+
+    let allowances = icrc103_get_allowances(env, index_id, account(1, 0), None, None);
+    assert_eq!(
+        allowances,
+        vec![
+            Allowance {
+                from_account: account(1, 0),
+                to_spender: account(2, 0),
+                allowance: Nat::from(1_000_000 - 100_000 - FEE),
+                expires_at: None,
+            },
+            Allowance {
+                from_account: account(1, 0),
+                to_spender: account(3, 0),
+                allowance: Nat::from(2_000_000 - 200_000 - FEE),
+                expires_at: None,
+            },
+            Allowance {
+                from_account: account(1, 0),
+                to_spender: account(4, 0),
+                allowance: Nat::from(3_000_000u64),
+                expires_at: None,
+            },
+        ]
+    );
+    assert_eq!(
+        allowances,
+        icrc103_get_allowances(env, ledger_id, account(1, 0), None, None)
+    );
+}
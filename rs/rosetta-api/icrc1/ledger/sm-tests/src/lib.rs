@@ -12,25 +12,38 @@ use ic_state_machine_tests::{CanisterId, ErrorCode, StateMachine, WasmResult};
 use ic_types::Cycles;
 use ic_universal_canister::{call_args, wasm, UNIVERSAL_CANISTER_WASM};
 use icrc_ledger_types::icrc::generic_metadata_value::MetadataValue as Value;
-use icrc_ledger_types::icrc::generic_value::Value as GenericValue;
+use icrc_ledger_types::icrc::generic_value::{ICRC3Value, Value as GenericValue};
 use icrc_ledger_types::icrc1::account::{Account, Subaccount};
 use icrc_ledger_types::icrc1::transfer::{Memo, TransferArg, TransferError};
+use icrc_ledger_types::icrc103::get_allowances::{
+    Allowances, GetAllowancesArgs, GetAllowancesError,
+};
 use icrc_ledger_types::icrc2::allowance::{Allowance, AllowanceArgs};
 use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
 use icrc_ledger_types::icrc2::transfer_from::{TransferFromArgs, TransferFromError};
+use icrc_ledger_types::icrc21::errors::Icrc21Error;
+use icrc_ledger_types::icrc21::requests::{
+    ConsentMessageMetadata, ConsentMessageRequest, ConsentMessageSpec, DisplayMessageType,
+};
+use icrc_ledger_types::icrc21::responses::{ConsentInfo, ConsentMessage};
 use icrc_ledger_types::icrc3;
-use icrc_ledger_types::icrc3::archive::ArchiveInfo;
+use icrc_ledger_types::icrc3::archive::{ArchiveInfo, GetArchivesArgs, GetArchivesResult};
 use icrc_ledger_types::icrc3::blocks::BlockRange;
 use icrc_ledger_types::icrc3::blocks::GenericBlock as IcrcBlock;
 use icrc_ledger_types::icrc3::blocks::GetBlocksResponse;
+use icrc_ledger_types::icrc3::blocks::{
+    GetBlocksRequest, GetBlocksResult, ICRC3DataCertificate, SupportedBlockType,
+};
 use icrc_ledger_types::icrc3::transactions::GetTransactionsRequest;
 use icrc_ledger_types::icrc3::transactions::GetTransactionsResponse;
 use icrc_ledger_types::icrc3::transactions::Transaction as Tx;
 use icrc_ledger_types::icrc3::transactions::TransactionRange;
 use icrc_ledger_types::icrc3::transactions::Transfer;
+use icrc_ledger_types::icrc4::transfer_batch::{TransferBatchError, TransferBatchResults};
 use num_traits::ToPrimitive;
 use proptest::prelude::*;
 use proptest::test_runner::{Config as TestRunnerConfig, TestCaseResult, TestRunner};
+use serde_bytes::ByteBuf;
 use std::{
     cmp,
     collections::{BTreeMap, HashMap},
@@ -751,7 +764,7 @@ where
         standards.push(standard.name);
     }
     standards.sort();
-    assert_eq!(standards, vec!["ICRC-1", "ICRC-2"]);
+    assert_eq!(standards, vec!["ICRC-1", "ICRC-10", "ICRC-2", "ICRC-21"]);
 }
 pub fn test_metadata<T>(ledger_wasm: Vec<u8>, encode_init_args: fn(InitArgs) -> T)
 where
@@ -819,7 +832,10 @@ where
         standards.push(standard.name);
     }
     standards.sort();
-    assert_eq!(standards, vec!["ICRC-1", "ICRC-2"]);
+    assert_eq!(
+        standards,
+        vec!["ICRC-1", "ICRC-10", "ICRC-103", "ICRC-2", "ICRC-21", "ICRC-3", "ICRC-4"]
+    );
 }
 
 pub fn test_total_supply<T>(ledger_wasm: Vec<u8>, encode_init_args: fn(InitArgs) -> T)
@@ -1565,6 +1581,498 @@ where
     assert_eq!(0, missing_blocks_reply.archived_blocks.len());
 }
 
+fn icrc3_get_blocks(
+    env: &StateMachine,
+    canister_id: CanisterId,
+    method: &str,
+    args: Vec<(u64, u64)>,
+) -> GetBlocksResult {
+    let args: Vec<GetBlocksRequest> = args
+        .into_iter()
+        .map(|(start, length)| GetBlocksRequest {
+            start: Nat::from(start),
+            length: Nat::from(length),
+        })
+        .collect();
+    Decode!(
+        &env.query(canister_id, method, Encode!(&args).unwrap())
+            .expect("failed to query icrc3_get_blocks")
+            .bytes(),
+        GetBlocksResult
+    )
+    .expect("failed to decode icrc3_get_blocks response")
+}
+
+pub fn test_icrc3_get_blocks<T>(ledger_wasm: Vec<u8>, encode_init_args: fn(InitArgs) -> T)
+where
+    T: CandidType,
+{
+    let p1 = PrincipalId::new_user_test_id(1);
+    let p2 = PrincipalId::new_user_test_id(2);
+
+    let (env, canister_id) = setup(
+        ledger_wasm,
+        encode_init_args,
+        vec![(Account::from(p1.0), 10_000_000)],
+    );
+
+    for i in 0..ARCHIVE_TRIGGER_THRESHOLD {
+        transfer(&env, canister_id, p1.0, p2.0, 10_000 + i * 10_000).expect("transfer failed");
+    }
+
+    env.run_until_completion(/*max_ticks=*/ 10);
+
+    let chain_length = ARCHIVE_TRIGGER_THRESHOLD + 1;
+    let resp = icrc3_get_blocks(&env, canister_id, "icrc3_get_blocks", vec![(0, 1_000_000)]);
+    assert_eq!(resp.log_length, Nat::from(chain_length));
+    assert_eq!(
+        resp.blocks.len(),
+        (chain_length - NUM_BLOCKS_TO_ARCHIVE) as usize
+    );
+    assert_eq!(resp.blocks[0].id, Nat::from(NUM_BLOCKS_TO_ARCHIVE));
+    assert_eq!(resp.archived_blocks.len(), 1);
+    let archived = &resp.archived_blocks[0];
+    assert_eq!(archived.callback.method, "icrc3_get_blocks");
+    assert_eq!(
+        archived.args,
+        vec![GetBlocksRequest {
+            start: Nat::from(0_u8),
+            length: Nat::from(NUM_BLOCKS_TO_ARCHIVE),
+        }]
+    );
+
+    let archives = Decode!(
+        &env.query(
+            canister_id,
+            "icrc3_get_archives",
+            Encode!(&GetArchivesArgs { from: None }).unwrap()
+        )
+        .expect("failed to query icrc3_get_archives")
+        .bytes(),
+        GetArchivesResult
+    )
+    .expect("failed to decode icrc3_get_archives response");
+    assert_eq!(archives.len(), 1);
+    assert_eq!(archives[0].canister_id, archived.callback.canister_id);
+    assert_eq!(archives[0].start, Nat::from(0_u8));
+    assert_eq!(archives[0].end, Nat::from(NUM_BLOCKS_TO_ARCHIVE - 1));
+
+    let archive_id = CanisterId::unchecked_from_principal(archived.callback.canister_id.into());
+    let archived_blocks =
+        icrc3_get_blocks(&env, archive_id, &archived.callback.method, vec![(0, 100)]);
+    assert_eq!(archived_blocks.blocks.len(), NUM_BLOCKS_TO_ARCHIVE as usize);
+    assert!(archived_blocks.archived_blocks.is_empty());
+
+    // The ICRC-3 blocks agree with the legacy generic blocks and form a hash chain.
+    let legacy_blocks = get_blocks(&env, canister_id.get().0, 0, 1_000_000).blocks;
+    let mut prev_hash = None;
+    for (i, block) in archived_blocks
+        .blocks
+        .into_iter()
+        .chain(resp.blocks.into_iter())
+        .enumerate()
+    {
+        assert_eq!(block.id, Nat::from(i));
+        let generic_block = GenericValue::from(block.block.clone());
+        assert_eq!(
+            prev_hash,
+            get_phash(&generic_block).expect("cannot get the hash of the previous block")
+        );
+        if i as u64 >= NUM_BLOCKS_TO_ARCHIVE {
+            assert_eq!(
+                block.block,
+                ICRC3Value::from(legacy_blocks[i - NUM_BLOCKS_TO_ARCHIVE as usize].clone())
+            );
+        }
+        prev_hash = Some(block.block.hash());
+    }
+
+    // Ranges that are out of bounds do not crash the ledger or the archive.
+    let missing = icrc3_get_blocks(&env, canister_id, "icrc3_get_blocks", vec![(100, 5)]);
+    assert!(missing.blocks.is_empty());
+    assert!(missing.archived_blocks.is_empty());
+    let missing = icrc3_get_blocks(&env, archive_id, "icrc3_get_blocks", vec![(100, 5)]);
+    assert!(missing.blocks.is_empty());
+
+    let certificate = Decode!(
+        &env.query(canister_id, "icrc3_get_tip_certificate", Encode!().unwrap())
+            .expect("failed to query icrc3_get_tip_certificate")
+            .bytes(),
+        Option<ICRC3DataCertificate>
+    )
+    .expect("failed to decode icrc3_get_tip_certificate response");
+    assert!(certificate.is_some());
+
+    let block_types: Vec<String> = Decode!(
+        &env.query(
+            canister_id,
+            "icrc3_supported_block_types",
+            Encode!().unwrap()
+        )
+        .expect("failed to query icrc3_supported_block_types")
+        .bytes(),
+        Vec<SupportedBlockType>
+    )
+    .expect("failed to decode icrc3_supported_block_types response")
+    .into_iter()
+    .map(|block_type| block_type.block_type)
+    .collect();
+    assert_eq!(
+        block_types,
+        vec!["1burn", "1mint", "1xfer", "2approve", "2xfer"]
+    );
+}
+
+fn icrc21_consent_message(
+    env: &StateMachine,
+    ledger: CanisterId,
+    caller: Principal,
+    request: &ConsentMessageRequest,
+) -> Result<ConsentInfo, Icrc21Error> {
+    Decode!(
+        &env.execute_ingress_as(
+            PrincipalId(caller),
+            ledger,
+            "icrc21_canister_call_consent_message",
+            Encode!(request).unwrap()
+        )
+        .expect("failed to call icrc21_canister_call_consent_message")
+        .bytes(),
+        Result<ConsentInfo, Icrc21Error>
+    )
+    .expect("failed to decode icrc21_canister_call_consent_message response")
+}
+
+pub fn test_icrc21_standard<T>(ledger_wasm: Vec<u8>, encode_init_args: fn(InitArgs) -> T)
+where
+    T: CandidType,
+{
+    let from = PrincipalId::new_user_test_id(1);
+    let to = PrincipalId::new_user_test_id(2);
+    let (env, canister_id) = setup(ledger_wasm, encode_init_args, vec![]);
+
+    let standards: Vec<String> = Decode!(
+        &env.query(
+            canister_id,
+            "icrc10_supported_standards",
+            Encode!().unwrap()
+        )
+        .expect("failed to query icrc10_supported_standards")
+        .bytes(),
+        Vec<StandardRecord>
+    )
+    .expect("failed to decode icrc10_supported_standards response")
+    .into_iter()
+    .map(|standard| standard.name)
+    .collect();
+    assert!(standards.contains(&"ICRC-21".to_string()));
+
+    let transfer_arg = TransferArg {
+        from_subaccount: None,
+        to: Account::from(to.0),
+        fee: None,
+        created_at_time: None,
+        memo: Some(Memo::from(b"test".to_vec())),
+        amount: Nat::from(123_450_000_u64),
+    };
+    let mut request = ConsentMessageRequest {
+        method: "icrc1_transfer".to_string(),
+        arg: ByteBuf::from(Encode!(&transfer_arg).unwrap()),
+        user_preferences: ConsentMessageSpec {
+            metadata: ConsentMessageMetadata {
+                language: "en".to_string(),
+                utc_offset_minutes: None,
+            },
+            device_spec: Some(DisplayMessageType::GenericDisplay),
+        },
+    };
+    let consent_info = icrc21_consent_message(&env, canister_id, from.0, &request)
+        .expect("failed to build the consent message");
+    assert_eq!(consent_info.metadata.language, "en");
+    match consent_info.consent_message {
+        ConsentMessage::GenericDisplayMessage(message) => {
+            assert!(message.contains(&format!("**From:**\n{}", Account::from(from.0))));
+            assert!(message.contains(&format!("**Amount:**\n1.2345 {}", TOKEN_SYMBOL)));
+            assert!(message.contains(&format!("**To:**\n{}", Account::from(to.0))));
+            assert!(message.contains(&format!("**Fee:**\n0.0001 {}", TOKEN_SYMBOL)));
+            assert!(message.contains("**Memo:**\ntest"));
+        }
+        message => panic!("expected a generic display message, got {:?}", message),
+    }
+
+    request.user_preferences.device_spec = Some(DisplayMessageType::LineDisplay {
+        characters_per_line: 30,
+        lines_per_page: 3,
+    });
+    let consent_info = icrc21_consent_message(&env, canister_id, from.0, &request)
+        .expect("failed to build the consent message");
+    match consent_info.consent_message {
+        ConsentMessage::LineDisplayMessage { pages } => {
+            assert!(pages.len() > 1);
+            for page in pages {
+                assert!(page.lines.len() <= 3);
+                assert!(page.lines.iter().all(|line| line.chars().count() <= 30));
+            }
+        }
+        message => panic!("expected a line display message, got {:?}", message),
+    }
+
+    request.method = "icrc1_balance_of".to_string();
+    assert!(matches!(
+        icrc21_consent_message(&env, canister_id, from.0, &request),
+        Err(Icrc21Error::UnsupportedCanisterCall(_))
+    ));
+}
+
+fn get_allowances(
+    env: &StateMachine,
+    ledger: CanisterId,
+    caller: Principal,
+    arg: &GetAllowancesArgs,
+) -> Result<Allowances, GetAllowancesError> {
+    Decode!(
+        &env.execute_ingress_as(
+            PrincipalId(caller),
+            ledger,
+            "icrc103_get_allowances",
+            Encode!(arg).unwrap()
+        )
+        .expect("failed to call icrc103_get_allowances")
+        .bytes(),
+        Result<Allowances, GetAllowancesError>
+    )
+    .expect("failed to decode icrc103_get_allowances response")
+}
+
+pub fn test_icrc103_get_allowances<T>(ledger_wasm: Vec<u8>, encode_init_args: fn(InitArgs) -> T)
+where
+    T: CandidType,
+{
+    let owner = PrincipalId::new_user_test_id(1);
+    let other_owner = PrincipalId::new_user_test_id(2);
+    let (env, canister_id) = setup(
+        ledger_wasm,
+        encode_init_args,
+        vec![
+            (Account::from(owner.0), 1_000_000),
+            (Account::from(other_owner.0), 1_000_000),
+        ],
+    );
+    let spenders: Vec<Account> = (10..15)
+        .map(|n| Account::from(PrincipalId::new_user_test_id(n).0))
+        .collect();
+
+    for (i, spender) in spenders.iter().enumerate() {
+        let mut approve_args = default_approve_args(*spender, 10_000 * (i as u64 + 1));
+        if i == 2 {
+            approve_args.expires_at = Some(system_time_to_nanos(env.time()) + 1_000_000_000);
+        }
+        send_approval(&env, canister_id, owner.0, &approve_args).expect("approval failed");
+    }
+    send_approval(
+        &env,
+        canister_id,
+        other_owner.0,
+        &default_approve_args(spenders[0], 50_000),
+    )
+    .expect("approval failed");
+
+    let mut sorted_spenders = spenders.clone();
+    sorted_spenders.sort();
+    let list_spenders = |allowances: Allowances| -> Vec<Account> {
+        allowances
+            .into_iter()
+            .map(|allowance| {
+                assert_eq!(allowance.from_account, Account::from(owner.0));
+                allowance.to_spender
+            })
+            .collect()
+    };
+
+    // The account defaults to the default account of the caller.
+    let all = get_allowances(
+        &env,
+        canister_id,
+        owner.0,
+        &GetAllowancesArgs {
+            from_account: None,
+            prev_spender: None,
+            take: None,
+        },
+    )
+    .expect("failed to get the allowances");
+    assert_eq!(list_spenders(all.clone()), sorted_spenders);
+    let expiring = all
+        .iter()
+        .find(|allowance| allowance.to_spender == spenders[2])
+        .unwrap();
+    assert_eq!(expiring.allowance, Nat::from(30_000_u64));
+    assert!(expiring.expires_at.is_some());
+
+    // Paginate through the allowances as any caller.
+    let first_page = get_allowances(
+        &env,
+        canister_id,
+        other_owner.0,
+        &GetAllowancesArgs {
+            from_account: Some(Account::from(owner.0)),
+            prev_spender: None,
+            take: Some(Nat::from(2_u64)),
+        },
+    )
+    .expect("failed to get the allowances");
+    assert_eq!(list_spenders(first_page), sorted_spenders[..2].to_vec());
+    let next_page = get_allowances(
+        &env,
+        canister_id,
+        other_owner.0,
+        &GetAllowancesArgs {
+            from_account: Some(Account::from(owner.0)),
+            prev_spender: Some(sorted_spenders[1]),
+            take: Some(Nat::from(10_u64)),
+        },
+    )
+    .expect("failed to get the allowances");
+    assert_eq!(list_spenders(next_page), sorted_spenders[2..].to_vec());
+
+    // Expired allowances are not listed.
+    env.advance_time(Duration::from_secs(2));
+    let unexpired_spenders: Vec<Account> = sorted_spenders
+        .iter()
+        .filter(|spender| **spender != spenders[2])
+        .copied()
+        .collect();
+    let all = get_allowances(
+        &env,
+        canister_id,
+        owner.0,
+        &GetAllowancesArgs {
+            from_account: None,
+            prev_spender: None,
+            take: None,
+        },
+    )
+    .expect("failed to get the allowances");
+    assert_eq!(list_spenders(all), unexpired_spenders);
+
+    assert_eq!(
+        get_allowances(
+            &env,
+            canister_id,
+            owner.0,
+            &GetAllowancesArgs {
+                from_account: None,
+                prev_spender: None,
+                take: Some(Nat::from(u128::MAX)),
+            },
+        ),
+        Err(GetAllowancesError::InvalidTakeValue)
+    );
+}
+
+pub fn transfer_batch(
+    env: &StateMachine,
+    ledger: CanisterId,
+    caller: Principal,
+    args: &[TransferArg],
+) -> Result<TransferBatchResults, TransferBatchError> {
+    Decode!(
+        &env.execute_ingress_as(
+            PrincipalId(caller),
+            ledger,
+            "icrc4_transfer_batch",
+            Encode!(&args).unwrap()
+        )
+        .expect("failed to call icrc4_transfer_batch")
+        .bytes(),
+        Result<TransferBatchResults, TransferBatchError>
+    )
+    .expect("failed to decode icrc4_transfer_batch response")
+}
+
+pub fn test_icrc4_transfer_batch<T>(ledger_wasm: Vec<u8>, encode_init_args: fn(InitArgs) -> T)
+where
+    T: CandidType,
+{
+    let p1 = PrincipalId::new_user_test_id(1);
+    let p2 = PrincipalId::new_user_test_id(2);
+    let p3 = PrincipalId::new_user_test_id(3);
+    let (env, canister_id) = setup(
+        ledger_wasm,
+        encode_init_args,
+        vec![(Account::from(p1.0), 1_000_000)],
+    );
+    let now = system_time_to_nanos(env.time());
+    let transfer_arg = |to: PrincipalId, amount: u64| TransferArg {
+        from_subaccount: None,
+        to: to.0.into(),
+        fee: None,
+        amount: Nat::from(amount),
+        created_at_time: None,
+        memo: None,
+    };
+    let deduplicated_arg = TransferArg {
+        created_at_time: Some(now),
+        ..transfer_arg(p3, 50_000)
+    };
+
+    let results = transfer_batch(
+        &env,
+        canister_id,
+        p1.0,
+        &[
+            transfer_arg(p2, 100_000),
+            transfer_arg(p3, 2_000_000),
+            deduplicated_arg.clone(),
+            deduplicated_arg,
+            TransferArg {
+                fee: Some(Nat::from(FEE + 1)),
+                ..transfer_arg(p2, 10_000)
+            },
+            TransferArg {
+                memo: Some(Memo::from(vec![0u8; 33])),
+                ..transfer_arg(p2, 10_000)
+            },
+            transfer_arg(p2, 200_000),
+        ],
+    )
+    .expect("failed to transfer a batch");
+
+    // Failed entries, including entries with an oversized memo, don't prevent
+    // the next entries from being applied.
+    assert_eq!(
+        results,
+        vec![
+            Ok(Nat::from(1_u64)),
+            Err(TransferError::InsufficientFunds {
+                balance: Nat::from(1_000_000 - 100_000 - FEE),
+            }),
+            Ok(Nat::from(2_u64)),
+            Err(TransferError::Duplicate {
+                duplicate_of: Nat::from(2_u64),
+            }),
+            Err(TransferError::BadFee {
+                expected_fee: Nat::from(FEE),
+            }),
+            Err(TransferError::GenericError {
+                error_code: Nat::from(0_u64),
+                message: "the memo field size of 33 bytes is above the allowed limit of 32 bytes"
+                    .to_string(),
+            }),
+            Ok(Nat::from(3_u64)),
+        ]
+    );
+    assert_eq!(
+        balance_of(&env, canister_id, p1.0),
+        1_000_000 - 350_000 - 3 * FEE
+    );
+    assert_eq!(balance_of(&env, canister_id, p2.0), 300_000);
+    assert_eq!(balance_of(&env, canister_id, p3.0), 50_000);
+
+    // An empty batch is a no-op.
+    assert_eq!(transfer_batch(&env, canister_id, p1.0, &[]), Ok(vec![]));
+}
+
 // Generate random blocks and check that their CBOR encoding complies with the CDDL spec.
 pub fn block_encoding_agrees_with_the_schema() {
     use std::path::PathBuf;
@@ -2655,9 +3163,12 @@ pub fn expect_icrc2_disabled(
         "Expected ICRC-2 disabled error, got: {}",
         err.description()
     );
-    let standards = supported_standards(env, canister_id);
-    assert_eq!(standards.len(), 1);
-    assert_eq!(standards[0].name, "ICRC-1");
+    let standards: Vec<String> = supported_standards(env, canister_id)
+        .into_iter()
+        .map(|standard| standard.name)
+        .collect();
+    assert!(standards.contains(&"ICRC-1".to_string()));
+    assert!(!standards.contains(&"ICRC-2".to_string()));
 }
 
 pub fn test_feature_flags<T>(ledger_wasm: Vec<u8>, encode_init_args: fn(InitArgs) -> T)
@@ -2729,7 +3240,10 @@ where
         standards.push(standard.name);
     }
     standards.sort();
-    assert_eq!(standards, vec!["ICRC-1", "ICRC-2"]);
+    assert_eq!(
+        standards,
+        vec!["ICRC-1", "ICRC-10", "ICRC-103", "ICRC-2", "ICRC-21", "ICRC-3", "ICRC-4"]
+    );
 
     let block_index =
         send_approval(&env, canister_id, from.0, &approve_args).expect("approval failed");
@@ -20,26 +20,42 @@ use ic_ledger_canister_core::{
     range_utils,
 };
 use ic_ledger_core::{
-    approvals::AllowanceTable,
-    balances::Balances,
+    approvals::{Allowance, AllowanceTable, AllowancesData, HeapAllowancesData},
+    balances::{Balances, BalancesStore, InspectableBalancesStore},
     block::{BlockIndex, BlockType, EncodedBlock, FeeCollector},
     timestamp::TimeStamp,
-    tokens::TokensType,
+    tokens::Zero,
 };
 use ic_ledger_hash_of::HashOf;
+use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory};
+use ic_stable_structures::storable::{Blob, Bound, Storable};
+use ic_stable_structures::{DefaultMemoryImpl, StableBTreeMap};
 use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc103::get_allowances::{Allowance as Allowance103, Allowances};
+use icrc_ledger_types::icrc3::blocks::{
+    ArchivedBlocks, BlockWithId, GetBlocksRequest, GetBlocksResult, ICRC3QueryArchiveFn,
+};
 use icrc_ledger_types::icrc3::transactions::Transaction as Tx;
 use icrc_ledger_types::icrc3::{blocks::GetBlocksResponse, transactions::GetTransactionsResponse};
 use icrc_ledger_types::{
     icrc::generic_metadata_value::MetadataValue as Value,
     icrc3::archive::{ArchivedRange, QueryBlockArchiveFn, QueryTxArchiveFn},
 };
+use num_traits::ToPrimitive;
 use serde::{Deserialize, Serialize};
 use serde_bytes::ByteBuf;
 use std::borrow::Cow;
+use std::cell::RefCell;
 use std::collections::{BTreeMap, VecDeque};
+use std::ops::Bound as RangeBound;
 use std::time::Duration;
 
+#[cfg(not(feature = "u256-tokens"))]
+pub type Tokens = ic_icrc1_tokens_u64::U64;
+
+#[cfg(feature = "u256-tokens")]
+pub type Tokens = ic_icrc1_tokens_u256::U256;
+
 const TRANSACTION_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
 const MAX_ACCOUNTS: usize = 28_000_000;
 /// The maximum number of transactions the ledger should return for a single
@@ -48,8 +64,63 @@ const MAX_TRANSACTIONS_PER_REQUEST: usize = 2_000;
 const ACCOUNTS_OVERFLOW_TRIM_QUANTITY: usize = 100_000;
 const MAX_TRANSACTIONS_IN_WINDOW: usize = 3_000_000;
 const MAX_TRANSACTIONS_TO_PURGE: usize = 100_000;
+/// The maximum number of allowances returned by a single
+/// icrc103_get_allowances request.
+pub const MAX_TAKE_ALLOWANCES: u64 = 500;
 
 const DEFAULT_MAX_MEMO_LENGTH: u16 = 32;
+/// The default maximum number of transfers in a single icrc4_transfer_batch
+/// request.
+const DEFAULT_MAX_TRANSFER_BATCH_SIZE: u64 = 500;
+/// The maximum number of bytes of a LEB128-encoded u64.
+const MAX_U64_ENCODING_BYTES: usize = 10;
+
+const UPGRADES_MEMORY_ID: MemoryId = MemoryId::new(0);
+const ALLOWANCES_MEMORY_ID: MemoryId = MemoryId::new(1);
+const ALLOWANCES_EXPIRATIONS_MEMORY_ID: MemoryId = MemoryId::new(2);
+const ALLOWANCES_ARRIVALS_MEMORY_ID: MemoryId = MemoryId::new(3);
+const BALANCES_MEMORY_ID: MemoryId = MemoryId::new(4);
+
+type VM = VirtualMemory<DefaultMemoryImpl>;
+/// An account stored as its owner and its effective subaccount.
+type AccountKey = (Blob<29>, [u8; 32]);
+/// An approval stored as the keys of the account and of the spender.
+type ApprovalStorageKey = (AccountKey, AccountKey);
+
+thread_local! {
+    static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(
+        MemoryManager::init(DefaultMemoryImpl::default())
+    );
+
+    /// The memory where the ledger writes its heap state during upgrades.
+    pub static UPGRADES_MEMORY: RefCell<VM> = with_memory_manager(|memory_manager| {
+        RefCell::new(memory_manager.get(UPGRADES_MEMORY_ID))
+    });
+
+    static ALLOWANCES: RefCell<StableBTreeMap<ApprovalStorageKey, StorableAllowance, VM>> =
+        with_memory_manager(|memory_manager| {
+            RefCell::new(StableBTreeMap::init(memory_manager.get(ALLOWANCES_MEMORY_ID)))
+        });
+
+    static ALLOWANCES_EXPIRATIONS: RefCell<StableBTreeMap<(u64, ApprovalStorageKey), (), VM>> =
+        with_memory_manager(|memory_manager| {
+            RefCell::new(StableBTreeMap::init(memory_manager.get(ALLOWANCES_EXPIRATIONS_MEMORY_ID)))
+        });
+
+    static ALLOWANCES_ARRIVALS: RefCell<StableBTreeMap<(u64, ApprovalStorageKey), (), VM>> =
+        with_memory_manager(|memory_manager| {
+            RefCell::new(StableBTreeMap::init(memory_manager.get(ALLOWANCES_ARRIVALS_MEMORY_ID)))
+        });
+
+    static BALANCES: RefCell<StableBTreeMap<AccountKey, Tokens, VM>> =
+        with_memory_manager(|memory_manager| {
+            RefCell::new(StableBTreeMap::init(memory_manager.get(BALANCES_MEMORY_ID)))
+        });
+}
+
+fn with_memory_manager<R>(f: impl FnOnce(&MemoryManager<DefaultMemoryImpl>) -> R) -> R {
+    MEMORY_MANAGER.with(|cell| f(&cell.borrow()))
+}
 
 #[derive(Debug, Clone)]
 pub struct Icrc1ArchiveWasm;
@@ -144,6 +215,7 @@ impl InitArgsBuilder {
             feature_flags: None,
             maximum_number_of_accounts: None,
             accounts_overflow_trim_quantity: None,
+            max_transfer_batch_size: None,
         })
     }
 
@@ -208,6 +280,11 @@ impl InitArgsBuilder {
         self
     }
 
+    pub fn with_max_transfer_batch_size(mut self, limit: u64) -> Self {
+        self.0.max_transfer_batch_size = Some(limit);
+        self
+    }
+
     pub fn build(self) -> InitArgs {
         self.0
     }
@@ -228,6 +305,7 @@ pub struct InitArgs {
     pub feature_flags: Option<FeatureFlags>,
     pub maximum_number_of_accounts: Option<u64>,
     pub accounts_overflow_trim_quantity: Option<u64>,
+    pub max_transfer_batch_size: Option<u64>,
 }
 
 #[derive(Deserialize, CandidType, Clone, Debug, PartialEq, Eq)]
@@ -265,6 +343,8 @@ pub struct UpgradeArgs {
     pub maximum_number_of_accounts: Option<u64>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub accounts_overflow_trim_quantity: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_transfer_batch_size: Option<u64>,
 }
 
 #[derive(Deserialize, CandidType, Clone, Debug, PartialEq, Eq)]
@@ -274,12 +354,23 @@ pub enum LedgerArgument {
     Upgrade(Option<UpgradeArgs>),
 }
 
+/// The approvals of ledger versions that kept them on the heap.
+pub type HeapLedgerAllowances = AllowanceTable<HeapAllowancesData<ApprovalKey, Account, Tokens>>;
+pub type StableLedgerAllowances = AllowanceTable<StableAllowancesData>;
+pub type StableLedgerBalances = Balances<StableBalances>;
+
 #[derive(Serialize, Deserialize, Debug)]
-#[serde(bound = "")]
-pub struct Ledger<Tokens: TokensType> {
+pub struct Ledger {
+    // The balances and approvals of older ledger versions. The ledger moves
+    // them to stable memory while it is in the [LedgerState::Migrating] state.
+    #[serde(default)]
     balances: LedgerBalances<Tokens>,
     #[serde(default)]
-    approvals: AllowanceTable<ApprovalKey, Account, Tokens>,
+    approvals: HeapLedgerAllowances,
+    #[serde(default)]
+    stable_balances: StableLedgerBalances,
+    #[serde(default)]
+    stable_approvals: StableLedgerAllowances,
     blockchain: Blockchain<CdkRuntime, Icrc1ArchiveWasm>,
 
     minting_account: Account,
@@ -305,6 +396,30 @@ pub struct Ledger<Tokens: TokensType> {
     maximum_number_of_accounts: usize,
     #[serde(default = "default_accounts_overflow_trim_quantity")]
     accounts_overflow_trim_quantity: usize,
+
+    #[serde(default = "default_max_transfer_batch_size")]
+    max_transfer_batch_size: u64,
+
+    #[serde(default)]
+    state: LedgerState,
+}
+
+/// The heap fields that the ledger moves to stable memory, in migration order.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LedgerField {
+    Allowances,
+    AllowancesExpirations,
+    AllowancesArrivals,
+    Balances,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LedgerState {
+    /// The ledger moves the specified field from the heap to stable memory
+    /// and rejects requests that read or modify balances or approvals.
+    Migrating(LedgerField),
+    #[default]
+    Ready,
 }
 
 fn default_maximum_number_of_accounts() -> usize {
@@ -336,11 +451,15 @@ fn default_max_memo_length() -> u16 {
     DEFAULT_MAX_MEMO_LENGTH
 }
 
+fn default_max_transfer_batch_size() -> u64 {
+    DEFAULT_MAX_TRANSFER_BATCH_SIZE
+}
+
 fn default_decimals() -> u8 {
     ic_ledger_core::tokens::DECIMAL_PLACES as u8
 }
 
-impl<Tokens: TokensType> Ledger<Tokens> {
+impl Ledger {
     pub fn from_init_args(
         sink: impl Sink + Clone,
         InitArgs {
@@ -357,6 +476,7 @@ impl<Tokens: TokensType> Ledger<Tokens> {
             feature_flags,
             maximum_number_of_accounts,
             accounts_overflow_trim_quantity,
+            max_transfer_batch_size,
         }: InitArgs,
         now: TimeStamp,
     ) -> Self {
@@ -369,6 +489,8 @@ impl<Tokens: TokensType> Ledger<Tokens> {
         let mut ledger = Self {
             balances: LedgerBalances::default(),
             approvals: Default::default(),
+            stable_balances: StableLedgerBalances::default(),
+            stable_approvals: Default::default(),
             blockchain: Blockchain::new_with_archive(archive_options),
             transactions_by_hash: BTreeMap::new(),
             transactions_by_height: VecDeque::new(),
@@ -397,6 +519,9 @@ impl<Tokens: TokensType> Ledger<Tokens> {
                 .unwrap_or_else(|| ACCOUNTS_OVERFLOW_TRIM_QUANTITY.try_into().unwrap())
                 .try_into()
                 .unwrap(),
+            max_transfer_batch_size: max_transfer_batch_size
+                .unwrap_or(DEFAULT_MAX_TRANSFER_BATCH_SIZE),
+            state: LedgerState::Ready,
         };
 
         for (account, balance) in initial_balances.into_iter() {
@@ -434,26 +559,26 @@ impl From<ApprovalKey> for (Account, Account) {
     }
 }
 
-impl<Tokens: TokensType> LedgerContext for Ledger<Tokens> {
+impl LedgerContext for Ledger {
     type AccountId = Account;
-    type Approvals = AllowanceTable<ApprovalKey, Account, Tokens>;
-    type BalancesStore = BTreeMap<Self::AccountId, Tokens>;
+    type Approvals = StableLedgerAllowances;
+    type BalancesStore = StableBalances;
     type Tokens = Tokens;
 
     fn balances(&self) -> &Balances<Self::BalancesStore> {
-        &self.balances
+        &self.stable_balances
     }
 
     fn balances_mut(&mut self) -> &mut Balances<Self::BalancesStore> {
-        &mut self.balances
+        &mut self.stable_balances
     }
 
     fn approvals(&self) -> &Self::Approvals {
-        &self.approvals
+        &self.stable_approvals
     }
 
     fn approvals_mut(&mut self) -> &mut Self::Approvals {
-        &mut self.approvals
+        &mut self.stable_approvals
     }
 
     fn fee_collector(&self) -> Option<&FeeCollector<Self::AccountId>> {
@@ -461,7 +586,7 @@ impl<Tokens: TokensType> LedgerContext for Ledger<Tokens> {
     }
 }
 
-impl<Tokens: TokensType> LedgerData for Ledger<Tokens> {
+impl LedgerData for Ledger {
     type Runtime = CdkRuntime;
     type ArchiveWasm = Icrc1ArchiveWasm;
     type Transaction = Transaction<Tokens>;
@@ -526,7 +651,7 @@ impl<Tokens: TokensType> LedgerData for Ledger<Tokens> {
     }
 }
 
-impl<Tokens: TokensType> Ledger<Tokens> {
+impl Ledger {
     pub fn minting_account(&self) -> &Account {
         &self.minting_account
     }
@@ -539,6 +664,10 @@ impl<Tokens: TokensType> Ledger<Tokens> {
         self.max_memo_length
     }
 
+    pub fn max_transfer_batch_size(&self) -> u64 {
+        self.max_transfer_batch_size
+    }
+
     pub fn decimals(&self) -> u8 {
         self.decimals
     }
@@ -617,6 +746,9 @@ impl<Tokens: TokensType> Ledger<Tokens> {
             self.accounts_overflow_trim_quantity =
                 accounts_overflow_trim_quantity.try_into().unwrap();
         }
+        if let Some(max_transfer_batch_size) = args.max_transfer_batch_size {
+            self.max_transfer_batch_size = max_transfer_batch_size;
+        }
     }
 
     /// Returns the root hash of the certified ledger state.
@@ -626,15 +758,28 @@ impl<Tokens: TokensType> Ledger<Tokens> {
         self.construct_hash_tree().digest().0
     }
 
+    /// Constructs the certified hash tree of the ledger state, as required by
+    /// the ICRC-3 standard: the `last_block_index` label holds the LEB128-encoded
+    /// index of the last block and the `last_block_hash` label holds its hash.
+    /// The tree also certifies the hash under the legacy `tip_hash` label.
     pub fn construct_hash_tree(&self) -> MixedHashTree {
         match self.blockchain().last_hash {
             Some(hash) => {
                 let last_block_index = self.blockchain().chain_length().checked_sub(1).unwrap();
+                let mut last_block_index_encoded = Vec::with_capacity(MAX_U64_ENCODING_BYTES);
+                leb128::write::unsigned(&mut last_block_index_encoded, last_block_index)
+                    .expect("bug: failed to encode the last block index");
                 MixedHashTree::Fork(Box::new((
-                    MixedHashTree::Labeled(
-                        Label::from("last_block_index"),
-                        Box::new(MixedHashTree::Leaf(last_block_index.to_be_bytes().to_vec())),
-                    ),
+                    MixedHashTree::Fork(Box::new((
+                        MixedHashTree::Labeled(
+                            Label::from("last_block_hash"),
+                            Box::new(MixedHashTree::Leaf(hash.as_slice().to_vec())),
+                        ),
+                        MixedHashTree::Labeled(
+                            Label::from("last_block_index"),
+                            Box::new(MixedHashTree::Leaf(last_block_index_encoded)),
+                        ),
+                    ))),
                     MixedHashTree::Labeled(
                         Label::from("tip_hash"),
                         Box::new(MixedHashTree::Leaf(hash.as_slice().to_vec())),
@@ -715,4 +860,416 @@ impl<Tokens: TokensType> Ledger<Tokens> {
             archived_blocks,
         }
     }
+
+    /// Returns the blocks in the specified ranges as ICRC-3 generic blocks,
+    /// together with callbacks for the ranges stored in archives.
+    pub fn icrc3_get_blocks(&self, args: Vec<GetBlocksRequest>) -> GetBlocksResult {
+        let mut blocks = vec![];
+        let mut archived_ranges: BTreeMap<Principal, Vec<GetBlocksRequest>> = BTreeMap::new();
+        for arg in args {
+            // No block can have an index that does not fit into u64.
+            let start = match arg.start.0.to_u64() {
+                Some(start) => start,
+                None => continue,
+            };
+            let length = arg.length.0.to_u64().unwrap_or(u64::MAX);
+            let length = usize::try_from(length).unwrap_or(usize::MAX);
+            let locations = block_locations(self, start, length);
+
+            let max_local_blocks = MAX_TRANSACTIONS_PER_REQUEST.saturating_sub(blocks.len());
+            let local_blocks_range = range_utils::take(&locations.local_blocks, max_local_blocks);
+            blocks.extend(
+                self.blockchain
+                    .block_slice(local_blocks_range.clone())
+                    .iter()
+                    .zip(local_blocks_range)
+                    .map(|(block, id)| BlockWithId {
+                        id: Nat::from(id),
+                        block: encoded_block_to_generic_block(block).into(),
+                    }),
+            );
+
+            for (canister_id, slice) in locations.archived_blocks {
+                archived_ranges
+                    .entry(canister_id.get().0)
+                    .or_default()
+                    .push(GetBlocksRequest {
+                        start: Nat::from(slice.start),
+                        length: Nat::from(range_utils::range_len(&slice)),
+                    });
+            }
+        }
+
+        GetBlocksResult {
+            log_length: Nat::from(self.blockchain.chain_length()),
+            blocks,
+            archived_blocks: archived_ranges
+                .into_iter()
+                .map(|(canister_id, args)| ArchivedBlocks {
+                    args,
+                    callback: ICRC3QueryArchiveFn::new(canister_id, "icrc3_get_blocks"),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Ledger {
+    /// Returns up to `max_results` unexpired allowances of the account,
+    /// ordered by their spenders and starting after `prev_spender`.
+    pub fn get_allowances(
+        &self,
+        from: Account,
+        prev_spender: Option<Account>,
+        max_results: u64,
+        now: TimeStamp,
+    ) -> Allowances {
+        self.stable_approvals
+            .get_allowances(&from, prev_spender.as_ref(), now, max_results as usize)
+            .into_iter()
+            .map(|(spender, allowance)| Allowance103 {
+                from_account: from,
+                to_spender: spender,
+                allowance: allowance.amount.into(),
+                expires_at: allowance.expires_at.map(|t| t.as_nanos_since_unix_epoch()),
+            })
+            .collect()
+    }
+}
+
+impl Ledger {
+    /// Returns true if the balances and approvals of the ledger are in stable
+    /// memory, i.e. the ledger can serve all requests.
+    pub fn is_ready(&self) -> bool {
+        self.state == LedgerState::Ready
+    }
+
+    pub fn state(&self) -> LedgerState {
+        self.state
+    }
+
+    /// Starts moving the balances and approvals of a ledger whose state was
+    /// decoded from a version that kept them on the heap to stable memory.
+    pub fn start_migration(&mut self) {
+        self.stable_balances.token_pool = self.balances.token_pool;
+        self.state = LedgerState::Migrating(LedgerField::Allowances);
+    }
+
+    /// Moves the next heap entry to stable memory. Returns false once the
+    /// migration is complete.
+    ///
+    /// Every call does a bounded amount of work, so that the caller can spread
+    /// the migration of large ledgers over multiple messages.
+    pub fn migrate_one_value(&mut self) -> bool {
+        match self.state {
+            LedgerState::Ready => {}
+            LedgerState::Migrating(LedgerField::Allowances) => {
+                match self.approvals.allowances_data_mut().pop_first_allowance() {
+                    Some(((account, spender), allowance)) => self
+                        .stable_approvals
+                        .allowances_data_mut()
+                        .set_allowance(&account, &spender, allowance),
+                    None => self.state = LedgerState::Migrating(LedgerField::AllowancesExpirations),
+                }
+            }
+            LedgerState::Migrating(LedgerField::AllowancesExpirations) => {
+                match self.approvals.allowances_data_mut().pop_first_expiry() {
+                    Some((timestamp, (account, spender))) => self
+                        .stable_approvals
+                        .allowances_data_mut()
+                        .insert_expiry(timestamp, &account, &spender),
+                    None => self.state = LedgerState::Migrating(LedgerField::AllowancesArrivals),
+                }
+            }
+            LedgerState::Migrating(LedgerField::AllowancesArrivals) => {
+                match self.approvals.allowances_data_mut().pop_first_arrival() {
+                    Some((timestamp, (account, spender))) => self
+                        .stable_approvals
+                        .allowances_data_mut()
+                        .insert_arrival(timestamp, &account, &spender),
+                    None => self.state = LedgerState::Migrating(LedgerField::Balances),
+                }
+            }
+            LedgerState::Migrating(LedgerField::Balances) => {
+                match self.balances.store.pop_first() {
+                    Some((account, balance)) => {
+                        self.stable_balances.store.insert(&account, balance);
+                    }
+                    None => self.state = LedgerState::Ready,
+                }
+            }
+        }
+        !self.is_ready()
+    }
+}
+
+fn account_key(account: &Account) -> AccountKey {
+    (
+        Blob::try_from(account.owner.as_slice()).expect("bug: principals have at most 29 bytes"),
+        *account.effective_subaccount(),
+    )
+}
+
+fn account_from_key((owner, subaccount): AccountKey) -> Account {
+    Account {
+        owner: Principal::from_slice(owner.as_slice()),
+        subaccount: (subaccount != [0; 32]).then_some(subaccount),
+    }
+}
+
+fn approval_key(account: &Account, spender: &Account) -> ApprovalStorageKey {
+    (account_key(account), account_key(spender))
+}
+
+fn approval_from_key((account, spender): ApprovalStorageKey) -> (Account, Account) {
+    (account_from_key(account), account_from_key(spender))
+}
+
+/// Keeps the account balances in stable memory.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct StableBalances {}
+
+impl StableBalances {
+    fn insert(&mut self, account: &Account, balance: Tokens) {
+        BALANCES.with(|cell| cell.borrow_mut().insert(account_key(account), balance));
+    }
+}
+
+impl BalancesStore for StableBalances {
+    type AccountId = Account;
+    type Tokens = Tokens;
+
+    fn get_balance(&self, k: &Account) -> Option<Tokens> {
+        BALANCES.with(|cell| cell.borrow().get(&account_key(k)))
+    }
+
+    fn update<F, E>(&mut self, k: Account, mut f: F) -> Result<Tokens, E>
+    where
+        F: FnMut(Option<&Tokens>) -> Result<Tokens, E>,
+    {
+        let key = account_key(&k);
+        BALANCES.with(|cell| {
+            let mut balances = cell.borrow_mut();
+            let new_v = f(balances.get(&key).as_ref())?;
+            if new_v.is_zero() {
+                balances.remove(&key);
+            } else {
+                balances.insert(key, new_v);
+            }
+            Ok(new_v)
+        })
+    }
+}
+
+impl InspectableBalancesStore for StableBalances {
+    fn iter(&self) -> Box<dyn Iterator<Item = (Account, Tokens)> + '_> {
+        // The iterator cannot borrow the map, so every step looks up the
+        // entry that follows the last returned key.
+        let mut last_key: Option<AccountKey> = None;
+        Box::new(std::iter::from_fn(move || {
+            BALANCES.with(|cell| {
+                let lower_bound = match last_key.take() {
+                    Some(key) => RangeBound::Excluded(key),
+                    None => RangeBound::Unbounded,
+                };
+                let (key, balance) = cell
+                    .borrow()
+                    .range((lower_bound, RangeBound::Unbounded))
+                    .next()?;
+                last_key = Some(key.clone());
+                Some((account_from_key(key), balance))
+            })
+        }))
+    }
+
+    fn len(&self) -> usize {
+        BALANCES.with(|cell| cell.borrow().len() as usize)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+struct StorableAllowance(Allowance<Tokens>);
+
+impl Storable for StorableAllowance {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut buf = vec![];
+        ciborium::ser::into_writer(self, &mut buf).expect("failed to encode an allowance");
+        Cow::Owned(buf)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        ciborium::de::from_reader(&bytes[..]).unwrap_or_else(|e| {
+            panic!(
+                "failed to decode allowance bytes {}: {e}",
+                hex::encode(bytes)
+            )
+        })
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// Keeps the approvals in stable memory.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct StableAllowancesData {}
+
+fn first_entry(
+    queue: &'static std::thread::LocalKey<
+        RefCell<StableBTreeMap<(u64, ApprovalStorageKey), (), VM>>,
+    >,
+) -> Option<(TimeStamp, (Account, Account))> {
+    queue.with(|cell| {
+        cell.borrow().iter().next().map(|((timestamp, key), ())| {
+            (
+                TimeStamp::from_nanos_since_unix_epoch(timestamp),
+                approval_from_key(key),
+            )
+        })
+    })
+}
+
+fn pop_first_entry(
+    queue: &'static std::thread::LocalKey<
+        RefCell<StableBTreeMap<(u64, ApprovalStorageKey), (), VM>>,
+    >,
+) -> Option<(TimeStamp, (Account, Account))> {
+    queue.with(|cell| {
+        let mut queue = cell.borrow_mut();
+        let (key, ()) = queue.iter().next()?;
+        queue.remove(&key);
+        Some((
+            TimeStamp::from_nanos_since_unix_epoch(key.0),
+            approval_from_key(key.1),
+        ))
+    })
+}
+
+impl AllowancesData for StableAllowancesData {
+    type AccountId = Account;
+    type Tokens = Tokens;
+
+    fn get_allowance(&self, account: &Account, spender: &Account) -> Option<Allowance<Tokens>> {
+        ALLOWANCES.with(|cell| {
+            cell.borrow()
+                .get(&approval_key(account, spender))
+                .map(|allowance| allowance.0)
+        })
+    }
+
+    fn set_allowance(
+        &mut self,
+        account: &Account,
+        spender: &Account,
+        allowance: Allowance<Tokens>,
+    ) {
+        ALLOWANCES.with(|cell| {
+            cell.borrow_mut()
+                .insert(approval_key(account, spender), StorableAllowance(allowance))
+        });
+    }
+
+    fn remove_allowance(&mut self, account: &Account, spender: &Account) {
+        ALLOWANCES.with(|cell| cell.borrow_mut().remove(&approval_key(account, spender)));
+    }
+
+    fn insert_expiry(&mut self, timestamp: TimeStamp, account: &Account, spender: &Account) {
+        let key = (
+            timestamp.as_nanos_since_unix_epoch(),
+            approval_key(account, spender),
+        );
+        ALLOWANCES_EXPIRATIONS.with(|cell| cell.borrow_mut().insert(key, ()));
+    }
+
+    fn remove_expiry(&mut self, timestamp: TimeStamp, account: &Account, spender: &Account) {
+        let key = (
+            timestamp.as_nanos_since_unix_epoch(),
+            approval_key(account, spender),
+        );
+        ALLOWANCES_EXPIRATIONS.with(|cell| cell.borrow_mut().remove(&key));
+    }
+
+    fn insert_arrival(&mut self, timestamp: TimeStamp, account: &Account, spender: &Account) {
+        let key = (
+            timestamp.as_nanos_since_unix_epoch(),
+            approval_key(account, spender),
+        );
+        ALLOWANCES_ARRIVALS.with(|cell| cell.borrow_mut().insert(key, ()));
+    }
+
+    fn remove_arrival(&mut self, timestamp: TimeStamp, account: &Account, spender: &Account) {
+        let key = (
+            timestamp.as_nanos_since_unix_epoch(),
+            approval_key(account, spender),
+        );
+        ALLOWANCES_ARRIVALS.with(|cell| cell.borrow_mut().remove(&key));
+    }
+
+    fn first_expiry(&self) -> Option<(TimeStamp, (Account, Account))> {
+        first_entry(&ALLOWANCES_EXPIRATIONS)
+    }
+
+    fn oldest_arrivals(&self, n: usize) -> Vec<(Account, Account)> {
+        ALLOWANCES_ARRIVALS.with(|cell| {
+            cell.borrow()
+                .iter()
+                .take(n)
+                .map(|((_, key), ())| approval_from_key(key))
+                .collect()
+        })
+    }
+
+    fn spender_allowances(
+        &self,
+        account: &Account,
+        prev_spender: Option<&Account>,
+        n: usize,
+    ) -> Vec<(Account, Allowance<Tokens>)> {
+        let from_key = account_key(account);
+        let lower_bound = match prev_spender {
+            Some(prev_spender) => {
+                RangeBound::Excluded((from_key.clone(), account_key(prev_spender)))
+            }
+            None => {
+                let min_account_key = (Blob::try_from(&[][..]).unwrap(), [0; 32]);
+                RangeBound::Included((from_key.clone(), min_account_key))
+            }
+        };
+        ALLOWANCES.with(|cell| {
+            cell.borrow()
+                .range((lower_bound, RangeBound::Unbounded))
+                .take_while(|((key_account, _), _)| key_account == &from_key)
+                .take(n)
+                .map(|((_, spender), allowance)| (account_from_key(spender), allowance.0))
+                .collect()
+        })
+    }
+
+    fn pop_first_allowance(&mut self) -> Option<((Account, Account), Allowance<Tokens>)> {
+        ALLOWANCES.with(|cell| {
+            let mut allowances = cell.borrow_mut();
+            let (key, allowance) = allowances.iter().next()?;
+            allowances.remove(&key);
+            Some((approval_from_key(key), allowance.0))
+        })
+    }
+
+    fn pop_first_expiry(&mut self) -> Option<(TimeStamp, (Account, Account))> {
+        pop_first_entry(&ALLOWANCES_EXPIRATIONS)
+    }
+
+    fn pop_first_arrival(&mut self) -> Option<(TimeStamp, (Account, Account))> {
+        pop_first_entry(&ALLOWANCES_ARRIVALS)
+    }
+
+    fn len_allowances(&self) -> usize {
+        ALLOWANCES.with(|cell| cell.borrow().len() as usize)
+    }
+
+    fn len_expirations(&self) -> usize {
+        ALLOWANCES_EXPIRATIONS.with(|cell| cell.borrow().len() as usize)
+    }
+
+    fn len_arrivals(&self) -> usize {
+        ALLOWANCES_ARRIVALS.with(|cell| cell.borrow().len() as usize)
+    }
 }
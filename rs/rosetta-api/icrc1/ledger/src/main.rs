@@ -1,28 +1,47 @@
 use candid::candid_method;
 use candid::types::number::Nat;
-use ic_canister_log::{declare_log_buffer, export};
+use ic_canister_log::{declare_log_buffer, export, log};
 use ic_canisters_http_types::{HttpRequest, HttpResponse, HttpResponseBuilder};
-use ic_cdk::api::stable::{StableReader, StableWriter};
+use ic_cdk::api::stable::StableReader;
 use ic_cdk_macros::{init, post_upgrade, pre_upgrade, query, update};
 use ic_icrc1::{
     endpoints::{convert_transfer_error, StandardRecord},
     Operation, Transaction,
 };
-use ic_icrc1_ledger::{Ledger, LedgerArgument};
+use ic_icrc1_ledger::{Ledger, LedgerArgument, Tokens, MAX_TAKE_ALLOWANCES, UPGRADES_MEMORY};
 use ic_ledger_canister_core::ledger::{
-    apply_transaction, archive_blocks, LedgerAccess, LedgerContext, LedgerData,
+    apply_transaction, apply_transactions, archive_blocks, LedgerAccess, LedgerContext, LedgerData,
     TransferError as CoreTransferError,
 };
 use ic_ledger_core::tokens::Zero;
-use ic_ledger_core::{approvals::Approvals, timestamp::TimeStamp};
+use ic_ledger_core::{
+    approvals::Approvals, balances::InspectableBalancesStore, timestamp::TimeStamp,
+};
+use ic_stable_structures::reader::{BufferedReader, Reader};
+use ic_stable_structures::writer::{BufferedWriter, Writer};
 use icrc_ledger_types::icrc1::transfer::Memo;
+use icrc_ledger_types::icrc103::get_allowances::{
+    Allowances, GetAllowancesArgs, GetAllowancesError,
+};
 use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
+use icrc_ledger_types::icrc21::{
+    consent_message::{build_consent_info, LedgerInfo},
+    errors::Icrc21Error,
+    requests::ConsentMessageRequest,
+    responses::ConsentInfo,
+};
 use icrc_ledger_types::icrc3::blocks::DataCertificate;
+use icrc_ledger_types::icrc4::transfer_batch::{
+    TransferBatchArgs, TransferBatchError, TransferBatchResults,
+};
 use icrc_ledger_types::{
     icrc::generic_metadata_value::MetadataValue as Value,
     icrc3::{
-        archive::ArchiveInfo,
-        blocks::{GetBlocksRequest, GetBlocksResponse},
+        archive::{ArchiveInfo, GetArchivesArgs, GetArchivesResult, ICRC3ArchiveInfo},
+        blocks::{
+            GetBlocksRequest, GetBlocksResponse, GetBlocksResult, ICRC3DataCertificate,
+            SupportedBlockType,
+        },
         transactions::{GetTransactionsRequest, GetTransactionsResponse},
     },
 };
@@ -37,24 +56,39 @@ use icrc_ledger_types::{
 use num_traits::{bounds::Bounded, ToPrimitive};
 use serde_bytes::ByteBuf;
 use std::cell::RefCell;
+use std::time::Duration;
 
 const MAX_MESSAGE_SIZE: u64 = 1024 * 1024;
 
-#[cfg(not(feature = "u256-tokens"))]
-type Tokens = ic_icrc1_tokens_u64::U64;
+/// The size of the buffer used to read and write the ledger state in the
+/// upgrades memory.
+const BUFFER_SIZE: usize = 10 * 1024 * 1024;
+
+/// The number of instructions after which post_upgrade stops moving the heap
+/// balances and approvals to stable memory and leaves the rest to timers.
+#[cfg(not(feature = "low-upgrade-instruction-limits"))]
+const MAX_INSTRUCTIONS_FOR_MIGRATION_IN_UPGRADE: u64 = 50_000_000_000;
+#[cfg(feature = "low-upgrade-instruction-limits")]
+const MAX_INSTRUCTIONS_FOR_MIGRATION_IN_UPGRADE: u64 = 13_000_000;
 
-#[cfg(feature = "u256-tokens")]
-type Tokens = ic_icrc1_tokens_u256::U256;
+/// The number of instructions a single timer call spends on the migration.
+#[cfg(not(feature = "low-upgrade-instruction-limits"))]
+const MAX_INSTRUCTIONS_PER_MIGRATION_TIMER_CALL: u64 = 1_900_000_000;
+#[cfg(feature = "low-upgrade-instruction-limits")]
+const MAX_INSTRUCTIONS_PER_MIGRATION_TIMER_CALL: u64 = 10_000_000;
+
+/// The magic bytes of the stable memory layout of the memory manager.
+const MEMORY_MANAGER_MAGIC: &[u8; 3] = b"MGR";
 
 thread_local! {
-    static LEDGER: RefCell<Option<Ledger<Tokens>>> = RefCell::new(None);
+    static LEDGER: RefCell<Option<Ledger>> = RefCell::new(None);
 }
 
 declare_log_buffer!(name = LOG, capacity = 1000);
 
 struct Access;
 impl LedgerAccess for Access {
-    type Ledger = Ledger<Tokens>;
+    type Ledger = Ledger;
 
     fn with_ledger<R>(f: impl FnOnce(&Self::Ledger) -> R) -> R {
         LEDGER.with(|cell| {
@@ -82,7 +116,7 @@ fn init(args: LedgerArgument) {
         LedgerArgument::Init(init_args) => {
             let now = TimeStamp::from_nanos_since_unix_epoch(ic_cdk::api::time());
             LEDGER.with(|cell| {
-                *cell.borrow_mut() = Some(Ledger::<Tokens>::from_init_args(&LOG, init_args, now))
+                *cell.borrow_mut() = Some(Ledger::from_init_args(&LOG, init_args, now))
             })
         }
         LedgerArgument::Upgrade(_) => {
@@ -94,18 +128,49 @@ fn init(args: LedgerArgument) {
 
 #[pre_upgrade]
 fn pre_upgrade() {
-    Access::with_ledger(|ledger| ciborium::ser::into_writer(ledger, StableWriter::default()))
-        .expect("failed to encode ledger state");
+    UPGRADES_MEMORY.with(|cell| {
+        let mut memory = cell.borrow_mut();
+        let writer = Writer::new(&mut *memory, 0);
+        let mut buffered_writer = BufferedWriter::new(BUFFER_SIZE, writer);
+        Access::with_ledger(|ledger| ciborium::ser::into_writer(ledger, &mut buffered_writer))
+            .expect("failed to encode ledger state");
+    });
+}
+
+/// Returns true if the stable memory holds the layout of the memory manager
+/// rather than the ledger state written by versions before stable structures.
+fn stable_memory_is_managed() -> bool {
+    if ic_cdk::api::stable::stable64_size() == 0 {
+        return false;
+    }
+    let mut magic = [0u8; 3];
+    ic_cdk::api::stable::stable64_read(0, &mut magic);
+    &magic == MEMORY_MANAGER_MAGIC
 }
 
 #[post_upgrade]
 fn post_upgrade(args: Option<LedgerArgument>) {
-    LEDGER.with(|cell| {
-        *cell.borrow_mut() = Some(
-            ciborium::de::from_reader(StableReader::default())
-                .expect("failed to decode ledger state"),
+    // The legacy state must be decoded before the memory manager initializes
+    // and overwrites the beginning of the stable memory.
+    let migrate_to_stable_memory = !stable_memory_is_managed();
+    let ledger: Ledger = if migrate_to_stable_memory {
+        ciborium::de::from_reader(StableReader::default()).expect("failed to decode ledger state")
+    } else {
+        UPGRADES_MEMORY.with(|cell| {
+            let memory = cell.borrow();
+            let reader = Reader::new(&*memory, 0);
+            let mut buffered_reader = BufferedReader::new(BUFFER_SIZE, reader);
+            ciborium::de::from_reader(&mut buffered_reader).expect("failed to decode ledger state")
+        })
+    };
+    LEDGER.with(|cell| *cell.borrow_mut() = Some(ledger));
+    if migrate_to_stable_memory {
+        log!(
+            &LOG,
+            "[ledger] starting the migration of the ledger state to stable memory"
         );
-    });
+        Access::with_ledger_mut(Ledger::start_migration);
+    }
 
     if let Some(args) = args {
         match args {
@@ -117,6 +182,47 @@ fn post_upgrade(args: Option<LedgerArgument>) {
             }
         }
     }
+
+    // NB. the format of the certified tree can change between versions.
+    ic_cdk::api::set_certified_data(&Access::with_ledger(Ledger::root_hash));
+
+    if !Access::with_ledger(Ledger::is_ready) {
+        migrate_next_part(MAX_INSTRUCTIONS_FOR_MIGRATION_IN_UPGRADE);
+    }
+}
+
+/// Moves heap balances and approvals to stable memory until the instruction
+/// counter reaches the limit, and schedules the next part if any remains.
+fn migrate_next_part(instruction_limit: u64) {
+    let migrated_all = Access::with_ledger_mut(|ledger| {
+        while ledger.migrate_one_value() {
+            if ic_cdk::api::instruction_counter() >= instruction_limit {
+                return false;
+            }
+        }
+        true
+    });
+    if migrated_all {
+        log!(
+            &LOG,
+            "[ledger] completed the migration of the ledger state to stable memory"
+        );
+    } else {
+        log!(
+            &LOG,
+            "[ledger] migration to stable memory in progress, state: {:?}",
+            Access::with_ledger(Ledger::state)
+        );
+        ic_cdk_timers::set_timer(Duration::ZERO, || {
+            migrate_next_part(MAX_INSTRUCTIONS_PER_MIGRATION_TIMER_CALL)
+        });
+    }
+}
+
+fn panic_if_not_ready() {
+    if !Access::with_ledger(Ledger::is_ready) {
+        ic_cdk::trap("The Ledger is not ready: it is migrating its state to stable memory.");
+    }
 }
 
 fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> std::io::Result<()> {
@@ -257,6 +363,12 @@ fn icrc1_metadata() -> Vec<(String, Value)> {
     Access::with_ledger(|ledger| ledger.metadata())
 }
 
+#[query]
+#[candid_method(query)]
+fn is_ledger_ready() -> bool {
+    Access::with_ledger(Ledger::is_ready)
+}
+
 #[query]
 #[candid_method(query)]
 fn icrc1_minting_account() -> Option<Account> {
@@ -266,6 +378,7 @@ fn icrc1_minting_account() -> Option<Account> {
 #[query(name = "icrc1_balance_of")]
 #[candid_method(query, rename = "icrc1_balance_of")]
 fn icrc1_balance_of(account: Account) -> Nat {
+    panic_if_not_ready();
     Access::with_ledger(|ledger| ledger.balances().account_balance(&account).into())
 }
 
@@ -284,95 +397,19 @@ async fn execute_transfer(
     memo: Option<Memo>,
     created_at_time: Option<u64>,
 ) -> Result<Nat, CoreTransferError<Tokens>> {
+    panic_if_not_ready();
     let block_idx = Access::with_ledger_mut(|ledger| {
         let now = TimeStamp::from_nanos_since_unix_epoch(ic_cdk::api::time());
-        let created_at_time = created_at_time.map(TimeStamp::from_nanos_since_unix_epoch);
-
-        match memo.as_ref() {
-            Some(memo) if memo.0.len() > ledger.max_memo_length() as usize => {
-                ic_cdk::trap(&format!(
-                    "the memo field size of {} bytes is above the allowed limit of {} bytes",
-                    memo.0.len(),
-                    ledger.max_memo_length()
-                ))
-            }
-            _ => {}
-        };
-        let amount = match Tokens::try_from(amount.clone()) {
-            Ok(n) => n,
-            Err(_) => {
-                // No one can have so many tokens
-                let balance_tokens = ledger.balances().account_balance(&from_account);
-                let balance = Nat::from(balance_tokens);
-                assert!(balance < amount);
-                return Err(CoreTransferError::InsufficientFunds {
-                    balance: balance_tokens,
-                });
-            }
-        };
-
-        let (tx, effective_fee) = if &to == ledger.minting_account() {
-            let expected_fee = Tokens::zero();
-            if fee.is_some() && fee.as_ref() != Some(&expected_fee.into()) {
-                return Err(CoreTransferError::BadFee { expected_fee });
-            }
-
-            let balance = ledger.balances().account_balance(&from_account);
-            let min_burn_amount = ledger.transfer_fee().min(balance);
-            if amount < min_burn_amount {
-                return Err(CoreTransferError::BadBurn { min_burn_amount });
-            }
-            if Tokens::is_zero(&amount) {
-                return Err(CoreTransferError::BadBurn {
-                    min_burn_amount: ledger.transfer_fee(),
-                });
-            }
-
-            (
-                Transaction {
-                    operation: Operation::Burn {
-                        from: from_account,
-                        spender,
-                        amount,
-                    },
-                    created_at_time: created_at_time.map(|t| t.as_nanos_since_unix_epoch()),
-                    memo,
-                },
-                Tokens::zero(),
-            )
-        } else if &from_account == ledger.minting_account() {
-            if spender.is_some() {
-                ic_cdk::trap("the minter account cannot delegate mints")
-            }
-            let expected_fee = Tokens::zero();
-            if fee.is_some() && fee.as_ref() != Some(&expected_fee.into()) {
-                return Err(CoreTransferError::BadFee { expected_fee });
-            }
-            (
-                Transaction::mint(to, amount, created_at_time, memo),
-                Tokens::zero(),
-            )
-        } else {
-            let expected_fee_tokens = ledger.transfer_fee();
-            if fee.is_some() && fee.as_ref() != Some(&expected_fee_tokens.into()) {
-                return Err(CoreTransferError::BadFee {
-                    expected_fee: expected_fee_tokens,
-                });
-            }
-            (
-                Transaction::transfer(
-                    from_account,
-                    to,
-                    spender,
-                    amount,
-                    fee.map(|_| expected_fee_tokens),
-                    created_at_time,
-                    memo,
-                ),
-                expected_fee_tokens,
-            )
-        };
-
+        let (tx, effective_fee) = prepare_transfer(
+            ledger,
+            from_account,
+            to,
+            spender,
+            fee,
+            amount,
+            memo,
+            created_at_time,
+        )?;
         let (block_idx, _) = apply_transaction(ledger, tx, now, effective_fee)?;
         Ok(block_idx)
     })?;
@@ -385,6 +422,105 @@ async fn execute_transfer(
     Ok(Nat::from(block_idx))
 }
 
+/// Validates the arguments of a transfer and returns the transaction to apply
+/// along with its effective fee.
+#[allow(clippy::too_many_arguments)]
+fn prepare_transfer(
+    ledger: &Ledger,
+    from_account: Account,
+    to: Account,
+    spender: Option<Account>,
+    fee: Option<Nat>,
+    amount: Nat,
+    memo: Option<Memo>,
+    created_at_time: Option<u64>,
+) -> Result<(Transaction<Tokens>, Tokens), CoreTransferError<Tokens>> {
+    let created_at_time = created_at_time.map(TimeStamp::from_nanos_since_unix_epoch);
+
+    match memo.as_ref() {
+        Some(memo) if memo.0.len() > ledger.max_memo_length() as usize => ic_cdk::trap(&format!(
+            "the memo field size of {} bytes is above the allowed limit of {} bytes",
+            memo.0.len(),
+            ledger.max_memo_length()
+        )),
+        _ => {}
+    };
+    let amount = match Tokens::try_from(amount.clone()) {
+        Ok(n) => n,
+        Err(_) => {
+            // No one can have so many tokens
+            let balance_tokens = ledger.balances().account_balance(&from_account);
+            let balance = Nat::from(balance_tokens);
+            assert!(balance < amount);
+            return Err(CoreTransferError::InsufficientFunds {
+                balance: balance_tokens,
+            });
+        }
+    };
+
+    if &to == ledger.minting_account() {
+        let expected_fee = Tokens::zero();
+        if fee.is_some() && fee.as_ref() != Some(&expected_fee.into()) {
+            return Err(CoreTransferError::BadFee { expected_fee });
+        }
+
+        let balance = ledger.balances().account_balance(&from_account);
+        let min_burn_amount = ledger.transfer_fee().min(balance);
+        if amount < min_burn_amount {
+            return Err(CoreTransferError::BadBurn { min_burn_amount });
+        }
+        if Tokens::is_zero(&amount) {
+            return Err(CoreTransferError::BadBurn {
+                min_burn_amount: ledger.transfer_fee(),
+            });
+        }
+
+        Ok((
+            Transaction {
+                operation: Operation::Burn {
+                    from: from_account,
+                    spender,
+                    amount,
+                },
+                created_at_time: created_at_time.map(|t| t.as_nanos_since_unix_epoch()),
+                memo,
+            },
+            Tokens::zero(),
+        ))
+    } else if &from_account == ledger.minting_account() {
+        if spender.is_some() {
+            ic_cdk::trap("the minter account cannot delegate mints")
+        }
+        let expected_fee = Tokens::zero();
+        if fee.is_some() && fee.as_ref() != Some(&expected_fee.into()) {
+            return Err(CoreTransferError::BadFee { expected_fee });
+        }
+        Ok((
+            Transaction::mint(to, amount, created_at_time, memo),
+            Tokens::zero(),
+        ))
+    } else {
+        let expected_fee_tokens = ledger.transfer_fee();
+        if fee.is_some() && fee.as_ref() != Some(&expected_fee_tokens.into()) {
+            return Err(CoreTransferError::BadFee {
+                expected_fee: expected_fee_tokens,
+            });
+        }
+        Ok((
+            Transaction::transfer(
+                from_account,
+                to,
+                spender,
+                amount,
+                fee.map(|_| expected_fee_tokens),
+                created_at_time,
+                memo,
+            ),
+            expected_fee_tokens,
+        ))
+    }
+}
+
 #[update]
 #[candid_method(update)]
 async fn icrc1_transfer(arg: TransferArg) -> Result<Nat, TransferError> {
@@ -439,6 +575,85 @@ async fn icrc2_transfer_from(arg: TransferFromArgs) -> Result<Nat, TransferFromE
     })
 }
 
+#[update]
+#[candid_method(update)]
+async fn icrc4_transfer_batch(
+    args: TransferBatchArgs,
+) -> Result<TransferBatchResults, TransferBatchError> {
+    panic_if_not_ready();
+    let limit = Access::with_ledger(|ledger| ledger.max_transfer_batch_size());
+    if args.len() as u64 > limit {
+        return Err(TransferBatchError::TooManyRequests {
+            limit: Nat::from(limit),
+        });
+    }
+    let caller = ic_cdk::api::caller();
+    let max_memo_length = Access::with_ledger(|ledger| ledger.max_memo_length()) as usize;
+    // Entries with an oversized memo are rejected individually, trapping would
+    // fail the entries of the batch that are valid.
+    let mut results: Vec<Option<Result<Nat, TransferError>>> = Vec::with_capacity(args.len());
+    let mut valid_args = Vec::with_capacity(args.len());
+    for arg in args {
+        match arg.memo.as_ref() {
+            Some(memo) if memo.0.len() > max_memo_length => {
+                results.push(Some(Err(TransferError::GenericError {
+                    error_code: Nat::from(0u64),
+                    message: format!(
+                        "the memo field size of {} bytes is above the allowed limit of {} bytes",
+                        memo.0.len(),
+                        max_memo_length
+                    ),
+                })));
+            }
+            _ => {
+                results.push(None);
+                valid_args.push(arg);
+            }
+        }
+    }
+    let applied = Access::with_ledger_mut(|ledger| {
+        let now = TimeStamp::from_nanos_since_unix_epoch(ic_cdk::api::time());
+        apply_transactions(ledger, valid_args, now, |ledger, arg| {
+            let from_account = Account {
+                owner: caller,
+                subaccount: arg.from_subaccount,
+            };
+            prepare_transfer(
+                ledger,
+                from_account,
+                arg.to,
+                None,
+                arg.fee,
+                arg.amount,
+                arg.memo,
+                arg.created_at_time,
+            )
+        })
+    });
+
+    // NB. we need to set the certified data before the first async call to make sure that the
+    // blockchain state agrees with the certificate while archiving is in progress.
+    ic_cdk::api::set_certified_data(&Access::with_ledger(Ledger::root_hash));
+
+    archive_blocks::<Access>(&LOG, MAX_MESSAGE_SIZE).await;
+    let mut applied = applied.into_iter().map(|result| {
+        result.map(Nat::from).map_err(|err| {
+            let err: TransferError =
+                convert_transfer_error(err)
+                    .try_into()
+                    .unwrap_or_else(|message| TransferError::GenericError {
+                        error_code: Nat::from(0u64),
+                        message,
+                    });
+            err
+        })
+    });
+    Ok(results
+        .into_iter()
+        .map(|result| result.unwrap_or_else(|| applied.next().unwrap()))
+        .collect())
+}
+
 #[query]
 fn archives() -> Vec<ArchiveInfo> {
     Access::with_ledger(|ledger| {
@@ -475,10 +690,36 @@ fn supported_standards() -> Vec<StandardRecord> {
             name: "ICRC-2".to_string(),
             url: "https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-2".to_string(),
         },
+        StandardRecord {
+            name: "ICRC-3".to_string(),
+            url: "https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-3".to_string(),
+        },
+        StandardRecord {
+            name: "ICRC-4".to_string(),
+            url: "https://github.com/dfinity/ICRC/blob/main/ICRCs/ICRC-4/ICRC-4.md".to_string(),
+        },
+        StandardRecord {
+            name: "ICRC-10".to_string(),
+            url: "https://github.com/dfinity/ICRC/blob/main/ICRCs/ICRC-10/ICRC-10.md".to_string(),
+        },
+        StandardRecord {
+            name: "ICRC-103".to_string(),
+            url: "https://github.com/dfinity/ICRC/blob/main/ICRCs/ICRC-103/ICRC-103.md".to_string(),
+        },
+        StandardRecord {
+            name: "ICRC-21".to_string(),
+            url: "https://github.com/dfinity/wg-identity-authentication/blob/main/topics/ICRC-21/icrc_21_consent_msg.md".to_string(),
+        },
     ];
     standards
 }
 
+#[query]
+#[candid_method(query)]
+fn icrc10_supported_standards() -> Vec<StandardRecord> {
+    supported_standards()
+}
+
 #[query]
 #[candid_method(query)]
 fn get_transactions(req: GetTransactionsRequest) -> GetTransactionsResponse {
@@ -509,9 +750,77 @@ fn get_data_certificate() -> DataCertificate {
     }
 }
 
+#[query]
+#[candid_method(query)]
+fn icrc3_get_archives(args: GetArchivesArgs) -> GetArchivesResult {
+    Access::with_ledger(|ledger| {
+        ledger
+            .blockchain()
+            .archive
+            .read()
+            .unwrap()
+            .as_ref()
+            .iter()
+            .flat_map(|archive| archive.index().into_iter())
+            .skip_while(|(_, canister_id)| match args.from {
+                Some(from) => canister_id.get().0 != from,
+                None => false,
+            })
+            .skip(args.from.is_some() as usize)
+            .map(|((start, end), canister_id)| ICRC3ArchiveInfo {
+                canister_id: canister_id.get().0,
+                start: Nat::from(start),
+                end: Nat::from(end),
+            })
+            .collect()
+    })
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc3_get_blocks(args: Vec<GetBlocksRequest>) -> GetBlocksResult {
+    Access::with_ledger(|ledger| ledger.icrc3_get_blocks(args))
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc3_get_tip_certificate() -> Option<ICRC3DataCertificate> {
+    let certificate = ByteBuf::from(ic_cdk::api::data_certificate()?);
+    let hash_tree = Access::with_ledger(|ledger| ledger.construct_hash_tree());
+    let mut tree_buf = vec![];
+    ciborium::ser::into_writer(&hash_tree, &mut tree_buf).unwrap();
+    Some(ICRC3DataCertificate {
+        certificate,
+        hash_tree: ByteBuf::from(tree_buf),
+    })
+}
+
+/// The ICRC-1 and ICRC-2 block types. The blocks of this ledger have no
+/// `btype` field: their type follows from the `tx.op` field of the encoding.
+#[query]
+#[candid_method(query)]
+fn icrc3_supported_block_types() -> Vec<SupportedBlockType> {
+    let icrc1_url = "https://github.com/dfinity/ICRC-1/blob/main/standards/ICRC-1/README.md";
+    let icrc2_url = "https://github.com/dfinity/ICRC-1/blob/main/standards/ICRC-2/README.md";
+    [
+        ("1burn", icrc1_url),
+        ("1mint", icrc1_url),
+        ("1xfer", icrc1_url),
+        ("2approve", icrc2_url),
+        ("2xfer", icrc2_url),
+    ]
+    .into_iter()
+    .map(|(block_type, url)| SupportedBlockType {
+        block_type: block_type.to_string(),
+        url: url.to_string(),
+    })
+    .collect()
+}
+
 #[update]
 #[candid_method(update)]
 async fn icrc2_approve(arg: ApproveArgs) -> Result<Nat, ApproveError> {
+    panic_if_not_ready();
     let block_idx = Access::with_ledger_mut(|ledger| {
         let now = TimeStamp::from_nanos_since_unix_epoch(ic_cdk::api::time());
 
@@ -590,6 +899,7 @@ async fn icrc2_approve(arg: ApproveArgs) -> Result<Nat, ApproveError> {
 #[query]
 #[candid_method(query)]
 fn icrc2_allowance(arg: AllowanceArgs) -> Allowance {
+    panic_if_not_ready();
     Access::with_ledger(|ledger| {
         let now = TimeStamp::from_nanos_since_unix_epoch(ic_cdk::api::time());
         let allowance = ledger
@@ -602,6 +912,41 @@ fn icrc2_allowance(arg: AllowanceArgs) -> Allowance {
     })
 }
 
+#[query]
+#[candid_method(query)]
+fn icrc103_get_allowances(arg: GetAllowancesArgs) -> Result<Allowances, GetAllowancesError> {
+    panic_if_not_ready();
+    let from_account = arg.from_account.unwrap_or_else(|| Account {
+        owner: ic_cdk::api::caller(),
+        subaccount: None,
+    });
+    let max_results = match arg.take {
+        Some(take) => take
+            .0
+            .to_u64()
+            .ok_or(GetAllowancesError::InvalidTakeValue)?
+            .min(MAX_TAKE_ALLOWANCES),
+        None => MAX_TAKE_ALLOWANCES,
+    };
+    let now = TimeStamp::from_nanos_since_unix_epoch(ic_cdk::api::time());
+    Ok(Access::with_ledger(|ledger| {
+        ledger.get_allowances(from_account, arg.prev_spender, max_results, now)
+    }))
+}
+
+#[update]
+#[candid_method(update)]
+fn icrc21_canister_call_consent_message(
+    consent_msg_request: ConsentMessageRequest,
+) -> Result<ConsentInfo, Icrc21Error> {
+    let ledger_info = Access::with_ledger(|ledger| LedgerInfo {
+        token_symbol: ledger.token_symbol().to_string(),
+        decimals: ledger.decimals(),
+        transfer_fee: ledger.transfer_fee().into(),
+    });
+    build_consent_info(consent_msg_request, ic_cdk::api::caller(), &ledger_info)
+}
+
 candid::export_service!();
 
 #[query]
@@ -1,4 +1,4 @@
-use crate::{InitArgs, Ledger};
+use crate::{InitArgs, Ledger, LedgerField, LedgerState, Tokens};
 use ic_base_types::PrincipalId;
 use ic_canister_log::Sink;
 use ic_icrc1::{Operation, Transaction};
@@ -6,7 +6,6 @@ use ic_ledger_canister_core::archive::ArchiveOptions;
 use ic_ledger_canister_core::ledger::{LedgerContext, LedgerTransaction, TxApplyError};
 use ic_ledger_core::approvals::{Allowance, Approvals};
 use ic_ledger_core::timestamp::TimeStamp;
-use ic_ledger_core::Tokens;
 use icrc_ledger_types::icrc::generic_metadata_value::MetadataValue as Value;
 use icrc_ledger_types::icrc1::account::Account;
 
@@ -33,7 +32,7 @@ fn test_account_id(n: u64) -> Account {
 }
 
 fn tokens(n: u64) -> Tokens {
-    Tokens::from_e8s(n)
+    Tokens::try_from(candid::Nat::from(n)).unwrap()
 }
 
 fn ts(n: u64) -> TimeStamp {
@@ -69,6 +68,7 @@ fn default_init_args() -> InitArgs {
         feature_flags: None,
         maximum_number_of_accounts: None,
         accounts_overflow_trim_quantity: None,
+        max_transfer_batch_size: None,
     }
 }
 
@@ -497,7 +497,7 @@ fn test_burn_smoke() {
 
     ctx.balances_mut().mint(&from, tokens(200_000)).unwrap();
 
-    assert_eq!(ctx.balances().total_supply().get_e8s(), 200_000);
+    assert_eq!(ctx.balances().total_supply(), tokens(200_000));
 
     let tr = Transaction {
         operation: Operation::Burn {
@@ -526,7 +526,7 @@ fn test_approval_burn_from() {
     ctx.balances_mut().mint(&from, tokens(200_000)).unwrap();
     let fee = tokens(10_000);
 
-    assert_eq!(ctx.balances().total_supply().get_e8s(), 200_000);
+    assert_eq!(ctx.balances().total_supply(), tokens(200_000));
 
     let tr = Transaction {
         operation: Operation::Burn {
@@ -544,7 +544,7 @@ fn test_approval_burn_from() {
         }
     );
 
-    assert_eq!(ctx.balances().total_supply().get_e8s(), 200_000);
+    assert_eq!(ctx.balances().total_supply(), tokens(200_000));
 
     let tr = Transaction {
         operation: Operation::Approve {
@@ -576,7 +576,7 @@ fn test_approval_burn_from() {
 
     assert_eq!(ctx.balances().account_balance(&spender), Tokens::ZERO);
     assert_eq!(ctx.balances().account_balance(&from), tokens(90_000));
-    assert_eq!(ctx.balances().total_supply().get_e8s(), 90_000);
+    assert_eq!(ctx.balances().total_supply(), tokens(90_000));
 
     assert_eq!(
         ctx.approvals().allowance(&from, &spender, now),
@@ -613,5 +613,132 @@ fn test_approval_burn_from() {
     );
     assert_eq!(ctx.balances().account_balance(&from), tokens(90_000));
     assert_eq!(ctx.balances().account_balance(&spender), Tokens::ZERO);
-    assert_eq!(ctx.balances().total_supply().get_e8s(), 90_000);
+    assert_eq!(ctx.balances().total_supply(), tokens(90_000));
+}
+
+#[test]
+fn test_icrc3_get_blocks_and_tip_hash_tree() {
+    use candid::Nat;
+    use ic_crypto_tree_hash::{LookupStatus, MixedHashTree};
+    use icrc_ledger_types::icrc3::blocks::GetBlocksRequest;
+
+    let now = ts(12345678);
+    let ledger = Ledger::from_init_args(
+        DummyLogger,
+        InitArgs {
+            initial_balances: (1..=3)
+                .map(|n| (test_account_id(n), Nat::from(n * 1_000_000)))
+                .collect(),
+            ..default_init_args()
+        },
+        now,
+    );
+
+    let result = ledger.icrc3_get_blocks(vec![
+        GetBlocksRequest {
+            start: Nat::from(2_u64),
+            length: Nat::from(5_u64),
+        },
+        GetBlocksRequest {
+            start: Nat::from(0_u64),
+            length: Nat::from(1_u64),
+        },
+        GetBlocksRequest {
+            start: Nat::from(10_u64),
+            length: Nat::from(1_u64),
+        },
+    ]);
+    assert_eq!(result.log_length, Nat::from(3_u64));
+    assert!(result.archived_blocks.is_empty());
+    let ids: Vec<Nat> = result.blocks.iter().map(|b| b.id.clone()).collect();
+    assert_eq!(ids, vec![Nat::from(2_u64), Nat::from(0_u64)]);
+
+    let hash_tree = ledger.construct_hash_tree();
+    let lookup_leaf = |label: &str| match hash_tree.lookup(&[label]) {
+        LookupStatus::Found(MixedHashTree::Leaf(bytes)) => bytes.clone(),
+        other => panic!("unexpected lookup result for {}: {:?}", label, other),
+    };
+    let last_block_hash = result.blocks[0].block.clone().hash().to_vec();
+    assert_eq!(lookup_leaf("last_block_hash"), last_block_hash);
+    assert_eq!(lookup_leaf("tip_hash"), last_block_hash);
+    // LEB128 encoding of the last block index 2.
+    assert_eq!(lookup_leaf("last_block_index"), vec![2]);
+}
+
+#[test]
+fn test_migrate_balances_and_approvals_to_stable_memory() {
+    use ic_ledger_core::approvals::AllowancesData;
+
+    let now = ts(1_000_000);
+    let mut ledger = Ledger::from_init_args(DummyLogger, default_init_args(), now);
+
+    // Populate the heap fields as a ledger decoded from an older version would.
+    for n in 1..=5 {
+        ledger
+            .balances
+            .mint(&test_account_id(n), tokens(n * 1_000))
+            .unwrap();
+    }
+    let owner = test_account_id(1);
+    let spender = test_account_id(2);
+    let other_spender = test_account_id(3);
+    ledger
+        .approvals
+        .approve(
+            &owner,
+            &spender,
+            tokens(100),
+            Some(ts(2_000_000)),
+            now,
+            None,
+        )
+        .unwrap();
+    ledger
+        .approvals
+        .approve(&owner, &other_spender, tokens(200), None, now, None)
+        .unwrap();
+    let token_pool = ledger.balances.token_pool;
+
+    ledger.start_migration();
+    assert!(!ledger.is_ready());
+    assert!(ledger.migrate_one_value());
+    assert_eq!(
+        ledger.state(),
+        LedgerState::Migrating(LedgerField::Allowances)
+    );
+    while ledger.migrate_one_value() {}
+    assert_eq!(ledger.state(), LedgerState::Ready);
+
+    assert!(ledger.balances.store.is_empty());
+    assert_eq!(ledger.approvals.allowances_data().len_allowances(), 0);
+    assert_eq!(ledger.approvals.allowances_data().len_expirations(), 0);
+    assert_eq!(ledger.approvals.allowances_data().len_arrivals(), 0);
+
+    assert_eq!(ledger.balances().token_pool, token_pool);
+    assert_eq!(ledger.balances().total_supply(), tokens(15_000));
+    for n in 1..=5 {
+        assert_eq!(
+            ledger.balances().account_balance(&test_account_id(n)),
+            tokens(n * 1_000)
+        );
+    }
+    assert_eq!(
+        ledger.approvals().allowance(&owner, &spender, now),
+        Allowance {
+            amount: tokens(100),
+            expires_at: Some(ts(2_000_000)),
+            arrived_at: now,
+        }
+    );
+    assert_eq!(
+        ledger
+            .approvals()
+            .allowance(&owner, &other_spender, now)
+            .amount,
+        tokens(200)
+    );
+    let allowances_data = ledger.approvals().allowances_data();
+    assert_eq!(allowances_data.len_allowances(), 2);
+    assert_eq!(allowances_data.len_expirations(), 1);
+    assert_eq!(allowances_data.len_arrivals(), 2);
 }
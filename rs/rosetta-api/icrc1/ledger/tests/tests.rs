@@ -1,11 +1,13 @@
 use candid::{CandidType, Decode, Encode, Nat};
 use ic_base_types::{CanisterId, PrincipalId};
-use ic_icrc1_ledger::{ChangeFeeCollector, FeatureFlags, InitArgs, LedgerArgument};
+use ic_icrc1_ledger::{
+    ChangeFeeCollector, FeatureFlags, InitArgs, InitArgsBuilder, LedgerArgument, UpgradeArgs,
+};
 use ic_icrc1_ledger_sm_tests::{
-    get_allowance, send_approval, send_transfer_from, ARCHIVE_TRIGGER_THRESHOLD, BLOB_META_KEY,
-    BLOB_META_VALUE, DECIMAL_PLACES, FEE, INT_META_KEY, INT_META_VALUE, MINTER, NAT_META_KEY,
-    NAT_META_VALUE, NUM_BLOCKS_TO_ARCHIVE, TEXT_META_KEY, TEXT_META_VALUE, TOKEN_NAME,
-    TOKEN_SYMBOL,
+    default_approve_args, get_allowance, send_approval, send_transfer_from, transfer_batch,
+    ARCHIVE_TRIGGER_THRESHOLD, BLOB_META_KEY, BLOB_META_VALUE, DECIMAL_PLACES, FEE, INT_META_KEY,
+    INT_META_VALUE, MINTER, NAT_META_KEY, NAT_META_VALUE, NUM_BLOCKS_TO_ARCHIVE, TEXT_META_KEY,
+    TEXT_META_VALUE, TOKEN_NAME, TOKEN_SYMBOL,
 };
 use ic_ledger_canister_core::archive::ArchiveOptions;
 use ic_ledger_core::block::BlockIndex;
@@ -16,6 +18,7 @@ use icrc_ledger_types::icrc1::transfer::{TransferArg, TransferError};
 use icrc_ledger_types::icrc2::allowance::Allowance;
 use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
 use icrc_ledger_types::icrc2::transfer_from::{TransferFromArgs, TransferFromError};
+use icrc_ledger_types::icrc4::transfer_batch::TransferBatchError;
 use num_traits::ToPrimitive;
 use std::path::PathBuf;
 
@@ -55,6 +58,14 @@ fn ledger_wasm() -> Vec<u8> {
     )
 }
 
+fn ledger_wasm_low_upgrade_instruction_limits() -> Vec<u8> {
+    ic_test_utilities_load_wasm::load_wasm(
+        std::env::var("CARGO_MANIFEST_DIR").unwrap(),
+        "ic-icrc1-ledger",
+        &["low-upgrade-instruction-limits"],
+    )
+}
+
 fn archive_wasm() -> Vec<u8> {
     ic_test_utilities_load_wasm::load_wasm(
         PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap())
@@ -95,6 +106,7 @@ fn encode_init_args(args: ic_icrc1_ledger_sm_tests::InitArgs) -> LedgerArgument
         feature_flags: args.feature_flags,
         maximum_number_of_accounts: args.maximum_number_of_accounts,
         accounts_overflow_trim_quantity: args.accounts_overflow_trim_quantity,
+        max_transfer_batch_size: None,
     })
 }
 
@@ -142,6 +154,80 @@ fn test_get_blocks() {
     ic_icrc1_ledger_sm_tests::test_get_blocks(ledger_wasm(), encode_init_args);
 }
 
+#[test]
+fn test_icrc3_get_blocks() {
+    ic_icrc1_ledger_sm_tests::test_icrc3_get_blocks(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_icrc21_standard() {
+    ic_icrc1_ledger_sm_tests::test_icrc21_standard(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_icrc103_get_allowances() {
+    ic_icrc1_ledger_sm_tests::test_icrc103_get_allowances(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_icrc4_transfer_batch() {
+    ic_icrc1_ledger_sm_tests::test_icrc4_transfer_batch(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_icrc4_transfer_batch_size_limit() {
+    let env = StateMachine::new();
+    let init_args = InitArgsBuilder::with_symbol_and_name(TOKEN_SYMBOL, TOKEN_NAME)
+        .with_minting_account(MINTER)
+        .with_transfer_fee(FEE)
+        .with_initial_balance(account(1), 1_000_000_u64)
+        .with_max_transfer_batch_size(2)
+        .build();
+    let ledger_id = env
+        .install_canister(
+            ledger_wasm(),
+            Encode!(&LedgerArgument::Init(init_args)).unwrap(),
+            None,
+        )
+        .unwrap();
+    let batch = |n: u64| -> Vec<TransferArg> {
+        (0..n)
+            .map(|i| TransferArg {
+                from_subaccount: None,
+                to: account(2),
+                fee: None,
+                created_at_time: None,
+                memo: None,
+                amount: Nat::from(1_000 + i),
+            })
+            .collect()
+    };
+
+    assert_eq!(
+        transfer_batch(&env, ledger_id, account(1).owner, &batch(3)),
+        Err(TransferBatchError::TooManyRequests {
+            limit: Nat::from(2_u64)
+        })
+    );
+    assert_eq!(balance_of(&env, ledger_id, account(2)), 0);
+    let results = transfer_batch(&env, ledger_id, account(1).owner, &batch(2))
+        .expect("failed to transfer a batch");
+    assert!(results.iter().all(Result::is_ok));
+
+    // The limit can be changed on upgrade.
+    let upgrade_args = Encode!(&LedgerArgument::Upgrade(Some(UpgradeArgs {
+        max_transfer_batch_size: Some(3),
+        ..UpgradeArgs::default()
+    })))
+    .unwrap();
+    env.upgrade_canister(ledger_id, ledger_wasm(), upgrade_args)
+        .expect("Unable to upgrade the ledger canister");
+    let results = transfer_batch(&env, ledger_id, account(1).owner, &batch(3))
+        .expect("failed to transfer a batch");
+    assert_eq!(results.len(), 3);
+    assert!(results.iter().all(Result::is_ok));
+}
+
 // Generate random blocks and check that their CBOR encoding complies with the CDDL spec.
 #[test]
 fn block_encoding_agrees_with_the_schema() {
@@ -424,6 +510,263 @@ fn test_upgrade_from_first_version() {
     transfer(&env, ledger_id, account(1), account(3), 1_000_000);
 }
 
+// Validate the migration of balances and approvals from the heap to stable memory
+
+const NUM_MIGRATION_ACCOUNTS: u64 = 5_000;
+const NUM_MIGRATION_APPROVALS: u64 = 200;
+const MAX_MIGRATION_TICKS: usize = 1_000;
+
+/// The balances and approvals of a ledger before the migration to stable memory.
+struct MigrationExpectations {
+    balances: Vec<(Account, u64)>,
+    allowances: Vec<(Account, Account, Allowance)>,
+}
+
+/// Installs the deployed ledger version, which keeps balances and approvals
+/// on the heap, with many balances and approvals, and returns them.
+fn install_deployed_ledger_with_many_accounts() -> (StateMachine, CanisterId, MigrationExpectations)
+{
+    let env = StateMachine::new();
+    let ledger_wasm_deployed_version =
+        std::fs::read(std::env::var("IC_ICRC1_LEDGER_DEPLOYED_VERSION_WASM_PATH").unwrap())
+            .unwrap();
+    let init_args = Encode!(&LegacyLedgerArgument::Init(LegacyInitArgs {
+        minting_account: MINTER,
+        fee_collector_account: None,
+        initial_balances: (1..=NUM_MIGRATION_ACCOUNTS)
+            .map(|i| (account(i), 1_000_000 + i))
+            .collect(),
+        transfer_fee: FEE,
+        token_name: TOKEN_NAME.to_string(),
+        token_symbol: TOKEN_SYMBOL.to_string(),
+        metadata: vec![],
+        archive_options: ArchiveOptions {
+            trigger_threshold: ARCHIVE_TRIGGER_THRESHOLD as usize,
+            num_blocks_to_archive: NUM_BLOCKS_TO_ARCHIVE as usize,
+            node_max_memory_size_bytes: None,
+            max_message_size_bytes: None,
+            controller_id: PrincipalId::new_user_test_id(100),
+            more_controller_ids: None,
+            cycles_for_archive_creation: None,
+            max_transactions_per_response: None,
+        },
+    }))
+    .unwrap();
+    let ledger_id = env
+        .install_canister(ledger_wasm_deployed_version, init_args, None)
+        .unwrap();
+
+    let now = env
+        .time()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64;
+    let mut allowances = vec![];
+    for i in 1..=NUM_MIGRATION_APPROVALS {
+        let from = account(i);
+        let spender = account(NUM_MIGRATION_ACCOUNTS + i);
+        let mut approve_args = default_approve_args(spender, 10_000 * i);
+        // Some approvals expire, so that the migration also moves expirations.
+        if i % 2 == 0 {
+            approve_args.expires_at = Some(now + 24 * 3_600 * 1_000_000_000);
+        }
+        send_approval(&env, ledger_id, from.owner, &approve_args)
+            .expect("Unable to perform icrc2_approve");
+        allowances.push((from, spender, get_allowance(&env, ledger_id, from, spender)));
+    }
+    let balances = (1..=NUM_MIGRATION_ACCOUNTS)
+        .map(|i| (account(i), balance_of(&env, ledger_id, account(i))))
+        .collect();
+    (
+        env,
+        ledger_id,
+        MigrationExpectations {
+            balances,
+            allowances,
+        },
+    )
+}
+
+fn upgrade_to_ledger_with_low_upgrade_instruction_limits(
+    env: &StateMachine,
+    ledger_id: CanisterId,
+) {
+    let upgrade_args = Encode!(&LedgerArgument::Upgrade(None)).unwrap();
+    env.upgrade_canister(
+        ledger_id,
+        ledger_wasm_low_upgrade_instruction_limits(),
+        upgrade_args,
+    )
+    .expect("Unable to upgrade the ledger canister");
+}
+
+fn is_ledger_ready(env: &StateMachine, ledger_id: CanisterId) -> bool {
+    let res = env
+        .query(ledger_id, "is_ledger_ready", Encode!().unwrap())
+        .expect("Unable to perform is_ledger_ready")
+        .bytes();
+    Decode!(&res, bool).unwrap()
+}
+
+/// Executes rounds (and thus the migration timers) until the migration is
+/// complete and returns the number of rounds.
+fn wait_for_migration(env: &StateMachine, ledger_id: CanisterId) -> usize {
+    for ticks in 1..=MAX_MIGRATION_TICKS {
+        env.advance_time(std::time::Duration::from_secs(1));
+        env.tick();
+        if is_ledger_ready(env, ledger_id) {
+            return ticks;
+        }
+    }
+    panic!(
+        "The migration did not complete in {} rounds",
+        MAX_MIGRATION_TICKS
+    );
+}
+
+fn assert_migrated(env: &StateMachine, ledger_id: CanisterId, expected: &MigrationExpectations) {
+    for (account, balance) in &expected.balances {
+        assert_eq!(*balance, balance_of(env, ledger_id, *account));
+    }
+    for (from, spender, allowance) in &expected.allowances {
+        assert_eq!(*allowance, get_allowance(env, ledger_id, *from, *spender));
+    }
+
+    // Check that the migrated state can be used.
+    transfer(env, ledger_id, account(1), account(2), 1_000);
+    assert_eq!(
+        expected.balances[0].1 - 1_000 - FEE,
+        balance_of(env, ledger_id, account(1))
+    );
+    let spender = account(NUM_MIGRATION_ACCOUNTS + 1);
+    send_approval(
+        env,
+        ledger_id,
+        account(1).owner,
+        &default_approve_args(spender, 42),
+    )
+    .expect("Unable to perform icrc2_approve");
+    assert_eq!(
+        get_allowance(env, ledger_id, account(1), spender).allowance,
+        Nat::from(42_u64)
+    );
+}
+
+#[cfg_attr(feature = "u256-tokens", ignore)]
+#[test]
+fn test_upgrade_from_deployed_version_migrates_to_stable_memory() {
+    let (env, ledger_id, expected) = install_deployed_ledger_with_many_accounts();
+
+    upgrade_to_ledger_with_low_upgrade_instruction_limits(&env, ledger_id);
+    assert!(!is_ledger_ready(&env, ledger_id));
+
+    let ticks = wait_for_migration(&env, ledger_id);
+    assert!(ticks > 1, "the migration completed in a single timer call");
+    assert_migrated(&env, ledger_id, &expected);
+
+    // Upgrading a migrated ledger does not restart the migration.
+    upgrade_to_ledger_with_low_upgrade_instruction_limits(&env, ledger_id);
+    assert!(is_ledger_ready(&env, ledger_id));
+    assert_eq!(
+        expected.balances[1].1 + 1_000,
+        balance_of(&env, ledger_id, account(2))
+    );
+}
+
+#[cfg_attr(feature = "u256-tokens", ignore)]
+#[test]
+fn test_upgrade_during_migration_to_stable_memory() {
+    let (env, ledger_id, expected) = install_deployed_ledger_with_many_accounts();
+
+    upgrade_to_ledger_with_low_upgrade_instruction_limits(&env, ledger_id);
+    env.advance_time(std::time::Duration::from_secs(1));
+    env.tick();
+    assert!(!is_ledger_ready(&env, ledger_id));
+
+    // The upgraded ledger resumes the migration where it was interrupted.
+    upgrade_to_ledger_with_low_upgrade_instruction_limits(&env, ledger_id);
+    assert!(!is_ledger_ready(&env, ledger_id));
+
+    wait_for_migration(&env, ledger_id);
+    assert_migrated(&env, ledger_id, &expected);
+}
+
+#[cfg_attr(feature = "u256-tokens", ignore)]
+#[test]
+fn test_endpoints_are_disabled_during_migration_to_stable_memory() {
+    let (env, ledger_id, expected) = install_deployed_ledger_with_many_accounts();
+    upgrade_to_ledger_with_low_upgrade_instruction_limits(&env, ledger_id);
+    assert!(!is_ledger_ready(&env, ledger_id));
+
+    let assert_not_ready = |result: Result<_, ic_state_machine_tests::UserError>| {
+        let err = result.expect_err("the ledger served a request during the migration");
+        assert!(
+            err.description().contains("The Ledger is not ready"),
+            "unexpected error: {}",
+            err
+        );
+    };
+    let spender = account(NUM_MIGRATION_ACCOUNTS + 1);
+    assert_not_ready(env.query(ledger_id, "icrc1_balance_of", Encode!(&account(1)).unwrap()));
+    assert_not_ready(
+        env.execute_ingress_as(
+            account(1).owner.into(),
+            ledger_id,
+            "icrc1_transfer",
+            Encode!(&TransferArg {
+                from_subaccount: None,
+                to: account(2),
+                amount: 1_000_u64.into(),
+                fee: None,
+                created_at_time: None,
+                memo: None,
+            })
+            .unwrap(),
+        ),
+    );
+    assert_not_ready(env.execute_ingress_as(
+        account(1).owner.into(),
+        ledger_id,
+        "icrc2_approve",
+        Encode!(&default_approve_args(spender, 42)).unwrap(),
+    ));
+    assert_not_ready(
+        env.query(
+            ledger_id,
+            "icrc2_allowance",
+            Encode!(&icrc_ledger_types::icrc2::allowance::AllowanceArgs {
+                account: account(1),
+                spender,
+            })
+            .unwrap(),
+        ),
+    );
+    assert_not_ready(
+        env.execute_ingress_as(
+            spender.owner.into(),
+            ledger_id,
+            "icrc2_transfer_from",
+            Encode!(&TransferFromArgs {
+                spender_subaccount: None,
+                from: account(1),
+                to: account(2),
+                amount: 1_000_u64.into(),
+                fee: None,
+                memo: None,
+                created_at_time: None,
+            })
+            .unwrap(),
+        ),
+    );
+
+    // Endpoints that do not depend on balances and approvals keep working.
+    env.query(ledger_id, "icrc1_total_supply", Encode!().unwrap())
+        .expect("Unable to perform icrc1_total_supply");
+
+    wait_for_migration(&env, ledger_id);
+    assert_migrated(&env, ledger_id, &expected);
+}
+
 #[test]
 fn test_icrc2_feature_flag_doesnt_disable_icrc2_endpoints() {
     // Disable ICRC-2 and check the endpoints still work
@@ -452,6 +795,7 @@ fn test_icrc2_feature_flag_doesnt_disable_icrc2_endpoints() {
         feature_flags: Some(FeatureFlags { icrc2: false }),
         maximum_number_of_accounts: None,
         accounts_overflow_trim_quantity: None,
+        max_transfer_batch_size: None,
     }))
     .unwrap();
     let ledger_id = env
@@ -779,6 +1123,7 @@ mod verify_written_blocks {
                 feature_flags: Some(FeatureFlags { icrc2: true }),
                 maximum_number_of_accounts: None,
                 accounts_overflow_trim_quantity: None,
+                max_transfer_batch_size: None,
             });
 
             let args = Encode!(&ledger_arg_init).unwrap();
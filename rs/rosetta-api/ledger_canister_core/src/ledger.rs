@@ -380,6 +380,37 @@ where
     Ok((height, ledger.blockchain().last_hash.unwrap()))
 }
 
+/// Adds a new block for each transaction of a batch to the ledger.
+///
+/// The `make_transaction` callback validates an argument of the batch against
+/// the current ledger state and returns the transaction to apply with its
+/// effective fee. Each entry is applied independently: a failed entry doesn't
+/// revert the entries before it nor prevent the entries after it from being
+/// applied. Deduplication applies within the batch as well, so an entry
+/// identical to an earlier entry of the same batch fails with
+/// [TransferError::TxDuplicate].
+pub fn apply_transactions<L, A, F>(
+    ledger: &mut L,
+    args: impl IntoIterator<Item = A>,
+    now: TimeStamp,
+    mut make_transaction: F,
+) -> Vec<Result<BlockIndex, TransferError<L::Tokens>>>
+where
+    L: LedgerData,
+    L::BalancesStore: InspectableBalancesStore,
+    F: FnMut(&L, A) -> Result<(L::Transaction, L::Tokens), TransferError<L::Tokens>>,
+{
+    let mut results = vec![];
+    for arg in args {
+        let result = make_transaction(ledger, arg).and_then(|(transaction, effective_fee)| {
+            apply_transaction(ledger, transaction, now, effective_fee)
+                .map(|(block_index, _)| block_index)
+        });
+        results.push(result);
+    }
+    results
+}
+
 /// Finds the archive canister that contains the block with the specified height.
 pub fn find_block_in_archive<L: LedgerData>(ledger: &L, block_height: u64) -> Option<CanisterId> {
     let index = ledger
@@ -490,15 +521,15 @@ where
 
     // Accumulate up to `trim_quantity` accounts
     for (account, balance) in iter.by_ref().take(num_accounts) {
-        to_trim.push((balance.clone(), account.clone()));
+        to_trim.push((balance, account));
     }
 
     for (account, balance) in iter {
         // If any account's balance is lower than the maximum in our set,
         // include that account, and remove the current maximum
         if let Some((greatest_balance, _)) = to_trim.peek() {
-            if balance < greatest_balance {
-                to_trim.push((balance.clone(), account.clone()));
+            if &balance < greatest_balance {
+                to_trim.push((balance, account));
                 to_trim.pop();
             }
         }
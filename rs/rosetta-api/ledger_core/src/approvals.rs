@@ -1,9 +1,9 @@
 use crate::timestamp::TimeStamp;
 use crate::tokens::{TokensType, Zero};
 use serde::{Deserialize, Serialize};
-use std::collections::btree_map::Entry;
 use std::collections::{BTreeMap, BTreeSet};
 use std::marker::PhantomData;
+use std::ops::Bound;
 
 #[cfg(test)]
 mod tests;
@@ -82,8 +82,97 @@ impl<Tokens: Zero> Default for Allowance<Tokens> {
     }
 }
 
+/// The storage of the approvals of an [AllowanceTable].
+///
+/// The table keeps three collections: the allowances, the approvals ordered by
+/// their expiration time, and the approvals ordered by the time they arrived.
+#[allow(clippy::len_without_is_empty)]
+pub trait AllowancesData {
+    type AccountId;
+    type Tokens;
+
+    fn get_allowance(
+        &self,
+        account: &Self::AccountId,
+        spender: &Self::AccountId,
+    ) -> Option<Allowance<Self::Tokens>>;
+
+    fn set_allowance(
+        &mut self,
+        account: &Self::AccountId,
+        spender: &Self::AccountId,
+        allowance: Allowance<Self::Tokens>,
+    );
+
+    fn remove_allowance(&mut self, account: &Self::AccountId, spender: &Self::AccountId);
+
+    fn insert_expiry(
+        &mut self,
+        timestamp: TimeStamp,
+        account: &Self::AccountId,
+        spender: &Self::AccountId,
+    );
+
+    fn remove_expiry(
+        &mut self,
+        timestamp: TimeStamp,
+        account: &Self::AccountId,
+        spender: &Self::AccountId,
+    );
+
+    fn insert_arrival(
+        &mut self,
+        timestamp: TimeStamp,
+        account: &Self::AccountId,
+        spender: &Self::AccountId,
+    );
+
+    fn remove_arrival(
+        &mut self,
+        timestamp: TimeStamp,
+        account: &Self::AccountId,
+        spender: &Self::AccountId,
+    );
+
+    /// Returns the approval that expires first.
+    fn first_expiry(&self) -> Option<(TimeStamp, (Self::AccountId, Self::AccountId))>;
+
+    /// Returns up to `n` approvals in the order of their arrival.
+    fn oldest_arrivals(&self, n: usize) -> Vec<(Self::AccountId, Self::AccountId)>;
+
+    /// Returns up to `n` allowances of the account ordered by their spenders,
+    /// starting after `prev_spender` if it is set.
+    fn spender_allowances(
+        &self,
+        account: &Self::AccountId,
+        prev_spender: Option<&Self::AccountId>,
+        n: usize,
+    ) -> Vec<(Self::AccountId, Allowance<Self::Tokens>)>;
+
+    /// Removes and returns the allowance with the smallest key.
+    fn pop_first_allowance(
+        &mut self,
+    ) -> Option<((Self::AccountId, Self::AccountId), Allowance<Self::Tokens>)>;
+
+    /// Removes and returns the approval that expires first.
+    fn pop_first_expiry(&mut self) -> Option<(TimeStamp, (Self::AccountId, Self::AccountId))>;
+
+    /// Removes and returns the approval that arrived first.
+    fn pop_first_arrival(&mut self) -> Option<(TimeStamp, (Self::AccountId, Self::AccountId))>;
+
+    fn len_allowances(&self) -> usize;
+
+    fn len_expirations(&self) -> usize;
+
+    fn len_arrivals(&self) -> usize;
+}
+
+/// Keeps the approvals on the heap, in maps keyed by `K`.
+///
+/// The order of `K` must group the approvals by account, so that the
+/// allowances of an account form a contiguous range of the map.
 #[derive(Serialize, Deserialize, Debug)]
-pub struct AllowanceTable<K, AccountId, Tokens>
+pub struct HeapAllowancesData<K, AccountId, Tokens>
 where
     K: Ord,
 {
@@ -96,37 +185,197 @@ where
     _marker: PhantomData<fn(&AccountId, &AccountId) -> K>,
 }
 
-impl<K: Ord, AccountId, Tokens> Default for AllowanceTable<K, AccountId, Tokens> {
+impl<K: Ord, AccountId, Tokens> Default for HeapAllowancesData<K, AccountId, Tokens> {
     fn default() -> Self {
-        Self::new()
+        Self {
+            allowances: BTreeMap::new(),
+            expiration_queue: BTreeSet::new(),
+            arrival_queue: BTreeSet::new(),
+            _marker: PhantomData,
+        }
     }
 }
 
-impl<K, AccountId, Tokens> AllowanceTable<K, AccountId, Tokens>
+impl<K, AccountId, Tokens> AllowancesData for HeapAllowancesData<K, AccountId, Tokens>
 where
-    K: Ord,
+    K: Ord + for<'a> From<(&'a AccountId, &'a AccountId)> + Clone,
+    K: Into<(AccountId, AccountId)>,
+    AccountId: PartialEq,
+    Tokens: Clone,
 {
-    pub fn new() -> Self {
+    type AccountId = AccountId;
+    type Tokens = Tokens;
+
+    fn get_allowance(&self, account: &AccountId, spender: &AccountId) -> Option<Allowance<Tokens>> {
+        self.allowances.get(&K::from((account, spender))).cloned()
+    }
+
+    fn set_allowance(
+        &mut self,
+        account: &AccountId,
+        spender: &AccountId,
+        allowance: Allowance<Tokens>,
+    ) {
+        self.allowances
+            .insert(K::from((account, spender)), allowance);
+    }
+
+    fn remove_allowance(&mut self, account: &AccountId, spender: &AccountId) {
+        self.allowances.remove(&K::from((account, spender)));
+    }
+
+    fn insert_expiry(&mut self, timestamp: TimeStamp, account: &AccountId, spender: &AccountId) {
+        self.expiration_queue
+            .insert((timestamp, K::from((account, spender))));
+    }
+
+    fn remove_expiry(&mut self, timestamp: TimeStamp, account: &AccountId, spender: &AccountId) {
+        self.expiration_queue
+            .remove(&(timestamp, K::from((account, spender))));
+    }
+
+    fn insert_arrival(&mut self, timestamp: TimeStamp, account: &AccountId, spender: &AccountId) {
+        self.arrival_queue
+            .insert((timestamp, K::from((account, spender))));
+    }
+
+    fn remove_arrival(&mut self, timestamp: TimeStamp, account: &AccountId, spender: &AccountId) {
+        self.arrival_queue
+            .remove(&(timestamp, K::from((account, spender))));
+    }
+
+    fn first_expiry(&self) -> Option<(TimeStamp, (AccountId, AccountId))> {
+        self.expiration_queue
+            .first()
+            .map(|(timestamp, key)| (*timestamp, key.clone().into()))
+    }
+
+    fn oldest_arrivals(&self, n: usize) -> Vec<(AccountId, AccountId)> {
+        self.arrival_queue
+            .iter()
+            .take(n)
+            .map(|(_, key)| key.clone().into())
+            .collect()
+    }
+
+    fn spender_allowances(
+        &self,
+        account: &AccountId,
+        prev_spender: Option<&AccountId>,
+        n: usize,
+    ) -> Vec<(AccountId, Allowance<Tokens>)> {
+        let account_entry = |(key, allowance): (&K, &Allowance<Tokens>)| {
+            let (key_account, spender) = key.clone().into();
+            (&key_account == account).then(|| (spender, allowance.clone()))
+        };
+        let (mut entries, lower_bound) = match prev_spender {
+            Some(prev_spender) => (vec![], Bound::Excluded(K::from((account, prev_spender)))),
+            None => {
+                // There is no smallest spender to start from, so the spenders
+                // below the account itself are collected backwards.
+                let start = K::from((account, account));
+                let mut entries: Vec<_> = self
+                    .allowances
+                    .range(..&start)
+                    .rev()
+                    .map_while(&account_entry)
+                    .collect();
+                entries.reverse();
+                entries.truncate(n);
+                (entries, Bound::Included(start))
+            }
+        };
+        let remaining = n - entries.len();
+        entries.extend(
+            self.allowances
+                .range((lower_bound, Bound::Unbounded))
+                .map_while(&account_entry)
+                .take(remaining),
+        );
+        entries
+    }
+
+    fn pop_first_allowance(&mut self) -> Option<((AccountId, AccountId), Allowance<Tokens>)> {
+        self.allowances
+            .pop_first()
+            .map(|(key, allowance)| (key.into(), allowance))
+    }
+
+    fn pop_first_expiry(&mut self) -> Option<(TimeStamp, (AccountId, AccountId))> {
+        self.expiration_queue
+            .pop_first()
+            .map(|(timestamp, key)| (timestamp, key.into()))
+    }
+
+    fn pop_first_arrival(&mut self) -> Option<(TimeStamp, (AccountId, AccountId))> {
+        self.arrival_queue
+            .pop_first()
+            .map(|(timestamp, key)| (timestamp, key.into()))
+    }
+
+    fn len_allowances(&self) -> usize {
+        self.allowances.len()
+    }
+
+    fn len_expirations(&self) -> usize {
+        self.expiration_queue.len()
+    }
+
+    fn len_arrivals(&self) -> usize {
+        self.arrival_queue.len()
+    }
+}
+
+/// The approvals of a ledger. The table has the same serialized form as its
+/// storage, so that switching the storage keeps the ledger state compatible.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(transparent)]
+pub struct AllowanceTable<AD> {
+    allowances_data: AD,
+}
+
+impl<AD: Default> Default for AllowanceTable<AD> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<AD> AllowanceTable<AD> {
+    pub fn new() -> Self
+    where
+        AD: Default,
+    {
         Self {
-            allowances: BTreeMap::new(),
-            expiration_queue: BTreeSet::new(),
-            arrival_queue: BTreeSet::new(),
-            _marker: PhantomData,
+            allowances_data: AD::default(),
         }
     }
 
+    /// Returns the storage of the table.
+    pub fn allowances_data(&self) -> &AD {
+        &self.allowances_data
+    }
+
+    /// Returns the storage of the table. Changing the storage directly can
+    /// break the invariants of the table, e.g. while migrating the approvals
+    /// from one storage to another.
+    pub fn allowances_data_mut(&mut self) -> &mut AD {
+        &mut self.allowances_data
+    }
+}
+
+impl<AD: AllowancesData> AllowanceTable<AD> {
     fn check_postconditions(&self) {
         debug_assert!(
-            self.expiration_queue.len() <= self.allowances.len(),
+            self.allowances_data.len_expirations() <= self.allowances_data.len_allowances(),
             "expiration queue length ({}) larger than allowances length ({})",
-            self.expiration_queue.len(),
-            self.allowances.len()
+            self.allowances_data.len_expirations(),
+            self.allowances_data.len_allowances()
         );
         debug_assert!(
-            self.arrival_queue.len() == self.allowances.len(),
+            self.allowances_data.len_arrivals() == self.allowances_data.len_allowances(),
             "arrival_queue length ({}) should be equal to allowances length ({})",
-            self.arrival_queue.len(),
-            self.allowances.len()
+            self.allowances_data.len_arrivals(),
+            self.allowances_data.len_allowances()
         );
     }
 
@@ -135,28 +384,60 @@ where
         self.check_postconditions();
         r
     }
+
+    /// Returns up to `n` allowances of the account that did not expire at
+    /// `now`, ordered by their spenders and starting after `prev_spender` if
+    /// it is set.
+    pub fn get_allowances(
+        &self,
+        account: &AD::AccountId,
+        prev_spender: Option<&AD::AccountId>,
+        now: TimeStamp,
+        n: usize,
+    ) -> Vec<(AD::AccountId, Allowance<AD::Tokens>)>
+    where
+        AD::AccountId: Clone,
+    {
+        let mut allowances = vec![];
+        let mut prev_spender = prev_spender.cloned();
+        while allowances.len() < n {
+            let batch = self.allowances_data.spender_allowances(
+                account,
+                prev_spender.as_ref(),
+                n - allowances.len(),
+            );
+            let Some((last_spender, _)) = batch.last() else {
+                break;
+            };
+            prev_spender = Some(last_spender.clone());
+            allowances.extend(
+                batch.into_iter().filter(|(_, allowance)| {
+                    allowance.expires_at.unwrap_or_else(remote_future) > now
+                }),
+            );
+        }
+        allowances
+    }
 }
 
-impl<K, AccountId, Tokens> Approvals for AllowanceTable<K, AccountId, Tokens>
+impl<AD> Approvals for AllowanceTable<AD>
 where
-    K: Ord + for<'a> From<(&'a AccountId, &'a AccountId)> + Clone,
-    K: Into<(AccountId, AccountId)>,
-    AccountId: std::cmp::PartialEq,
-    Tokens: TokensType,
+    AD: AllowancesData,
+    AD::AccountId: std::cmp::PartialEq,
+    AD::Tokens: TokensType,
 {
-    type AccountId = AccountId;
-    type Tokens = Tokens;
+    type AccountId = AD::AccountId;
+    type Tokens = AD::Tokens;
 
     fn allowance(
         &self,
-        account: &AccountId,
-        spender: &AccountId,
+        account: &Self::AccountId,
+        spender: &Self::AccountId,
         now: TimeStamp,
-    ) -> Allowance<Tokens> {
-        let key = K::from((account, spender));
-        match self.allowances.get(&key) {
+    ) -> Allowance<Self::Tokens> {
+        match self.allowances_data.get_allowance(account, spender) {
             Some(allowance) if allowance.expires_at.unwrap_or_else(remote_future) > now => {
-                allowance.clone()
+                allowance
             }
             _ => Allowance::default(),
         }
@@ -164,13 +445,13 @@ where
 
     fn approve(
         &mut self,
-        account: &AccountId,
-        spender: &AccountId,
-        amount: Tokens,
+        account: &Self::AccountId,
+        spender: &Self::AccountId,
+        amount: Self::Tokens,
         expires_at: Option<TimeStamp>,
         now: TimeStamp,
-        expected_allowance: Option<Tokens>,
-    ) -> Result<Tokens, ApproveError<Tokens>> {
+        expected_allowance: Option<Self::Tokens>,
+    ) -> Result<Self::Tokens, ApproveError<Self::Tokens>> {
         self.with_postconditions_check(|table| {
             if account == spender {
                 return Err(ApproveError::SelfApproval);
@@ -180,66 +461,70 @@ where
                 return Err(ApproveError::ExpiredApproval { now });
             }
 
-            let key = K::from((account, spender));
-
-            match table.allowances.entry(key.clone()) {
-                Entry::Vacant(e) => {
-                    if amount == Tokens::zero() {
+            let data = &mut table.allowances_data;
+            match data.get_allowance(account, spender) {
+                None => {
+                    if amount == Self::Tokens::zero() {
                         return Ok(amount);
                     }
                     if let Some(expected_allowance) = expected_allowance {
                         if !expected_allowance.is_zero() {
                             return Err(ApproveError::AllowanceChanged {
-                                current_allowance: Tokens::zero(),
+                                current_allowance: Self::Tokens::zero(),
                             });
                         }
                     }
                     if let Some(expires_at) = expires_at {
-                        table.expiration_queue.insert((expires_at, key.clone()));
+                        data.insert_expiry(expires_at, account, spender);
                     }
-                    table.arrival_queue.insert((now, key));
-                    e.insert(Allowance {
-                        amount: amount.clone(),
-                        expires_at,
-                        arrived_at: now,
-                    });
+                    data.insert_arrival(now, account, spender);
+                    data.set_allowance(
+                        account,
+                        spender,
+                        Allowance {
+                            amount: amount.clone(),
+                            expires_at,
+                            arrived_at: now,
+                        },
+                    );
                     Ok(amount)
                 }
-                Entry::Occupied(mut e) => {
-                    let allowance = e.get_mut();
+                Some(old_allowance) => {
                     if let Some(expected_allowance) = expected_allowance {
-                        if expected_allowance != allowance.amount {
+                        if expected_allowance != old_allowance.amount {
                             return Err(ApproveError::AllowanceChanged {
-                                current_allowance: allowance.amount.clone(),
+                                current_allowance: old_allowance.amount,
                             });
                         }
                     }
-                    table
-                        .arrival_queue
-                        .remove(&(allowance.arrived_at, key.clone()));
-                    if amount == Tokens::zero() {
-                        if let Some(expires_at) = e.get().expires_at {
-                            table.expiration_queue.remove(&(expires_at, key.clone()));
+                    data.remove_arrival(old_allowance.arrived_at, account, spender);
+                    if amount == Self::Tokens::zero() {
+                        if let Some(expires_at) = old_allowance.expires_at {
+                            data.remove_expiry(expires_at, account, spender);
                         }
-                        e.remove();
+                        data.remove_allowance(account, spender);
                         return Ok(amount);
                     }
-                    table.arrival_queue.insert((now, key.clone()));
-                    allowance.amount = amount;
-                    allowance.arrived_at = now;
-                    let old_expiration = std::mem::replace(&mut allowance.expires_at, expires_at);
-
-                    if expires_at != old_expiration {
-                        if let Some(old_expiration) = old_expiration {
-                            table
-                                .expiration_queue
-                                .remove(&(old_expiration, key.clone()));
+                    data.insert_arrival(now, account, spender);
+
+                    if expires_at != old_allowance.expires_at {
+                        if let Some(old_expiration) = old_allowance.expires_at {
+                            data.remove_expiry(old_expiration, account, spender);
                         }
                         if let Some(expires_at) = expires_at {
-                            table.expiration_queue.insert((expires_at, key));
+                            data.insert_expiry(expires_at, account, spender);
                         }
                     }
-                    Ok(e.get().amount.clone())
+                    data.set_allowance(
+                        account,
+                        spender,
+                        Allowance {
+                            amount: amount.clone(),
+                            expires_at,
+                            arrived_at: now,
+                        },
+                    );
+                    Ok(amount)
                 }
             }
         })
@@ -247,23 +532,21 @@ where
 
     fn use_allowance(
         &mut self,
-        account: &AccountId,
-        spender: &AccountId,
-        amount: Tokens,
+        account: &Self::AccountId,
+        spender: &Self::AccountId,
+        amount: Self::Tokens,
         now: TimeStamp,
-    ) -> Result<Tokens, InsufficientAllowance<Tokens>> {
+    ) -> Result<Self::Tokens, InsufficientAllowance<Self::Tokens>> {
         self.with_postconditions_check(|table| {
-            let key = K::from((account, spender));
-
-            match table.allowances.entry(key.clone()) {
-                Entry::Vacant(_) => Err(InsufficientAllowance(Tokens::zero())),
-                Entry::Occupied(mut e) => {
-                    if e.get().expires_at.unwrap_or_else(remote_future) <= now {
-                        Err(InsufficientAllowance(Tokens::zero()))
+            let data = &mut table.allowances_data;
+            match data.get_allowance(account, spender) {
+                None => Err(InsufficientAllowance(Self::Tokens::zero())),
+                Some(mut allowance) => {
+                    if allowance.expires_at.unwrap_or_else(remote_future) <= now {
+                        Err(InsufficientAllowance(Self::Tokens::zero()))
                     } else {
-                        let allowance = e.get_mut();
                         if allowance.amount < amount {
-                            return Err(InsufficientAllowance(allowance.amount.clone()));
+                            return Err(InsufficientAllowance(allowance.amount));
                         }
                         allowance.amount = allowance
                             .amount
@@ -271,11 +554,13 @@ where
                             .expect("Underflow when using allowance");
                         let rest = allowance.amount.clone();
                         if rest.is_zero() {
-                            if let Some(expires_at) = e.get().expires_at {
-                                table.expiration_queue.remove(&(expires_at, key.clone()));
+                            if let Some(expires_at) = allowance.expires_at {
+                                data.remove_expiry(expires_at, account, spender);
                             }
-                            table.arrival_queue.remove(&(e.get().arrived_at, key));
-                            e.remove();
+                            data.remove_arrival(allowance.arrived_at, account, spender);
+                            data.remove_allowance(account, spender);
+                        } else {
+                            data.set_allowance(account, spender, allowance);
                         }
                         Ok(rest)
                     }
@@ -285,44 +570,29 @@ where
     }
 
     fn select_approvals_to_trim(&self, n: usize) -> Vec<(Self::AccountId, Self::AccountId)> {
-        let mut result = vec![];
-        for (_expiration, key) in &self.arrival_queue {
-            if result.len() >= n {
-                break;
-            }
-            result.push(key.clone().into());
-        }
-        result
+        self.allowances_data.oldest_arrivals(n)
     }
 }
 
-impl<K, AccountId, Tokens> PrunableApprovals for AllowanceTable<K, AccountId, Tokens>
+impl<AD> PrunableApprovals for AllowanceTable<AD>
 where
-    K: Ord + Clone,
+    AD: AllowancesData,
 {
     fn prune(&mut self, now: TimeStamp, limit: usize) -> usize {
         self.with_postconditions_check(|table| {
+            let data = &mut table.allowances_data;
             let mut pruned = 0;
             for _ in 0..limit {
-                match table.expiration_queue.first() {
-                    Some((ts, _key)) => {
-                        if *ts > now {
-                            return pruned;
-                        }
-                    }
-                    None => {
-                        return pruned;
-                    }
-                }
-                if let Some((_, key)) = table.expiration_queue.pop_first() {
-                    if let Some(allowance) = table.allowances.get(&key) {
-                        if allowance.expires_at.unwrap_or_else(remote_future) <= now {
-                            table
-                                .arrival_queue
-                                .remove(&(allowance.arrived_at, key.clone()));
-                            table.allowances.remove(&key);
-                            pruned += 1;
-                        }
+                let (timestamp, (account, spender)) = match data.first_expiry() {
+                    Some((timestamp, key)) if timestamp <= now => (timestamp, key),
+                    _ => return pruned,
+                };
+                data.remove_expiry(timestamp, &account, &spender);
+                if let Some(allowance) = data.get_allowance(&account, &spender) {
+                    if allowance.expires_at.unwrap_or_else(remote_future) <= now {
+                        data.remove_arrival(allowance.arrived_at, &account, &spender);
+                        data.remove_allowance(&account, &spender);
+                        pruned += 1;
                     }
                 }
             }
@@ -331,7 +601,7 @@ where
     }
 
     fn len(&self) -> usize {
-        self.allowances.len()
+        self.allowances_data.len_allowances()
     }
 }
 
@@ -14,7 +14,7 @@ fn tokens(n: u64) -> Tokens {
     Tokens::from_e8s(n)
 }
 
-#[derive(PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 struct Account(u64);
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -32,7 +32,7 @@ impl From<Key> for (Account, Account) {
     }
 }
 
-type TestAllowanceTable = AllowanceTable<Key, Account, Tokens>;
+type TestAllowanceTable = AllowanceTable<HeapAllowancesData<Key, Account, Tokens>>;
 
 #[test]
 fn allowance_table_default() {
@@ -560,3 +560,69 @@ fn arrival_table_updated_correctly() {
             .unwrap();
     }
 }
+
+#[test]
+fn allowance_table_get_allowances() {
+    let mut table = TestAllowanceTable::default();
+    let spenders_and_amounts = |allowances: Vec<(Account, Allowance<Tokens>)>| {
+        allowances
+            .into_iter()
+            .map(|(spender, allowance)| (spender.0, allowance.amount.get_e8s()))
+            .collect::<Vec<_>>()
+    };
+
+    for spender in [1, 3, 4, 6] {
+        table
+            .approve(
+                &Account(2),
+                &Account(spender),
+                tokens(spender * 10),
+                None,
+                ts(1),
+                None,
+            )
+            .unwrap();
+    }
+    table
+        .approve(
+            &Account(2),
+            &Account(5),
+            tokens(50),
+            Some(ts(5)),
+            ts(1),
+            None,
+        )
+        .unwrap();
+    table
+        .approve(&Account(1), &Account(2), tokens(5), None, ts(1), None)
+        .unwrap();
+    table
+        .approve(&Account(3), &Account(1), tokens(5), None, ts(1), None)
+        .unwrap();
+
+    assert_eq!(
+        spenders_and_amounts(table.get_allowances(&Account(2), None, ts(2), 10)),
+        vec![(1, 10), (3, 30), (4, 40), (5, 50), (6, 60)]
+    );
+    assert_eq!(
+        spenders_and_amounts(table.get_allowances(&Account(2), None, ts(2), 2)),
+        vec![(1, 10), (3, 30)]
+    );
+    assert_eq!(
+        spenders_and_amounts(table.get_allowances(&Account(2), Some(&Account(3)), ts(2), 2)),
+        vec![(4, 40), (5, 50)]
+    );
+    // The expired allowance is skipped, but the page is still full.
+    assert_eq!(
+        spenders_and_amounts(table.get_allowances(&Account(2), Some(&Account(3)), ts(5), 2)),
+        vec![(4, 40), (6, 60)]
+    );
+    assert_eq!(
+        spenders_and_amounts(table.get_allowances(&Account(2), Some(&Account(6)), ts(2), 2)),
+        vec![]
+    );
+    assert_eq!(
+        spenders_and_amounts(table.get_allowances(&Account(4), None, ts(2), 2)),
+        vec![]
+    );
+}
@@ -3,7 +3,7 @@ use crate::utils::do_copy;
 
 use ic_base_types::{NumBytes, NumSeconds};
 use ic_config::flag_status::FlagStatus;
-use ic_ic00_types::LogVisibility;
+use ic_ic00_types::{CanisterLogRecord, LogVisibility};
 use ic_logger::{error, info, warn, ReplicaLogger};
 use ic_metrics::{buckets::decimal_buckets, MetricsRegistry};
 use ic_protobuf::{
@@ -24,9 +24,9 @@ use ic_replicated_state::{
 };
 use ic_sys::{fs::sync_path, mmap::ScopedMmap};
 use ic_types::{
-    batch::TotalQueryStats, nominal_cycles::NominalCycles, AccumulatedPriority, CanisterId,
-    ComputeAllocation, Cycles, ExecutionRound, Height, MemoryAllocation, NumInstructions,
-    PrincipalId, Time,
+    batch::TotalQueryStats, canister_log::CanisterLog, nominal_cycles::NominalCycles,
+    AccumulatedPriority, CanisterId, ComputeAllocation, Cycles, ExecutionRound, Height,
+    MemoryAllocation, NumInstructions, PrincipalId, Time,
 };
 use ic_utils::thread::parallel_map;
 use ic_wasm_types::{CanisterModule, WasmHash};
@@ -55,6 +55,8 @@ pub const SPLIT_MARKER_FILE: &str = "split_from.pbuf";
 pub const SUBNET_QUEUES_FILE: &str = "subnet_queues.pbuf";
 pub const SYSTEM_METADATA_FILE: &str = "system_metadata.pbuf";
 pub const STATS_FILE: &str = "stats.pbuf";
+pub const SNAPSHOTS_DIR: &str = "snapshots";
+pub const SNAPSHOT_FILE: &str = "snapshot.pbuf";
 
 /// `ReadOnly` is the access policy used for reading checkpoints. We
 /// don't want to ever modify persisted states.
@@ -164,11 +166,13 @@ pub struct CanisterStateBits {
     pub wasm_chunk_store_metadata: WasmChunkStoreMetadata,
     pub total_query_stats: TotalQueryStats,
     pub log_visibility: LogVisibility,
+    pub wasm_memory_limit: Option<NumBytes>,
+    pub canister_log: CanisterLog,
 }
 
 /// This struct contains bits of the `CanisterSnapshot` that are not already
 /// covered somewhere else and are too small to be serialized separately.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq)]
 pub struct CanisterSnapshotBits {
     /// The ID of the canister snapshot.
     pub snapshot_id: SnapshotId,
@@ -184,6 +188,12 @@ pub struct CanisterSnapshotBits {
     pub certified_data: Vec<u8>,
     /// The metadata required for a wasm chunk store.
     pub wasm_chunk_store_metadata: WasmChunkStoreMetadata,
+    /// The size of the stable memory in pages.
+    pub stable_memory_size: NumWasmPages,
+    /// The size of the wasm memory in pages.
+    pub wasm_memory_size: NumWasmPages,
+    /// The exported globals of the canister module.
+    pub exported_globals: Vec<Global>,
 }
 
 #[derive(Clone)]
@@ -423,6 +433,26 @@ impl TipHandler {
         }
         Ok(())
     }
+
+    pub fn filter_tip_snapshots(
+        &mut self,
+        height: Height,
+        ids: &BTreeSet<SnapshotId>,
+    ) -> Result<(), LayoutError> {
+        let tip = self.tip(height)?;
+        let snapshots_on_disk = tip.snapshot_ids()?;
+        for id in snapshots_on_disk {
+            if !ids.contains(&id) {
+                let snapshot_path = tip.snapshot(&id)?.raw_path();
+                std::fs::remove_dir_all(&snapshot_path).map_err(|err| LayoutError::IoError {
+                    path: snapshot_path,
+                    message: "Cannot remove snapshot.".to_string(),
+                    io_err: err,
+                })?;
+            }
+        }
+        Ok(())
+    }
 }
 
 impl StateLayout {
@@ -1236,6 +1266,20 @@ fn parse_canister_id(hex: &str) -> Result<CanisterId, String> {
     ))
 }
 
+fn parse_snapshot_id(hex: &str) -> Result<SnapshotId, String> {
+    let blob = hex::decode(hex).map_err(|err| {
+        format!(
+            "failed to convert directory name {} into a snapshot ID: {}",
+            hex, err
+        )
+    })?;
+    let bytes: [u8; 8] = blob
+        .try_into()
+        .map_err(|_| format!("snapshot directory name {} is not 8 bytes long", hex))?;
+
+    Ok(SnapshotId::new(u64::from_be_bytes(bytes)))
+}
+
 /// Parses the canister ID from a relative path, if it is the path of a canister
 /// state file (e.g. `canister_states/00000000000000010101/queues.pbuf`).
 /// Returns `None` if the path is not under `canister_states`; or if parsing
@@ -1379,6 +1423,23 @@ impl<Permissions: AccessPolicy> CheckpointLayout<Permissions> {
         )
     }
 
+    pub fn snapshot_ids(&self) -> Result<Vec<SnapshotId>, LayoutError> {
+        let snapshots_dir = self.root.join(SNAPSHOTS_DIR);
+        Permissions::check_dir(&snapshots_dir)?;
+        collect_subdirs(snapshots_dir.as_path(), parse_snapshot_id)
+    }
+
+    pub fn snapshot(
+        &self,
+        snapshot_id: &SnapshotId,
+    ) -> Result<SnapshotLayout<Permissions>, LayoutError> {
+        SnapshotLayout::new(
+            self.root
+                .join(SNAPSHOTS_DIR)
+                .join(hex::encode(snapshot_id.get().to_be_bytes())),
+        )
+    }
+
     pub fn height(&self) -> Height {
         self.height
     }
@@ -1421,32 +1482,8 @@ impl<Permissions: AccessPolicy> CanisterLayout<Permissions> {
     }
 
     /// List all overlay files with a particular name ending.
-    ///
-    /// All overlay files have the format {number}{name_end}`, where `name_end` distinguises
-    /// between wasm memory, stable memory etc, and the number imposes an ordering of the
-    /// overlay files, with higher number denoting a higher-priority overlay. The number is
-    /// typically the height when the overlay was written.
     fn overlays_impl(&self, name_end: &str) -> Result<Vec<PathBuf>, LayoutError> {
-        let map_error = |err| LayoutError::IoError {
-            path: self.canister_root.clone(),
-            message: "Failed list overlays".to_string(),
-            io_err: err,
-        };
-
-        let files = std::fs::read_dir(&self.canister_root).map_err(map_error)?;
-        let mut result = Vec::default();
-        for file in files {
-            let path = file.map_err(map_error)?.path();
-            match path.to_str() {
-                Some(p) if p.ends_with(name_end) => {
-                    result.push(path);
-                }
-                _ => (),
-            }
-        }
-        result.sort();
-
-        Ok(result)
+        list_overlays(&self.canister_root, name_end)
     }
 
     /// Base file for wasm memory.
@@ -1498,6 +1535,114 @@ impl<Permissions: AccessPolicy> CanisterLayout<Permissions> {
     }
 }
 
+/// List all overlay files in `root` with a particular name ending.
+///
+/// All overlay files have the format {number}{name_end}`, where `name_end` distinguises
+/// between wasm memory, stable memory etc, and the number imposes an ordering of the
+/// overlay files, with higher number denoting a higher-priority overlay. The number is
+/// typically the height when the overlay was written.
+fn list_overlays(root: &Path, name_end: &str) -> Result<Vec<PathBuf>, LayoutError> {
+    let map_error = |err| LayoutError::IoError {
+        path: root.to_path_buf(),
+        message: "Failed list overlays".to_string(),
+        io_err: err,
+    };
+
+    let files = std::fs::read_dir(root).map_err(map_error)?;
+    let mut result = Vec::default();
+    for file in files {
+        let path = file.map_err(map_error)?.path();
+        match path.to_str() {
+            Some(p) if p.ends_with(name_end) => {
+                result.push(path);
+            }
+            _ => (),
+        }
+    }
+    result.sort();
+
+    Ok(result)
+}
+
+/// Layout of a canister snapshot inside a checkpoint. Mirrors the files of a
+/// `CanisterLayout` that are captured by the snapshot.
+pub struct SnapshotLayout<Permissions: AccessPolicy> {
+    snapshot_root: PathBuf,
+    permissions_tag: PhantomData<Permissions>,
+}
+
+impl<Permissions: AccessPolicy> SnapshotLayout<Permissions> {
+    pub fn new(snapshot_root: PathBuf) -> Result<Self, LayoutError> {
+        Permissions::check_dir(&snapshot_root)?;
+        Ok(Self {
+            snapshot_root,
+            permissions_tag: PhantomData,
+        })
+    }
+
+    pub fn raw_path(&self) -> PathBuf {
+        self.snapshot_root.clone()
+    }
+
+    pub fn snapshot(
+        &self,
+    ) -> ProtoFileWith<pb_canister_snapshot_bits::CanisterSnapshotBits, Permissions> {
+        self.snapshot_root.join(SNAPSHOT_FILE).into()
+    }
+
+    pub fn wasm(&self) -> WasmFile<Permissions> {
+        self.snapshot_root.join("software.wasm").into()
+    }
+
+    /// Base file for wasm memory.
+    pub fn vmemory_0(&self) -> PathBuf {
+        self.snapshot_root.join("vmemory_0.bin")
+    }
+
+    /// List of existing overlay files for wasm memory.
+    pub fn vmemory_0_overlays(&self) -> Result<Vec<PathBuf>, LayoutError> {
+        list_overlays(&self.snapshot_root, "_vmemory_0.overlay")
+    }
+
+    /// Name of a (potentially new) overlay file for the wasm memory written at `height`.
+    pub fn vmemory_0_overlay(&self, height: Height) -> PathBuf {
+        self.snapshot_root
+            .join(format!("{:016x}_vmemory_0.overlay", height.get()))
+    }
+
+    /// Base file for stable memory.
+    pub fn stable_memory_blob(&self) -> PathBuf {
+        self.snapshot_root.join("stable_memory.bin")
+    }
+
+    /// List of existing overlay files for stable memory.
+    pub fn stable_memory_overlays(&self) -> Result<Vec<PathBuf>, LayoutError> {
+        list_overlays(&self.snapshot_root, "_stable_memory.overlay")
+    }
+
+    /// Name of a (potentially new) overlay file for the stable memory written at `height`.
+    pub fn stable_memory_overlay(&self, height: Height) -> PathBuf {
+        self.snapshot_root
+            .join(format!("{:016x}_stable_memory.overlay", height.get()))
+    }
+
+    /// Base file for wasm chunk store.
+    pub fn wasm_chunk_store(&self) -> PathBuf {
+        self.snapshot_root.join("wasm_chunk_store.bin")
+    }
+
+    /// List of existing overlay files for wasm chunk store.
+    pub fn wasm_chunk_store_overlays(&self) -> Result<Vec<PathBuf>, LayoutError> {
+        list_overlays(&self.snapshot_root, "_wasm_chunk_store.overlay")
+    }
+
+    /// Name of a (potentially new) overlay file for the wasm chunk store written at `height`.
+    pub fn wasm_chunk_store_overlay(&self, height: Height) -> PathBuf {
+        self.snapshot_root
+            .join(format!("{:016x}_wasm_chunk_store.overlay", height.get()))
+    }
+}
+
 fn open_for_write(path: &Path) -> Result<std::fs::File, LayoutError> {
     OpenOptions::new()
         .write(true)
@@ -1790,6 +1935,18 @@ impl From<CanisterStateBits> for pb_canister_state_bits::CanisterStateBits {
             wasm_chunk_store_metadata: Some((&item.wasm_chunk_store_metadata).into()),
             total_query_stats: Some((&item.total_query_stats).into()),
             log_visibility: item.log_visibility.into(),
+            wasm_memory_limit: item.wasm_memory_limit.map(|v| v.get()),
+            canister_log_records: item
+                .canister_log
+                .records()
+                .iter()
+                .map(|record| pb_canister_state_bits::CanisterLogRecord {
+                    idx: record.idx,
+                    timestamp_nanos: record.timestamp_nanos,
+                    content: record.content.clone(),
+                })
+                .collect(),
+            next_canister_log_record_idx: item.canister_log.next_idx(),
         }
     }
 }
@@ -1910,6 +2067,19 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
             )
             .unwrap_or_default(),
             log_visibility: LogVisibility::from(value.log_visibility),
+            wasm_memory_limit: value.wasm_memory_limit.map(NumBytes::from),
+            canister_log: CanisterLog::new(
+                value.next_canister_log_record_idx,
+                value
+                    .canister_log_records
+                    .into_iter()
+                    .map(|record| CanisterLogRecord {
+                        idx: record.idx,
+                        timestamp_nanos: record.timestamp_nanos,
+                        content: record.content,
+                    })
+                    .collect(),
+            ),
         })
     }
 }
@@ -1988,6 +2158,13 @@ impl From<&CanisterSnapshotBits> for pb_canister_snapshot_bits::CanisterSnapshot
             binary_hash: item.binary_hash.as_ref().map(|h| h.to_vec()),
             certified_data: item.certified_data.clone(),
             wasm_chunk_store_metadata: Some((&item.wasm_chunk_store_metadata).into()),
+            stable_memory_size: item.stable_memory_size.get() as u64,
+            wasm_memory_size: item.wasm_memory_size.get() as u64,
+            exported_globals: item
+                .exported_globals
+                .iter()
+                .map(|global| global.into())
+                .collect(),
         }
     }
 }
@@ -2012,6 +2189,10 @@ impl TryFrom<pb_canister_snapshot_bits::CanisterSnapshotBits> for CanisterSnapsh
             }
             None => None,
         };
+        let mut exported_globals = Vec::with_capacity(item.exported_globals.len());
+        for global in item.exported_globals.into_iter() {
+            exported_globals.push(global.try_into()?);
+        }
         Ok(Self {
             snapshot_id: SnapshotId::new(item.snapshot_id),
             canister_id,
@@ -2024,6 +2205,9 @@ impl TryFrom<pb_canister_snapshot_bits::CanisterSnapshotBits> for CanisterSnapsh
                 "CanisterSnapshotBits::wasm_chunk_store_metadata",
             )
             .unwrap_or_default(),
+            stable_memory_size: NumWasmPages::from(item.stable_memory_size as usize),
+            wasm_memory_size: NumWasmPages::from(item.wasm_memory_size as usize),
+            exported_globals,
         })
     }
 }
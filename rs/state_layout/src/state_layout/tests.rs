@@ -53,6 +53,8 @@ fn default_canister_state_bits() -> CanisterStateBits {
         wasm_chunk_store_metadata: WasmChunkStoreMetadata::default(),
         total_query_stats: TotalQueryStats::default(),
         log_visibility: LogVisibility::default(),
+        wasm_memory_limit: None,
+        canister_log: CanisterLog::default(),
     }
 }
 
@@ -180,6 +182,12 @@ fn test_encode_decode_non_empty_history() {
         CanisterChangeOrigin::from_canister(canister_test_id(123).get(), None),
         CanisterChangeDetails::controllers_change(vec![]),
     ));
+    canister_history.add_canister_change(CanisterChange::new(
+        555,
+        7,
+        CanisterChangeOrigin::from_user(user_test_id(42).get()),
+        CanisterChangeDetails::load_snapshot(5, 1, 333),
+    ));
 
     // A canister state with non-empty history.
     let canister_state_bits = CanisterStateBits {
@@ -193,6 +201,38 @@ fn test_encode_decode_non_empty_history() {
     assert_eq!(canister_state_bits.canister_history, canister_history);
 }
 
+#[test]
+fn test_encode_decode_wasm_memory_limit() {
+    for wasm_memory_limit in [None, Some(NumBytes::from(0)), Some(NumBytes::from(1 << 30))] {
+        let canister_state_bits = CanisterStateBits {
+            wasm_memory_limit,
+            ..default_canister_state_bits()
+        };
+
+        let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+        let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+
+        assert_eq!(canister_state_bits.wasm_memory_limit, wasm_memory_limit);
+    }
+}
+
+#[test]
+fn test_encode_decode_canister_log() {
+    let mut canister_log = CanisterLog::new_delta(5);
+    canister_log.add_record(100, b"first".to_vec());
+    canister_log.add_record(200, b"second".to_vec());
+    let canister_state_bits = CanisterStateBits {
+        canister_log: canister_log.clone(),
+        ..default_canister_state_bits()
+    };
+
+    let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+    let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+
+    assert_eq!(canister_state_bits.canister_log, canister_log);
+    assert_eq!(canister_state_bits.canister_log.next_idx(), 7);
+}
+
 #[test]
 fn test_canister_snapshots_decode() {
     let canister_snapshot_bits = CanisterSnapshotBits {
@@ -203,6 +243,9 @@ fn test_canister_snapshots_decode() {
         binary_hash: Some(WasmHash::from(&CanisterModule::new(vec![2, 3, 4]))),
         certified_data: vec![3, 4, 7],
         wasm_chunk_store_metadata: WasmChunkStoreMetadata::default(),
+        stable_memory_size: NumWasmPages::new(10),
+        wasm_memory_size: NumWasmPages::new(10),
+        exported_globals: vec![Global::I32(1), Global::I64(2), Global::F64(0.1)],
     };
 
     let pb_bits = pb_canister_snapshot_bits::CanisterSnapshotBits::from(&canister_snapshot_bits);
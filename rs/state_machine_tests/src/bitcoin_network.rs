@@ -0,0 +1,393 @@
+//! An in-process stand-in for the Bitcoin adapter in `StateMachine` tests.
+//!
+//! A [`SimulatedBitcoinNetwork`] holds a regtest block tree that a test
+//! extends with blocks of its choosing, e.g., blocks paying chosen amounts to
+//! chosen addresses. Once attached to a `StateMachine` (see
+//! [`StateMachine::set_bitcoin_network`]), every round answers the pending
+//! `bitcoin_get_successors` requests of the Bitcoin canister from that block
+//! tree, the same way the adapter answers them from its block cache, and
+//! captures the transactions sent via `bitcoin_send_transaction_internal`.
+//!
+//! Blocks are built deterministically, so the same sequence of calls always
+//! produces the same block hashes. Reorgs are simulated by mining blocks on top
+//! of a block that is not the current tip (see
+//! [`SimulatedBitcoinNetwork::mine_block_on`]).
+
+use crate::StateMachine;
+use bitcoin::blockdata::constants::genesis_block;
+use bitcoin::blockdata::script::Builder;
+use bitcoin::consensus::{deserialize, serialize};
+use bitcoin::hashes::Hash;
+use bitcoin::{
+    Address, Block, BlockHash, BlockHeader, OutPoint, Script, Transaction, TxIn, TxOut, Txid,
+    Witness,
+};
+use ic_btc_types_internal::{
+    BitcoinAdapterResponse, BitcoinAdapterResponseWrapper, BitcoinReject,
+    GetSuccessorsRequestInitial, GetSuccessorsResponseComplete, SendTransactionRequest,
+    SendTransactionResponse,
+};
+use ic_error_types::RejectCode;
+use ic_ic00_types::BitcoinNetwork;
+use ic_interfaces_state_manager::StateReader;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+
+// The limits below mirror the ones applied by the adapter's
+// `get_successors_handler.rs`.
+
+// Max number of blocks that can be returned in a `GetSuccessorsResponse`.
+const MAX_BLOCKS_LENGTH: usize = 100;
+
+// Max number of next block headers that can be returned in a
+// `GetSuccessorsResponse`.
+const MAX_NEXT_BLOCK_HEADERS_LENGTH: usize = 100;
+
+// The maximum number of bytes the `blocks` in a response can take. This is a
+// soft limit which only applies if the response contains at least one block.
+const MAX_BLOCKS_BYTES: usize = 2_000_000 - MAX_NEXT_BLOCK_HEADERS_LENGTH * 80;
+
+// The time between two consecutive blocks.
+const BLOCK_INTERVAL_SECONDS: u32 = 600;
+
+struct BlockNode {
+    block: Block,
+    height: u32,
+    children: Vec<BlockHash>,
+}
+
+struct ChainState {
+    blocks: BTreeMap<BlockHash, BlockNode>,
+    genesis: BlockHash,
+    tip: BlockHash,
+    // Number of blocks mined so far. Included in the coinbase transactions so
+    // that mining the same content on the same parent twice yields two
+    // different blocks.
+    mined_blocks: u64,
+    // All transactions sent by canisters, in the order they were sent.
+    sent_transactions: Vec<Transaction>,
+    // Sent transactions that were not included in a block yet.
+    mempool: Vec<Transaction>,
+}
+
+/// A simulated regtest Bitcoin network answering the requests the Bitcoin
+/// canister sends to the adapter.
+///
+/// Only requests for [`BitcoinNetwork::Regtest`] are served; requests for the
+/// other networks are rejected as if the adapter was unreachable.
+pub struct SimulatedBitcoinNetwork {
+    state: Mutex<ChainState>,
+}
+
+impl SimulatedBitcoinNetwork {
+    /// Creates a network only consisting of the regtest genesis block.
+    pub fn new() -> Arc<Self> {
+        let genesis = genesis_block(bitcoin::Network::Regtest);
+        let genesis_hash = genesis.block_hash();
+        let mut blocks = BTreeMap::new();
+        blocks.insert(
+            genesis_hash,
+            BlockNode {
+                block: genesis,
+                height: 0,
+                children: vec![],
+            },
+        );
+        Arc::new(Self {
+            state: Mutex::new(ChainState {
+                blocks,
+                genesis: genesis_hash,
+                tip: genesis_hash,
+                mined_blocks: 0,
+                sent_transactions: vec![],
+                mempool: vec![],
+            }),
+        })
+    }
+
+    /// Returns the hash of the genesis block.
+    pub fn genesis(&self) -> BlockHash {
+        self.state.lock().unwrap().genesis
+    }
+
+    /// Returns the hash of the tip, i.e., the first block mined at the
+    /// greatest height.
+    pub fn tip(&self) -> BlockHash {
+        self.state.lock().unwrap().tip
+    }
+
+    /// Returns the height of the tip.
+    pub fn tip_height(&self) -> u32 {
+        let state = self.state.lock().unwrap();
+        state.blocks[&state.tip].height
+    }
+
+    /// Returns the block with the given hash, if any.
+    pub fn block(&self, hash: &BlockHash) -> Option<Block> {
+        let state = self.state.lock().unwrap();
+        state.blocks.get(hash).map(|node| node.block.clone())
+    }
+
+    /// Returns the number of blocks in the block tree, including the genesis
+    /// block and the blocks of all forks.
+    pub fn num_blocks(&self) -> usize {
+        self.state.lock().unwrap().blocks.len()
+    }
+
+    /// Returns the height of the block with the given hash, if any.
+    pub fn height(&self, hash: &BlockHash) -> Option<u32> {
+        let state = self.state.lock().unwrap();
+        state.blocks.get(hash).map(|node| node.height)
+    }
+
+    /// Mines a block on top of the tip and returns its hash.
+    ///
+    /// The coinbase transaction of the block pays the given amounts (in
+    /// satoshi) to the given addresses and is followed by `transactions`.
+    /// Transactions included in the block are removed from the mempool.
+    pub fn mine_block(
+        &self,
+        outputs: &[(Address, u64)],
+        transactions: Vec<Transaction>,
+    ) -> BlockHash {
+        let tip = self.tip();
+        self.mine_block_on(tip, outputs, transactions)
+    }
+
+    /// Mines a block on top of the block with the given hash and returns its
+    /// hash. See [`Self::mine_block`] for the content of the block.
+    ///
+    /// Mining on top of a block other than the tip creates a fork; the fork
+    /// becomes the tip once it is strictly longer than the current chain.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the parent block is unknown.
+    pub fn mine_block_on(
+        &self,
+        parent: BlockHash,
+        outputs: &[(Address, u64)],
+        transactions: Vec<Transaction>,
+    ) -> BlockHash {
+        let mut state = self.state.lock().unwrap();
+        let (parent_header, parent_height) = match state.blocks.get(&parent) {
+            Some(node) => (node.block.header, node.height),
+            None => panic!("Cannot mine a block on top of unknown block {}", parent),
+        };
+        let height = parent_height + 1;
+
+        // A transaction needs at least one output, so a coinbase without chosen
+        // outputs gets an unspendable one.
+        let coinbase_outputs = if outputs.is_empty() {
+            vec![TxOut {
+                value: 0,
+                script_pubkey: Script::new_op_return(&[]),
+            }]
+        } else {
+            outputs
+                .iter()
+                .map(|(address, value)| TxOut {
+                    value: *value,
+                    script_pubkey: address.script_pubkey(),
+                })
+                .collect()
+        };
+        let coinbase = Transaction {
+            version: 1,
+            lock_time: 0,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                // BIP-34 requires the coinbase to start with the block height.
+                script_sig: Builder::new()
+                    .push_int(height as i64)
+                    .push_int(state.mined_blocks as i64)
+                    .into_script(),
+                sequence: u32::MAX,
+                witness: Witness::default(),
+            }],
+            output: coinbase_outputs,
+        };
+
+        let mut block = Block {
+            header: BlockHeader {
+                version: 1,
+                prev_blockhash: parent,
+                merkle_root: Default::default(),
+                time: parent_header.time + BLOCK_INTERVAL_SECONDS,
+                bits: parent_header.bits,
+                nonce: 0,
+            },
+            txdata: std::iter::once(coinbase).chain(transactions).collect(),
+        };
+        block.header.merkle_root = block.compute_merkle_root().unwrap_or_default();
+        let target = block.header.target();
+        while block.header.validate_pow(&target).is_err() {
+            block.header.nonce += 1;
+        }
+
+        let hash = block.block_hash();
+        let mined: HashSet<Txid> = block.txdata.iter().map(|tx| tx.txid()).collect();
+        state.mempool.retain(|tx| !mined.contains(&tx.txid()));
+        state.blocks.get_mut(&parent).unwrap().children.push(hash);
+        state.blocks.insert(
+            hash,
+            BlockNode {
+                block,
+                height,
+                children: vec![],
+            },
+        );
+        state.mined_blocks += 1;
+        if height > state.blocks[&state.tip].height {
+            state.tip = hash;
+        }
+        hash
+    }
+
+    /// Mines `n` empty blocks on top of the tip, e.g., to confirm the
+    /// transactions in the tip. Returns the hash of the new tip.
+    pub fn mine_empty_blocks(&self, n: usize) -> BlockHash {
+        for _ in 0..n {
+            self.mine_block(&[], vec![]);
+        }
+        self.tip()
+    }
+
+    /// Mines a block on top of the tip including all transactions in the
+    /// mempool and returns its hash.
+    pub fn mine_mempool(&self) -> BlockHash {
+        let mempool = self.mempool();
+        self.mine_block(&[], mempool)
+    }
+
+    /// Returns all transactions sent by canisters so far, in the order they
+    /// were sent.
+    pub fn sent_transactions(&self) -> Vec<Transaction> {
+        self.state.lock().unwrap().sent_transactions.clone()
+    }
+
+    /// Returns the transactions sent by canisters that were not included in a
+    /// block yet.
+    pub fn mempool(&self) -> Vec<Transaction> {
+        self.state.lock().unwrap().mempool.clone()
+    }
+
+    /// Builds the adapter responses to all Bitcoin requests pending in the
+    /// latest state of the given subnet.
+    pub(crate) fn responses(&self, sm: &StateMachine) -> Vec<BitcoinAdapterResponse> {
+        let latest_state = sm.state_manager.get_latest_state().take();
+        let manager = &latest_state.metadata.subnet_call_context_manager;
+        let mut state = self.state.lock().unwrap();
+
+        let mut responses = vec![];
+        for (callback_id, context) in manager.bitcoin_send_transaction_internal_contexts.iter() {
+            responses.push(BitcoinAdapterResponse {
+                response: state.send_transaction(&context.payload),
+                callback_id: callback_id.get(),
+            });
+        }
+        for (callback_id, context) in manager.bitcoin_get_successors_contexts.iter() {
+            responses.push(BitcoinAdapterResponse {
+                response: state.get_successors(&context.payload),
+                callback_id: callback_id.get(),
+            });
+        }
+        responses
+    }
+}
+
+impl ChainState {
+    fn send_transaction(
+        &mut self,
+        request: &SendTransactionRequest,
+    ) -> BitcoinAdapterResponseWrapper {
+        if let Err(reject) = check_network(request.network) {
+            return BitcoinAdapterResponseWrapper::SendTransactionReject(reject);
+        }
+        match deserialize::<Transaction>(&request.transaction) {
+            Ok(transaction) => {
+                self.sent_transactions.push(transaction.clone());
+                self.mempool.push(transaction);
+                BitcoinAdapterResponseWrapper::SendTransactionResponse(SendTransactionResponse {})
+            }
+            Err(err) => BitcoinAdapterResponseWrapper::SendTransactionReject(BitcoinReject {
+                reject_code: RejectCode::SysTransient,
+                message: format!("Failed to deserialize the transaction: {}", err),
+            }),
+        }
+    }
+
+    fn get_successors(
+        &self,
+        request: &GetSuccessorsRequestInitial,
+    ) -> BitcoinAdapterResponseWrapper {
+        if let Err(reject) = check_network(request.network) {
+            return BitcoinAdapterResponseWrapper::GetSuccessorsReject(reject);
+        }
+        let anchor = match BlockHash::from_slice(&request.anchor) {
+            Ok(anchor) => anchor,
+            Err(_) => {
+                return BitcoinAdapterResponseWrapper::GetSuccessorsReject(BitcoinReject {
+                    reject_code: RejectCode::SysTransient,
+                    message: "Failed to parse anchor hash!".to_string(),
+                })
+            }
+        };
+        let mut seen = HashSet::new();
+        for hash in request.processed_block_hashes.iter() {
+            if let Ok(hash) = BlockHash::from_slice(hash) {
+                seen.insert(hash);
+            }
+        }
+
+        // Breadth-first search through the successors of the anchor, returning
+        // the unprocessed blocks that fit into the response and the headers of
+        // the remaining ones.
+        let mut blocks = vec![];
+        let mut blocks_bytes = 0;
+        let mut next = vec![];
+        let mut blocks_full = false;
+        let mut queue: VecDeque<BlockHash> = self
+            .blocks
+            .get(&anchor)
+            .map(|node| node.children.iter().copied().collect())
+            .unwrap_or_default();
+        while let Some(hash) = queue.pop_front() {
+            let node = &self.blocks[&hash];
+            if !seen.contains(&hash) {
+                let block = serialize(&node.block);
+                blocks_full = blocks_full
+                    || (!blocks.is_empty()
+                        && (blocks_bytes + block.len() > MAX_BLOCKS_BYTES
+                            || blocks.len() >= MAX_BLOCKS_LENGTH));
+                if !blocks_full {
+                    blocks_bytes += block.len();
+                    blocks.push(block);
+                } else if next.len() < MAX_NEXT_BLOCK_HEADERS_LENGTH {
+                    next.push(serialize(&node.block.header));
+                } else {
+                    break;
+                }
+            }
+            queue.extend(node.children.iter().copied());
+        }
+
+        BitcoinAdapterResponseWrapper::GetSuccessorsResponse(GetSuccessorsResponseComplete {
+            blocks,
+            next,
+        })
+    }
+}
+
+fn check_network(network: BitcoinNetwork) -> Result<(), BitcoinReject> {
+    match network {
+        BitcoinNetwork::Regtest => Ok(()),
+        BitcoinNetwork::Mainnet | BitcoinNetwork::Testnet => Err(BitcoinReject {
+            reject_code: RejectCode::SysTransient,
+            message: format!(
+                "The simulated Bitcoin network does not serve {:?} requests",
+                network
+            ),
+        }),
+    }
+}
@@ -0,0 +1,512 @@
+//! Deterministic fault injection for (multi-subnet) `StateMachine` tests.
+//!
+//! A [`FaultInjector`] is shared between the `StateMachine`s of a test (see
+//! [`StateMachine::set_fault_injector`]) and applies its faults while the
+//! subnets execute rounds:
+//!
+//! * message faults drop, delay, reorder or reject messages as they are
+//!   appended to a subnet's outgoing streams, i.e., before they are ever
+//!   certified and made available to the remote subnet;
+//! * subnet faults make a subnet skip a number of rounds;
+//! * canister faults make a canister trap or run out of cycles at a chosen
+//!   call, counting both ingress messages and requests from other subnets.
+//!
+//! All random decisions (fault probabilities and reordering) are taken from a
+//! PRNG seeded with the seed passed to [`FaultInjector::new`], so a test
+//! executing the same sequence of rounds always observes the same faults.
+
+use crate::StateMachine;
+use ic_error_types::{ErrorCode, RejectCode, UserError};
+use ic_interfaces_state_manager::{CertificationScope, StateManager, StateReader};
+use ic_replicated_state::canister_state::system_state::CyclesUseCase;
+use ic_replicated_state::replicated_state::ReplicatedStateMessageRouting;
+use ic_replicated_state::testing::ReplicatedStateTesting;
+use ic_replicated_state::{ReplicatedState, Stream};
+use ic_types::ingress::{IngressState, IngressStatus};
+use ic_types::messages::{
+    Payload, RejectContext, Request, RequestOrResponse, Response, SignedIngress,
+};
+use ic_types::xnet::StreamIndexedQueue;
+use ic_types::{CanisterId, NumBytes, SubnetId};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// A fault applied to a message appended to an outgoing stream.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MessageFault {
+    /// The message is never delivered.
+    Drop,
+    /// The message is held back for the given number of rounds of the sending
+    /// subnet and then appended to the end of the stream, i.e., after any
+    /// messages sent in the meantime.
+    Delay { rounds: u64 },
+    /// The message is shuffled with the other messages matched by a `Reorder`
+    /// fault in the same round and stream.
+    Reorder,
+    /// The request is answered by a reject response with the given code and
+    /// message instead of being delivered. Never applied to responses.
+    Reject { code: RejectCode, message: String },
+}
+
+/// A fault making a canister fail at a chosen call.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CanisterFault {
+    /// The call fails as if the canister trapped while executing it.
+    Trap,
+    /// The canister runs out of cycles right before the call.
+    OutOfCycles,
+}
+
+type MessageMatcher = Box<dyn Fn(SubnetId, &RequestOrResponse) -> bool + Send>;
+
+/// A rule selecting the stream messages to apply a [`MessageFault`] to.
+///
+/// By default, a rule matches every message, always applies and never
+/// expires.
+pub struct MessageFaultRule {
+    fault: MessageFault,
+    matcher: MessageMatcher,
+    probability: f64,
+    max_count: Option<u64>,
+}
+
+impl MessageFaultRule {
+    pub fn new(fault: MessageFault) -> Self {
+        Self {
+            fault,
+            matcher: Box::new(|_, _| true),
+            probability: 1.0,
+            max_count: None,
+        }
+    }
+
+    /// Only applies the fault to messages for which `matcher` returns `true`
+    /// given the destination subnet and the message.
+    pub fn with_matcher(
+        self,
+        matcher: impl Fn(SubnetId, &RequestOrResponse) -> bool + Send + 'static,
+    ) -> Self {
+        Self {
+            matcher: Box::new(matcher),
+            ..self
+        }
+    }
+
+    /// Applies the fault to a matching message with the given probability.
+    pub fn with_probability(self, probability: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&probability),
+            "Invalid fault probability: {}",
+            probability
+        );
+        Self {
+            probability,
+            ..self
+        }
+    }
+
+    /// Applies the fault to at most `max_count` messages.
+    pub fn with_max_count(self, max_count: u64) -> Self {
+        Self {
+            max_count: Some(max_count),
+            ..self
+        }
+    }
+
+    fn applies_to(&self, destination: SubnetId, msg: &RequestOrResponse) -> bool {
+        self.max_count != Some(0)
+            && !(matches!(self.fault, MessageFault::Reject { .. })
+                && matches!(msg, RequestOrResponse::Response(_)))
+            && (self.matcher)(destination, msg)
+    }
+}
+
+struct CanisterFaultRule {
+    canister_id: CanisterId,
+    method_name: Option<String>,
+    call_index: u64,
+    calls_seen: u64,
+    fault: CanisterFault,
+}
+
+impl CanisterFaultRule {
+    /// Counts a call to `canister_id` and returns whether the fault applies to
+    /// it.
+    fn observe_call(&mut self, canister_id: CanisterId, method_name: &str) -> bool {
+        if self.canister_id != canister_id
+            || self
+                .method_name
+                .as_ref()
+                .is_some_and(|name| name != method_name)
+        {
+            return false;
+        }
+        self.calls_seen += 1;
+        self.calls_seen == self.call_index + 1
+    }
+}
+
+struct DelayedMessage {
+    sender: SubnetId,
+    destination: SubnetId,
+    remaining_rounds: u64,
+    msg: RequestOrResponse,
+}
+
+struct FaultInjectorState {
+    rng: StdRng,
+    message_rules: Vec<MessageFaultRule>,
+    canister_rules: Vec<CanisterFaultRule>,
+    stalled_rounds: BTreeMap<SubnetId, u64>,
+    delayed_messages: Vec<DelayedMessage>,
+    /// End of every outgoing stream (by sending and destination subnet) up to
+    /// which faults have been applied.
+    processed_stream_ends: BTreeMap<(SubnetId, SubnetId), u64>,
+}
+
+/// Seeded fault injector shared by the `StateMachine`s of a test.
+pub struct FaultInjector {
+    state: Mutex<FaultInjectorState>,
+}
+
+impl FaultInjector {
+    pub fn new(seed: u64) -> Arc<Self> {
+        Arc::new(Self {
+            state: Mutex::new(FaultInjectorState {
+                rng: StdRng::seed_from_u64(seed),
+                message_rules: vec![],
+                canister_rules: vec![],
+                stalled_rounds: BTreeMap::new(),
+                delayed_messages: vec![],
+                processed_stream_ends: BTreeMap::new(),
+            }),
+        })
+    }
+
+    /// Adds a message fault rule. Rules are evaluated in the order they were
+    /// added and at most one message fault is applied to every message.
+    pub fn add_message_fault(&self, rule: MessageFaultRule) {
+        self.state.lock().unwrap().message_rules.push(rule);
+    }
+
+    /// Makes the call with (0-based) index `call_index` among the calls to
+    /// `canister_id` (and `method_name`, if specified) fail with `fault`.
+    ///
+    /// Ingress messages are failed on the subnet of the canister. Requests
+    /// from other subnets are answered with the corresponding reject response
+    /// by the sending subnet. Calls between canisters on the same subnet are
+    /// not counted.
+    pub fn add_canister_fault(
+        &self,
+        canister_id: CanisterId,
+        method_name: Option<String>,
+        call_index: u64,
+        fault: CanisterFault,
+    ) {
+        self.state
+            .lock()
+            .unwrap()
+            .canister_rules
+            .push(CanisterFaultRule {
+                canister_id,
+                method_name,
+                call_index,
+                calls_seen: 0,
+                fault,
+            });
+    }
+
+    /// Makes the subnet skip its next `rounds` rounds. The payloads of the
+    /// skipped rounds are discarded.
+    pub fn stall_subnet(&self, subnet_id: SubnetId, rounds: u64) {
+        *self
+            .state
+            .lock()
+            .unwrap()
+            .stalled_rounds
+            .entry(subnet_id)
+            .or_default() += rounds;
+    }
+
+    /// Applies subnet and canister faults to the round about to be executed
+    /// by `sm`. Returns `false` if the round must be skipped.
+    pub(crate) fn before_round(
+        &self,
+        sm: &StateMachine,
+        ingress_messages: &mut Vec<SignedIngress>,
+    ) -> bool {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(rounds) = state.stalled_rounds.get_mut(&sm.subnet_id) {
+            if *rounds > 0 {
+                *rounds -= 1;
+                return false;
+            }
+        }
+
+        // Messages already in the streams were sent before the fault injector
+        // was set and are not subject to faults.
+        let sender = sm.subnet_id;
+        let latest_state = sm.state_manager.get_latest_state().take();
+        for (destination, stream) in latest_state.streams() {
+            state
+                .processed_stream_ends
+                .entry((sender, *destination))
+                .or_insert(stream.messages_end().get());
+        }
+
+        let mut trapped = vec![];
+        let mut out_of_cycles = vec![];
+        ingress_messages.retain(|msg| {
+            match state.observe_call(msg.canister_id(), &msg.method_name()) {
+                Some(CanisterFault::Trap) => {
+                    trapped.push(msg.clone());
+                    false
+                }
+                Some(CanisterFault::OutOfCycles) => {
+                    out_of_cycles.push(msg.canister_id());
+                    true
+                }
+                None => true,
+            }
+        });
+        if trapped.is_empty() && out_of_cycles.is_empty() {
+            return true;
+        }
+
+        let (height, mut replicated_state) = sm.state_manager.take_tip();
+        let time = replicated_state.time();
+        for msg in trapped {
+            let status = IngressStatus::Known {
+                receiver: msg.canister_id().get(),
+                user_id: msg.sender(),
+                time,
+                state: IngressState::Failed(UserError::new(
+                    ErrorCode::CanisterCalledTrap,
+                    trap_message(msg.canister_id()),
+                )),
+            };
+            replicated_state.set_ingress_status(msg.id(), status, NumBytes::from(u64::MAX));
+        }
+        for canister_id in out_of_cycles {
+            if let Some(canister_state) = replicated_state.canister_state_mut(&canister_id) {
+                let balance = canister_state.system_state.balance();
+                canister_state
+                    .system_state
+                    .remove_cycles(balance, CyclesUseCase::BurnedCycles);
+            }
+        }
+        sm.state_manager.commit_and_certify(
+            replicated_state,
+            height.increment(),
+            CertificationScope::Full,
+        );
+        true
+    }
+
+    /// Applies message and canister faults to the messages appended to the
+    /// outgoing streams of `sm` in the round it just executed.
+    pub(crate) fn after_round(&self, sm: &StateMachine) {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let sender = sm.subnet_id;
+
+        let mut released = BTreeMap::<SubnetId, Vec<RequestOrResponse>>::new();
+        let mut delayed_messages = vec![];
+        for mut delayed in state.delayed_messages.drain(..) {
+            if delayed.sender != sender {
+                delayed_messages.push(delayed);
+            } else if delayed.remaining_rounds > 1 {
+                delayed.remaining_rounds -= 1;
+                delayed_messages.push(delayed);
+            } else {
+                released
+                    .entry(delayed.destination)
+                    .or_default()
+                    .push(delayed.msg);
+            }
+        }
+        state.delayed_messages = delayed_messages;
+
+        let mut streams = sm.state_manager.get_latest_state().take().streams().clone();
+        let mut rejects = vec![];
+        let mut modified = false;
+        for (destination, stream) in streams.iter_mut() {
+            let processed_end = state
+                .processed_stream_ends
+                .get(&(sender, *destination))
+                .copied()
+                .unwrap_or_default()
+                .max(stream.messages_begin().get());
+            let released = released.remove(destination).unwrap_or_default();
+            if stream.messages_end().get() == processed_end && released.is_empty() {
+                continue;
+            }
+
+            let mut messages = StreamIndexedQueue::with_begin(stream.messages_begin());
+            let mut new_messages = vec![];
+            for (index, msg) in stream.messages().iter() {
+                if index.get() < processed_end {
+                    messages.push(msg.clone());
+                } else {
+                    new_messages.push(msg.clone());
+                }
+            }
+
+            let new_message_count = new_messages.len();
+            let mut kept = vec![];
+            let mut reordered = vec![];
+            for msg in new_messages {
+                if let Some(fault) = state.canister_fault(&msg) {
+                    if let RequestOrResponse::Request(request) = &msg {
+                        rejects.push(canister_fault_reject(request, fault));
+                    }
+                    continue;
+                }
+                match state.message_fault(*destination, &msg) {
+                    None => kept.push(msg),
+                    Some(MessageFault::Drop) => {}
+                    Some(MessageFault::Delay { rounds }) if rounds > 0 => {
+                        state.delayed_messages.push(DelayedMessage {
+                            sender,
+                            destination: *destination,
+                            remaining_rounds: rounds,
+                            msg,
+                        })
+                    }
+                    Some(MessageFault::Delay { .. }) => kept.push(msg),
+                    Some(MessageFault::Reorder) => {
+                        reordered.push(kept.len());
+                        kept.push(msg);
+                    }
+                    Some(MessageFault::Reject { code, message }) => {
+                        if let RequestOrResponse::Request(request) = &msg {
+                            rejects.push(reject_response(request, code, message));
+                        }
+                    }
+                }
+            }
+            let unchanged =
+                kept.len() == new_message_count && reordered.is_empty() && released.is_empty();
+            let mut shuffled: Vec<_> = reordered.iter().map(|i| kept[*i].clone()).collect();
+            shuffled.shuffle(&mut state.rng);
+            for (i, msg) in reordered.into_iter().zip(shuffled) {
+                kept[i] = msg;
+            }
+
+            let appended = kept.len() + released.len();
+            for msg in kept.into_iter().chain(released) {
+                messages.push(msg);
+            }
+            state
+                .processed_stream_ends
+                .insert((sender, *destination), processed_end + appended as u64);
+            if !unchanged {
+                *stream = Stream::with_signals(
+                    messages,
+                    stream.signals_end(),
+                    stream.reject_signals().clone(),
+                );
+                modified = true;
+            }
+        }
+
+        if !modified {
+            return;
+        }
+        let (height, mut replicated_state) = sm.state_manager.take_tip();
+        replicated_state.with_streams(streams);
+        push_rejects(&mut replicated_state, rejects);
+        sm.state_manager.commit_and_certify(
+            replicated_state,
+            height.increment(),
+            CertificationScope::Full,
+        );
+    }
+}
+
+impl FaultInjectorState {
+    /// Counts a call towards all canister fault rules and returns the fault to
+    /// apply to it, if any.
+    fn observe_call(
+        &mut self,
+        canister_id: CanisterId,
+        method_name: &str,
+    ) -> Option<CanisterFault> {
+        let mut fault = None;
+        for rule in self.canister_rules.iter_mut() {
+            if rule.observe_call(canister_id, method_name) {
+                fault.get_or_insert(rule.fault);
+            }
+        }
+        fault
+    }
+
+    fn canister_fault(&mut self, msg: &RequestOrResponse) -> Option<CanisterFault> {
+        match msg {
+            RequestOrResponse::Request(request) => {
+                self.observe_call(request.receiver, &request.method_name)
+            }
+            RequestOrResponse::Response(_) => None,
+        }
+    }
+
+    fn message_fault(
+        &mut self,
+        destination: SubnetId,
+        msg: &RequestOrResponse,
+    ) -> Option<MessageFault> {
+        for rule in self.message_rules.iter_mut() {
+            if rule.applies_to(destination, msg) && self.rng.gen_bool(rule.probability) {
+                if let Some(max_count) = rule.max_count.as_mut() {
+                    *max_count -= 1;
+                }
+                return Some(rule.fault.clone());
+            }
+        }
+        None
+    }
+}
+
+fn trap_message(canister_id: CanisterId) -> String {
+    format!("Canister {} trapped: injected fault", canister_id)
+}
+
+fn canister_fault_reject(request: &Request, fault: CanisterFault) -> Response {
+    match fault {
+        CanisterFault::Trap => reject_response(
+            request,
+            RejectCode::CanisterError,
+            trap_message(request.receiver),
+        ),
+        CanisterFault::OutOfCycles => reject_response(
+            request,
+            RejectCode::SysTransient,
+            format!("Canister {} is out of cycles", request.receiver),
+        ),
+    }
+}
+
+fn reject_response(request: &Request, code: RejectCode, message: String) -> Response {
+    Response {
+        originator: request.sender,
+        respondent: request.receiver,
+        originator_reply_callback: request.sender_reply_callback,
+        refund: request.payment,
+        response_payload: Payload::Reject(RejectContext::new(code, message)),
+        deadline: request.deadline,
+    }
+}
+
+/// Delivers reject responses to the local canisters which sent the rejected
+/// requests.
+fn push_rejects(state: &mut ReplicatedState, rejects: Vec<Response>) {
+    // Responses always have a reserved slot, so memory is not a concern.
+    let mut subnet_available_memory = i64::MAX;
+    for response in rejects {
+        let _ = state.push_input(response.into(), &mut subnet_available_memory);
+    }
+}
@@ -18,11 +18,13 @@ use ic_crypto_tree_hash::{
 };
 use ic_cycles_account_manager::CyclesAccountManager;
 pub use ic_error_types::{ErrorCode, UserError};
-use ic_execution_environment::{ExecutionServices, IngressHistoryReaderImpl};
+pub use ic_execution_environment::CanisterWasmProfile;
+use ic_execution_environment::{ExecutionServices, IngressHistoryReaderImpl, WasmProfileLog};
 use ic_ic00_types::{self as ic00, CanisterIdRecord, InstallCodeArgs, Method, Payload};
 pub use ic_ic00_types::{
     CanisterHttpResponsePayload, CanisterInstallMode, CanisterSettingsArgs, CanisterStatusResultV2,
-    ECDSAPublicKeyResponse, EcdsaCurve, EcdsaKeyId, HttpHeader, HttpMethod, SignWithECDSAReply,
+    ECDSAPublicKeyResponse, EcdsaCurve, EcdsaKeyId, HttpHeader, HttpMethod, SchnorrAlgorithm,
+    SchnorrKeyId, SchnorrPublicKeyResponse, SignWithECDSAReply, SignWithSchnorrReply,
     UpdateSettingsArgs,
 };
 use ic_ingress_manager::{CustomRandomState, IngressManager};
@@ -46,7 +48,7 @@ use ic_messaging::SyncMessageRouting;
 use ic_metrics::MetricsRegistry;
 use ic_protobuf::registry::crypto::v1::PublicKey as PublicKeyProto;
 use ic_protobuf::registry::{
-    crypto::v1::EcdsaSigningSubnetList,
+    crypto::v1::{EcdsaSigningSubnetList, SchnorrSigningSubnetList},
     node::v1::{ConnectionEndpoint, NodeRecord},
     provisional_whitelist::v1::ProvisionalWhitelist as PbProvisionalWhitelist,
     routing_table::v1::CanisterMigrations as PbCanisterMigrations,
@@ -60,7 +62,7 @@ use ic_registry_client_helpers::subnet::{SubnetListRegistry, SubnetRegistry};
 use ic_registry_keys::{
     make_canister_migrations_record_key, make_crypto_node_key, make_ecdsa_signing_subnet_list_key,
     make_node_record_key, make_provisional_whitelist_record_key, make_routing_table_record_key,
-    ROOT_SUBNET_ID_KEY,
+    make_schnorr_signing_subnet_list_key, ROOT_SUBNET_ID_KEY,
 };
 use ic_registry_proto_data_provider::{ProtoRegistryDataProvider, INITIAL_REGISTRY_VERSION};
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
@@ -70,7 +72,9 @@ use ic_registry_routing_table::{
 use ic_registry_subnet_features::{EcdsaConfig, SubnetFeatures, DEFAULT_ECDSA_MAX_QUEUE_SIZE};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::canister_state::system_state::CyclesUseCase;
-use ic_replicated_state::metadata_state::subnet_call_context_manager::SignWithEcdsaContext;
+use ic_replicated_state::metadata_state::subnet_call_context_manager::{
+    SignWithEcdsaContext, SignWithSchnorrContext,
+};
 use ic_replicated_state::page_map::Buffer;
 use ic_replicated_state::{
     canister_state::{NumWasmPages, WASM_PAGE_SIZE_IN_BYTES},
@@ -95,8 +99,8 @@ use ic_types::consensus::certification::CertificationContent;
 use ic_types::crypto::threshold_sig::ni_dkg::{NiDkgId, NiDkgTag, NiDkgTargetSubnet};
 pub use ic_types::crypto::threshold_sig::ThresholdSigPublicKey;
 use ic_types::crypto::{
-    canister_threshold_sig::MasterEcdsaPublicKey, AlgorithmId, CombinedThresholdSig,
-    CombinedThresholdSigOf, KeyPurpose, Signable, Signed,
+    canister_threshold_sig::{MasterEcdsaPublicKey, MasterSchnorrPublicKey},
+    AlgorithmId, CombinedThresholdSig, CombinedThresholdSigOf, KeyPurpose, Signable, Signed,
 };
 use ic_types::malicious_flags::MaliciousFlags;
 use ic_types::messages::{
@@ -138,7 +142,7 @@ pub use slog::Level;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 use std::io::stderr;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::string::ToString;
 use std::sync::Arc;
@@ -151,9 +155,14 @@ use tempfile::TempDir;
 use tokio::runtime::Runtime;
 use tokio::sync::mpsc;
 
+mod bitcoin_network;
+mod fault_injection;
 #[cfg(test)]
 mod tests;
 
+pub use bitcoin_network::SimulatedBitcoinNetwork;
+pub use fault_injection::{CanisterFault, FaultInjector, MessageFault, MessageFaultRule};
+
 #[derive(Debug, Serialize, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
 pub enum SubmitIngressError {
     HttpError(String),
@@ -220,6 +229,7 @@ fn make_nodes_registry(
     subnet_id: SubnetId,
     subnet_type: SubnetType,
     ecdsa_keys: &[EcdsaKeyId],
+    schnorr_keys: &[SchnorrKeyId],
     features: SubnetFeatures,
     registry_version: RegistryVersion,
     registry_data_provider: Arc<ProtoRegistryDataProvider>,
@@ -246,6 +256,18 @@ fn make_nodes_registry(
             )
             .unwrap();
     }
+    for key_id in schnorr_keys {
+        let id = make_schnorr_signing_subnet_list_key(key_id);
+        registry_data_provider
+            .add(
+                &id.clone(),
+                registry_version,
+                Some(SchnorrSigningSubnetList {
+                    subnets: vec![subnet_id_proto.clone()],
+                }),
+            )
+            .unwrap();
+    }
 
     for node in nodes {
         let node_record = NodeRecord {
@@ -555,6 +577,26 @@ impl From<u64> for StateMachineNode {
     }
 }
 
+/// The directory in which a `StateMachine` stores its states.
+///
+/// A temporary directory is deleted when the `StateMachine` is dropped, while
+/// a plain path is kept, so that a later `StateMachine` can load its states.
+pub trait StateMachineStateDir: Send + Sync {
+    fn path(&self) -> PathBuf;
+}
+
+impl StateMachineStateDir for TempDir {
+    fn path(&self) -> PathBuf {
+        TempDir::path(self).to_path_buf()
+    }
+}
+
+impl StateMachineStateDir for PathBuf {
+    fn path(&self) -> PathBuf {
+        self.clone()
+    }
+}
+
 /// Represents a replicated state machine detached from the network layer that
 /// can be used to test this part of the stack in isolation.
 pub struct StateMachine {
@@ -575,13 +617,18 @@ pub struct StateMachine {
     ingress_history_reader: Box<dyn IngressHistoryReader>,
     query_handler: Arc<dyn QueryHandler<State = ReplicatedState>>,
     _runtime: Arc<Runtime>,
-    pub state_dir: TempDir,
+    pub state_dir: Box<dyn StateMachineStateDir>,
     checkpoints_enabled: std::sync::atomic::AtomicBool,
     nonce: std::sync::atomic::AtomicU64,
     time: std::sync::atomic::AtomicU64,
     ecdsa_subnet_public_keys: BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+    schnorr_secret_keys: BTreeMap<SchnorrKeyId, SchnorrSecretKey>,
+    schnorr_subnet_public_keys: BTreeMap<SchnorrKeyId, MasterSchnorrPublicKey>,
     replica_logger: ReplicaLogger,
     nodes: Vec<StateMachineNode>,
+    fault_injector: RwLock<Option<Arc<FaultInjector>>>,
+    bitcoin_network: RwLock<Option<Arc<SimulatedBitcoinNetwork>>>,
+    wasm_profiles: Arc<WasmProfileLog>,
 }
 
 impl Default for StateMachine {
@@ -600,7 +647,7 @@ impl fmt::Debug for StateMachine {
 }
 
 pub struct StateMachineBuilder {
-    state_dir: TempDir,
+    state_dir: Box<dyn StateMachineStateDir>,
     nonce: u64,
     time: Time,
     config: Option<StateMachineConfig>,
@@ -617,6 +664,7 @@ pub struct StateMachineBuilder {
     routing_table: RoutingTable,
     use_cost_scaling_flag: bool,
     ecdsa_keys: Vec<EcdsaKeyId>,
+    schnorr_keys: Vec<SchnorrKeyId>,
     features: SubnetFeatures,
     runtime: Option<Arc<Runtime>>,
     registry_data_provider: Arc<ProtoRegistryDataProvider>,
@@ -654,7 +702,7 @@ impl StateMachineBuilder {
         let (own_subnet_id, public_key, secret_key) =
             Self::compute_subnet_id_and_key_from_seed(seed);
         Self {
-            state_dir: TempDir::new().expect("failed to create a temporary directory"),
+            state_dir: Box::new(TempDir::new().expect("failed to create a temporary directory")),
             nonce: 0,
             time: GENESIS,
             config: None,
@@ -670,6 +718,7 @@ impl StateMachineBuilder {
                 curve: EcdsaCurve::Secp256k1,
                 name: "master_ecdsa_public_key".to_string(),
             }],
+            schnorr_keys: vec![],
             features: SubnetFeatures {
                 http_requests: true,
                 ..SubnetFeatures::default()
@@ -690,6 +739,13 @@ impl StateMachineBuilder {
     }
 
     pub fn with_state_dir(self, state_dir: TempDir) -> Self {
+        Self {
+            state_dir: Box::new(state_dir),
+            ..self
+        }
+    }
+
+    pub fn with_state_machine_state_dir(self, state_dir: Box<dyn StateMachineStateDir>) -> Self {
         Self { state_dir, ..self }
     }
 
@@ -797,6 +853,22 @@ impl StateMachineBuilder {
         Self { ecdsa_keys, ..self }
     }
 
+    pub fn with_schnorr_key(self, key: SchnorrKeyId) -> Self {
+        let mut schnorr_keys = self.schnorr_keys;
+        schnorr_keys.push(key);
+        Self {
+            schnorr_keys,
+            ..self
+        }
+    }
+
+    pub fn with_schnorr_keys(self, schnorr_keys: Vec<SchnorrKeyId>) -> Self {
+        Self {
+            schnorr_keys,
+            ..self
+        }
+    }
+
     pub fn with_features(self, features: SubnetFeatures) -> Self {
         Self { features, ..self }
     }
@@ -863,6 +935,7 @@ impl StateMachineBuilder {
             self.subnet_id,
             self.use_cost_scaling_flag,
             self.ecdsa_keys,
+            self.schnorr_keys,
             self.features,
             self.runtime.unwrap_or_else(|| {
                 tokio::runtime::Builder::new_current_thread()
@@ -1031,9 +1104,15 @@ impl StateMachine {
                 originator_reply_callback: id,
                 refund: Cycles::zero(),
                 response_payload: MsgPayload::Data(reply.encode()),
+                deadline: None,
             });
         }
 
+        // Push responses to Schnorr management canister calls into `PayloadBuilder`.
+        payload
+            .consensus_responses
+            .extend(self.sign_with_schnorr_responses(&state));
+
         // Finally execute the payload.
         self.execute_payload(payload);
     }
@@ -1049,7 +1128,7 @@ impl StateMachine {
     /// directory for storing states.
     #[allow(clippy::too_many_arguments)]
     fn setup_from_dir(
-        state_dir: TempDir,
+        state_dir: Box<dyn StateMachineStateDir>,
         nonce: u64,
         time: Time,
         config: Option<StateMachineConfig>,
@@ -1059,6 +1138,7 @@ impl StateMachine {
         subnet_id: SubnetId,
         use_cost_scaling_flag: bool,
         ecdsa_keys: Vec<EcdsaKeyId>,
+        schnorr_keys: Vec<SchnorrKeyId>,
         features: SubnetFeatures,
         runtime: Arc<Runtime>,
         registry_version: RegistryVersion,
@@ -1091,6 +1171,7 @@ impl StateMachine {
             subnet_id,
             subnet_type,
             &ecdsa_keys,
+            &schnorr_keys,
             features,
             registry_version,
             registry_data_provider.clone(),
@@ -1099,7 +1180,7 @@ impl StateMachine {
             public_key,
         );
 
-        let mut sm_config = ic_config::state_manager::Config::new(state_dir.path().to_path_buf());
+        let mut sm_config = ic_config::state_manager::Config::new(state_dir.path());
         if let Some(lsmt_override) = lsmt_override {
             sm_config.lsmt_storage = lsmt_override;
         }
@@ -1155,6 +1236,7 @@ impl StateMachine {
             )
         });
 
+        let wasm_profiles = Arc::clone(&execution_services.wasm_profiles);
         let message_routing = SyncMessageRouting::new(
             Arc::clone(&state_manager) as _,
             Arc::clone(&state_manager) as _,
@@ -1206,6 +1288,15 @@ impl StateMachine {
             },
         );
 
+        let mut schnorr_secret_keys = BTreeMap::new();
+        let mut schnorr_subnet_public_keys = BTreeMap::new();
+
+        for schnorr_key in schnorr_keys {
+            let secret_key = SchnorrSecretKey::new_for_key_id(&schnorr_key);
+            schnorr_subnet_public_keys.insert(schnorr_key.clone(), secret_key.master_public_key());
+            schnorr_secret_keys.insert(schnorr_key, secret_key);
+        }
+
         let time_source = FastForwardTimeSource::new();
         time_source.set_time(time).unwrap();
         let consensus_time = Arc::new(PocketConsensusTime::new(time));
@@ -1254,12 +1345,17 @@ impl StateMachine {
             nonce: std::sync::atomic::AtomicU64::new(nonce),
             time: std::sync::atomic::AtomicU64::new(time.as_nanos_since_unix_epoch()),
             ecdsa_subnet_public_keys,
+            schnorr_secret_keys,
+            schnorr_subnet_public_keys,
             replica_logger,
             nodes,
+            fault_injector: RwLock::new(None),
+            bitcoin_network: RwLock::new(None),
+            wasm_profiles,
         }
     }
 
-    fn into_components(self) -> (TempDir, u64, Time, bool) {
+    fn into_components(self) -> (Box<dyn StateMachineStateDir>, u64, Time, bool) {
         (
             self.state_dir,
             self.nonce.into_inner(),
@@ -1268,7 +1364,7 @@ impl StateMachine {
         )
     }
 
-    pub fn into_state_dir(self) -> TempDir {
+    pub fn into_state_dir(self) -> Box<dyn StateMachineStateDir> {
         let (path, _, _, _) = self.into_components();
         path
     }
@@ -1302,13 +1398,29 @@ impl StateMachine {
         let (state_dir, nonce, time, checkpoints_enabled) = self.into_components();
 
         StateMachineBuilder::new()
-            .with_state_dir(state_dir)
+            .with_state_machine_state_dir(state_dir)
             .with_nonce(nonce)
             .with_time(time)
             .with_checkpoints_enabled(checkpoints_enabled)
             .build()
     }
 
+    /// Emulates a node restart after the latest checkpoint was lost, i.e., the
+    /// node recovers from the checkpoint before it (if any).
+    pub fn restart_node_without_latest_checkpoint(self) -> Self {
+        let state_layout = self.state_manager.state_layout();
+        if let Some(height) = state_layout
+            .checkpoint_heights()
+            .expect("failed to list checkpoints")
+            .pop()
+        {
+            state_layout
+                .force_remove_checkpoint(height)
+                .expect("failed to remove checkpoint");
+        }
+        self.restart_node()
+    }
+
     /// Same as [restart_node], but allows overwriting the LSMT flag.
     pub fn restart_node_with_lsmt_override(self, lsmt_override: Option<FlagStatus>) -> Self {
         // We must drop self before setup_form_dir so that we don't have two StateManagers pointing
@@ -1316,7 +1428,7 @@ impl StateMachine {
         let (state_dir, nonce, time, checkpoints_enabled) = self.into_components();
 
         StateMachineBuilder::new()
-            .with_state_dir(state_dir)
+            .with_state_machine_state_dir(state_dir)
             .with_nonce(nonce)
             .with_time(time)
             .with_checkpoints_enabled(checkpoints_enabled)
@@ -1332,7 +1444,7 @@ impl StateMachine {
         let (state_dir, nonce, time, checkpoints_enabled) = self.into_components();
 
         StateMachineBuilder::new()
-            .with_state_dir(state_dir)
+            .with_state_machine_state_dir(state_dir)
             .with_nonce(nonce)
             .with_time(time)
             .with_config(Some(config))
@@ -1383,6 +1495,40 @@ impl StateMachine {
         })
     }
 
+    /// Reads the given paths from the latest certified state and returns
+    /// a certificate signed with the subnet's threshold key.
+    ///
+    /// As on a replica, the `time` path is always included in the
+    /// certificate, even if it was not requested explicitly.
+    pub fn read_state(
+        &self,
+        mut paths: Vec<LabeledTreePath>,
+        delegation: Option<CertificateDelegation>,
+    ) -> Result<Certificate, String> {
+        self.certify_latest_state();
+        let certified_state_reader = match self.state_manager.get_certified_state_snapshot() {
+            Some(reader) => reader,
+            None => {
+                return Err("No certified state available.".to_string());
+            }
+        };
+        paths.push(LabeledTreePath::from(Label::from("time")));
+        let labeled_tree = sparse_labeled_tree_from_paths(&paths)
+            .map_err(|_| "Failed to parse requested paths: path is too long.".to_string())?;
+        let (tree, certification) = match certified_state_reader.read_certified_state(&labeled_tree)
+        {
+            Some(r) => r,
+            None => {
+                return Err("Certified state could not be read.".to_string());
+            }
+        };
+        Ok(Certificate {
+            tree,
+            signature: Blob(certification.signed.signature.signature.get().0),
+            delegation,
+        })
+    }
+
     /// If the argument is true, the state machine will create an on-disk
     /// checkpoint for each new state it creates.
     ///
@@ -1569,11 +1715,46 @@ impl StateMachine {
                 originator_reply_callback: id,
                 refund: Cycles::zero(),
                 response_payload: MsgPayload::Data(reply.encode()),
+                deadline: None,
             });
         }
+        payload
+            .consensus_responses
+            .extend(self.sign_with_schnorr_responses(&state));
         self.execute_payload(payload);
     }
 
+    /// Signs all pending `sign_with_schnorr` requests with the secret keys held
+    /// by this state machine and returns the corresponding responses.
+    fn sign_with_schnorr_responses(&self, state: &ReplicatedState) -> Vec<Response> {
+        state
+            .metadata
+            .subnet_call_context_manager
+            .sign_with_schnorr_contexts
+            .iter()
+            .map(|(id, context)| {
+                let response_payload = match self.schnorr_secret_keys.get(&context.key_id) {
+                    Some(secret_key) => {
+                        let signature = secret_key.sign_with_derived_key(context);
+                        MsgPayload::Data(SignWithSchnorrReply { signature }.encode())
+                    }
+                    None => MsgPayload::Reject(RejectContext::new(
+                        RejectCode::CanisterReject,
+                        format!("Subnet does not hold Schnorr key {}.", context.key_id),
+                    )),
+                };
+                Response {
+                    originator: CanisterId::ic_00(),
+                    respondent: CanisterId::ic_00(),
+                    originator_reply_callback: *id,
+                    refund: Cycles::zero(),
+                    response_payload,
+                    deadline: None,
+                }
+            })
+            .collect()
+    }
+
     /// Makes the state machine tick until there are no more messages in the system.
     /// This method is useful if you need to wait for asynchronous canister communication to
     /// complete.
@@ -1605,9 +1786,21 @@ impl StateMachine {
     }
 
     /// Triggers a single round of execution with block payload as an input.
-    pub fn execute_payload(&self, payload: PayloadBuilder) -> Height {
+    pub fn execute_payload(&self, mut payload: PayloadBuilder) -> Height {
+        let fault_injector = self.fault_injector.read().unwrap().clone();
+        if let Some(fault_injector) = &fault_injector {
+            if !fault_injector.before_round(self, &mut payload.ingress_messages) {
+                return self.state_manager.latest_state_height();
+            }
+        }
+
         let batch_number = self.message_routing.expected_batch_height();
 
+        let bitcoin_adapter_responses = match self.bitcoin_network.read().unwrap().as_ref() {
+            Some(bitcoin_network) => bitcoin_network.responses(self),
+            None => vec![],
+        };
+
         let mut seed = [0u8; 32];
         // use the batch number to seed randomness
         seed[..8].copy_from_slice(batch_number.get().to_le_bytes().as_slice());
@@ -1618,11 +1811,12 @@ impl StateMachine {
             messages: BatchMessages {
                 signed_ingress_msgs: payload.ingress_messages,
                 certified_stream_slices: payload.xnet_payload.stream_slices,
-                bitcoin_adapter_responses: vec![],
+                bitcoin_adapter_responses,
                 query_stats: payload.query_stats,
             },
             randomness: Randomness::from(seed),
             ecdsa_subnet_public_keys: self.ecdsa_subnet_public_keys.clone(),
+            schnorr_subnet_public_keys: self.schnorr_subnet_public_keys.clone(),
             ecdsa_quadruple_ids: BTreeMap::new(),
             registry_version: self.registry_client.get_latest_version(),
             time: Time::from_nanos_since_unix_epoch(self.time.load(Ordering::Relaxed)),
@@ -1643,9 +1837,34 @@ impl StateMachine {
             batch_number
         );
 
+        if let Some(fault_injector) = &fault_injector {
+            fault_injector.after_round(self);
+        }
+
         batch_number
     }
 
+    /// Sets the fault injector applying faults to the rounds executed by this
+    /// state machine. The same fault injector is typically shared by all
+    /// subnets of a multi-subnet test.
+    pub fn set_fault_injector(&self, fault_injector: Arc<FaultInjector>) {
+        *self.fault_injector.write().unwrap() = Some(fault_injector);
+    }
+
+    /// Sets the simulated Bitcoin network answering the requests the Bitcoin
+    /// canister on this subnet sends to the Bitcoin adapter. The requests
+    /// pending at the beginning of a round are answered in that round.
+    pub fn set_bitcoin_network(&self, bitcoin_network: Arc<SimulatedBitcoinNetwork>) {
+        *self.bitcoin_network.write().unwrap() = Some(bitcoin_network);
+    }
+
+    /// Returns and removes the Wasm profiles of the messages executed so far.
+    /// Profiles are only collected if `wasm_profiling` is enabled in the
+    /// embedders config of the `StateMachineConfig`.
+    pub fn take_wasm_profiles(&self) -> Vec<CanisterWasmProfile> {
+        self.wasm_profiles.take()
+    }
+
     pub fn execute_block_with_xnet_payload(&self, xnet_payload: XNetPayload) {
         self.execute_payload(PayloadBuilder::new().xnet_payload(xnet_payload));
     }
@@ -2653,6 +2872,16 @@ impl StateMachine {
             .clone()
     }
 
+    /// Returns sign with Schnorr contexts from internal subnet call context manager.
+    pub fn sign_with_schnorr_contexts(&self) -> BTreeMap<CallbackId, SignWithSchnorrContext> {
+        let state = self.state_manager.get_latest_state().take();
+        state
+            .metadata
+            .subnet_call_context_manager
+            .sign_with_schnorr_contexts
+            .clone()
+    }
+
     /// Returns canister HTTP request contexts from internal subnet call context manager.
     pub fn canister_http_request_contexts(
         &self,
@@ -2704,6 +2933,68 @@ fn sign_prehashed_message_with_derived_key(
     signature.to_vec()
 }
 
+/// A threshold Schnorr master secret key held by the state machine.
+///
+/// Only BIP340 keys are supported, as threshold Ed25519 keys are not
+/// supported by the replica either.
+struct SchnorrSecretKey {
+    secret_key: PrivateKey,
+}
+
+impl SchnorrSecretKey {
+    /// Deterministically derives a master secret key from the key id.
+    ///
+    /// Please do not use these keys anywhere outside of tests.
+    fn new_for_key_id(key_id: &SchnorrKeyId) -> Self {
+        assert_eq!(
+            key_id.algorithm,
+            SchnorrAlgorithm::Bip340Secp256k1,
+            "Unsupported Schnorr key {}: only BIP340 keys are supported.",
+            key_id
+        );
+        let seed = ic_crypto_sha2::Sha256::hash(key_id.to_string().as_bytes());
+        Self {
+            secret_key: PrivateKey::deserialize_sec1(&seed)
+                .expect("failed to derive BIP340 secret key"),
+        }
+    }
+
+    fn master_public_key(&self) -> MasterSchnorrPublicKey {
+        MasterSchnorrPublicKey {
+            algorithm_id: AlgorithmId::SchnorrSecp256k1,
+            public_key: self.secret_key.public_key().serialize_sec1(true),
+        }
+    }
+
+    /// Signs the message of the context with the key derived for the
+    /// caller and derivation path of the context.
+    fn sign_with_derived_key(&self, context: &SignWithSchnorrContext) -> Vec<u8> {
+        // We use the same chain code of 32 zeros as for ECDSA.
+        const CHAIN_CODE: &[u8] = &[0; 32];
+        let derivation_path = DerivationPath::new(
+            std::iter::once(context.request.sender.get().as_slice().to_vec())
+                .chain(context.derivation_path.clone())
+                .map(DerivationIndex)
+                .collect::<Vec<_>>(),
+        );
+        let derived_private_key_bytes = derivation_path
+            .private_key_derivation(&self.secret_key.serialize_sec1(), CHAIN_CODE)
+            .expect("couldn't derive BIP340 private key");
+        let derived_private_key =
+            PrivateKey::deserialize_sec1(&derived_private_key_bytes.derived_private_key)
+                .expect("couldn't deserialize to sec1 private key");
+        let message: [u8; 32] = context
+            .message
+            .as_slice()
+            .try_into()
+            .expect("BIP340 messages are checked to be 32 bytes by execution");
+        let mut rng = StdRng::from_seed(context.pseudo_random_id);
+        derived_private_key
+            .sign_message_with_bip340(&message, &mut rng)
+            .to_vec()
+    }
+}
+
 #[derive(Clone)]
 pub struct PayloadBuilder {
     expiry_time: Time,
@@ -2813,6 +3104,7 @@ impl PayloadBuilder {
             originator_reply_callback: id,
             refund: Cycles::zero(),
             response_payload: MsgPayload::Data(payload.encode()),
+            deadline: None,
         });
         self
     }
@@ -2829,6 +3121,7 @@ impl PayloadBuilder {
             originator_reply_callback: id,
             refund: Cycles::zero(),
             response_payload: MsgPayload::Reject(RejectContext::new(code, message)),
+            deadline: None,
         });
         self
     }
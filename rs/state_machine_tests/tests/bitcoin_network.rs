@@ -0,0 +1,181 @@
+use bitcoin::consensus::{deserialize, serialize};
+use bitcoin::hashes::Hash;
+use bitcoin::{Address, Block, Script, Transaction};
+use ic_config::execution_environment::BITCOIN_TESTNET_CANISTER_ID;
+use ic_ic00_types::{
+    BitcoinGetSuccessorsArgs, BitcoinGetSuccessorsRequestInitial, BitcoinGetSuccessorsResponse,
+    BitcoinNetwork, BitcoinSendTransactionInternalArgs, Method, Payload, IC_00,
+};
+use ic_state_machine_tests::{SimulatedBitcoinNetwork, StateMachine, StateMachineBuilder};
+use ic_types::{ingress::WasmResult, CanisterId, Cycles};
+use ic_universal_canister::{call_args, wasm, UNIVERSAL_CANISTER_WASM};
+use std::str::FromStr;
+
+const INITIAL_CYCLES_BALANCE: Cycles = Cycles::new(100_000_000_000_000);
+
+// Installs a universal canister under the ID of the Bitcoin testnet canister,
+// which has privileged access to the Bitcoin adapter APIs.
+fn setup() -> (StateMachine, CanisterId) {
+    let bitcoin_canister_id = CanisterId::from_str(BITCOIN_TESTNET_CANISTER_ID).unwrap();
+    let env = StateMachineBuilder::new()
+        .with_default_canister_range()
+        .with_extra_canister_range(bitcoin_canister_id..=bitcoin_canister_id)
+        .build();
+    let canister_id = env.create_canister_with_cycles(
+        Some(bitcoin_canister_id.get()),
+        INITIAL_CYCLES_BALANCE,
+        None,
+    );
+    env.install_existing_canister(canister_id, UNIVERSAL_CANISTER_WASM.to_vec(), vec![])
+        .unwrap();
+    (env, canister_id)
+}
+
+fn call_adapter(
+    env: &StateMachine,
+    canister_id: CanisterId,
+    method: Method,
+    arg: Vec<u8>,
+) -> WasmResult {
+    env.execute_ingress(
+        canister_id,
+        "update",
+        wasm()
+            .call_simple(
+                IC_00,
+                method,
+                call_args()
+                    .other_side(arg)
+                    .on_reject(wasm().reject_message().reject()),
+            )
+            .build(),
+    )
+    .unwrap()
+}
+
+fn get_successors(
+    env: &StateMachine,
+    canister_id: CanisterId,
+    network: BitcoinNetwork,
+    anchor: bitcoin::BlockHash,
+    processed: &[bitcoin::BlockHash],
+) -> Result<(Vec<Block>, usize), String> {
+    let arg = BitcoinGetSuccessorsArgs::Initial(BitcoinGetSuccessorsRequestInitial {
+        network,
+        anchor: anchor.as_inner().to_vec(),
+        processed_block_hashes: processed
+            .iter()
+            .map(|hash| hash.as_inner().to_vec())
+            .collect(),
+    });
+    match call_adapter(env, canister_id, Method::BitcoinGetSuccessors, arg.encode()) {
+        WasmResult::Reply(bytes) => match BitcoinGetSuccessorsResponse::decode(&bytes).unwrap() {
+            BitcoinGetSuccessorsResponse::Complete(response) => Ok((
+                response
+                    .blocks
+                    .iter()
+                    .map(|block| deserialize(block).unwrap())
+                    .collect(),
+                response.next.len(),
+            )),
+            response => panic!("Unexpected response {:?}", response),
+        },
+        WasmResult::Reject(message) => Err(message),
+    }
+}
+
+#[test]
+fn bitcoin_network_serves_blocks_and_reorgs() {
+    let (env, canister_id) = setup();
+    let network = SimulatedBitcoinNetwork::new();
+    env.set_bitcoin_network(network.clone());
+
+    let genesis = network.genesis();
+    let address = Address::p2wsh(&Script::new(), bitcoin::Network::Regtest);
+    let deposit = network.mine_block(&[(address.clone(), 100_000)], vec![]);
+    network.mine_empty_blocks(2);
+    assert_eq!(network.tip_height(), 3);
+
+    let (blocks, next) =
+        get_successors(&env, canister_id, BitcoinNetwork::Regtest, genesis, &[]).unwrap();
+    assert_eq!(blocks.len(), 3);
+    assert_eq!(next, 0);
+    assert_eq!(blocks[0].block_hash(), deposit);
+    let output = &blocks[0].txdata[0].output[0];
+    assert_eq!(output.value, 100_000);
+    assert_eq!(output.script_pubkey, address.script_pubkey());
+
+    // A longer fork without the deposit replaces the chain.
+    let fork = network.mine_block_on(genesis, &[], vec![]);
+    assert_ne!(fork, deposit);
+    let mut tip = fork;
+    for _ in 0..3 {
+        tip = network.mine_block_on(tip, &[], vec![]);
+    }
+    assert_eq!(network.tip(), tip);
+    assert_eq!(network.tip_height(), 4);
+
+    let processed: Vec<_> = blocks.iter().map(|block| block.block_hash()).collect();
+    let (blocks, _) = get_successors(
+        &env,
+        canister_id,
+        BitcoinNetwork::Regtest,
+        genesis,
+        &processed,
+    )
+    .unwrap();
+    assert_eq!(blocks.len(), 4);
+    assert_eq!(blocks[0].block_hash(), fork);
+    assert_eq!(blocks[3].block_hash(), tip);
+
+    // Only regtest requests are served.
+    get_successors(&env, canister_id, BitcoinNetwork::Testnet, genesis, &[]).unwrap_err();
+}
+
+#[test]
+fn bitcoin_network_captures_sent_transactions() {
+    let (env, canister_id) = setup();
+    let network = SimulatedBitcoinNetwork::new();
+    env.set_bitcoin_network(network.clone());
+
+    let address = Address::p2wsh(&Script::new(), bitcoin::Network::Regtest);
+    let block = network.mine_block(&[(address, 100_000)], vec![]);
+    let transaction: Transaction = network.block(&block).unwrap().txdata[0].clone();
+
+    let arg = BitcoinSendTransactionInternalArgs {
+        network: BitcoinNetwork::Regtest,
+        transaction: serialize(&transaction),
+    };
+    assert!(matches!(
+        call_adapter(
+            &env,
+            canister_id,
+            Method::BitcoinSendTransactionInternal,
+            arg.encode()
+        ),
+        WasmResult::Reply(_)
+    ));
+    assert_eq!(network.sent_transactions(), vec![transaction.clone()]);
+    assert_eq!(network.mempool(), vec![transaction.clone()]);
+
+    let confirmed = network.mine_mempool();
+    assert_eq!(network.block(&confirmed).unwrap().txdata[1], transaction);
+    assert!(network.mempool().is_empty());
+    assert_eq!(network.sent_transactions().len(), 1);
+
+    // Transactions that cannot be deserialized are rejected.
+    let arg = BitcoinSendTransactionInternalArgs {
+        network: BitcoinNetwork::Regtest,
+        transaction: vec![1, 2, 3],
+    };
+    assert!(matches!(
+        call_adapter(
+            &env,
+            canister_id,
+            Method::BitcoinSendTransactionInternal,
+            arg.encode()
+        ),
+        WasmResult::Reject(_)
+    ));
+    assert_eq!(network.sent_transactions().len(), 1);
+}
@@ -3,10 +3,13 @@ use ic_ic00_types::{EcdsaCurve, EcdsaKeyId};
 use ic_registry_proto_data_provider::ProtoRegistryDataProvider;
 use ic_registry_routing_table::{CanisterIdRange, RoutingTable, CANISTER_IDS_PER_SUBNET};
 use ic_registry_subnet_type::SubnetType;
-use ic_state_machine_tests::{StateMachine, StateMachineBuilder, StateMachineConfig};
+use ic_state_machine_tests::{
+    CanisterFault, FaultInjector, MessageFault, MessageFaultRule, StateMachine,
+    StateMachineBuilder, StateMachineConfig,
+};
 use ic_test_utilities::types::ids::{subnet_test_id, user_test_id};
 use ic_types::{
-    ingress::{IngressStatus, WasmResult},
+    ingress::{IngressState, IngressStatus, WasmResult},
     CanisterId, Cycles, SubnetId,
 };
 use ic_universal_canister::{wasm, CallArgs, UNIVERSAL_CANISTER_WASM};
@@ -386,3 +389,137 @@ fn counter_canister_call_test() {
         _ => panic!("unreachable"),
     };
 }
+
+#[test]
+fn fault_injection_test() {
+    const MAX_TICKS: usize = 100;
+    let user_id = user_test_id(1).get();
+
+    // Set up two subnets as in `counter_canister_call_test`.
+    let subnet_id1 = subnet_test_id(1);
+    let subnet_id2 = subnet_test_id(2);
+    let mut routing_table = RoutingTable::new();
+    routing_table
+        .insert(
+            CanisterIdRange {
+                start: CanisterId::from_u64(0),
+                end: CanisterId::from_u64(CANISTER_IDS_PER_SUBNET - 1),
+            },
+            subnet_id1,
+        )
+        .unwrap();
+    routing_table
+        .insert(
+            CanisterIdRange {
+                start: CanisterId::from_u64(CANISTER_IDS_PER_SUBNET),
+                end: CanisterId::from_u64(2 * CANISTER_IDS_PER_SUBNET - 1),
+            },
+            subnet_id2,
+        )
+        .unwrap();
+    let subnet_list = vec![subnet_id1, subnet_id2];
+    let registry_data_provider = Arc::new(ProtoRegistryDataProvider::new());
+    let subnets = Arc::new(RwLock::new(BTreeMap::new()));
+    let env1 = test_setup(
+        subnets.clone(),
+        subnet_id1,
+        SubnetType::Application,
+        subnet_list.clone(),
+        routing_table.clone(),
+        registry_data_provider.clone(),
+    );
+    let env2 = test_setup(
+        subnets.clone(),
+        subnet_id2,
+        SubnetType::Application,
+        subnet_list,
+        routing_table,
+        registry_data_provider.clone(),
+    );
+    env1.reload_registry();
+    env2.reload_registry();
+
+    let canister_id1 = env1
+        .install_canister_with_cycles(
+            UNIVERSAL_CANISTER_WASM.to_vec(),
+            vec![],
+            None,
+            INITIAL_CYCLES_BALANCE,
+        )
+        .unwrap();
+    let canister_id2 = env2
+        .install_canister_with_cycles(
+            UNIVERSAL_CANISTER_WASM.to_vec(),
+            vec![],
+            None,
+            INITIAL_CYCLES_BALANCE,
+        )
+        .unwrap();
+
+    let fault_injector = FaultInjector::new(42);
+    env1.set_fault_injector(fault_injector.clone());
+    env2.set_fault_injector(fault_injector.clone());
+    let call_canister_id2 = || {
+        env1.submit_ingress_as(
+            user_id,
+            canister_id1,
+            "update",
+            wasm()
+                .inter_update(
+                    canister_id2,
+                    CallArgs::default().other_side(wasm().reply_data(b"pong")),
+                )
+                .build(),
+        )
+        .unwrap()
+    };
+
+    // The first call into the 2nd subnet traps and is rejected
+    // with CANISTER_ERROR reject code (5).
+    fault_injector.add_canister_fault(canister_id2, None, 0, CanisterFault::Trap);
+    let msg_id = call_canister_id2();
+    env1.execute_round();
+    env1.execute_round();
+    let wasm_result = env1.await_ingress(msg_id, MAX_TICKS).unwrap();
+    match wasm_result {
+        WasmResult::Reject(reject) => assert_eq!(reject.as_bytes(), 5_u32.to_le_bytes().to_vec()),
+        _ => panic!("unreachable"),
+    };
+
+    // The second call into the 2nd subnet is dropped, so it never completes.
+    fault_injector.add_message_fault(MessageFaultRule::new(MessageFault::Drop).with_max_count(1));
+    let msg_id = call_canister_id2();
+    for _ in 0..3 {
+        env1.execute_round();
+        env2.execute_round();
+    }
+    assert!(matches!(
+        env1.ingress_status(&msg_id),
+        IngressStatus::Known {
+            state: IngressState::Processing,
+            ..
+        }
+    ));
+
+    // The third call only completes once the stalled 2nd subnet makes progress again.
+    fault_injector.stall_subnet(subnet_id2, 2);
+    let msg_id = call_canister_id2();
+    env1.execute_round();
+    env2.execute_round();
+    env2.execute_round();
+    env1.execute_round();
+    assert!(matches!(
+        env1.ingress_status(&msg_id),
+        IngressStatus::Known {
+            state: IngressState::Processing,
+            ..
+        }
+    ));
+    env2.execute_round();
+    env1.execute_round();
+    let wasm_result = env1.await_ingress(msg_id, MAX_TICKS).unwrap();
+    match wasm_result {
+        WasmResult::Reply(bytes) => assert_eq!(bytes, b"pong".to_vec()),
+        _ => panic!("unreachable"),
+    };
+}
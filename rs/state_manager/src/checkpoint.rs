@@ -9,11 +9,17 @@ use ic_logger::error;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::page_map::PageAllocatorFileDescriptor;
 use ic_replicated_state::{
-    canister_state::execution_state::WasmBinary, page_map::PageMap, CanisterMetrics, CanisterState,
-    ExecutionState, ReplicatedState, SchedulerState, SystemState,
+    canister_snapshots::{
+        CanisterSnapshot, CanisterSnapshots, ExecutionStateSnapshot, MemorySnapshot, SnapshotId,
+    },
+    canister_state::{execution_state::WasmBinary, system_state::wasm_chunk_store::WasmChunkStore},
+    page_map::PageMap,
+    CanisterMetrics, CanisterState, ExecutionState, ReplicatedState, SchedulerState, SystemState,
 };
 use ic_replicated_state::{CheckpointLoadingMetrics, Memory};
-use ic_state_layout::{CanisterLayout, CanisterStateBits, CheckpointLayout, ReadOnly, ReadPolicy};
+use ic_state_layout::{
+    CanisterLayout, CanisterSnapshotBits, CanisterStateBits, CheckpointLayout, ReadOnly, ReadPolicy,
+};
 use ic_types::batch::RawQueryStats;
 use ic_types::{CanisterTimer, Height, LongExecutionMode, Time};
 use ic_utils::thread::parallel_map;
@@ -77,6 +83,17 @@ pub(crate) fn make_checkpoint(
         })
         .unwrap();
 
+    tip_channel
+        .send(TipRequest::FilterTipSnapshots {
+            height,
+            ids: state
+                .canister_snapshots
+                .iter()
+                .map(|(snapshot_id, _)| *snapshot_id)
+                .collect(),
+        })
+        .unwrap();
+
     let cp = {
         let _timer = metrics
             .make_checkpoint_step_duration
@@ -165,6 +182,7 @@ pub fn load_checkpoint<P: ReadPolicy + Send + Sync>(
             proto_err: err.to_string(),
         };
 
+    let next_snapshot_id;
     let metadata = {
         let _timer = metrics
             .load_checkpoint_step_duration
@@ -176,6 +194,7 @@ pub fn load_checkpoint<P: ReadPolicy + Send + Sync>(
             ic_replicated_state::IngressHistoryState::try_from(ingress_history_proto)
                 .map_err(|err| into_checkpoint_error("IngressHistoryState".into(), err))?;
         let metadata_proto = checkpoint_layout.system_metadata().deserialize()?;
+        next_snapshot_id = metadata_proto.next_snapshot_id;
         let mut metadata = ic_replicated_state::SystemMetadata::try_from((
             metadata_proto,
             metrics as &dyn CheckpointLoadingMetrics,
@@ -214,7 +233,7 @@ pub fn load_checkpoint<P: ReadPolicy + Send + Sync>(
         RawQueryStats::default()
     };
 
-    let canister_states = {
+    let mut canister_states = {
         let _timer = metrics
             .load_checkpoint_step_duration
             .with_label_values(&["canister_states"])
@@ -258,12 +277,106 @@ pub fn load_checkpoint<P: ReadPolicy + Send + Sync>(
         canister_states
     };
 
-    let state =
-        ReplicatedState::new_from_checkpoint(canister_states, metadata, subnet_queues, query_stats);
+    let canister_snapshots = {
+        let _timer = metrics
+            .load_checkpoint_step_duration
+            .with_label_values(&["canister_snapshots"])
+            .start_timer();
+
+        let mut snapshots = BTreeMap::new();
+        for snapshot_id in checkpoint_layout.snapshot_ids()? {
+            let snapshot = load_snapshot_from_checkpoint(
+                checkpoint_layout,
+                &snapshot_id,
+                Arc::clone(&fd_factory),
+            )?;
+            snapshots.insert(snapshot_id, Arc::new(snapshot));
+        }
+
+        CanisterSnapshots::new(SnapshotId::new(next_snapshot_id), snapshots)
+    };
+
+    for canister_state in canister_states.values_mut() {
+        canister_state.system_state.snapshots_memory_usage =
+            canister_snapshots.memory_usage(canister_state.canister_id());
+    }
+
+    let state = ReplicatedState::new_from_checkpoint(
+        canister_states,
+        metadata,
+        subnet_queues,
+        query_stats,
+        canister_snapshots,
+    );
 
     Ok(state)
 }
 
+fn load_snapshot_from_checkpoint<P: ReadPolicy>(
+    checkpoint_layout: &CheckpointLayout<P>,
+    snapshot_id: &SnapshotId,
+    fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
+) -> Result<CanisterSnapshot, CheckpointError> {
+    let snapshot_layout = checkpoint_layout.snapshot(snapshot_id)?;
+    let height = checkpoint_layout.height();
+    let canister_snapshot_bits: CanisterSnapshotBits = CanisterSnapshotBits::try_from(
+        snapshot_layout.snapshot().deserialize()?,
+    )
+    .map_err(|err| CheckpointError::ProtoError {
+        path: snapshot_layout.raw_path(),
+        field: format!(
+            "canister_snapshots[{}]::canister_snapshot_bits",
+            snapshot_id
+        ),
+        proto_err: err.to_string(),
+    })?;
+
+    let wasm_memory = MemorySnapshot::new(
+        PageMap::open(
+            &snapshot_layout.vmemory_0(),
+            &snapshot_layout.vmemory_0_overlays()?,
+            height,
+            Arc::clone(&fd_factory),
+        )?,
+        canister_snapshot_bits.wasm_memory_size,
+    );
+    let stable_memory = MemorySnapshot::new(
+        PageMap::open(
+            &snapshot_layout.stable_memory_blob(),
+            &snapshot_layout.stable_memory_overlays()?,
+            height,
+            Arc::clone(&fd_factory),
+        )?,
+        canister_snapshot_bits.stable_memory_size,
+    );
+    let wasm_binary = snapshot_layout
+        .wasm()
+        .deserialize(canister_snapshot_bits.binary_hash)?;
+    let chunk_store = WasmChunkStore::from_checkpoint(
+        PageMap::open(
+            &snapshot_layout.wasm_chunk_store(),
+            &snapshot_layout.wasm_chunk_store_overlays()?,
+            height,
+            Arc::clone(&fd_factory),
+        )?,
+        canister_snapshot_bits.wasm_chunk_store_metadata,
+    );
+
+    Ok(CanisterSnapshot::new(
+        canister_snapshot_bits.canister_id,
+        canister_snapshot_bits.taken_at_timestamp,
+        canister_snapshot_bits.canister_version,
+        canister_snapshot_bits.certified_data,
+        chunk_store,
+        ExecutionStateSnapshot {
+            wasm_binary,
+            exported_globals: canister_snapshot_bits.exported_globals,
+            stable_memory,
+            wasm_memory,
+        },
+    ))
+}
+
 #[derive(Default)]
 pub struct LoadCanisterMetrics {
     durations: BTreeMap<&'static str, Duration>,
@@ -410,6 +523,8 @@ pub fn load_canister_state<P: ReadPolicy>(
         wasm_chunk_store_data,
         canister_state_bits.wasm_chunk_store_metadata,
         canister_state_bits.log_visibility,
+        canister_state_bits.wasm_memory_limit,
+        canister_state_bits.canister_log,
     );
 
     let canister_state = CanisterState {
@@ -43,6 +43,7 @@ use ic_protobuf::proxy::{ProtoProxy, ProxyDecodeError};
 use ic_protobuf::{messaging::xnet::v1, state::v1 as pb};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
+    canister_snapshots::{SnapshotId, SnapshotOperation},
     canister_state::execution_state::SandboxMemory,
     page_map::{PersistenceError, StorageMetrics},
     PageIndex, PageMap, ReplicatedState,
@@ -1050,6 +1051,9 @@ pub enum PageMapType {
     WasmMemory(CanisterId),
     StableMemory(CanisterId),
     WasmChunkStore(CanisterId),
+    SnapshotWasmMemory(SnapshotId),
+    SnapshotStableMemory(SnapshotId),
+    SnapshotWasmChunkStore(SnapshotId),
 }
 
 impl PageMapType {
@@ -1063,6 +1067,11 @@ impl PageMapType {
                 result.push(Self::StableMemory(id.to_owned()));
             }
         }
+        for (id, _snapshot) in state.canister_snapshots.iter() {
+            result.push(Self::SnapshotWasmMemory(id.to_owned()));
+            result.push(Self::SnapshotStableMemory(id.to_owned()));
+            result.push(Self::SnapshotWasmChunkStore(id.to_owned()));
+        }
 
         result
     }
@@ -1076,6 +1085,9 @@ impl PageMapType {
             PageMapType::WasmMemory(id) => Ok(layout.canister(id)?.vmemory_0()),
             PageMapType::StableMemory(id) => Ok(layout.canister(id)?.stable_memory_blob()),
             PageMapType::WasmChunkStore(id) => Ok(layout.canister(id)?.wasm_chunk_store()),
+            PageMapType::SnapshotWasmMemory(id) => Ok(layout.snapshot(id)?.vmemory_0()),
+            PageMapType::SnapshotStableMemory(id) => Ok(layout.snapshot(id)?.stable_memory_blob()),
+            PageMapType::SnapshotWasmChunkStore(id) => Ok(layout.snapshot(id)?.wasm_chunk_store()),
         }
     }
 
@@ -1094,6 +1106,15 @@ impl PageMapType {
             PageMapType::WasmChunkStore(id) => {
                 Ok(layout.canister(id)?.wasm_chunk_store_overlay(height))
             }
+            PageMapType::SnapshotWasmMemory(id) => {
+                Ok(layout.snapshot(id)?.vmemory_0_overlay(height))
+            }
+            PageMapType::SnapshotStableMemory(id) => {
+                Ok(layout.snapshot(id)?.stable_memory_overlay(height))
+            }
+            PageMapType::SnapshotWasmChunkStore(id) => {
+                Ok(layout.snapshot(id)?.wasm_chunk_store_overlay(height))
+            }
         }
     }
 
@@ -1109,6 +1130,11 @@ impl PageMapType {
             PageMapType::WasmMemory(id) => layout.canister(id)?.vmemory_0_overlays(),
             PageMapType::StableMemory(id) => layout.canister(id)?.stable_memory_overlays(),
             PageMapType::WasmChunkStore(id) => layout.canister(id)?.wasm_chunk_store_overlays(),
+            PageMapType::SnapshotWasmMemory(id) => layout.snapshot(id)?.vmemory_0_overlays(),
+            PageMapType::SnapshotStableMemory(id) => layout.snapshot(id)?.stable_memory_overlays(),
+            PageMapType::SnapshotWasmChunkStore(id) => {
+                layout.snapshot(id)?.wasm_chunk_store_overlays()
+            }
         }
     }
 
@@ -1128,6 +1154,18 @@ impl PageMapType {
             PageMapType::WasmChunkStore(id) => state
                 .canister_state(id)
                 .map(|can| can.system_state.wasm_chunk_store.page_map()),
+            PageMapType::SnapshotWasmMemory(id) => state
+                .canister_snapshots
+                .get(*id)
+                .map(|snap| &snap.wasm_memory().page_map),
+            PageMapType::SnapshotStableMemory(id) => state
+                .canister_snapshots
+                .get(*id)
+                .map(|snap| &snap.stable_memory().page_map),
+            PageMapType::SnapshotWasmChunkStore(id) => state
+                .canister_snapshots
+                .get(*id)
+                .map(|snap| snap.chunk_store().page_map()),
         }
     }
 
@@ -1147,6 +1185,18 @@ impl PageMapType {
             PageMapType::WasmChunkStore(id) => state
                 .canister_state_mut(id)
                 .map(|can| can.system_state.wasm_chunk_store.page_map_mut()),
+            PageMapType::SnapshotWasmMemory(id) => state
+                .canister_snapshots
+                .get_mut(*id)
+                .map(|snap| &mut snap.execution_snapshot_mut().wasm_memory.page_map),
+            PageMapType::SnapshotStableMemory(id) => state
+                .canister_snapshots
+                .get_mut(*id)
+                .map(|snap| &mut snap.execution_snapshot_mut().stable_memory.page_map),
+            PageMapType::SnapshotWasmChunkStore(id) => state
+                .canister_snapshots
+                .get_mut(*id)
+                .map(|snap| snap.chunk_store_mut().page_map_mut()),
         }
     }
 }
@@ -1260,6 +1310,20 @@ fn switch_to_checkpoint(tip: &mut ReplicatedState, src: &ReplicatedState) {
             tip_state.stable_memory.sandbox_memory = SandboxMemory::new();
         }
     }
+
+    for ((tip_id, tip_snapshot), (src_id, src_snapshot)) in tip
+        .canister_snapshots
+        .iter_mut()
+        .zip(src.canister_snapshots.iter())
+    {
+        assert_eq!(tip_id, src_id);
+        debug_assert_eq!(
+            tip_snapshot.wasm_binary().as_slice(),
+            src_snapshot.wasm_binary().as_slice()
+        );
+        // Use the Wasm binary backed by the checkpoint file.
+        tip_snapshot.execution_snapshot_mut().wasm_binary = src_snapshot.wasm_binary().clone();
+    }
 }
 
 /// Persists metadata after releasing the write lock
@@ -1919,8 +1983,26 @@ impl StateManagerImpl {
     /// during execution from the last flush.
     fn flush_page_maps(&self, tip_state: &mut ReplicatedState, height: Height) {
         self.metrics.checkpoint_metrics.page_map_flushes.inc();
+        // Snapshot operations are applied to the files in the tip before the deltas are
+        // flushed, as the deltas of the affected PageMaps are relative to the copied files.
+        let snapshot_operations = tip_state.canister_snapshots.take_unflushed_changes();
+        let restored_canisters: BTreeSet<CanisterId> = snapshot_operations
+            .iter()
+            .filter_map(|op| match op {
+                SnapshotOperation::Restore(canister_id, _) => Some(*canister_id),
+                SnapshotOperation::Backup(..) | SnapshotOperation::Delete(_) => None,
+            })
+            .collect();
         let mut pagemaps = Vec::new();
         for entry in PageMapType::list_all(tip_state) {
+            let is_restored = match &entry {
+                PageMapType::WasmMemory(id)
+                | PageMapType::StableMemory(id)
+                | PageMapType::WasmChunkStore(id) => restored_canisters.contains(id),
+                PageMapType::SnapshotWasmMemory(_)
+                | PageMapType::SnapshotStableMemory(_)
+                | PageMapType::SnapshotWasmChunkStore(_) => false,
+            };
             if let Some(page_map) = entry.get_mut(tip_state) {
                 // In cases where a PageMap's data has to be wiped, execution will replace the PageMap with a newly
                 // created one. In these cases, we also need to wipe the data from the file on disk.
@@ -1947,11 +2029,20 @@ impl StateManagerImpl {
                 }
                 // We strip empty unflushed deltas to keep has_stripped_unflushed_deltas() correct
                 page_map.strip_unflushed_delta();
+                // The files of a restored canister were replaced by the files of the snapshot,
+                // so the page deltas no longer describe the difference to the base checkpoint.
+                if is_restored {
+                    page_map.base_height = None;
+                }
             }
         }
-        if !pagemaps.is_empty() {
+        if !pagemaps.is_empty() || !snapshot_operations.is_empty() {
             self.tip_channel
-                .send(TipRequest::FlushPageMapDelta { height, pagemaps })
+                .send(TipRequest::FlushPageMapDelta {
+                    height,
+                    pagemaps,
+                    snapshot_operations,
+                })
                 .unwrap();
             // We flush further when the tip_channel queue is not empty. Meaning we're blind
             // to a request being processed, so we send Noop to signal for the busy Tip Thread.
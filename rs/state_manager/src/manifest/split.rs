@@ -10,8 +10,8 @@ use ic_registry_routing_table::RoutingTable;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::SystemMetadata;
 use ic_state_layout::{
-    canister_id_from_path, INGRESS_HISTORY_FILE, SPLIT_MARKER_FILE, STATS_FILE, SUBNET_QUEUES_FILE,
-    SYSTEM_METADATA_FILE,
+    canister_id_from_path, INGRESS_HISTORY_FILE, SNAPSHOTS_DIR, SPLIT_MARKER_FILE, STATS_FILE,
+    SUBNET_QUEUES_FILE, SYSTEM_METADATA_FILE,
 };
 use ic_types::state_sync::StateSyncVersion;
 use ic_types::Time;
@@ -95,6 +95,10 @@ pub fn split_manifest(
                     ),
                 });
             }
+        } else if path.starts_with(SNAPSHOTS_DIR) {
+            // Canister snapshots are preserved unmodified on subnet A' only. Snapshots
+            // of canisters migrated to subnet B are dropped after the split.
+            manifest_a.append(file, chunks);
         } else {
             match path.to_str() {
                 Some(INGRESS_HISTORY_FILE) => {
@@ -14,6 +14,7 @@ use ic_protobuf::state::{
     stats::v1::Stats,
     system_metadata::v1::{SplitFrom, SystemMetadata},
 };
+use ic_replicated_state::canister_snapshots::{CanisterSnapshot, SnapshotId, SnapshotOperation};
 use ic_replicated_state::page_map::{
     MergeCandidate, PersistDestination, PersistenceError, StorageMetrics, MAX_NUMBER_OF_FILES,
 };
@@ -23,8 +24,8 @@ use ic_replicated_state::{
     NumWasmPages, PageMap, ReplicatedState,
 };
 use ic_state_layout::{
-    error::LayoutError, CanisterStateBits, CheckpointLayout, ExecutionStateBits, ReadOnly,
-    RwPolicy, StateLayout, TipHandler,
+    error::LayoutError, CanisterSnapshotBits, CanisterStateBits, CheckpointLayout,
+    ExecutionStateBits, ReadOnly, RwPolicy, StateLayout, TipHandler,
 };
 use ic_sys::fs::defrag_file_partially;
 use ic_types::{malicious_flags::MaliciousFlags, CanisterId, Height};
@@ -80,11 +81,18 @@ pub(crate) enum TipRequest {
         height: Height,
         ids: BTreeSet<CanisterId>,
     },
-    /// Flush PageMaps's unflushed delta on disc.
+    /// Filter snapshots in tip. Remove ones not present in the set.
+    /// State: !Empty
+    FilterTipSnapshots {
+        height: Height,
+        ids: BTreeSet<SnapshotId>,
+    },
+    /// Apply snapshot operations and flush PageMaps's unflushed delta on disc.
     /// State: ReadyForPageDeltas(h) -> ReadyForPageDeltas(height), height >= h
     FlushPageMapDelta {
         height: Height,
         pagemaps: Vec<PageMapToFlush>,
+        snapshot_operations: Vec<SnapshotOperation>,
     },
     /// Reset tip folder to the checkpoint with given height.
     /// Merge overlays in tip folder if necessary.
@@ -210,6 +218,21 @@ pub(crate) fn spawn_tip_thread(
                                     )
                                 });
                         }
+                        TipRequest::FilterTipSnapshots { height, ids } => {
+                            debug_assert_ne!(tip_state, TipState::Empty);
+
+                            let _timer = request_timer(&metrics, "filter_tip_snapshots");
+                            tip_handler
+                                .filter_tip_snapshots(height, &ids)
+                                .unwrap_or_else(|err| {
+                                    fatal!(
+                                        log,
+                                        "Failed to filter tip snapshots for height @{}: {}",
+                                        height,
+                                        err
+                                    )
+                                });
+                        }
                         TipRequest::TipToCheckpoint { height, sender } => {
                             debug_assert_eq!(tip_state, TipState::Serialized(height));
                             debug_assert!(have_latest_manifest);
@@ -250,7 +273,11 @@ pub(crate) fn spawn_tip_thread(
                             }
                         }
 
-                        TipRequest::FlushPageMapDelta { height, pagemaps } => {
+                        TipRequest::FlushPageMapDelta {
+                            height,
+                            pagemaps,
+                            snapshot_operations,
+                        } => {
                             let _timer = request_timer(&metrics, "flush_unflushed_delta");
                             #[cfg(debug_assertions)]
                             match tip_state {
@@ -258,6 +285,20 @@ pub(crate) fn spawn_tip_thread(
                                 _ => panic!("Unexpected tip state: {:?}", tip_state),
                             }
                             tip_state = TipState::ReadyForPageDeltas(height);
+                            apply_snapshot_operations(
+                                &log,
+                                &mut tip_handler,
+                                height,
+                                snapshot_operations,
+                            )
+                            .unwrap_or_else(|err| {
+                                fatal!(
+                                    log,
+                                    "Failed to apply snapshot operations @{}: {}",
+                                    height,
+                                    err
+                                );
+                            });
                             parallel_map(
                                 &mut thread_pool,
                                 pagemaps.into_iter().map(
@@ -769,7 +810,8 @@ fn serialize_to_tip(
     let ingress_history = (&state.system_metadata().ingress_history).into();
     tip.ingress_history().serialize(ingress_history)?;
 
-    let system_metadata: SystemMetadata = state.system_metadata().into();
+    let mut system_metadata: SystemMetadata = state.system_metadata().into();
+    system_metadata.next_snapshot_id = state.canister_snapshots.next_snapshot_id().get();
     tip.system_metadata().serialize(system_metadata)?;
 
     // The split marker is also serialized separately from `SystemMetadata` because
@@ -801,6 +843,206 @@ fn serialize_to_tip(
         result?;
     }
 
+    let results = parallel_map(
+        thread_pool,
+        state.canister_snapshots.iter(),
+        |(snapshot_id, snapshot)| {
+            serialize_snapshot_to_tip(log, snapshot_id, snapshot, tip, metrics, lsmt_storage)
+        },
+    );
+
+    for result in results.into_iter() {
+        result?;
+    }
+
+    Ok(())
+}
+
+fn serialize_snapshot_to_tip(
+    log: &ReplicaLogger,
+    snapshot_id: &SnapshotId,
+    canister_snapshot: &CanisterSnapshot,
+    tip: &CheckpointLayout<RwPolicy<TipHandler>>,
+    metrics: &StorageMetrics,
+    lsmt_storage: FlagStatus,
+) -> Result<(), CheckpointError> {
+    let snapshot_layout = tip.snapshot(snapshot_id)?;
+
+    // Snapshots are immutable, so an existing Wasm file is always up to date.
+    let wasm = snapshot_layout.wasm();
+    if !wasm.raw_path().exists() {
+        match canister_snapshot.wasm_binary().file() {
+            Some(path) => {
+                ic_state_layout::utils::do_copy(log, path, wasm.raw_path()).map_err(|io_err| {
+                    CheckpointError::IoError {
+                        path: path.to_path_buf(),
+                        message: "failed to copy Wasm file".to_string(),
+                        io_err: io_err.to_string(),
+                    }
+                })?;
+            }
+            None => wasm.serialize(canister_snapshot.wasm_binary())?,
+        }
+    }
+
+    let memory_dst = PersistDestination::new(
+        snapshot_layout.vmemory_0(),
+        snapshot_layout.vmemory_0_overlay(tip.height()),
+        lsmt_storage,
+    );
+    let stable_dst = PersistDestination::new(
+        snapshot_layout.stable_memory_blob(),
+        snapshot_layout.stable_memory_overlay(tip.height()),
+        lsmt_storage,
+    );
+    let wasm_chunk_store_dst = PersistDestination::new(
+        snapshot_layout.wasm_chunk_store(),
+        snapshot_layout.wasm_chunk_store_overlay(tip.height()),
+        lsmt_storage,
+    );
+    canister_snapshot
+        .wasm_memory()
+        .page_map
+        .persist_delta(memory_dst, metrics)?;
+    canister_snapshot
+        .stable_memory()
+        .page_map
+        .persist_delta(stable_dst, metrics)?;
+    canister_snapshot
+        .chunk_store()
+        .page_map()
+        .persist_delta(wasm_chunk_store_dst, metrics)?;
+
+    snapshot_layout.snapshot().serialize(
+        (&CanisterSnapshotBits {
+            snapshot_id: *snapshot_id,
+            canister_id: *canister_snapshot.canister_id(),
+            taken_at_timestamp: *canister_snapshot.taken_at_timestamp(),
+            canister_version: canister_snapshot.canister_version(),
+            binary_hash: Some(canister_snapshot.wasm_binary().module_hash().into()),
+            certified_data: canister_snapshot.certified_data().clone(),
+            wasm_chunk_store_metadata: canister_snapshot.chunk_store().metadata().clone(),
+            stable_memory_size: canister_snapshot.stable_memory().size,
+            wasm_memory_size: canister_snapshot.wasm_memory().size,
+            exported_globals: canister_snapshot.exported_globals().clone(),
+        })
+            .into(),
+    )?;
+
+    Ok(())
+}
+
+/// Applies the snapshot operations accumulated since the last flush to the
+/// files in the tip. Must be called before flushing the PageMap deltas of the
+/// same batch, as those deltas are relative to the files copied here.
+fn apply_snapshot_operations(
+    log: &ReplicaLogger,
+    tip_handler: &mut TipHandler,
+    height: Height,
+    snapshot_operations: Vec<SnapshotOperation>,
+) -> Result<(), LayoutError> {
+    if snapshot_operations.is_empty() {
+        return Ok(());
+    }
+    let tip = tip_handler.tip(height)?;
+    for op in snapshot_operations {
+        match op {
+            SnapshotOperation::Backup(canister_id, snapshot_id) => {
+                let canister_layout = tip.canister(&canister_id)?;
+                let snapshot_layout = tip.snapshot(&snapshot_id)?;
+                copy_pagemap_files(
+                    log,
+                    &canister_layout.vmemory_0(),
+                    &canister_layout.vmemory_0_overlays()?,
+                    &snapshot_layout.vmemory_0(),
+                    &[],
+                )?;
+                copy_pagemap_files(
+                    log,
+                    &canister_layout.stable_memory_blob(),
+                    &canister_layout.stable_memory_overlays()?,
+                    &snapshot_layout.stable_memory_blob(),
+                    &[],
+                )?;
+                copy_pagemap_files(
+                    log,
+                    &canister_layout.wasm_chunk_store(),
+                    &canister_layout.wasm_chunk_store_overlays()?,
+                    &snapshot_layout.wasm_chunk_store(),
+                    &[],
+                )?;
+            }
+            SnapshotOperation::Restore(canister_id, snapshot_id) => {
+                let canister_layout = tip.canister(&canister_id)?;
+                let snapshot_layout = tip.snapshot(&snapshot_id)?;
+                copy_pagemap_files(
+                    log,
+                    &snapshot_layout.vmemory_0(),
+                    &snapshot_layout.vmemory_0_overlays()?,
+                    &canister_layout.vmemory_0(),
+                    &canister_layout.vmemory_0_overlays()?,
+                )?;
+                copy_pagemap_files(
+                    log,
+                    &snapshot_layout.stable_memory_blob(),
+                    &snapshot_layout.stable_memory_overlays()?,
+                    &canister_layout.stable_memory_blob(),
+                    &canister_layout.stable_memory_overlays()?,
+                )?;
+                copy_pagemap_files(
+                    log,
+                    &snapshot_layout.wasm_chunk_store(),
+                    &snapshot_layout.wasm_chunk_store_overlays()?,
+                    &canister_layout.wasm_chunk_store(),
+                    &canister_layout.wasm_chunk_store_overlays()?,
+                )?;
+                // The Wasm module of the canister is written again from its
+                // execution state when serializing the tip.
+                canister_layout.wasm().try_delete_file()?;
+            }
+            SnapshotOperation::Delete(snapshot_id) => {
+                let snapshot_path = tip.snapshot(&snapshot_id)?.raw_path();
+                std::fs::remove_dir_all(&snapshot_path).map_err(|err| LayoutError::IoError {
+                    path: snapshot_path,
+                    message: "Cannot remove snapshot.".to_string(),
+                    io_err: err,
+                })?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the PageMap files at `dst_base` and `dst_overlays` by copies of the
+/// PageMap files at `src_base` and `src_overlays`. Overlays keep their file
+/// names and are placed next to `dst_base`.
+fn copy_pagemap_files(
+    log: &ReplicaLogger,
+    src_base: &Path,
+    src_overlays: &[PathBuf],
+    dst_base: &Path,
+    dst_overlays: &[PathBuf],
+) -> Result<(), LayoutError> {
+    delete_pagemap_files(log, dst_base, dst_overlays);
+    let dst_dir = dst_base
+        .parent()
+        .expect("PageMap files are always inside a directory");
+    let copy = |src: &Path, dst: &Path| {
+        ic_state_layout::utils::do_copy(log, src, dst).map_err(|io_err| LayoutError::IoError {
+            path: src.to_path_buf(),
+            message: "Failed to copy PageMap file".to_string(),
+            io_err,
+        })
+    };
+    if src_base.exists() {
+        copy(src_base, dst_base)?;
+    }
+    for overlay in src_overlays {
+        let file_name = overlay
+            .file_name()
+            .expect("Overlay paths always have a file name");
+        copy(overlay, &dst_dir.join(file_name))?;
+    }
     Ok(())
 }
 
@@ -967,6 +1209,8 @@ fn serialize_canister_to_tip(
                 .clone(),
             total_query_stats: canister_state.scheduler_state.total_query_stats.clone(),
             log_visibility: canister_state.system_state.log_visibility,
+            wasm_memory_limit: canister_state.system_state.wasm_memory_limit,
+            canister_log: canister_state.system_state.canister_log.clone(),
         }
         .into(),
     )?;
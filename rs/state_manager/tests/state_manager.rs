@@ -16,9 +16,11 @@ use ic_metrics::MetricsRegistry;
 use ic_registry_subnet_features::SubnetFeatures;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
-    canister_state::system_state::wasm_chunk_store::WasmChunkStore, page_map::PageIndex,
-    testing::ReplicatedStateTesting, Memory, NetworkTopology, NumWasmPages, PageMap,
-    ReplicatedState, Stream, SubnetTopology,
+    canister_snapshots::{CanisterSnapshot, ExecutionStateSnapshot, MemorySnapshot, SnapshotId},
+    canister_state::system_state::wasm_chunk_store::WasmChunkStore,
+    page_map::PageIndex,
+    testing::ReplicatedStateTesting,
+    Memory, NetworkTopology, NumWasmPages, PageMap, ReplicatedState, Stream, SubnetTopology,
 };
 use ic_state_layout::{CheckpointLayout, ReadOnly, StateLayout, SYSTEM_METADATA_FILE};
 use ic_state_machine_tests::{StateMachine, StateMachineBuilder};
@@ -813,6 +815,140 @@ fn stable_memory_is_persisted() {
     });
 }
 
+#[test]
+fn canister_snapshots_are_persisted() {
+    state_manager_restart_test(|state_manager, restart_fn| {
+        let canister_id = canister_test_id(100);
+        let (_height, mut state) = state_manager.take_tip();
+        insert_dummy_canister(&mut state, canister_id);
+        let canister_state = state.canister_state_mut(&canister_id).unwrap();
+        let execution_state = canister_state.execution_state.as_mut().unwrap();
+        execution_state.stable_memory =
+            Memory::new(PageMap::from(&[1; 100][..]), NumWasmPages::new(1));
+        let snapshot = CanisterSnapshot::new(
+            canister_id,
+            mock_time(),
+            canister_state.system_state.canister_version,
+            vec![4, 2],
+            canister_state.system_state.wasm_chunk_store.clone(),
+            ExecutionStateSnapshot {
+                wasm_binary: execution_state.wasm_binary.binary.clone(),
+                exported_globals: execution_state.exported_globals.clone(),
+                stable_memory: MemorySnapshot::new(
+                    execution_state.stable_memory.page_map.clone(),
+                    execution_state.stable_memory.size,
+                ),
+                wasm_memory: MemorySnapshot::new(
+                    execution_state.wasm_memory.page_map.clone(),
+                    execution_state.wasm_memory.size,
+                ),
+            },
+        );
+        let snapshot_id = state.canister_snapshots.push(Arc::new(snapshot));
+        state_manager.commit_and_certify(state, height(1), CertificationScope::Full);
+
+        // Overwrite the stable memory of the canister and restore it from the snapshot.
+        let (_height, mut state) = state_manager.take_tip();
+        let snapshot = state.canister_snapshots.get(snapshot_id).unwrap().clone();
+        let execution_state = state
+            .canister_state_mut(&canister_id)
+            .unwrap()
+            .execution_state
+            .as_mut()
+            .unwrap();
+        execution_state.stable_memory.page_map = PageMap::from(&[2; 100][..]);
+        state_manager.commit_and_certify(state, height(2), CertificationScope::Full);
+
+        let (_height, mut state) = state_manager.take_tip();
+        let execution_state = state
+            .canister_state_mut(&canister_id)
+            .unwrap()
+            .execution_state
+            .as_mut()
+            .unwrap();
+        assert_eq!(
+            execution_state.stable_memory.page_map,
+            PageMap::from(&[2; 100][..])
+        );
+        execution_state.stable_memory.page_map = snapshot.stable_memory().page_map.clone();
+        state.canister_snapshots.restore(canister_id, snapshot_id);
+        state_manager.commit_and_certify(state, height(3), CertificationScope::Full);
+
+        let state_manager = restart_fn(state_manager, None);
+
+        let recovered = state_manager.get_latest_state();
+        assert_eq!(height(3), recovered.height());
+        let state = recovered.take();
+        assert_eq!(
+            state.canister_snapshots.next_snapshot_id(),
+            SnapshotId::new(snapshot_id.get() + 1)
+        );
+        let snapshot = state.canister_snapshots.get(snapshot_id).unwrap();
+        assert_eq!(*snapshot.canister_id(), canister_id);
+        assert_eq!(snapshot.certified_data(), &vec![4, 2]);
+        assert_eq!(
+            snapshot.stable_memory().page_map,
+            PageMap::from(&[1; 100][..])
+        );
+        let canister_state = state.canister_state(&canister_id).unwrap();
+        assert_eq!(
+            canister_state
+                .execution_state
+                .as_ref()
+                .unwrap()
+                .stable_memory
+                .page_map,
+            PageMap::from(&[1; 100][..])
+        );
+        assert_eq!(
+            canister_state.system_state.snapshots_memory_usage,
+            snapshot.size()
+        );
+    });
+}
+
+#[test]
+fn deleted_canister_snapshots_are_removed_from_checkpoint() {
+    state_manager_test(|_metrics, state_manager| {
+        let canister_id = canister_test_id(100);
+        let (_height, mut state) = state_manager.take_tip();
+        insert_dummy_canister(&mut state, canister_id);
+        let canister_state = state.canister_state(&canister_id).unwrap();
+        let execution_state = canister_state.execution_state.as_ref().unwrap();
+        let snapshot = CanisterSnapshot::new(
+            canister_id,
+            mock_time(),
+            canister_state.system_state.canister_version,
+            vec![],
+            canister_state.system_state.wasm_chunk_store.clone(),
+            ExecutionStateSnapshot {
+                wasm_binary: execution_state.wasm_binary.binary.clone(),
+                exported_globals: execution_state.exported_globals.clone(),
+                stable_memory: MemorySnapshot::new(
+                    execution_state.stable_memory.page_map.clone(),
+                    execution_state.stable_memory.size,
+                ),
+                wasm_memory: MemorySnapshot::new(
+                    execution_state.wasm_memory.page_map.clone(),
+                    execution_state.wasm_memory.size,
+                ),
+            },
+        );
+        let snapshot_id = state.canister_snapshots.push(Arc::new(snapshot));
+        state_manager.commit_and_certify(state, height(1), CertificationScope::Full);
+
+        let checkpoint_layout = state_manager.state_layout().checkpoint(height(1)).unwrap();
+        assert_eq!(checkpoint_layout.snapshot_ids().unwrap(), vec![snapshot_id]);
+
+        let (_height, mut state) = state_manager.take_tip();
+        state.canister_snapshots.remove(snapshot_id);
+        state_manager.commit_and_certify(state, height(2), CertificationScope::Full);
+
+        let checkpoint_layout = state_manager.state_layout().checkpoint(height(2)).unwrap();
+        assert!(checkpoint_layout.snapshot_ids().unwrap().is_empty());
+    });
+}
+
 #[test]
 fn missing_stable_memory_file_is_handled() {
     use ic_state_layout::{CheckpointLayout, RwPolicy};
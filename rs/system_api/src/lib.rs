@@ -8,6 +8,7 @@ use ic_base_types::PrincipalIdBlobParseError;
 use ic_config::flag_status::FlagStatus;
 use ic_cycles_account_manager::ResourceSaturation;
 use ic_error_types::RejectCode;
+use ic_ic00_types::{EcdsaCurve, EcdsaKeyId, SchnorrAlgorithm, SchnorrKeyId};
 use ic_interfaces::execution_environment::{
     ExecutionMode,
     HypervisorError::{self, *},
@@ -23,6 +24,7 @@ use ic_replicated_state::{
 };
 use ic_sys::PageBytes;
 use ic_types::{
+    canister_log::CanisterLog,
     ingress::WasmResult,
     messages::{CallContextId, RejectContext, Request, MAX_INTER_CANISTER_PAYLOAD_IN_BYTES},
     methods::{SystemMethod, WasmClosure},
@@ -41,7 +43,7 @@ use std::{
 
 pub const MULTIPLIER_MAX_SIZE_LOCAL_SUBNET: u64 = 5;
 const MAX_NON_REPLICATED_QUERY_REPLY_SIZE: NumBytes = NumBytes::new(3 << 20);
-const CERTIFIED_DATA_MAX_LENGTH: u32 = 32;
+const CERTIFIED_DATA_MAX_LENGTH: usize = 32;
 
 // Enables tracing of system calls for local debugging.
 const TRACE_SYSCALLS: bool = false;
@@ -78,10 +80,10 @@ macro_rules! trace_syscall {
 
 // This helper is used in system calls for displaying a summary hash of a heap region.
 #[inline]
-fn summarize(heap: &[u8], start: u32, size: u32) -> u64 {
+fn summarize(heap: &[u8], start: usize, size: usize) -> u64 {
     if TRACE_SYSCALLS {
-        let start = (start as usize).min(heap.len());
-        let end = (start + (size as usize)).min(heap.len());
+        let start = start.min(heap.len());
+        let end = start.saturating_add(size).min(heap.len());
         // The actual hash function doesn't matter much as long as it is
         // cheap to compute and maps the input to u64 reasonably well.
         let mut sum = 0;
@@ -239,6 +241,9 @@ pub enum ApiType {
         /// request is currently under construction.
         outgoing_request: Option<RequestInPrep>,
         max_reply_size: NumBytes,
+        /// Deadline of the incoming best-effort call. `None` for ingress
+        /// messages and guaranteed response calls.
+        deadline: Option<Time>,
     },
 
     // For executing canister methods marked as `query`
@@ -288,6 +293,9 @@ pub enum ApiType {
         execution_mode: ExecutionMode,
         /// The total number of instructions executed in the call context
         call_context_instructions_executed: NumInstructions,
+        /// Deadline of the best-effort call that this is a response to.
+        /// `None` for guaranteed responses.
+        deadline: Option<Time>,
     },
 
     // For executing closures when a `Reject` is received
@@ -308,6 +316,9 @@ pub enum ApiType {
         execution_mode: ExecutionMode,
         /// The total number of instructions executed in the call context
         call_context_instructions_executed: NumInstructions,
+        /// Deadline of the best-effort call that this is a response to.
+        /// `None` for guaranteed responses.
+        deadline: Option<Time>,
     },
 
     PreUpgrade {
@@ -389,6 +400,7 @@ impl ApiType {
         incoming_cycles: Cycles,
         caller: PrincipalId,
         call_context_id: CallContextId,
+        deadline: Option<Time>,
     ) -> Self {
         Self::Update {
             time,
@@ -400,6 +412,7 @@ impl ApiType {
             response_status: ResponseStatus::NotRepliedYet,
             outgoing_request: None,
             max_reply_size: MAX_INTER_CANISTER_PAYLOAD_IN_BYTES,
+            deadline,
         }
     }
 
@@ -452,6 +465,7 @@ impl ApiType {
         replied: bool,
         execution_mode: ExecutionMode,
         call_context_instructions_executed: NumInstructions,
+        deadline: Option<Time>,
     ) -> Self {
         Self::ReplyCallback {
             time,
@@ -469,6 +483,7 @@ impl ApiType {
             max_reply_size: MAX_INTER_CANISTER_PAYLOAD_IN_BYTES,
             execution_mode,
             call_context_instructions_executed,
+            deadline,
         }
     }
 
@@ -482,6 +497,7 @@ impl ApiType {
         replied: bool,
         execution_mode: ExecutionMode,
         call_context_instructions_executed: NumInstructions,
+        deadline: Option<Time>,
     ) -> Self {
         Self::RejectCallback {
             time,
@@ -499,6 +515,7 @@ impl ApiType {
             max_reply_size: MAX_INTER_CANISTER_PAYLOAD_IN_BYTES,
             execution_mode,
             call_context_instructions_executed,
+            deadline,
         }
     }
 
@@ -978,6 +995,39 @@ impl SystemApiImpl {
         self.memory_usage.allocated_message_memory
     }
 
+    /// Checks that growing the Wasm memory to `new_size_in_pages` does not
+    /// exceed the `wasm_memory_limit` of the canister.
+    ///
+    /// The limit is only enforced in update and replicated executions: install
+    /// and upgrade hooks (and cleanup callbacks) must always be able to run to
+    /// completion, while non-replicated executions do not persist any changes.
+    fn check_wasm_memory_limit(&self, new_size_in_pages: u64) -> HypervisorResult<()> {
+        let limit = match self.sandbox_safe_system_state.wasm_memory_limit {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+        match &self.api_type {
+            ApiType::Start { .. }
+            | ApiType::Init { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::InspectMessage { .. } => Ok(()),
+            ApiType::Update { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. } => {
+                let bytes =
+                    NumBytes::new(new_size_in_pages.saturating_mul(WASM_PAGE_SIZE_IN_BYTES as u64));
+                if bytes > limit {
+                    return Err(HypervisorError::WasmMemoryLimitExceeded { bytes, limit });
+                }
+                Ok(())
+            }
+        }
+    }
+
     fn error_for(&self, method_name: &str) -> HypervisorError {
         HypervisorError::ContractViolation(format!(
             "\"{}\" cannot be executed in {} mode",
@@ -1257,6 +1307,13 @@ impl SystemApiImpl {
         self.sandbox_safe_system_state.take_changes()
     }
 
+    /// Takes the log records produced by the execution so far. They are
+    /// returned separately from the system state changes so that they are
+    /// kept even if the execution fails.
+    pub fn take_canister_log(&mut self) -> CanisterLog {
+        self.sandbox_safe_system_state.take_canister_log()
+    }
+
     pub fn stable_memory_size(&self) -> NumWasmPages {
         self.stable_memory().stable_memory_size
     }
@@ -1432,9 +1489,9 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_msg_caller_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: usize,
+        offset: usize,
+        size: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = match self.get_msg_caller_id("ic0_msg_caller_copy") {
@@ -1442,7 +1499,6 @@ impl SystemApi for SystemApiImpl {
                 let id_bytes = caller_id.as_slice();
                 valid_subslice("ic0.msg_caller_copy heap", dst, size, heap)?;
                 let slice = valid_subslice("ic0.msg_caller_copy id", offset, size, id_bytes)?;
-                let (dst, size) = (dst as usize, size as usize);
                 deterministic_copy_from_slice(&mut heap[dst..dst + size], slice);
                 Ok(())
             }
@@ -1492,9 +1548,9 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_msg_arg_data_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: usize,
+        offset: usize,
+        size: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = match &self.api_type {
@@ -1528,7 +1584,6 @@ impl SystemApi for SystemApiImpl {
                     size,
                     incoming_payload,
                 )?;
-                let (dst, size) = (dst as usize, size as usize);
                 deterministic_copy_from_slice(&mut heap[dst..dst + size], payload_subslice);
                 Ok(())
             }
@@ -1565,9 +1620,9 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_msg_method_name_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: usize,
+        offset: usize,
+        size: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = match &self.api_type {
@@ -1589,7 +1644,6 @@ impl SystemApi for SystemApiImpl {
                     size,
                     method_name.as_bytes(),
                 )?;
-                let (dst, size) = (dst as usize, size as usize);
                 deterministic_copy_from_slice(&mut heap[dst..dst + size], payload_subslice);
                 Ok(())
             }
@@ -1656,15 +1710,15 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_msg_reply_data_append(
         &mut self,
-        src: u32,
-        size: u32,
+        src: usize,
+        size: usize,
         heap: &[u8],
     ) -> HypervisorResult<()> {
         let result = match self.get_response_info() {
             None => Err(self.error_for("ic0_msg_reply_data_append")),
             Some((data, max_reply_size, response_status)) => match response_status {
                 ResponseStatus::NotRepliedYet => {
-                    let payload_size = (data.len() + size as usize) as u64;
+                    let payload_size = (data.len() + size) as u64;
                     if payload_size > max_reply_size.get() {
                         let string = format!(
                             "ic0.msg_reply_data_append: application payload size ({}) cannot be larger than {}",
@@ -1694,7 +1748,7 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_msg_reject(&mut self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()> {
+    fn ic0_msg_reject(&mut self, src: usize, size: usize, heap: &[u8]) -> HypervisorResult<()> {
         let result = match self.get_response_info() {
             None => Err(self.error_for("ic0_msg_reject")),
             Some((_, max_reply_size, response_status)) => match response_status {
@@ -1740,6 +1794,25 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
+    fn ic0_msg_deadline(&self) -> HypervisorResult<u64> {
+        let result = match &self.api_type {
+            ApiType::Start { .. }
+            | ApiType::Init { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::InspectMessage { .. } => Err(self.error_for("ic0_msg_deadline")),
+            ApiType::ReplicatedQuery { .. } | ApiType::NonReplicatedQuery { .. } => Ok(0),
+            ApiType::Update { deadline, .. }
+            | ApiType::ReplyCallback { deadline, .. }
+            | ApiType::RejectCallback { deadline, .. } => Ok(deadline
+                .map(|deadline| deadline.as_nanos_since_unix_epoch())
+                .unwrap_or(0)),
+        };
+        trace_syscall!(self, MsgDeadline, result);
+        result
+    }
+
     fn ic0_msg_reject_msg_size(&self) -> HypervisorResult<u32> {
         let reject_context = self
             .get_reject_context()
@@ -1751,9 +1824,9 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_msg_reject_msg_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: usize,
+        offset: usize,
+        size: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = {
@@ -1763,10 +1836,8 @@ impl SystemApi for SystemApiImpl {
             valid_subslice("ic0.msg_reject_msg_copy heap", dst, size, heap)?;
 
             let msg = reject_context.message();
-            let dst = dst as usize;
             let msg_bytes =
                 valid_subslice("ic0.msg_reject_msg_copy msg", offset, size, msg.as_bytes())?;
-            let size = size as usize;
             deterministic_copy_from_slice(&mut heap[dst..dst + size], msg_bytes);
             Ok(())
         };
@@ -1807,9 +1878,9 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_canister_self_copy(
         &mut self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: usize,
+        offset: usize,
+        size: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = match &self.api_type {
@@ -1828,7 +1899,6 @@ impl SystemApi for SystemApiImpl {
                 let canister_id = self.sandbox_safe_system_state.canister_id;
                 let id_bytes = canister_id.get_ref().as_slice();
                 let slice = valid_subslice("ic0.canister_self_copy id", offset, size, id_bytes)?;
-                let (dst, size) = (dst as usize, size as usize);
                 deterministic_copy_from_slice(&mut heap[dst..dst + size], slice);
                 Ok(())
             }
@@ -1845,12 +1915,130 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
+    fn ic0_subnet_self_size(&self) -> HypervisorResult<usize> {
+        let result = match &self.api_type {
+            ApiType::Start { .. } => Err(self.error_for("ic0_subnet_self_size")),
+            ApiType::Init { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::Update { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::InspectMessage { .. } => Ok(self
+                .sandbox_safe_system_state
+                .subnet_id()
+                .get_ref()
+                .as_slice()
+                .len()),
+        };
+        trace_syscall!(self, SubnetSelfSize, result);
+        result
+    }
+
+    fn ic0_subnet_self_copy(
+        &mut self,
+        dst: usize,
+        offset: usize,
+        size: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()> {
+        let result = match &self.api_type {
+            ApiType::Start { .. } => Err(self.error_for("ic0_subnet_self_copy")),
+            ApiType::Init { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::Update { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::InspectMessage { .. } => {
+                valid_subslice("ic0.subnet_self_copy heap", dst, size, heap)?;
+                let subnet_id = self.sandbox_safe_system_state.subnet_id();
+                let id_bytes = subnet_id.get_ref().as_slice();
+                let slice = valid_subslice("ic0.subnet_self_copy id", offset, size, id_bytes)?;
+                deterministic_copy_from_slice(&mut heap[dst..dst + size], slice);
+                Ok(())
+            }
+        };
+        trace_syscall!(
+            self,
+            SubnetSelfCopy,
+            result,
+            dst,
+            offset,
+            size,
+            summarize(heap, dst, size)
+        );
+        result
+    }
+
+    fn ic0_root_key_size(&self) -> HypervisorResult<usize> {
+        let result = match &self.api_type {
+            ApiType::Start { .. } => Err(self.error_for("ic0_root_key_size")),
+            ApiType::Init { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::Update { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::InspectMessage { .. } => Ok(self.sandbox_safe_system_state.root_key().len()),
+        };
+        trace_syscall!(self, RootKeySize, result);
+        result
+    }
+
+    fn ic0_root_key_copy(
+        &mut self,
+        dst: usize,
+        offset: usize,
+        size: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()> {
+        let result = match &self.api_type {
+            ApiType::Start { .. } => Err(self.error_for("ic0_root_key_copy")),
+            ApiType::Init { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::Update { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::InspectMessage { .. } => {
+                valid_subslice("ic0.root_key_copy heap", dst, size, heap)?;
+                let root_key = self.sandbox_safe_system_state.root_key();
+                let slice = valid_subslice("ic0.root_key_copy key", offset, size, root_key)?;
+                deterministic_copy_from_slice(&mut heap[dst..dst + size], slice);
+                Ok(())
+            }
+        };
+        trace_syscall!(
+            self,
+            RootKeyCopy,
+            result,
+            dst,
+            offset,
+            size,
+            summarize(heap, dst, size)
+        );
+        result
+    }
+
     fn ic0_call_new(
         &mut self,
-        callee_src: u32,
-        callee_size: u32,
-        name_src: u32,
-        name_len: u32,
+        callee_src: usize,
+        callee_size: usize,
+        name_src: usize,
+        name_len: usize,
         reply_fun: u32,
         reply_env: u32,
         reject_fun: u32,
@@ -1926,7 +2114,12 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_call_data_append(&mut self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()> {
+    fn ic0_call_data_append(
+        &mut self,
+        src: usize,
+        size: usize,
+        heap: &[u8],
+    ) -> HypervisorResult<()> {
         let result = match &mut self.api_type {
             ApiType::Start { .. }
             | ApiType::Init { .. }
@@ -2008,6 +2201,50 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
+    fn ic0_call_with_best_effort_response(&mut self, timeout_seconds: u32) -> HypervisorResult<()> {
+        let result = match &mut self.api_type {
+            ApiType::Start { .. }
+            | ApiType::Init { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery {
+                query_kind: NonReplicatedQueryKind::Pure,
+                ..
+            }
+            | ApiType::Cleanup { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::InspectMessage { .. } => {
+                Err(self.error_for("ic0_call_with_best_effort_response"))
+            }
+            ApiType::Update {
+                outgoing_request, ..
+            }
+            | ApiType::NonReplicatedQuery {
+                query_kind:
+                    NonReplicatedQueryKind::Stateful {
+                        outgoing_request, ..
+                    },
+                ..
+            }
+            | ApiType::SystemTask {
+                outgoing_request, ..
+            }
+            | ApiType::ReplyCallback {
+                outgoing_request, ..
+            }
+            | ApiType::RejectCallback {
+                outgoing_request, ..
+            } => match outgoing_request {
+                None => Err(HypervisorError::ContractViolation(
+                    "ic0.call_with_best_effort_response called when no call is under construction."
+                        .to_string(),
+                )),
+                Some(request) => request.set_timeout(timeout_seconds),
+            },
+        };
+        trace_syscall!(self, CallWithBestEffortResponse, timeout_seconds);
+        result
+    }
+
     fn ic0_call_cycles_add(&mut self, amount: u64) -> HypervisorResult<()> {
         let result = self.ic0_call_cycles_add_helper("ic0_call_cycles_add", Cycles::from(amount));
         trace_syscall!(self, CallCyclesAdd, result, amount);
@@ -2043,21 +2280,25 @@ impl SystemApi for SystemApiImpl {
             | ApiType::PreUpgrade { .. }
             | ApiType::InspectMessage { .. } => Err(self.error_for("ic0_call_perform")),
             ApiType::Update {
+                time,
                 call_context_id,
                 outgoing_request,
                 ..
             }
             | ApiType::SystemTask {
+                time,
                 call_context_id,
                 outgoing_request,
                 ..
             }
             | ApiType::ReplyCallback {
+                time,
                 call_context_id,
                 outgoing_request,
                 ..
             }
             | ApiType::RejectCallback {
+                time,
                 call_context_id,
                 outgoing_request,
                 ..
@@ -2068,6 +2309,7 @@ impl SystemApi for SystemApiImpl {
                         call_context_id,
                         outgoing_request,
                     },
+                time,
                 ..
             } => {
                 let req_in_prep = outgoing_request.take().ok_or_else(|| {
@@ -2078,6 +2320,7 @@ impl SystemApi for SystemApiImpl {
 
                 let req = into_request(
                     req_in_prep,
+                    *time,
                     *call_context_id,
                     &mut self.sandbox_safe_system_state,
                     &self.log,
@@ -2148,7 +2391,7 @@ impl SystemApi for SystemApiImpl {
             dst,
             offset,
             size,
-            summarize(heap, dst, size)
+            summarize(heap, dst as usize, size as usize)
         );
         result
     }
@@ -2170,7 +2413,7 @@ impl SystemApi for SystemApiImpl {
             offset,
             src,
             size,
-            summarize(heap, src, size)
+            summarize(heap, src as usize, size as usize)
         );
         result
     }
@@ -2217,7 +2460,7 @@ impl SystemApi for SystemApiImpl {
             dst,
             offset,
             size,
-            summarize(heap, dst as u32, size as u32)
+            summarize(heap, dst as usize, size as usize)
         );
         result
     }
@@ -2250,7 +2493,7 @@ impl SystemApi for SystemApiImpl {
             offset,
             src,
             size,
-            summarize(heap, src as u32, size as u32)
+            summarize(heap, src as usize, size as usize)
         );
         result
     }
@@ -2414,6 +2657,12 @@ impl SystemApi for SystemApiImpl {
                 .map(NumBytes::new)
                 .ok_or(HypervisorError::OutOfMemory)?;
 
+            if element_size == WASM_PAGE_SIZE_IN_BYTES as u64 {
+                self.check_wasm_memory_limit(
+                    (native_memory_grow_res as u64).saturating_add(additional_elements),
+                )?;
+            }
+
             match self.memory_usage.allocate_execution_memory(
                 bytes,
                 &self.api_type,
@@ -2504,7 +2753,11 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_canister_cycle_balance128(&mut self, dst: u32, heap: &mut [u8]) -> HypervisorResult<()> {
+    fn ic0_canister_cycle_balance128(
+        &mut self,
+        dst: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()> {
         self.call_counters.canister_cycle_balance128 += 1;
         let result = {
             let method_name = "ic0_canister_cycle_balance128";
@@ -2530,7 +2783,7 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_msg_cycles_available128(&self, dst: u32, heap: &mut [u8]) -> HypervisorResult<()> {
+    fn ic0_msg_cycles_available128(&self, dst: usize, heap: &mut [u8]) -> HypervisorResult<()> {
         let result = {
             let method_name = "ic0_msg_cycles_available128";
             let cycles = self.ic0_msg_cycles_available_helper(method_name)?;
@@ -2555,7 +2808,7 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_msg_cycles_refunded128(&self, dst: u32, heap: &mut [u8]) -> HypervisorResult<()> {
+    fn ic0_msg_cycles_refunded128(&self, dst: usize, heap: &mut [u8]) -> HypervisorResult<()> {
         let result = {
             let method_name = "ic0_msg_cycles_refunded128";
             let cycles = self.ic0_msg_cycles_refunded_helper(method_name)?;
@@ -2589,7 +2842,7 @@ impl SystemApi for SystemApiImpl {
     fn ic0_msg_cycles_accept128(
         &mut self,
         max_amount: Cycles,
-        dst: u32,
+        dst: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = {
@@ -2654,9 +2907,9 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_data_certificate_copy(
         &mut self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: usize,
+        offset: usize,
+        size: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         self.call_counters.data_certificate_copy += 1;
@@ -2677,8 +2930,6 @@ impl SystemApi for SystemApiImpl {
                 data_certificate, ..
             } => match data_certificate {
                 Some(data_certificate) => {
-                    let (dst, offset, size) = (dst as usize, offset as usize, size as usize);
-
                     let (upper_bound, overflow) = offset.overflowing_add(size);
                     if overflow || upper_bound > data_certificate.len() {
                         return Err(ContractViolation(format!(
@@ -2724,7 +2975,12 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_certified_data_set(&mut self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()> {
+    fn ic0_certified_data_set(
+        &mut self,
+        src: usize,
+        size: usize,
+        heap: &[u8],
+    ) -> HypervisorResult<()> {
         let result = match &mut self.api_type {
             ApiType::Start { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -2751,7 +3007,6 @@ impl SystemApi for SystemApiImpl {
                     )));
                 }
 
-                let (src, size) = (src as usize, size as usize);
                 let (upper_bound, overflow) = src.overflowing_add(size);
                 if overflow || upper_bound > heap.len() {
                     return Err(ContractViolation(format!(
@@ -2826,8 +3081,8 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_debug_print(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()> {
-        const MAX_DEBUG_MESSAGE_SIZE: u32 = 32 * 1024;
+    fn ic0_debug_print(&self, src: usize, size: usize, heap: &[u8]) -> HypervisorResult<()> {
+        const MAX_DEBUG_MESSAGE_SIZE: usize = 32 * 1024;
         let size = size.min(MAX_DEBUG_MESSAGE_SIZE);
         let msg = match valid_subslice("ic0.debug_print", src, size, heap) {
             Ok(bytes) => String::from_utf8_lossy(bytes).to_string(),
@@ -2858,8 +3113,30 @@ impl SystemApi for SystemApiImpl {
         Ok(())
     }
 
-    fn ic0_trap(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()> {
-        const MAX_ERROR_MESSAGE_SIZE: u32 = 16 * 1024;
+    fn save_log_message(&mut self, src: usize, size: usize, heap: &[u8]) {
+        let content = match valid_subslice("save_log_message", src, size, heap) {
+            Ok(bytes) => bytes.to_vec(),
+            Err(_) => b"(debug message out of memory bounds)".to_vec(),
+        };
+        let time = match &self.api_type {
+            ApiType::Start { time }
+            | ApiType::Init { time, .. }
+            | ApiType::SystemTask { time, .. }
+            | ApiType::Update { time, .. }
+            | ApiType::Cleanup { time, .. }
+            | ApiType::NonReplicatedQuery { time, .. }
+            | ApiType::ReplicatedQuery { time, .. }
+            | ApiType::PreUpgrade { time, .. }
+            | ApiType::ReplyCallback { time, .. }
+            | ApiType::RejectCallback { time, .. }
+            | ApiType::InspectMessage { time, .. } => *time,
+        };
+        self.sandbox_safe_system_state
+            .append_canister_log(time, content);
+    }
+
+    fn ic0_trap(&self, src: usize, size: usize, heap: &[u8]) -> HypervisorResult<()> {
+        const MAX_ERROR_MESSAGE_SIZE: usize = 16 * 1024;
         let size = size.min(MAX_ERROR_MESSAGE_SIZE);
         let result = {
             let msg = valid_subslice("trap", src, size, heap)
@@ -2871,7 +3148,7 @@ impl SystemApi for SystemApiImpl {
         Err(result)
     }
 
-    fn ic0_is_controller(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<u32> {
+    fn ic0_is_controller(&self, src: usize, size: usize, heap: &[u8]) -> HypervisorResult<u32> {
         let result = match &self.api_type {
             ApiType::Start { .. }
             | ApiType::Init { .. }
@@ -2936,7 +3213,7 @@ impl SystemApi for SystemApiImpl {
     fn ic0_cycles_burn128(
         &mut self,
         amount: Cycles,
-        dst: u32,
+        dst: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let method_name = "ic0_cycles_burn128";
@@ -2964,6 +3241,147 @@ impl SystemApi for SystemApiImpl {
         trace_syscall!(self, CyclesBurn128, result, amount);
         result
     }
+
+    fn ic0_cost_call(
+        &self,
+        method_name_size: u64,
+        payload_size: u64,
+        dst: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()> {
+        let cost = self
+            .sandbox_safe_system_state
+            .cost_call(method_name_size, payload_size);
+        let result = copy_cycles_to_heap(cost, dst, heap, "ic0_cost_call");
+        trace_syscall!(
+            self,
+            CostCall,
+            result,
+            method_name_size,
+            payload_size,
+            summarize(heap, dst, 16)
+        );
+        result
+    }
+
+    fn ic0_cost_create_canister(&self, dst: usize, heap: &mut [u8]) -> HypervisorResult<()> {
+        let cost = self.sandbox_safe_system_state.cost_create_canister();
+        let result = copy_cycles_to_heap(cost, dst, heap, "ic0_cost_create_canister");
+        trace_syscall!(self, CostCreateCanister, result, summarize(heap, dst, 16));
+        result
+    }
+
+    fn ic0_cost_http_request(
+        &self,
+        request_size: u64,
+        max_res_bytes: u64,
+        dst: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()> {
+        let cost = self
+            .sandbox_safe_system_state
+            .cost_http_request(request_size, max_res_bytes);
+        let result = copy_cycles_to_heap(cost, dst, heap, "ic0_cost_http_request");
+        trace_syscall!(
+            self,
+            CostHttpRequest,
+            result,
+            request_size,
+            max_res_bytes,
+            summarize(heap, dst, 16)
+        );
+        result
+    }
+
+    fn ic0_cost_sign_with_ecdsa(
+        &self,
+        src: usize,
+        size: usize,
+        ecdsa_curve: u32,
+        dst: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<u32> {
+        let method_name = "ic0_cost_sign_with_ecdsa";
+        let result = {
+            let key_name = valid_subslice(method_name, src, size, heap)?;
+            let key_name = String::from_utf8_lossy(key_name).to_string();
+            match ecdsa_curve {
+                0 => {
+                    let key_id = EcdsaKeyId {
+                        curve: EcdsaCurve::Secp256k1,
+                        name: key_name,
+                    };
+                    match self.sandbox_safe_system_state.cost_sign_with_ecdsa(&key_id) {
+                        Some(cost) => {
+                            copy_cycles_to_heap(cost, dst, heap, method_name)?;
+                            Ok(0)
+                        }
+                        None => Ok(2),
+                    }
+                }
+                _ => Ok(1),
+            }
+        };
+        trace_syscall!(
+            self,
+            CostSignWithEcdsa,
+            result,
+            src,
+            size,
+            ecdsa_curve,
+            summarize(heap, dst, 16)
+        );
+        result
+    }
+
+    fn ic0_cost_sign_with_schnorr(
+        &self,
+        src: usize,
+        size: usize,
+        algorithm: u32,
+        dst: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<u32> {
+        let method_name = "ic0_cost_sign_with_schnorr";
+        let result = {
+            let key_name = valid_subslice(method_name, src, size, heap)?;
+            let key_name = String::from_utf8_lossy(key_name).to_string();
+            let schnorr_algorithm = match algorithm {
+                0 => Some(SchnorrAlgorithm::Bip340Secp256k1),
+                1 => Some(SchnorrAlgorithm::Ed25519),
+                _ => None,
+            };
+            match schnorr_algorithm {
+                Some(algorithm) => {
+                    let key_id = SchnorrKeyId {
+                        algorithm,
+                        name: key_name,
+                    };
+                    match self
+                        .sandbox_safe_system_state
+                        .cost_sign_with_schnorr(&key_id)
+                    {
+                        Some(cost) => {
+                            copy_cycles_to_heap(cost, dst, heap, method_name)?;
+                            Ok(0)
+                        }
+                        None => Ok(2),
+                    }
+                }
+                None => Ok(1),
+            }
+        };
+        trace_syscall!(
+            self,
+            CostSignWithSchnorr,
+            result,
+            src,
+            size,
+            algorithm,
+            summarize(heap, dst, 16)
+        );
+        result
+    }
 }
 
 /// The default implementation of the `OutOfInstructionHandler` trait.
@@ -2984,7 +3402,7 @@ impl OutOfInstructionsHandler for DefaultOutOfInstructionsHandler {
 
 pub(crate) fn copy_cycles_to_heap(
     cycles: Cycles,
-    dst: u32,
+    dst: usize,
     heap: &mut [u8],
     method_name: &str,
 ) -> HypervisorResult<()> {
@@ -2993,7 +3411,6 @@ pub(crate) fn copy_cycles_to_heap(
     let size = bytes.len();
     assert_eq!(size, 16);
 
-    let dst = dst as usize;
     let (upper_bound, overflow) = dst.overflowing_add(size);
     if overflow || upper_bound > heap.len() {
         return Err(ContractViolation(format!(
@@ -3011,13 +3428,11 @@ pub(crate) fn copy_cycles_to_heap(
 
 pub(crate) fn valid_subslice<'a>(
     ctx: &str,
-    src: u32,
-    len: u32,
+    src: usize,
+    len: usize,
     slice: &'a [u8],
 ) -> HypervisorResult<&'a [u8]> {
-    let len = len as usize;
-    let src = src as usize;
-    if slice.len() < src + len {
+    if src.checked_add(len).map_or(true, |end| end > slice.len()) {
         return Err(ContractViolation(format!(
             "{}: src={} + length={} exceeds the slice size={}",
             ctx,
@@ -4,10 +4,15 @@ use ic_logger::ReplicaLogger;
 use ic_types::{
     messages::{CallContextId, Request},
     methods::{Callback, WasmClosure},
-    CanisterId, Cycles, NumBytes, PrincipalId,
+    CanisterId, Cycles, NumBytes, PrincipalId, Time,
 };
 use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
+use std::time::Duration;
+
+/// The maximum timeout of a best-effort call. Longer timeouts requested via
+/// `ic0.call_with_best_effort_response` are silently capped to this value.
+pub const MAX_CALL_TIMEOUT_SECONDS: u32 = 300;
 
 /// Represents an under construction `Request`.
 ///
@@ -47,16 +52,19 @@ pub struct RequestInPrep {
     /// them up creating tricky bugs. Storing this an integer means that the two
     /// limits are stored as different types and are more difficult to mix up.
     multiplier_max_size_local_subnet: u64,
+    /// The timeout requested via `ic0.call_with_best_effort_response`, if
+    /// any. `None` for guaranteed response calls.
+    timeout_seconds: Option<u32>,
 }
 
 impl RequestInPrep {
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         sender: CanisterId,
-        callee_src: u32,
-        callee_size: u32,
-        method_name_src: u32,
-        method_name_len: u32,
+        callee_src: usize,
+        callee_size: usize,
+        method_name_src: usize,
+        method_name_len: usize,
         heap: &[u8],
         on_reply: WasmClosure,
         on_reject: WasmClosure,
@@ -70,7 +78,7 @@ impl RequestInPrep {
             // the minimum of the limits.
 
             // method_name checked against sum of exported function names.
-            if method_name_len as usize > max_sum_exported_function_name_lengths {
+            if method_name_len > max_sum_exported_function_name_lengths {
                 return Err(HypervisorError::ContractViolation(format!(
                     "Size of method_name {} exceeds the allowed sum of exported function name lengths {}",
                     method_name_len, max_sum_exported_function_name_lengths
@@ -110,6 +118,7 @@ impl RequestInPrep {
             method_payload: Vec::new(),
             max_size_remote_subnet,
             multiplier_max_size_local_subnet,
+            timeout_seconds: None,
         })
     }
 
@@ -125,14 +134,26 @@ impl RequestInPrep {
         }
     }
 
+    pub(crate) fn set_timeout(&mut self, timeout_seconds: u32) -> HypervisorResult<()> {
+        if self.timeout_seconds.is_some() {
+            Err(HypervisorError::ContractViolation(
+                "ic0.call_with_best_effort_response can be called at most once between `ic0.call_new` and `ic0.call_perform`"
+                    .to_string(),
+            ))
+        } else {
+            self.timeout_seconds = Some(timeout_seconds.min(MAX_CALL_TIMEOUT_SECONDS));
+            Ok(())
+        }
+    }
+
     pub(crate) fn take_cycles(self) -> Cycles {
         self.cycles
     }
 
     pub(crate) fn extend_method_payload(
         &mut self,
-        src: u32,
-        size: u32,
+        src: usize,
+        size: usize,
         heap: &[u8],
     ) -> HypervisorResult<()> {
         let current_size = self.method_name.len() + self.method_payload.len();
@@ -143,7 +164,7 @@ impl RequestInPrep {
                 "Request to {}:{} has a payload size of {}, which exceeds the allowed local-subnet limit of {}",
                 self.callee,
                 self.method_name,
-                current_size + size as usize,
+                current_size.saturating_add(size),
                 max_size_local_subnet
             )))
         } else {
@@ -178,7 +199,9 @@ pub(crate) fn into_request(
         method_payload,
         max_size_remote_subnet,
         multiplier_max_size_local_subnet,
+        timeout_seconds,
     }: RequestInPrep,
+    time: Time,
     call_context_id: CallContextId,
     sandbox_safe_system_state: &mut SandboxSafeSystemState,
     _logger: &ReplicaLogger,
@@ -203,6 +226,8 @@ pub(crate) fn into_request(
     let prepayment_for_response_transmission =
         sandbox_safe_system_state.prepayment_for_response_transmission();
 
+    let deadline = timeout_seconds.map(|timeout| time + Duration::from_secs(timeout as u64));
+
     let callback_id = sandbox_safe_system_state.register_callback(Callback::new(
         call_context_id,
         sender,
@@ -213,6 +238,7 @@ pub(crate) fn into_request(
         on_reply,
         on_reject,
         on_cleanup,
+        deadline,
     ))?;
 
     let req = Request {
@@ -223,6 +249,7 @@ pub(crate) fn into_request(
         sender_reply_callback: callback_id,
         payment: cycles,
         metadata: Some(sandbox_safe_system_state.request_metadata.clone()),
+        deadline,
     };
     // We cannot call `Request::payload_size_bytes()` before constructing the
     // request, so ensure our separate calculation matches the actual size.
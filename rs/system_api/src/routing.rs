@@ -7,10 +7,11 @@ use ic_error_types::UserError;
 use ic_ic00_types::{
     BitcoinGetBalanceArgs, BitcoinGetCurrentFeePercentilesArgs, BitcoinGetUtxosArgs,
     BitcoinSendTransactionArgs, CanisterIdRecord, CanisterInfoRequest, ClearChunkStoreArgs,
-    ComputeInitialEcdsaDealingsArgs, ECDSAPublicKeyArgs, EcdsaKeyId, InstallChunkedCodeArgs,
-    InstallCodeArgsV2, Method as Ic00Method, NodeMetricsHistoryArgs, Payload,
-    ProvisionalTopUpCanisterArgs, SignWithECDSAArgs, StoredChunksArgs, UninstallCodeArgs,
-    UpdateSettingsArgs, UploadChunkArgs,
+    ComputeInitialEcdsaDealingsArgs, DeleteCanisterSnapshotArgs, ECDSAPublicKeyArgs, EcdsaKeyId,
+    InstallChunkedCodeArgs, InstallCodeArgsV2, ListCanisterSnapshotArgs, LoadCanisterSnapshotArgs,
+    Method as Ic00Method, NodeMetricsHistoryArgs, Payload, ProvisionalTopUpCanisterArgs,
+    SchnorrKeyId, SchnorrPublicKeyArgs, SignWithECDSAArgs, SignWithSchnorrArgs, StoredChunksArgs,
+    TakeCanisterSnapshotArgs, UninstallCodeArgs, UpdateSettingsArgs, UploadChunkArgs,
 };
 use ic_replicated_state::NetworkTopology;
 
@@ -21,6 +22,7 @@ pub(super) enum ResolveDestinationError {
     SubnetNotFound(CanisterId, Ic00Method),
     AlreadyResolved(PrincipalId),
     EcdsaKeyError(String),
+    SchnorrKeyError(String),
 }
 
 impl From<UserError> for ResolveDestinationError {
@@ -205,6 +207,14 @@ pub(super) fn resolve_destination(
                 EcdsaSubnetKind::HoldsAndSignWithKey,
             )
         }
+        Ok(Ic00Method::SchnorrPublicKey) => {
+            let key_id = SchnorrPublicKeyArgs::decode(payload)?.key_id;
+            route_schnorr_message(&key_id, network_topology)
+        }
+        Ok(Ic00Method::SignWithSchnorr) => {
+            let key_id = SignWithSchnorrArgs::decode(payload)?.key_id;
+            route_schnorr_message(&key_id, network_topology)
+        }
         Ok(Ic00Method::ComputeInitialEcdsaDealings) => {
             let args = ComputeInitialEcdsaDealingsArgs::decode(payload)?;
             route_ecdsa_message(
@@ -254,14 +264,61 @@ pub(super) fn resolve_destination(
             ic_error_types::ErrorCode::CanisterRejectedMessage,
             "Delete chunks API is not yet implemented",
         ))),
-        Ok(Ic00Method::TakeCanisterSnapshot)
-        | Ok(Ic00Method::LoadCanisterSnapshot)
-        | Ok(Ic00Method::ListCanisterSnapshots)
-        | Ok(Ic00Method::DeleteCanisterSnapshot) => {
-            Err(ResolveDestinationError::UserError(UserError::new(
-                ic_error_types::ErrorCode::CanisterRejectedMessage,
-                "Snapshotting API is not yet implemented",
-            )))
+        Ok(Ic00Method::TakeCanisterSnapshot) => {
+            let args = TakeCanisterSnapshotArgs::decode(payload)?;
+            let canister_id = args.get_canister_id();
+            network_topology
+                .routing_table
+                .route(canister_id.get())
+                .map(|subnet_id| subnet_id.get())
+                .ok_or({
+                    ResolveDestinationError::SubnetNotFound(
+                        canister_id,
+                        Ic00Method::TakeCanisterSnapshot,
+                    )
+                })
+        }
+        Ok(Ic00Method::LoadCanisterSnapshot) => {
+            let args = LoadCanisterSnapshotArgs::decode(payload)?;
+            let canister_id = args.get_canister_id();
+            network_topology
+                .routing_table
+                .route(canister_id.get())
+                .map(|subnet_id| subnet_id.get())
+                .ok_or({
+                    ResolveDestinationError::SubnetNotFound(
+                        canister_id,
+                        Ic00Method::LoadCanisterSnapshot,
+                    )
+                })
+        }
+        Ok(Ic00Method::ListCanisterSnapshots) => {
+            let args = ListCanisterSnapshotArgs::decode(payload)?;
+            let canister_id = args.get_canister_id();
+            network_topology
+                .routing_table
+                .route(canister_id.get())
+                .map(|subnet_id| subnet_id.get())
+                .ok_or({
+                    ResolveDestinationError::SubnetNotFound(
+                        canister_id,
+                        Ic00Method::ListCanisterSnapshots,
+                    )
+                })
+        }
+        Ok(Ic00Method::DeleteCanisterSnapshot) => {
+            let args = DeleteCanisterSnapshotArgs::decode(payload)?;
+            let canister_id = args.get_canister_id();
+            network_topology
+                .routing_table
+                .route(canister_id.get())
+                .map(|subnet_id| subnet_id.get())
+                .ok_or({
+                    ResolveDestinationError::SubnetNotFound(
+                        canister_id,
+                        Ic00Method::DeleteCanisterSnapshot,
+                    )
+                })
         }
         Err(_) => Err(ResolveDestinationError::MethodNotFound(
             method_name.to_string(),
@@ -363,6 +420,28 @@ fn route_ecdsa_message(
     }
 }
 
+/// Routes to the first subnet enabled to sign with the given Schnorr key.
+fn route_schnorr_message(
+    key_id: &SchnorrKeyId,
+    network_topology: &NetworkTopology,
+) -> Result<PrincipalId, ResolveDestinationError> {
+    match network_topology.schnorr_signing_subnets(key_id).first() {
+        Some(subnet_id) => Ok((*subnet_id).get()),
+        None => {
+            let keys: Vec<_> = network_topology
+                .schnorr_signing_subnets
+                .keys()
+                .map(|key| key.to_string())
+                .collect();
+            Err(ResolveDestinationError::SchnorrKeyError(format!(
+                "Requested Schnorr key: {}, existing keys with signing enabled: [{}]",
+                key_id,
+                keys.join(", ")
+            )))
+        }
+    }
+}
+
 fn route_bitcoin_message(
     network: BitcoinNetwork,
     network_topology: &NetworkTopology,
@@ -392,7 +471,8 @@ mod tests {
     use candid::Encode;
     use ic_base_types::RegistryVersion;
     use ic_ic00_types::{
-        ComputeInitialEcdsaDealingsArgs, DerivationPath, EcdsaCurve, EcdsaKeyId, SignWithECDSAArgs,
+        ComputeInitialEcdsaDealingsArgs, DerivationPath, EcdsaCurve, EcdsaKeyId, SchnorrAlgorithm,
+        SignWithECDSAArgs,
     };
     use ic_replicated_state::SubnetTopology;
     use ic_test_utilities::types::ids::{canister_test_id, node_test_id, subnet_test_id};
@@ -658,4 +738,91 @@ mod tests {
             _ => panic!("Unexpected result."),
         };
     }
+
+    fn schnorr_key_id(name: &str) -> SchnorrKeyId {
+        SchnorrKeyId {
+            algorithm: SchnorrAlgorithm::Bip340Secp256k1,
+            name: name.to_string(),
+        }
+    }
+
+    fn network_with_schnorr_subnets() -> NetworkTopology {
+        NetworkTopology {
+            schnorr_signing_subnets: btreemap! {
+                schnorr_key_id("some_key") => vec![subnet_test_id(0)],
+                schnorr_key_id("other_key") => vec![subnet_test_id(1)],
+            },
+            ..NetworkTopology::default()
+        }
+    }
+
+    fn schnorr_sign_req(key_id: SchnorrKeyId) -> Vec<u8> {
+        let args = SignWithSchnorrArgs {
+            message: vec![1; 32],
+            derivation_path: DerivationPath::new(vec![]),
+            key_id,
+        };
+        Encode!(&args).unwrap()
+    }
+
+    fn schnorr_public_key_req(key_id: SchnorrKeyId) -> Vec<u8> {
+        let args = SchnorrPublicKeyArgs {
+            canister_id: Some(canister_test_id(1)),
+            derivation_path: DerivationPath::new(vec![]),
+            key_id,
+        };
+        Encode!(&args).unwrap()
+    }
+
+    #[test]
+    fn resolve_schnorr_requests_to_signing_subnet() {
+        for (key_name, subnet_id) in [
+            ("some_key", subnet_test_id(0)),
+            ("other_key", subnet_test_id(1)),
+        ] {
+            assert_eq!(
+                resolve_destination(
+                    &network_with_schnorr_subnets(),
+                    &Ic00Method::SignWithSchnorr.to_string(),
+                    &schnorr_sign_req(schnorr_key_id(key_name)),
+                    subnet_test_id(2),
+                )
+                .unwrap(),
+                subnet_id.get()
+            );
+            assert_eq!(
+                resolve_destination(
+                    &network_with_schnorr_subnets(),
+                    &Ic00Method::SchnorrPublicKey.to_string(),
+                    &schnorr_public_key_req(schnorr_key_id(key_name)),
+                    subnet_test_id(2),
+                )
+                .unwrap(),
+                subnet_id.get()
+            );
+        }
+    }
+
+    #[test]
+    fn resolve_schnorr_sign_unknown_key_error() {
+        let key_id = schnorr_key_id("unknown_key");
+        assert_matches!(
+            resolve_destination(
+                &network_with_schnorr_subnets(),
+                &Ic00Method::SignWithSchnorr.to_string(),
+                &schnorr_sign_req(key_id.clone()),
+                subnet_test_id(2),
+            )
+            .unwrap_err(),
+            ResolveDestinationError::SchnorrKeyError(err) => assert_eq!(
+                err,
+                format!(
+                    "Requested Schnorr key: {}, existing keys with signing enabled: [{}, {}]",
+                    key_id,
+                    schnorr_key_id("other_key"),
+                    schnorr_key_id("some_key"),
+                )
+            )
+        )
+    }
 }
@@ -8,8 +8,10 @@ use ic_cycles_account_manager::{
 };
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
-    CreateCanisterArgs, InstallChunkedCodeArgs, InstallCodeArgsV2, Method as Ic00Method, Payload,
-    ProvisionalCreateCanisterWithCyclesArgs, UninstallCodeArgs, UpdateSettingsArgs, IC_00,
+    CreateCanisterArgs, EcdsaKeyId, InstallChunkedCodeArgs, InstallCodeArgsV2,
+    LoadCanisterSnapshotArgs, Method as Ic00Method, Payload,
+    ProvisionalCreateCanisterWithCyclesArgs, SchnorrKeyId, UninstallCodeArgs, UpdateSettingsArgs,
+    IC_00,
 };
 use ic_interfaces::execution_environment::{HypervisorError, HypervisorResult};
 use ic_logger::{info, ReplicaLogger};
@@ -20,6 +22,7 @@ use ic_replicated_state::{
     CallOrigin, CanisterStatus, NetworkTopology, SystemState,
 };
 use ic_types::{
+    canister_log::CanisterLog,
     messages::{CallContextId, CallbackId, RejectContext, Request, RequestMetadata},
     methods::Callback,
     CanisterTimer, ComputeAllocation, Cycles, MemoryAllocation, NumInstructions, NumPages, Time,
@@ -223,6 +226,8 @@ impl SystemStateChanges {
                 ProvisionalCreateCanisterWithCyclesArgs::decode(payload)
                     .map(|record| record.get_sender_canister_version())
             }
+            Ok(Ic00Method::LoadCanisterSnapshot) => LoadCanisterSnapshotArgs::decode(payload)
+                .map(|record| record.get_sender_canister_version()),
             Ok(Ic00Method::SignWithECDSA)
             | Ok(Ic00Method::CanisterStatus)
             | Ok(Ic00Method::CanisterInfo)
@@ -234,6 +239,8 @@ impl SystemStateChanges {
             | Ok(Ic00Method::HttpRequest)
             | Ok(Ic00Method::SetupInitialDKG)
             | Ok(Ic00Method::ECDSAPublicKey)
+            | Ok(Ic00Method::SchnorrPublicKey)
+            | Ok(Ic00Method::SignWithSchnorr)
             | Ok(Ic00Method::ComputeInitialEcdsaDealings)
             | Ok(Ic00Method::ProvisionalTopUpCanister)
             | Ok(Ic00Method::BitcoinSendTransactionInternal)
@@ -250,8 +257,7 @@ impl SystemStateChanges {
             | Ok(Ic00Method::ClearChunkStore)
             | Ok(Ic00Method::TakeCanisterSnapshot)
             | Ok(Ic00Method::ListCanisterSnapshots)
-            | Ok(Ic00Method::DeleteCanisterSnapshot)
-            | Ok(Ic00Method::LoadCanisterSnapshot) => Ok(None),
+            | Ok(Ic00Method::DeleteCanisterSnapshot) => Ok(None),
             Err(_) => Err(UserError::new(
                 ErrorCode::CanisterMethodNotFound,
                 format!("Management canister has no method '{}'", msg.method_name),
@@ -281,7 +287,7 @@ impl SystemStateChanges {
     /// Verify that the changes to the system state are sound and apply them to
     /// the system state if they are.
     pub fn apply_changes(
-        self,
+        mut self,
         time: Time,
         system_state: &mut SystemState,
         network_topology: &NetworkTopology,
@@ -310,7 +316,7 @@ impl SystemStateChanges {
                 })?;
                 if (*amount_taken).get() > LOG_CANISTER_OPERATION_CYCLES_THRESHOLD {
                     match call_context.call_origin() {
-                        CallOrigin::CanisterUpdate(origin_canister_id, _)
+                        CallOrigin::CanisterUpdate(origin_canister_id, _, _)
                         | CallOrigin::CanisterQuery(origin_canister_id, _) => info!(
                             logger,
                             "Canister {} accepted {} cycles from canister {}.",
@@ -538,6 +544,15 @@ pub struct SandboxSafeSystemState {
     pub(super) status: CanisterStatusView,
     pub(super) subnet_type: SubnetType,
     pub(super) subnet_size: usize,
+    /// Sizes of the subnets that hold each of the available ECDSA keys, used to
+    /// compute the fee for signing with a given key.
+    ecdsa_signing_subnet_sizes: BTreeMap<EcdsaKeyId, usize>,
+    /// Sizes of the subnets that hold each of the available Schnorr keys, used
+    /// to compute the fee for signing with a given key.
+    schnorr_signing_subnet_sizes: BTreeMap<SchnorrKeyId, usize>,
+    /// The DER-encoded public key of the IC root of trust, i.e. of the NNS
+    /// subnet.
+    root_key: Vec<u8>,
     dirty_page_overhead: NumInstructions,
     freeze_threshold: NumSeconds,
     memory_allocation: MemoryAllocation,
@@ -545,6 +560,7 @@ pub struct SandboxSafeSystemState {
     initial_cycles_balance: Cycles,
     initial_reserved_balance: Cycles,
     reserved_balance_limit: Option<Cycles>,
+    pub(super) wasm_memory_limit: Option<NumBytes>,
     call_context_balances: BTreeMap<CallContextId, Cycles>,
     cycles_account_manager: CyclesAccountManager,
     // None indicates that we are in a context where the canister cannot
@@ -558,6 +574,9 @@ pub struct SandboxSafeSystemState {
     canister_version: u64,
     controllers: BTreeSet<PrincipalId>,
     pub(super) request_metadata: RequestMetadata,
+    /// Log records produced during the execution. Unlike the other changes,
+    /// they are kept even if the execution fails.
+    canister_log: CanisterLog,
 }
 
 impl SandboxSafeSystemState {
@@ -573,6 +592,7 @@ impl SandboxSafeSystemState {
         initial_cycles_balance: Cycles,
         initial_reserved_balance: Cycles,
         reserved_balance_limit: Option<Cycles>,
+        wasm_memory_limit: Option<NumBytes>,
         call_context_balances: BTreeMap<CallContextId, Cycles>,
         cycles_account_manager: CyclesAccountManager,
         next_callback_id: Option<u64>,
@@ -580,17 +600,24 @@ impl SandboxSafeSystemState {
         ic00_available_request_slots: usize,
         ic00_aliases: BTreeSet<CanisterId>,
         subnet_size: usize,
+        ecdsa_signing_subnet_sizes: BTreeMap<EcdsaKeyId, usize>,
+        schnorr_signing_subnet_sizes: BTreeMap<SchnorrKeyId, usize>,
+        root_key: Vec<u8>,
         dirty_page_overhead: NumInstructions,
         global_timer: CanisterTimer,
         canister_version: u64,
         controllers: BTreeSet<PrincipalId>,
         request_metadata: RequestMetadata,
+        next_canister_log_record_idx: u64,
     ) -> Self {
         Self {
             canister_id,
             status,
             subnet_type: cycles_account_manager.subnet_type(),
             subnet_size,
+            ecdsa_signing_subnet_sizes,
+            schnorr_signing_subnet_sizes,
+            root_key,
             dirty_page_overhead,
             freeze_threshold,
             memory_allocation,
@@ -599,6 +626,7 @@ impl SandboxSafeSystemState {
             initial_cycles_balance,
             initial_reserved_balance,
             reserved_balance_limit,
+            wasm_memory_limit,
             call_context_balances,
             cycles_account_manager,
             next_callback_id,
@@ -609,6 +637,7 @@ impl SandboxSafeSystemState {
             canister_version,
             controllers,
             request_metadata,
+            canister_log: CanisterLog::new_delta(next_canister_log_record_idx),
         }
     }
 
@@ -658,6 +687,31 @@ impl SandboxSafeSystemState {
         let subnet_size = network_topology
             .get_subnet_size(&cycles_account_manager.get_subnet_id())
             .unwrap_or(SMALL_APP_SUBNET_MAX_SIZE);
+        let ecdsa_signing_subnet_sizes = network_topology
+            .ecdsa_signing_subnets
+            .iter()
+            .filter_map(|(key_id, subnets)| {
+                subnets
+                    .first()
+                    .and_then(|subnet_id| network_topology.get_subnet_size(subnet_id))
+                    .map(|size| (key_id.clone(), size))
+            })
+            .collect();
+        let schnorr_signing_subnet_sizes = network_topology
+            .schnorr_signing_subnets
+            .iter()
+            .filter_map(|(key_id, subnets)| {
+                subnets
+                    .first()
+                    .and_then(|subnet_id| network_topology.get_subnet_size(subnet_id))
+                    .map(|size| (key_id.clone(), size))
+            })
+            .collect();
+        let root_key = network_topology
+            .subnets
+            .get(&network_topology.nns_subnet_id)
+            .map(|subnet| subnet.public_key.clone())
+            .unwrap_or_default();
 
         Self::new_internal(
             system_state.canister_id,
@@ -668,6 +722,7 @@ impl SandboxSafeSystemState {
             system_state.balance(),
             system_state.reserved_balance(),
             system_state.reserved_balance_limit(),
+            system_state.wasm_memory_limit,
             call_context_balances,
             cycles_account_manager,
             system_state
@@ -677,11 +732,15 @@ impl SandboxSafeSystemState {
             ic00_available_request_slots,
             ic00_aliases,
             subnet_size,
+            ecdsa_signing_subnet_sizes,
+            schnorr_signing_subnet_sizes,
+            root_key,
             dirty_page_overhead,
             system_state.global_timer,
             system_state.canister_version,
             system_state.controllers.clone(),
             request_metadata,
+            system_state.canister_log.next_idx(),
         )
     }
 
@@ -689,6 +748,16 @@ impl SandboxSafeSystemState {
         self.canister_id
     }
 
+    /// Returns the ID of the subnet on which the canister is running.
+    pub fn subnet_id(&self) -> SubnetId {
+        self.cycles_account_manager.get_subnet_id()
+    }
+
+    /// Returns the DER-encoded public key of the IC root of trust.
+    pub fn root_key(&self) -> &[u8] {
+        &self.root_key
+    }
+
     pub fn global_timer(&self) -> CanisterTimer {
         self.global_timer
     }
@@ -703,6 +772,18 @@ impl SandboxSafeSystemState {
         self.global_timer = timer;
     }
 
+    /// Adds a record with the given content to the log records produced by
+    /// this execution.
+    pub fn append_canister_log(&mut self, time: Time, content: Vec<u8>) {
+        self.canister_log
+            .add_record(time.as_nanos_since_unix_epoch(), content);
+    }
+
+    /// Takes the log records produced by this execution so far.
+    pub fn take_canister_log(&mut self) -> CanisterLog {
+        std::mem::take(&mut self.canister_log)
+    }
+
     pub fn changes(self) -> SystemStateChanges {
         self.system_state_changes
     }
@@ -872,6 +953,57 @@ impl SandboxSafeSystemState {
         amount_to_accept
     }
 
+    /// Returns the amount of cycles that `call_perform` would withdraw for a
+    /// call with the given method name and payload sizes.
+    pub(super) fn cost_call(&self, method_name_size: u64, payload_size: u64) -> Cycles {
+        self.cycles_account_manager
+            .xnet_call_performed_fee(self.subnet_size)
+            + self.cycles_account_manager.xnet_call_bytes_transmitted_fee(
+                NumBytes::from(method_name_size.saturating_add(payload_size)),
+                self.subnet_size,
+            )
+            + self.prepayment_for_response_transmission()
+            + self.prepayment_for_response_execution()
+    }
+
+    /// Returns the fee for creating a canister on this subnet.
+    pub(super) fn cost_create_canister(&self) -> Cycles {
+        self.cycles_account_manager
+            .canister_creation_fee(self.subnet_size)
+    }
+
+    /// Returns the fee for an HTTPS outcall with the given request size and
+    /// maximum response size.
+    pub(super) fn cost_http_request(&self, request_size: u64, max_res_bytes: u64) -> Cycles {
+        self.cycles_account_manager.http_request_fee(
+            NumBytes::from(request_size),
+            Some(NumBytes::from(max_res_bytes)),
+            self.subnet_size,
+        )
+    }
+
+    /// Returns the fee for a signature with the given ECDSA key, or `None` if
+    /// the key is not available on any subnet.
+    pub(super) fn cost_sign_with_ecdsa(&self, key_id: &EcdsaKeyId) -> Option<Cycles> {
+        self.ecdsa_signing_subnet_sizes
+            .get(key_id)
+            .map(|subnet_size| {
+                self.cycles_account_manager
+                    .ecdsa_signature_fee(*subnet_size)
+            })
+    }
+
+    /// Returns the fee for a signature with the given Schnorr key, or `None`
+    /// if the key is not available on any subnet.
+    pub(super) fn cost_sign_with_schnorr(&self, key_id: &SchnorrKeyId) -> Option<Cycles> {
+        self.schnorr_signing_subnet_sizes
+            .get(key_id)
+            .map(|subnet_size| {
+                self.cycles_account_manager
+                    .schnorr_signature_fee(*subnet_size)
+            })
+    }
+
     pub fn prepayment_for_response_execution(&self) -> Cycles {
         self.cycles_account_manager
             .prepayment_for_response_execution(self.subnet_size)
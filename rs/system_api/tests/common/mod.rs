@@ -84,6 +84,7 @@ impl ApiTypeBuilder {
             Cycles::zero(),
             user_test_id(1).get(),
             CallContextId::from(1),
+            None,
         )
     }
 
@@ -106,6 +107,7 @@ impl ApiTypeBuilder {
             false,
             ExecutionMode::Replicated,
             0.into(),
+            None,
         )
     }
 
@@ -119,6 +121,7 @@ impl ApiTypeBuilder {
             false,
             ExecutionMode::Replicated,
             0.into(),
+            None,
         )
     }
 }
@@ -163,7 +166,7 @@ pub fn get_system_state() -> SystemState {
         .call_context_manager_mut()
         .unwrap()
         .new_call_context(
-            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5)),
+            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5), None),
             Cycles::new(50),
             Time::from_nanos_since_unix_epoch(0),
             RequestMetadata::new(0, mock_time()),
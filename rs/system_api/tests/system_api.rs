@@ -1,9 +1,11 @@
+use assert_matches::assert_matches;
 use ic_base_types::{NumSeconds, PrincipalIdBlobParseError};
 use ic_config::{
     embedders::Config as EmbeddersConfig, flag_status::FlagStatus, subnet_config::SchedulerConfig,
 };
 use ic_constants::SMALL_APP_SUBNET_MAX_SIZE;
 use ic_error_types::RejectCode;
+use ic_ic00_types::{EcdsaCurve, EcdsaKeyId, SchnorrAlgorithm, SchnorrKeyId};
 use ic_interfaces::execution_environment::{
     CanisterOutOfCyclesError, HypervisorError, HypervisorResult, PerformanceCounterType,
     SubnetAvailableMemory, SystemApi, TrapCode,
@@ -21,7 +23,7 @@ use ic_test_utilities::{
     cycles_account_manager::CyclesAccountManagerBuilder,
     state::SystemStateBuilder,
     types::{
-        ids::{call_context_test_id, canister_test_id, subnet_test_id, user_test_id},
+        ids::{call_context_test_id, canister_test_id, node_test_id, subnet_test_id, user_test_id},
         messages::RequestBuilder,
     },
 };
@@ -31,7 +33,7 @@ use ic_types::{
         CallContextId, CallbackId, RejectContext, RequestMetadata, MAX_RESPONSE_COUNT_BYTES,
     },
     methods::{Callback, WasmClosure},
-    time, CanisterTimer, CountBytes, Cycles, NumInstructions, PrincipalId, Time,
+    time, CanisterTimer, CountBytes, Cycles, NumBytes, NumInstructions, PrincipalId, Time,
 };
 use std::{
     collections::BTreeSet,
@@ -149,15 +151,21 @@ fn test_canister_init_support() {
     assert_api_not_supported(api.ic0_msg_reply_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_msg_reject(0, 0, &[]));
     assert_api_not_supported(api.ic0_msg_reject_code());
+    assert_api_not_supported(api.ic0_msg_deadline());
     assert_api_not_supported(api.ic0_msg_reject_msg_size());
     assert_api_not_supported(api.ic0_msg_reject_msg_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_canister_self_size());
     assert_api_supported(api.ic0_canister_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_subnet_self_size());
+    assert_api_supported(api.ic0_subnet_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_root_key_size());
+    assert_api_supported(api.ic0_root_key_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_debug_print(0, 0, &[]));
     assert_api_supported(api.ic0_trap(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_not_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_not_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_not_supported(api.ic0_call_cycles_add(0));
     assert_api_not_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_not_supported(api.ic0_call_perform());
@@ -184,6 +192,11 @@ fn test_canister_init_support() {
     assert_api_supported(api.ic0_is_controller(0, 0, &[]));
     assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_supported(api.ic0_cycles_burn128(Cycles::zero(), 0, &mut []));
+    assert_api_supported(api.ic0_cost_call(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_create_canister(0, &mut []));
+    assert_api_supported(api.ic0_cost_http_request(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_ecdsa(0, 0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_schnorr(0, 0, 0, 0, &mut []));
     check_stable_apis_support(api);
 }
 
@@ -207,15 +220,21 @@ fn test_canister_update_support() {
     assert_api_supported(api.ic0_msg_reply_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_msg_reject(0, 0, &[]));
     assert_api_not_supported(api.ic0_msg_reject_code());
+    assert_api_supported(api.ic0_msg_deadline());
     assert_api_not_supported(api.ic0_msg_reject_msg_size());
     assert_api_not_supported(api.ic0_msg_reject_msg_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_canister_self_size());
     assert_api_supported(api.ic0_canister_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_subnet_self_size());
+    assert_api_supported(api.ic0_subnet_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_root_key_size());
+    assert_api_supported(api.ic0_root_key_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_debug_print(0, 0, &[]));
     assert_api_supported(api.ic0_trap(0, 0, &[]));
     assert_api_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_supported(api.ic0_call_cycles_add(0));
     assert_api_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_supported(api.ic0_call_perform());
@@ -242,6 +261,11 @@ fn test_canister_update_support() {
     assert_api_supported(api.ic0_is_controller(0, 0, &[]));
     assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_supported(api.ic0_cycles_burn128(Cycles::zero(), 0, &mut []));
+    assert_api_supported(api.ic0_cost_call(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_create_canister(0, &mut []));
+    assert_api_supported(api.ic0_cost_http_request(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_ecdsa(0, 0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_schnorr(0, 0, 0, 0, &mut []));
     check_stable_apis_support(api);
 }
 
@@ -265,15 +289,21 @@ fn test_canister_replicated_query_support() {
     assert_api_supported(api.ic0_msg_reply_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_msg_reject(0, 0, &[]));
     assert_api_not_supported(api.ic0_msg_reject_code());
+    assert_api_supported(api.ic0_msg_deadline());
     assert_api_not_supported(api.ic0_msg_reject_msg_size());
     assert_api_not_supported(api.ic0_msg_reject_msg_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_canister_self_size());
     assert_api_supported(api.ic0_canister_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_subnet_self_size());
+    assert_api_supported(api.ic0_subnet_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_root_key_size());
+    assert_api_supported(api.ic0_root_key_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_debug_print(0, 0, &[]));
     assert_api_supported(api.ic0_trap(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_not_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_not_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_not_supported(api.ic0_call_cycles_add(0));
     assert_api_not_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_not_supported(api.ic0_call_perform());
@@ -300,6 +330,11 @@ fn test_canister_replicated_query_support() {
     assert_api_supported(api.ic0_is_controller(0, 0, &[]));
     assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_not_supported(api.ic0_cycles_burn128(Cycles::zero(), 0, &mut []));
+    assert_api_supported(api.ic0_cost_call(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_create_canister(0, &mut []));
+    assert_api_supported(api.ic0_cost_http_request(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_ecdsa(0, 0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_schnorr(0, 0, 0, 0, &mut []));
     check_stable_apis_support(api);
 }
 
@@ -323,15 +358,21 @@ fn test_canister_pure_query_support() {
     assert_api_supported(api.ic0_msg_reply_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_msg_reject(0, 0, &[]));
     assert_api_not_supported(api.ic0_msg_reject_code());
+    assert_api_supported(api.ic0_msg_deadline());
     assert_api_not_supported(api.ic0_msg_reject_msg_size());
     assert_api_not_supported(api.ic0_msg_reject_msg_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_canister_self_size());
     assert_api_supported(api.ic0_canister_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_subnet_self_size());
+    assert_api_supported(api.ic0_subnet_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_root_key_size());
+    assert_api_supported(api.ic0_root_key_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_debug_print(0, 0, &[]));
     assert_api_supported(api.ic0_trap(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_not_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_not_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_not_supported(api.ic0_call_cycles_add(0));
     assert_api_not_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_not_supported(api.ic0_call_perform());
@@ -358,6 +399,11 @@ fn test_canister_pure_query_support() {
     assert_api_supported(api.ic0_is_controller(0, 0, &[]));
     assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_not_supported(api.ic0_cycles_burn128(Cycles::zero(), 0, &mut []));
+    assert_api_supported(api.ic0_cost_call(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_create_canister(0, &mut []));
+    assert_api_supported(api.ic0_cost_http_request(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_ecdsa(0, 0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_schnorr(0, 0, 0, 0, &mut []));
     check_stable_apis_support(api);
 }
 
@@ -391,15 +437,21 @@ fn test_canister_stateful_query_support() {
     assert_api_supported(api.ic0_msg_reply_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_msg_reject(0, 0, &[]));
     assert_api_not_supported(api.ic0_msg_reject_code());
+    assert_api_supported(api.ic0_msg_deadline());
     assert_api_not_supported(api.ic0_msg_reject_msg_size());
     assert_api_not_supported(api.ic0_msg_reject_msg_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_canister_self_size());
     assert_api_supported(api.ic0_canister_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_subnet_self_size());
+    assert_api_supported(api.ic0_subnet_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_root_key_size());
+    assert_api_supported(api.ic0_root_key_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_debug_print(0, 0, &[]));
     assert_api_supported(api.ic0_trap(0, 0, &[]));
     assert_api_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_not_supported(api.ic0_call_cycles_add(0));
     assert_api_not_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_supported(api.ic0_call_perform());
@@ -426,6 +478,11 @@ fn test_canister_stateful_query_support() {
     assert_api_supported(api.ic0_is_controller(0, 0, &[]));
     assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_not_supported(api.ic0_cycles_burn128(Cycles::zero(), 0, &mut []));
+    assert_api_supported(api.ic0_cost_call(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_create_canister(0, &mut []));
+    assert_api_supported(api.ic0_cost_http_request(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_ecdsa(0, 0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_schnorr(0, 0, 0, 0, &mut []));
     check_stable_apis_support(api);
 }
 
@@ -449,15 +506,21 @@ fn test_reply_api_support_on_nns() {
     assert_api_supported(api.ic0_msg_reply_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_msg_reject(0, 0, &[]));
     assert_api_supported(api.ic0_msg_reject_code());
+    assert_api_supported(api.ic0_msg_deadline());
     assert_api_not_supported(api.ic0_msg_reject_msg_size());
     assert_api_not_supported(api.ic0_msg_reject_msg_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_canister_self_size());
     assert_api_supported(api.ic0_canister_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_subnet_self_size());
+    assert_api_supported(api.ic0_subnet_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_root_key_size());
+    assert_api_supported(api.ic0_root_key_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_debug_print(0, 0, &[]));
     assert_api_supported(api.ic0_trap(0, 0, &[]));
     assert_api_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_supported(api.ic0_call_cycles_add(0));
     assert_api_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_supported(api.ic0_call_perform());
@@ -484,6 +547,11 @@ fn test_reply_api_support_on_nns() {
     assert_api_supported(api.ic0_is_controller(0, 0, &[]));
     assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_supported(api.ic0_cycles_burn128(Cycles::zero(), 0, &mut []));
+    assert_api_supported(api.ic0_cost_call(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_create_canister(0, &mut []));
+    assert_api_supported(api.ic0_cost_http_request(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_ecdsa(0, 0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_schnorr(0, 0, 0, 0, &mut []));
     check_stable_apis_support(api);
 }
 
@@ -507,15 +575,21 @@ fn test_reply_api_support_non_nns() {
     assert_api_supported(api.ic0_msg_reply_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_msg_reject(0, 0, &[]));
     assert_api_supported(api.ic0_msg_reject_code());
+    assert_api_supported(api.ic0_msg_deadline());
     assert_api_not_supported(api.ic0_msg_reject_msg_size());
     assert_api_not_supported(api.ic0_msg_reject_msg_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_canister_self_size());
     assert_api_supported(api.ic0_canister_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_subnet_self_size());
+    assert_api_supported(api.ic0_subnet_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_root_key_size());
+    assert_api_supported(api.ic0_root_key_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_debug_print(0, 0, &[]));
     assert_api_supported(api.ic0_trap(0, 0, &[]));
     assert_api_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_supported(api.ic0_call_cycles_add(0));
     assert_api_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_supported(api.ic0_call_perform());
@@ -542,6 +616,11 @@ fn test_reply_api_support_non_nns() {
     assert_api_supported(api.ic0_is_controller(0, 0, &[]));
     assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_supported(api.ic0_cycles_burn128(Cycles::zero(), 0, &mut []));
+    assert_api_supported(api.ic0_cost_call(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_create_canister(0, &mut []));
+    assert_api_supported(api.ic0_cost_http_request(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_ecdsa(0, 0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_schnorr(0, 0, 0, 0, &mut []));
     check_stable_apis_support(api);
 }
 
@@ -566,15 +645,21 @@ fn test_reject_api_support_on_nns() {
     assert_api_supported(api.ic0_msg_reply_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_msg_reject(0, 0, &[]));
     assert_api_supported(api.ic0_msg_reject_code());
+    assert_api_supported(api.ic0_msg_deadline());
     assert_api_supported(api.ic0_msg_reject_msg_size());
     assert_api_supported(api.ic0_msg_reject_msg_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_canister_self_size());
     assert_api_supported(api.ic0_canister_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_subnet_self_size());
+    assert_api_supported(api.ic0_subnet_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_root_key_size());
+    assert_api_supported(api.ic0_root_key_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_debug_print(0, 0, &[]));
     assert_api_supported(api.ic0_trap(0, 0, &[]));
     assert_api_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_supported(api.ic0_call_cycles_add(0));
     assert_api_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_supported(api.ic0_call_perform());
@@ -601,6 +686,11 @@ fn test_reject_api_support_on_nns() {
     assert_api_supported(api.ic0_is_controller(0, 0, &[]));
     assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_supported(api.ic0_cycles_burn128(Cycles::zero(), 0, &mut []));
+    assert_api_supported(api.ic0_cost_call(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_create_canister(0, &mut []));
+    assert_api_supported(api.ic0_cost_http_request(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_ecdsa(0, 0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_schnorr(0, 0, 0, 0, &mut []));
     check_stable_apis_support(api);
 }
 
@@ -625,15 +715,21 @@ fn test_reject_api_support_non_nns() {
     assert_api_supported(api.ic0_msg_reply_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_msg_reject(0, 0, &[]));
     assert_api_supported(api.ic0_msg_reject_code());
+    assert_api_supported(api.ic0_msg_deadline());
     assert_api_supported(api.ic0_msg_reject_msg_size());
     assert_api_supported(api.ic0_msg_reject_msg_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_canister_self_size());
     assert_api_supported(api.ic0_canister_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_subnet_self_size());
+    assert_api_supported(api.ic0_subnet_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_root_key_size());
+    assert_api_supported(api.ic0_root_key_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_debug_print(0, 0, &[]));
     assert_api_supported(api.ic0_trap(0, 0, &[]));
     assert_api_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_supported(api.ic0_call_cycles_add(0));
     assert_api_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_supported(api.ic0_call_perform());
@@ -660,6 +756,11 @@ fn test_reject_api_support_non_nns() {
     assert_api_supported(api.ic0_is_controller(0, 0, &[]));
     assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_supported(api.ic0_cycles_burn128(Cycles::zero(), 0, &mut []));
+    assert_api_supported(api.ic0_cost_call(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_create_canister(0, &mut []));
+    assert_api_supported(api.ic0_cost_http_request(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_ecdsa(0, 0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_schnorr(0, 0, 0, 0, &mut []));
     check_stable_apis_support(api);
 }
 
@@ -683,15 +784,21 @@ fn test_pre_upgrade_support() {
     assert_api_not_supported(api.ic0_msg_reply_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_msg_reject(0, 0, &[]));
     assert_api_not_supported(api.ic0_msg_reject_code());
+    assert_api_not_supported(api.ic0_msg_deadline());
     assert_api_not_supported(api.ic0_msg_reject_msg_size());
     assert_api_not_supported(api.ic0_msg_reject_msg_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_canister_self_size());
     assert_api_supported(api.ic0_canister_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_subnet_self_size());
+    assert_api_supported(api.ic0_subnet_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_root_key_size());
+    assert_api_supported(api.ic0_root_key_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_debug_print(0, 0, &[]));
     assert_api_supported(api.ic0_trap(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_not_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_not_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_not_supported(api.ic0_call_cycles_add(0));
     assert_api_not_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_not_supported(api.ic0_call_perform());
@@ -718,6 +825,11 @@ fn test_pre_upgrade_support() {
     assert_api_supported(api.ic0_is_controller(0, 0, &[]));
     assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_supported(api.ic0_cycles_burn128(Cycles::zero(), 0, &mut []));
+    assert_api_supported(api.ic0_cost_call(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_create_canister(0, &mut []));
+    assert_api_supported(api.ic0_cost_http_request(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_ecdsa(0, 0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_schnorr(0, 0, 0, 0, &mut []));
     check_stable_apis_support(api);
 }
 
@@ -741,15 +853,21 @@ fn test_start_support() {
     assert_api_not_supported(api.ic0_msg_reply_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_msg_reject(0, 0, &[]));
     assert_api_not_supported(api.ic0_msg_reject_code());
+    assert_api_not_supported(api.ic0_msg_deadline());
     assert_api_not_supported(api.ic0_msg_reject_msg_size());
     assert_api_not_supported(api.ic0_msg_reject_msg_copy(0, 0, 0, &mut []));
     assert_api_not_supported(api.ic0_canister_self_size());
     assert_api_not_supported(api.ic0_canister_self_copy(0, 0, 0, &mut []));
+    assert_api_not_supported(api.ic0_subnet_self_size());
+    assert_api_not_supported(api.ic0_subnet_self_copy(0, 0, 0, &mut []));
+    assert_api_not_supported(api.ic0_root_key_size());
+    assert_api_not_supported(api.ic0_root_key_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_debug_print(0, 0, &[]));
     assert_api_supported(api.ic0_trap(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_not_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_not_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_not_supported(api.ic0_call_cycles_add(0));
     assert_api_not_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_not_supported(api.ic0_call_perform());
@@ -776,6 +894,11 @@ fn test_start_support() {
     assert_api_supported(api.ic0_is_controller(0, 0, &[]));
     assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_not_supported(api.ic0_cycles_burn128(Cycles::zero(), 0, &mut []));
+    assert_api_supported(api.ic0_cost_call(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_create_canister(0, &mut []));
+    assert_api_supported(api.ic0_cost_http_request(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_ecdsa(0, 0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_schnorr(0, 0, 0, 0, &mut []));
     check_stable_apis_support(api);
 }
 
@@ -803,15 +926,21 @@ fn test_cleanup_support() {
     assert_api_not_supported(api.ic0_msg_reply_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_msg_reject(0, 0, &[]));
     assert_api_not_supported(api.ic0_msg_reject_code());
+    assert_api_not_supported(api.ic0_msg_deadline());
     assert_api_not_supported(api.ic0_msg_reject_msg_size());
     assert_api_not_supported(api.ic0_msg_reject_msg_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_canister_self_size());
     assert_api_supported(api.ic0_canister_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_subnet_self_size());
+    assert_api_supported(api.ic0_subnet_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_root_key_size());
+    assert_api_supported(api.ic0_root_key_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_debug_print(0, 0, &[]));
     assert_api_supported(api.ic0_trap(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_not_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_not_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_not_supported(api.ic0_call_cycles_add(0));
     assert_api_not_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_not_supported(api.ic0_call_perform());
@@ -838,6 +967,11 @@ fn test_cleanup_support() {
     assert_api_supported(api.ic0_is_controller(0, 0, &[]));
     assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_supported(api.ic0_cycles_burn128(Cycles::zero(), 0, &mut []));
+    assert_api_supported(api.ic0_cost_call(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_create_canister(0, &mut []));
+    assert_api_supported(api.ic0_cost_http_request(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_ecdsa(0, 0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_schnorr(0, 0, 0, 0, &mut []));
     check_stable_apis_support(api);
 }
 
@@ -866,15 +1000,21 @@ fn test_inspect_message_support() {
     assert_api_not_supported(api.ic0_msg_reply_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_msg_reject(0, 0, &[]));
     assert_api_not_supported(api.ic0_msg_reject_code());
+    assert_api_not_supported(api.ic0_msg_deadline());
     assert_api_not_supported(api.ic0_msg_reject_msg_size());
     assert_api_not_supported(api.ic0_msg_reject_msg_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_canister_self_size());
     assert_api_supported(api.ic0_canister_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_subnet_self_size());
+    assert_api_supported(api.ic0_subnet_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_root_key_size());
+    assert_api_supported(api.ic0_root_key_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_debug_print(0, 0, &[]));
     assert_api_supported(api.ic0_trap(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_not_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_not_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_not_supported(api.ic0_call_cycles_add(0));
     assert_api_not_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_not_supported(api.ic0_call_perform());
@@ -901,6 +1041,11 @@ fn test_inspect_message_support() {
     assert_api_supported(api.ic0_is_controller(0, 0, &[]));
     assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_not_supported(api.ic0_cycles_burn128(Cycles::zero(), 0, &mut []));
+    assert_api_supported(api.ic0_cost_call(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_create_canister(0, &mut []));
+    assert_api_supported(api.ic0_cost_http_request(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_ecdsa(0, 0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_schnorr(0, 0, 0, 0, &mut []));
     check_stable_apis_support(api);
 }
 
@@ -925,15 +1070,21 @@ fn test_canister_system_task_support() {
     assert_api_not_supported(api.ic0_msg_reply_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_msg_reject(0, 0, &[]));
     assert_api_not_supported(api.ic0_msg_reject_code());
+    assert_api_not_supported(api.ic0_msg_deadline());
     assert_api_not_supported(api.ic0_msg_reject_msg_size());
     assert_api_not_supported(api.ic0_msg_reject_msg_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_canister_self_size());
     assert_api_supported(api.ic0_canister_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_subnet_self_size());
+    assert_api_supported(api.ic0_subnet_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_root_key_size());
+    assert_api_supported(api.ic0_root_key_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_debug_print(0, 0, &[]));
     assert_api_supported(api.ic0_trap(0, 0, &[]));
     assert_api_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_supported(api.ic0_call_cycles_add(0));
     assert_api_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_supported(api.ic0_call_perform());
@@ -960,6 +1111,11 @@ fn test_canister_system_task_support() {
     assert_api_supported(api.ic0_is_controller(0, 0, &[]));
     assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_supported(api.ic0_cycles_burn128(Cycles::zero(), 0, &mut []));
+    assert_api_supported(api.ic0_cost_call(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_create_canister(0, &mut []));
+    assert_api_supported(api.ic0_cost_http_request(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_ecdsa(0, 0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_schnorr(0, 0, 0, 0, &mut []));
     check_stable_apis_support(api);
 }
 
@@ -983,15 +1139,21 @@ fn test_canister_system_task_support_nns() {
     assert_api_not_supported(api.ic0_msg_reply_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_msg_reject(0, 0, &[]));
     assert_api_not_supported(api.ic0_msg_reject_code());
+    assert_api_not_supported(api.ic0_msg_deadline());
     assert_api_not_supported(api.ic0_msg_reject_msg_size());
     assert_api_not_supported(api.ic0_msg_reject_msg_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_canister_self_size());
     assert_api_supported(api.ic0_canister_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_subnet_self_size());
+    assert_api_supported(api.ic0_subnet_self_copy(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_root_key_size());
+    assert_api_supported(api.ic0_root_key_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_debug_print(0, 0, &[]));
     assert_api_supported(api.ic0_trap(0, 0, &[]));
     assert_api_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_supported(api.ic0_call_cycles_add(0));
     assert_api_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_supported(api.ic0_call_perform());
@@ -1018,6 +1180,11 @@ fn test_canister_system_task_support_nns() {
     assert_api_supported(api.ic0_is_controller(0, 0, &[]));
     assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_supported(api.ic0_cycles_burn128(Cycles::zero(), 0, &mut []));
+    assert_api_supported(api.ic0_cost_call(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_create_canister(0, &mut []));
+    assert_api_supported(api.ic0_cost_http_request(0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_ecdsa(0, 0, 0, 0, &mut []));
+    assert_api_supported(api.ic0_cost_sign_with_schnorr(0, 0, 0, 0, &mut []));
     check_stable_apis_support(api);
 }
 
@@ -1166,7 +1333,7 @@ fn test_canister_balance() {
         .call_context_manager_mut()
         .unwrap()
         .new_call_context(
-            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5)),
+            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5), None),
             Cycles::new(50),
             Time::from_nanos_since_unix_epoch(0),
             RequestMetadata::new(0, mock_time()),
@@ -1195,7 +1362,7 @@ fn test_canister_cycle_balance() {
         .call_context_manager_mut()
         .unwrap()
         .new_call_context(
-            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5)),
+            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5), None),
             Cycles::new(50),
             Time::from_nanos_since_unix_epoch(0),
             RequestMetadata::new(0, mock_time()),
@@ -1230,7 +1397,7 @@ fn test_msg_cycles_available_traps() {
         .call_context_manager_mut()
         .unwrap()
         .new_call_context(
-            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5)),
+            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5), None),
             available_cycles,
             Time::from_nanos_since_unix_epoch(0),
             RequestMetadata::new(0, mock_time()),
@@ -1311,6 +1478,43 @@ fn certified_data_set() {
     assert_eq!(system_state.certified_data, vec![10; 32])
 }
 
+#[test]
+fn save_log_message_appends_to_canister_log() {
+    let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
+    let mut system_state = SystemStateBuilder::default().build();
+    system_state
+        .canister_log
+        .add_record(0, b"previous execution".to_vec());
+    let mut api = get_system_api(
+        ApiTypeBuilder::build_update_api(),
+        &system_state,
+        cycles_account_manager,
+    );
+    let heap = b"hello world".to_vec();
+
+    api.save_log_message(0, 5, &heap);
+    // Out of bounds messages are replaced by a placeholder instead of trapping.
+    api.save_log_message(5, 100, &heap);
+
+    system_state
+        .canister_log
+        .append_delta_log(&mut api.take_canister_log());
+    let records: Vec<_> = system_state
+        .canister_log
+        .records()
+        .iter()
+        .map(|record| (record.idx, record.content.clone()))
+        .collect();
+    assert_eq!(
+        records,
+        vec![
+            (0, b"previous execution".to_vec()),
+            (1, b"hello".to_vec()),
+            (2, b"(debug message out of memory bounds)".to_vec()),
+        ]
+    );
+}
+
 #[test]
 fn data_certificate_copy() {
     let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
@@ -1392,7 +1596,7 @@ fn msg_cycles_accept_all_cycles_in_call_context() {
         .call_context_manager_mut()
         .unwrap()
         .new_call_context(
-            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5)),
+            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5), None),
             Cycles::from(amount),
             Time::from_nanos_since_unix_epoch(0),
             RequestMetadata::new(0, mock_time()),
@@ -1416,7 +1620,7 @@ fn msg_cycles_accept_all_cycles_in_call_context_when_more_asked() {
         .call_context_manager_mut()
         .unwrap()
         .new_call_context(
-            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5)),
+            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5), None),
             Cycles::new(40),
             Time::from_nanos_since_unix_epoch(0),
             RequestMetadata::new(0, mock_time()),
@@ -1449,7 +1653,7 @@ fn call_perform_not_enough_cycles_does_not_trap() {
         .call_context_manager_mut()
         .unwrap()
         .new_call_context(
-            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5)),
+            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5), None),
             Cycles::new(40),
             Time::from_nanos_since_unix_epoch(0),
             RequestMetadata::new(0, mock_time()),
@@ -1488,6 +1692,247 @@ fn call_perform_not_enough_cycles_does_not_trap() {
     assert_eq!(call_context_manager.callbacks().len(), 0);
 }
 
+#[test]
+fn call_with_best_effort_response_caps_timeout_and_sets_deadline() {
+    let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
+    let mut system_state = SystemStateBuilder::default().build();
+    system_state
+        .call_context_manager_mut()
+        .unwrap()
+        .new_call_context(
+            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5), None),
+            Cycles::zero(),
+            Time::from_nanos_since_unix_epoch(0),
+            RequestMetadata::new(0, mock_time()),
+        );
+    let mut api = get_system_api(
+        ApiTypeBuilder::build_update_api(),
+        &system_state,
+        cycles_account_manager,
+    );
+    api.ic0_call_new(0, 10, 0, 10, 0, 0, 0, 0, &[0; 1024])
+        .unwrap();
+    api.ic0_call_with_best_effort_response(1000).unwrap();
+    // Setting the timeout a second time is not allowed.
+    assert_matches!(
+        api.ic0_call_with_best_effort_response(10),
+        Err(HypervisorError::ContractViolation(_))
+    );
+    assert_eq!(api.ic0_call_perform().unwrap(), 0);
+
+    let system_state_changes = api.into_system_state_changes();
+    system_state_changes
+        .apply_changes(
+            mock_time(),
+            &mut system_state,
+            &default_network_topology(),
+            subnet_test_id(1),
+            &no_op_logger(),
+        )
+        .unwrap();
+    // The timeout is capped at 300 seconds.
+    let expected_deadline = mock_time() + std::time::Duration::from_secs(300);
+    let callbacks = system_state.call_context_manager().unwrap().callbacks();
+    assert_eq!(callbacks.len(), 1);
+    assert_eq!(
+        callbacks.values().next().unwrap().deadline,
+        Some(expected_deadline)
+    );
+}
+
+fn cycles_from_heap(heap: &[u8]) -> Cycles {
+    Cycles::new(u128::from_le_bytes(heap[..16].try_into().unwrap()))
+}
+
+#[test]
+fn cost_apis_match_cycles_account_manager_fees() {
+    let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
+    let system_state = SystemStateBuilder::default().build();
+    let api = get_system_api(
+        ApiTypeBuilder::build_update_api(),
+        &system_state,
+        cycles_account_manager,
+    );
+    let subnet_size = SMALL_APP_SUBNET_MAX_SIZE;
+    let mut heap = vec![0; 16];
+
+    api.ic0_cost_call(10, 100, 0, &mut heap).unwrap();
+    assert_eq!(
+        cycles_from_heap(&heap),
+        cycles_account_manager.xnet_call_performed_fee(subnet_size)
+            + cycles_account_manager
+                .xnet_call_bytes_transmitted_fee(NumBytes::from(110), subnet_size)
+            + cycles_account_manager.prepayment_for_response_transmission(subnet_size)
+            + cycles_account_manager.prepayment_for_response_execution(subnet_size)
+    );
+
+    api.ic0_cost_create_canister(0, &mut heap).unwrap();
+    assert_eq!(
+        cycles_from_heap(&heap),
+        cycles_account_manager.canister_creation_fee(subnet_size)
+    );
+
+    api.ic0_cost_http_request(1_000, 2_000, 0, &mut heap)
+        .unwrap();
+    assert_eq!(
+        cycles_from_heap(&heap),
+        cycles_account_manager.http_request_fee(
+            NumBytes::from(1_000),
+            Some(NumBytes::from(2_000)),
+            subnet_size
+        )
+    );
+}
+
+#[test]
+fn cost_sign_with_ecdsa_uses_size_of_signing_subnet() {
+    let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
+    let system_state = SystemStateBuilder::default().build();
+    let key_id = EcdsaKeyId {
+        curve: EcdsaCurve::Secp256k1,
+        name: "key".to_string(),
+    };
+    let signing_subnet_size = 34;
+    let mut network_topology = default_network_topology();
+    network_topology
+        .subnets
+        .get_mut(&subnet_test_id(1))
+        .unwrap()
+        .nodes = (0..signing_subnet_size as u64).map(node_test_id).collect();
+    network_topology
+        .ecdsa_signing_subnets
+        .insert(key_id, vec![subnet_test_id(1)]);
+    let sandbox_safe_system_state = SandboxSafeSystemState::new(
+        &system_state,
+        cycles_account_manager,
+        &network_topology,
+        SchedulerConfig::application_subnet().dirty_page_overhead,
+        execution_parameters().compute_allocation,
+        RequestMetadata::new(0, mock_time()),
+    );
+    let api = SystemApiImpl::new(
+        ApiTypeBuilder::build_update_api(),
+        sandbox_safe_system_state,
+        CANISTER_CURRENT_MEMORY_USAGE,
+        CANISTER_CURRENT_MESSAGE_MEMORY_USAGE,
+        execution_parameters(),
+        SubnetAvailableMemory::new(i64::MAX / 2, i64::MAX / 2, i64::MAX / 2),
+        EmbeddersConfig::default()
+            .feature_flags
+            .wasm_native_stable_memory,
+        EmbeddersConfig::default().max_sum_exported_function_name_lengths,
+        Memory::new_for_testing(),
+        Rc::new(DefaultOutOfInstructionsHandler {}),
+        no_op_logger(),
+    );
+    // The key name is stored right after the 16 bytes reserved for the result.
+    let mut heap = vec![0; 16];
+    heap.extend_from_slice(b"key");
+
+    assert_eq!(
+        api.ic0_cost_sign_with_ecdsa(16, 3, 0, 0, &mut heap)
+            .unwrap(),
+        0
+    );
+    assert_eq!(
+        cycles_from_heap(&heap),
+        cycles_account_manager.ecdsa_signature_fee(signing_subnet_size)
+    );
+    // Unknown curve.
+    assert_eq!(
+        api.ic0_cost_sign_with_ecdsa(16, 3, 1, 0, &mut heap)
+            .unwrap(),
+        1
+    );
+    // Unknown key name.
+    assert_eq!(
+        api.ic0_cost_sign_with_ecdsa(16, 2, 0, 0, &mut heap)
+            .unwrap(),
+        2
+    );
+}
+
+#[test]
+fn cost_sign_with_schnorr_uses_size_of_signing_subnet() {
+    let cycles_account_manager = CyclesAccountManagerBuilder::new()
+        .with_schnorr_signature_fee(Cycles::new(20_000_000_000))
+        .build();
+    let system_state = SystemStateBuilder::default().build();
+    let key_id = SchnorrKeyId {
+        algorithm: SchnorrAlgorithm::Bip340Secp256k1,
+        name: "key".to_string(),
+    };
+    let signing_subnet_size = 34;
+    let mut network_topology = default_network_topology();
+    network_topology
+        .subnets
+        .get_mut(&subnet_test_id(1))
+        .unwrap()
+        .nodes = (0..signing_subnet_size as u64).map(node_test_id).collect();
+    network_topology
+        .schnorr_signing_subnets
+        .insert(key_id, vec![subnet_test_id(1)]);
+    let sandbox_safe_system_state = SandboxSafeSystemState::new(
+        &system_state,
+        cycles_account_manager,
+        &network_topology,
+        SchedulerConfig::application_subnet().dirty_page_overhead,
+        execution_parameters().compute_allocation,
+        RequestMetadata::new(0, mock_time()),
+    );
+    let api = SystemApiImpl::new(
+        ApiTypeBuilder::build_update_api(),
+        sandbox_safe_system_state,
+        CANISTER_CURRENT_MEMORY_USAGE,
+        CANISTER_CURRENT_MESSAGE_MEMORY_USAGE,
+        execution_parameters(),
+        SubnetAvailableMemory::new(i64::MAX / 2, i64::MAX / 2, i64::MAX / 2),
+        EmbeddersConfig::default()
+            .feature_flags
+            .wasm_native_stable_memory,
+        EmbeddersConfig::default().max_sum_exported_function_name_lengths,
+        Memory::new_for_testing(),
+        Rc::new(DefaultOutOfInstructionsHandler {}),
+        no_op_logger(),
+    );
+    // The key name is stored right after the 16 bytes reserved for the result.
+    let mut heap = vec![0; 16];
+    heap.extend_from_slice(b"key");
+
+    assert_eq!(
+        api.ic0_cost_sign_with_schnorr(16, 3, 0, 0, &mut heap)
+            .unwrap(),
+        0
+    );
+    assert_eq!(
+        cycles_from_heap(&heap),
+        cycles_account_manager.schnorr_signature_fee(signing_subnet_size)
+    );
+    // The fee differs from the one of ECDSA signatures.
+    assert_ne!(
+        cycles_from_heap(&heap),
+        cycles_account_manager.ecdsa_signature_fee(signing_subnet_size)
+    );
+    // No Ed25519 key with that name.
+    assert_eq!(
+        api.ic0_cost_sign_with_schnorr(16, 3, 1, 0, &mut heap)
+            .unwrap(),
+        2
+    );
+    // Unknown algorithm.
+    assert_eq!(
+        api.ic0_cost_sign_with_schnorr(16, 3, 2, 0, &mut heap)
+            .unwrap(),
+        1
+    );
+    // Unknown key name.
+    assert_eq!(
+        api.ic0_cost_sign_with_schnorr(16, 2, 0, 0, &mut heap)
+            .unwrap(),
+        2
+    );
+}
+
 #[test]
 fn update_available_memory_updates_subnet_available_memory() {
     let wasm_page_size = 64 << 10;
@@ -1572,6 +2017,7 @@ fn push_output_request_respects_memory_limits() {
             WasmClosure::new(0, 0),
             WasmClosure::new(0, 0),
             None,
+            None,
         ))
         .unwrap();
     let mut api = SystemApiImpl::new(
@@ -1678,6 +2124,7 @@ fn push_output_request_oversized_request_memory_limits() {
             WasmClosure::new(0, 0),
             WasmClosure::new(0, 0),
             None,
+            None,
         ))
         .unwrap();
     let mut api = SystemApiImpl::new(
@@ -1807,7 +2254,7 @@ fn ic0_is_controller_test() {
     for i in 1..5 {
         let controller = user_test_id(i).get();
         assert_eq!(
-            api.ic0_is_controller(0, controller.as_slice().len() as u32, controller.as_slice())
+            api.ic0_is_controller(0, controller.as_slice().len(), controller.as_slice())
                 .unwrap(),
             (i <= 2) as u32
         );
@@ -1823,7 +2270,7 @@ fn ic0_is_controller_invalid_principal_id() {
     );
     let controller = [0u8; 70];
     assert!(matches!(
-        api.ic0_is_controller(0, controller.len() as u32, &controller),
+        api.ic0_is_controller(0, controller.len(), &controller),
         Err(HypervisorError::InvalidPrincipalId(
             PrincipalIdBlobParseError(..)
         ))
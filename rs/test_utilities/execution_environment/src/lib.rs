@@ -23,7 +23,7 @@ use ic_ic00_types::{
     CanisterIdRecord, CanisterInstallMode, CanisterInstallModeV2, CanisterSettingsArgs,
     CanisterSettingsArgsBuilder, CanisterStatusType, EcdsaKeyId, EmptyBlob, InstallCodeArgs,
     InstallCodeArgsV2, LogVisibility, Method, Payload, ProvisionalCreateCanisterWithCyclesArgs,
-    SkipPreUpgrade, UpdateSettingsArgs,
+    SchnorrAlgorithm, SchnorrKeyId, SkipPreUpgrade, UpdateSettingsArgs,
 };
 use ic_interfaces::execution_environment::{
     ExecutionMode, IngressHistoryWriter, QueryHandler, RegistryExecutionSettings,
@@ -49,7 +49,10 @@ use ic_replicated_state::{page_map::TestPageAllocatorFileDescriptorImpl, PageMap
 use ic_system_api::InstructionLimits;
 use ic_types::{
     batch::QueryStats,
-    crypto::{canister_threshold_sig::MasterEcdsaPublicKey, AlgorithmId},
+    crypto::{
+        canister_threshold_sig::{MasterEcdsaPublicKey, MasterSchnorrPublicKey},
+        AlgorithmId,
+    },
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::{
         AnonymousQuery, CallbackId, CanisterCall, CanisterMessage, CanisterTask, MessageId,
@@ -134,6 +137,7 @@ pub fn test_registry_settings() -> RegistryExecutionSettings {
         max_number_of_canisters: 0x2000,
         provisional_whitelist: ProvisionalWhitelist::Set(BTreeSet::new()),
         max_ecdsa_queue_size: 20,
+        max_schnorr_queue_size: 20,
         quadruples_to_create_in_advance: 5,
         subnet_size: SMALL_APP_SUBNET_MAX_SIZE,
     }
@@ -196,6 +200,7 @@ pub struct ExecutionTest {
     manual_execution: bool,
     caller_canister_id: Option<CanisterId>,
     ecdsa_subnet_public_keys: BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+    schnorr_subnet_public_keys: BTreeMap<SchnorrKeyId, MasterSchnorrPublicKey>,
 
     // The actual implementation.
     exec_env: ExecutionEnvironment,
@@ -566,6 +571,23 @@ impl ExecutionTest {
         self.subnet_message(Method::UpdateSettings, payload)
     }
 
+    /// Updates the Wasm memory limit of the canister.
+    pub fn canister_update_wasm_memory_limit(
+        &mut self,
+        canister_id: CanisterId,
+        wasm_memory_limit: NumBytes,
+    ) -> Result<WasmResult, UserError> {
+        let payload = UpdateSettingsArgs {
+            canister_id: canister_id.into(),
+            settings: CanisterSettingsArgsBuilder::new()
+                .with_wasm_memory_limit(wasm_memory_limit.get())
+                .build(),
+            sender_canister_version: None,
+        }
+        .encode();
+        self.subnet_message(Method::UpdateSettings, payload)
+    }
+
     /// Sends an `install_code` message to the IC management canister.
     /// Consider using higher-level helpers like `canister_from_wat()`.
     pub fn install_code(&mut self, args: InstallCodeArgs) -> Result<WasmResult, UserError> {
@@ -1166,6 +1188,7 @@ impl ExecutionTest {
             self.install_code_instruction_limits.clone(),
             &mut mock_random_number_generator(),
             &self.ecdsa_subnet_public_keys,
+            &self.schnorr_subnet_public_keys,
             &self.registry_settings,
             &mut round_limits,
         );
@@ -1572,7 +1595,9 @@ pub struct ExecutionTestBuilder {
     log: ReplicaLogger,
     caller_canister_id: Option<CanisterId>,
     ecdsa_signature_fee: Option<Cycles>,
+    schnorr_signature_fee: Option<Cycles>,
     ecdsa_key: Option<EcdsaKeyId>,
+    schnorr_key: Option<SchnorrKeyId>,
     instruction_limit: NumInstructions,
     slice_instruction_limit: NumInstructions,
     install_code_instruction_limit: NumInstructions,
@@ -1610,7 +1635,9 @@ impl Default for ExecutionTestBuilder {
             log: no_op_logger(),
             caller_canister_id: None,
             ecdsa_signature_fee: None,
+            schnorr_signature_fee: None,
             ecdsa_key: None,
+            schnorr_key: None,
             instruction_limit: scheduler_config.max_instructions_per_message,
             slice_instruction_limit: scheduler_config.max_instructions_per_slice,
             install_code_instruction_limit: scheduler_config.max_instructions_per_install_code,
@@ -1686,6 +1713,13 @@ impl ExecutionTestBuilder {
         }
     }
 
+    pub fn with_schnorr_signature_fee(self, schnorr_signing_fee: u128) -> Self {
+        Self {
+            schnorr_signature_fee: Some(Cycles::new(schnorr_signing_fee)),
+            ..self
+        }
+    }
+
     pub fn with_ecdsa_key(self, ecdsa_key: EcdsaKeyId) -> Self {
         Self {
             ecdsa_key: Some(ecdsa_key),
@@ -1693,6 +1727,13 @@ impl ExecutionTestBuilder {
         }
     }
 
+    pub fn with_schnorr_key(self, schnorr_key: SchnorrKeyId) -> Self {
+        Self {
+            schnorr_key: Some(schnorr_key),
+            ..self
+        }
+    }
+
     pub fn with_instruction_limit(self, limit: u64) -> Self {
         Self {
             instruction_limit: NumInstructions::from(limit),
@@ -1909,6 +1950,11 @@ impl ExecutionTestBuilder {
         self
     }
 
+    pub fn with_wasm_profiling(mut self) -> Self {
+        self.execution_config.embedders_config.wasm_profiling = FlagStatus::Enabled;
+        self
+    }
+
     pub fn with_wasm_chunk_store(mut self, status: FlagStatus) -> Self {
         self.execution_config.wasm_chunk_store = status;
         self
@@ -1922,6 +1968,24 @@ impl ExecutionTestBuilder {
         self
     }
 
+    pub fn with_best_effort_responses(mut self, status: FlagStatus) -> Self {
+        self.execution_config
+            .embedders_config
+            .feature_flags
+            .best_effort_responses = status;
+        self
+    }
+
+    pub fn with_wasm64(mut self) -> Self {
+        self.execution_config.embedders_config.feature_flags.wasm64 = FlagStatus::Enabled;
+        self
+    }
+
+    pub fn with_max_canister_memory_size(mut self, max_canister_memory_size: u64) -> Self {
+        self.execution_config.max_canister_memory_size = NumBytes::new(max_canister_memory_size);
+        self
+    }
+
     pub fn with_snapshots(mut self, status: FlagStatus) -> Self {
         self.execution_config.canister_snapshots = status;
         self
@@ -2003,6 +2067,9 @@ impl ExecutionTestBuilder {
         if let Some(ecdsa_signature_fee) = self.ecdsa_signature_fee {
             config.ecdsa_signature_fee = ecdsa_signature_fee;
         }
+        if let Some(schnorr_signature_fee) = self.schnorr_signature_fee {
+            config.schnorr_signature_fee = schnorr_signature_fee;
+        }
         if let Some(ecdsa_key) = &self.ecdsa_key {
             state
                 .metadata
@@ -2018,6 +2085,13 @@ impl ExecutionTestBuilder {
                 .ecdsa_keys_held
                 .insert(ecdsa_key.clone());
         }
+        if let Some(schnorr_key) = &self.schnorr_key {
+            state
+                .metadata
+                .network_topology
+                .schnorr_signing_subnets
+                .insert(schnorr_key.clone(), vec![self.own_subnet_id]);
+        }
 
         state.metadata.network_topology.bitcoin_mainnet_canister_id =
             self.execution_config.bitcoin.mainnet_canister_id;
@@ -2038,6 +2112,14 @@ impl ExecutionTestBuilder {
                 )
             })
             .collect();
+        let schnorr_subnet_public_keys = self
+            .schnorr_key
+            .into_iter()
+            .map(|key| {
+                let public_key = master_schnorr_public_key_for_testing(key.algorithm);
+                (key, public_key)
+            })
+            .collect();
         let cycles_account_manager = Arc::new(CyclesAccountManager::new(
             self.instruction_limit,
             self.subnet_type,
@@ -2144,12 +2226,33 @@ impl ExecutionTestBuilder {
             ingress_history_writer,
             manual_execution: self.manual_execution,
             ecdsa_subnet_public_keys,
+            schnorr_subnet_public_keys,
             log: self.log,
             checkpoint_files: vec![],
         }
     }
 }
 
+/// Returns a valid master public key for the given Schnorr algorithm.
+///
+/// The key is the generator of the secp256k1 group, which is enough for key
+/// derivation to succeed in tests. Only BIP340 keys are supported.
+pub fn master_schnorr_public_key_for_testing(
+    algorithm: SchnorrAlgorithm,
+) -> MasterSchnorrPublicKey {
+    match algorithm {
+        SchnorrAlgorithm::Bip340Secp256k1 => MasterSchnorrPublicKey {
+            algorithm_id: AlgorithmId::SchnorrSecp256k1,
+            public_key: vec![
+                0x02, 0x79, 0xbe, 0x66, 0x7e, 0xf9, 0xdc, 0xbb, 0xac, 0x55, 0xa0, 0x62, 0x95, 0xce,
+                0x87, 0x0b, 0x07, 0x02, 0x9b, 0xfc, 0xdb, 0x2d, 0xce, 0x28, 0xd9, 0x59, 0xf2, 0x81,
+                0x5b, 0x16, 0xf8, 0x17, 0x98,
+            ],
+        },
+        SchnorrAlgorithm::Ed25519 => panic!("Threshold Ed25519 keys are not supported"),
+    }
+}
+
 /// A helper to extract the reply from an execution result.
 pub fn get_reply(result: Result<WasmResult, UserError>) -> Vec<u8> {
     match result {
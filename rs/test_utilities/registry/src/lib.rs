@@ -12,7 +12,7 @@ use ic_registry_keys::{
     make_subnet_list_record_key, make_subnet_record_key,
 };
 use ic_registry_proto_data_provider::ProtoRegistryDataProvider;
-use ic_registry_subnet_features::{EcdsaConfig, SubnetFeatures};
+use ic_registry_subnet_features::{EcdsaConfig, SchnorrConfig, SubnetFeatures};
 use ic_registry_subnet_type::SubnetType;
 use ic_types::crypto::threshold_sig::ThresholdSigPublicKey;
 use ic_types::{
@@ -218,6 +218,7 @@ pub fn test_subnet_record() -> SubnetRecord {
         ssh_readonly_access: vec![],
         ssh_backup_access: vec![],
         ecdsa_config: None,
+        schnorr_config: None,
     }
 }
 
@@ -308,6 +309,11 @@ impl SubnetRecordBuilder {
         self
     }
 
+    pub fn with_schnorr_config(mut self, schnorr_config: SchnorrConfig) -> Self {
+        self.record.schnorr_config = Some(schnorr_config.into());
+        self
+    }
+
     pub fn with_membership(mut self, node_ids: &[NodeId]) -> Self {
         self.record.membership = node_ids
             .iter()
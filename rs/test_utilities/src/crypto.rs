@@ -14,7 +14,8 @@ use ic_interfaces::crypto::{
     BasicSigVerifier, BasicSigner, CheckKeysWithRegistryError, CurrentNodePublicKeysError,
     IDkgDealingEncryptionKeyRotationError, IDkgKeyRotationResult, IDkgProtocol, KeyManager,
     LoadTranscriptResult, NiDkgAlgorithm, ThresholdEcdsaSigVerifier, ThresholdEcdsaSigner,
-    ThresholdSigVerifier, ThresholdSigVerifierByPublicKey, ThresholdSigner,
+    ThresholdSchnorrSigVerifier, ThresholdSchnorrSigner, ThresholdSigVerifier,
+    ThresholdSigVerifierByPublicKey, ThresholdSigner,
 };
 use ic_interfaces::crypto::{MultiSigVerifier, MultiSigner};
 use ic_interfaces_registry::RegistryClient;
@@ -489,6 +490,44 @@ impl ThresholdEcdsaSigVerifier for CryptoReturningOk {
     }
 }
 
+impl ThresholdSchnorrSigner for CryptoReturningOk {
+    fn sign_share(
+        &self,
+        _inputs: &ThresholdSchnorrSigInputs,
+    ) -> Result<ThresholdSchnorrSigShare, ThresholdSchnorrSignShareError> {
+        Ok(ThresholdSchnorrSigShare {
+            sig_share_raw: vec![],
+        })
+    }
+}
+
+impl ThresholdSchnorrSigVerifier for CryptoReturningOk {
+    fn verify_sig_share(
+        &self,
+        _signer: NodeId,
+        _inputs: &ThresholdSchnorrSigInputs,
+        _share: &ThresholdSchnorrSigShare,
+    ) -> Result<(), ThresholdSchnorrVerifySigShareError> {
+        Ok(())
+    }
+
+    fn combine_sig_shares(
+        &self,
+        _inputs: &ThresholdSchnorrSigInputs,
+        _shares: &BTreeMap<NodeId, ThresholdSchnorrSigShare>,
+    ) -> Result<ThresholdSchnorrCombinedSignature, ThresholdSchnorrCombineSigSharesError> {
+        Ok(ThresholdSchnorrCombinedSignature { signature: vec![] })
+    }
+
+    fn verify_combined_sig(
+        &self,
+        _inputs: &ThresholdSchnorrSigInputs,
+        _signature: &ThresholdSchnorrCombinedSignature,
+    ) -> Result<(), ThresholdSchnorrVerifyCombinedSignatureError> {
+        Ok(())
+    }
+}
+
 impl TlsConfig for CryptoReturningOk {
     fn server_config(
         &self,
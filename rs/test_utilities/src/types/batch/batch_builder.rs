@@ -20,6 +20,7 @@ impl Default for BatchBuilder {
                 messages: BatchMessages::default(),
                 randomness: Randomness::from([0; 32]),
                 ecdsa_subnet_public_keys: BTreeMap::new(),
+                schnorr_subnet_public_keys: BTreeMap::new(),
                 ecdsa_quadruple_ids: BTreeMap::new(),
                 registry_version: RegistryVersion::from(1),
                 time: mock_time(),
@@ -1,7 +1,7 @@
 use crate::types::ids::canister_test_id;
 use ic_types::{
     messages::{CallbackId, Request, RequestMetadata},
-    CanisterId, Cycles,
+    CanisterId, Cycles, Time,
 };
 
 pub struct RequestBuilder {
@@ -21,6 +21,7 @@ impl Default for RequestBuilder {
                 method_name: name.to_string(),
                 method_payload: Vec::new(),
                 metadata: None,
+                deadline: None,
             },
         }
     }
@@ -75,6 +76,12 @@ impl RequestBuilder {
         self
     }
 
+    /// Sets the `deadline` field.
+    pub fn deadline(mut self, deadline: Option<Time>) -> Self {
+        self.request.deadline = deadline;
+        self
+    }
+
     /// Returns the built `Request`.
     pub fn build(self) -> Request {
         self.request
@@ -1,7 +1,7 @@
 use crate::types::ids::canister_test_id;
 use ic_types::{
     messages::{CallbackId, Payload, Response},
-    CanisterId, Cycles,
+    CanisterId, Cycles, Time,
 };
 
 pub struct ResponseBuilder {
@@ -19,6 +19,7 @@ impl Default for ResponseBuilder {
                 originator_reply_callback: CallbackId::from(0),
                 refund: Cycles::zero(),
                 response_payload: rpb.build(),
+                deadline: None,
             },
         }
     }
@@ -60,6 +61,12 @@ impl ResponseBuilder {
         self
     }
 
+    /// Sets the `deadline` field.
+    pub fn deadline(mut self, deadline: Option<Time>) -> Self {
+        self.response.deadline = deadline;
+        self
+    }
+
     /// Returns the built `Response`.
     pub fn build(&self) -> Response {
         self.response.clone()
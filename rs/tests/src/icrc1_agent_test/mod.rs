@@ -295,9 +295,15 @@ pub fn test(env: TestEnv) {
         use LookupStatus::Found;
         let hash_tree: MixedHashTree = serde_cbor::from_slice(&data_certificate.hash_tree).unwrap();
 
+        // The last block index is LEB128-encoded, as required by ICRC-3.
         assert_eq!(
             hash_tree.lookup(&[b"last_block_index"]),
-            Found(&mleaf((1_u64).to_be_bytes()))
+            Found(&mleaf([1_u8]))
+        );
+
+        assert_eq!(
+            hash_tree.lookup(&[b"last_block_hash"]),
+            Found(&mleaf(archived_blocks.blocks[1].hash()))
         );
 
         assert_eq!(
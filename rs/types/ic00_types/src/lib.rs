@@ -60,6 +60,8 @@ pub enum Method {
     RawRand,
     SetupInitialDKG,
     SignWithECDSA,
+    SchnorrPublicKey,
+    SignWithSchnorr,
     StartCanister,
     StopCanister,
     UninstallCode,
@@ -269,6 +271,36 @@ impl CanisterControllersChangeRecord {
     }
 }
 
+/// `CandidType` for `CanisterLoadSnapshotRecord`
+/// ```text
+/// record {
+///   canister_version : nat64;
+///   snapshot_id : blob;
+///   taken_at_timestamp : nat64;
+/// }
+/// ```
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CanisterLoadSnapshotRecord {
+    canister_version: u64,
+    snapshot_id: ByteBuf,
+    taken_at_timestamp: u64,
+}
+
+impl CanisterLoadSnapshotRecord {
+    pub fn canister_version(&self) -> u64 {
+        self.canister_version
+    }
+
+    /// Returns `None` if the stored bytes do not represent a valid snapshot ID.
+    pub fn snapshot_id(&self) -> Option<u64> {
+        decode_snapshot_id(&self.snapshot_id)
+    }
+
+    pub fn taken_at_timestamp(&self) -> u64 {
+        self.taken_at_timestamp
+    }
+}
+
 /// `CandidType` for `CanisterChangeDetails`
 /// ```text
 /// variant {
@@ -283,6 +315,11 @@ impl CanisterControllersChangeRecord {
 ///   controllers_change : record {
 ///     controllers : vec principal;
 ///   };
+///   load_snapshot : record {
+///     canister_version : nat64;
+///     snapshot_id : blob;
+///     taken_at_timestamp : nat64;
+///   };
 /// }
 /// ```
 #[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -295,6 +332,8 @@ pub enum CanisterChangeDetails {
     CanisterCodeDeployment(CanisterCodeDeploymentRecord),
     #[serde(rename = "controllers_change")]
     CanisterControllersChange(CanisterControllersChangeRecord),
+    #[serde(rename = "load_snapshot")]
+    CanisterLoadSnapshot(CanisterLoadSnapshotRecord),
 }
 
 impl CanisterChangeDetails {
@@ -317,9 +356,21 @@ impl CanisterChangeDetails {
             controllers,
         })
     }
+
+    pub fn load_snapshot(
+        canister_version: u64,
+        snapshot_id: u64,
+        taken_at_timestamp: u64,
+    ) -> CanisterChangeDetails {
+        CanisterChangeDetails::CanisterLoadSnapshot(CanisterLoadSnapshotRecord {
+            canister_version,
+            snapshot_id: encode_snapshot_id(snapshot_id),
+            taken_at_timestamp,
+        })
+    }
 }
 
-/// Every canister change (canister creation, code uninstallation, code deployment, or controllers change) consists of
+/// Every canister change (canister creation, code uninstallation, code deployment, controllers change, or snapshot load) consists of
 ///
 /// 1. the system timestamp (in nanoseconds since Unix Epoch) at which the change was performed,
 /// 2. the canister version after performing the change,
@@ -335,6 +386,9 @@ impl CanisterChangeDetails {
 ///
 /// Controllers changes are described by the full new set of the canister controllers after the change.
 ///
+/// Snapshot loads are described by the loaded snapshot's ID, the canister version at which the snapshot
+/// was taken, and the time at which the snapshot was taken.
+///
 /// `CandidType` for `CanisterChange`
 /// ```text
 /// record {
@@ -369,20 +423,23 @@ impl CanisterChange {
 
     /// Returns the number of bytes to represent a canister change in memory.
     /// The vector of controllers in `CanisterCreation` and `CanisterControllersChange`
-    /// is counted separately because the controllers are stored on heap
-    /// and thus not accounted for in `size_of::<CanisterChange>()`.
+    /// and the snapshot ID in `CanisterLoadSnapshot` are counted separately because
+    /// they are stored on heap and thus not accounted for in `size_of::<CanisterChange>()`.
     pub fn count_bytes(&self) -> NumBytes {
-        let controllers_memory_size = match &self.details {
+        let heap_memory_size = match &self.details {
             CanisterChangeDetails::CanisterCreation(canister_creation) => {
                 std::mem::size_of_val(canister_creation.controllers())
             }
             CanisterChangeDetails::CanisterControllersChange(canister_controllers_change) => {
                 std::mem::size_of_val(canister_controllers_change.controllers())
             }
+            CanisterChangeDetails::CanisterLoadSnapshot(canister_load_snapshot) => {
+                canister_load_snapshot.snapshot_id.len()
+            }
             CanisterChangeDetails::CanisterCodeDeployment(_)
             | CanisterChangeDetails::CanisterCodeUninstall => 0,
         };
-        NumBytes::from((size_of::<CanisterChange>() + controllers_memory_size) as u64)
+        NumBytes::from((size_of::<CanisterChange>() + heap_memory_size) as u64)
     }
 
     pub fn canister_version(&self) -> u64 {
@@ -560,6 +617,15 @@ impl From<&CanisterChangeDetails> for pb_canister_state_bits::canister_change::C
                     },
                 )
             }
+            CanisterChangeDetails::CanisterLoadSnapshot(canister_load_snapshot) => {
+                pb_canister_state_bits::canister_change::ChangeDetails::CanisterLoadSnapshot(
+                    pb_canister_state_bits::CanisterLoadSnapshot {
+                        canister_version: canister_load_snapshot.canister_version,
+                        snapshot_id: canister_load_snapshot.snapshot_id.to_vec(),
+                        taken_at_timestamp: canister_load_snapshot.taken_at_timestamp,
+                    },
+                )
+            }
         }
     }
 }
@@ -603,6 +669,15 @@ impl TryFrom<pb_canister_state_bits::canister_change::ChangeDetails> for Caniste
                     .map(TryInto::try_into)
                     .collect::<Result<Vec<PrincipalId>, _>>()?,
             )),
+            pb_canister_state_bits::canister_change::ChangeDetails::CanisterLoadSnapshot(
+                canister_load_snapshot,
+            ) => Ok(CanisterChangeDetails::CanisterLoadSnapshot(
+                CanisterLoadSnapshotRecord {
+                    canister_version: canister_load_snapshot.canister_version,
+                    snapshot_id: ByteBuf::from(canister_load_snapshot.snapshot_id),
+                    taken_at_timestamp: canister_load_snapshot.taken_at_timestamp,
+                },
+            )),
         }
     }
 }
@@ -707,7 +782,10 @@ impl From<i32> for LogVisibility {
 ///     freezing_threshold: nat;
 ///     reserved_cycles_limit: nat;
 ///     log_visibility: log_visibility;
+///     wasm_memory_limit: nat;
 /// })`
+///
+/// A `wasm_memory_limit` of zero means that the Wasm memory is not limited.
 #[derive(CandidType, Clone, Deserialize, Debug, Eq, PartialEq)]
 pub struct DefiniteCanisterSettingsArgs {
     controller: PrincipalId,
@@ -717,9 +795,11 @@ pub struct DefiniteCanisterSettingsArgs {
     freezing_threshold: candid::Nat,
     reserved_cycles_limit: candid::Nat,
     log_visibility: LogVisibility,
+    wasm_memory_limit: candid::Nat,
 }
 
 impl DefiniteCanisterSettingsArgs {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         controller: PrincipalId,
         controllers: Vec<PrincipalId>,
@@ -728,9 +808,11 @@ impl DefiniteCanisterSettingsArgs {
         freezing_threshold: u64,
         reserved_cycles_limit: Option<u128>,
         log_visibility: LogVisibility,
+        wasm_memory_limit: Option<u64>,
     ) -> Self {
         let memory_allocation = candid::Nat::from(memory_allocation.unwrap_or(0));
         let reserved_cycles_limit = candid::Nat::from(reserved_cycles_limit.unwrap_or(0));
+        let wasm_memory_limit = candid::Nat::from(wasm_memory_limit.unwrap_or(0));
         Self {
             controller,
             controllers,
@@ -739,6 +821,7 @@ impl DefiniteCanisterSettingsArgs {
             freezing_threshold: candid::Nat::from(freezing_threshold),
             reserved_cycles_limit,
             log_visibility,
+            wasm_memory_limit,
         }
     }
 
@@ -753,6 +836,10 @@ impl DefiniteCanisterSettingsArgs {
     pub fn log_visibility(&self) -> LogVisibility {
         self.log_visibility
     }
+
+    pub fn wasm_memory_limit(&self) -> candid::Nat {
+        self.wasm_memory_limit.clone()
+    }
 }
 
 impl Payload<'_> for DefiniteCanisterSettingsArgs {}
@@ -869,6 +956,7 @@ impl CanisterStatusResultV2 {
         freezing_threshold: u64,
         reserved_cycles_limit: Option<u128>,
         log_visibility: LogVisibility,
+        wasm_memory_limit: Option<u64>,
         idle_cycles_burned_per_day: u128,
         reserved_cycles: u128,
         query_num_calls: u128,
@@ -893,6 +981,7 @@ impl CanisterStatusResultV2 {
                 freezing_threshold,
                 reserved_cycles_limit,
                 log_visibility,
+                wasm_memory_limit,
             ),
             freezing_threshold: candid::Nat::from(freezing_threshold),
             idle_cycles_burned_per_day: candid::Nat::from(idle_cycles_burned_per_day),
@@ -1500,6 +1589,7 @@ fn verify_max_bounded_controllers_length() {
 ///     freezing_threshold: opt nat;
 ///     reserved_cycles_limit: opt nat;
 ///     log_visibility : opt log_visibility;
+///     wasm_memory_limit: opt nat;
 /// })`
 #[derive(Default, Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
 pub struct CanisterSettingsArgs {
@@ -1511,6 +1601,8 @@ pub struct CanisterSettingsArgs {
     pub freezing_threshold: Option<candid::Nat>,
     pub reserved_cycles_limit: Option<candid::Nat>,
     pub log_visibility: Option<LogVisibility>,
+    /// Setting the limit to zero removes it.
+    pub wasm_memory_limit: Option<candid::Nat>,
 }
 
 impl Payload<'_> for CanisterSettingsArgs {}
@@ -1527,6 +1619,7 @@ impl CanisterSettingsArgs {
             freezing_threshold: None,
             reserved_cycles_limit: None,
             log_visibility: None,
+            wasm_memory_limit: None,
         }
     }
 
@@ -1544,6 +1637,7 @@ pub struct CanisterSettingsArgsBuilder {
     freezing_threshold: Option<candid::Nat>,
     reserved_cycles_limit: Option<candid::Nat>,
     log_visibility: Option<LogVisibility>,
+    wasm_memory_limit: Option<candid::Nat>,
 }
 
 #[allow(dead_code)]
@@ -1561,6 +1655,7 @@ impl CanisterSettingsArgsBuilder {
             freezing_threshold: self.freezing_threshold,
             reserved_cycles_limit: self.reserved_cycles_limit,
             log_visibility: self.log_visibility,
+            wasm_memory_limit: self.wasm_memory_limit,
         }
     }
 
@@ -1636,6 +1731,14 @@ impl CanisterSettingsArgsBuilder {
             ..self
         }
     }
+
+    /// Sets the Wasm memory limit in bytes.
+    pub fn with_wasm_memory_limit(self, wasm_memory_limit: u64) -> Self {
+        Self {
+            wasm_memory_limit: Some(candid::Nat::from(wasm_memory_limit)),
+            ..self
+        }
+    }
 }
 
 /// Struct used for encoding/decoding
@@ -1966,6 +2069,191 @@ fn ecdsa_key_id_round_trip() {
     }
 }
 
+/// Types of algorithms that can be used for Schnorr signatures.
+/// ```text
+/// (variant { bip340secp256k1; ed25519 })
+/// ```
+///
+/// Threshold Ed25519 signatures are not supported yet. Key ids using
+/// `Ed25519` are rejected when decoding management canister payloads and
+/// registry records.
+#[derive(
+    CandidType,
+    Copy,
+    Clone,
+    Debug,
+    PartialOrd,
+    Ord,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    Hash,
+    EnumIter,
+)]
+pub enum SchnorrAlgorithm {
+    #[serde(rename = "bip340secp256k1")]
+    Bip340Secp256k1,
+    #[serde(rename = "ed25519")]
+    Ed25519,
+}
+
+impl TryFrom<pb_registry_crypto::SchnorrAlgorithm> for SchnorrAlgorithm {
+    type Error = ProxyDecodeError;
+
+    fn try_from(item: pb_registry_crypto::SchnorrAlgorithm) -> Result<Self, Self::Error> {
+        match item {
+            pb_registry_crypto::SchnorrAlgorithm::Bip340secp256k1 => {
+                Ok(SchnorrAlgorithm::Bip340Secp256k1)
+            }
+            pb_registry_crypto::SchnorrAlgorithm::Ed25519 => {
+                Err(ProxyDecodeError::ValueOutOfRange {
+                    typ: "SchnorrAlgorithm",
+                    err: "Threshold Ed25519 keys are not supported".to_string(),
+                })
+            }
+            pb_registry_crypto::SchnorrAlgorithm::Unspecified => {
+                Err(ProxyDecodeError::ValueOutOfRange {
+                    typ: "SchnorrAlgorithm",
+                    err: format!("Unable to convert {:?} to a SchnorrAlgorithm", item),
+                })
+            }
+        }
+    }
+}
+
+impl From<SchnorrAlgorithm> for pb_registry_crypto::SchnorrAlgorithm {
+    fn from(item: SchnorrAlgorithm) -> Self {
+        match item {
+            SchnorrAlgorithm::Bip340Secp256k1 => {
+                pb_registry_crypto::SchnorrAlgorithm::Bip340secp256k1
+            }
+            SchnorrAlgorithm::Ed25519 => pb_registry_crypto::SchnorrAlgorithm::Ed25519,
+        }
+    }
+}
+
+impl std::fmt::Display for SchnorrAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FromStr for SchnorrAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Bip340Secp256k1" => Ok(Self::Bip340Secp256k1),
+            "Ed25519" => Ok(Self::Ed25519),
+            _ => Err(format!("{} is not a recognized Schnorr algorithm", s)),
+        }
+    }
+}
+
+#[test]
+fn schnorr_algorithm_round_trip() {
+    use strum::IntoEnumIterator;
+    for algorithm in SchnorrAlgorithm::iter() {
+        assert_eq!(
+            format!("{}", algorithm)
+                .parse::<SchnorrAlgorithm>()
+                .unwrap(),
+            algorithm
+        );
+    }
+}
+
+/// Unique identifier for a key that can be used for Schnorr signatures. The
+/// name is just a identifier, but it may be used to convey some information
+/// about the key (e.g. that the key is meant to be used for testing purposes).
+/// ```text
+/// (record { algorithm: schnorr_algorithm; name: text})
+/// ```
+#[derive(
+    CandidType, Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize, Hash,
+)]
+pub struct SchnorrKeyId {
+    pub algorithm: SchnorrAlgorithm,
+    pub name: String,
+}
+
+impl TryFrom<pb_registry_crypto::SchnorrKeyId> for SchnorrKeyId {
+    type Error = ProxyDecodeError;
+    fn try_from(item: pb_registry_crypto::SchnorrKeyId) -> Result<Self, Self::Error> {
+        Ok(Self {
+            algorithm: SchnorrAlgorithm::try_from(
+                pb_registry_crypto::SchnorrAlgorithm::try_from(item.algorithm).map_err(|_| {
+                    ProxyDecodeError::ValueOutOfRange {
+                        typ: "SchnorrKeyId",
+                        err: format!("Unable to convert {} to a SchnorrAlgorithm", item.algorithm),
+                    }
+                })?,
+            )?,
+            name: item.name,
+        })
+    }
+}
+
+impl From<&SchnorrKeyId> for pb_registry_crypto::SchnorrKeyId {
+    fn from(item: &SchnorrKeyId) -> Self {
+        Self {
+            algorithm: pb_registry_crypto::SchnorrAlgorithm::from(item.algorithm) as i32,
+            name: item.name.clone(),
+        }
+    }
+}
+
+impl SchnorrKeyId {
+    /// Returns an error if threshold signatures are not supported for the
+    /// algorithm of this key.
+    fn ensure_supported(&self) -> Result<(), UserError> {
+        match self.algorithm {
+            SchnorrAlgorithm::Bip340Secp256k1 => Ok(()),
+            SchnorrAlgorithm::Ed25519 => Err(UserError::new(
+                ErrorCode::InvalidManagementPayload,
+                format!(
+                    "Schnorr key {} is not supported: threshold Ed25519 signatures are not available",
+                    self
+                ),
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for SchnorrKeyId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.algorithm, self.name)
+    }
+}
+
+impl FromStr for SchnorrKeyId {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (algorithm, name) = s
+            .split_once(':')
+            .ok_or_else(|| format!("Schnorr key id {} does not contain a ':'", s))?;
+        Ok(SchnorrKeyId {
+            algorithm: algorithm.parse::<SchnorrAlgorithm>()?,
+            name: name.to_string(),
+        })
+    }
+}
+
+#[test]
+fn schnorr_key_id_round_trip() {
+    use strum::IntoEnumIterator;
+    for algorithm in SchnorrAlgorithm::iter() {
+        for name in ["Ed25519", "", "other_key", "other key", "other:key"] {
+            let key = SchnorrKeyId {
+                algorithm,
+                name: name.to_string(),
+            };
+            assert_eq!(format!("{}", key).parse::<SchnorrKeyId>().unwrap(), key);
+        }
+    }
+}
+
 pub type DerivationPath = BoundedVec<MAXIMUM_DERIVATION_PATH_LENGTH, UNBOUNDED, UNBOUNDED, ByteBuf>;
 
 impl Payload<'_> for DerivationPath {}
@@ -2072,6 +2360,89 @@ fn verify_max_derivation_path_length() {
     }
 }
 
+#[test]
+fn verify_max_derivation_path_length_for_schnorr() {
+    let key_id = SchnorrKeyId {
+        algorithm: SchnorrAlgorithm::Bip340Secp256k1,
+        name: "test".to_string(),
+    };
+
+    let path = DerivationPath::new(vec![
+        ByteBuf::from(vec![0_u8, 32]);
+        MAXIMUM_DERIVATION_PATH_LENGTH
+    ]);
+    let sign_with_schnorr = SignWithSchnorrArgs {
+        message: vec![1; 100],
+        derivation_path: path.clone(),
+        key_id: key_id.clone(),
+    };
+    assert_eq!(
+        SignWithSchnorrArgs::decode(&sign_with_schnorr.encode()).unwrap(),
+        sign_with_schnorr
+    );
+    let schnorr_public_key = SchnorrPublicKeyArgs {
+        canister_id: None,
+        derivation_path: path,
+        key_id: key_id.clone(),
+    };
+    assert_eq!(
+        SchnorrPublicKeyArgs::decode(&schnorr_public_key.encode()).unwrap(),
+        schnorr_public_key
+    );
+
+    let path = DerivationPath::new(vec![
+        ByteBuf::from(vec![0_u8, 32]);
+        MAXIMUM_DERIVATION_PATH_LENGTH + 1
+    ]);
+    let sign_with_schnorr = SignWithSchnorrArgs {
+        message: vec![1; 100],
+        derivation_path: path.clone(),
+        key_id: key_id.clone(),
+    };
+    let result = SignWithSchnorrArgs::decode(&sign_with_schnorr.encode()).unwrap_err();
+    assert_eq!(result.code(), ErrorCode::InvalidManagementPayload);
+    let schnorr_public_key = SchnorrPublicKeyArgs {
+        canister_id: None,
+        derivation_path: path,
+        key_id,
+    };
+    let result = SchnorrPublicKeyArgs::decode(&schnorr_public_key.encode()).unwrap_err();
+    assert_eq!(result.code(), ErrorCode::InvalidManagementPayload);
+}
+
+#[test]
+fn ed25519_schnorr_key_ids_are_rejected() {
+    let key_id = SchnorrKeyId {
+        algorithm: SchnorrAlgorithm::Ed25519,
+        name: "test".to_string(),
+    };
+
+    let sign_with_schnorr = SignWithSchnorrArgs {
+        message: vec![1; 100],
+        derivation_path: DerivationPath::new(vec![]),
+        key_id: key_id.clone(),
+    };
+    let result = SignWithSchnorrArgs::decode(&sign_with_schnorr.encode()).unwrap_err();
+    assert_eq!(result.code(), ErrorCode::InvalidManagementPayload);
+    assert!(
+        result
+            .description()
+            .contains("threshold Ed25519 signatures are not available"),
+        "Actual: {}",
+        result.description()
+    );
+
+    let schnorr_public_key = SchnorrPublicKeyArgs {
+        canister_id: None,
+        derivation_path: DerivationPath::new(vec![]),
+        key_id: key_id.clone(),
+    };
+    let result = SchnorrPublicKeyArgs::decode(&schnorr_public_key.encode()).unwrap_err();
+    assert_eq!(result.code(), ErrorCode::InvalidManagementPayload);
+
+    assert!(SchnorrKeyId::try_from(pb_registry_crypto::SchnorrKeyId::from(&key_id)).is_err());
+}
+
 /// Represents the argument of the sign_with_ecdsa API.
 /// ```text
 /// (record {
@@ -2132,6 +2503,79 @@ pub struct ECDSAPublicKeyResponse {
 
 impl Payload<'_> for ECDSAPublicKeyResponse {}
 
+/// Represents the argument of the sign_with_schnorr API.
+/// ```text
+/// (record {
+///   message : blob;
+///   derivation_path : vec blob;
+///   key_id : schnorr_key_id;
+/// })
+/// ```
+#[derive(CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct SignWithSchnorrArgs {
+    #[serde(with = "serde_bytes")]
+    pub message: Vec<u8>,
+    pub derivation_path: DerivationPath,
+    pub key_id: SchnorrKeyId,
+}
+
+impl<'a> Payload<'a> for SignWithSchnorrArgs {
+    fn decode(blob: &'a [u8]) -> Result<Self, UserError> {
+        let args = Decode!(blob, Self).map_err(candid_error_to_user_error)?;
+        args.key_id.ensure_supported()?;
+        Ok(args)
+    }
+}
+
+/// Struct used to return a Schnorr signature.
+#[derive(CandidType, Deserialize, Debug)]
+pub struct SignWithSchnorrReply {
+    #[serde(with = "serde_bytes")]
+    pub signature: Vec<u8>,
+}
+
+impl Payload<'_> for SignWithSchnorrReply {}
+
+/// Represents the argument of the schnorr_public_key API.
+/// ```text
+/// (record {
+///   canister_id : opt canister_id;
+///   derivation_path : vec blob;
+///   key_id : schnorr_key_id;
+/// })
+/// ```
+#[derive(CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct SchnorrPublicKeyArgs {
+    pub canister_id: Option<CanisterId>,
+    pub derivation_path: DerivationPath,
+    pub key_id: SchnorrKeyId,
+}
+
+impl<'a> Payload<'a> for SchnorrPublicKeyArgs {
+    fn decode(blob: &'a [u8]) -> Result<Self, UserError> {
+        let args = Decode!(blob, Self).map_err(candid_error_to_user_error)?;
+        args.key_id.ensure_supported()?;
+        Ok(args)
+    }
+}
+
+/// Represents the response of the schnorr_public_key API.
+/// ```text
+/// (record {
+///   public_key : blob;
+///   chain_code : blob;
+/// })
+/// ```
+#[derive(CandidType, Deserialize, Debug)]
+pub struct SchnorrPublicKeyResponse {
+    #[serde(with = "serde_bytes")]
+    pub public_key: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    pub chain_code: Vec<u8>,
+}
+
+impl Payload<'_> for SchnorrPublicKeyResponse {}
+
 /// Argument of the compute_initial_ecdsa_dealings API.
 /// `(record {
 ///     key_id: ecdsa_key_id;
@@ -2296,23 +2740,107 @@ pub struct NodeMetricsHistoryResponse {
 
 impl Payload<'_> for NodeMetricsHistoryResponse {}
 
+/// `CandidType` for `FetchCanisterLogsRange`
+/// ```text
+/// record {
+///     start: nat64;
+///     end: nat64;
+/// }
+/// ```
+///
+/// A half-open range `[start, end)`.
+#[derive(Default, Clone, Copy, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct FetchCanisterLogsRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl FetchCanisterLogsRange {
+    pub fn new(start: u64, end: u64) -> Self {
+        Self { start, end }
+    }
+
+    pub fn contains(&self, value: u64) -> bool {
+        self.start <= value && value < self.end
+    }
+}
+
+/// `CandidType` for `CanisterLogContentFilter`
+/// ```text
+/// variant {
+///     prefix: blob;
+///     substring: blob;
+/// }
+/// ```
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub enum CanisterLogContentFilter {
+    #[serde(rename = "prefix")]
+    Prefix(#[serde(with = "serde_bytes")] Vec<u8>),
+    #[serde(rename = "substring")]
+    Substring(#[serde(with = "serde_bytes")] Vec<u8>),
+}
+
+impl CanisterLogContentFilter {
+    pub fn matches(&self, content: &[u8]) -> bool {
+        match self {
+            Self::Prefix(prefix) => content.starts_with(prefix),
+            Self::Substring(substring) => {
+                substring.is_empty()
+                    || content
+                        .windows(substring.len())
+                        .any(|window| window == substring.as_slice())
+            }
+        }
+    }
+}
+
 /// `CandidType` for `FetchCanisterLogsRequest`
 /// ```text
 /// record {
 ///     canister_id: principal;
+///     idx_range: opt fetch_canister_logs_range;
+///     timestamp_nanos_range: opt fetch_canister_logs_range;
+///     max_records: opt nat64;
+///     content_filter: opt canister_log_content_filter;
 /// }
 /// ```
 #[derive(Default, Clone, CandidType, Deserialize, Debug)]
 pub struct FetchCanisterLogsRequest {
     pub canister_id: PrincipalId,
+    pub idx_range: Option<FetchCanisterLogsRange>,
+    pub timestamp_nanos_range: Option<FetchCanisterLogsRange>,
+    pub max_records: Option<u64>,
+    pub content_filter: Option<CanisterLogContentFilter>,
 }
 
 impl Payload<'_> for FetchCanisterLogsRequest {}
 
 impl FetchCanisterLogsRequest {
+    /// Creates a request for all log records of the given canister.
+    pub fn new(canister_id: CanisterId) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+            ..Default::default()
+        }
+    }
+
     pub fn get_canister_id(&self) -> CanisterId {
         CanisterId::unchecked_from_principal(self.canister_id)
     }
+
+    /// Returns true if the given record passes all filters of the request.
+    /// The `max_records` limit is not taken into account.
+    pub fn matches(&self, record: &CanisterLogRecord) -> bool {
+        self.idx_range
+            .map_or(true, |range| range.contains(record.idx))
+            && self
+                .timestamp_nanos_range
+                .map_or(true, |range| range.contains(record.timestamp_nanos))
+            && self
+                .content_filter
+                .as_ref()
+                .map_or(true, |filter| filter.matches(&record.content))
+    }
 }
 
 /// `CandidType` for `CanisterLogRecord`
@@ -2323,7 +2851,7 @@ impl FetchCanisterLogsRequest {
 ///     content: blob;
 /// }
 /// ```
-#[derive(Default, Clone, CandidType, Deserialize, Debug, PartialEq)]
+#[derive(Default, Clone, CandidType, Deserialize, Serialize, Debug, PartialEq, Eq)]
 pub struct CanisterLogRecord {
     pub idx: u64,
     pub timestamp_nanos: u64,
@@ -2337,11 +2865,16 @@ impl Payload<'_> for CanisterLogRecord {}
 /// ```text
 /// record {
 ///     canister_log_records: vec canister_log_record;
+///     has_more: bool;
 /// }
 /// ```
+///
+/// `has_more` is set if more records matched the filters of the request
+/// than `max_records` allowed to return.
 #[derive(Default, Clone, CandidType, Deserialize, Debug, PartialEq)]
 pub struct FetchCanisterLogsResponse {
     pub canister_log_records: Vec<CanisterLogRecord>,
+    pub has_more: bool,
 }
 
 impl Payload<'_> for FetchCanisterLogsResponse {}
@@ -2497,3 +3030,194 @@ impl StoredChunksArgs {
 pub struct StoredChunksReply(pub Vec<serde_bytes::ByteBuf>);
 
 impl Payload<'_> for StoredChunksReply {}
+
+/// Canister snapshot IDs are encoded as the big-endian bytes of the `u64` ID
+/// assigned by the subnet.
+fn encode_snapshot_id(snapshot_id: u64) -> ByteBuf {
+    ByteBuf::from(snapshot_id.to_be_bytes().to_vec())
+}
+
+/// Decodes a snapshot ID encoded by `encode_snapshot_id`. Returns `None` if
+/// the given bytes cannot be a valid snapshot ID.
+fn decode_snapshot_id(bytes: &ByteBuf) -> Option<u64> {
+    <[u8; 8]>::try_from(bytes.as_slice())
+        .ok()
+        .map(u64::from_be_bytes)
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id : principal;
+///     replace_snapshot : opt blob;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug)]
+pub struct TakeCanisterSnapshotArgs {
+    canister_id: PrincipalId,
+    replace_snapshot: Option<ByteBuf>,
+}
+
+impl Payload<'_> for TakeCanisterSnapshotArgs {}
+
+impl TakeCanisterSnapshotArgs {
+    pub fn new(canister_id: CanisterId, replace_snapshot: Option<u64>) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+            replace_snapshot: replace_snapshot.map(encode_snapshot_id),
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+
+    /// Returns the snapshot to be replaced, if any. The inner `Option` is
+    /// `None` if the provided bytes do not represent a valid snapshot ID.
+    pub fn replace_snapshot(&self) -> Option<Option<u64>> {
+        self.replace_snapshot.as_ref().map(decode_snapshot_id)
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id : principal;
+///     snapshot_id : blob;
+///     sender_canister_version : opt nat64;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug)]
+pub struct LoadCanisterSnapshotArgs {
+    canister_id: PrincipalId,
+    snapshot_id: ByteBuf,
+    sender_canister_version: Option<u64>,
+}
+
+impl Payload<'_> for LoadCanisterSnapshotArgs {}
+
+impl LoadCanisterSnapshotArgs {
+    pub fn new(
+        canister_id: CanisterId,
+        snapshot_id: u64,
+        sender_canister_version: Option<u64>,
+    ) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+            snapshot_id: encode_snapshot_id(snapshot_id),
+            sender_canister_version,
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+
+    /// Returns `None` if the provided bytes do not represent a valid snapshot ID.
+    pub fn snapshot_id(&self) -> Option<u64> {
+        decode_snapshot_id(&self.snapshot_id)
+    }
+
+    pub fn get_sender_canister_version(&self) -> Option<u64> {
+        self.sender_canister_version
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id : principal;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug)]
+pub struct ListCanisterSnapshotArgs {
+    canister_id: PrincipalId,
+}
+
+impl Payload<'_> for ListCanisterSnapshotArgs {}
+
+impl ListCanisterSnapshotArgs {
+    pub fn new(canister_id: CanisterId) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id : principal;
+///     snapshot_id : blob;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug)]
+pub struct DeleteCanisterSnapshotArgs {
+    canister_id: PrincipalId,
+    snapshot_id: ByteBuf,
+}
+
+impl Payload<'_> for DeleteCanisterSnapshotArgs {}
+
+impl DeleteCanisterSnapshotArgs {
+    pub fn new(canister_id: CanisterId, snapshot_id: u64) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+            snapshot_id: encode_snapshot_id(snapshot_id),
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+
+    /// Returns `None` if the provided bytes do not represent a valid snapshot ID.
+    pub fn snapshot_id(&self) -> Option<u64> {
+        decode_snapshot_id(&self.snapshot_id)
+    }
+}
+
+/// Struct to be returned when taking or listing canister snapshots.
+/// `(record {
+///     id : blob;
+///     taken_at_timestamp : nat64;
+///     total_size : nat64;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct CanisterSnapshotResponse {
+    id: ByteBuf,
+    taken_at_timestamp: u64,
+    total_size: u64,
+}
+
+impl Payload<'_> for CanisterSnapshotResponse {}
+
+impl CanisterSnapshotResponse {
+    pub fn new(snapshot_id: u64, taken_at_timestamp: u64, total_size: NumBytes) -> Self {
+        Self {
+            id: encode_snapshot_id(snapshot_id),
+            taken_at_timestamp,
+            total_size: total_size.get(),
+        }
+    }
+
+    /// Returns `None` if the returned bytes do not represent a valid snapshot ID.
+    pub fn snapshot_id(&self) -> Option<u64> {
+        decode_snapshot_id(&self.id)
+    }
+
+    pub fn taken_at_timestamp(&self) -> u64 {
+        self.taken_at_timestamp
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.total_size
+    }
+}
+
+/// Struct to be returned when listing canister snapshots.
+/// `(vec record {
+///     id : blob;
+///     taken_at_timestamp : nat64;
+///     total_size : nat64;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct ListCanisterSnapshotsResponse(pub Vec<CanisterSnapshotResponse>);
+
+impl Payload<'_> for ListCanisterSnapshotsResponse {}
@@ -652,6 +652,7 @@ mod tests {
                 method_name: "tansform".to_string(),
                 method_payload: Vec::new(),
                 metadata: None,
+                deadline: None,
             },
             time: UNIX_EPOCH,
         };
@@ -693,6 +694,7 @@ mod tests {
                 method_name: "tansform".to_string(),
                 method_payload: Vec::new(),
                 metadata: None,
+                deadline: None,
             },
             time: UNIX_EPOCH,
         };
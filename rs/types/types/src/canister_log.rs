@@ -0,0 +1,185 @@
+//! The log of a canister, filled by the canister via `ic0.debug_print` and
+//! read back via the `fetch_canister_logs` management canister method.
+use ic_ic00_types::CanisterLogRecord;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// The maximum total size of the contents of the records kept in a canister
+/// log. Once it is exceeded, the oldest records are dropped.
+pub const MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE: usize = 4 * 1024;
+
+/// Returns the number of bytes accounted for the given record.
+fn record_size(record: &CanisterLogRecord) -> usize {
+    std::mem::size_of::<u64>() * 2 + record.content.len()
+}
+
+/// A bounded log of canister records, ordered by `idx`.
+///
+/// Records are assigned consecutive indices that are never reused, even after
+/// older records are dropped to stay within
+/// `MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CanisterLog {
+    /// The index that will be assigned to the next record.
+    next_idx: u64,
+    records: VecDeque<CanisterLogRecord>,
+    /// Total size of `records`, as computed by `record_size()`.
+    used_space: usize,
+}
+
+impl CanisterLog {
+    /// Creates a log from the given records, e.g. when loading a checkpoint.
+    pub fn new(next_idx: u64, records: Vec<CanisterLogRecord>) -> Self {
+        let used_space = records.iter().map(record_size).sum();
+        let mut log = Self {
+            next_idx,
+            records: records.into(),
+            used_space,
+        };
+        log.drop_oldest_records();
+        log
+    }
+
+    /// Creates an empty log whose first record will get index `next_idx`.
+    ///
+    /// Used to collect the records produced by a single message execution,
+    /// which are later appended to the canister log via `append_delta_log()`.
+    pub fn new_delta(next_idx: u64) -> Self {
+        Self::new(next_idx, vec![])
+    }
+
+    /// Returns the index that will be assigned to the next record.
+    pub fn next_idx(&self) -> u64 {
+        self.next_idx
+    }
+
+    /// Returns the records, ordered by `idx`.
+    pub fn records(&self) -> &VecDeque<CanisterLogRecord> {
+        &self.records
+    }
+
+    /// Returns the total size of the records in the log.
+    pub fn used_space(&self) -> usize {
+        self.used_space
+    }
+
+    /// Adds a new record with the given timestamp and content, dropping the
+    /// oldest records if the log grows too large. Contents larger than
+    /// `MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE` are truncated.
+    pub fn add_record(&mut self, timestamp_nanos: u64, content: Vec<u8>) {
+        let max_content_size =
+            MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE - record_size(&CanisterLogRecord::default());
+        let mut content = content;
+        content.truncate(max_content_size);
+        self.push_record(CanisterLogRecord {
+            idx: self.next_idx,
+            timestamp_nanos,
+            content,
+        });
+    }
+
+    /// Moves all records of `delta_log` into this log, preserving their
+    /// indices. `delta_log` is expected to have been created via
+    /// `new_delta(self.next_idx())`.
+    pub fn append_delta_log(&mut self, delta_log: &mut CanisterLog) {
+        for record in delta_log.records.drain(..) {
+            self.push_record(record);
+        }
+        delta_log.used_space = 0;
+        self.next_idx = self.next_idx.max(delta_log.next_idx);
+    }
+
+    /// Removes all records. Indices keep increasing monotonically.
+    pub fn clear(&mut self) {
+        self.records.clear();
+        self.used_space = 0;
+    }
+
+    fn push_record(&mut self, record: CanisterLogRecord) {
+        self.next_idx = self.next_idx.max(record.idx.saturating_add(1));
+        self.used_space += record_size(&record);
+        self.records.push_back(record);
+        self.drop_oldest_records();
+    }
+
+    fn drop_oldest_records(&mut self) {
+        while self.used_space > MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE {
+            match self.records.pop_front() {
+                Some(record) => self.used_space -= record_size(&record),
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents(log: &CanisterLog) -> Vec<(u64, Vec<u8>)> {
+        log.records()
+            .iter()
+            .map(|record| (record.idx, record.content.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn records_get_consecutive_indices() {
+        let mut log = CanisterLog::default();
+        log.add_record(10, b"a".to_vec());
+        log.add_record(20, b"b".to_vec());
+        assert_eq!(log.next_idx(), 2);
+        assert_eq!(contents(&log), vec![(0, b"a".to_vec()), (1, b"b".to_vec())]);
+        assert_eq!(log.records()[1].timestamp_nanos, 20);
+    }
+
+    #[test]
+    fn oldest_records_are_dropped_when_full() {
+        let mut log = CanisterLog::default();
+        let content = vec![7; 1000];
+        for i in 0..10 {
+            log.add_record(i, content.clone());
+        }
+        assert!(log.used_space() <= MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE);
+        assert_eq!(log.next_idx(), 10);
+        let indices: Vec<_> = log.records().iter().map(|record| record.idx).collect();
+        assert_eq!(indices, vec![6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn oversized_content_is_truncated() {
+        let mut log = CanisterLog::default();
+        log.add_record(0, vec![1; 2 * MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE]);
+        assert_eq!(log.records().len(), 1);
+        assert_eq!(log.used_space(), MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE);
+    }
+
+    #[test]
+    fn append_delta_log_preserves_indices() {
+        let mut log = CanisterLog::default();
+        log.add_record(0, b"a".to_vec());
+
+        let mut delta = CanisterLog::new_delta(log.next_idx());
+        delta.add_record(1, b"b".to_vec());
+        delta.add_record(2, b"c".to_vec());
+        log.append_delta_log(&mut delta);
+
+        assert!(delta.records().is_empty());
+        assert_eq!(log.next_idx(), 3);
+        assert_eq!(
+            contents(&log),
+            vec![(0, b"a".to_vec()), (1, b"b".to_vec()), (2, b"c".to_vec())]
+        );
+    }
+
+    #[test]
+    fn clear_keeps_next_idx() {
+        let mut log = CanisterLog::default();
+        log.add_record(0, b"a".to_vec());
+        log.clear();
+        assert!(log.records().is_empty());
+        assert_eq!(log.used_space(), 0);
+        log.add_record(1, b"b".to_vec());
+        assert_eq!(contents(&log), vec![(1, b"b".to_vec())]);
+    }
+}
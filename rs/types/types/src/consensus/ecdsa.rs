@@ -2,12 +2,14 @@
 
 pub use crate::consensus::ecdsa_refs::{
     unpack_reshare_of_unmasked_params, EcdsaBlockReader, IDkgTranscriptAttributes,
-    IDkgTranscriptOperationRef, IDkgTranscriptParamsRef, MaskedTranscript,
+    IDkgTranscriptOperationRef, IDkgTranscriptParamsRef, MaskedTranscript, PreSigId,
     PreSignatureQuadrupleRef, PseudoRandomId, QuadrupleId, QuadrupleInCreation,
-    RandomTranscriptParams, RequestId, ReshareOfMaskedParams, ReshareOfUnmaskedParams,
-    ThresholdEcdsaSigInputsError, ThresholdEcdsaSigInputsRef, TranscriptAttributes,
-    TranscriptCastError, TranscriptLookupError, TranscriptParamsError, TranscriptRef,
-    UnmaskedTimesMaskedParams, UnmaskedTranscript,
+    RandomTranscriptParams, RandomUnmaskedTranscriptParams, RequestId, ReshareOfMaskedParams,
+    ReshareOfUnmaskedParams, SchnorrPreSignatureError, SchnorrPreSignatureInCreation,
+    SchnorrPreSignatureRef, SchnorrRequestId, ThresholdEcdsaSigInputsError,
+    ThresholdEcdsaSigInputsRef, ThresholdSchnorrSigInputsError, ThresholdSchnorrSigInputsRef,
+    TranscriptAttributes, TranscriptCastError, TranscriptLookupError, TranscriptParamsError,
+    TranscriptRef, UnmaskedTimesMaskedParams, UnmaskedTranscript,
 };
 use crate::{
     consensus::BasicSignature,
@@ -18,7 +20,7 @@ use crate::{
                 IDkgComplaint, IDkgDealingSupport, IDkgOpening, IDkgTranscript, IDkgTranscriptId,
                 IDkgTranscriptParams, InitialIDkgDealings, SignedIDkgDealing,
             },
-            ThresholdEcdsaSigShare,
+            ThresholdEcdsaSigShare, ThresholdSchnorrSigShare,
         },
         crypto_hash, AlgorithmId, CryptoHash, CryptoHashOf, CryptoHashable, Signed,
         SignedBytesWithoutDomainSeparator,
@@ -28,7 +30,7 @@ use crate::{
 use ic_crypto_sha2::Sha256;
 #[cfg(test)]
 use ic_exhaustive_derive::ExhaustiveSet;
-use ic_ic00_types::EcdsaKeyId;
+use ic_ic00_types::{EcdsaKeyId, SchnorrKeyId};
 use ic_protobuf::{
     proxy::{try_from_option_field, ProxyDecodeError},
     registry::crypto::v1 as crypto_pb,
@@ -96,6 +98,22 @@ pub struct EcdsaPayload {
 
     /// State of the key transcripts.
     pub key_transcript: EcdsaKeyTranscript,
+
+    /// State of the threshold Schnorr key transcripts, one for each threshold
+    /// Schnorr key enabled on this subnet.
+    pub schnorr_key_transcripts: BTreeMap<SchnorrKeyId, SchnorrKeyTranscript>,
+
+    /// Threshold Schnorr pre-signatures that we can use to create signatures.
+    pub available_schnorr_pre_signatures: BTreeMap<PreSigId, SchnorrPreSignatureRef>,
+
+    /// Threshold Schnorr pre-signatures in creation.
+    pub schnorr_pre_signatures_in_creation: BTreeMap<PreSigId, SchnorrPreSignatureInCreation>,
+
+    /// The `SchnorrRequestIds` for which we are currently generating signatures.
+    pub ongoing_schnorr_signatures: BTreeMap<SchnorrRequestId, ThresholdSchnorrSigInputsRef>,
+
+    /// Collection of completed threshold Schnorr signatures.
+    pub schnorr_signature_agreements: BTreeMap<PseudoRandomId, CompletedSignature>,
 }
 
 impl EcdsaPayload {
@@ -112,6 +130,16 @@ impl EcdsaPayload {
             .key_transcript
             .transcript_config_in_creation()
             .into_iter()
+            .chain(
+                self.schnorr_key_transcripts
+                    .values()
+                    .flat_map(|key_transcript| key_transcript.transcript_config_in_creation()),
+            )
+            .chain(iter);
+        let iter = self
+            .schnorr_pre_signatures_in_creation
+            .values()
+            .flat_map(|pre_signature| pre_signature.iter_transcript_configs_in_creation())
             .chain(iter);
         Box::new(
             self.quadruples_in_creation
@@ -150,6 +178,21 @@ impl EcdsaPayload {
         Box::new(self.ongoing_signatures.keys())
     }
 
+    /// Return an iterator of all threshold Schnorr request ids that are used in the payload.
+    pub fn iter_schnorr_request_ids(&self) -> Box<dyn Iterator<Item = &SchnorrRequestId> + '_> {
+        Box::new(self.ongoing_schnorr_signatures.keys())
+    }
+
+    /// Return an iterator of all ids of threshold Schnorr pre-signatures in the payload.
+    pub fn iter_pre_signature_ids(&self) -> Box<dyn Iterator<Item = PreSigId> + '_> {
+        Box::new(
+            self.available_schnorr_pre_signatures
+                .keys()
+                .chain(self.schnorr_pre_signatures_in_creation.keys())
+                .cloned(),
+        )
+    }
+
     /// Return an iterator of all ids of quadruples in the payload.
     pub fn iter_quadruple_ids(&self) -> Box<dyn Iterator<Item = QuadrupleId> + '_> {
         Box::new(
@@ -178,6 +221,19 @@ impl EcdsaPayload {
         )
     }
 
+    /// Return an iterator of the ids of all threshold Schnorr pre-signatures that
+    /// are not yet paired with a signature request.
+    pub fn unassigned_pre_signature_ids(&self) -> Box<dyn Iterator<Item = PreSigId> + '_> {
+        let assigned = self
+            .iter_schnorr_request_ids()
+            .map(|id| id.pre_signature_id.clone())
+            .collect::<BTreeSet<_>>();
+        Box::new(
+            self.iter_pre_signature_ids()
+                .filter(move |id| !assigned.contains(id)),
+        )
+    }
+
     /// Return active transcript references in the  payload.
     pub fn active_transcripts(&self) -> BTreeSet<TranscriptRef> {
         let mut active_refs = BTreeSet::new();
@@ -198,7 +254,19 @@ impl EcdsaPayload {
         for obj in self.ongoing_xnet_reshares.values() {
             insert(obj.as_ref().get_refs())
         }
+        for obj in self.ongoing_schnorr_signatures.values() {
+            insert(obj.get_refs())
+        }
+        for obj in self.available_schnorr_pre_signatures.values() {
+            insert(obj.get_refs())
+        }
+        for obj in self.schnorr_pre_signatures_in_creation.values() {
+            insert(obj.get_refs())
+        }
         insert(self.key_transcript.get_refs());
+        for key_transcript in self.schnorr_key_transcripts.values() {
+            insert(key_transcript.get_refs());
+        }
         active_refs
     }
 
@@ -216,7 +284,19 @@ impl EcdsaPayload {
         for obj in self.ongoing_xnet_reshares.values_mut() {
             obj.as_mut().update(height);
         }
-        self.key_transcript.update_refs(height)
+        for obj in self.ongoing_schnorr_signatures.values_mut() {
+            obj.update(height);
+        }
+        for obj in self.available_schnorr_pre_signatures.values_mut() {
+            obj.update(height);
+        }
+        for obj in self.schnorr_pre_signatures_in_creation.values_mut() {
+            obj.update(height);
+        }
+        self.key_transcript.update_refs(height);
+        for key_transcript in self.schnorr_key_transcripts.values_mut() {
+            key_transcript.update_refs(height);
+        }
     }
 
     /// Return the oldest registry version required to keep nodes in the subnet
@@ -237,31 +317,51 @@ impl EcdsaPayload {
                 version_1.min(version_2)
             }
         };
-        let key_version = self
-            .key_transcript
-            .current
-            .as_ref()
-            .map(|transcript| transcript.registry_version());
-        let in_creation_version = match &self.key_transcript.next_in_creation {
-            Begin => None,
-            RandomTranscriptParams(params) => Some(params.as_ref().registry_version()),
-            ReshareOfMaskedParams(params) => Some(params.as_ref().registry_version()),
-            ReshareOfUnmaskedParams(params) => Some(params.as_ref().registry_version()),
-            XnetReshareOfUnmaskedParams((_, params)) => Some(params.as_ref().registry_version()),
-            Created(transcript) => idkg_transcripts
-                .get(&transcript.as_ref().transcript_id)
-                .map(|transcript| transcript.registry_version),
+        let key_versions = |current: &Option<UnmaskedTranscriptWithAttributes>,
+                            next_in_creation: &KeyTranscriptCreation| {
+            let key_version = current
+                .as_ref()
+                .map(|transcript| transcript.registry_version());
+            let in_creation_version = match next_in_creation {
+                Begin => None,
+                RandomTranscriptParams(params) => Some(params.as_ref().registry_version()),
+                ReshareOfMaskedParams(params) => Some(params.as_ref().registry_version()),
+                ReshareOfUnmaskedParams(params) => Some(params.as_ref().registry_version()),
+                XnetReshareOfUnmaskedParams((_, params)) => {
+                    Some(params.as_ref().registry_version())
+                }
+                Created(transcript) => idkg_transcripts
+                    .get(&transcript.as_ref().transcript_id)
+                    .map(|transcript| transcript.registry_version),
+            };
+            min_version(key_version, in_creation_version)
         };
-        let mut registry_version = min_version(key_version, in_creation_version);
-        for (_, sig_input_ref) in self.ongoing_signatures.iter() {
-            for r in sig_input_ref.get_refs().iter() {
-                registry_version = min_version(
-                    registry_version,
-                    idkg_transcripts
-                        .get(&r.transcript_id)
-                        .map(|transcript| transcript.registry_version),
-                );
-            }
+        let mut registry_version = key_versions(
+            &self.key_transcript.current,
+            &self.key_transcript.next_in_creation,
+        );
+        for key_transcript in self.schnorr_key_transcripts.values() {
+            registry_version = min_version(
+                registry_version,
+                key_versions(&key_transcript.current, &key_transcript.next_in_creation),
+            );
+        }
+        let ongoing_refs = self
+            .ongoing_signatures
+            .values()
+            .flat_map(|sig_input_ref| sig_input_ref.get_refs())
+            .chain(
+                self.ongoing_schnorr_signatures
+                    .values()
+                    .flat_map(|sig_input_ref| sig_input_ref.get_refs()),
+            );
+        for r in ongoing_refs {
+            registry_version = min_version(
+                registry_version,
+                idkg_transcripts
+                    .get(&r.transcript_id)
+                    .map(|transcript| transcript.registry_version),
+            );
         }
         registry_version
     }
@@ -361,87 +461,101 @@ pub struct EcdsaKeyTranscript {
 
 impl EcdsaKeyTranscript {
     pub fn get_refs(&self) -> Vec<TranscriptRef> {
-        let mut active_refs = match &self.next_in_creation {
-            KeyTranscriptCreation::Begin => vec![],
-            KeyTranscriptCreation::RandomTranscriptParams(params) => params.as_ref().get_refs(),
-            KeyTranscriptCreation::ReshareOfMaskedParams(params) => params.as_ref().get_refs(),
-            KeyTranscriptCreation::ReshareOfUnmaskedParams(params) => params.as_ref().get_refs(),
-            KeyTranscriptCreation::XnetReshareOfUnmaskedParams((_, params)) => {
-                params.as_ref().get_refs()
-            }
-            KeyTranscriptCreation::Created(unmasked) => vec![*unmasked.as_ref()],
-        };
-        if let Some(unmasked) = &self.current {
-            active_refs.push(*unmasked.as_ref());
-        }
-        active_refs
+        key_transcript_refs(&self.current, &self.next_in_creation)
     }
 
     fn update_refs(&mut self, height: Height) {
-        match &mut self.next_in_creation {
-            KeyTranscriptCreation::Begin => (),
-            KeyTranscriptCreation::RandomTranscriptParams(params) => params.as_mut().update(height),
-            KeyTranscriptCreation::ReshareOfMaskedParams(params) => params.as_mut().update(height),
-            KeyTranscriptCreation::ReshareOfUnmaskedParams(params) => {
-                params.as_mut().update(height)
-            }
-            KeyTranscriptCreation::XnetReshareOfUnmaskedParams((_, params)) => {
-                params.as_mut().update(height)
-            }
-            KeyTranscriptCreation::Created(unmasked) => unmasked.as_mut().update(height),
-        }
-        if let Some(unmasked) = &mut self.current {
-            unmasked.as_mut().update(height);
-        }
+        update_key_transcript_refs(&mut self.current, &mut self.next_in_creation, height)
     }
 
     pub fn transcript_config_in_creation(&self) -> Option<&IDkgTranscriptParamsRef> {
-        match &self.next_in_creation {
-            KeyTranscriptCreation::Begin => None,
-            KeyTranscriptCreation::RandomTranscriptParams(x) => Some(x.as_ref()),
-            KeyTranscriptCreation::ReshareOfMaskedParams(x) => Some(x.as_ref()),
-            KeyTranscriptCreation::ReshareOfUnmaskedParams(x) => Some(x.as_ref()),
-            KeyTranscriptCreation::XnetReshareOfUnmaskedParams((_, x)) => Some(x.as_ref()),
-            KeyTranscriptCreation::Created(_) => None,
-        }
+        self.next_in_creation.transcript_config_in_creation()
     }
 }
 
 impl Display for EcdsaKeyTranscript {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let current = if let Some(transcript) = &self.current {
-            format!("Current = {:?}", transcript.as_ref())
-        } else {
-            "Current = None".to_string()
-        };
-        match &self.next_in_creation {
-            KeyTranscriptCreation::Begin => write!(f, "{}, Next = Begin", current),
-            KeyTranscriptCreation::RandomTranscriptParams(x) => write!(
-                f,
-                "{}, Next = RandomTranscriptParams({:?}",
-                current,
-                x.as_ref().transcript_id
-            ),
-            KeyTranscriptCreation::ReshareOfMaskedParams(x) => write!(
-                f,
-                "{}, Next = ReshareOfMaskedParams({:?})",
-                current,
-                x.as_ref().transcript_id
-            ),
-            KeyTranscriptCreation::ReshareOfUnmaskedParams(x) => write!(
-                f,
-                "{}, Next = ReshareOfUnmaskedParams({:?})",
-                current,
-                x.as_ref().transcript_id
-            ),
-            KeyTranscriptCreation::XnetReshareOfUnmaskedParams((_, x)) => write!(
-                f,
-                "{}, Next = XnetReshareOfUnmaskedParams({:?})",
-                current,
-                x.as_ref().transcript_id
-            ),
-            KeyTranscriptCreation::Created(x) => write!(f, "{}, Next = Created({:?})", current, x),
+        fmt_key_transcript(f, &self.current, &self.next_in_creation)
+    }
+}
+
+fn key_transcript_refs(
+    current: &Option<UnmaskedTranscriptWithAttributes>,
+    next_in_creation: &KeyTranscriptCreation,
+) -> Vec<TranscriptRef> {
+    let mut active_refs = match next_in_creation {
+        KeyTranscriptCreation::Begin => vec![],
+        KeyTranscriptCreation::RandomTranscriptParams(params) => params.as_ref().get_refs(),
+        KeyTranscriptCreation::ReshareOfMaskedParams(params) => params.as_ref().get_refs(),
+        KeyTranscriptCreation::ReshareOfUnmaskedParams(params) => params.as_ref().get_refs(),
+        KeyTranscriptCreation::XnetReshareOfUnmaskedParams((_, params)) => {
+            params.as_ref().get_refs()
+        }
+        KeyTranscriptCreation::Created(unmasked) => vec![*unmasked.as_ref()],
+    };
+    if let Some(unmasked) = current {
+        active_refs.push(*unmasked.as_ref());
+    }
+    active_refs
+}
+
+fn update_key_transcript_refs(
+    current: &mut Option<UnmaskedTranscriptWithAttributes>,
+    next_in_creation: &mut KeyTranscriptCreation,
+    height: Height,
+) {
+    match next_in_creation {
+        KeyTranscriptCreation::Begin => (),
+        KeyTranscriptCreation::RandomTranscriptParams(params) => params.as_mut().update(height),
+        KeyTranscriptCreation::ReshareOfMaskedParams(params) => params.as_mut().update(height),
+        KeyTranscriptCreation::ReshareOfUnmaskedParams(params) => params.as_mut().update(height),
+        KeyTranscriptCreation::XnetReshareOfUnmaskedParams((_, params)) => {
+            params.as_mut().update(height)
         }
+        KeyTranscriptCreation::Created(unmasked) => unmasked.as_mut().update(height),
+    }
+    if let Some(unmasked) = current {
+        unmasked.as_mut().update(height);
+    }
+}
+
+fn fmt_key_transcript(
+    f: &mut Formatter<'_>,
+    current: &Option<UnmaskedTranscriptWithAttributes>,
+    next_in_creation: &KeyTranscriptCreation,
+) -> fmt::Result {
+    let current = if let Some(transcript) = current {
+        format!("Current = {:?}", transcript.as_ref())
+    } else {
+        "Current = None".to_string()
+    };
+    match next_in_creation {
+        KeyTranscriptCreation::Begin => write!(f, "{}, Next = Begin", current),
+        KeyTranscriptCreation::RandomTranscriptParams(x) => write!(
+            f,
+            "{}, Next = RandomTranscriptParams({:?}",
+            current,
+            x.as_ref().transcript_id
+        ),
+        KeyTranscriptCreation::ReshareOfMaskedParams(x) => write!(
+            f,
+            "{}, Next = ReshareOfMaskedParams({:?})",
+            current,
+            x.as_ref().transcript_id
+        ),
+        KeyTranscriptCreation::ReshareOfUnmaskedParams(x) => write!(
+            f,
+            "{}, Next = ReshareOfUnmaskedParams({:?})",
+            current,
+            x.as_ref().transcript_id
+        ),
+        KeyTranscriptCreation::XnetReshareOfUnmaskedParams((_, x)) => write!(
+            f,
+            "{}, Next = XnetReshareOfUnmaskedParams({:?})",
+            current,
+            x.as_ref().transcript_id
+        ),
+        KeyTranscriptCreation::Created(x) => write!(f, "{}, Next = Created({:?})", current, x),
     }
 }
 
@@ -499,6 +613,87 @@ impl TryFrom<&pb::EcdsaKeyTranscript> for EcdsaKeyTranscript {
     }
 }
 
+/// The threshold Schnorr key transcript. It is created and reshared in the same
+/// way as the [`EcdsaKeyTranscript`], but for a threshold Schnorr algorithm.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ExhaustiveSet))]
+pub struct SchnorrKeyTranscript {
+    /// The threshold Schnorr key transcript used for the current interval.
+    pub current: Option<UnmaskedTranscriptWithAttributes>,
+    /// Progress of creating the next threshold Schnorr key transcript.
+    pub next_in_creation: KeyTranscriptCreation,
+    /// Key id.
+    pub key_id: SchnorrKeyId,
+}
+
+impl SchnorrKeyTranscript {
+    pub fn new(key_id: SchnorrKeyId) -> Self {
+        Self {
+            current: None,
+            next_in_creation: KeyTranscriptCreation::Begin,
+            key_id,
+        }
+    }
+
+    pub fn get_refs(&self) -> Vec<TranscriptRef> {
+        key_transcript_refs(&self.current, &self.next_in_creation)
+    }
+
+    fn update_refs(&mut self, height: Height) {
+        update_key_transcript_refs(&mut self.current, &mut self.next_in_creation, height)
+    }
+
+    pub fn transcript_config_in_creation(&self) -> Option<&IDkgTranscriptParamsRef> {
+        self.next_in_creation.transcript_config_in_creation()
+    }
+}
+
+impl Display for SchnorrKeyTranscript {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fmt_key_transcript(f, &self.current, &self.next_in_creation)
+    }
+}
+
+impl From<&SchnorrKeyTranscript> for pb::SchnorrKeyTranscript {
+    fn from(transcript: &SchnorrKeyTranscript) -> Self {
+        Self {
+            key_id: Some(crypto_pb::SchnorrKeyId::from(&transcript.key_id)),
+            current: transcript
+                .current
+                .as_ref()
+                .map(pb::UnmaskedTranscriptWithAttributes::from),
+            next_in_creation: Some(pb::KeyTranscriptCreation::from(
+                &transcript.next_in_creation,
+            )),
+        }
+    }
+}
+
+impl TryFrom<&pb::SchnorrKeyTranscript> for SchnorrKeyTranscript {
+    type Error = ProxyDecodeError;
+
+    fn try_from(proto: &pb::SchnorrKeyTranscript) -> Result<Self, Self::Error> {
+        let key_id = try_from_option_field(proto.key_id.clone(), "SchnorrKeyTranscript::key_id")?;
+
+        let current = proto
+            .current
+            .as_ref()
+            .map(UnmaskedTranscriptWithAttributes::try_from)
+            .transpose()?;
+
+        let next_in_creation = try_from_option_field(
+            proto.next_in_creation.as_ref(),
+            "SchnorrKeyTranscript::next_in_creation",
+        )?;
+
+        Ok(Self {
+            key_id,
+            current,
+            next_in_creation,
+        })
+    }
+}
+
 /// The creation of an ecdsa key transcript goes through one of the three paths below:
 /// 1. Begin -> RandomTranscript -> ReshareOfMasked -> Created
 /// 2. Begin -> ReshareOfUnmasked -> Created
@@ -530,6 +725,20 @@ pub enum KeyTranscriptCreation {
     Created(UnmaskedTranscript),
 }
 
+impl KeyTranscriptCreation {
+    /// Returns the params of the transcript currently in creation, if any.
+    pub fn transcript_config_in_creation(&self) -> Option<&IDkgTranscriptParamsRef> {
+        match self {
+            KeyTranscriptCreation::Begin => None,
+            KeyTranscriptCreation::RandomTranscriptParams(x) => Some(x.as_ref()),
+            KeyTranscriptCreation::ReshareOfMaskedParams(x) => Some(x.as_ref()),
+            KeyTranscriptCreation::ReshareOfUnmaskedParams(x) => Some(x.as_ref()),
+            KeyTranscriptCreation::XnetReshareOfUnmaskedParams((_, x)) => Some(x.as_ref()),
+            KeyTranscriptCreation::Created(_) => None,
+        }
+    }
+}
+
 impl From<&KeyTranscriptCreation> for pb::KeyTranscriptCreation {
     fn from(key_transcript_in_creation: &KeyTranscriptCreation) -> Self {
         let mut ret = pb::KeyTranscriptCreation {
@@ -705,6 +914,13 @@ impl EcdsaUIDGenerator {
 
         QuadrupleId(id, Some(key_id))
     }
+
+    pub fn next_pre_signature_id(&mut self, key_id: SchnorrKeyId) -> PreSigId {
+        let id = self.next_unused_quadruple_id;
+        self.next_unused_quadruple_id += 1;
+
+        PreSigId(id, key_id)
+    }
 }
 
 /// The ECDSA artifact.
@@ -715,6 +931,7 @@ pub enum EcdsaMessage {
     EcdsaSigShare(EcdsaSigShare),
     EcdsaComplaint(EcdsaComplaint),
     EcdsaOpening(EcdsaOpening),
+    SchnorrSigShare(SchnorrSigShare),
 }
 
 impl From<EcdsaMessage> for pb::EcdsaMessage {
@@ -726,6 +943,7 @@ impl From<EcdsaMessage> for pb::EcdsaMessage {
             EcdsaMessage::EcdsaSigShare(x) => Msg::SigShare(x.into()),
             EcdsaMessage::EcdsaComplaint(x) => Msg::Complaint(x.into()),
             EcdsaMessage::EcdsaOpening(x) => Msg::Opening(x.into()),
+            EcdsaMessage::SchnorrSigShare(x) => Msg::SchnorrSigShare(x.into()),
         };
         Self { msg: Some(msg) }
     }
@@ -745,6 +963,7 @@ impl TryFrom<pb::EcdsaMessage> for EcdsaMessage {
             Msg::SigShare(x) => EcdsaMessage::EcdsaSigShare(x.try_into()?),
             Msg::Complaint(x) => EcdsaMessage::EcdsaComplaint(x.try_into()?),
             Msg::Opening(x) => EcdsaMessage::EcdsaOpening(x.try_into()?),
+            Msg::SchnorrSigShare(x) => EcdsaMessage::SchnorrSigShare(x.try_into()?),
         })
     }
 }
@@ -872,6 +1091,20 @@ pub fn sig_share_prefix(
     ))
 }
 
+pub fn schnorr_sig_share_prefix(
+    request_id: &SchnorrRequestId,
+    sig_share_node_id: &NodeId,
+) -> EcdsaPrefixOf<SchnorrSigShare> {
+    // Group_tag: pre-signature Id, Meta info: <sig share sender>
+    let mut hasher = Sha256::new();
+    sig_share_node_id.hash(&mut hasher);
+
+    EcdsaPrefixOf::new(EcdsaPrefix::new(
+        request_id.pre_signature_id.id(),
+        hasher.finish(),
+    ))
+}
+
 pub fn complaint_prefix(
     transcript_id: &IDkgTranscriptId,
     dealer_id: &NodeId,
@@ -912,6 +1145,10 @@ pub enum EcdsaArtifactId {
     SigShare(EcdsaPrefixOf<EcdsaSigShare>, CryptoHashOf<EcdsaSigShare>),
     Complaint(EcdsaPrefixOf<EcdsaComplaint>, CryptoHashOf<EcdsaComplaint>),
     Opening(EcdsaPrefixOf<EcdsaOpening>, CryptoHashOf<EcdsaOpening>),
+    SchnorrSigShare(
+        EcdsaPrefixOf<SchnorrSigShare>,
+        CryptoHashOf<SchnorrSigShare>,
+    ),
 }
 
 impl EcdsaArtifactId {
@@ -922,6 +1159,7 @@ impl EcdsaArtifactId {
             EcdsaArtifactId::SigShare(prefix, _) => prefix.as_ref().clone(),
             EcdsaArtifactId::Complaint(prefix, _) => prefix.as_ref().clone(),
             EcdsaArtifactId::Opening(prefix, _) => prefix.as_ref().clone(),
+            EcdsaArtifactId::SchnorrSigShare(prefix, _) => prefix.as_ref().clone(),
         }
     }
 
@@ -932,6 +1170,7 @@ impl EcdsaArtifactId {
             EcdsaArtifactId::SigShare(_, hash) => hash.as_ref().clone(),
             EcdsaArtifactId::Complaint(_, hash) => hash.as_ref().clone(),
             EcdsaArtifactId::Opening(_, hash) => hash.as_ref().clone(),
+            EcdsaArtifactId::SchnorrSigShare(_, hash) => hash.as_ref().clone(),
         }
     }
 
@@ -966,6 +1205,10 @@ impl From<(EcdsaMessageType, EcdsaPrefix, CryptoHash)> for EcdsaArtifactId {
             EcdsaMessageType::Opening => {
                 EcdsaArtifactId::Opening(EcdsaPrefixOf::new(prefix), CryptoHashOf::new(crypto_hash))
             }
+            EcdsaMessageType::SchnorrSigShare => EcdsaArtifactId::SchnorrSigShare(
+                EcdsaPrefixOf::new(prefix),
+                CryptoHashOf::new(crypto_hash),
+            ),
         }
     }
 }
@@ -994,6 +1237,10 @@ impl From<EcdsaArtifactId> for pb::EcdsaArtifactId {
                 prefix: Some((&p.get()).into()),
                 hash: h.get().0,
             }),
+            EcdsaArtifactId::SchnorrSigShare(p, h) => Kind::SchnorrSigShare(pb::PrefixHashPair {
+                prefix: Some((&p.get()).into()),
+                hash: h.get().0,
+            }),
         };
         Self { kind: Some(kind) }
     }
@@ -1038,6 +1285,13 @@ impl TryFrom<pb::EcdsaArtifactId> for EcdsaArtifactId {
                 EcdsaPrefixOf::new(try_from_option_field(p.prefix.as_ref(), "Opening::prefix")?),
                 CryptoHashOf::new(CryptoHash(p.hash)),
             ),
+            Kind::SchnorrSigShare(p) => Self::SchnorrSigShare(
+                EcdsaPrefixOf::new(try_from_option_field(
+                    p.prefix.as_ref(),
+                    "SchnorrSigShare::prefix",
+                )?),
+                CryptoHashOf::new(CryptoHash(p.hash)),
+            ),
         })
     }
 }
@@ -1051,6 +1305,7 @@ pub enum EcdsaMessageType {
     SigShare,
     Complaint,
     Opening,
+    SchnorrSigShare,
 }
 
 impl From<&EcdsaMessage> for EcdsaMessageType {
@@ -1061,6 +1316,7 @@ impl From<&EcdsaMessage> for EcdsaMessageType {
             EcdsaMessage::EcdsaSigShare(_) => EcdsaMessageType::SigShare,
             EcdsaMessage::EcdsaComplaint(_) => EcdsaMessageType::Complaint,
             EcdsaMessage::EcdsaOpening(_) => EcdsaMessageType::Opening,
+            EcdsaMessage::SchnorrSigShare(_) => EcdsaMessageType::SchnorrSigShare,
         }
     }
 }
@@ -1073,6 +1329,7 @@ impl From<&EcdsaArtifactId> for EcdsaMessageType {
             EcdsaArtifactId::SigShare(..) => EcdsaMessageType::SigShare,
             EcdsaArtifactId::Complaint(..) => EcdsaMessageType::Complaint,
             EcdsaArtifactId::Opening(..) => EcdsaMessageType::Opening,
+            EcdsaArtifactId::SchnorrSigShare(..) => EcdsaMessageType::SchnorrSigShare,
         }
     }
 }
@@ -1085,6 +1342,7 @@ impl EcdsaMessageType {
             Self::SigShare => "sig_share",
             Self::Complaint => "complaint",
             Self::Opening => "opening",
+            Self::SchnorrSigShare => "schnorr_sig_share",
         }
     }
 }
@@ -1138,6 +1396,55 @@ impl Display for EcdsaSigShare {
     }
 }
 
+/// The threshold Schnorr signature share
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub struct SchnorrSigShare {
+    /// The node that signed the share
+    pub signer_id: NodeId,
+
+    /// The request this signature share belongs to
+    pub request_id: SchnorrRequestId,
+
+    /// The signature share
+    pub share: ThresholdSchnorrSigShare,
+}
+
+impl From<&SchnorrSigShare> for pb::SchnorrSigShare {
+    fn from(value: &SchnorrSigShare) -> Self {
+        Self {
+            signer_id: Some(node_id_into_protobuf(value.signer_id)),
+            request_id: Some(pb::SchnorrRequestId::from(value.request_id.clone())),
+            sig_share_raw: value.share.sig_share_raw.clone(),
+        }
+    }
+}
+
+impl TryFrom<&pb::SchnorrSigShare> for SchnorrSigShare {
+    type Error = ProxyDecodeError;
+    fn try_from(value: &pb::SchnorrSigShare) -> Result<Self, Self::Error> {
+        Ok(Self {
+            signer_id: node_id_try_from_option(value.signer_id.clone())?,
+            request_id: try_from_option_field(
+                value.request_id.as_ref(),
+                "SchnorrSigShare::request_id",
+            )?,
+            share: ThresholdSchnorrSigShare {
+                sig_share_raw: value.sig_share_raw.clone(),
+            },
+        })
+    }
+}
+
+impl Display for SchnorrSigShare {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "SchnorrSigShare[request_id = {:?}, signer_id = {:?}]",
+            self.request_id, self.signer_id,
+        )
+    }
+}
+
 /// Complaint related defines
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Hash)]
 pub struct EcdsaComplaintContent {
@@ -1302,6 +1609,7 @@ pub enum EcdsaMessageAttribute {
     EcdsaSigShare(RequestId),
     EcdsaComplaint(IDkgTranscriptId),
     EcdsaOpening(IDkgTranscriptId),
+    SchnorrSigShare(SchnorrRequestId),
 }
 
 impl From<EcdsaMessageAttribute> for pb::EcdsaMessageAttribute {
@@ -1313,6 +1621,7 @@ impl From<EcdsaMessageAttribute> for pb::EcdsaMessageAttribute {
             EcdsaMessageAttribute::EcdsaSigShare(id) => Kind::SigShare(id.into()),
             EcdsaMessageAttribute::EcdsaComplaint(id) => Kind::Complaint((&id).into()),
             EcdsaMessageAttribute::EcdsaOpening(id) => Kind::Opening((&id).into()),
+            EcdsaMessageAttribute::SchnorrSigShare(id) => Kind::SchnorrSigShare(id.into()),
         };
         Self { kind: Some(kind) }
     }
@@ -1333,6 +1642,7 @@ impl TryFrom<pb::EcdsaMessageAttribute> for EcdsaMessageAttribute {
             Kind::SigShare(id) => EcdsaMessageAttribute::EcdsaSigShare(id.try_into()?),
             Kind::Complaint(id) => EcdsaMessageAttribute::EcdsaComplaint(id.try_into()?),
             Kind::Opening(id) => EcdsaMessageAttribute::EcdsaOpening(id.try_into()?),
+            Kind::SchnorrSigShare(id) => EcdsaMessageAttribute::SchnorrSigShare(id.try_into()?),
         })
     }
 }
@@ -1355,6 +1665,9 @@ impl From<&EcdsaMessage> for EcdsaMessageAttribute {
             EcdsaMessage::EcdsaOpening(opening) => {
                 EcdsaMessageAttribute::EcdsaOpening(opening.content.idkg_opening.transcript_id)
             }
+            EcdsaMessage::SchnorrSigShare(share) => {
+                EcdsaMessageAttribute::SchnorrSigShare(share.request_id.clone())
+            }
         }
     }
 }
@@ -1367,6 +1680,7 @@ impl EcdsaMessageAttribute {
             Self::EcdsaSigShare(_) => "sig_share",
             Self::EcdsaComplaint(_) => "complaint",
             Self::EcdsaOpening(_) => "opening",
+            Self::SchnorrSigShare(_) => "schnorr_sig_share",
         }
     }
 }
@@ -1401,6 +1715,16 @@ impl TryFrom<EcdsaMessage> for EcdsaSigShare {
     }
 }
 
+impl TryFrom<EcdsaMessage> for SchnorrSigShare {
+    type Error = EcdsaMessage;
+    fn try_from(msg: EcdsaMessage) -> Result<Self, Self::Error> {
+        match msg {
+            EcdsaMessage::SchnorrSigShare(x) => Ok(x),
+            _ => Err(msg),
+        }
+    }
+}
+
 impl TryFrom<EcdsaMessage> for EcdsaComplaint {
     type Error = EcdsaMessage;
     fn try_from(msg: EcdsaMessage) -> Result<Self, Self::Error> {
@@ -1425,20 +1749,56 @@ pub type Summary = Option<EcdsaPayload>;
 
 pub type Payload = Option<EcdsaPayload>;
 
+fn completed_signatures_to_proto(
+    agreements: &BTreeMap<PseudoRandomId, CompletedSignature>,
+) -> Vec<pb::CompletedSignature> {
+    let mut ret = Vec::new();
+    for (pseudo_random_id, completed) in agreements {
+        let unreported = match completed {
+            CompletedSignature::Unreported(response) => Some(response.into()),
+            CompletedSignature::ReportedToExecution => None,
+        };
+        ret.push(pb::CompletedSignature {
+            pseudo_random_id: pseudo_random_id.to_vec(),
+            unreported,
+        });
+    }
+    ret
+}
+
+fn completed_signatures_from_proto(
+    agreements: &[pb::CompletedSignature],
+) -> Result<BTreeMap<PseudoRandomId, CompletedSignature>, ProxyDecodeError> {
+    let mut ret = BTreeMap::new();
+    for completed_signature in agreements {
+        let pseudo_random_id = {
+            if completed_signature.pseudo_random_id.len() != 32 {
+                return Err(ProxyDecodeError::Other(
+                    "Expects 32 bytes of pseudo_random_id".to_string(),
+                ));
+            }
+
+            let mut x = [0; 32];
+            x.copy_from_slice(&completed_signature.pseudo_random_id);
+            x
+        };
+
+        let signature = if let Some(unreported) = &completed_signature.unreported {
+            let response = crate::messages::Response::try_from(unreported.clone())?;
+            CompletedSignature::Unreported(response)
+        } else {
+            CompletedSignature::ReportedToExecution
+        };
+
+        ret.insert(pseudo_random_id, signature);
+    }
+    Ok(ret)
+}
+
 impl From<&EcdsaPayload> for pb::EcdsaPayload {
     fn from(payload: &EcdsaPayload) -> Self {
         // signature_agreements
-        let mut signature_agreements = Vec::new();
-        for (pseudo_random_id, completed) in &payload.signature_agreements {
-            let unreported = match completed {
-                CompletedSignature::Unreported(response) => Some(response.into()),
-                CompletedSignature::ReportedToExecution => None,
-            };
-            signature_agreements.push(pb::CompletedSignature {
-                pseudo_random_id: pseudo_random_id.to_vec(),
-                unreported,
-            });
-        }
+        let signature_agreements = completed_signatures_to_proto(&payload.signature_agreements);
 
         // ongoing_signatures
         let mut ongoing_signatures = Vec::new();
@@ -1509,6 +1869,35 @@ impl From<&EcdsaPayload> for pb::EcdsaPayload {
             next_in_creation: next_key_in_creation,
         } = pb::EcdsaKeyTranscript::from(payload.key_transcript.clone());
 
+        // available_schnorr_pre_signatures
+        let mut available_schnorr_pre_signatures = Vec::new();
+        for (pre_signature_id, pre_signature) in &payload.available_schnorr_pre_signatures {
+            available_schnorr_pre_signatures.push(pb::AvailableSchnorrPreSignature {
+                pre_signature_id: pre_signature_id.id(),
+                key_id: Some(pre_signature_id.key_id().into()),
+                pre_signature: Some(pre_signature.into()),
+            });
+        }
+
+        // schnorr_pre_signatures_in_creation
+        let mut schnorr_pre_signatures_in_creation = Vec::new();
+        for (pre_signature_id, pre_signature) in &payload.schnorr_pre_signatures_in_creation {
+            schnorr_pre_signatures_in_creation.push(pb::SchnorrPreSignatureInProgress {
+                pre_signature_id: pre_signature_id.id(),
+                key_id: Some(pre_signature_id.key_id().into()),
+                pre_signature: Some(pre_signature.into()),
+            });
+        }
+
+        // ongoing_schnorr_signatures
+        let mut ongoing_schnorr_signatures = Vec::new();
+        for (request_id, ongoing) in &payload.ongoing_schnorr_signatures {
+            ongoing_schnorr_signatures.push(pb::OngoingSchnorrSignature {
+                request_id: Some(request_id.clone().into()),
+                sig_inputs: Some(ongoing.into()),
+            })
+        }
+
         Self {
             signature_agreements,
             ongoing_signatures,
@@ -1524,6 +1913,17 @@ impl From<&EcdsaPayload> for pb::EcdsaPayload {
             current_key_transcript,
             next_key_in_creation,
             key_id,
+            schnorr_key_transcripts: payload
+                .schnorr_key_transcripts
+                .values()
+                .map(Into::into)
+                .collect(),
+            available_schnorr_pre_signatures,
+            schnorr_pre_signatures_in_creation,
+            ongoing_schnorr_signatures,
+            schnorr_signature_agreements: completed_signatures_to_proto(
+                &payload.schnorr_signature_agreements,
+            ),
         }
     }
 }
@@ -1554,29 +1954,7 @@ impl TryFrom<&pb::EcdsaPayload> for EcdsaPayload {
 
         let key_transcript = key_transcript_proto.try_into()?;
 
-        let mut signature_agreements = BTreeMap::new();
-        for completed_signature in &payload.signature_agreements {
-            let pseudo_random_id = {
-                if completed_signature.pseudo_random_id.len() != 32 {
-                    return Err(ProxyDecodeError::Other(
-                        "Expects 32 bytes of pseudo_random_id".to_string(),
-                    ));
-                }
-
-                let mut x = [0; 32];
-                x.copy_from_slice(&completed_signature.pseudo_random_id);
-                x
-            };
-
-            let signature = if let Some(unreported) = &completed_signature.unreported {
-                let response = crate::messages::Response::try_from(unreported.clone())?;
-                CompletedSignature::Unreported(response)
-            } else {
-                CompletedSignature::ReportedToExecution
-            };
-
-            signature_agreements.insert(pseudo_random_id, signature);
-        }
+        let signature_agreements = completed_signatures_from_proto(&payload.signature_agreements)?;
 
         // ongoing_signatures
         let mut ongoing_signatures = BTreeMap::new();
@@ -1684,6 +2062,60 @@ impl TryFrom<&pb::EcdsaPayload> for EcdsaPayload {
             xnet_reshare_agreements.insert(request, completed);
         }
 
+        let mut schnorr_key_transcripts = BTreeMap::new();
+        for key_transcript in &payload.schnorr_key_transcripts {
+            let key_transcript = SchnorrKeyTranscript::try_from(key_transcript)?;
+            schnorr_key_transcripts.insert(key_transcript.key_id.clone(), key_transcript);
+        }
+
+        // available_schnorr_pre_signatures
+        let mut available_schnorr_pre_signatures = BTreeMap::new();
+        for available_pre_signature in &payload.available_schnorr_pre_signatures {
+            let key_id = try_from_option_field(
+                available_pre_signature.key_id.clone(),
+                "EcdsaPayload::available_schnorr_pre_signature::key_id",
+            )?;
+            let pre_signature_id = PreSigId(available_pre_signature.pre_signature_id, key_id);
+            let pre_signature: SchnorrPreSignatureRef = try_from_option_field(
+                available_pre_signature.pre_signature.as_ref(),
+                "EcdsaPayload::available_schnorr_pre_signature::pre_signature",
+            )?;
+            available_schnorr_pre_signatures.insert(pre_signature_id, pre_signature);
+        }
+
+        // schnorr_pre_signatures_in_creation
+        let mut schnorr_pre_signatures_in_creation = BTreeMap::new();
+        for pre_signature_in_creation in &payload.schnorr_pre_signatures_in_creation {
+            let key_id = try_from_option_field(
+                pre_signature_in_creation.key_id.clone(),
+                "EcdsaPayload::schnorr_pre_signature_in_creation::key_id",
+            )?;
+            let pre_signature_id = PreSigId(pre_signature_in_creation.pre_signature_id, key_id);
+            let pre_signature: SchnorrPreSignatureInCreation = try_from_option_field(
+                pre_signature_in_creation.pre_signature.as_ref(),
+                "EcdsaPayload::schnorr_pre_signature_in_creation::pre_signature",
+            )?;
+            schnorr_pre_signatures_in_creation.insert(pre_signature_id, pre_signature);
+        }
+
+        // ongoing_schnorr_signatures
+        let mut ongoing_schnorr_signatures = BTreeMap::new();
+        for ongoing_signature in &payload.ongoing_schnorr_signatures {
+            let request_id: SchnorrRequestId = try_from_option_field(
+                ongoing_signature.request_id.as_ref(),
+                "EcdsaPayload::ongoing_schnorr_signature::request_id",
+            )?;
+
+            let sig_inputs = try_from_option_field(
+                ongoing_signature.sig_inputs.as_ref(),
+                "EcdsaPayload::ongoing_schnorr_signature::sig_inputs",
+            )?;
+            ongoing_schnorr_signatures.insert(request_id, sig_inputs);
+        }
+
+        let schnorr_signature_agreements =
+            completed_signatures_from_proto(&payload.schnorr_signature_agreements)?;
+
         Ok(Self {
             signature_agreements,
             ongoing_signatures,
@@ -1694,6 +2126,11 @@ impl TryFrom<&pb::EcdsaPayload> for EcdsaPayload {
             xnet_reshare_agreements,
             uid_generator,
             key_transcript,
+            schnorr_key_transcripts,
+            available_schnorr_pre_signatures,
+            schnorr_pre_signatures_in_creation,
+            ongoing_schnorr_signatures,
+            schnorr_signature_agreements,
         })
     }
 }
@@ -1809,6 +2246,16 @@ impl EcdsaObject for EcdsaSigShare {
     }
 }
 
+impl EcdsaObject for SchnorrSigShare {
+    fn message_prefix(&self) -> EcdsaPrefixOf<Self> {
+        schnorr_sig_share_prefix(&self.request_id, &self.signer_id)
+    }
+
+    fn message_id(&self) -> EcdsaArtifactId {
+        EcdsaArtifactId::SchnorrSigShare(self.message_prefix(), crypto_hash(self))
+    }
+}
+
 impl EcdsaObject for EcdsaComplaint {
     fn message_prefix(&self) -> EcdsaPrefixOf<Self> {
         complaint_prefix(
@@ -1845,6 +2292,7 @@ impl From<&EcdsaMessage> for EcdsaArtifactId {
             EcdsaMessage::EcdsaSigShare(object) => object.message_id(),
             EcdsaMessage::EcdsaComplaint(object) => object.message_id(),
             EcdsaMessage::EcdsaOpening(object) => object.message_id(),
+            EcdsaMessage::SchnorrSigShare(object) => object.message_id(),
         }
     }
 }
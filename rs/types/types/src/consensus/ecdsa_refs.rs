@@ -2,14 +2,16 @@
 use crate::crypto::{
     canister_threshold_sig::error::{
         IDkgParamsValidationError, PresignatureQuadrupleCreationError,
-        ThresholdEcdsaSigInputsCreationError,
+        SchnorrPreSignatureTranscriptCreationError, ThresholdEcdsaSigInputsCreationError,
+        ThresholdSchnorrSigInputsCreationError,
     },
     canister_threshold_sig::idkg::{
         IDkgTranscript, IDkgTranscriptId, IDkgTranscriptOperation, IDkgTranscriptParams,
         IDkgTranscriptType,
     },
     canister_threshold_sig::{
-        ExtendedDerivationPath, PreSignatureQuadruple, ThresholdEcdsaSigInputs,
+        ExtendedDerivationPath, PreSignatureQuadruple, SchnorrPreSignatureTranscript,
+        ThresholdEcdsaSigInputs, ThresholdSchnorrSigInputs,
     },
     AlgorithmId,
 };
@@ -17,7 +19,7 @@ use crate::{Height, Randomness, RegistryVersion};
 use ic_base_types::NodeId;
 #[cfg(test)]
 use ic_exhaustive_derive::ExhaustiveSet;
-use ic_ic00_types::EcdsaKeyId;
+use ic_ic00_types::{EcdsaKeyId, SchnorrKeyId};
 use ic_protobuf::proxy::{try_from_option_field, ProxyDecodeError};
 use ic_protobuf::registry::subnet::v1 as subnet_pb;
 use ic_protobuf::types::v1 as pb;
@@ -84,6 +86,55 @@ impl TryFrom<&pb::RequestId> for RequestId {
     }
 }
 
+/// SchnorrRequestId is the threshold Schnorr counterpart of [`RequestId`]:
+/// it identifies the matching request in sign_with_schnorr_contexts, and
+/// the pre-signature the request is matched to.
+///
+/// The height field represents at which block the SchnorrRequestId is created.
+/// It is used for purging purpose.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Hash)]
+#[cfg_attr(test, derive(ExhaustiveSet))]
+pub struct SchnorrRequestId {
+    pub pre_signature_id: PreSigId,
+    pub pseudo_random_id: PseudoRandomId,
+    pub height: Height,
+}
+
+impl From<SchnorrRequestId> for pb::SchnorrRequestId {
+    fn from(request_id: SchnorrRequestId) -> Self {
+        Self {
+            pre_signature_id: request_id.pre_signature_id.id(),
+            key_id: Some(request_id.pre_signature_id.key_id().into()),
+            pseudo_random_id: request_id.pseudo_random_id.to_vec(),
+            height: request_id.height.get(),
+        }
+    }
+}
+
+impl TryFrom<&pb::SchnorrRequestId> for SchnorrRequestId {
+    type Error = ProxyDecodeError;
+
+    fn try_from(request_id: &pb::SchnorrRequestId) -> Result<Self, Self::Error> {
+        if request_id.pseudo_random_id.len() != 32 {
+            Err(ProxyDecodeError::Other(String::from(
+                "request_id.pseudo_random_id must be 32 bytes long",
+            )))
+        } else {
+            let mut pseudo_random_id = [0; 32];
+            pseudo_random_id.copy_from_slice(&request_id.pseudo_random_id);
+
+            let key_id =
+                try_from_option_field(request_id.key_id.clone(), "SchnorrRequestId::key_id")?;
+
+            Ok(Self {
+                pre_signature_id: PreSigId(request_id.pre_signature_id, key_id),
+                pseudo_random_id,
+                height: Height::from(request_id.height),
+            })
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(test, derive(ExhaustiveSet))]
 pub struct QuadrupleId(pub u64, pub Option<EcdsaKeyId>);
@@ -106,6 +157,31 @@ impl Hash for QuadrupleId {
     }
 }
 
+/// Identifies a threshold Schnorr pre-signature and the key it was created
+/// for. Pre-signature ids are drawn from the same counter as quadruple ids, so
+/// they are unique across both kinds.
+#[derive(Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ExhaustiveSet))]
+pub struct PreSigId(pub u64, pub SchnorrKeyId);
+
+impl PreSigId {
+    pub fn id(&self) -> u64 {
+        self.0
+    }
+
+    pub fn key_id(&self) -> &SchnorrKeyId {
+        &self.1
+    }
+}
+
+// Since `PreSigId.0` is globally unique across all keys (this is guaranteed by the
+// `EcdsaUIDGenerator`), we use only this field to compute the hash of the `PreSigId`.
+impl Hash for PreSigId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id().hash(state);
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Hash)]
 #[cfg_attr(test, derive(ExhaustiveSet))]
 pub struct TranscriptRef {
@@ -997,6 +1073,86 @@ impl TryFrom<&pb::QuadrupleInCreation> for QuadrupleInCreation {
     }
 }
 
+/// Threshold Schnorr pre-signature in creation.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Hash)]
+#[cfg_attr(test, derive(ExhaustiveSet))]
+pub struct SchnorrPreSignatureInCreation {
+    pub blinder_unmasked_config: RandomUnmaskedTranscriptParams,
+    pub blinder_unmasked: Option<UnmaskedTranscript>,
+}
+
+impl SchnorrPreSignatureInCreation {
+    /// Initialization with the given random unmasked param.
+    pub fn new(blinder_unmasked_config: RandomUnmaskedTranscriptParams) -> Self {
+        Self {
+            blinder_unmasked_config,
+            blinder_unmasked: None,
+        }
+    }
+
+    /// Return an iterator of all transcript configs that have no matching
+    /// results yet.
+    pub fn iter_transcript_configs_in_creation(
+        &self,
+    ) -> Box<dyn Iterator<Item = &IDkgTranscriptParamsRef> + '_> {
+        if self.blinder_unmasked.is_none() {
+            Box::new(std::iter::once(self.blinder_unmasked_config.as_ref()))
+        } else {
+            Box::new(std::iter::empty())
+        }
+    }
+
+    /// Returns the refs held
+    pub fn get_refs(&self) -> Vec<TranscriptRef> {
+        let mut ret = self.blinder_unmasked_config.as_ref().get_refs();
+        if let Some(r) = &self.blinder_unmasked {
+            ret.push(*r.as_ref());
+        }
+        ret
+    }
+
+    /// Updates the height of the references.
+    pub fn update(&mut self, height: Height) {
+        self.blinder_unmasked_config.as_mut().update(height);
+        if let Some(r) = &mut self.blinder_unmasked {
+            r.as_mut().update(height);
+        }
+    }
+}
+
+impl From<&SchnorrPreSignatureInCreation> for pb::SchnorrPreSignatureInCreation {
+    fn from(pre_signature: &SchnorrPreSignatureInCreation) -> Self {
+        Self {
+            blinder_unmasked_config: Some((&pre_signature.blinder_unmasked_config).into()),
+            blinder_unmasked: pre_signature
+                .blinder_unmasked
+                .as_ref()
+                .map(|transcript| transcript.into()),
+        }
+    }
+}
+
+impl TryFrom<&pb::SchnorrPreSignatureInCreation> for SchnorrPreSignatureInCreation {
+    type Error = ProxyDecodeError;
+    fn try_from(pre_signature: &pb::SchnorrPreSignatureInCreation) -> Result<Self, Self::Error> {
+        let blinder_unmasked_config: RandomUnmaskedTranscriptParams = try_from_option_field(
+            pre_signature.blinder_unmasked_config.as_ref(),
+            "SchnorrPreSignatureInCreation::blinder_unmasked_config",
+        )?;
+
+        let blinder_unmasked: Option<UnmaskedTranscript> = pre_signature
+            .blinder_unmasked
+            .as_ref()
+            .map(|transcript| transcript.try_into())
+            .transpose()?;
+
+        Ok(Self {
+            blinder_unmasked_config,
+            blinder_unmasked,
+        })
+    }
+}
+
 pub type TranscriptLookupError = String;
 
 /// Wrapper to access the ECDSA related info from the blocks.
@@ -1018,6 +1174,11 @@ pub trait EcdsaBlockReader: Send + Sync {
     /// For the given quadruple ID, returns the quadruple ref if available.
     fn available_quadruple(&self, id: &QuadrupleId) -> Option<&PreSignatureQuadrupleRef>;
 
+    /// Returns the threshold Schnorr signatures requested by the tip.
+    fn requested_schnorr_signatures(
+        &self,
+    ) -> Box<dyn Iterator<Item = (&SchnorrRequestId, &ThresholdSchnorrSigInputsRef)> + '_>;
+
     /// Returns the set of all the active references.
     fn active_transcripts(&self) -> BTreeSet<TranscriptRef>;
 
@@ -1650,3 +1811,189 @@ impl TryFrom<&pb::ThresholdEcdsaSigInputsRef> for ThresholdEcdsaSigInputsRef {
         ))
     }
 }
+
+/// Counterpart of SchnorrPreSignatureTranscript that holds transcript references,
+/// instead of the transcripts.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ExhaustiveSet))]
+pub struct SchnorrPreSignatureRef {
+    pub blinder_unmasked_ref: UnmaskedTranscript,
+}
+
+#[derive(Clone, Debug)]
+pub enum SchnorrPreSignatureError {
+    BlinderUnmasked(TranscriptLookupError),
+    Failed(SchnorrPreSignatureTranscriptCreationError),
+}
+
+impl SchnorrPreSignatureRef {
+    pub fn new(blinder_unmasked_ref: UnmaskedTranscript) -> Self {
+        Self {
+            blinder_unmasked_ref,
+        }
+    }
+
+    /// Resolves the refs to get the SchnorrPreSignatureTranscript.
+    pub fn translate(
+        &self,
+        resolver: &dyn EcdsaBlockReader,
+    ) -> Result<SchnorrPreSignatureTranscript, SchnorrPreSignatureError> {
+        let blinder_unmasked = resolver
+            .transcript(self.blinder_unmasked_ref.as_ref())
+            .map_err(SchnorrPreSignatureError::BlinderUnmasked)?;
+        SchnorrPreSignatureTranscript::new(blinder_unmasked)
+            .map_err(SchnorrPreSignatureError::Failed)
+    }
+
+    /// Returns the refs held
+    pub fn get_refs(&self) -> Vec<TranscriptRef> {
+        vec![*self.blinder_unmasked_ref.as_ref()]
+    }
+
+    /// Updates the height of the references.
+    pub fn update(&mut self, height: Height) {
+        self.blinder_unmasked_ref.as_mut().update(height);
+    }
+}
+
+impl From<&SchnorrPreSignatureRef> for pb::SchnorrPreSignatureRef {
+    fn from(pre_signature: &SchnorrPreSignatureRef) -> Self {
+        Self {
+            blinder_unmasked_ref: Some((&pre_signature.blinder_unmasked_ref).into()),
+        }
+    }
+}
+
+impl TryFrom<&pb::SchnorrPreSignatureRef> for SchnorrPreSignatureRef {
+    type Error = ProxyDecodeError;
+    fn try_from(pre_signature: &pb::SchnorrPreSignatureRef) -> Result<Self, Self::Error> {
+        let blinder_unmasked_ref: UnmaskedTranscript = try_from_option_field(
+            pre_signature.blinder_unmasked_ref.as_ref(),
+            "SchnorrPreSignatureRef::blinder_unmasked_ref",
+        )?;
+
+        Ok(Self::new(blinder_unmasked_ref))
+    }
+}
+
+/// Counterpart of ThresholdSchnorrSigInputs that holds transcript references,
+/// instead of the transcripts.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ExhaustiveSet))]
+pub struct ThresholdSchnorrSigInputsRef {
+    pub derivation_path: ExtendedDerivationPath,
+    pub message: Vec<u8>,
+    pub nonce: Randomness,
+    pub presig_transcript_ref: SchnorrPreSignatureRef,
+    pub key_transcript_ref: UnmaskedTranscript,
+}
+
+#[derive(Clone, Debug)]
+pub enum ThresholdSchnorrSigInputsError {
+    PreSignature(SchnorrPreSignatureError),
+    KeyTranscript(TranscriptLookupError),
+    Failed(ThresholdSchnorrSigInputsCreationError),
+}
+
+impl ThresholdSchnorrSigInputsRef {
+    pub fn new(
+        derivation_path: ExtendedDerivationPath,
+        message: Vec<u8>,
+        nonce: Randomness,
+        presig_transcript_ref: SchnorrPreSignatureRef,
+        key_transcript_ref: UnmaskedTranscript,
+    ) -> Self {
+        Self {
+            derivation_path,
+            message,
+            nonce,
+            presig_transcript_ref,
+            key_transcript_ref,
+        }
+    }
+
+    /// Resolves the refs to get the ThresholdSchnorrSigInputs.
+    pub fn translate(
+        &self,
+        resolver: &dyn EcdsaBlockReader,
+    ) -> Result<ThresholdSchnorrSigInputs, ThresholdSchnorrSigInputsError> {
+        let presig_transcript = self
+            .presig_transcript_ref
+            .translate(resolver)
+            .map_err(ThresholdSchnorrSigInputsError::PreSignature)?;
+        let key_transcript = resolver
+            .transcript(self.key_transcript_ref.as_ref())
+            .map_err(ThresholdSchnorrSigInputsError::KeyTranscript)?;
+        ThresholdSchnorrSigInputs::new(
+            &self.derivation_path,
+            &self.message,
+            self.nonce,
+            presig_transcript,
+            key_transcript,
+        )
+        .map_err(ThresholdSchnorrSigInputsError::Failed)
+    }
+
+    /// Returns the refs held
+    pub fn get_refs(&self) -> Vec<TranscriptRef> {
+        let mut ret = self.presig_transcript_ref.get_refs();
+        ret.push(*self.key_transcript_ref.as_ref());
+        ret
+    }
+
+    /// Updates the height of the references.
+    pub fn update(&mut self, height: Height) {
+        self.presig_transcript_ref.update(height);
+        self.key_transcript_ref.as_mut().update(height);
+    }
+}
+
+impl From<&ThresholdSchnorrSigInputsRef> for pb::ThresholdSchnorrSigInputsRef {
+    fn from(sig_inputs: &ThresholdSchnorrSigInputsRef) -> Self {
+        Self {
+            derivation_path: Some((sig_inputs.derivation_path.clone()).into()),
+            message: sig_inputs.message.clone(),
+            nonce: sig_inputs.nonce.get().to_vec(),
+            presig_transcript_ref: Some((&sig_inputs.presig_transcript_ref).into()),
+            key_transcript_ref: Some((&sig_inputs.key_transcript_ref).into()),
+        }
+    }
+}
+
+impl TryFrom<&pb::ThresholdSchnorrSigInputsRef> for ThresholdSchnorrSigInputsRef {
+    type Error = ProxyDecodeError;
+    fn try_from(sig_inputs: &pb::ThresholdSchnorrSigInputsRef) -> Result<Self, Self::Error> {
+        let derivation_path: ExtendedDerivationPath = try_from_option_field(
+            sig_inputs.derivation_path.clone(),
+            "ThresholdSchnorrSigInputsRef::derivation_path",
+        )?;
+
+        if sig_inputs.nonce.len() != 32 {
+            return Err(ProxyDecodeError::Other(format!(
+                "ThresholdSchnorrSigInputsRef:: Invalid nonce length: {:?}",
+                sig_inputs.nonce.len()
+            )));
+        }
+        let mut nonce = [0; 32];
+        nonce.copy_from_slice(&sig_inputs.nonce[0..32]);
+        let nonce = Randomness::from(nonce);
+
+        let presig_transcript_ref: SchnorrPreSignatureRef = try_from_option_field(
+            sig_inputs.presig_transcript_ref.as_ref(),
+            "ThresholdSchnorrSigInputsRef::presig_transcript_ref",
+        )?;
+
+        let key_transcript_ref: UnmaskedTranscript = try_from_option_field(
+            sig_inputs.key_transcript_ref.as_ref(),
+            "ThresholdSchnorrSigInputsRef::key_transcript_ref",
+        )?;
+
+        Ok(Self::new(
+            derivation_path,
+            sig_inputs.message.clone(),
+            nonce,
+            presig_transcript_ref,
+            key_transcript_ref,
+        ))
+    }
+}
@@ -43,6 +43,30 @@ pub struct MasterEcdsaPublicKey {
     pub public_key: Vec<u8>,
 }
 
+/// A threshold Schnorr public key.
+///
+/// The public key itself is stored as raw bytes.
+///
+/// The chain key is included for BIP32-style key derivation
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SchnorrPublicKey {
+    pub algorithm_id: AlgorithmId,
+    #[serde(with = "serde_bytes")]
+    pub public_key: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    pub chain_key: Vec<u8>,
+}
+
+/// A threshold Schnorr master public key.
+///
+/// The public key itself is stored as raw bytes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MasterSchnorrPublicKey {
+    pub algorithm_id: AlgorithmId,
+    #[serde(with = "serde_bytes")]
+    pub public_key: Vec<u8>,
+}
+
 /// A combined threshold ECDSA signature.
 ///
 /// The signature itself is stored as raw bytes.
@@ -512,3 +536,231 @@ impl fmt::Debug for ThresholdEcdsaSigShare {
         )
     }
 }
+
+/// A combined threshold Schnorr signature.
+///
+/// The signature itself is stored as raw bytes.
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ThresholdSchnorrCombinedSignature {
+    #[serde(with = "serde_bytes")]
+    pub signature: Vec<u8>,
+}
+
+impl Display for ThresholdSchnorrCombinedSignature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl fmt::Debug for ThresholdSchnorrCombinedSignature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ThresholdSchnorrCombinedSignature {{ signature: 0x{} }}",
+            hex::encode(&self.signature)
+        )
+    }
+}
+
+/// Pre-signature of a canister-requested threshold Schnorr signature.
+/// Each pre-signature MUST be used *at most once* for a signature. Otherwise, the private key
+/// may be leaked!
+///
+/// Unlike for ECDSA, a single unmasked transcript for the sharing of a random value (the
+/// blinder) is sufficient, since the Schnorr signing equation is linear in the secrets.
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SchnorrPreSignatureTranscript {
+    blinder_unmasked: IDkgTranscript,
+}
+
+impl Display for SchnorrPreSignatureTranscript {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl fmt::Debug for SchnorrPreSignatureTranscript {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "SchnorrPreSignatureTranscript {{ blinder_unmasked: {:?} }}",
+            self.blinder_unmasked.transcript_id
+        )
+    }
+}
+
+impl SchnorrPreSignatureTranscript {
+    /// Creates a Schnorr pre-signature.
+    ///
+    /// A `SchnorrPreSignatureTranscript` can only be created if the following invariants hold:
+    /// * The `blinder_unmasked` transcript is of type `Unmasked` with origin `Random`
+    ///   (error: `InvalidTranscriptOrigin`)
+    /// * The algorithm ID of the `blinder_unmasked` transcript is supported for the
+    ///   creation of threshold Schnorr signatures (error: `UnsupportedAlgorithm`)
+    pub fn new(
+        blinder_unmasked: IDkgTranscript,
+    ) -> Result<Self, error::SchnorrPreSignatureTranscriptCreationError> {
+        if !blinder_unmasked.algorithm_id.is_threshold_schnorr() {
+            return Err(error::SchnorrPreSignatureTranscriptCreationError::UnsupportedAlgorithm);
+        }
+        match &blinder_unmasked.transcript_type {
+            IDkgTranscriptType::Unmasked(IDkgUnmaskedTranscriptOrigin::Random) => {
+                Ok(Self { blinder_unmasked })
+            }
+            _ => Err(error::SchnorrPreSignatureTranscriptCreationError::InvalidTranscriptOrigin(
+                format!("`blinder_unmasked` transcript expected to have type `Unmasked` with `Random` origin, but found transcript of type {:?}", blinder_unmasked.transcript_type))
+            ),
+        }
+    }
+
+    pub fn blinder_unmasked(&self) -> &IDkgTranscript {
+        &self.blinder_unmasked
+    }
+}
+
+/// All inputs required to generate a canister threshold Schnorr signature.
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ThresholdSchnorrSigInputs {
+    derivation_path: ExtendedDerivationPath,
+    #[serde(with = "serde_bytes")]
+    message: Vec<u8>,
+    nonce: Randomness,
+    presig_transcript: SchnorrPreSignatureTranscript,
+    key_transcript: IDkgTranscript,
+}
+
+impl Display for ThresholdSchnorrSigInputs {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl fmt::Debug for ThresholdSchnorrSigInputs {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "ThresholdSchnorrSigInputs {{ ")?;
+        write!(f, "derivation_path: {:?}", self.derivation_path)?;
+        write!(f, ", message: 0x{}", hex::encode(&self.message))?;
+        write!(f, ", nonce: 0x{}", hex::encode(self.nonce.as_ref()))?;
+        write!(f, ", presig_transcript: {}", self.presig_transcript)?;
+        write!(f, ", key_transcript: {}", self.key_transcript.transcript_id)?;
+        write!(f, " }}")?;
+        Ok(())
+    }
+}
+
+impl AsRef<IDkgReceivers> for ThresholdSchnorrSigInputs {
+    fn as_ref(&self) -> &IDkgReceivers {
+        self.receivers()
+    }
+}
+
+impl ThresholdSchnorrSigInputs {
+    /// Creates the inputs to the threshold Schnorr signing protocol.
+    ///
+    /// A `ThresholdSchnorrSigInputs` can only be created if the following invariants hold:
+    /// * The algorithm ID of the `key_transcript` is the same as the algorithm ID
+    ///   of the `presig_transcript` (error: `InconsistentAlgorithmIds`)
+    /// * The algorithm ID of the `key_transcript` is supported for the creation
+    ///   of threshold Schnorr signatures (error: `UnsupportedAlgorithm`)
+    /// * All transcripts have the same receiver set (error: `InconsistentReceivers`)
+    /// * The `key_transcript` is unmasked (error: `InvalidKeyTranscriptType`)
+    pub fn new(
+        derivation_path: &ExtendedDerivationPath,
+        message: &[u8],
+        nonce: Randomness,
+        presig_transcript: SchnorrPreSignatureTranscript,
+        key_transcript: IDkgTranscript,
+    ) -> Result<Self, error::ThresholdSchnorrSigInputsCreationError> {
+        if presig_transcript.blinder_unmasked().algorithm_id != key_transcript.algorithm_id {
+            return Err(error::ThresholdSchnorrSigInputsCreationError::InconsistentAlgorithmIds);
+        }
+        if !key_transcript.algorithm_id.is_threshold_schnorr() {
+            return Err(error::ThresholdSchnorrSigInputsCreationError::UnsupportedAlgorithm);
+        }
+        if presig_transcript.blinder_unmasked().receivers != key_transcript.receivers {
+            return Err(error::ThresholdSchnorrSigInputsCreationError::InconsistentReceivers);
+        }
+        if !matches!(
+            key_transcript.transcript_type,
+            IDkgTranscriptType::Unmasked(_)
+        ) {
+            return Err(
+                error::ThresholdSchnorrSigInputsCreationError::InvalidKeyTranscriptType(format!(
+                    "`key_transcript` expected to have type `Unmasked`, but found transcript of type {:?}",
+                    key_transcript.transcript_type
+                )),
+            );
+        }
+
+        Ok(Self {
+            derivation_path: derivation_path.clone(),
+            message: message.to_vec(),
+            nonce,
+            presig_transcript,
+            key_transcript,
+        })
+    }
+
+    pub fn derivation_path(&self) -> &ExtendedDerivationPath {
+        &self.derivation_path
+    }
+
+    pub fn message(&self) -> &[u8] {
+        &self.message
+    }
+
+    pub fn nonce(&self) -> &Randomness {
+        &self.nonce
+    }
+
+    pub fn presig_transcript(&self) -> &SchnorrPreSignatureTranscript {
+        &self.presig_transcript
+    }
+
+    pub fn key_transcript(&self) -> &IDkgTranscript {
+        &self.key_transcript
+    }
+
+    /// Number of contributions needed to reconstruct a sharing.
+    pub fn reconstruction_threshold(&self) -> NumberOfNodes {
+        // We already checked that all receiver sets are equal
+        self.key_transcript.reconstruction_threshold()
+    }
+
+    pub fn receivers(&self) -> &IDkgReceivers {
+        // We already checked that all receiver sets are equal
+        &self.key_transcript.receivers
+    }
+
+    pub fn algorithm_id(&self) -> AlgorithmId {
+        // We already checked that all transcripts have the same alg_id
+        self.key_transcript.algorithm_id
+    }
+
+    pub fn index_for_signer_id(&self, node_id: NodeId) -> Option<NodeIndex> {
+        self.key_transcript().index_for_signer_id(node_id)
+    }
+}
+
+/// A single threshold Schnorr signature share.
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ThresholdSchnorrSigShare {
+    #[serde(with = "serde_bytes")]
+    pub sig_share_raw: Vec<u8>,
+}
+
+impl Display for ThresholdSchnorrSigShare {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl fmt::Debug for ThresholdSchnorrSigShare {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ThresholdSchnorrSigShare {{ sig_share_raw: 0x{} }}",
+            hex::encode(&self.sig_share_raw)
+        )
+    }
+}
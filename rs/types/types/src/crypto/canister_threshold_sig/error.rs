@@ -46,6 +46,22 @@ pub enum ThresholdEcdsaSigInputsCreationError {
 }
 impl_display_using_debug!(ThresholdEcdsaSigInputsCreationError);
 
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SchnorrPreSignatureTranscriptCreationError {
+    InvalidTranscriptOrigin(String),
+    UnsupportedAlgorithm,
+}
+impl_display_using_debug!(SchnorrPreSignatureTranscriptCreationError);
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThresholdSchnorrSigInputsCreationError {
+    InconsistentAlgorithmIds,
+    InconsistentReceivers,
+    InvalidKeyTranscriptType(String),
+    UnsupportedAlgorithm,
+}
+impl_display_using_debug!(ThresholdSchnorrSigInputsCreationError);
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum IDkgParamsValidationError {
     TooManyReceivers { receivers_count: usize },
@@ -86,6 +102,13 @@ pub enum ThresholdEcdsaGetPublicKeyError {
 }
 impl_display_using_debug!(ThresholdEcdsaGetPublicKeyError);
 
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThresholdSchnorrGetPublicKeyError {
+    InvalidArgument(String),
+    InternalError(String),
+}
+impl_display_using_debug!(ThresholdSchnorrGetPublicKeyError);
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum IDkgCreateTranscriptError {
     SerializationError {
@@ -359,3 +382,41 @@ pub enum ThresholdEcdsaCombineSigSharesError {
     SignerNotAllowed { node_id: NodeId },
 }
 impl_display_using_debug!(ThresholdEcdsaCombineSigSharesError);
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThresholdSchnorrVerifySigShareError {
+    InternalError { internal_error: String },
+    SerializationError { internal_error: String },
+    InvalidSignatureShare,
+    InvalidArgumentMissingSignerInTranscript { signer_id: NodeId },
+    InvalidArguments(String),
+}
+impl_display_using_debug!(ThresholdSchnorrVerifySigShareError);
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThresholdSchnorrSignShareError {
+    InternalError { internal_error: String },
+    NotAReceiver,
+    SerializationError { internal_error: String },
+    SecretSharesNotFound { commitment_string: String },
+    TransientInternalError { internal_error: String },
+}
+impl_display_using_debug!(ThresholdSchnorrSignShareError);
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThresholdSchnorrVerifyCombinedSignatureError {
+    InternalError { internal_error: String },
+    InvalidSignature,
+    SerializationError { internal_error: String },
+    InvalidArguments(String),
+}
+impl_display_using_debug!(ThresholdSchnorrVerifyCombinedSignatureError);
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThresholdSchnorrCombineSigSharesError {
+    InternalError { internal_error: String },
+    UnsatisfiedReconstructionThreshold { threshold: u32, share_count: usize },
+    SerializationError { internal_error: String },
+    SignerNotAllowed { node_id: NodeId },
+}
+impl_display_using_debug!(ThresholdSchnorrCombineSigSharesError);
@@ -303,8 +303,9 @@ pub struct IDkgTranscriptParams {
     registry_version: RegistryVersion,
     /// Identifies the cryptographic signature scheme used in the
     /// protocol.  Currently only
-    /// [`AlgorithmId::ThresholdEcdsaSecp256k1`] and
-    /// [`AlgorithmId::ThresholdEcdsaSecp256r1`] are supported.
+    /// [`AlgorithmId::ThresholdEcdsaSecp256k1`],
+    /// [`AlgorithmId::ThresholdEcdsaSecp256r1`] and
+    /// [`AlgorithmId::ThresholdSchnorrBip340`] are supported.
     algorithm_id: AlgorithmId,
     /// Mode of operation for this current execution of the protocol.
     operation_type: IDkgTranscriptOperation,
@@ -328,8 +329,8 @@ impl IDkgTranscriptParams {
     ///   and `ReceiversEmpty`)
     /// * |dealers| >= self.collection_threshold + faults_tolerated(|dealers|)
     ///   (error: `UnsatisfiedCollectionThreshold`)
-    /// * algorithm_id is of type `ThresholdEcdsaSecp256k1` (error:
-    ///   `UnsupportedAlgorithmId`)
+    /// * algorithm_id is a threshold ECDSA or threshold Schnorr algorithm
+    ///   (error: `UnsupportedAlgorithmId`)
     /// * If `operation_type` is:
     ///   - ReshareOfMasked(t):
     ///     - t is of type Masked(_)
@@ -507,6 +508,7 @@ impl IDkgTranscriptParams {
         match self.algorithm_id {
             AlgorithmId::ThresholdEcdsaSecp256k1 => Ok(()),
             AlgorithmId::ThresholdEcdsaSecp256r1 => Ok(()),
+            AlgorithmId::ThresholdSchnorrBip340 => Ok(()),
             _ => Err(IDkgParamsValidationError::UnsupportedAlgorithmId {
                 algorithm_id: self.algorithm_id,
             }),
@@ -52,6 +52,7 @@ pub enum DomainSeparator {
     IdkgDealingSupport,
     EcdsaTranscript,
     EcdsaSigShare,
+    SchnorrSigShare,
     EcdsaComplaintContent,
     EcdsaComplaint,
     EcdsaOpeningContent,
@@ -109,6 +110,7 @@ impl DomainSeparator {
             DomainSeparator::IdkgDealingSupport => "ic-idkg-dealing-support-domain",
             DomainSeparator::EcdsaTranscript => "ic-idkg-transcript-domain",
             DomainSeparator::EcdsaSigShare => "ic-threshold-ecdsa-sig-share-domain",
+            DomainSeparator::SchnorrSigShare => "ic-threshold-schnorr-sig-share-domain",
             DomainSeparator::EcdsaComplaintContent => "ic-threshold-ecdsa-complaint-content-domain",
             DomainSeparator::EcdsaComplaint => "ic-threshold-ecdsa-complaint-domain",
             DomainSeparator::EcdsaOpeningContent => "ic-threshold-ecdsa-opening-content-domain",
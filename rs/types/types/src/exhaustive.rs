@@ -23,7 +23,7 @@ use ic_btc_types_internal::{BitcoinAdapterResponse, BitcoinAdapterResponseWrappe
 use ic_crypto_test_utils_canister_threshold_sigs::random_node_id_excluding;
 use ic_error_types::RejectCode;
 use ic_exhaustive_derive::ExhaustiveSet;
-use ic_ic00_types::{EcdsaCurve, EcdsaKeyId};
+use ic_ic00_types::{EcdsaCurve, EcdsaKeyId, SchnorrAlgorithm, SchnorrKeyId};
 use ic_protobuf::types::v1 as pb;
 use phantom_newtype::{AmountOf, Id};
 use prost::Message;
@@ -336,6 +336,25 @@ impl ExhaustiveSet for EcdsaKeyId {
     }
 }
 
+impl ExhaustiveSet for SchnorrAlgorithm {
+    fn exhaustive_set<R: RngCore + CryptoRng>(_: &mut R) -> Vec<Self> {
+        // Ed25519 keys are rejected when decoded from protobuf.
+        vec![SchnorrAlgorithm::Bip340Secp256k1]
+    }
+}
+
+impl ExhaustiveSet for SchnorrKeyId {
+    fn exhaustive_set<R: RngCore + CryptoRng>(rng: &mut R) -> Vec<Self> {
+        <(SchnorrAlgorithm, String)>::exhaustive_set(rng)
+            .into_iter()
+            .map(|elem| Self {
+                algorithm: elem.0,
+                name: elem.1,
+            })
+            .collect()
+    }
+}
+
 impl<V: ExhaustiveSet + CryptoHashable> ExhaustiveSet for Hashed<CryptoHashOf<V>, V> {
     fn exhaustive_set<R: RngCore + CryptoRng>(rng: &mut R) -> Vec<Self> {
         let mut res = Vec::new();
@@ -67,6 +67,7 @@ pub mod artifact;
 pub mod artifact_kind;
 pub mod batch;
 pub mod canister_http;
+pub mod canister_log;
 pub mod consensus;
 pub mod crypto;
 pub mod filetree_sync;
@@ -471,6 +472,15 @@ pub const MAX_STABLE_MEMORY_IN_BYTES: u64 = 400 * GB;
 /// it is public and `u64` (`NumBytes` cannot be used in const expressions).
 pub const MAX_WASM_MEMORY_IN_BYTES: u64 = 4 * GB;
 
+/// The upper limit on the size of a 64-bit Wasm memory.
+/// This constant is used by other crates to define other constants, that's why
+/// it is public and `u64` (`NumBytes` cannot be used in const expressions).
+pub const MAX_WASM64_MEMORY_IN_BYTES: u64 = 6 * GB;
+
+/// The amount by which the memory limit of a canister with a 64-bit Wasm
+/// memory exceeds the limit of a canister with a 32-bit one.
+pub const WASM64_EXTRA_MEMORY_IN_BYTES: u64 = MAX_WASM64_MEMORY_IN_BYTES - MAX_WASM_MEMORY_IN_BYTES;
+
 const MIN_MEMORY_ALLOCATION: NumBytes = NumBytes::new(0);
 pub const MAX_MEMORY_ALLOCATION: NumBytes =
     NumBytes::new(MAX_STABLE_MEMORY_IN_BYTES + MAX_WASM_MEMORY_IN_BYTES);
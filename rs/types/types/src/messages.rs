@@ -17,7 +17,9 @@ pub use self::http::{
     SignedDelegation,
 };
 pub use crate::methods::SystemMethod;
-use crate::{user_id_into_protobuf, user_id_try_from_protobuf, Cycles, Funds, NumBytes, UserId};
+use crate::{
+    user_id_into_protobuf, user_id_try_from_protobuf, Cycles, Funds, NumBytes, Time, UserId,
+};
 pub use blob::Blob;
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_ic00_types::CanisterChangeOrigin;
@@ -112,6 +114,9 @@ pub enum StopCanisterContext {
         /// here so that they can be returned to the caller in the eventual
         /// reply.
         cycles: Cycles,
+        /// Deadline of the request to stop the canister, if it was a
+        /// best-effort call.
+        deadline: Option<Time>,
     },
 }
 
@@ -148,6 +153,7 @@ impl From<(CanisterCall, StopCanisterCallId)> for StopCanisterContext {
                 reply_callback: req.sender_reply_callback,
                 call_id: Some(call_id),
                 cycles: Arc::make_mut(&mut req).payment.take(),
+                deadline: req.deadline,
             },
             CanisterCall::Ingress(ingress) => StopCanisterContext::Ingress {
                 sender: ingress.source,
@@ -179,6 +185,7 @@ impl From<&StopCanisterContext> for pb::StopCanisterContext {
                 reply_callback,
                 call_id,
                 cycles,
+                deadline,
             } => Self {
                 context: Some(pb::stop_canister_context::Context::Canister(
                     pb::stop_canister_context::Canister {
@@ -187,6 +194,8 @@ impl From<&StopCanisterContext> for pb::StopCanisterContext {
                         call_id: call_id.map(|id| id.get()),
                         funds: Some((&Funds::new(*cycles)).into()),
                         cycles: Some((*cycles).into()),
+                        deadline_nanos: deadline
+                            .map(|deadline| deadline.as_nanos_since_unix_epoch()),
                     },
                 )),
             },
@@ -220,6 +229,7 @@ impl TryFrom<pb::StopCanisterContext> for StopCanisterContext {
                         call_id,
                         funds,
                         cycles,
+                        deadline_nanos,
                     },
                 ) => {
                     // To maintain backwards compatibility we fall back to reading from `funds` if
@@ -246,6 +256,7 @@ impl TryFrom<pb::StopCanisterContext> for StopCanisterContext {
                         reply_callback: CallbackId::from(reply_callback),
                         call_id: call_id.map(StopCanisterCallId::from),
                         cycles,
+                        deadline: deadline_nanos.map(Time::from_nanos_since_unix_epoch),
                     }
                 }
             };
@@ -382,6 +393,14 @@ impl CanisterCall {
         }
     }
 
+    /// Returns the deadline of this message, if it is a best-effort call.
+    pub fn deadline(&self) -> Option<Time> {
+        match self {
+            CanisterCall::Request(request) => request.deadline,
+            CanisterCall::Ingress(_) => None,
+        }
+    }
+
     /// Extracts the cycles received with this message.
     pub fn take_cycles(&mut self) -> Cycles {
         match self {
@@ -704,6 +723,7 @@ mod tests {
                 method_name: "method".into(),
                 method_payload: vec![0_u8, 1_u8, 2_u8, 3_u8, 4_u8, 5_u8],
                 metadata,
+                deadline: None,
             };
             let bytes = bincode::serialize(&request).unwrap();
             let request1 = bincode::deserialize::<Request>(&bytes);
@@ -719,6 +739,7 @@ mod tests {
             originator_reply_callback: CallbackId::from(100),
             refund: Cycles::from(100_000_000_u128),
             response_payload: Payload::Data(vec![0_u8, 1_u8, 2_u8, 3_u8, 4_u8, 5_u8]),
+            deadline: None,
         };
         let bytes = bincode::serialize(&response).unwrap();
         let response1 = bincode::deserialize::<Response>(&bytes);
@@ -3,8 +3,9 @@ use ic_error_types::{RejectCode, TryFromError, UserError};
 #[cfg(test)]
 use ic_exhaustive_derive::ExhaustiveSet;
 use ic_ic00_types::{
-    CanisterIdRecord, CanisterInfoRequest, ClearChunkStoreArgs, InstallChunkedCodeArgs,
-    InstallCodeArgsV2, Method, Payload as _, ProvisionalTopUpCanisterArgs, StoredChunksArgs,
+    CanisterIdRecord, CanisterInfoRequest, ClearChunkStoreArgs, DeleteCanisterSnapshotArgs,
+    InstallChunkedCodeArgs, InstallCodeArgsV2, ListCanisterSnapshotArgs, LoadCanisterSnapshotArgs,
+    Method, Payload as _, ProvisionalTopUpCanisterArgs, StoredChunksArgs, TakeCanisterSnapshotArgs,
     UpdateSettingsArgs, UploadChunkArgs,
 };
 use ic_protobuf::{
@@ -85,6 +86,8 @@ pub struct Request {
     #[serde(with = "serde_bytes")]
     pub method_payload: Vec<u8>,
     pub metadata: Option<RequestMetadata>,
+    /// Deadline of a best-effort call. `None` for guaranteed response calls.
+    pub deadline: Option<Time>,
 }
 
 impl Request {
@@ -103,6 +106,11 @@ impl Request {
         &self.method_payload
     }
 
+    /// Returns `true` if this is a best-effort call, i.e. it has a deadline.
+    pub fn is_best_effort(&self) -> bool {
+        self.deadline.is_some()
+    }
+
     /// Returns the size of the user-controlled part of this `Request`,
     /// in bytes.
     pub fn payload_size_bytes(&self) -> NumBytes {
@@ -162,17 +170,39 @@ impl Request {
                 Ok(record) => Some(record.get_canister_id()),
                 Err(_) => None,
             },
-            Ok(Method::DeleteChunks)
-            | Ok(Method::TakeCanisterSnapshot)
-            | Ok(Method::LoadCanisterSnapshot)
-            | Ok(Method::ListCanisterSnapshots)
-            | Ok(Method::DeleteCanisterSnapshot) => None,
+            Ok(Method::TakeCanisterSnapshot) => {
+                match TakeCanisterSnapshotArgs::decode(&self.method_payload) {
+                    Ok(record) => Some(record.get_canister_id()),
+                    Err(_) => None,
+                }
+            }
+            Ok(Method::LoadCanisterSnapshot) => {
+                match LoadCanisterSnapshotArgs::decode(&self.method_payload) {
+                    Ok(record) => Some(record.get_canister_id()),
+                    Err(_) => None,
+                }
+            }
+            Ok(Method::ListCanisterSnapshots) => {
+                match ListCanisterSnapshotArgs::decode(&self.method_payload) {
+                    Ok(record) => Some(record.get_canister_id()),
+                    Err(_) => None,
+                }
+            }
+            Ok(Method::DeleteCanisterSnapshot) => {
+                match DeleteCanisterSnapshotArgs::decode(&self.method_payload) {
+                    Ok(record) => Some(record.get_canister_id()),
+                    Err(_) => None,
+                }
+            }
+            Ok(Method::DeleteChunks) => None,
             Ok(Method::CreateCanister)
             | Ok(Method::SetupInitialDKG)
             | Ok(Method::HttpRequest)
             | Ok(Method::RawRand)
             | Ok(Method::ECDSAPublicKey)
             | Ok(Method::SignWithECDSA)
+            | Ok(Method::SchnorrPublicKey)
+            | Ok(Method::SignWithSchnorr)
             | Ok(Method::ComputeInitialEcdsaDealings)
             | Ok(Method::BitcoinGetBalance)
             | Ok(Method::BitcoinGetUtxos)
@@ -213,7 +243,8 @@ impl std::fmt::Debug for Request {
             "method_payload: [{}], ",
             truncate_and_format(&self.method_payload, 1024)
         )?;
-        write!(f, "metadata: {:?} }}", self.metadata)?;
+        write!(f, "metadata: {:?}, ", self.metadata)?;
+        write!(f, "deadline: {:?} }}", self.deadline)?;
         Ok(())
     }
 }
@@ -363,6 +394,9 @@ pub struct Response {
     pub originator_reply_callback: CallbackId,
     pub refund: Cycles,
     pub response_payload: Payload,
+    /// Deadline of the best-effort call that this is a response to. `None`
+    /// for guaranteed responses.
+    pub deadline: Option<Time>,
 }
 
 impl Response {
@@ -487,6 +521,9 @@ impl From<&Request> for pb_queues::Request {
             method_payload: req.method_payload.clone(),
             cycles_payment: Some((req.payment).into()),
             metadata: req.metadata.as_ref().map(From::from),
+            deadline_nanos: req
+                .deadline
+                .map(|deadline| deadline.as_nanos_since_unix_epoch()),
         }
     }
 }
@@ -522,6 +559,7 @@ impl TryFrom<pb_queues::Request> for Request {
             method_name: req.method_name,
             method_payload: req.method_payload,
             metadata: req.metadata.map(From::from),
+            deadline: req.deadline_nanos.map(Time::from_nanos_since_unix_epoch),
         })
     }
 }
@@ -564,6 +602,9 @@ impl From<&Response> for pb_queues::Response {
             refund: Some((&Funds::new(rep.refund)).into()),
             response_payload: Some(p),
             cycles_refund: Some((rep.refund).into()),
+            deadline_nanos: rep
+                .deadline
+                .map(|deadline| deadline.as_nanos_since_unix_epoch()),
         }
     }
 }
@@ -594,6 +635,7 @@ impl TryFrom<pb_queues::Response> for Response {
             originator_reply_callback: rep.originator_reply_callback.into(),
             refund,
             response_payload,
+            deadline: rep.deadline_nanos.map(Time::from_nanos_since_unix_epoch),
         })
     }
 }
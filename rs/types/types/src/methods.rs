@@ -1,7 +1,7 @@
 //! This module contains a collection of types and structs that define the
 //! various types of methods in the IC.
 
-use crate::{messages::CallContextId, Cycles};
+use crate::{messages::CallContextId, Cycles, Time};
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_protobuf::proxy::{try_from_option_field, ProxyDecodeError};
 use ic_protobuf::state::{canister_state_bits::v1 as pb, queues::v1::Cycles as PbCycles};
@@ -253,6 +253,8 @@ pub struct Callback {
     /// An optional closure to be executed if the execution of `on_reply` or
     /// `on_reject` traps.
     pub on_cleanup: Option<WasmClosure>,
+    /// Deadline of a best-effort call. `None` for guaranteed response calls.
+    pub deadline: Option<Time>,
 }
 
 impl Callback {
@@ -266,6 +268,7 @@ impl Callback {
         on_reply: WasmClosure,
         on_reject: WasmClosure,
         on_cleanup: Option<WasmClosure>,
+        deadline: Option<Time>,
     ) -> Self {
         Self {
             call_context_id,
@@ -277,6 +280,7 @@ impl Callback {
             on_reply,
             on_reject,
             on_cleanup,
+            deadline,
         }
     }
 }
@@ -304,6 +308,9 @@ impl From<&Callback> for pb::Callback {
                 func_idx: on_cleanup.func_idx,
                 env: on_cleanup.env,
             }),
+            deadline_nanos: item
+                .deadline
+                .map(|deadline| deadline.as_nanos_since_unix_epoch()),
         }
     }
 }
@@ -356,6 +363,7 @@ impl TryFrom<pb::Callback> for Callback {
                 func_idx: on_cleanup.func_idx,
                 env: on_cleanup.env,
             }),
+            deadline: value.deadline_nanos.map(Time::from_nanos_since_unix_epoch),
         })
     }
 }
@@ -135,6 +135,7 @@ prop_compose! {
             method_name,
             method_payload,
             metadata,
+            deadline: None,
         }
     }
 }
@@ -158,7 +159,7 @@ prop_compose! {
                 let req: CanonicalRequestV13 = (&request, certification_version).into();
                 req.try_into().unwrap()
             }
-            V14 | V15 => {
+            V14 | V15 | V16 => {
                 let req: CanonicalRequestV14 = (&request, certification_version).into();
                 req.try_into().unwrap()
             }
@@ -205,7 +206,8 @@ prop_compose! {
             respondent,
             originator_reply_callback: CallbackId::from(callback),
             refund: Cycles::from(cycles_refund),
-            response_payload
+            response_payload,
+            deadline: None,
         }
     }
 }
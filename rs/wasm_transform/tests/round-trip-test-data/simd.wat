@@ -0,0 +1,20 @@
+(module
+  (memory 1)
+  (func (param i32) (result i32)
+    local.get 0
+    v128.load offset=16 align=8
+    local.get 0
+    i32x4.splat
+    i32x4.add
+    v128.const i32x4 1 2 3 4
+    i8x16.shuffle 0 1 2 3 4 5 6 7 16 17 18 19 20 21 22 23
+    f32x4.abs
+    i32x4.extract_lane 2
+  )
+  (func (param i32)
+    local.get 0
+    local.get 0
+    v128.load32_zero
+    v128.store64_lane offset=8 1
+  )
+)